@@ -0,0 +1,482 @@
+//! Derive macros for the sponge absorption and squeezing traits of `ark-sponge`.
+#![deny(
+    future_incompatible,
+    missing_docs,
+    non_shorthand_field_patterns,
+    renamed_and_removed_lints,
+    rust_2018_idioms,
+    stable_features,
+    trivial_casts,
+    trivial_numeric_casts,
+    unused,
+    variant_size_differences,
+    warnings
+)]
+#![forbid(unsafe_code)]
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Index, Lit, Meta, NestedMeta};
+
+/// Derives `ark_sponge::Absorb` for a struct.
+///
+/// Each field is converted with its own `Absorb` implementation, in declaration order.
+/// A field annotated with `#[sponge(skip)]` is left out of both the byte and the field element
+/// encodings, and a field annotated with `#[sponge(with_length)]` is prefixed with its length, with
+/// its `AbsorbWithLength` implementation.
+///
+/// A struct annotated with `#[sponge(labeled)]` absorbs each field after its label, absorbed as a
+/// `&str`: the name of the field, its index for tuple structs, or the label given with
+/// `#[sponge(rename = "label")]`, so that a field can be renamed in Rust without changing the
+/// encoding.
+#[proc_macro_derive(Absorb, attributes(sponge))]
+pub fn derive_absorb(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    impl_absorb(&ast)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+fn impl_absorb(ast: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &ast.ident;
+    let fields = absorbed_fields(ast)?;
+
+    let mut generics = ast.generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(ark_sponge::Absorb));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let to_bytes = fields.iter().map(|field| {
+        let accessor = &field.accessor;
+        let label = field.label.as_ref().map(|label| {
+            quote!(ark_sponge::Absorb::to_sponge_bytes(&#label, dest);)
+        });
+        let value = if field.with_length {
+            quote!(ark_sponge::AbsorbWithLength::to_sponge_bytes_with_length(&self.#accessor, dest);)
+        } else {
+            quote!(ark_sponge::Absorb::to_sponge_bytes(&self.#accessor, dest);)
+        };
+        quote!(#label #value)
+    });
+    let to_field_elements = fields.iter().map(|field| {
+        let accessor = &field.accessor;
+        let label = field
+            .label
+            .as_ref()
+            .map(|label| quote!(ark_sponge::Absorb::to_sponge_field_elements(&#label, dest);));
+        let value = if field.with_length {
+            quote!(ark_sponge::AbsorbWithLength::to_sponge_field_elements_with_length(
+                &self.#accessor,
+                dest,
+            );)
+        } else {
+            quote!(ark_sponge::Absorb::to_sponge_field_elements(&self.#accessor, dest);)
+        };
+        quote!(#label #value)
+    });
+
+    Ok(quote! {
+        impl #impl_generics ark_sponge::Absorb for #name #ty_generics #where_clause {
+            fn to_sponge_bytes(&self, dest: &mut ark_sponge::__private::Vec<u8>) {
+                #( #to_bytes )*
+            }
+
+            fn to_sponge_field_elements<F: ark_sponge::__private::PrimeField>(
+                &self,
+                dest: &mut ark_sponge::__private::Vec<F>,
+            ) {
+                #( #to_field_elements )*
+            }
+        }
+    })
+}
+
+/// Derives `ark_sponge::constraints::AbsorbGadget` for a struct of gadgets.
+///
+/// The implementation is generic over the constraint field, requiring every absorbed field type
+/// to implement `AbsorbGadget` for it. Fields are converted in declaration order, fields
+/// annotated with `#[sponge(skip)]` are not absorbed, fields annotated with
+/// `#[sponge(with_length)]` are prefixed with their length, and the fields of a struct annotated
+/// with `#[sponge(labeled)]` are prefixed with their labels, as constants, exactly as in
+/// `#[derive(Absorb)]`.
+#[proc_macro_derive(AbsorbGadget, attributes(sponge))]
+pub fn derive_absorb_gadget(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    impl_absorb_gadget(&ast)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+fn impl_absorb_gadget(ast: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &ast.ident;
+    let fields = absorbed_fields(ast)?;
+
+    let mut generics = ast.generics.clone();
+    generics
+        .params
+        .push(parse_quote!(__CF: ark_sponge::__private::PrimeField));
+    {
+        let where_clause = generics.make_where_clause();
+        for field in &fields {
+            let ty = &field.ty;
+            where_clause.predicates.push(if field.with_length {
+                parse_quote!(#ty: ark_sponge::constraints::AbsorbGadgetWithLength<__CF>)
+            } else {
+                parse_quote!(#ty: ark_sponge::constraints::AbsorbGadget<__CF>)
+            });
+        }
+    }
+    let to_bytes = fields.iter().map(|field| {
+        let accessor = &field.accessor;
+        let label = field.label.as_ref().map(|label| {
+            quote! {
+                output.append(
+                    &mut <&str as ark_sponge::constraints::AbsorbGadget<__CF>>::to_sponge_bytes(
+                        &#label,
+                    )?,
+                );
+            }
+        });
+        let value = if field.with_length {
+            quote!(ark_sponge::constraints::AbsorbGadgetWithLength::to_sponge_bytes_with_length(
+                &self.#accessor,
+            ))
+        } else {
+            quote!(ark_sponge::constraints::AbsorbGadget::to_sponge_bytes(&self.#accessor))
+        };
+        quote!(#label output.append(&mut #value?);)
+    });
+    let to_field_elements = fields.iter().map(|field| {
+        let accessor = &field.accessor;
+        let label = field.label.as_ref().map(|label| {
+            quote! {
+                output.append(
+                    &mut <&str as ark_sponge::constraints::AbsorbGadget<__CF>>::to_sponge_field_elements(
+                        &#label,
+                    )?,
+                );
+            }
+        });
+        let value = if field.with_length {
+            quote!(
+                ark_sponge::constraints::AbsorbGadgetWithLength::to_sponge_field_elements_with_length(
+                    &self.#accessor,
+                )
+            )
+        } else {
+            quote!(ark_sponge::constraints::AbsorbGadget::to_sponge_field_elements(&self.#accessor))
+        };
+        quote!(#label output.append(&mut #value?);)
+    });
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = ast.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ark_sponge::constraints::AbsorbGadget<__CF> for #name #ty_generics
+        #where_clause
+        {
+            fn to_sponge_bytes(
+                &self,
+            ) -> Result<
+                ark_sponge::__private::Vec<ark_sponge::__private::UInt8<__CF>>,
+                ark_sponge::__private::SynthesisError,
+            > {
+                let mut output = ark_sponge::__private::Vec::new();
+                #( #to_bytes )*
+                Ok(output)
+            }
+
+            fn to_sponge_field_elements(
+                &self,
+            ) -> Result<
+                ark_sponge::__private::Vec<ark_sponge::__private::FpVar<__CF>>,
+                ark_sponge::__private::SynthesisError,
+            > {
+                let mut output = ark_sponge::__private::Vec::new();
+                #( #to_field_elements )*
+                Ok(output)
+            }
+        }
+    })
+}
+
+/// Derives `ark_sponge::SqueezeFromSponge` for a struct.
+///
+/// The implementation is generic over the field of the sponge, requiring every squeezed field
+/// type to implement `SqueezeFromSponge` for it. Fields are squeezed in declaration order, and
+/// the shape of the struct is the tuple of the shapes of its squeezed fields, in the same
+/// order. Fields annotated with `#[sponge(skip)]` are not squeezed and take their default value.
+#[proc_macro_derive(SqueezeFromSponge, attributes(sponge))]
+pub fn derive_squeeze_from_sponge(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    impl_squeeze_from_sponge(&ast, false)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Derives `ark_sponge::constraints::SqueezeFromSpongeGadget` for a struct of gadgets.
+///
+/// Fields are squeezed in declaration order, and the shape of the struct is the tuple of the
+/// shapes of its squeezed fields, exactly as in `#[derive(SqueezeFromSponge)]`, so that a struct
+/// and its gadget with the same shape squeeze the same values.
+#[proc_macro_derive(SqueezeFromSpongeGadget, attributes(sponge))]
+pub fn derive_squeeze_from_sponge_gadget(
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    impl_squeeze_from_sponge(&ast, true)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+fn impl_squeeze_from_sponge(ast: &DeriveInput, gadget: bool) -> syn::Result<TokenStream> {
+    let name = &ast.ident;
+    if container_attributes(&ast.attrs)?.labeled {
+        return Err(syn::Error::new_spanned(
+            &ast.ident,
+            "`labeled` is not supported when squeezing",
+        ));
+    }
+    let fields = sponge_fields(ast)?;
+    if let Some(field) = fields.iter().find(|field| field.attributes.with_length) {
+        return Err(syn::Error::new_spanned(
+            &field.ty,
+            "`with_length` is not supported when squeezing",
+        ));
+    }
+    if let Some(field) = fields
+        .iter()
+        .find(|field| field.attributes.rename.is_some())
+    {
+        return Err(syn::Error::new_spanned(
+            &field.ty,
+            "`rename` is not supported when squeezing",
+        ));
+    }
+    let trait_path = if gadget {
+        quote!(ark_sponge::constraints::SqueezeFromSpongeGadget<__CF>)
+    } else {
+        quote!(ark_sponge::SqueezeFromSponge<__CF>)
+    };
+
+    let mut generics = ast.generics.clone();
+    generics
+        .params
+        .push(parse_quote!(__CF: ark_sponge::__private::PrimeField));
+    {
+        let where_clause = generics.make_where_clause();
+        for field in &fields {
+            let ty = &field.ty;
+            where_clause.predicates.push(if field.attributes.skip {
+                parse_quote!(#ty: Default)
+            } else {
+                parse_quote!(#ty: #trait_path)
+            });
+        }
+    }
+    let squeezed_types = fields
+        .iter()
+        .filter(|field| !field.attributes.skip)
+        .map(|field| &field.ty)
+        .collect::<Vec<_>>();
+    let mut shape_index = 0;
+    let initializers = fields.iter().map(|field| {
+        let accessor = &field.accessor;
+        let ty = &field.ty;
+        if field.attributes.skip {
+            quote!(#accessor: Default::default())
+        } else {
+            let index = Index::from(shape_index);
+            shape_index += 1;
+            if gadget {
+                quote!(#accessor: <#ty as #trait_path>::squeeze_from_sponge(sponge, &shape.#index)?)
+            } else {
+                quote!(#accessor: <#ty as #trait_path>::squeeze_from_sponge(sponge, &shape.#index))
+            }
+        }
+    });
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = ast.generics.split_for_impl();
+
+    let shape = quote!(( #( <#squeezed_types as #trait_path>::Shape, )* ));
+    let squeeze = if gadget {
+        quote! {
+            fn squeeze_from_sponge<
+                __S: ark_sponge::CryptographicSponge,
+                __SV: ark_sponge::constraints::CryptographicSpongeVar<__CF, __S>,
+            >(
+                sponge: &mut __SV,
+                shape: &Self::Shape,
+            ) -> Result<Self, ark_sponge::__private::SynthesisError> {
+                let _ = (&sponge, &shape);
+                Ok(Self { #( #initializers, )* })
+            }
+        }
+    } else {
+        quote! {
+            fn squeeze_from_sponge<__S: ark_sponge::FieldBasedCryptographicSponge<__CF>>(
+                sponge: &mut __S,
+                shape: &Self::Shape,
+            ) -> Self {
+                let _ = (&sponge, &shape);
+                Self { #( #initializers, )* }
+            }
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics #trait_path for #name #ty_generics #where_clause {
+            type Shape = #shape;
+
+            #squeeze
+        }
+    })
+}
+
+/// A field of a struct, with its sponge attributes.
+struct SpongeField {
+    /// The accessor of the field, its `ident` or tuple index.
+    accessor: TokenStream,
+    /// The name of the field, or its index for tuple structs.
+    name: String,
+    ty: syn::Type,
+    attributes: FieldAttributes,
+}
+
+/// Returns all the fields of a struct, in declaration order.
+fn sponge_fields(ast: &DeriveInput) -> syn::Result<Vec<SpongeField>> {
+    let data = match &ast.data {
+        Data::Struct(data) => data,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &ast.ident,
+                "sponge derives are only supported for structs",
+            ))
+        }
+    };
+
+    let mut fields = Vec::new();
+    for (i, field) in data.fields.iter().enumerate() {
+        let (accessor, name) = match &field.ident {
+            Some(ident) => (quote!(#ident), ident.to_string()),
+            None => {
+                let index = Index::from(i);
+                (quote!(#index), i.to_string())
+            }
+        };
+        fields.push(SpongeField {
+            accessor,
+            name,
+            ty: field.ty.clone(),
+            attributes: field_attributes(&field.attrs)?,
+        });
+    }
+
+    Ok(fields)
+}
+
+/// A field that should be absorbed.
+struct AbsorbedField {
+    /// The accessor of the field, its `ident` or tuple index.
+    accessor: TokenStream,
+    ty: syn::Type,
+    /// Whether the field carries the `#[sponge(with_length)]` attribute.
+    with_length: bool,
+    /// The label absorbed before the field, if the struct is labeled.
+    label: Option<syn::LitStr>,
+}
+
+/// The sponge attributes of a struct.
+#[derive(Default)]
+struct ContainerAttributes {
+    labeled: bool,
+}
+
+/// The sponge attributes of a field.
+#[derive(Default)]
+struct FieldAttributes {
+    skip: bool,
+    with_length: bool,
+    rename: Option<syn::LitStr>,
+}
+
+/// Returns the fields that should be absorbed, in declaration order.
+fn absorbed_fields(ast: &DeriveInput) -> syn::Result<Vec<AbsorbedField>> {
+    let labeled = container_attributes(&ast.attrs)?.labeled;
+    let mut fields = Vec::new();
+    for field in sponge_fields(ast)? {
+        if field.attributes.skip {
+            continue;
+        }
+        let label = match field.attributes.rename {
+            Some(rename) if !labeled => {
+                return Err(syn::Error::new_spanned(
+                    rename,
+                    "`rename` requires `#[sponge(labeled)]` on the struct",
+                ))
+            }
+            Some(rename) => Some(rename),
+            None if labeled => Some(syn::LitStr::new(
+                &field.name,
+                proc_macro2::Span::call_site(),
+            )),
+            None => None,
+        };
+        fields.push(AbsorbedField {
+            accessor: field.accessor,
+            ty: field.ty,
+            with_length: field.attributes.with_length,
+            label,
+        });
+    }
+    Ok(fields)
+}
+
+/// Returns the items of the `#[sponge(...)]` attributes in `attrs`.
+fn sponge_attribute_items(attrs: &[syn::Attribute]) -> syn::Result<Vec<NestedMeta>> {
+    let mut items = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("sponge")) {
+        match attr.parse_meta()? {
+            Meta::List(list) => items.extend(list.nested),
+            meta => return Err(syn::Error::new_spanned(meta, "expected `sponge(...)`")),
+        }
+    }
+    Ok(items)
+}
+
+/// Parses the `#[sponge(...)]` attributes of a struct.
+fn container_attributes(attrs: &[syn::Attribute]) -> syn::Result<ContainerAttributes> {
+    let mut attributes = ContainerAttributes::default();
+    for nested in sponge_attribute_items(attrs)? {
+        match &nested {
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("labeled") => {
+                attributes.labeled = true
+            }
+            _ => return Err(syn::Error::new_spanned(nested, "unknown sponge attribute")),
+        }
+    }
+    Ok(attributes)
+}
+
+/// Parses the `#[sponge(...)]` attributes of a field.
+fn field_attributes(attrs: &[syn::Attribute]) -> syn::Result<FieldAttributes> {
+    let mut attributes = FieldAttributes::default();
+    for nested in sponge_attribute_items(attrs)? {
+        match &nested {
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip") => attributes.skip = true,
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("with_length") => {
+                attributes.with_length = true
+            }
+            NestedMeta::Meta(Meta::NameValue(name_value)) if name_value.path.is_ident("rename") => {
+                match &name_value.lit {
+                    Lit::Str(rename) => attributes.rename = Some(rename.clone()),
+                    lit => return Err(syn::Error::new_spanned(lit, "expected a string literal")),
+                }
+            }
+            _ => return Err(syn::Error::new_spanned(nested, "unknown sponge attribute")),
+        }
+    }
+
+    Ok(attributes)
+}
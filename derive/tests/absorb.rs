@@ -0,0 +1,159 @@
+use ark_ff::{One, PrimeField, Zero};
+use ark_sponge::{collect_sponge_bytes, collect_sponge_field_elements, Absorb};
+use ark_std::vec::Vec;
+use ark_test_curves::bls12_381::Fr;
+
+#[derive(Absorb)]
+struct Named {
+    a: u8,
+    b: Fr,
+    c: Vec<u32>,
+}
+
+#[derive(Absorb)]
+struct Tuple(Fr, u64);
+
+#[derive(Absorb)]
+struct Unit;
+
+#[derive(Absorb)]
+struct WithSkip {
+    a: Fr,
+    #[sponge(skip)]
+    #[allow(dead_code)]
+    cache: String,
+    b: u16,
+}
+
+#[derive(Absorb)]
+struct Generic<T> {
+    items: Vec<T>,
+    count: usize,
+}
+
+#[test]
+fn named_fields_are_absorbed_in_order() {
+    let value = Named {
+        a: 7,
+        b: Fr::one(),
+        c: vec![1, 2, 3],
+    };
+
+    assert_eq!(
+        value.to_sponge_bytes_as_vec(),
+        collect_sponge_bytes!(value.a, value.b, value.c)
+    );
+    let expected: Vec<Fr> = collect_sponge_field_elements!(value.a, value.b, value.c);
+    assert_eq!(value.to_sponge_field_elements_as_vec::<Fr>(), expected);
+}
+
+#[test]
+fn tuple_and_unit_structs() {
+    let value = Tuple(Fr::from(5u8), 42);
+    assert_eq!(
+        value.to_sponge_bytes_as_vec(),
+        collect_sponge_bytes!(value.0, value.1)
+    );
+    let expected: Vec<Fr> = collect_sponge_field_elements!(value.0, value.1);
+    assert_eq!(value.to_sponge_field_elements_as_vec::<Fr>(), expected);
+
+    assert!(Unit.to_sponge_bytes_as_vec().is_empty());
+    assert!(Unit.to_sponge_field_elements_as_vec::<Fr>().is_empty());
+}
+
+#[test]
+fn skipped_fields_are_not_absorbed() {
+    let value = WithSkip {
+        a: Fr::zero(),
+        cache: "not absorbed".into(),
+        b: 9,
+    };
+    assert_eq!(
+        value.to_sponge_bytes_as_vec(),
+        collect_sponge_bytes!(value.a, value.b)
+    );
+    let expected: Vec<Fr> = collect_sponge_field_elements!(value.a, value.b);
+    assert_eq!(value.to_sponge_field_elements_as_vec::<Fr>(), expected);
+}
+
+fn absorb_generic<F: PrimeField, T: Absorb>(value: &Generic<T>) -> Vec<F> {
+    value.to_sponge_field_elements_as_vec()
+}
+
+#[test]
+fn generic_structs() {
+    let value = Generic {
+        items: vec![Fr::one(), Fr::from(2u8)],
+        count: 2,
+    };
+    let expected: Vec<Fr> = collect_sponge_field_elements!(value.items, value.count);
+    assert_eq!(absorb_generic::<Fr, _>(&value), expected);
+}
+
+#[derive(Absorb)]
+struct WithLength {
+    #[sponge(with_length)]
+    a: Vec<u8>,
+    #[sponge(with_length)]
+    b: Vec<u8>,
+}
+
+#[test]
+fn fields_with_length_are_prefixed() {
+    let value = WithLength {
+        a: vec![1, 2],
+        b: vec![3],
+    };
+    let shifted = WithLength {
+        a: vec![1],
+        b: vec![2, 3],
+    };
+    assert_eq!(
+        value.to_sponge_bytes_as_vec(),
+        collect_sponge_bytes!(2usize, value.a, 1usize, value.b)
+    );
+    let expected: Vec<Fr> = collect_sponge_field_elements!(2usize, value.a, 1usize, value.b);
+    assert_eq!(value.to_sponge_field_elements_as_vec::<Fr>(), expected);
+    assert_ne!(
+        value.to_sponge_field_elements_as_vec::<Fr>(),
+        shifted.to_sponge_field_elements_as_vec::<Fr>()
+    );
+}
+
+#[derive(Absorb)]
+#[sponge(labeled)]
+struct Labeled {
+    a: u8,
+    #[sponge(rename = "commitment")]
+    b: Fr,
+    #[sponge(skip)]
+    #[allow(dead_code)]
+    cache: u64,
+}
+
+#[derive(Absorb)]
+#[sponge(labeled)]
+struct LabeledTuple(Fr, #[sponge(rename = "count")] u64);
+
+#[test]
+fn labeled_fields_are_absorbed_after_their_names() {
+    let value = Labeled {
+        a: 7,
+        b: Fr::one(),
+        cache: 0,
+    };
+    assert_eq!(
+        value.to_sponge_bytes_as_vec(),
+        collect_sponge_bytes!("a", value.a, "commitment", value.b)
+    );
+    let expected: Vec<Fr> = collect_sponge_field_elements!("a", value.a, "commitment", value.b);
+    assert_eq!(value.to_sponge_field_elements_as_vec::<Fr>(), expected);
+
+    let value = LabeledTuple(Fr::from(3u8), 4);
+    assert_eq!(
+        value.to_sponge_bytes_as_vec(),
+        collect_sponge_bytes!("0", value.0, "count", value.1)
+    );
+    let expected: Vec<Fr> = collect_sponge_field_elements!("0", value.0, "count", value.1);
+    assert_eq!(value.to_sponge_field_elements_as_vec::<Fr>(), expected);
+}
@@ -0,0 +1,102 @@
+use ark_ff::{One, PrimeField};
+use ark_r1cs_std::alloc::AllocVar;
+use ark_r1cs_std::bits::boolean::Boolean;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::uint8::UInt8;
+use ark_r1cs_std::R1CSVar;
+use ark_relations::ns;
+use ark_relations::r1cs::{ConstraintSystem, SynthesisError};
+use ark_sponge::constraints::AbsorbGadget;
+use ark_sponge::Absorb;
+use ark_std::vec::Vec;
+use ark_test_curves::bls12_381::Fr;
+
+#[derive(Absorb)]
+struct Message {
+    commitment: Fr,
+    #[sponge(with_length)]
+    tag: Vec<u8>,
+    flag: bool,
+}
+
+#[derive(AbsorbGadget)]
+struct MessageVar<F: PrimeField> {
+    commitment: FpVar<F>,
+    #[sponge(with_length)]
+    tag: Vec<UInt8<F>>,
+    #[sponge(skip)]
+    #[allow(dead_code)]
+    scratch: FpVar<F>,
+    flag: Boolean<F>,
+}
+
+#[test]
+fn derived_gadget_matches_native() -> Result<(), SynthesisError> {
+    let cs = ConstraintSystem::<Fr>::new_ref();
+
+    let native = Message {
+        commitment: Fr::from(12345u64),
+        tag: vec![1, 2, 3, 4],
+        flag: true,
+    };
+    let var = MessageVar {
+        commitment: FpVar::new_witness(ns!(cs, "commitment"), || Ok(native.commitment))?,
+        tag: UInt8::new_witness_vec(ns!(cs, "tag"), &native.tag)?,
+        scratch: FpVar::new_witness(ns!(cs, "scratch"), || Ok(Fr::one()))?,
+        flag: Boolean::new_witness(ns!(cs, "flag"), || Ok(native.flag))?,
+    };
+
+    assert_eq!(
+        var.to_sponge_bytes()?.value()?,
+        native.to_sponge_bytes_as_vec()
+    );
+    assert_eq!(
+        var.to_sponge_field_elements()?.value()?,
+        native.to_sponge_field_elements_as_vec::<Fr>()
+    );
+    assert!(cs.is_satisfied()?);
+
+    Ok(())
+}
+
+#[derive(Absorb)]
+#[sponge(labeled)]
+struct LabeledMessage {
+    commitment: Fr,
+    #[sponge(rename = "challenge")]
+    value: Fr,
+}
+
+#[derive(AbsorbGadget)]
+#[sponge(labeled)]
+struct LabeledMessageVar<F: PrimeField> {
+    commitment: FpVar<F>,
+    #[sponge(rename = "challenge")]
+    value_var: FpVar<F>,
+}
+
+#[test]
+fn labeled_gadget_matches_native() -> Result<(), SynthesisError> {
+    let cs = ConstraintSystem::<Fr>::new_ref();
+
+    let native = LabeledMessage {
+        commitment: Fr::from(12345u64),
+        value: Fr::from(6u64),
+    };
+    let var = LabeledMessageVar {
+        commitment: FpVar::new_witness(ns!(cs, "commitment"), || Ok(native.commitment))?,
+        value_var: FpVar::new_witness(ns!(cs, "value"), || Ok(native.value))?,
+    };
+
+    assert_eq!(
+        var.to_sponge_bytes()?.value()?,
+        native.to_sponge_bytes_as_vec()
+    );
+    assert_eq!(
+        var.to_sponge_field_elements()?.value()?,
+        native.to_sponge_field_elements_as_vec::<Fr>()
+    );
+    assert!(cs.is_satisfied()?);
+
+    Ok(())
+}
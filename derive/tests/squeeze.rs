@@ -0,0 +1,87 @@
+use ark_ff::PrimeField;
+use ark_r1cs_std::alloc::AllocVar;
+use ark_r1cs_std::bits::boolean::Boolean;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::R1CSVar;
+use ark_relations::r1cs::{ConstraintSystem, SynthesisError};
+use ark_sponge::constraints::{CryptographicSpongeVar, SqueezeFromSpongeGadget};
+use ark_sponge::poseidon::constraints::PoseidonSpongeVar;
+use ark_sponge::poseidon::{PoseidonParameters, PoseidonSponge};
+use ark_sponge::{CryptographicSponge, FieldBasedCryptographicSponge, SqueezeFromSponge};
+use ark_std::vec::Vec;
+use ark_test_curves::bls12_381::Fr;
+
+#[derive(SqueezeFromSponge, Debug, PartialEq)]
+struct RoundChallenges<F: PrimeField> {
+    alpha: F,
+    betas: Vec<F>,
+    #[sponge(skip)]
+    round: usize,
+    flip: bool,
+}
+
+#[derive(SqueezeFromSpongeGadget)]
+struct RoundChallengesVar<F: PrimeField> {
+    alpha: FpVar<F>,
+    betas: Vec<FpVar<F>>,
+    #[sponge(skip)]
+    #[allow(dead_code)]
+    round: usize,
+    flip: Boolean<F>,
+}
+
+#[derive(SqueezeFromSponge)]
+struct Tuple(Fr, Fr);
+
+#[derive(SqueezeFromSponge)]
+struct Unit;
+
+#[test]
+fn derived_squeeze_order() {
+    let params = PoseidonParameters::<Fr>::default();
+    let mut sponge = PoseidonSponge::new(&params);
+    sponge.absorb(&Fr::from(3u64));
+    let mut expected_sponge = sponge.clone();
+
+    let challenges = RoundChallenges::<Fr>::squeeze_from_sponge(&mut sponge, &((), 3, ()));
+    let alpha = expected_sponge.squeeze_native_field_elements(1)[0];
+    let betas = (0..3)
+        .map(|_| expected_sponge.squeeze_native_field_elements(1)[0])
+        .collect();
+    let flip = expected_sponge.squeeze_bits(1)[0];
+    assert_eq!(
+        challenges,
+        RoundChallenges {
+            alpha,
+            betas,
+            round: 0,
+            flip,
+        }
+    );
+
+    let Tuple(a, b) = Tuple::squeeze_from_sponge(&mut sponge, &((), ()));
+    assert_eq!(a, expected_sponge.squeeze_native_field_elements(1)[0]);
+    assert_eq!(b, expected_sponge.squeeze_native_field_elements(1)[0]);
+
+    let Unit = <Unit as SqueezeFromSponge<Fr>>::squeeze_from_sponge(&mut sponge, &());
+}
+
+#[test]
+fn derived_gadget_matches_native() -> Result<(), SynthesisError> {
+    let params = PoseidonParameters::<Fr>::default();
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    let mut sponge = PoseidonSponge::new(&params);
+    let mut sponge_var = PoseidonSpongeVar::new(cs.clone(), &params);
+    sponge.absorb(&Fr::from(3u64));
+    sponge_var.absorb(&FpVar::new_witness(cs.clone(), || Ok(Fr::from(3u64)))?)?;
+
+    let shape = ((), 4, ());
+    let native = RoundChallenges::<Fr>::squeeze_from_sponge(&mut sponge, &shape);
+    let var = RoundChallengesVar::squeeze_from_sponge(&mut sponge_var, &shape)?;
+    assert_eq!(var.alpha.value()?, native.alpha);
+    assert_eq!(var.betas.value()?, native.betas);
+    assert_eq!(var.flip.value()?, native.flip);
+    assert!(cs.is_satisfied()?);
+
+    Ok(())
+}
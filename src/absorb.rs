@@ -1,13 +1,48 @@
-use ark_ec::models::short_weierstrass_jacobian::GroupAffine as SWAffine;
-use ark_ec::models::twisted_edwards_extended::GroupAffine as TEAffine;
+use ark_ec::models::short_weierstrass_jacobian::{
+    GroupAffine as SWAffine, GroupProjective as SWProjective,
+};
+use ark_ec::models::twisted_edwards_extended::{
+    GroupAffine as TEAffine, GroupProjective as TEProjective,
+};
 use ark_ec::models::{SWModelParameters, TEModelParameters};
+#[cfg(feature = "groth16")]
+use ark_ec::PairingEngine;
+use ark_ec::ProjectiveCurve;
+use ark_ff::biginteger::{
+    BigInteger, BigInteger128, BigInteger256, BigInteger320, BigInteger384, BigInteger448,
+    BigInteger64, BigInteger768, BigInteger832,
+};
 use ark_ff::models::{
-    Fp256, Fp256Parameters, Fp320, Fp320Parameters, Fp384, Fp384Parameters, Fp768, Fp768Parameters,
-    Fp832, Fp832Parameters,
+    Fp256, Fp256Parameters, Fp320, Fp320Parameters, Fp384, Fp384Parameters, Fp64, Fp64Parameters,
+    Fp768, Fp768Parameters, Fp832, Fp832Parameters,
+};
+use ark_ff::{
+    CubicExtField, CubicExtParameters, FpParameters, PrimeField, QuadExtField, QuadExtParameters,
+    ToConstraintField,
+};
+#[cfg(feature = "poly")]
+use ark_ff::{FftField, Field};
+#[cfg(feature = "groth16")]
+use ark_groth16::{PreparedVerifyingKey, Proof, VerifyingKey};
+#[cfg(feature = "poly")]
+use ark_poly::{
+    univariate::{DensePolynomial, SparsePolynomial},
+    EvaluationDomain, Evaluations,
 };
-use ark_ff::{PrimeField, ToConstraintField};
 use ark_serialize::CanonicalSerialize;
+use ark_std::string::String;
+use ark_std::vec;
 use ark_std::vec::Vec;
+use ark_std::{cfg_chunks, cfg_iter};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Derives [`Absorb`] for a struct by absorbing its fields in declaration order.
+/// Fields marked with `#[sponge(skip)]` are not absorbed, and the fields of a struct marked with
+/// `#[sponge(labeled)]` are absorbed after their names, which `#[sponge(rename = "...")]` changes.
+#[cfg(feature = "derive")]
+pub use ark_sponge_derive::Absorb;
+
 /// An interface for objects that can be absorbed by a `CryptographicSponge`.
 pub trait Absorb {
     /// Converts the object into a list of bytes that can be absorbed by a `CryptographicSponge`.
@@ -95,7 +130,7 @@ pub trait AbsorbWithLength: Absorb {
     /// Append the list to `dest`
     fn to_sponge_field_elements_with_length<F: PrimeField>(&self, dest: &mut Vec<F>) {
         self.absorb_length().to_sponge_field_elements(dest);
-        <Self as Absorb>::to_sponge_field_elements(&self, dest)
+        <Self as Absorb>::to_sponge_field_elements(self, dest)
     }
 }
 
@@ -123,15 +158,111 @@ pub(crate) fn batch_field_cast<'a, F1: PrimeField, F2: PrimeField>(
         // "Trying to absorb non-native field elements."
         None
     } else {
+        let elems: Vec<F2> = cfg_iter!(x)
+            .map(|item| {
+                let mut buf = Vec::new();
+                item.serialize(&mut buf).unwrap();
+                F2::from_le_bytes_mod_order(&buf)
+            })
+            .collect();
+        dest.extend(elems);
+        Some(dest)
+    }
+}
+
+/// Packs `bytes` into field elements of `F`, `CAPACITY / 8` little-endian bytes per element, as
+/// `ToConstraintField` does.
+pub(crate) fn pack_bytes<F: PrimeField>(bytes: &[u8], dest: &mut Vec<F>) {
+    let chunk_size = (F::Params::CAPACITY / 8) as usize;
+    let elems: Vec<F> = cfg_chunks!(bytes, chunk_size)
+        .map(F::from_le_bytes_mod_order)
+        .collect();
+    dest.extend(elems);
+}
+
+/// Packs `bits` into field elements of `F`: the number of bits as a `u64`, followed by the bits in
+/// chunks of `CAPACITY` little-endian bits per element.
+pub(crate) fn pack_bits<F: PrimeField>(bits: &[bool]) -> Vec<F> {
+    let mut elems = vec![F::from(bits.len() as u64)];
+    pack_bits_without_length(bits, &mut elems);
+    elems
+}
+
+/// Packs `bits` into field elements of `F`, in chunks of `CAPACITY` little-endian bits per
+/// element, and adds them to `dest`.
+pub(crate) fn pack_bits_without_length<F: PrimeField>(bits: &[bool], dest: &mut Vec<F>) {
+    dest.extend(bits.chunks(F::Params::CAPACITY as usize).map(|chunk| {
+        F::from_repr(F::BigInt::from_bits_le(chunk)).expect("the chunk fits in the capacity")
+    }));
+}
+
+/// Packs the little-endian bits of `words` into field elements of `F` as
+/// [`pack_bits_without_length`] does, without expanding the words into bits.
+pub(crate) fn pack_u64s_without_length<F: PrimeField>(words: &[u64], dest: &mut Vec<F>) {
+    let chunk_bits = F::Params::CAPACITY as usize;
+    let num_bits = 64 * words.len();
+    let mut start = 0;
+    while start < num_bits {
+        let end = ark_std::cmp::min(start + chunk_bits, num_bits);
+        let mut limbs = vec![0u64; (end - start).div_ceil(64)];
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let bit = start + 64 * i;
+            let (word, offset) = (bit / 64, bit % 64);
+            *limb = words[word] >> offset;
+            if offset > 0 && word + 1 < words.len() {
+                *limb |= words[word + 1] << (64 - offset);
+            }
+        }
+        let excess = 64 * limbs.len() - (end - start);
+        if excess > 0 {
+            *limbs.last_mut().unwrap() &= u64::MAX >> excess;
+        }
+        let mut bytes: Vec<u8> = limbs.iter().flat_map(|limb| limb.to_le_bytes()).collect();
+        bytes.truncate((end - start).div_ceil(8));
+        dest.push(F::from_le_bytes_mod_order(&bytes));
+        start = end;
+    }
+}
+
+/// Adds the sponge field elements of the items of `batch` to `dest`, in order. With the `parallel`
+/// feature, the items are converted in parallel.
+pub(crate) fn batch_to_sponge_field_elements_in_parallel<A: Absorb + Sync, F: PrimeField>(
+    batch: &[A],
+    dest: &mut Vec<F>,
+) {
+    let elems: Vec<Vec<F>> = cfg_iter!(batch)
+        .map(|item| item.to_sponge_field_elements_as_vec())
+        .collect();
+    dest.extend(elems.into_iter().flatten());
+}
+
+/// Adds the sponge bytes of the items of `batch` to `dest`, in order. With the `parallel` feature,
+/// the items are converted in parallel.
+pub(crate) fn batch_to_sponge_bytes_in_parallel<A: Absorb + Sync>(batch: &[A], dest: &mut Vec<u8>) {
+    let bytes: Vec<Vec<u8>> = cfg_iter!(batch)
+        .map(|item| item.to_sponge_bytes_as_vec())
+        .collect();
+    dest.extend(bytes.into_iter().flatten());
+}
+
+/// Adds the elements of `x` to `dest`, cast to `F2` if `F1` and `F2` have the same modulus.
+/// Otherwise, the byte encodings of the elements are packed into elements of `F2`.
+pub(crate) fn batch_field_cast_or_pack<F1: PrimeField, F2: PrimeField>(
+    x: &[F1],
+    dest: &mut Vec<F2>,
+) {
+    if batch_field_cast(x, dest).is_none() {
+        let mut bytes = Vec::new();
         x.iter().for_each(|item| {
-            let mut buf = Vec::new();
-            item.serialize(&mut buf).unwrap();
-            dest.push(F2::from_le_bytes_mod_order(&buf))
+            item.serialize(&mut bytes)
+                .expect("writing to a Vec cannot fail")
         });
-        Some(dest)
+        pack_bytes(&bytes, dest);
     }
 }
 
+/// A slice of bytes is absorbed as its bytes, and as field elements as its length, as a `u64`,
+/// followed by its bytes, packed `CAPACITY / 8` bytes per element as the gadgets pack them.
 impl Absorb for u8 {
     fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
         dest.push(*self)
@@ -148,7 +279,7 @@ impl Absorb for u8 {
     fn batch_to_sponge_field_elements<F: PrimeField>(batch: &[Self], dest: &mut Vec<F>) {
         let mut bytes = (batch.len() as u64).to_le_bytes().to_vec();
         bytes.extend_from_slice(batch);
-        dest.extend_from_slice(&bytes.to_field_elements().unwrap()[..])
+        pack_bytes(&bytes, dest)
     }
 }
 
@@ -169,26 +300,97 @@ macro_rules! impl_absorbable_field {
                 self.serialize(dest).unwrap()
             }
 
+            /// Elements of another field are absorbed through their byte encoding, packed into
+            /// elements of `F`.
             fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
-                dest.push(field_cast(*self).unwrap())
+                match field_cast(*self) {
+                    Some(elem) => dest.push(elem),
+                    None => batch_field_cast_or_pack(&[*self], dest),
+                }
             }
 
             fn batch_to_sponge_field_elements<F: PrimeField>(batch: &[Self], dest: &mut Vec<F>)
             where
                 Self: Sized,
             {
-                batch_field_cast(batch, dest).unwrap();
+                batch_field_cast_or_pack(batch, dest)
             }
         }
     };
 }
 
+impl_absorbable_field!(Fp64, Fp64Parameters);
 impl_absorbable_field!(Fp256, Fp256Parameters);
 impl_absorbable_field!(Fp320, Fp320Parameters);
 impl_absorbable_field!(Fp384, Fp384Parameters);
 impl_absorbable_field!(Fp768, Fp768Parameters);
 impl_absorbable_field!(Fp832, Fp832Parameters);
 
+/// Big integers are absorbed limb by limb, least significant limb first, exactly like the
+/// `[u64; N]` holding their limbs: each limb is 8 little-endian bytes or one field element.
+/// The value is never reduced, so integers larger than the field modulus are encoded faithfully.
+macro_rules! impl_absorbable_bigint {
+    ($bigint:ident) => {
+        impl Absorb for $bigint {
+            fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+                self.0.to_sponge_bytes(dest)
+            }
+
+            fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
+                self.0.to_sponge_field_elements(dest)
+            }
+        }
+    };
+}
+
+impl_absorbable_bigint!(BigInteger64);
+impl_absorbable_bigint!(BigInteger128);
+impl_absorbable_bigint!(BigInteger256);
+impl_absorbable_bigint!(BigInteger320);
+impl_absorbable_bigint!(BigInteger384);
+impl_absorbable_bigint!(BigInteger448);
+impl_absorbable_bigint!(BigInteger768);
+impl_absorbable_bigint!(BigInteger832);
+
+/// Elements of a quadratic extension `c0 + c1 * X` are absorbed as `c0` followed by `c1`.
+/// For towers of extensions, each coefficient is recursively flattened in the same way, so that
+/// the field elements are the coefficients over the base prime field in ascending order.
+impl<P: QuadExtParameters> Absorb for QuadExtField<P>
+where
+    P::BaseField: Absorb,
+{
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.c0.to_sponge_bytes(dest);
+        self.c1.to_sponge_bytes(dest)
+    }
+
+    fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
+        self.c0.to_sponge_field_elements(dest);
+        self.c1.to_sponge_field_elements(dest)
+    }
+}
+
+/// Elements of a cubic extension `c0 + c1 * X + c2 * X^2` are absorbed as `c0`, `c1`, `c2`,
+/// recursively flattening each coefficient like [`QuadExtField`].
+impl<P: CubicExtParameters> Absorb for CubicExtField<P>
+where
+    P::BaseField: Absorb,
+{
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.c0.to_sponge_bytes(dest);
+        self.c1.to_sponge_bytes(dest);
+        self.c2.to_sponge_bytes(dest)
+    }
+
+    fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
+        self.c0.to_sponge_field_elements(dest);
+        self.c1.to_sponge_field_elements(dest);
+        self.c2.to_sponge_field_elements(dest)
+    }
+}
+
+/// Unsigned integers are absorbed as their fixed-width little-endian bytes,
+/// or as the single field element with the same value.
 macro_rules! impl_absorbable_unsigned {
     ($t:ident) => {
         impl Absorb for $t {
@@ -208,15 +410,17 @@ impl_absorbable_unsigned!(u32);
 impl_absorbable_unsigned!(u64);
 impl_absorbable_unsigned!(u128);
 
+/// Signed integers are absorbed as their fixed-width little-endian two's complement bytes,
+/// or as the single field element `x` for `x >= 0` and `-|x|` (i.e. `p - |x|`) for `x < 0`.
 macro_rules! impl_absorbable_signed {
-    ($signed:ident, $unsigned:ident) => {
+    ($signed:ident) => {
         impl Absorb for $signed {
             fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
                 dest.extend_from_slice(&self.to_le_bytes()[..])
             }
 
             fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
-                let mut elem = F::from(self.abs() as $unsigned);
+                let mut elem = F::from(self.unsigned_abs());
                 if *self < 0 {
                     elem = -elem;
                 }
@@ -226,12 +430,13 @@ macro_rules! impl_absorbable_signed {
     };
 }
 
-impl_absorbable_signed!(i8, u8);
-impl_absorbable_signed!(i16, u16);
-impl_absorbable_signed!(i32, u32);
-impl_absorbable_signed!(i64, u64);
-impl_absorbable_signed!(i128, u128);
+impl_absorbable_signed!(i8);
+impl_absorbable_signed!(i16);
+impl_absorbable_signed!(i32);
+impl_absorbable_signed!(i64);
+impl_absorbable_signed!(i128);
 
+/// `usize` is always absorbed as a `u64`, so that the encoding does not depend on the platform.
 impl Absorb for usize {
     fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
         dest.extend_from_slice(&((*self as u64).to_le_bytes())[..])
@@ -242,9 +447,10 @@ impl Absorb for usize {
     }
 }
 
+/// `isize` is always absorbed as an `i64`, so that the encoding does not depend on the platform.
 impl Absorb for isize {
     fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
-        dest.extend_from_slice(&self.to_le_bytes()[..])
+        (*self as i64).to_sponge_bytes(dest)
     }
 
     fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
@@ -252,13 +458,47 @@ impl Absorb for isize {
     }
 }
 
+/// A string is absorbed as its UTF-8 bytes prefixed with their length, in both encodings: the
+/// bytes are those of [`AbsorbWithLength::to_sponge_bytes_with_length`] for the byte slice, and
+/// the field elements are those of the byte slice, which start with the length and pack the bytes
+/// as the gadgets do. Protocol labels and context strings thus have a single, unambiguous
+/// encoding, which the gadget for `&str` reproduces with constants.
+impl Absorb for &str {
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.as_bytes().to_sponge_bytes_with_length(dest)
+    }
+
+    fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
+        self.as_bytes().to_sponge_field_elements(dest)
+    }
+}
+
+/// A string is absorbed as the `&str` it derefs to.
+impl Absorb for String {
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.as_str().to_sponge_bytes(dest)
+    }
+
+    fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
+        self.as_str().to_sponge_field_elements(dest)
+    }
+}
+
 impl<CF: PrimeField, P: TEModelParameters<BaseField = CF>> Absorb for TEAffine<P> {
     fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
         self.to_field_elements().unwrap().serialize(dest).unwrap()
     }
 
     fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
-        batch_field_cast::<P::BaseField, _>(&self.to_field_elements().unwrap(), dest).unwrap();
+        batch_field_cast_or_pack::<P::BaseField, _>(&self.to_field_elements().unwrap(), dest)
+    }
+
+    fn batch_to_sponge_bytes(batch: &[Self], dest: &mut Vec<u8>) {
+        batch_to_sponge_bytes_in_parallel(batch, dest)
+    }
+
+    fn batch_to_sponge_field_elements<F: PrimeField>(batch: &[Self], dest: &mut Vec<F>) {
+        batch_to_sponge_field_elements_in_parallel(batch, dest)
     }
 }
 
@@ -268,7 +508,50 @@ impl<CF: PrimeField, P: SWModelParameters<BaseField = CF>> Absorb for SWAffine<P
     }
 
     fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
-        batch_field_cast::<P::BaseField, _>(&self.to_field_elements().unwrap(), dest).unwrap();
+        batch_field_cast_or_pack::<P::BaseField, _>(&self.to_field_elements().unwrap(), dest)
+    }
+
+    fn batch_to_sponge_bytes(batch: &[Self], dest: &mut Vec<u8>) {
+        batch_to_sponge_bytes_in_parallel(batch, dest)
+    }
+
+    fn batch_to_sponge_field_elements<F: PrimeField>(batch: &[Self], dest: &mut Vec<F>) {
+        batch_to_sponge_field_elements_in_parallel(batch, dest)
+    }
+}
+
+/// Projective points are absorbed through their affine representation, so that a point is encoded
+/// identically regardless of the coordinates it is held in.
+impl<CF: PrimeField, P: TEModelParameters<BaseField = CF>> Absorb for TEProjective<P> {
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.into_affine().to_sponge_bytes(dest)
+    }
+
+    fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
+        self.into_affine().to_sponge_field_elements(dest)
+    }
+}
+
+/// Projective points are absorbed through their affine representation (including the infinity
+/// flag), so that a point is encoded identically regardless of the coordinates it is held in.
+impl<CF: PrimeField, P: SWModelParameters<BaseField = CF>> Absorb for SWProjective<P> {
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.into_affine().to_sponge_bytes(dest)
+    }
+
+    fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
+        self.into_affine().to_sponge_field_elements(dest)
+    }
+
+    fn batch_to_sponge_bytes(batch: &[Self], dest: &mut Vec<u8>) {
+        SWAffine::batch_to_sponge_bytes(&Self::batch_normalization_into_affine(batch), dest)
+    }
+
+    fn batch_to_sponge_field_elements<F: PrimeField>(batch: &[Self], dest: &mut Vec<F>) {
+        SWAffine::batch_to_sponge_field_elements(
+            &Self::batch_normalization_into_affine(batch),
+            dest,
+        )
     }
 }
 
@@ -296,6 +579,22 @@ impl<A: Absorb> Absorb for Vec<A> {
     fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
         self.as_slice().to_sponge_field_elements(dest)
     }
+
+    /// A list of lists is ambiguous without the inner lengths,
+    /// so each inner list is prefixed with its length.
+    fn batch_to_sponge_bytes(batch: &[Self], dest: &mut Vec<u8>) {
+        for list in batch {
+            list.to_sponge_bytes_with_length(dest)
+        }
+    }
+
+    /// A list of lists is ambiguous without the inner lengths,
+    /// so each inner list is prefixed with its length.
+    fn batch_to_sponge_field_elements<F: PrimeField>(batch: &[Self], dest: &mut Vec<F>) {
+        for list in batch {
+            list.to_sponge_field_elements_with_length(dest)
+        }
+    }
 }
 
 impl<A: Absorb> AbsorbWithLength for Vec<A> {
@@ -304,6 +603,39 @@ impl<A: Absorb> AbsorbWithLength for Vec<A> {
     }
 }
 
+impl<A: Absorb, const N: usize> Absorb for [A; N] {
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        A::batch_to_sponge_bytes(self, dest)
+    }
+
+    fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
+        A::batch_to_sponge_field_elements(self, dest)
+    }
+}
+
+macro_rules! impl_absorbable_tuple {
+    ($($name:ident : $index:tt),+) => {
+        impl<$($name: Absorb),+> Absorb for ($($name,)+) {
+            fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+                $(self.$index.to_sponge_bytes(dest);)+
+            }
+
+            fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
+                $(self.$index.to_sponge_field_elements(dest);)+
+            }
+        }
+    };
+}
+
+impl_absorbable_tuple!(A: 0);
+impl_absorbable_tuple!(A: 0, B: 1);
+impl_absorbable_tuple!(A: 0, B: 1, C: 2);
+impl_absorbable_tuple!(A: 0, B: 1, C: 2, D: 3);
+impl_absorbable_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4);
+impl_absorbable_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, G: 5);
+impl_absorbable_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, G: 5, H: 6);
+impl_absorbable_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, G: 5, H: 6, I: 7);
+
 impl<A: Absorb> Absorb for Option<A> {
     fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
         self.is_some().to_sponge_bytes(dest);
@@ -320,6 +652,176 @@ impl<A: Absorb> Absorb for Option<A> {
     }
 }
 
+/// A dense polynomial is absorbed as its number of coefficients followed by the coefficients,
+/// from the constant term upwards.
+#[cfg(feature = "poly")]
+impl<F: Field + Absorb> Absorb for DensePolynomial<F> {
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.coeffs.to_sponge_bytes_with_length(dest)
+    }
+
+    fn to_sponge_field_elements<CF: PrimeField>(&self, dest: &mut Vec<CF>) {
+        self.coeffs.to_sponge_field_elements_with_length(dest)
+    }
+}
+
+/// A sparse polynomial is absorbed as its number of terms followed by each `(degree, coefficient)`
+/// pair, in increasing order of degree.
+#[cfg(feature = "poly")]
+impl<F: Field + Absorb> Absorb for SparsePolynomial<F> {
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.len().to_sponge_bytes(dest);
+        for (degree, coeff) in self.iter() {
+            degree.to_sponge_bytes(dest);
+            coeff.to_sponge_bytes(dest);
+        }
+    }
+
+    fn to_sponge_field_elements<CF: PrimeField>(&self, dest: &mut Vec<CF>) {
+        self.len().to_sponge_field_elements(dest);
+        for (degree, coeff) in self.iter() {
+            degree.to_sponge_field_elements(dest);
+            coeff.to_sponge_field_elements(dest);
+        }
+    }
+}
+
+/// Evaluations are absorbed as the number of evaluations followed by the evaluations,
+/// in the order of the elements of the evaluation domain.
+#[cfg(feature = "poly")]
+impl<F: FftField + Absorb, D: EvaluationDomain<F>> Absorb for Evaluations<F, D> {
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.evals.to_sponge_bytes_with_length(dest)
+    }
+
+    fn to_sponge_field_elements<CF: PrimeField>(&self, dest: &mut Vec<CF>) {
+        self.evals.to_sponge_field_elements_with_length(dest)
+    }
+}
+
+/// Appends the bytes of `point`, a point of a pairing group, encoded as the points of curves over
+/// prime fields: its coordinates in the base prime field `CF` and its infinity flag, preceded by
+/// their number as a little-endian `u64`. Points of `G2`, whose coordinates are in an extension
+/// field, are thereby encoded as the gadgets of their curves encode them.
+#[cfg(feature = "groth16")]
+fn pairing_point_to_sponge_bytes<CF: PrimeField, G: ToConstraintField<CF>>(
+    point: &G,
+    dest: &mut Vec<u8>,
+) {
+    point.to_field_elements().unwrap().serialize(dest).unwrap()
+}
+
+/// Appends the field elements of `point`, a point of a pairing group, encoded as the points of
+/// curves over prime fields: its coordinates in the base prime field `CF` and its infinity flag.
+#[cfg(feature = "groth16")]
+fn pairing_point_to_sponge_field_elements<
+    CF: PrimeField,
+    G: ToConstraintField<CF>,
+    F: PrimeField,
+>(
+    point: &G,
+    dest: &mut Vec<F>,
+) {
+    batch_field_cast_or_pack::<CF, _>(&point.to_field_elements().unwrap(), dest)
+}
+
+/// A Groth16 proof is absorbed as its points `a`, `b` and `c`, in this order, each as its
+/// coordinates in the base prime field of the pairing followed by its infinity flag. This is how
+/// the gadget of the proof absorbs it.
+#[cfg(feature = "groth16")]
+impl<E: PairingEngine> Absorb for Proof<E>
+where
+    E::G1Affine: ToConstraintField<E::Fq>,
+    E::G2Affine: ToConstraintField<E::Fq>,
+{
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        pairing_point_to_sponge_bytes::<E::Fq, _>(&self.a, dest);
+        pairing_point_to_sponge_bytes::<E::Fq, _>(&self.b, dest);
+        pairing_point_to_sponge_bytes::<E::Fq, _>(&self.c, dest);
+    }
+
+    fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
+        pairing_point_to_sponge_field_elements::<E::Fq, _, _>(&self.a, dest);
+        pairing_point_to_sponge_field_elements::<E::Fq, _, _>(&self.b, dest);
+        pairing_point_to_sponge_field_elements::<E::Fq, _, _>(&self.c, dest);
+    }
+}
+
+/// A Groth16 verifying key is absorbed as `alpha_g1`, `beta_g2`, `gamma_g2` and `delta_g2`, then
+/// the number of points of `gamma_abc_g1`, as a `usize`, and these points, with the points
+/// encoded as those of a [`Proof`].
+#[cfg(feature = "groth16")]
+impl<E: PairingEngine> Absorb for VerifyingKey<E>
+where
+    E::G1Affine: ToConstraintField<E::Fq>,
+    E::G2Affine: ToConstraintField<E::Fq>,
+{
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        pairing_point_to_sponge_bytes::<E::Fq, _>(&self.alpha_g1, dest);
+        pairing_point_to_sponge_bytes::<E::Fq, _>(&self.beta_g2, dest);
+        pairing_point_to_sponge_bytes::<E::Fq, _>(&self.gamma_g2, dest);
+        pairing_point_to_sponge_bytes::<E::Fq, _>(&self.delta_g2, dest);
+        self.gamma_abc_g1.len().to_sponge_bytes(dest);
+        for point in &self.gamma_abc_g1 {
+            pairing_point_to_sponge_bytes::<E::Fq, _>(point, dest);
+        }
+    }
+
+    fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
+        pairing_point_to_sponge_field_elements::<E::Fq, _, _>(&self.alpha_g1, dest);
+        pairing_point_to_sponge_field_elements::<E::Fq, _, _>(&self.beta_g2, dest);
+        pairing_point_to_sponge_field_elements::<E::Fq, _, _>(&self.gamma_g2, dest);
+        pairing_point_to_sponge_field_elements::<E::Fq, _, _>(&self.delta_g2, dest);
+        self.gamma_abc_g1.len().to_sponge_field_elements(dest);
+        for point in &self.gamma_abc_g1 {
+            pairing_point_to_sponge_field_elements::<E::Fq, _, _>(point, dest);
+        }
+    }
+}
+
+/// A prepared Groth16 verifying key is absorbed as its [`VerifyingKey`], from which the prepared
+/// elements are derived.
+#[cfg(feature = "groth16")]
+impl<E: PairingEngine> Absorb for PreparedVerifyingKey<E>
+where
+    E::G1Affine: ToConstraintField<E::Fq>,
+    E::G2Affine: ToConstraintField<E::Fq>,
+{
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.vk.to_sponge_bytes(dest)
+    }
+
+    fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
+        self.vk.to_sponge_field_elements(dest)
+    }
+}
+
+/// Absorbs any [`CanonicalSerialize`] type through its canonical (compressed) serialization.
+///
+/// This lets protocol objects such as proofs and keys be absorbed without a dedicated [`Absorb`]
+/// implementation. The bytes are the serialization itself, and the field elements are those
+/// bytes packed like any other list of bytes (a length prefix followed by the packed bytes).
+#[derive(Clone, Copy, Debug)]
+pub struct AbsorbSerialized<'a, T: CanonicalSerialize + ?Sized>(pub &'a T);
+
+impl<'a, T: CanonicalSerialize + ?Sized> AbsorbSerialized<'a, T> {
+    fn serialized_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.0.serialized_size());
+        self.0.serialize(&mut bytes).unwrap();
+        bytes
+    }
+}
+
+impl<'a, T: CanonicalSerialize + ?Sized> Absorb for AbsorbSerialized<'a, T> {
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.0.serialize(dest).unwrap()
+    }
+
+    fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
+        self.serialized_bytes().to_sponge_field_elements(dest)
+    }
+}
+
 // TODO: add more for common data structures, treemap?
 
 impl<A: Absorb> Absorb for &A {
@@ -332,6 +834,119 @@ impl<A: Absorb> Absorb for &A {
     }
 }
 
+/// An object-safe form of [`Absorb`] for sponges over `F`, so that values of different types,
+/// such as the points, scalars and byte strings of a protocol message, can be absorbed in one
+/// call as a `&[&dyn Absorbable<F>]` or with an [`AbsorbList`].
+///
+/// Every [`Absorb`] type implements it, and a `&dyn Absorbable<F>` is absorbed as the value it
+/// refers to, without a length prefix.
+pub trait Absorbable<F: PrimeField> {
+    /// Appends the encoding of [`Absorb::to_sponge_bytes`] to `dest`.
+    fn absorbable_to_sponge_bytes(&self, dest: &mut Vec<u8>);
+
+    /// Appends the encoding of [`Absorb::to_sponge_field_elements`] to `dest`.
+    fn absorbable_to_sponge_field_elements(&self, dest: &mut Vec<F>);
+}
+
+impl<F: PrimeField, A: Absorb> Absorbable<F> for A {
+    fn absorbable_to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.to_sponge_bytes(dest)
+    }
+
+    fn absorbable_to_sponge_field_elements(&self, dest: &mut Vec<F>) {
+        self.to_sponge_field_elements(dest)
+    }
+}
+
+/// A value absorbed into a sponge over another field than `F` is encoded for `F` first, and its
+/// elements are then cast or packed as elements of `F` are.
+impl<F: PrimeField> Absorb for &dyn Absorbable<F> {
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        (**self).absorbable_to_sponge_bytes(dest)
+    }
+
+    fn to_sponge_field_elements<F2: PrimeField>(&self, dest: &mut Vec<F2>) {
+        let mut elems = Vec::<F>::new();
+        (**self).absorbable_to_sponge_field_elements(&mut elems);
+        batch_field_cast_or_pack(&elems, dest)
+    }
+}
+
+/// A list of values of different types, absorbed one after the other as a single input.
+///
+/// The values are only borrowed, and are converted when the list is absorbed, directly into the
+/// buffer of the sponge, so that a protocol message is absorbed without building a temporary
+/// vector per type:
+///
+/// ```
+/// # use ark_sponge::{AbsorbList, CryptographicSponge};
+/// # use ark_sponge::poseidon::{PoseidonParameters, PoseidonSponge};
+/// # use ark_test_curves::bls12_381::Fr;
+/// let params = PoseidonParameters::<Fr>::default();
+/// let mut sponge = PoseidonSponge::new(&params);
+/// let (commitment, label) = (Fr::from(7u64), b"round 1".to_vec());
+/// sponge.absorb(&AbsorbList::<Fr>::new().with(&commitment).with(&label).with(&3u32));
+/// ```
+///
+/// The list is absorbed exactly as the tuple of its values, without a length prefix. Its field
+/// is that of the sponge, and must be given when the list is created, since it cannot be inferred
+/// from [`CryptographicSponge::absorb`](crate::CryptographicSponge::absorb).
+#[derive(Clone)]
+pub struct AbsorbList<'a, F: PrimeField> {
+    items: Vec<&'a dyn Absorbable<F>>,
+}
+
+impl<'a, F: PrimeField> AbsorbList<'a, F> {
+    /// Creates an empty list.
+    pub fn new() -> Self {
+        Self { items: Vec::new() }
+    }
+
+    /// Appends `item` to the list.
+    pub fn push(&mut self, item: &'a dyn Absorbable<F>) -> &mut Self {
+        self.items.push(item);
+        self
+    }
+
+    /// Returns the list with `item` appended.
+    pub fn with(mut self, item: &'a dyn Absorbable<F>) -> Self {
+        self.items.push(item);
+        self
+    }
+
+    /// The number of values in the list.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Whether the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+impl<'a, F: PrimeField> Default for AbsorbList<'a, F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, F: PrimeField> Absorb for AbsorbList<'a, F> {
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        for item in &self.items {
+            (**item).absorbable_to_sponge_bytes(dest)
+        }
+    }
+
+    fn to_sponge_field_elements<F2: PrimeField>(&self, dest: &mut Vec<F2>) {
+        let mut elems = Vec::<F>::new();
+        for item in &self.items {
+            (**item).absorbable_to_sponge_field_elements(&mut elems)
+        }
+        batch_field_cast_or_pack(&elems, dest)
+    }
+}
+
 /// Individually absorbs each element in a comma-separated list of absorbables into a sponge.
 /// Format is `absorb!(s, a_0, a_1, ..., a_n)`, where `s` is a mutable reference to a sponge
 /// and each `a_i` implements `Absorb`.
@@ -373,12 +988,136 @@ macro_rules! collect_sponge_field_elements {
 }
 
 #[cfg(test)]
-mod tests {
-    use crate::{batch_field_cast, field_cast};
-    use ark_ff::UniformRand;
+pub(crate) mod tests {
+    use crate::{batch_field_cast, field_cast, Absorb, AbsorbSerialized};
+    use ark_ff::{field_new, BigInteger, BigInteger384, Fp2, Fp2Parameters, UniformRand};
+    use ark_serialize::CanonicalSerialize;
     use ark_std::test_rng;
+    use ark_std::vec;
     use ark_std::vec::Vec;
-    use ark_test_curves::bls12_381::Fr;
+    use ark_test_curves::bls12_381::{Fq, Fr};
+
+    /// The quadratic extension of the BLS12-381 base field.
+    pub(crate) struct Fq2Parameters;
+
+    impl Fp2Parameters for Fq2Parameters {
+        type Fp = Fq;
+
+        const NONRESIDUE: Fq = field_new!(Fq, "-1");
+
+        const QUADRATIC_NONRESIDUE: (Fq, Fq) = (field_new!(Fq, "1"), field_new!(Fq, "1"));
+
+        const FROBENIUS_COEFF_FP2_C1: &'static [Fq] = &[field_new!(Fq, "1"), field_new!(Fq, "-1")];
+    }
+
+    pub(crate) type Fq2 = Fp2<Fq2Parameters>;
+
+    #[test]
+    fn test_integer_encodings() {
+        assert_eq!((-2i16).to_sponge_bytes_as_vec(), vec![0xfe, 0xff]);
+        assert_eq!(
+            (-2i16).to_sponge_field_elements_as_vec::<Fr>(),
+            vec![-Fr::from(2u8)]
+        );
+        assert_eq!(
+            i128::MIN.to_sponge_field_elements_as_vec::<Fr>(),
+            vec![-Fr::from(1u128 << 127)]
+        );
+
+        // pointer-sized integers use the 64-bit encoding on every platform
+        assert_eq!(
+            7usize.to_sponge_bytes_as_vec(),
+            7u64.to_sponge_bytes_as_vec()
+        );
+        assert_eq!(
+            (-7isize).to_sponge_bytes_as_vec(),
+            (-7i64).to_sponge_bytes_as_vec()
+        );
+        assert_eq!(
+            (-7isize).to_sponge_field_elements_as_vec::<Fr>(),
+            (-7i64).to_sponge_field_elements_as_vec::<Fr>()
+        );
+    }
+
+    #[test]
+    fn test_nonnative_field_elements_are_packed() {
+        let mut rng = test_rng();
+        let elem = Fq::rand(&mut rng);
+
+        let mut bytes = Vec::new();
+        elem.serialize(&mut bytes).unwrap();
+        let mut expected = Vec::<Fr>::new();
+        crate::pack_bytes(&bytes, &mut expected);
+
+        assert_eq!(elem.to_sponge_field_elements_as_vec::<Fr>(), expected);
+        assert_eq!(elem.to_sponge_field_elements_as_vec::<Fq>(), vec![elem]);
+    }
+
+    #[test]
+    fn test_bigint_limbs() {
+        let bigint = BigInteger384([1, 2, 3, 4, 5, u64::MAX]);
+        assert_eq!(bigint.to_sponge_bytes_as_vec(), bigint.to_bytes_le());
+
+        let expected: Vec<Fr> = bigint.0.iter().map(|limb| Fr::from(*limb)).collect();
+        assert_eq!(bigint.to_sponge_field_elements_as_vec::<Fr>(), expected);
+    }
+
+    #[test]
+    fn test_absorb_serialized() {
+        let mut rng = test_rng();
+        let value: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+
+        let mut serialized = Vec::new();
+        value.serialize(&mut serialized).unwrap();
+
+        let wrapped = AbsorbSerialized(&value);
+        assert_eq!(wrapped.to_sponge_bytes_as_vec(), serialized);
+        assert_eq!(
+            wrapped.to_sponge_field_elements_as_vec::<Fr>(),
+            serialized.to_sponge_field_elements_as_vec::<Fr>()
+        );
+    }
+
+    #[cfg(feature = "poly")]
+    #[test]
+    fn test_polynomials() {
+        use crate::AbsorbWithLength;
+        use ark_poly::univariate::{DensePolynomial, SparsePolynomial};
+        use ark_poly::{EvaluationDomain, GeneralEvaluationDomain, UVPolynomial};
+
+        let mut rng = test_rng();
+        let dense = DensePolynomial::<Fr>::rand(7, &mut rng);
+        let mut expected: Vec<Fr> = vec![Fr::from(8u8)];
+        expected.extend_from_slice(&dense.coeffs);
+        assert_eq!(dense.to_sponge_field_elements_as_vec::<Fr>(), expected);
+
+        let coeff = Fr::rand(&mut rng);
+        let sparse = SparsePolynomial::from_coefficients_vec(vec![(5, coeff)]);
+        let expected: Vec<Fr> = vec![Fr::from(1u8), Fr::from(5u8), coeff];
+        assert_eq!(sparse.to_sponge_field_elements_as_vec::<Fr>(), expected);
+
+        let domain = GeneralEvaluationDomain::<Fr>::new(8).unwrap();
+        let evals = dense.clone().evaluate_over_domain(domain);
+        let mut expected = vec![Fr::from(8u8)];
+        expected.extend_from_slice(&evals.evals);
+        assert_eq!(evals.to_sponge_field_elements_as_vec::<Fr>(), expected);
+        let mut expected = Vec::new();
+        evals.evals.to_sponge_bytes_with_length(&mut expected);
+        assert_eq!(evals.to_sponge_bytes_as_vec(), expected);
+    }
+
+    #[test]
+    fn test_extension_field_order() {
+        let mut rng = test_rng();
+        let elem = Fq2::rand(&mut rng);
+
+        let expected = vec![elem.c0, elem.c1];
+        assert_eq!(elem.to_sponge_field_elements_as_vec::<Fq>(), expected);
+        assert_eq!(
+            elem.to_sponge_bytes_as_vec(),
+            expected.to_sponge_bytes_as_vec()
+        );
+    }
 
     #[test]
     fn test_cast() {
@@ -391,4 +1130,63 @@ mod tests {
         batch_field_cast::<_, Fr>(&expected, &mut actual).unwrap();
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    #[cfg(all(feature = "groth16", feature = "bls12_381"))]
+    fn test_groth16_encodings() {
+        use ark_bls12_381::{Bls12_381, Fq, G1Projective, G2Projective};
+        use ark_ec::ProjectiveCurve;
+        use ark_ff::ToConstraintField;
+        use ark_groth16::{prepare_verifying_key, Proof, VerifyingKey};
+
+        let mut rng = test_rng();
+        let g1: Vec<_> = (0..5)
+            .map(|_| G1Projective::rand(&mut rng).into_affine())
+            .collect();
+        let g2: Vec<_> = (0..4)
+            .map(|_| G2Projective::rand(&mut rng).into_affine())
+            .collect();
+        let (a, c, alpha_g1) = (g1[0], g1[1], g1[2]);
+        let gamma_abc_g1 = g1[3..].to_vec();
+        let (b, beta_g2, gamma_g2, delta_g2) = (g2[0], g2[1], g2[2], g2[3]);
+
+        let proof = Proof::<Bls12_381> { a, b, c };
+        let mut expected: Vec<Fq> = a.to_field_elements().unwrap();
+        expected.extend(b.to_field_elements().unwrap());
+        expected.extend(c.to_field_elements().unwrap());
+        assert_eq!(expected.len(), 11);
+        assert_eq!(proof.to_sponge_field_elements_as_vec::<Fq>(), expected);
+        let mut expected_bytes = a.to_sponge_bytes_as_vec();
+        b.to_field_elements()
+            .unwrap()
+            .serialize(&mut expected_bytes)
+            .unwrap();
+        expected_bytes.extend(c.to_sponge_bytes_as_vec());
+        assert_eq!(proof.to_sponge_bytes_as_vec(), expected_bytes);
+
+        let vk = VerifyingKey::<Bls12_381> {
+            alpha_g1,
+            beta_g2,
+            gamma_g2,
+            delta_g2,
+            gamma_abc_g1,
+        };
+        let elems = vk.to_sponge_field_elements_as_vec::<Fq>();
+        assert_eq!(elems.len(), 3 + 3 * 5 + 1 + 2 * 3);
+        assert_eq!(elems[18], Fq::from(2u64));
+        // the points of `gamma_abc_g1` are preceded by their number
+        let mut shorter = vk.clone();
+        shorter.gamma_abc_g1.pop();
+        assert_eq!(
+            shorter.to_sponge_field_elements_as_vec::<Fq>()[18],
+            Fq::from(1u64)
+        );
+
+        let pvk = prepare_verifying_key(&vk);
+        assert_eq!(pvk.to_sponge_bytes_as_vec(), vk.to_sponge_bytes_as_vec());
+        assert_eq!(
+            pvk.to_sponge_field_elements_as_vec::<Fr>(),
+            vk.to_sponge_field_elements_as_vec::<Fr>()
+        );
+    }
 }
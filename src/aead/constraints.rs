@@ -0,0 +1,161 @@
+use crate::aead::AEAD_DOMAIN;
+use crate::duplex::constraints::FieldPermutationGadget;
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::SynthesisError;
+use ark_std::vec;
+use ark_std::vec::Vec;
+
+/// The gadget for [`SpongeAead`](crate::aead::SpongeAead).
+#[derive(Clone)]
+pub struct SpongeAeadVar<F: PrimeField, P: FieldPermutationGadget<F>> {
+    permutation: P,
+    key: Vec<FpVar<F>>,
+}
+
+impl<F: PrimeField, P: FieldPermutationGadget<F>> SpongeAeadVar<F, P> {
+    /// Creates the scheme for the given permutation and key.
+    pub fn new(permutation: &P, key: &[FpVar<F>]) -> Self {
+        Self {
+            permutation: permutation.clone(),
+            key: key.to_vec(),
+        }
+    }
+
+    #[tracing::instrument(target = "r1cs", skip(self, nonce, associated_data))]
+    fn start(
+        &self,
+        nonce: &[FpVar<F>],
+        associated_data: &[FpVar<F>],
+        plaintext_len: usize,
+    ) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        let rate = self.permutation.rate();
+        let capacity = self.permutation.capacity();
+        let mut state = vec![FpVar::zero(); rate + capacity];
+        state[0] = FpVar::Constant(F::from_le_bytes_mod_order(AEAD_DOMAIN));
+
+        let lengths = [
+            self.key.len(),
+            nonce.len(),
+            associated_data.len(),
+            plaintext_len,
+        ]
+        .iter()
+        .map(|len| FpVar::Constant(F::from(*len as u64)))
+        .collect::<Vec<_>>();
+        let header = [&lengths, &self.key[..], nonce, associated_data].concat();
+        for chunk in header.chunks(rate) {
+            for (state_elem, elem) in state[capacity..].iter_mut().zip(chunk) {
+                *state_elem += elem;
+            }
+            self.permutation.permute_var(&mut state)?;
+        }
+        Ok(state)
+    }
+
+    /// Encrypts `plaintext` with `nonce`, authenticating it along with `associated_data`.
+    /// Returns the ciphertext followed by the tag.
+    #[tracing::instrument(target = "r1cs", skip(self, nonce, associated_data, plaintext))]
+    pub fn seal(
+        &self,
+        nonce: &[FpVar<F>],
+        associated_data: &[FpVar<F>],
+        plaintext: &[FpVar<F>],
+    ) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        let capacity = self.permutation.capacity();
+        let mut state = self.start(nonce, associated_data, plaintext.len())?;
+
+        let mut ciphertext = Vec::with_capacity(plaintext.len() + 1);
+        for chunk in plaintext.chunks(self.permutation.rate()) {
+            for (state_elem, elem) in state[capacity..].iter_mut().zip(chunk) {
+                *state_elem += elem;
+                ciphertext.push(state_elem.clone());
+            }
+            self.permutation.permute_var(&mut state)?;
+        }
+        ciphertext.push(state[capacity].clone());
+        Ok(ciphertext)
+    }
+
+    /// Decrypts `ciphertext`, the output of [`SpongeAeadVar::seal`] for the same nonce and
+    /// associated data. Returns the plaintext and whether the tag is valid.
+    #[tracing::instrument(target = "r1cs", skip(self, nonce, associated_data, ciphertext))]
+    pub fn open(
+        &self,
+        nonce: &[FpVar<F>],
+        associated_data: &[FpVar<F>],
+        ciphertext: &[FpVar<F>],
+    ) -> Result<(Vec<FpVar<F>>, Boolean<F>), SynthesisError> {
+        let (tag, ciphertext) = ciphertext
+            .split_last()
+            .ok_or(SynthesisError::Unsatisfiable)?;
+        let capacity = self.permutation.capacity();
+        let mut state = self.start(nonce, associated_data, ciphertext.len())?;
+
+        let mut plaintext = Vec::with_capacity(ciphertext.len());
+        for chunk in ciphertext.chunks(self.permutation.rate()) {
+            for (state_elem, elem) in state[capacity..].iter_mut().zip(chunk) {
+                plaintext.push(elem - &*state_elem);
+                *state_elem = elem.clone();
+            }
+            self.permutation.permute_var(&mut state)?;
+        }
+
+        let is_valid = state[capacity].is_eq(tag)?;
+        Ok((plaintext, is_valid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aead::constraints::SpongeAeadVar;
+    use crate::aead::SpongeAead;
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::PoseidonPermutation;
+    use ark_ff::UniformRand;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::r1cs::{ConstraintSystem, ConstraintSystemRef};
+    use ark_std::test_rng;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    fn witnesses(cs: &ConstraintSystemRef<Fr>, elems: &[Fr]) -> Vec<FpVar<Fr>> {
+        elems
+            .iter()
+            .map(|e| FpVar::new_witness(cs.clone(), || Ok(*e)).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_seal_open_var() {
+        let mut rng = test_rng();
+        let permutation = PoseidonPermutation::new(&poseidon_parameters_for_test());
+        let key = [Fr::rand(&mut rng)];
+        let nonce = [Fr::from(1u64)];
+        let plaintext: Vec<_> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        let aead = SpongeAead::new(&permutation, &key);
+        let ciphertext = aead.seal(&nonce, &[], &plaintext);
+
+        let cs = ConstraintSystem::new_ref();
+        let key_var = witnesses(&cs, &key);
+        let nonce_var = witnesses(&cs, &nonce);
+        let plaintext_var = witnesses(&cs, &plaintext);
+        let ciphertext_var = witnesses(&cs, &ciphertext);
+        let aead_var = SpongeAeadVar::new(&permutation, &key_var);
+
+        let sealed = aead_var.seal(&nonce_var, &[], &plaintext_var).unwrap();
+        assert_eq!(sealed.value().unwrap(), ciphertext);
+
+        let (opened, is_valid) = aead_var.open(&nonce_var, &[], &ciphertext_var).unwrap();
+        assert_eq!(opened.value().unwrap(), plaintext);
+        assert!(is_valid.value().unwrap());
+
+        let mut forged = ciphertext_var;
+        forged[0] += FpVar::one();
+        let (_, is_valid) = aead_var.open(&nonce_var, &[], &forged).unwrap();
+        assert!(!is_valid.value().unwrap());
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
@@ -0,0 +1,207 @@
+use crate::duplex::FieldPermutation;
+use ark_ff::{BigInteger, PrimeField};
+use ark_std::vec;
+use ark_std::vec::Vec;
+
+/// constraints for the sponge AEAD
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+/// The domain separator placed in the capacity of the state of [`SpongeAead`].
+pub const AEAD_DOMAIN: &[u8] = b"ark-sponge/aead";
+
+/// A SpongeWrap-style authenticated encryption scheme with associated data over the permutation
+/// `P`, with keys, nonces, associated data and ciphertexts made of field elements.
+///
+/// The state starts with [`AEAD_DOMAIN`] in its capacity and absorbs the lengths of the key, the
+/// nonce, the associated data and the plaintext, then the key, the nonce and the associated
+/// data, permuting after every full rate. Each block of `rate` plaintext elements is then
+/// encrypted by adding it to the rate, which is overwritten with the ciphertext before the next
+/// permutation. The tag is the first element of the rate at the end, and is appended to the
+/// ciphertext.
+///
+/// A nonce must never be reused with the same key.
+#[derive(Clone)]
+pub struct SpongeAead<F: PrimeField, P: FieldPermutation<F>> {
+    permutation: P,
+    key: Vec<F>,
+}
+
+impl<F: PrimeField, P: FieldPermutation<F>> SpongeAead<F, P> {
+    /// Creates the scheme for the given permutation and key.
+    pub fn new(permutation: &P, key: &[F]) -> Self {
+        Self {
+            permutation: permutation.clone(),
+            key: key.to_vec(),
+        }
+    }
+
+    fn start(&self, nonce: &[F], associated_data: &[F], plaintext_len: usize) -> Vec<F> {
+        let rate = self.permutation.rate();
+        let capacity = self.permutation.capacity();
+        let mut state = vec![F::zero(); rate + capacity];
+        state[0] = F::from_le_bytes_mod_order(AEAD_DOMAIN);
+
+        let lengths = [
+            self.key.len(),
+            nonce.len(),
+            associated_data.len(),
+            plaintext_len,
+        ]
+        .iter()
+        .map(|len| F::from(*len as u64))
+        .collect::<Vec<_>>();
+        let header = [&lengths, &self.key[..], nonce, associated_data].concat();
+        for chunk in header.chunks(rate) {
+            for (state_elem, elem) in state[capacity..].iter_mut().zip(chunk) {
+                *state_elem += elem;
+            }
+            self.permutation.permute(&mut state);
+        }
+        state
+    }
+
+    /// Encrypts `plaintext` with `nonce`, authenticating it along with `associated_data`.
+    /// Returns the ciphertext followed by the tag.
+    pub fn seal(&self, nonce: &[F], associated_data: &[F], plaintext: &[F]) -> Vec<F> {
+        let capacity = self.permutation.capacity();
+        let mut state = self.start(nonce, associated_data, plaintext.len());
+
+        let mut ciphertext = Vec::with_capacity(plaintext.len() + 1);
+        for chunk in plaintext.chunks(self.permutation.rate()) {
+            for (state_elem, elem) in state[capacity..].iter_mut().zip(chunk) {
+                *state_elem += elem;
+                ciphertext.push(*state_elem);
+            }
+            self.permutation.permute(&mut state);
+        }
+        ciphertext.push(state[capacity]);
+        ciphertext
+    }
+
+    /// Decrypts `ciphertext`, the output of [`SpongeAead::seal`] for the same nonce and
+    /// associated data. Returns `None` if the tag is invalid.
+    pub fn open(&self, nonce: &[F], associated_data: &[F], ciphertext: &[F]) -> Option<Vec<F>> {
+        let (tag, ciphertext) = ciphertext.split_last()?;
+        let capacity = self.permutation.capacity();
+        let mut state = self.start(nonce, associated_data, ciphertext.len());
+
+        let mut plaintext = Vec::with_capacity(ciphertext.len());
+        for chunk in ciphertext.chunks(self.permutation.rate()) {
+            for (state_elem, elem) in state[capacity..].iter_mut().zip(chunk) {
+                plaintext.push(*elem - *state_elem);
+                *state_elem = *elem;
+            }
+            self.permutation.permute(&mut state);
+        }
+
+        if state[capacity] == *tag {
+            Some(plaintext)
+        } else {
+            None
+        }
+    }
+
+    /// Encrypts the bytes `plaintext`, which are packed into field elements of
+    /// `F::Params::CAPACITY / 8` bytes after a `0x01` byte is appended to them.
+    pub fn seal_bytes(&self, nonce: &[F], associated_data: &[F], plaintext: &[u8]) -> Vec<F> {
+        let mut padded = plaintext.to_vec();
+        padded.push(1);
+        let packed: Vec<F> = padded
+            .chunks(bytes_per_element::<F>())
+            .map(F::from_le_bytes_mod_order)
+            .collect();
+        self.seal(nonce, associated_data, &packed)
+    }
+
+    /// Decrypts the output of [`SpongeAead::seal_bytes`]. Returns `None` if the tag or the
+    /// encoding of the plaintext is invalid.
+    pub fn open_bytes(
+        &self,
+        nonce: &[F],
+        associated_data: &[F],
+        ciphertext: &[F],
+    ) -> Option<Vec<u8>> {
+        let packed = self.open(nonce, associated_data, ciphertext)?;
+        let usable_bytes = bytes_per_element::<F>();
+        let mut plaintext = Vec::with_capacity(packed.len() * usable_bytes);
+        for elem in &packed {
+            let bytes = elem.into_repr().to_bytes_le();
+            if bytes[usable_bytes..].iter().any(|b| *b != 0) {
+                return None;
+            }
+            plaintext.extend_from_slice(&bytes[..usable_bytes]);
+        }
+
+        // Only the last element may end with zeros, after the padding byte.
+        let padding = plaintext.iter().rposition(|b| *b != 0)?;
+        if plaintext[padding] != 1 || plaintext.len() - padding > usable_bytes {
+            return None;
+        }
+        plaintext.truncate(padding);
+        Some(plaintext)
+    }
+}
+
+/// The number of bytes packed into each field element by [`SpongeAead::seal_bytes`].
+fn bytes_per_element<F: PrimeField>() -> usize {
+    crate::squeeze_bytes_per_element::<F>()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aead::SpongeAead;
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::PoseidonPermutation;
+    use ark_ff::UniformRand;
+    use ark_std::test_rng;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    fn aead() -> SpongeAead<Fr, PoseidonPermutation<Fr>> {
+        let permutation = PoseidonPermutation::new(&poseidon_parameters_for_test());
+        SpongeAead::new(&permutation, &[Fr::from(1u64), Fr::from(2u64)])
+    }
+
+    #[test]
+    fn test_seal_open() {
+        let mut rng = test_rng();
+        let aead = aead();
+        let nonce = [Fr::from(7u64)];
+        let associated_data = [Fr::from(8u64)];
+
+        for len in 0..6 {
+            let plaintext: Vec<_> = (0..len).map(|_| Fr::rand(&mut rng)).collect();
+            let ciphertext = aead.seal(&nonce, &associated_data, &plaintext);
+            assert_eq!(ciphertext.len(), len + 1);
+            assert_eq!(
+                aead.open(&nonce, &associated_data, &ciphertext),
+                Some(plaintext.clone())
+            );
+
+            // Any change to the inputs is detected.
+            assert_eq!(
+                aead.open(&[Fr::from(6u64)], &associated_data, &ciphertext),
+                None
+            );
+            assert_eq!(aead.open(&nonce, &[], &ciphertext), None);
+            for i in 0..ciphertext.len() {
+                let mut forged = ciphertext.clone();
+                forged[i] += Fr::from(1u64);
+                assert_eq!(aead.open(&nonce, &associated_data, &forged), None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_seal_open_bytes() {
+        let aead = aead();
+        let nonce = [Fr::from(7u64)];
+        for len in [0, 1, 30, 31, 32, 100] {
+            let plaintext: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let ciphertext = aead.seal_bytes(&nonce, &[], &plaintext);
+            assert_eq!(aead.open_bytes(&nonce, &[], &ciphertext), Some(plaintext));
+        }
+        assert_eq!(aead.open_bytes(&nonce, &[], &[]), None);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::anemoi::AnemoiParameters;
+use crate::duplex::constraints::{DuplexSpongeVar, FieldPermutationGadget};
+use crate::rescue::constraints::inverse_s_box_var;
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::SynthesisError;
+use ark_std::vec::Vec;
+
+/// The gadget for [`AnemoiSponge`](crate::anemoi::AnemoiSponge).
+pub type AnemoiSpongeVar<F> = DuplexSpongeVar<F, AnemoiParameters<F>>;
+
+impl<F: PrimeField> AnemoiParameters<F> {
+    /// The gadget for [`AnemoiParameters::jive`].
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    pub fn jive_var(
+        &self,
+        left: &[FpVar<F>],
+        right: &[FpVar<F>],
+    ) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        let l = self.state_size() / 2;
+        assert_eq!(left.len(), l);
+        assert_eq!(right.len(), l);
+
+        let mut state = [left, right].concat();
+        self.permute_var(&mut state)?;
+        let (x, y) = state.split_at(l);
+        Ok((0..l)
+            .map(|j| &left[j] + &right[j] + &x[j] + &y[j])
+            .collect())
+    }
+
+    fn apply_linear_layer_var(&self, state: &mut [FpVar<F>]) {
+        let l = state.len() / 2;
+        let (x, y) = state.split_at_mut(l);
+        if l > 1 {
+            let mds_mul = |input: &[FpVar<F>]| -> Vec<FpVar<F>> {
+                self.mds
+                    .iter()
+                    .map(|row| {
+                        row.iter()
+                            .zip(input)
+                            .map(|(entry, elem)| elem * *entry)
+                            .fold(FpVar::zero(), |sum, term| sum + term)
+                    })
+                    .collect()
+            };
+            let new_x = mds_mul(x);
+            y.rotate_left(1);
+            let new_y = mds_mul(y);
+            x.clone_from_slice(&new_x);
+            y.clone_from_slice(&new_y);
+        }
+        for (x_j, y_j) in x.iter_mut().zip(y.iter_mut()) {
+            *y_j += &*x_j;
+            *x_j += &*y_j;
+        }
+    }
+
+    fn apply_flystel_var(&self, x: &mut FpVar<F>, y: &mut FpVar<F>) -> Result<(), SynthesisError> {
+        *x -= y.square()? * self.beta + self.gamma;
+        *y -= inverse_s_box_var(x, self.alpha, &self.alpha_inverse)?;
+        *x += y.square()? * self.beta + self.delta;
+        Ok(())
+    }
+}
+
+impl<F: PrimeField> FieldPermutationGadget<F> for AnemoiParameters<F> {
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    fn permute_var(&self, state: &mut [FpVar<F>]) -> Result<(), SynthesisError> {
+        let l = state.len() / 2;
+        for (c, d) in self.c.iter().zip(&self.d) {
+            for j in 0..l {
+                state[j] += c[j];
+                state[l + j] += d[j];
+            }
+            self.apply_linear_layer_var(state);
+            let (x, y) = state.split_at_mut(l);
+            for (x_j, y_j) in x.iter_mut().zip(y.iter_mut()) {
+                self.apply_flystel_var(x_j, y_j)?;
+            }
+        }
+        self.apply_linear_layer_var(state);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::anemoi::constraints::AnemoiSpongeVar;
+    use crate::anemoi::{AnemoiParameters, AnemoiSponge};
+    use crate::constraints::CryptographicSpongeVar;
+    use crate::duplex::constraints::FieldPermutationGadget;
+    use crate::duplex::FieldPermutation;
+    use crate::{CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_ff::UniformRand;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_relations::*;
+    use ark_std::test_rng;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn permutation_test() {
+        let mut rng = test_rng();
+        for l in 1..=4 {
+            let cs = ConstraintSystem::new_ref();
+            let parameters = AnemoiParameters::<Fr>::generate(l, l, 128);
+
+            let mut state: Vec<_> = (0..2 * l).map(|_| Fr::rand(&mut rng)).collect();
+            let mut state_var: Vec<_> = state
+                .iter()
+                .map(|v| FpVar::new_witness(ns!(cs, "state"), || Ok(*v)).unwrap())
+                .collect();
+
+            parameters.permute(&mut state);
+            parameters.permute_var(&mut state_var).unwrap();
+
+            assert_eq!(state_var.value().unwrap(), state);
+            assert!(cs.is_satisfied().unwrap());
+            // two squares and x^(1/5), which costs 3 constraints, per Flystel
+            assert_eq!(cs.num_constraints(), 5 * l * parameters.rounds);
+        }
+    }
+
+    #[test]
+    fn jive_test() {
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::new_ref();
+        let parameters = AnemoiParameters::<Fr>::generate(1, 1, 128);
+
+        let (left, right) = ([Fr::rand(&mut rng)], [Fr::rand(&mut rng)]);
+        let left_var = [FpVar::new_witness(ns!(cs, "left"), || Ok(left[0])).unwrap()];
+        let right_var = [FpVar::new_witness(ns!(cs, "right"), || Ok(right[0])).unwrap()];
+
+        let output = parameters.jive_var(&left_var, &right_var).unwrap();
+        assert_eq!(output.value().unwrap(), parameters.jive(&left, &right));
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn sponge_test() {
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::new_ref();
+        let parameters = AnemoiParameters::<Fr>::generate(1, 1, 128);
+
+        let absorb: Vec<_> = (0..5).map(|_| Fr::rand(&mut rng)).collect();
+        let absorb_var: Vec<_> = absorb
+            .iter()
+            .map(|v| FpVar::new_input(ns!(cs, "absorb"), || Ok(*v)).unwrap())
+            .collect();
+
+        let mut native_sponge = AnemoiSponge::<Fr>::new(&parameters);
+        let mut constraint_sponge = AnemoiSpongeVar::<Fr>::new(cs.clone(), &parameters);
+
+        native_sponge.absorb(&absorb);
+        constraint_sponge.absorb(&absorb_var).unwrap();
+
+        let squeeze1 = native_sponge.squeeze_native_field_elements(3);
+        let squeeze2 = constraint_sponge.squeeze_field_elements(3).unwrap();
+
+        assert_eq!(squeeze2.value().unwrap(), squeeze1);
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
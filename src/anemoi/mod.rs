@@ -0,0 +1,346 @@
+use crate::duplex::{DuplexSponge, FieldPermutation};
+use crate::poseidon::find_poseidon_alpha;
+use crate::poseidon::round_numbers::log2;
+use crate::rescue::inverse_exponent;
+use ark_ff::PrimeField;
+use ark_std::vec::Vec;
+
+/// constraints for Anemoi
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+/// The first 100 decimal digits of pi after the leading 3, from which the round constants
+/// are derived.
+const PI_0: &str = "1415926535897932384626433832795028841971693993751058209749445923078164062862089986280348253421170679";
+
+/// The following 100 decimal digits of pi.
+const PI_1: &str = "8214808651328230664709384460955058223172535940812848111745028410270193852110555964462294895493038196";
+
+/// A duplex sponge based on the [Anemoi][anemoi] permutation.
+///
+/// [anemoi]: https://eprint.iacr.org/2022/840
+pub type AnemoiSponge<F> = DuplexSponge<F, AnemoiParameters<F>>;
+
+/// Parameters of the [Anemoi][anemoi] permutation over a state of `2 * l` elements, split into
+/// the halves `X = state[..l]` and `Y = state[l..]`.
+///
+/// Each of the `rounds` rounds adds the round constants `c` to `X` and `d` to `Y`, applies the
+/// linear layer, and applies the open Flystel to every pair `(x, y) = (X_j, Y_j)`:
+///
+/// ```text
+/// x <- x - beta * y^2 - gamma
+/// y <- y - x^(1/alpha)
+/// x <- x + beta * y^2 + delta
+/// ```
+///
+/// The linear layer multiplies `X` by `mds`, multiplies `Y` rotated to the left by one element
+/// by `mds`, and mixes the two halves with `Y <- Y + X` and `X <- X + Y`. The linear layer is
+/// applied once more after the last round.
+///
+/// [anemoi]: https://eprint.iacr.org/2022/840
+#[derive(Clone, Debug)]
+pub struct AnemoiParameters<F: PrimeField> {
+    /// Number of rounds.
+    pub rounds: usize,
+    /// Exponent of the Flystel, which uses `x^(1/alpha)`.
+    pub alpha: u64,
+    /// The inverse of `alpha` modulo `p - 1`, as little-endian limbs.
+    pub alpha_inverse: Vec<u64>,
+    /// The coefficient `beta` of the Flystel.
+    pub beta: F,
+    /// The constant `gamma` of the Flystel.
+    pub gamma: F,
+    /// The constant `delta` of the Flystel.
+    pub delta: F,
+    /// The `l x l` matrix of the linear layer.
+    pub mds: Vec<Vec<F>>,
+    /// Round constants added to `X`, indexed by `c[round][element_index]`.
+    pub c: Vec<Vec<F>>,
+    /// Round constants added to `Y`, indexed by `d[round][element_index]`.
+    pub d: Vec<Vec<F>>,
+    /// The rate (in terms of number of field elements).
+    pub rate: usize,
+    /// The capacity (in terms of number of field elements).
+    pub capacity: usize,
+}
+
+impl<F: PrimeField> AnemoiParameters<F> {
+    /// Initialize the parameters of the Anemoi permutation.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        alpha: u64,
+        beta: F,
+        gamma: F,
+        delta: F,
+        mds: Vec<Vec<F>>,
+        c: Vec<Vec<F>>,
+        d: Vec<Vec<F>>,
+        rate: usize,
+        capacity: usize,
+    ) -> Self {
+        let state_size = rate + capacity;
+        assert!(state_size.is_multiple_of(2), "the state size must be even");
+        let l = state_size / 2;
+        assert_eq!(mds.len(), l);
+        for row in &mds {
+            assert_eq!(row.len(), l);
+        }
+        assert_eq!(c.len(), d.len());
+        for item in c.iter().chain(&d) {
+            assert_eq!(item.len(), l);
+        }
+
+        Self {
+            rounds: c.len(),
+            alpha,
+            alpha_inverse: inverse_exponent::<F>(alpha),
+            beta,
+            gamma,
+            delta,
+            mds,
+            c,
+            d,
+            rate,
+            capacity,
+        }
+    }
+
+    /// Generate parameters for `F` providing `security_level` bits of security, for a state of
+    /// `2 * l` elements with `l <= 4`, as specified in the paper.
+    ///
+    /// The exponent is the smallest `alpha` for which `x^alpha` is a permutation of `F`, and
+    /// the number of rounds is computed by [`find_anemoi_rounds`]. With `g` the generator of the
+    /// multiplicative group of `F`, the Flystel uses `beta = g`, `gamma = g^(-1)` and
+    /// `delta = 0`, the round constants are `c[i][j] = g * pi_0^(2i) + (pi_0^i + pi_1^j)^alpha`
+    /// and `d[i][j] = g * pi_1^(2j) + (pi_0^i + pi_1^j)^alpha + g^(-1)`, where `pi_0` and `pi_1`
+    /// are the first two blocks of 100 decimal digits of pi, and the matrix is that of the paper
+    /// for `l`.
+    pub fn generate(rate: usize, capacity: usize, security_level: usize) -> Self {
+        let state_size = rate + capacity;
+        let l = state_size / 2;
+        let alpha = find_poseidon_alpha::<F>();
+        let rounds = find_anemoi_rounds(l, alpha, security_level);
+
+        let g = F::multiplicative_generator();
+        let g_inverse = g.inverse().unwrap();
+        let mds = match l {
+            1 => ark_std::vec![ark_std::vec![F::one()]],
+            2 => ark_std::vec![
+                ark_std::vec![F::one(), g],
+                ark_std::vec![g, g.square() + F::one()],
+            ],
+            3 => ark_std::vec![
+                ark_std::vec![g + F::one(), F::one(), g + F::one()],
+                ark_std::vec![F::one(), F::one(), g],
+                ark_std::vec![g, F::one(), F::one()],
+            ],
+            4 => ark_std::vec![
+                ark_std::vec![F::one(), F::one() + g, g, g],
+                ark_std::vec![
+                    g.square(),
+                    F::one() + g + g.square(),
+                    F::one() + g,
+                    F::one() + g.double()
+                ],
+                ark_std::vec![g.square(), g.square(), F::one(), F::one() + g],
+                ark_std::vec![F::one() + g, F::one() + g.double(), g, F::one() + g],
+            ],
+            _ => panic!("parameters can only be generated for states of 2 to 8 elements"),
+        };
+
+        let pi_0 = F::from_str(PI_0).ok().unwrap();
+        let pi_1 = F::from_str(PI_1).ok().unwrap();
+        let mut c = Vec::with_capacity(rounds);
+        let mut d = Vec::with_capacity(rounds);
+        for i in 0..rounds {
+            let pi_0_i = pi_0.pow([i as u64]);
+            let (c_i, d_i) = (0..l)
+                .map(|j| {
+                    let pi_1_j = pi_1.pow([j as u64]);
+                    let sum = (pi_0_i + pi_1_j).pow([alpha]);
+                    (
+                        g * pi_0_i.square() + sum,
+                        g * pi_1_j.square() + sum + g_inverse,
+                    )
+                })
+                .unzip();
+            c.push(c_i);
+            d.push(d_i);
+        }
+
+        Self::new(alpha, g, g_inverse, F::zero(), mds, c, d, rate, capacity)
+    }
+
+    /// The width of the permutation state, `t = rate + capacity`.
+    pub fn state_size(&self) -> usize {
+        self.rate + self.capacity
+    }
+
+    /// Compresses the `l` elements of `left` and the `l` elements of `right` into `l` elements
+    /// with the Jive mode of Anemoi, which sums the halves of the input and output of the
+    /// permutation: `X + Y + P(X, Y)_X + P(X, Y)_Y`.
+    ///
+    /// This is meant as the two-to-one compression function of Merkle trees, where it is
+    /// cheaper than hashing the two children with the sponge.
+    pub fn jive(&self, left: &[F], right: &[F]) -> Vec<F> {
+        let l = self.state_size() / 2;
+        assert_eq!(left.len(), l);
+        assert_eq!(right.len(), l);
+
+        let mut state = [left, right].concat();
+        self.permute(&mut state);
+        let (x, y) = state.split_at(l);
+        (0..l).map(|j| left[j] + right[j] + x[j] + y[j]).collect()
+    }
+
+    fn apply_linear_layer(&self, state: &mut [F]) {
+        let l = state.len() / 2;
+        let (x, y) = state.split_at_mut(l);
+        if l > 1 {
+            let mds_mul = |input: &[F]| -> Vec<F> {
+                self.mds
+                    .iter()
+                    .map(|row| row.iter().zip(input).map(|(a, b)| *a * b).sum())
+                    .collect()
+            };
+            let new_x = mds_mul(x);
+            y.rotate_left(1);
+            let new_y = mds_mul(y);
+            x.copy_from_slice(&new_x);
+            y.copy_from_slice(&new_y);
+        }
+        for (x_j, y_j) in x.iter_mut().zip(y.iter_mut()) {
+            *y_j += *x_j;
+            *x_j += *y_j;
+        }
+    }
+
+    fn apply_flystel(&self, x: &mut F, y: &mut F) {
+        *x -= self.beta * y.square() + self.gamma;
+        *y -= x.pow(&self.alpha_inverse);
+        *x += self.beta * y.square() + self.delta;
+    }
+}
+
+impl<F: PrimeField> FieldPermutation<F> for AnemoiParameters<F> {
+    fn rate(&self) -> usize {
+        self.rate
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn permute(&self, state: &mut [F]) {
+        let l = state.len() / 2;
+        for (c, d) in self.c.iter().zip(&self.d) {
+            for j in 0..l {
+                state[j] += c[j];
+                state[l + j] += d[j];
+            }
+            self.apply_linear_layer(state);
+            let (x, y) = state.split_at_mut(l);
+            for (x_j, y_j) in x.iter_mut().zip(y.iter_mut()) {
+                self.apply_flystel(x_j, y_j);
+            }
+        }
+        self.apply_linear_layer(state);
+    }
+}
+
+/// Computes the number of rounds of Anemoi for a state of `2 * l` elements, following
+/// Section 6 of the paper.
+///
+/// This is `max(8, r + 2 + l + 1)`, where `r` is the smallest number of rounds for which the
+/// complexity of Gröbner basis attacks, `binomial(4 * l * r + kappa, 2 * l * r)^2`, exceeds
+/// `2^security_level`. `kappa` depends on `alpha`, which must be 3, 5, 7, 9 or 11.
+pub fn find_anemoi_rounds(l: usize, alpha: u64, security_level: usize) -> usize {
+    let kappa = match alpha {
+        3 => 1,
+        5 => 2,
+        7 => 4,
+        9 => 7,
+        11 => 9,
+        _ => panic!("the number of rounds is only known for alpha = 3, 5, 7, 9 or 11"),
+    };
+    // log2 of the binomial coefficient `(n choose k)`
+    let log2_binomial = |n: usize, k: usize| -> f64 {
+        (1..=k)
+            .map(|i| log2((n - k + i) as f64) - log2(i as f64))
+            .sum()
+    };
+
+    let mut r = 1;
+    while 2.0 * log2_binomial(4 * l * r + kappa, 2 * l * r) < security_level as f64 {
+        r += 1;
+    }
+
+    // add a security margin of l + 1 rounds
+    core::cmp::max(8, r + 2 + l + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::anemoi::{find_anemoi_rounds, AnemoiParameters, AnemoiSponge};
+    use crate::{CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_ff::{Field, UniformRand};
+    use ark_std::test_rng;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn test_rounds() {
+        // 128 bits of security, for alpha = 3 and alpha = 11
+        assert_eq!(find_anemoi_rounds(1, 3, 128), 21);
+        assert_eq!(find_anemoi_rounds(2, 3, 128), 14);
+        assert_eq!(find_anemoi_rounds(3, 3, 128), 12);
+        assert_eq!(find_anemoi_rounds(4, 3, 128), 12);
+        assert_eq!(find_anemoi_rounds(1, 11, 128), 19);
+        assert_eq!(find_anemoi_rounds(2, 11, 128), 13);
+        assert_eq!(find_anemoi_rounds(4, 11, 128), 11);
+    }
+
+    #[test]
+    fn test_flystel() {
+        let mut rng = test_rng();
+        let parameters = AnemoiParameters::<Fr>::generate(1, 1, 128);
+        let (x, y) = (Fr::rand(&mut rng), Fr::rand(&mut rng));
+        let (mut u, mut v) = (x, y);
+        parameters.apply_flystel(&mut u, &mut v);
+
+        // Invert the open Flystel.
+        u -= parameters.beta * v.square() + parameters.delta;
+        v += u.pow(&parameters.alpha_inverse);
+        u += parameters.beta * v.square() + parameters.gamma;
+        assert_eq!((u, v), (x, y));
+    }
+
+    #[test]
+    fn test_jive() {
+        let mut rng = test_rng();
+        for l in 1..=4 {
+            let parameters = AnemoiParameters::<Fr>::generate(l, l, 128);
+            let left: Vec<_> = (0..l).map(|_| Fr::rand(&mut rng)).collect();
+            let right: Vec<_> = (0..l).map(|_| Fr::rand(&mut rng)).collect();
+
+            let output = parameters.jive(&left, &right);
+            assert_eq!(output.len(), l);
+            assert_ne!(output, parameters.jive(&right, &left));
+        }
+    }
+
+    #[test]
+    fn test_sponge() {
+        let mut rng = test_rng();
+        let parameters = AnemoiParameters::<Fr>::generate(1, 1, 128);
+        let input: Vec<_> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+
+        let mut sponge = AnemoiSponge::new(&parameters);
+        sponge.absorb(&input);
+        let output = sponge.squeeze_native_field_elements(3);
+
+        let mut other = AnemoiSponge::new(&parameters);
+        other.absorb(&input[..2].to_vec());
+        assert_ne!(other.squeeze_native_field_elements(3), output);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::{
+    squeeze_field_elements_with_sizes_from_bytes, Absorb, CryptographicSponge, FieldElementSize,
+};
+use ark_ff::PrimeField;
+use ark_std::vec::Vec;
+use blake2::digest::{FixedOutput, Update};
+use blake2::Blake2b;
+
+/// A byte-oriented sponge based on BLAKE2b, for Fiat-Shamir transforms that are never verified
+/// in a circuit, where it is much faster than algebraic sponges.
+///
+/// Inputs are absorbed through their byte encoding ([`Absorb::to_sponge_bytes`]). The output
+/// stream is the concatenation of the blocks `BLAKE2b(inputs || i)` for `i = 0, 1, ...`, where
+/// `i` is a little-endian `u64`, and consecutive squeezes continue reading the same stream.
+/// Absorbing after squeezing restarts BLAKE2b from the next block of the stream, which binds the
+/// new inputs to all previous operations.
+///
+/// Field elements are squeezed with a modular reduction of 128 bits more than the size of the
+/// modulus, so they are statistically close to uniform.
+#[derive(Clone)]
+pub struct Blake2Sponge {
+    hasher: Blake2b,
+    /// The index of the next block and the unread bytes of the current block, if squeezing
+    squeeze_state: Option<(u64, Vec<u8>)>,
+}
+
+impl Blake2Sponge {
+    fn next_block(&self, index: u64) -> Vec<u8> {
+        let mut hasher = self.hasher.clone();
+        hasher.update(index.to_le_bytes());
+        hasher.finalize_fixed().to_vec()
+    }
+
+    fn read_bytes(&mut self, num_bytes: usize) -> Vec<u8> {
+        let (mut index, mut buffer) = self.squeeze_state.take().unwrap_or((0, Vec::new()));
+        while buffer.len() < num_bytes {
+            buffer.extend(self.next_block(index));
+            index += 1;
+        }
+        let remaining = buffer.split_off(num_bytes);
+        self.squeeze_state = Some((index, remaining));
+        buffer
+    }
+}
+
+/// Resets the hash state, which may depend on secret inputs, and zeroes the unread output.
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for Blake2Sponge {
+    fn zeroize(&mut self) {
+        blake2::digest::Reset::reset(&mut self.hasher);
+        if let Some((index, buffer)) = &mut self.squeeze_state {
+            index.zeroize();
+            buffer.zeroize();
+        }
+        self.squeeze_state = None;
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Blake2Sponge {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::ZeroizeOnDrop for Blake2Sponge {}
+
+impl CryptographicSponge for Blake2Sponge {
+    type Parameters = ();
+
+    fn new(_params: &Self::Parameters) -> Self {
+        Self {
+            hasher: Blake2b::default(),
+            squeeze_state: None,
+        }
+    }
+
+    /// BLAKE2b compresses blocks of 128 bytes into a chaining value of 64 bytes.
+    fn rate(&self) -> usize {
+        128
+    }
+
+    fn capacity(&self) -> usize {
+        64
+    }
+
+    fn security_level(&self) -> usize {
+        256
+    }
+
+    fn absorb(&mut self, input: &impl Absorb) {
+        if let Some((index, _)) = self.squeeze_state.take() {
+            let chaining_value = self.next_block(index);
+            self.hasher = Blake2b::default();
+            self.hasher.update(chaining_value);
+        }
+        self.hasher.update(input.to_sponge_bytes_as_vec());
+    }
+
+    fn squeeze_bytes(&mut self, num_bytes: usize) -> Vec<u8> {
+        self.read_bytes(num_bytes)
+    }
+
+    fn squeeze_bits(&mut self, num_bits: usize) -> Vec<bool> {
+        let bytes = self.squeeze_bytes(num_bits.div_ceil(8));
+        let mut bits: Vec<bool> = bytes
+            .iter()
+            .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1))
+            .collect();
+        bits.truncate(num_bits);
+        bits
+    }
+
+    fn squeeze_field_elements_with_sizes<F: PrimeField>(
+        &mut self,
+        sizes: &[FieldElementSize],
+    ) -> Vec<F> {
+        squeeze_field_elements_with_sizes_from_bytes(self, sizes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::blake2b::Blake2Sponge;
+    use crate::CryptographicSponge;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+    use blake2::digest::{FixedOutput, Update};
+    use blake2::Blake2b;
+
+    #[test]
+    fn test_output_stream() {
+        let mut sponge = Blake2Sponge::new(&());
+        sponge.absorb(&[1u8, 2, 3].as_ref());
+        let mut squeezed = sponge.squeeze_bytes(50);
+        squeezed.extend(sponge.squeeze_bytes(100));
+
+        let mut expected = Vec::new();
+        for i in 0u64..3 {
+            let mut hasher = Blake2b::default();
+            hasher.update([1u8, 2, 3]);
+            hasher.update(i.to_le_bytes());
+            expected.extend(hasher.finalize_fixed());
+        }
+        assert_eq!(squeezed, expected[..150].to_vec());
+    }
+
+    #[test]
+    fn test_absorb_after_squeeze() {
+        let mut sponge = Blake2Sponge::new(&());
+        sponge.absorb(&1u8);
+        let mut other = sponge.clone();
+
+        sponge.squeeze_bytes(1);
+        sponge.absorb(&2u8);
+        other.absorb(&2u8);
+        assert_ne!(sponge.squeeze_bytes(32), other.squeeze_bytes(32));
+    }
+
+    #[test]
+    fn test_squeeze_field_elements() {
+        let mut sponge = Blake2Sponge::new(&());
+        sponge.absorb(&42u64);
+        let elements: Vec<Fr> = sponge.squeeze_field_elements(10);
+        assert_eq!(elements.len(), 10);
+        assert!(elements[1..].iter().all(|e| e != &elements[0]));
+    }
+}
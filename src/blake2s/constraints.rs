@@ -0,0 +1,178 @@
+use crate::blake2s::Blake2sSponge;
+use crate::constraints::{AbsorbGadget, CryptographicSpongeVar, SpongeWithGadget};
+use ark_crypto_primitives::prf::blake2s::constraints::evaluate_blake2s;
+use ark_ff::{FpParameters, PrimeField};
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use ark_std::vec::Vec;
+
+/// The gadget for [`Blake2sSponge`], over the BLAKE2s gadget of `ark-crypto-primitives`.
+///
+/// The absorbed bytes are kept until the sponge is squeezed, and every block of the output
+/// stream hashes all of them again, so a squeeze of `n` bytes costs `ceil(n / 32)` evaluations of
+/// BLAKE2s over the absorbed input. Native field elements are reduced from the squeezed bits with
+/// a linear combination, as [`Blake2sSponge`] reduces them.
+#[derive(Clone)]
+pub struct Blake2sSpongeVar<F: PrimeField> {
+    cs: ConstraintSystemRef<F>,
+    /// The bytes absorbed since BLAKE2s was last restarted
+    absorbed: Vec<UInt8<F>>,
+    /// The index of the next block and the unread bytes of the current block, if squeezing
+    squeeze_state: Option<(u64, Vec<UInt8<F>>)>,
+}
+
+impl<F: PrimeField> SpongeWithGadget<F> for Blake2sSponge {
+    type Var = Blake2sSpongeVar<F>;
+}
+
+impl<F: PrimeField> Blake2sSpongeVar<F> {
+    fn next_block(&self, index: u64) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        let mut input = self.absorbed.clone();
+        input.extend(UInt8::constant_vec(&index.to_le_bytes()));
+        let bits = input.to_bits_le()?;
+        let mut block = Vec::new();
+        for word in evaluate_blake2s(&bits)? {
+            block.extend(word.to_bytes()?);
+        }
+        Ok(block)
+    }
+
+    fn read_bytes(&mut self, num_bytes: usize) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        let (mut index, mut buffer) = self.squeeze_state.take().unwrap_or((0, Vec::new()));
+        while buffer.len() < num_bytes {
+            buffer.extend(self.next_block(index)?);
+            index += 1;
+        }
+        let remaining = buffer.split_off(num_bytes);
+        self.squeeze_state = Some((index, remaining));
+        Ok(buffer)
+    }
+}
+
+impl<F: PrimeField> CryptographicSpongeVar<F, Blake2sSponge> for Blake2sSpongeVar<F> {
+    type Parameters = ();
+
+    fn new(cs: ConstraintSystemRef<F>, _params: &Self::Parameters) -> Self {
+        Self {
+            cs,
+            absorbed: Vec::new(),
+            squeeze_state: None,
+        }
+    }
+
+    fn cs(&self) -> ConstraintSystemRef<F> {
+        self.cs.clone()
+    }
+
+    fn rate(&self) -> usize {
+        64
+    }
+
+    fn capacity(&self) -> usize {
+        32
+    }
+
+    fn security_level(&self) -> usize {
+        128
+    }
+
+    #[tracing::instrument(target = "r1cs", skip(self, input))]
+    fn absorb(&mut self, input: &impl AbsorbGadget<F>) -> Result<(), SynthesisError> {
+        if let Some((index, _)) = self.squeeze_state.take() {
+            self.absorbed = self.next_block(index)?;
+        }
+        self.absorbed.extend(input.to_sponge_bytes()?);
+        Ok(())
+    }
+
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    fn squeeze_bytes(&mut self, num_bytes: usize) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        self.read_bytes(num_bytes)
+    }
+
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    fn squeeze_bits(&mut self, num_bits: usize) -> Result<Vec<Boolean<F>>, SynthesisError> {
+        let bytes = self.squeeze_bytes(num_bits.div_ceil(8))?;
+        let mut bits = bytes.to_bits_le()?;
+        bits.truncate(num_bits);
+        Ok(bits)
+    }
+
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    fn squeeze_field_elements(
+        &mut self,
+        num_elements: usize,
+    ) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        let num_bytes = (F::Params::MODULUS_BITS as usize + 128).div_ceil(8);
+        (0..num_elements)
+            .map(|_| {
+                let bytes = self.squeeze_bytes(num_bytes)?;
+                let mut elem = FpVar::zero();
+                let mut power = F::one();
+                for bit in bytes.to_bits_le()? {
+                    elem += FpVar::from(bit) * power;
+                    power.double_in_place();
+                }
+                Ok(elem)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::blake2s::constraints::Blake2sSpongeVar;
+    use crate::blake2s::Blake2sSponge;
+    use crate::constraints::CryptographicSpongeVar;
+    use crate::{CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_ff::UniformRand;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::test_rng;
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn consistency_with_native() {
+        let mut rng = test_rng();
+        let elem = Fr::rand(&mut rng);
+        let mut sponge = Blake2sSponge::new(&());
+        sponge.absorb(&elem);
+        sponge.absorb(&[1u8, 2, 3].as_ref());
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let mut sponge_var = Blake2sSpongeVar::new(cs.clone(), &());
+        let elem_var = FpVar::new_witness(cs.clone(), || Ok(elem)).unwrap();
+        let bytes_var = UInt8::new_witness_vec(cs.clone(), &[1, 2, 3]).unwrap();
+        sponge_var.absorb(&elem_var).unwrap();
+        sponge_var.absorb(&bytes_var).unwrap();
+
+        assert_eq!(
+            sponge_var.squeeze_bytes(40).unwrap().value().unwrap(),
+            sponge.squeeze_bytes(40)
+        );
+        assert_eq!(
+            sponge_var.squeeze_bits(13).unwrap().value().unwrap(),
+            sponge.squeeze_bits(13)
+        );
+        assert_eq!(
+            sponge_var
+                .squeeze_field_elements(2)
+                .unwrap()
+                .value()
+                .unwrap(),
+            sponge.squeeze_native_field_elements(2)
+        );
+
+        sponge.absorb(&7u64);
+        sponge_var
+            .absorb(&UInt8::constant_vec(&7u64.to_le_bytes()))
+            .unwrap();
+        assert_eq!(
+            sponge_var.squeeze_bytes(32).unwrap().value().unwrap(),
+            sponge.squeeze_bytes(32)
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
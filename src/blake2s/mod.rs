@@ -0,0 +1,178 @@
+use crate::{
+    squeeze_field_elements_with_sizes_from_bytes, Absorb, CryptographicSponge,
+    FieldBasedCryptographicSponge, FieldElementSize,
+};
+use ark_ff::PrimeField;
+use ark_std::vec::Vec;
+use blake2::digest::{FixedOutput, Update};
+use blake2::Blake2s;
+
+/// constraints for the BLAKE2s sponge
+#[cfg(all(feature = "r1cs", feature = "crypto-primitives"))]
+pub mod constraints;
+
+/// A byte-oriented sponge based on BLAKE2s, for verifying in circuits the Fiat-Shamir
+/// transforms of protocols that use BLAKE2s.
+///
+/// The construction is that of [`Blake2Sponge`](crate::blake2b::Blake2Sponge) with BLAKE2s:
+/// inputs are absorbed through their byte encoding ([`Absorb::to_sponge_bytes`]), the output
+/// stream is the concatenation of the blocks `BLAKE2s(inputs || i)` for `i = 0, 1, ...`, where
+/// `i` is a little-endian `u64`, and absorbing after squeezing restarts BLAKE2s from the next
+/// block of the stream.
+///
+/// Field elements are squeezed with a modular reduction of 128 bits more than the size of the
+/// modulus, so they are statistically close to uniform.
+#[derive(Clone)]
+pub struct Blake2sSponge {
+    hasher: Blake2s,
+    /// The index of the next block and the unread bytes of the current block, if squeezing
+    squeeze_state: Option<(u64, Vec<u8>)>,
+}
+
+impl Blake2sSponge {
+    fn next_block(&self, index: u64) -> Vec<u8> {
+        let mut hasher = self.hasher.clone();
+        hasher.update(index.to_le_bytes());
+        hasher.finalize_fixed().to_vec()
+    }
+
+    fn read_bytes(&mut self, num_bytes: usize) -> Vec<u8> {
+        let (mut index, mut buffer) = self.squeeze_state.take().unwrap_or((0, Vec::new()));
+        while buffer.len() < num_bytes {
+            buffer.extend(self.next_block(index));
+            index += 1;
+        }
+        let remaining = buffer.split_off(num_bytes);
+        self.squeeze_state = Some((index, remaining));
+        buffer
+    }
+}
+
+/// Resets the hash state, which may depend on secret inputs, and zeroes the unread output.
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for Blake2sSponge {
+    fn zeroize(&mut self) {
+        blake2::digest::Reset::reset(&mut self.hasher);
+        if let Some((index, buffer)) = &mut self.squeeze_state {
+            index.zeroize();
+            buffer.zeroize();
+        }
+        self.squeeze_state = None;
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Blake2sSponge {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::ZeroizeOnDrop for Blake2sSponge {}
+
+impl CryptographicSponge for Blake2sSponge {
+    type Parameters = ();
+
+    fn new(_params: &Self::Parameters) -> Self {
+        Self {
+            hasher: Blake2s::default(),
+            squeeze_state: None,
+        }
+    }
+
+    /// BLAKE2s compresses blocks of 64 bytes into a chaining value of 32 bytes.
+    fn rate(&self) -> usize {
+        64
+    }
+
+    fn capacity(&self) -> usize {
+        32
+    }
+
+    fn security_level(&self) -> usize {
+        128
+    }
+
+    fn absorb(&mut self, input: &impl Absorb) {
+        if let Some((index, _)) = self.squeeze_state.take() {
+            let chaining_value = self.next_block(index);
+            self.hasher = Blake2s::default();
+            self.hasher.update(chaining_value);
+        }
+        self.hasher.update(input.to_sponge_bytes_as_vec());
+    }
+
+    fn squeeze_bytes(&mut self, num_bytes: usize) -> Vec<u8> {
+        self.read_bytes(num_bytes)
+    }
+
+    fn squeeze_bits(&mut self, num_bits: usize) -> Vec<bool> {
+        let bytes = self.squeeze_bytes(num_bits.div_ceil(8));
+        let mut bits: Vec<bool> = bytes
+            .iter()
+            .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1))
+            .collect();
+        bits.truncate(num_bits);
+        bits
+    }
+
+    fn squeeze_field_elements_with_sizes<F: PrimeField>(
+        &mut self,
+        sizes: &[FieldElementSize],
+    ) -> Vec<F> {
+        squeeze_field_elements_with_sizes_from_bytes(self, sizes)
+    }
+}
+
+/// The native field elements are squeezed as [`CryptographicSponge::squeeze_field_elements`]
+/// squeezes them, whatever the field, which is how the gadget squeezes them over its constraint
+/// field.
+impl<CF: PrimeField> FieldBasedCryptographicSponge<CF> for Blake2sSponge {
+    fn squeeze_native_field_elements(&mut self, num_elements: usize) -> Vec<CF> {
+        self.squeeze_field_elements(num_elements)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::blake2s::Blake2sSponge;
+    use crate::CryptographicSponge;
+    use ark_std::vec::Vec;
+    use blake2::digest::{FixedOutput, Update};
+    use blake2::Blake2s;
+
+    #[test]
+    fn test_output_stream() {
+        let mut sponge = Blake2sSponge::new(&());
+        sponge.absorb(&[1u8, 2, 3].as_ref());
+        let mut squeezed = sponge.squeeze_bytes(20);
+        squeezed.extend(sponge.squeeze_bytes(70));
+
+        let mut expected = Vec::new();
+        for i in 0u64..3 {
+            let mut hasher = Blake2s::default();
+            hasher.update([1u8, 2, 3]);
+            hasher.update(i.to_le_bytes());
+            expected.extend(hasher.finalize_fixed());
+        }
+        assert_eq!(squeezed, expected[..90].to_vec());
+    }
+
+    #[test]
+    fn test_absorb_after_squeeze() {
+        let mut sponge = Blake2sSponge::new(&());
+        sponge.absorb(&1u8);
+        sponge.squeeze_bytes(40);
+        sponge.absorb(&2u8);
+
+        let mut chaining_value = Blake2s::default();
+        chaining_value.update([1u8]);
+        chaining_value.update(2u64.to_le_bytes());
+        let mut hasher = Blake2s::default();
+        hasher.update(chaining_value.finalize_fixed());
+        hasher.update([2u8]);
+        hasher.update(0u64.to_le_bytes());
+        assert_eq!(sponge.squeeze_bytes(32), hasher.finalize_fixed().to_vec());
+    }
+}
@@ -0,0 +1,132 @@
+use crate::commitment::COMMITMENT_DOMAIN;
+use crate::constraints::{AbsorbGadget, CryptographicSpongeVar, SpongeWithGadget};
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use ark_std::marker::PhantomData;
+
+/// The gadget for [`SpongeCommitment`](crate::commitment::SpongeCommitment), over the gadget
+/// of the sponge `S`.
+pub struct SpongeCommitmentVar<F: PrimeField, S: SpongeWithGadget<F>> {
+    _field: PhantomData<F>,
+    _sponge: PhantomData<S>,
+}
+
+impl<F: PrimeField, S: SpongeWithGadget<F>> SpongeCommitmentVar<F, S> {
+    /// Commits to `message` with `randomness`.
+    #[tracing::instrument(target = "r1cs", skip(cs, params, message, randomness))]
+    pub fn commit(
+        cs: ConstraintSystemRef<F>,
+        params: &<S::Var as CryptographicSpongeVar<F, S>>::Parameters,
+        message: &impl AbsorbGadget<F>,
+        randomness: &FpVar<F>,
+    ) -> Result<FpVar<F>, SynthesisError> {
+        let mut sponge = S::Var::new(cs, params);
+        sponge.absorb(&FpVar::Constant(F::from_le_bytes_mod_order(
+            COMMITMENT_DOMAIN,
+        )))?;
+        sponge.absorb(randomness)?;
+        let message = message.to_sponge_field_elements()?;
+        sponge.absorb(&FpVar::Constant(F::from(message.len() as u64)))?;
+        sponge.absorb(&message)?;
+        Ok(sponge.squeeze_field_elements(1)?.remove(0))
+    }
+
+    /// Returns whether `commitment` opens to `message` with `randomness`.
+    #[tracing::instrument(target = "r1cs", skip(cs, params, commitment, message, randomness))]
+    pub fn verify(
+        cs: ConstraintSystemRef<F>,
+        params: &<S::Var as CryptographicSpongeVar<F, S>>::Parameters,
+        commitment: &FpVar<F>,
+        message: &impl AbsorbGadget<F>,
+        randomness: &FpVar<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        Self::commit(cs, params, message, randomness)?.is_eq(commitment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commitment::constraints::SpongeCommitmentVar;
+    use crate::commitment::SpongeCommitment;
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::PoseidonSponge;
+    use ark_ff::{UniformRand, Zero};
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::test_rng;
+    use ark_std::vec;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn test_commitment_var() {
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::new_ref();
+        let params = poseidon_parameters_for_test();
+        let message = [Fr::from(1u64), Fr::from(2u64)];
+        let randomness = Fr::rand(&mut rng);
+        let commitment =
+            SpongeCommitment::<Fr, PoseidonSponge<Fr>>::commit(&params, &message, &randomness);
+
+        let message_var: Vec<_> = message
+            .iter()
+            .map(|m| FpVar::new_witness(cs.clone(), || Ok(*m)).unwrap())
+            .collect();
+        let randomness_var = FpVar::new_witness(cs.clone(), || Ok(randomness)).unwrap();
+        let commitment_var = FpVar::new_input(cs.clone(), || Ok(commitment)).unwrap();
+
+        type CommitmentVar = SpongeCommitmentVar<Fr, PoseidonSponge<Fr>>;
+        let is_valid = CommitmentVar::verify(
+            cs.clone(),
+            &params,
+            &commitment_var,
+            &message_var,
+            &randomness_var,
+        )
+        .unwrap();
+        assert!(is_valid.value().unwrap());
+
+        let is_valid = CommitmentVar::verify(
+            cs.clone(),
+            &params,
+            &commitment_var,
+            &message_var[..1].to_vec(),
+            &randomness_var,
+        )
+        .unwrap();
+        assert!(!is_valid.value().unwrap());
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_commitment_var_trailing_zero() {
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::new_ref();
+        let params = poseidon_parameters_for_test();
+        let a = Fr::rand(&mut rng);
+        let randomness = Fr::rand(&mut rng);
+        let commitment =
+            SpongeCommitment::<Fr, PoseidonSponge<Fr>>::commit(&params, &[a], &randomness);
+
+        let message_var = vec![
+            FpVar::new_witness(cs.clone(), || Ok(a)).unwrap(),
+            FpVar::Constant(Fr::zero()),
+        ];
+        let randomness_var = FpVar::new_witness(cs.clone(), || Ok(randomness)).unwrap();
+        let commitment_var = FpVar::new_input(cs.clone(), || Ok(commitment)).unwrap();
+
+        let is_valid = SpongeCommitmentVar::<Fr, PoseidonSponge<Fr>>::verify(
+            cs.clone(),
+            &params,
+            &commitment_var,
+            &message_var,
+            &randomness_var,
+        )
+        .unwrap();
+        assert!(!is_valid.value().unwrap());
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{Absorb, FieldBasedCryptographicSponge};
+use ark_ff::PrimeField;
+use ark_std::marker::PhantomData;
+
+/// constraints for the sponge commitment
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+/// The domain separator absorbed before the randomness of a [`SpongeCommitment`].
+pub const COMMITMENT_DOMAIN: &[u8] = b"ark-sponge/commitment";
+
+/// A hiding commitment to any absorbable message, computed with a sponge over `F`.
+///
+/// The commitment is the first native field element squeezed after absorbing
+/// [`COMMITMENT_DOMAIN`] (as a field element), the randomness, the number of field elements of the
+/// message, and the message. The length is absorbed since a sponge without padding absorbs `x` and
+/// `(x, 0)` to the same state. It is binding as long as the sponge is collision-resistant, and hiding as long as the randomness is sampled
+/// uniformly from `F` and never reused. It is opened by revealing the message and the randomness.
+pub struct SpongeCommitment<F: PrimeField + Absorb, S: FieldBasedCryptographicSponge<F>> {
+    _field: PhantomData<F>,
+    _sponge: PhantomData<S>,
+}
+
+impl<F: PrimeField + Absorb, S: FieldBasedCryptographicSponge<F>> SpongeCommitment<F, S> {
+    /// Commits to `message` with `randomness`.
+    pub fn commit(params: &S::Parameters, message: &impl Absorb, randomness: &F) -> F {
+        let mut sponge = S::new(params);
+        sponge.absorb(&F::from_le_bytes_mod_order(COMMITMENT_DOMAIN));
+        sponge.absorb(randomness);
+        let message = message.to_sponge_field_elements_as_vec::<F>();
+        sponge.absorb(&F::from(message.len() as u64));
+        sponge.absorb(&message);
+        sponge.squeeze_native_field_elements(1)[0]
+    }
+
+    /// Checks that `commitment` opens to `message` with `randomness`.
+    pub fn verify(
+        params: &S::Parameters,
+        commitment: &F,
+        message: &impl Absorb,
+        randomness: &F,
+    ) -> bool {
+        Self::commit(params, message, randomness) == *commitment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commitment::SpongeCommitment;
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::PoseidonSponge;
+    use ark_ff::{UniformRand, Zero};
+    use ark_std::test_rng;
+    use ark_test_curves::bls12_381::Fr;
+
+    type Commitment = SpongeCommitment<Fr, PoseidonSponge<Fr>>;
+
+    #[test]
+    fn test_commit_verify() {
+        let mut rng = test_rng();
+        let params = poseidon_parameters_for_test();
+        let message = [Fr::from(1u64), Fr::from(2u64)];
+        let randomness = Fr::rand(&mut rng);
+
+        let commitment = Commitment::commit(&params, &message, &randomness);
+        assert!(Commitment::verify(
+            &params,
+            &commitment,
+            &message,
+            &randomness
+        ));
+        assert!(!Commitment::verify(
+            &params,
+            &commitment,
+            &[Fr::from(1u64), Fr::from(3u64)],
+            &randomness
+        ));
+        assert!(!Commitment::verify(
+            &params,
+            &commitment,
+            &message,
+            &Fr::rand(&mut rng)
+        ));
+    }
+
+    #[test]
+    fn test_commit_trailing_zero() {
+        let mut rng = test_rng();
+        let params = poseidon_parameters_for_test();
+        let a = Fr::rand(&mut rng);
+        let randomness = Fr::rand(&mut rng);
+
+        assert_ne!(
+            Commitment::commit(&params, &[a], &randomness),
+            Commitment::commit(&params, &[a, Fr::zero()], &randomness)
+        );
+    }
+}
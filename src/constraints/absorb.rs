@@ -1,17 +1,32 @@
+use crate::Absorb;
+#[cfg(feature = "groth16")]
+use ark_ec::PairingEngine;
 use ark_ec::{ModelParameters, SWModelParameters, TEModelParameters};
 use ark_ff::{Field, PrimeField};
+#[cfg(feature = "groth16")]
+use ark_groth16::constraints::{ProofVar, VerifyingKeyVar};
 use ark_r1cs_std::bits::boolean::Boolean;
 use ark_r1cs_std::bits::uint8::UInt8;
+use ark_r1cs_std::fields::cubic_extension::{CubicExtVar, CubicExtVarParams};
 use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::fields::quadratic_extension::{QuadExtVar, QuadExtVarParams};
 use ark_r1cs_std::fields::{FieldOpsBounds, FieldVar};
 use ark_r1cs_std::groups::curves::short_weierstrass::{
     AffineVar as SWAffineVar, ProjectiveVar as SWProjectiveVar,
 };
 use ark_r1cs_std::groups::curves::twisted_edwards::AffineVar as TEAffineVar;
+#[cfg(feature = "groth16")]
+use ark_r1cs_std::pairing::PairingVar;
 use ark_r1cs_std::{ToBytesGadget, ToConstraintFieldGadget};
 use ark_relations::r1cs::SynthesisError;
 use ark_std::vec;
 use ark_std::vec::Vec;
+
+/// Derives [`AbsorbGadget`] for a struct of gadgets by absorbing its fields in declaration order.
+/// Fields marked with `#[sponge(skip)]` are not absorbed.
+#[cfg(feature = "derive")]
+pub use ark_sponge_derive::AbsorbGadget;
+
 /// An interface for objects that can be absorbed by a `CryptographicSpongeVar` whose constraint field
 /// is `CF`.
 pub trait AbsorbGadget<F: PrimeField> {
@@ -48,6 +63,48 @@ pub trait AbsorbGadget<F: PrimeField> {
     }
 }
 
+/// The gadget counterpart of [`AbsorbWithLength`](crate::AbsorbWithLength), for gadgets of
+/// variable length such as lists. The length is fixed when generating the circuit, so it is
+/// encoded as constants.
+pub trait AbsorbGadgetWithLength<F: PrimeField>: AbsorbGadget<F> {
+    /// The length of the `self` being absorbed.
+    fn absorb_length(&self) -> usize;
+
+    /// Converts the object into a list of bytes along with its length information, as
+    /// [`AbsorbWithLength::to_sponge_bytes_with_length`](crate::AbsorbWithLength::to_sponge_bytes_with_length).
+    fn to_sponge_bytes_with_length(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        let mut output = ConstantLength(self.absorb_length()).to_sponge_bytes()?;
+        output.append(&mut self.to_sponge_bytes()?);
+        Ok(output)
+    }
+
+    /// Converts the object into field elements along with its length information, as
+    /// [`AbsorbWithLength::to_sponge_field_elements_with_length`](crate::AbsorbWithLength::to_sponge_field_elements_with_length).
+    fn to_sponge_field_elements_with_length(&self) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        let mut output = ConstantLength(self.absorb_length()).to_sponge_field_elements()?;
+        output.append(&mut self.to_sponge_field_elements()?);
+        Ok(output)
+    }
+}
+
+/// A length fixed when generating the circuit, absorbed as constants in the encoding of `usize`.
+pub(crate) struct ConstantLength(pub(crate) usize);
+
+impl<F: PrimeField> AbsorbGadget<F> for ConstantLength {
+    fn to_sponge_bytes(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        Ok(UInt8::constant_vec(&Absorb::to_sponge_bytes_as_vec(
+            &self.0,
+        )))
+    }
+
+    fn to_sponge_field_elements(&self) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        Ok(Absorb::to_sponge_field_elements_as_vec::<F>(&self.0)
+            .into_iter()
+            .map(FpVar::Constant)
+            .collect())
+    }
+}
+
 impl<F: PrimeField> AbsorbGadget<F> for UInt8<F> {
     fn to_sponge_bytes(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
         Ok(vec![self.clone()])
@@ -90,6 +147,51 @@ impl<F: PrimeField> AbsorbGadget<F> for FpVar<F> {
     }
 }
 
+/// Absorbs `c0` followed by `c1`, matching the native encoding of `QuadExtField`.
+impl<BF, P> AbsorbGadget<P::BasePrimeField> for QuadExtVar<BF, P>
+where
+    BF: FieldVar<P::BaseField, P::BasePrimeField> + AbsorbGadget<P::BasePrimeField>,
+    for<'a> &'a BF: FieldOpsBounds<'a, P::BaseField, BF>,
+    P: QuadExtVarParams<BF>,
+{
+    fn to_sponge_bytes(&self) -> Result<Vec<UInt8<P::BasePrimeField>>, SynthesisError> {
+        let mut output = self.c0.to_sponge_bytes()?;
+        output.append(&mut self.c1.to_sponge_bytes()?);
+        Ok(output)
+    }
+
+    fn to_sponge_field_elements(&self) -> Result<Vec<FpVar<P::BasePrimeField>>, SynthesisError> {
+        let mut output = self.c0.to_sponge_field_elements()?;
+        output.append(&mut self.c1.to_sponge_field_elements()?);
+        Ok(output)
+    }
+}
+
+/// Absorbs `c0`, `c1`, `c2` in order, matching the native encoding of `CubicExtField`.
+impl<BF, P> AbsorbGadget<P::BasePrimeField> for CubicExtVar<BF, P>
+where
+    BF: FieldVar<P::BaseField, P::BasePrimeField> + AbsorbGadget<P::BasePrimeField>,
+    for<'a> &'a BF: FieldOpsBounds<'a, P::BaseField, BF>,
+    P: CubicExtVarParams<BF>,
+{
+    fn to_sponge_bytes(&self) -> Result<Vec<UInt8<P::BasePrimeField>>, SynthesisError> {
+        let mut output = self.c0.to_sponge_bytes()?;
+        output.append(&mut self.c1.to_sponge_bytes()?);
+        output.append(&mut self.c2.to_sponge_bytes()?);
+        Ok(output)
+    }
+
+    fn to_sponge_field_elements(&self) -> Result<Vec<FpVar<P::BasePrimeField>>, SynthesisError> {
+        let mut output = self.c0.to_sponge_field_elements()?;
+        output.append(&mut self.c1.to_sponge_field_elements()?);
+        output.append(&mut self.c2.to_sponge_field_elements()?);
+        Ok(output)
+    }
+}
+
+/// Points are absorbed as their coordinates, followed by the infinity flag for short Weierstrass
+/// curves, exactly as the native points: as field elements, or as bytes with the number of
+/// coordinates as a little-endian `u64` before them.
 macro_rules! impl_absorbable_group {
     ($group:ident, $params:ident) => {
         impl<P, F> AbsorbGadget<<P::BaseField as Field>::BasePrimeField> for $group<P, F>
@@ -102,7 +204,10 @@ macro_rules! impl_absorbable_group {
             fn to_sponge_bytes(
                 &self,
             ) -> Result<Vec<UInt8<<P::BaseField as Field>::BasePrimeField>>, SynthesisError> {
-                self.to_constraint_field()?.to_sponge_bytes()
+                let elems = self.to_constraint_field()?;
+                let mut bytes = UInt8::constant_vec(&(elems.len() as u64).to_le_bytes());
+                bytes.extend(elems.to_sponge_bytes()?);
+                Ok(bytes)
             }
 
             fn to_sponge_field_elements(
@@ -130,7 +235,7 @@ where
         Vec<UInt8<<<P as ModelParameters>::BaseField as Field>::BasePrimeField>>,
         SynthesisError,
     > {
-        self.to_bytes()
+        self.to_affine()?.to_sponge_bytes()
     }
 
     fn to_sponge_field_elements(
@@ -143,6 +248,62 @@ where
     }
 }
 
+/// The gadget of a Groth16 proof is absorbed as the native [`ark_groth16::Proof`]: its points
+/// `a`, `b` and `c`, each as its coordinates and its infinity flag.
+#[cfg(feature = "groth16")]
+impl<E: PairingEngine, P: PairingVar<E>> AbsorbGadget<E::Fq> for ProofVar<E, P>
+where
+    P::G1Var: AbsorbGadget<E::Fq>,
+    P::G2Var: AbsorbGadget<E::Fq>,
+{
+    fn to_sponge_bytes(&self) -> Result<Vec<UInt8<E::Fq>>, SynthesisError> {
+        let mut output = self.a.to_sponge_bytes()?;
+        output.extend(self.b.to_sponge_bytes()?);
+        output.extend(self.c.to_sponge_bytes()?);
+        Ok(output)
+    }
+
+    fn to_sponge_field_elements(&self) -> Result<Vec<FpVar<E::Fq>>, SynthesisError> {
+        let mut output = self.a.to_sponge_field_elements()?;
+        output.extend(self.b.to_sponge_field_elements()?);
+        output.extend(self.c.to_sponge_field_elements()?);
+        Ok(output)
+    }
+}
+
+/// The gadget of a Groth16 verifying key is absorbed as the native
+/// [`ark_groth16::VerifyingKey`], with the number of points of `gamma_abc_g1` as a constant.
+#[cfg(feature = "groth16")]
+impl<E: PairingEngine, P: PairingVar<E>> AbsorbGadget<E::Fq> for VerifyingKeyVar<E, P>
+where
+    P::G1Var: AbsorbGadget<E::Fq>,
+    P::G2Var: AbsorbGadget<E::Fq>,
+{
+    fn to_sponge_bytes(&self) -> Result<Vec<UInt8<E::Fq>>, SynthesisError> {
+        let mut output = self.alpha_g1.to_sponge_bytes()?;
+        output.extend(self.beta_g2.to_sponge_bytes()?);
+        output.extend(self.gamma_g2.to_sponge_bytes()?);
+        output.extend(self.delta_g2.to_sponge_bytes()?);
+        output.extend(ConstantLength(self.gamma_abc_g1.len()).to_sponge_bytes()?);
+        for point in &self.gamma_abc_g1 {
+            output.extend(point.to_sponge_bytes()?);
+        }
+        Ok(output)
+    }
+
+    fn to_sponge_field_elements(&self) -> Result<Vec<FpVar<E::Fq>>, SynthesisError> {
+        let mut output = self.alpha_g1.to_sponge_field_elements()?;
+        output.extend(self.beta_g2.to_sponge_field_elements()?);
+        output.extend(self.gamma_g2.to_sponge_field_elements()?);
+        output.extend(self.delta_g2.to_sponge_field_elements()?);
+        output.extend(ConstantLength(self.gamma_abc_g1.len()).to_sponge_field_elements()?);
+        for point in &self.gamma_abc_g1 {
+            output.extend(point.to_sponge_field_elements()?);
+        }
+        Ok(output)
+    }
+}
+
 impl<F: PrimeField, A: AbsorbGadget<F>> AbsorbGadget<F> for &[A] {
     fn to_sponge_bytes(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
         A::batch_to_sponge_bytes(self)
@@ -153,6 +314,12 @@ impl<F: PrimeField, A: AbsorbGadget<F>> AbsorbGadget<F> for &[A] {
     }
 }
 
+impl<F: PrimeField, A: AbsorbGadget<F>> AbsorbGadgetWithLength<F> for &[A] {
+    fn absorb_length(&self) -> usize {
+        self.len()
+    }
+}
+
 impl<F: PrimeField, A: AbsorbGadget<F>> AbsorbGadget<F> for Vec<A> {
     fn to_sponge_bytes(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
         self.as_slice().to_sponge_bytes()
@@ -161,8 +328,79 @@ impl<F: PrimeField, A: AbsorbGadget<F>> AbsorbGadget<F> for Vec<A> {
     fn to_sponge_field_elements(&self) -> Result<Vec<FpVar<F>>, SynthesisError> {
         self.as_slice().to_sponge_field_elements()
     }
+
+    /// Each inner list is prefixed with its length, matching the native encoding.
+    /// The lengths are allocated as constants since they are fixed at circuit-generation time.
+    fn batch_to_sponge_bytes(batch: &[Self]) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        let mut output = Vec::new();
+        for list in batch {
+            output.extend_from_slice(&UInt8::constant_vec(&Absorb::to_sponge_bytes_as_vec(
+                &list.len(),
+            )));
+            output.append(&mut list.to_sponge_bytes()?);
+        }
+        Ok(output)
+    }
+
+    /// Each inner list is prefixed with its length, matching the native encoding.
+    /// The lengths are allocated as constants since they are fixed at circuit-generation time.
+    fn batch_to_sponge_field_elements(batch: &[Self]) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        let mut output = Vec::new();
+        for list in batch {
+            output.extend(
+                Absorb::to_sponge_field_elements_as_vec::<F>(&list.len())
+                    .into_iter()
+                    .map(FpVar::Constant),
+            );
+            output.append(&mut list.to_sponge_field_elements()?);
+        }
+        Ok(output)
+    }
 }
 
+impl<F: PrimeField, A: AbsorbGadget<F>> AbsorbGadgetWithLength<F> for Vec<A> {
+    fn absorb_length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<F: PrimeField, A: AbsorbGadget<F>, const N: usize> AbsorbGadget<F> for [A; N] {
+    fn to_sponge_bytes(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        A::batch_to_sponge_bytes(self)
+    }
+
+    fn to_sponge_field_elements(&self) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        A::batch_to_sponge_field_elements(self)
+    }
+}
+
+macro_rules! impl_absorbable_tuple {
+    ($($name:ident : $index:tt),+) => {
+        impl<F: PrimeField, $($name: AbsorbGadget<F>),+> AbsorbGadget<F> for ($($name,)+) {
+            fn to_sponge_bytes(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
+                let mut output = Vec::new();
+                $(output.append(&mut self.$index.to_sponge_bytes()?);)+
+                Ok(output)
+            }
+
+            fn to_sponge_field_elements(&self) -> Result<Vec<FpVar<F>>, SynthesisError> {
+                let mut output = Vec::new();
+                $(output.append(&mut self.$index.to_sponge_field_elements()?);)+
+                Ok(output)
+            }
+        }
+    };
+}
+
+impl_absorbable_tuple!(A: 0);
+impl_absorbable_tuple!(A: 0, B: 1);
+impl_absorbable_tuple!(A: 0, B: 1, C: 2);
+impl_absorbable_tuple!(A: 0, B: 1, C: 2, D: 3);
+impl_absorbable_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4);
+impl_absorbable_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, G: 5);
+impl_absorbable_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, G: 5, H: 6);
+impl_absorbable_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, G: 5, H: 6, I: 7);
+
 impl<F: PrimeField, A: AbsorbGadget<F>> AbsorbGadget<F> for Option<A> {
     fn to_sponge_bytes(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
         let mut output = Vec::new();
@@ -182,6 +420,17 @@ impl<F: PrimeField, A: AbsorbGadget<F>> AbsorbGadget<F> for Option<A> {
     }
 }
 
+/// A string is a constant of the circuit, absorbed as [`Absorb`] absorbs it.
+impl<F: PrimeField> AbsorbGadget<F> for &str {
+    fn to_sponge_bytes(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        Ok(UInt8::constant_vec(&Absorb::to_sponge_bytes_as_vec(self)))
+    }
+
+    fn to_sponge_field_elements(&self) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        UInt8::constant_vec(self.as_bytes()).to_sponge_field_elements()
+    }
+}
+
 impl<F: PrimeField, A: AbsorbGadget<F>> AbsorbGadget<F> for &A {
     fn to_sponge_bytes(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
         (*self).to_sponge_bytes()
@@ -192,9 +441,9 @@ impl<F: PrimeField, A: AbsorbGadget<F>> AbsorbGadget<F> for &A {
     }
 }
 
-/// Individually absorbs each element in a comma-separated list of [`Absorbable`]s into a sponge.
+/// Individually absorbs each element in a comma-separated list of [`AbsorbGadget`]s into a sponge.
 /// Format is `absorb!(s, a_0, a_1, ..., a_n)`, where `s` is a mutable reference to a sponge
-/// and each `a_i` implements `AbsorbableVar`.
+/// and each `a_i` implements `AbsorbGadget`.
 #[macro_export]
 macro_rules! absorb_gadget {
     ($sponge:expr, $($absorbable:expr),+ ) => {
@@ -204,7 +453,27 @@ macro_rules! absorb_gadget {
     };
 }
 
-/// Quickly convert a list of different [`Absorbable`]s into sponge field elements.
+/// Quickly convert a list of different [`AbsorbGadget`]s into sponge bytes, as
+/// [`collect_sponge_bytes!`](crate::collect_sponge_bytes) does natively. The macro evaluates to a
+/// `Result`, and must be used in a function that returns a `Result<_, SynthesisError>`.
+#[macro_export]
+macro_rules! collect_sponge_bytes_gadget {
+    ($head:expr $(, $tail:expr)* ) => {
+        {
+            let mut output = AbsorbGadget::to_sponge_bytes(&$head)?;
+            $(
+                output.append(&mut AbsorbGadget::to_sponge_bytes(&$tail)?);
+            )*
+
+            Ok(output)
+        }
+    };
+}
+
+/// Quickly convert a list of different [`AbsorbGadget`]s into sponge field elements, as
+/// [`collect_sponge_field_elements!`](crate::collect_sponge_field_elements) does natively. The
+/// macro evaluates to a `Result`, and must be used in a function that returns a
+/// `Result<_, SynthesisError>`.
 #[macro_export]
 macro_rules! collect_sponge_field_elements_gadget {
     ($head:expr $(, $tail:expr)* ) => {
@@ -221,16 +490,21 @@ macro_rules! collect_sponge_field_elements_gadget {
 
 #[cfg(test)]
 mod tests {
+    use crate::absorb::tests::{Fq2, Fq2Parameters};
     use crate::constraints::AbsorbGadget;
     use crate::Absorb;
+    use ark_ff::PrimeField;
     use ark_r1cs_std::alloc::AllocVar;
+    use ark_r1cs_std::bits::boolean::Boolean;
     use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::fields::fp2::Fp2Var;
     use ark_r1cs_std::uint8::UInt8;
     use ark_r1cs_std::R1CSVar;
-    use ark_relations::r1cs::ConstraintSystem;
+    use ark_relations::r1cs::{ConstraintSystem, SynthesisError};
     use ark_relations::*;
-    use ark_std::{test_rng, UniformRand};
-    use ark_test_curves::bls12_381::Fr;
+    use ark_std::vec::Vec;
+    use ark_std::{test_rng, vec, UniformRand};
+    use ark_test_curves::bls12_381::{Fq, Fr};
 
     #[test]
     fn consistency_check() {
@@ -260,4 +534,281 @@ mod tests {
 
         assert!(cs.is_satisfied().unwrap())
     }
+
+    #[test]
+    fn structured_field_elements_consistency_check() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let mut rng = test_rng();
+
+        // boolean
+        let data = true;
+        let data_var = Boolean::new_witness(ns!(cs, "booldata"), || Ok(data)).unwrap();
+
+        let native_elems: Vec<Fr> = data.to_sponge_field_elements_as_vec();
+        let constraint_elems = data_var.to_sponge_field_elements().unwrap();
+        assert_eq!(constraint_elems.value().unwrap(), native_elems);
+
+        // option of a list of field elements
+        let data: Option<Vec<_>> = Some((0..4).map(|_| Fr::rand(&mut rng)).collect());
+        let data_var = data.as_ref().map(|elems| {
+            elems
+                .iter()
+                .map(|item| FpVar::new_witness(ns!(cs, "fpdata"), || Ok(*item)).unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        let native_elems: Vec<Fr> = data.to_sponge_field_elements_as_vec();
+        let constraint_elems = data_var.to_sponge_field_elements().unwrap();
+        assert_eq!(constraint_elems.value().unwrap(), native_elems);
+
+        let none: Option<Fr> = None;
+        let none_var: Option<FpVar<Fr>> = None;
+        let native_elems: Vec<Fr> = none.to_sponge_field_elements_as_vec();
+        let constraint_elems = none_var.to_sponge_field_elements().unwrap();
+        assert_eq!(constraint_elems.value().unwrap(), native_elems);
+
+        assert!(cs.is_satisfied().unwrap())
+    }
+
+    #[test]
+    fn composite_consistency_check() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let mut rng = test_rng();
+
+        let elem = Fr::rand(&mut rng);
+        let nested = vec![vec![1u8, 2, 3], vec![4u8]];
+        let array = [Fr::rand(&mut rng), Fr::rand(&mut rng)];
+        let data = (elem, nested.clone(), Some(array), true);
+
+        let elem_var = FpVar::new_witness(ns!(cs, "elem"), || Ok(elem)).unwrap();
+        let nested_var: Vec<_> = nested
+            .iter()
+            .map(|bytes| UInt8::new_witness_vec(ns!(cs, "nested"), bytes).unwrap())
+            .collect();
+        let array_var = [
+            FpVar::new_witness(ns!(cs, "array"), || Ok(array[0])).unwrap(),
+            FpVar::new_witness(ns!(cs, "array"), || Ok(array[1])).unwrap(),
+        ];
+        let flag_var = Boolean::new_witness(ns!(cs, "flag"), || Ok(true)).unwrap();
+        let data_var = (elem_var, nested_var, Some(array_var), flag_var);
+
+        let native_bytes = data.to_sponge_bytes_as_vec();
+        let constraint_bytes = data_var.to_sponge_bytes().unwrap();
+        assert_eq!(constraint_bytes.value().unwrap(), native_bytes);
+
+        let native_elems: Vec<Fr> = data.to_sponge_field_elements_as_vec();
+        let constraint_elems = data_var.to_sponge_field_elements().unwrap();
+        assert_eq!(constraint_elems.value().unwrap(), native_elems);
+
+        assert!(cs.is_satisfied().unwrap())
+    }
+
+    #[test]
+    fn extension_field_consistency_check() {
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let mut rng = test_rng();
+
+        let data: Vec<_> = (0..3).map(|_| Fq2::rand(&mut rng)).collect();
+        let data_var: Vec<_> = data
+            .iter()
+            .map(|item| {
+                Fp2Var::<Fq2Parameters>::new_witness(ns!(cs, "fq2data"), || Ok(*item)).unwrap()
+            })
+            .collect();
+
+        let native_bytes = data.to_sponge_bytes_as_vec();
+        let constraint_bytes = data_var.to_sponge_bytes().unwrap();
+        assert_eq!(constraint_bytes.value().unwrap(), native_bytes);
+
+        let native_elems: Vec<Fq> = data.to_sponge_field_elements_as_vec();
+        let constraint_elems = data_var.to_sponge_field_elements().unwrap();
+        assert_eq!(constraint_elems.value().unwrap(), native_elems);
+
+        assert!(cs.is_satisfied().unwrap())
+    }
+    #[test]
+    fn group_consistency_check() {
+        use ark_ec::ProjectiveCurve;
+        use ark_ff::Zero;
+        use ark_r1cs_std::groups::curves::short_weierstrass::ProjectiveVar;
+        use ark_test_curves::bls12_381::{g1, G1Affine, G1Projective};
+
+        type G1Var = ProjectiveVar<g1::Parameters, FpVar<Fq>>;
+
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let mut rng = test_rng();
+
+        let data: Vec<G1Projective> = vec![
+            G1Projective::rand(&mut rng),
+            G1Projective::zero(),
+            G1Projective::rand(&mut rng).double(),
+        ];
+        let data_var: Vec<_> = data
+            .iter()
+            .map(|item| G1Var::new_witness(ns!(cs, "g1data"), || Ok(*item)).unwrap())
+            .collect();
+        let affine_data: Vec<G1Affine> = data.iter().map(|item| item.into_affine()).collect();
+        let affine_data_var: Vec<_> = data_var
+            .iter()
+            .map(|item| item.to_affine().unwrap())
+            .collect();
+
+        let native_elems: Vec<Fq> = data.to_sponge_field_elements_as_vec();
+        assert_eq!(
+            affine_data.to_sponge_field_elements_as_vec::<Fq>(),
+            native_elems
+        );
+        assert_eq!(
+            data_var
+                .to_sponge_field_elements()
+                .unwrap()
+                .value()
+                .unwrap(),
+            native_elems
+        );
+        assert_eq!(
+            affine_data_var
+                .to_sponge_field_elements()
+                .unwrap()
+                .value()
+                .unwrap(),
+            native_elems
+        );
+
+        let native_bytes = data.to_sponge_bytes_as_vec();
+        assert_eq!(affine_data.to_sponge_bytes_as_vec(), native_bytes);
+        assert_eq!(
+            data_var.to_sponge_bytes().unwrap().value().unwrap(),
+            native_bytes
+        );
+        assert_eq!(
+            affine_data_var.to_sponge_bytes().unwrap().value().unwrap(),
+            native_bytes
+        );
+
+        assert!(cs.is_satisfied().unwrap())
+    }
+
+    struct Record {
+        value: Fr,
+        tag: Vec<u8>,
+        flag: bool,
+    }
+
+    impl Absorb for Record {
+        fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+            dest.append(&mut crate::collect_sponge_bytes!(
+                self.value, self.tag, self.flag
+            ));
+        }
+
+        fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
+            dest.append(&mut crate::collect_sponge_field_elements!(
+                self.value, self.tag, self.flag
+            ));
+        }
+    }
+
+    struct RecordVar {
+        value: FpVar<Fr>,
+        tag: Vec<UInt8<Fr>>,
+        flag: Boolean<Fr>,
+    }
+
+    impl AbsorbGadget<Fr> for RecordVar {
+        fn to_sponge_bytes(&self) -> Result<Vec<UInt8<Fr>>, SynthesisError> {
+            crate::collect_sponge_bytes_gadget!(self.value, self.tag, self.flag)
+        }
+
+        fn to_sponge_field_elements(&self) -> Result<Vec<FpVar<Fr>>, SynthesisError> {
+            crate::collect_sponge_field_elements_gadget!(self.value, self.tag, self.flag)
+        }
+    }
+
+    #[test]
+    fn collect_macros_consistency_check() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let mut rng = test_rng();
+        let record = Record {
+            value: Fr::rand(&mut rng),
+            tag: vec![1, 2, 3],
+            flag: true,
+        };
+        let record_var = RecordVar {
+            value: FpVar::new_witness(ns!(cs, "value"), || Ok(record.value)).unwrap(),
+            tag: UInt8::new_witness_vec(ns!(cs, "tag"), &record.tag).unwrap(),
+            flag: Boolean::new_witness(ns!(cs, "flag"), || Ok(record.flag)).unwrap(),
+        };
+
+        assert_eq!(
+            record_var.to_sponge_bytes().unwrap().value().unwrap(),
+            record.to_sponge_bytes_as_vec()
+        );
+        let native_elems: Vec<Fr> = record.to_sponge_field_elements_as_vec();
+        assert_eq!(
+            record_var
+                .to_sponge_field_elements()
+                .unwrap()
+                .value()
+                .unwrap(),
+            native_elems
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    #[cfg(all(feature = "groth16", feature = "bls12_381"))]
+    fn groth16_consistency() {
+        use ark_bls12_381::{Bls12_381, Fq, G1Projective, G2Projective, Parameters};
+        use ark_ec::ProjectiveCurve;
+        use ark_groth16::constraints::{ProofVar, VerifyingKeyVar};
+        use ark_groth16::{Proof, VerifyingKey};
+        use ark_r1cs_std::pairing::bls12::PairingVar;
+
+        type P = PairingVar<Parameters>;
+
+        let mut rng = test_rng();
+        let g1 = G1Projective::rand(&mut rng).into_affine();
+        let g2 = G2Projective::rand(&mut rng).into_affine();
+        let proof = Proof::<Bls12_381> {
+            a: g1,
+            b: g2,
+            c: G1Projective::rand(&mut rng).into_affine(),
+        };
+        let vk = VerifyingKey::<Bls12_381> {
+            alpha_g1: g1,
+            beta_g2: g2,
+            gamma_g2: G2Projective::rand(&mut rng).into_affine(),
+            delta_g2: G2Projective::rand(&mut rng).into_affine(),
+            gamma_abc_g1: vec![g1, G1Projective::rand(&mut rng).into_affine()],
+        };
+
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let proof_var =
+            ProofVar::<Bls12_381, P>::new_witness(cs.clone(), || Ok(proof.clone())).unwrap();
+        let vk_var =
+            VerifyingKeyVar::<Bls12_381, P>::new_witness(cs.clone(), || Ok(vk.clone())).unwrap();
+
+        assert_eq!(
+            proof_var
+                .to_sponge_field_elements()
+                .unwrap()
+                .value()
+                .unwrap(),
+            proof.to_sponge_field_elements_as_vec::<Fq>()
+        );
+        assert_eq!(
+            proof_var.to_sponge_bytes().unwrap().value().unwrap(),
+            proof.to_sponge_bytes_as_vec()
+        );
+        assert_eq!(
+            vk_var.to_sponge_field_elements().unwrap().value().unwrap(),
+            vk.to_sponge_field_elements_as_vec::<Fq>()
+        );
+        assert_eq!(
+            vk_var.to_sponge_bytes().unwrap().value().unwrap(),
+            vk.to_sponge_bytes_as_vec()
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
 }
@@ -1,24 +1,264 @@
-use crate::{Absorb, CryptographicSponge, FieldElementSize};
-use ark_ff::PrimeField;
+use crate::{
+    integer_in_range_num_bits, order_squeezed_units, split_squeezed_bits, squeeze_bits_per_element,
+    squeeze_bytes_per_element, squeeze_powers_input, squeezed_units_for_endianness, Absorb,
+    CryptographicSponge, Endianness, FieldElementSize, SpongeExt, SqueezeMode,
+    UNIFORM_SQUEEZE_NUM_EXTRA_BITS,
+};
+use ark_ff::{BigInteger, FpParameters, PrimeField, Zero};
 use ark_nonnative_field::params::{get_params, OptimizationType};
 use ark_nonnative_field::{AllocatedNonNativeFieldVar, NonNativeFieldVar};
-use ark_r1cs_std::alloc::AllocVar;
+use ark_r1cs_std::alloc::{AllocVar, AllocationMode};
 use ark_r1cs_std::bits::boolean::Boolean;
+use ark_r1cs_std::bits::uint64::UInt64;
 use ark_r1cs_std::bits::uint8::UInt8;
+use ark_r1cs_std::eq::EqGadget;
 use ark_r1cs_std::fields::fp::{AllocatedFp, FpVar};
-use ark_r1cs_std::R1CSVar;
+use ark_r1cs_std::fields::FieldVar;
+use ark_r1cs_std::select::CondSelectGadget;
+use ark_r1cs_std::{R1CSVar, ToBitsGadget};
 use ark_relations::lc;
-use ark_relations::r1cs::{ConstraintSystemRef, LinearCombination, SynthesisError};
+use ark_relations::r1cs::{
+    ConstraintSystemRef, LinearCombination, OptimizationGoal, SynthesisError, Variable,
+};
+use ark_std::any::TypeId;
+use ark_std::boxed::Box;
+use ark_std::marker::PhantomData;
 use ark_std::vec;
 use ark_std::vec::Vec;
+use num_bigint::BigUint;
 
 mod absorb;
 pub use absorb::*;
 
-/// Converts little-endian bits to a list of nonnative elements.
+mod profile;
+pub use profile::*;
+
+mod squeeze;
+pub use squeeze::*;
+
+/// Returns the values of the elements of `state` if they are all constants.
+///
+/// The sponge gadgets then evaluate the permutation natively, so that absorbing constants, such
+/// as a verifying key, costs no constraints until a variable is absorbed.
+pub(crate) fn constant_state<F: PrimeField>(state: &[FpVar<F>]) -> Option<Vec<F>> {
+    state
+        .iter()
+        .map(|elem| match elem {
+            FpVar::Constant(value) => Some(*value),
+            FpVar::Var(_) => None,
+        })
+        .collect()
+}
+
+/// Returns the `MODULUS_BITS` little-endian bits of the canonical representation of `elem`,
+/// which the sponge gadgets squeeze bits from.
+///
+/// This is the decomposition of [`ToBitsGadget::to_bits_le`], but instead of comparing the bits
+/// with the modulus bit by bit, the high half `H` and the low half `L` of the bits are compared
+/// with those of `p - 1` through two range checks: `H <= H(p - 1)`, and `L <= L(p - 1)` when
+/// `H = H(p - 1)`. The differences are much smaller than the modulus, so a negative difference
+/// wraps around to an element that does not fit in the range. This saves about a fifth of the
+/// constraints of the decomposition.
+pub(crate) fn to_canonical_bits_le<F: PrimeField>(
+    elem: &FpVar<F>,
+) -> Result<Vec<Boolean<F>>, SynthesisError> {
+    if elem.is_constant() {
+        return elem.to_bits_le();
+    }
+    let bits = elem.to_non_unique_bits_le()?;
+
+    let num_bits = F::Params::MODULUS_BITS as usize;
+    let num_low_bits = num_bits / 2;
+    let mut max = F::Params::MODULUS;
+    max.sub_noborrow(&F::BigInt::from(1u64));
+    let max_bits = max.to_bits_le();
+    let max_low = F::from_repr(F::BigInt::from_bits_le(&max_bits[..num_low_bits])).unwrap();
+    let max_high =
+        F::from_repr(F::BigInt::from_bits_le(&max_bits[num_low_bits..num_bits])).unwrap();
+
+    let low = Boolean::le_bits_to_fp_var(&bits[..num_low_bits])?;
+    let high = Boolean::le_bits_to_fp_var(&bits[num_low_bits..])?;
+    enforce_num_bits(
+        &(FpVar::constant(max_high) - &high),
+        num_bits - num_low_bits,
+    )?;
+    let is_max_high = high.is_eq(&FpVar::constant(max_high))?;
+    let low_diff = FpVar::from(is_max_high) * (FpVar::constant(max_low) - &low);
+    enforce_num_bits(&low_diff, num_low_bits)?;
+
+    Ok(bits)
+}
+
+/// Enforces that `elem` fits in `num_bits` bits, with a constraint for each bit.
+fn enforce_num_bits<F: PrimeField>(elem: &FpVar<F>, num_bits: usize) -> Result<(), SynthesisError> {
+    let cs = elem.cs();
+    let value = elem
+        .value()
+        .ok()
+        .map(|value| value.into_repr().to_bits_le());
+    let bits = (0..num_bits)
+        .map(|i| {
+            Boolean::new_witness(cs.clone(), || {
+                value
+                    .as_ref()
+                    .map(|bits| bits[i])
+                    .ok_or(SynthesisError::AssignmentMissing)
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Boolean::le_bits_to_fp_var(&bits)?.enforce_equal(elem)
+}
+
+/// Reduces the little-endian integer of `bits` modulo `bound`, as
+/// [`CryptographicSponge::squeeze_integers_in_range`] does with squeezed bits.
+///
+/// The reduction is proven by allocating the quotient and the remainder in binary in `cs`, or as
+/// constants if `cs` is none. The integer of `bits` and `quotient * bound + remainder` must not
+/// wrap around the modulus of `CF`.
+fn integer_in_range_var<CF: PrimeField>(
+    cs: &ConstraintSystemRef<CF>,
+    bits: &[Boolean<CF>],
+    bound: u64,
+) -> Result<FpVar<CF>, SynthesisError> {
+    let bound_bits = (64 - bound.leading_zeros()) as usize;
+    let mode = if cs.is_none() {
+        AllocationMode::Constant
+    } else {
+        AllocationMode::Witness
+    };
+
+    // Allocates `num_bits` bits of the integer computed by `f`.
+    let alloc_integer = |num_bits: usize, f: &dyn Fn() -> Result<BigUint, SynthesisError>| {
+        let value = f();
+        let mut integer = FpVar::zero();
+        let mut power = CF::one();
+        for i in 0..num_bits {
+            let bit =
+                Boolean::new_variable(cs.clone(), || value.clone().map(|v| v.bit(i as u64)), mode)?;
+            integer += FpVar::from(bit) * power;
+            power.double_in_place();
+        }
+        Ok::<_, SynthesisError>(integer)
+    };
+
+    let mut integer = FpVar::zero();
+    let mut power = CF::one();
+    for bit in bits {
+        integer += FpVar::from(bit.clone()) * power;
+        power.double_in_place();
+    }
+    let division = || {
+        let value = bits.value()?;
+        let integer = value
+            .iter()
+            .rev()
+            .fold(BigUint::zero(), |acc, &bit| (acc << 1u8) + bit as u8);
+        Ok((&integer / bound, integer % bound))
+    };
+    let quotient = alloc_integer(bits.len() - bound_bits + 1, &|| division().map(|d| d.0))?;
+    let remainder = alloc_integer(bound_bits, &|| division().map(|d| d.1))?;
+    // `bound - 1 - remainder` is nonnegative, so `remainder < bound`.
+    let slack = alloc_integer(bound_bits, &|| division().map(|d| (bound - 1) - d.1))?;
+
+    (&remainder + &slack).enforce_equal(&FpVar::Constant(CF::from(bound - 1)))?;
+    (quotient * CF::from(bound) + &remainder).enforce_equal(&integer)?;
+    Ok(remainder)
+}
+
+/// Selects `true_state` if `cond` is true and `false_state` otherwise, element by element.
+///
+/// Returns [`SynthesisError::Unsatisfiable`] if the states do not have the same length.
+pub(crate) fn select_state<F: PrimeField>(
+    cond: &Boolean<F>,
+    true_state: &[FpVar<F>],
+    false_state: &[FpVar<F>],
+) -> Result<Vec<FpVar<F>>, SynthesisError> {
+    if true_state.len() != false_state.len() {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    true_state
+        .iter()
+        .zip(false_state)
+        .map(|(t, f)| FpVar::conditionally_select(cond, t, f))
+        .collect()
+}
+
+/// Returns the [`OptimizationType`] that the nonnative field gadgets use in `cs`, which follows
+/// the optimization goal of `cs`.
+pub fn optimization_type<CF: PrimeField>(cs: &ConstraintSystemRef<CF>) -> OptimizationType {
+    match cs.optimization_goal() {
+        OptimizationGoal::None | OptimizationGoal::Constraints => OptimizationType::Constraints,
+        OptimizationGoal::Weight => OptimizationType::Weight,
+    }
+}
+
+/// The limbs of the powers of two of `F` for each [`OptimizationType`], which
+/// [`bits_le_to_nonnative`] caches in the constraint system instead of recomputing them on every
+/// call.
+struct PowersOfTwoLimbs<F: PrimeField, CF: PrimeField> {
+    constraints: Vec<Vec<CF>>,
+    weight: Vec<Vec<CF>>,
+    target_phantom: PhantomData<F>,
+}
+
+/// Returns the limbs of the first `num_bits` powers of two of `F` for `optimization_type`,
+/// computing only those that are not yet in the cache of `cs`.
+fn powers_of_two_limbs<F: PrimeField, CF: PrimeField>(
+    cs: &ConstraintSystemRef<CF>,
+    num_bits: usize,
+    optimization_type: OptimizationType,
+) -> Result<Vec<Vec<CF>>, SynthesisError> {
+    let extend = |table: &mut Vec<Vec<CF>>| -> Result<(), SynthesisError> {
+        let mut cur = F::from(2u64).pow([table.len() as u64]);
+        while table.len() < num_bits {
+            table.push(
+                AllocatedNonNativeFieldVar::<F, CF>::get_limbs_representations(
+                    &cur,
+                    optimization_type,
+                )?,
+            );
+            cur.double_in_place();
+        }
+        Ok(())
+    };
+
+    let cs = match cs.borrow() {
+        Some(cs) => cs,
+        None => {
+            let mut table = Vec::with_capacity(num_bits);
+            extend(&mut table)?;
+            return Ok(table);
+        }
+    };
+    let mut cache = cs.cache_map.borrow_mut();
+    let limbs = cache
+        .entry(TypeId::of::<PowersOfTwoLimbs<F, CF>>())
+        .or_insert_with(|| {
+            Box::new(PowersOfTwoLimbs::<F, CF> {
+                constraints: Vec::new(),
+                weight: Vec::new(),
+                target_phantom: PhantomData,
+            })
+        })
+        .downcast_mut::<PowersOfTwoLimbs<F, CF>>()
+        .expect("the cached value has the type of its key");
+    let table = match optimization_type {
+        OptimizationType::Constraints => &mut limbs.constraints,
+        OptimizationType::Weight => &mut limbs.weight,
+    };
+    extend(table)?;
+    Ok(table[..num_bits].to_vec())
+}
+
+/// Converts little-endian bits to a list of nonnative elements, whose limbs are laid out for
+/// `optimization_type`. Elements whose bits are all constants are constants.
+///
+/// The nonnative field gadgets lay out their limbs for the optimization goal of their constraint
+/// system, so `optimization_type` should be [`optimization_type`] of `cs`.
 pub fn bits_le_to_nonnative<'a, F: PrimeField, CF: PrimeField>(
     cs: ConstraintSystemRef<CF>,
     all_nonnative_bits_le: impl IntoIterator<Item = &'a Vec<Boolean<CF>>>,
+    optimization_type: OptimizationType,
 ) -> Result<Vec<NonNativeFieldVar<F, CF>>, SynthesisError> {
     let all_nonnative_bits_le = all_nonnative_bits_le.into_iter().collect::<Vec<_>>();
     if all_nonnative_bits_le.is_empty() {
@@ -30,25 +270,24 @@ pub fn bits_le_to_nonnative<'a, F: PrimeField, CF: PrimeField>(
         max_nonnative_bits = max_nonnative_bits.max(bits.len());
     }
 
-    let mut lookup_table = Vec::<Vec<CF>>::new();
-    let mut cur = F::one();
-    for _ in 0..max_nonnative_bits {
-        let repr = AllocatedNonNativeFieldVar::<F, CF>::get_limbs_representations(
-            &cur,
-            OptimizationType::Constraints,
-        )?;
-        lookup_table.push(repr);
-        cur.double_in_place();
-    }
+    let lookup_table = powers_of_two_limbs::<F, CF>(&cs, max_nonnative_bits, optimization_type)?;
 
-    let params = get_params(
-        F::size_in_bits(),
-        CF::size_in_bits(),
-        OptimizationType::Constraints,
-    );
+    let params = get_params(F::size_in_bits(), CF::size_in_bits(), optimization_type);
 
     let mut output = Vec::with_capacity(all_nonnative_bits_le.len());
     for nonnative_bits_le in all_nonnative_bits_le {
+        // Constant bits, such as those squeezed from a constant state, give a constant element.
+        if nonnative_bits_le.is_constant() {
+            let value = nonnative_bits_le
+                .iter()
+                .rev()
+                .try_fold(F::zero(), |value, bit| {
+                    Ok::<_, SynthesisError>(value.double() + F::from(bit.value()?))
+                })?;
+            output.push(NonNativeFieldVar::Constant(value));
+            continue;
+        }
+
         let mut val = vec![CF::zero(); params.num_limbs];
         let mut lc = vec![LinearCombination::<CF>::zero(); params.num_limbs];
 
@@ -88,8 +327,52 @@ pub fn bits_le_to_nonnative<'a, F: PrimeField, CF: PrimeField>(
     Ok(output)
 }
 
+/// Converts little-endian bits, fewer than the capacity of `F`, to a nonnative element whose
+/// limbs are linear combinations of the bits, so that the conversion costs no constraints.
+fn bits_le_to_nonnative_limbs<F: PrimeField, CF: PrimeField>(
+    bits: &[Boolean<CF>],
+    optimization_type: OptimizationType,
+) -> Result<NonNativeFieldVar<F, CF>, SynthesisError> {
+    let cs = bits.cs();
+    if cs.is_none() {
+        let bits = bits.value()?;
+        return Ok(NonNativeFieldVar::Constant(
+            F::from_repr(F::BigInt::from_bits_le(&bits)).unwrap(),
+        ));
+    }
+
+    let params = get_params(F::size_in_bits(), CF::size_in_bits(), optimization_type);
+    // The limbs are ordered from the most significant one.
+    let limbs = (0..params.num_limbs)
+        .rev()
+        .map(|i| {
+            let start = (i * params.bits_per_limb).min(bits.len());
+            let end = ((i + 1) * params.bits_per_limb).min(bits.len());
+            if start == end {
+                Ok(FpVar::zero())
+            } else {
+                Boolean::le_bits_to_fp_var(&bits[start..end])
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(NonNativeFieldVar::Var(AllocatedNonNativeFieldVar {
+        cs,
+        limbs,
+        num_of_additions_over_normal_form: CF::zero(),
+        is_in_the_normal_form: true,
+        target_phantom: Default::default(),
+    }))
+}
+
 /// Enables simple access to the "gadget" version of the sponge.
 /// Simplifies trait bounds in downstream generic code.
+///
+/// Code generic over a sponge and its gadget should take a single `S: SpongeWithGadget<CF>` and
+/// name the gadget `S::Var`, as [`CycleSpongeVar`](crate::cycle::constraints::CycleSpongeVar)
+/// and [`PathVar`](crate::merkle::constraints::PathVar) do, rather than a second type parameter
+/// `SV: CryptographicSpongeVar<CF, S>` and a `PhantomData<S>`. Every sponge gadget of this crate
+/// implements it.
 pub trait SpongeWithGadget<CF: PrimeField>: CryptographicSponge {
     /// The gadget version of `Self`.
     type Var: CryptographicSpongeVar<CF, Self>;
@@ -105,12 +388,133 @@ pub trait CryptographicSpongeVar<CF: PrimeField, S: CryptographicSponge>: Clone
     /// Initialize a new instance of the sponge.
     fn new(cs: ConstraintSystemRef<CF>, params: &Self::Parameters) -> Self;
 
+    /// The rate of the sponge, as [`CryptographicSponge::rate`].
+    fn rate(&self) -> usize;
+
+    /// The capacity of the sponge, as [`CryptographicSponge::capacity`].
+    fn capacity(&self) -> usize;
+
+    /// The security level of the sponge, as [`CryptographicSponge::security_level`].
+    fn security_level(&self) -> usize;
+
     /// Returns a ref to the underlying constraint system the sponge is operating in.
     fn cs(&self) -> ConstraintSystemRef<CF>;
 
     /// Absorb an input into the sponge.
     fn absorb(&mut self, input: &impl AbsorbGadget<CF>) -> Result<(), SynthesisError>;
 
+    /// Absorb the items of `iter` one by one, without collecting them, as
+    /// [`CryptographicSponge::absorb_iter`].
+    fn absorb_iter<A: AbsorbGadget<CF>>(
+        &mut self,
+        iter: impl IntoIterator<Item = A>,
+    ) -> Result<(), SynthesisError> {
+        for item in iter {
+            self.absorb(&item)?;
+        }
+        Ok(())
+    }
+
+    /// Absorbs the length of `input` and then `input`, as
+    /// [`CryptographicSponge::absorb_with_length`]. The length is fixed when generating the
+    /// circuit, so it is absorbed as a constant.
+    fn absorb_with_length(
+        &mut self,
+        input: &impl AbsorbGadgetWithLength<CF>,
+    ) -> Result<(), SynthesisError> {
+        self.absorb(&ConstantLength(input.absorb_length()))?;
+        self.absorb(input)
+    }
+
+    /// Absorb `bytes`, packed into field elements exactly as the native sponge packs a slice of
+    /// bytes absorbed with [`CryptographicSponge::absorb`]: the length of `bytes` as a
+    /// little-endian `u64` followed by `bytes`, `CAPACITY / 8` bytes per field element.
+    ///
+    /// This lets byte-level protocol messages be absorbed consistently with the native sponge.
+    fn absorb_bytes(&mut self, bytes: &[UInt8<CF>]) -> Result<(), SynthesisError> {
+        self.absorb(&bytes)
+    }
+
+    /// Absorb `bits`, packed into field elements as
+    /// [`FieldBasedCryptographicSponge::absorb_bits`] packs them: the number of bits as a
+    /// constant, followed by the bits in chunks of `CAPACITY` little-endian bits per element.
+    ///
+    /// This is how bits squeezed earlier, e.g. by a recursive verifier, are absorbed again.
+    ///
+    /// [`FieldBasedCryptographicSponge::absorb_bits`]: crate::FieldBasedCryptographicSponge::absorb_bits
+    fn absorb_bits(&mut self, bits: &[Boolean<CF>]) -> Result<(), SynthesisError> {
+        let mut elems = vec![FpVar::Constant(CF::from(bits.len() as u64))];
+        for chunk in bits.chunks(CF::Params::CAPACITY as usize) {
+            elems.push(Boolean::le_bits_to_fp_var(chunk)?);
+        }
+        self.absorb(&elems)
+    }
+
+    /// Absorb `words`, packed into field elements as
+    /// [`FieldBasedCryptographicSponge::absorb_u64s`] packs them: as the little-endian bits of
+    /// the words absorbed with [`CryptographicSpongeVar::absorb_bits`].
+    ///
+    /// [`FieldBasedCryptographicSponge::absorb_u64s`]: crate::FieldBasedCryptographicSponge::absorb_u64s
+    fn absorb_u64s(&mut self, words: &[UInt64<CF>]) -> Result<(), SynthesisError> {
+        let bits: Vec<_> = words.iter().flat_map(|word| word.to_bits_le()).collect();
+        self.absorb_bits(&bits)
+    }
+
+    /// Absorb the public inputs of a statement, in order and prefixed with their number as a
+    /// constant, as [`FieldBasedCryptographicSponge::absorb_public_inputs`] absorbs them.
+    ///
+    /// [`FieldBasedCryptographicSponge::absorb_public_inputs`]: crate::FieldBasedCryptographicSponge::absorb_public_inputs
+    fn absorb_public_inputs(&mut self, inputs: &[FpVar<CF>]) -> Result<(), SynthesisError> {
+        self.absorb_with_length(&inputs)
+    }
+
+    /// Absorb all the instance variables of the constraint system of the sponge, in the order
+    /// in which they were allocated and without the constant one, with
+    /// [`Self::absorb_public_inputs`]. Natively, the public inputs are those given to the verifier
+    /// of the circuit.
+    ///
+    /// The number of inputs is that of the instance variables allocated so far, so this should
+    /// be called once they have all been allocated.
+    fn absorb_all_public_inputs(&mut self) -> Result<(), SynthesisError> {
+        let cs = self.cs();
+        let inputs: Vec<_> = (1..cs.num_instance_variables())
+            .map(|index| {
+                let variable = Variable::Instance(index);
+                FpVar::Var(AllocatedFp::new(
+                    cs.assigned_value(variable),
+                    variable,
+                    cs.clone(),
+                ))
+            })
+            .collect();
+        self.absorb_public_inputs(&inputs)
+    }
+
+    /// Absorb elements of another field `F`, e.g. scalars of another curve in a recursive
+    /// verifier, as [`FieldBasedCryptographicSponge::absorb_nonnative`] does.
+    ///
+    /// The limbs of the elements are not absorbed as they are, since an element has several limb
+    /// representations. Each element is instead decomposed into its `MODULUS_BITS` little-endian
+    /// bits, which are enforced to be the canonical representation of the element, and the bits
+    /// are packed in chunks of `CAPACITY` bits per field element.
+    ///
+    /// [`FieldBasedCryptographicSponge::absorb_nonnative`]: crate::FieldBasedCryptographicSponge::absorb_nonnative
+    fn absorb_nonnative<F: PrimeField>(
+        &mut self,
+        elems: &[NonNativeFieldVar<F, CF>],
+    ) -> Result<(), SynthesisError> {
+        let mut packed = Vec::new();
+        for elem in elems {
+            let bits = elem.to_bits_le()?;
+            for chunk in
+                bits[..F::Params::MODULUS_BITS as usize].chunks(CF::Params::CAPACITY as usize)
+            {
+                packed.push(Boolean::le_bits_to_fp_var(chunk)?);
+            }
+        }
+        self.absorb(&packed)
+    }
+
     /// Squeeze `num_bytes` bytes from the sponge.
     fn squeeze_bytes(&mut self, num_bytes: usize) -> Result<Vec<UInt8<CF>>, SynthesisError>;
 
@@ -119,40 +523,129 @@ pub trait CryptographicSpongeVar<CF: PrimeField, S: CryptographicSponge>: Clone
 
     /// Squeeze `sizes.len()` nonnative field elements from the sponge, where the `i`-th element of
     /// the output has size `sizes[i]`.
+    ///
+    /// The limbs of the elements are laid out for the optimization goal of the constraint system,
+    /// as the nonnative field gadgets expect.
+    #[allow(clippy::type_complexity)]
     fn squeeze_nonnative_field_elements_with_sizes<F: PrimeField>(
         &mut self,
         sizes: &[FieldElementSize],
     ) -> Result<(Vec<NonNativeFieldVar<F, CF>>, Vec<Vec<Boolean<CF>>>), SynthesisError> {
-        if sizes.len() == 0 {
+        if sizes.is_empty() {
             return Ok((Vec::new(), Vec::new()));
         }
 
         let cs = self.cs();
 
-        let mut total_bits = 0usize;
-        for size in sizes {
-            total_bits += size.num_bits::<F>();
-        }
+        let num_bits: Vec<usize> = sizes.iter().map(|size| size.num_bits::<F>()).collect();
+        let bits = self.squeeze_bits(num_bits.iter().sum())?;
+        let dest_bits = split_squeezed_bits(&bits, &num_bits);
 
-        let bits = self.squeeze_bits(total_bits)?;
+        let optimization_type = optimization_type(&cs);
+        let dest_gadgets = bits_le_to_nonnative(cs, dest_bits.iter(), optimization_type)?;
 
-        let mut dest_bits = Vec::<Vec<Boolean<CF>>>::with_capacity(sizes.len());
+        Ok((dest_gadgets, dest_bits))
+    }
 
-        let mut bits_window = bits.as_slice();
-        for size in sizes {
-            let num_bits = size.num_bits::<F>();
-            let nonnative_bits_le = bits_window[..num_bits].to_vec();
-            bits_window = &bits_window[num_bits..];
+    /// Squeeze `num_bits` bits from the sponge in `endianness`, as
+    /// [`FieldBasedCryptographicSponge::squeeze_bits_with_endianness`](crate::FieldBasedCryptographicSponge::squeeze_bits_with_endianness).
+    fn squeeze_bits_with_endianness(
+        &mut self,
+        num_bits: usize,
+        endianness: Endianness,
+    ) -> Result<Vec<Boolean<CF>>, SynthesisError> {
+        let bits_per_element = squeeze_bits_per_element::<CF>();
+        let bits = self.squeeze_bits(squeezed_units_for_endianness(
+            num_bits,
+            bits_per_element,
+            endianness,
+        ))?;
+        Ok(order_squeezed_units(
+            bits,
+            bits_per_element,
+            num_bits,
+            endianness,
+        ))
+    }
+
+    /// Squeeze `num_bytes` bytes from the sponge in `endianness`, as
+    /// [`FieldBasedCryptographicSponge::squeeze_bytes_with_endianness`](crate::FieldBasedCryptographicSponge::squeeze_bytes_with_endianness).
+    fn squeeze_bytes_with_endianness(
+        &mut self,
+        num_bytes: usize,
+        endianness: Endianness,
+    ) -> Result<Vec<UInt8<CF>>, SynthesisError> {
+        let bytes_per_element = squeeze_bytes_per_element::<CF>();
+        let bytes = self.squeeze_bytes(squeezed_units_for_endianness(
+            num_bytes,
+            bytes_per_element,
+            endianness,
+        ))?;
+        Ok(order_squeezed_units(
+            bytes,
+            bytes_per_element,
+            num_bytes,
+            endianness,
+        ))
+    }
 
-            dest_bits.push(nonnative_bits_le);
+    /// Squeeze `sizes.len()` nonnative field elements from the sponge, as
+    /// [`FieldBasedCryptographicSponge::squeeze_nonnative_field_elements_with_sizes_and_endianness`](crate::FieldBasedCryptographicSponge::squeeze_nonnative_field_elements_with_sizes_and_endianness).
+    ///
+    /// The bits of each element are returned in `endianness`.
+    #[allow(clippy::type_complexity)]
+    fn squeeze_nonnative_field_elements_with_sizes_and_endianness<F: PrimeField>(
+        &mut self,
+        sizes: &[FieldElementSize],
+        endianness: Endianness,
+    ) -> Result<(Vec<NonNativeFieldVar<F, CF>>, Vec<Vec<Boolean<CF>>>), SynthesisError> {
+        if sizes.is_empty() {
+            return Ok((Vec::new(), Vec::new()));
         }
 
-        let dest_gadgets = bits_le_to_nonnative(cs, dest_bits.iter())?;
+        let cs = self.cs();
+
+        let num_bits: Vec<usize> = sizes.iter().map(|size| size.num_bits::<F>()).collect();
+        let bits = self.squeeze_bits_with_endianness(num_bits.iter().sum(), endianness)?;
+        let dest_bits = split_squeezed_bits(&bits, &num_bits);
+        let dest_bits_le: Vec<_> = dest_bits
+            .iter()
+            .map(|bits| order_squeezed_units(bits.clone(), bits.len(), bits.len(), endianness))
+            .collect();
+
+        let optimization_type = optimization_type(&cs);
+        let dest_gadgets = bits_le_to_nonnative(cs, dest_bits_le.iter(), optimization_type)?;
 
         Ok((dest_gadgets, dest_bits))
     }
 
+    /// Squeeze `sizes.len()` nonnative field elements from the sponge, with the same values as
+    /// [`Self::squeeze_nonnative_field_elements_with_sizes`], but without returning their bits.
+    ///
+    /// The limbs of each element are linear combinations of the bits of the squeezed native
+    /// elements, so the only constraints are those of decomposing the native elements, instead
+    /// of a constraint and a variable for every limb.
+    fn squeeze_nonnative_field_elements_direct<F: PrimeField>(
+        &mut self,
+        sizes: &[FieldElementSize],
+    ) -> Result<Vec<NonNativeFieldVar<F, CF>>, SynthesisError> {
+        let optimization_type = optimization_type(&self.cs());
+        let total_bits = sizes.iter().map(|size| size.num_bits::<F>()).sum();
+        let bits = self.squeeze_bits(total_bits)?;
+
+        let mut bits_window = bits.as_slice();
+        sizes
+            .iter()
+            .map(|size| {
+                let (elem_bits, rest) = bits_window.split_at(size.num_bits::<F>());
+                bits_window = rest;
+                bits_le_to_nonnative_limbs(elem_bits, optimization_type)
+            })
+            .collect()
+    }
+
     /// Squeeze `num_elements` nonnative field elements from the sponge.
+    #[allow(clippy::type_complexity)]
     fn squeeze_nonnative_field_elements<F: PrimeField>(
         &mut self,
         num_elements: usize,
@@ -162,8 +655,12 @@ pub trait CryptographicSpongeVar<CF: PrimeField, S: CryptographicSponge>: Clone
         )
     }
 
-    /// Creates a new sponge with applied domain separation.
+    /// Creates a new sponge with applied domain separation, as [`CryptographicSponge::fork`].
+    ///
+    /// The tag is absorbed as constants.
     fn fork(&self, domain: &[u8]) -> Result<Self, SynthesisError> {
+        #[cfg(feature = "trace")]
+        tracing::trace!(target: "ark_sponge", domain = ?domain, "fork");
         let mut new_sponge = self.clone();
 
         let mut input = Absorb::to_sponge_bytes_as_vec(&domain.len());
@@ -185,4 +682,347 @@ pub trait CryptographicSpongeVar<CF: PrimeField, S: CryptographicSponge>: Clone
         &mut self,
         num_elements: usize,
     ) -> Result<Vec<FpVar<CF>>, SynthesisError>;
+
+    /// Squeeze `num_elements` field elements from the sponge in the given [`SqueezeMode`], as
+    /// [`CryptographicSponge::squeeze_field_elements_with_mode`].
+    ///
+    /// In [`SqueezeMode::Uniform`], each element is a linear combination of the squeezed bits,
+    /// so the reduction costs no constraints.
+    fn squeeze_field_elements_with_mode(
+        &mut self,
+        num_elements: usize,
+        mode: SqueezeMode,
+    ) -> Result<Vec<FpVar<CF>>, SynthesisError> {
+        match mode {
+            SqueezeMode::Truncated => self.squeeze_field_elements(num_elements),
+            SqueezeMode::Uniform => {
+                let num_bits = CF::Params::MODULUS_BITS as usize + UNIFORM_SQUEEZE_NUM_EXTRA_BITS;
+                let bits = self.squeeze_bits(num_bits * num_elements)?;
+                Ok(bits
+                    .chunks(num_bits)
+                    .map(|bits| {
+                        let mut elem = FpVar::zero();
+                        let mut power = CF::one();
+                        for bit in bits {
+                            elem += FpVar::from(bit.clone()) * power;
+                            power.double_in_place();
+                        }
+                        elem
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    /// Squeeze `num_challenges` short challenges of `num_bits` bits each, as
+    /// [`FieldBasedCryptographicSponge::squeeze_short_challenges`], together with their
+    /// little-endian bits, so that the bits can be reused (e.g. for scalar multiplications)
+    /// without going through nonnative arithmetic.
+    ///
+    /// Returns [`SynthesisError::Unsatisfiable`] if `num_bits` is greater than the capacity of
+    /// the field.
+    ///
+    /// [`FieldBasedCryptographicSponge::squeeze_short_challenges`]: crate::FieldBasedCryptographicSponge::squeeze_short_challenges
+    #[allow(clippy::type_complexity)]
+    fn squeeze_short_challenges(
+        &mut self,
+        num_challenges: usize,
+        num_bits: usize,
+    ) -> Result<(Vec<FpVar<CF>>, Vec<Vec<Boolean<CF>>>), SynthesisError> {
+        if num_bits > CF::Params::CAPACITY as usize {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let bits = self.squeeze_bits(num_bits * num_challenges)?;
+        let bits: Vec<Vec<_>> = bits.chunks(num_bits).map(|bits| bits.to_vec()).collect();
+        let challenges = bits
+            .iter()
+            .map(|bits| Boolean::le_bits_to_fp_var(bits))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((challenges, bits))
+    }
+
+    /// Squeeze `num_integers` integers uniformly distributed in `[0, bound)`, as
+    /// [`CryptographicSponge::squeeze_integers_in_range`].
+    ///
+    /// The reduction is proven by allocating the quotient and the remainder in binary, which
+    /// costs a boolean constraint per bit.
+    ///
+    /// Returns [`SynthesisError::Unsatisfiable`] if `bound` is zero, or if the squeezed integers
+    /// do not fit in `CF`.
+    fn squeeze_integers_in_range(
+        &mut self,
+        bound: u64,
+        num_integers: usize,
+    ) -> Result<Vec<FpVar<CF>>, SynthesisError> {
+        let num_bits = integer_in_range_num_bits(bound);
+        // `quotient * bound + remainder` is less than `2^(num_bits + 2)`, which must not wrap.
+        if bound == 0 || num_bits + 2 > CF::Params::CAPACITY as usize {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        let cs = self.cs();
+        let bits = self.squeeze_bits(num_bits * num_integers)?;
+        bits.chunks(num_bits)
+            .map(|bits| integer_in_range_var(&cs, bits, bound))
+            .collect()
+    }
+
+    /// Squeeze a permutation of `[0, n)`, as [`CryptographicSponge::squeeze_permutation`]. The
+    /// `i`-th output is the image of `i`.
+    ///
+    /// Each swap of the Fisher-Yates shuffle compares the squeezed position with every position
+    /// it may take and selects the swapped elements, so the gadget costs `O(n^2)` constraints in
+    /// addition to the reductions of [`CryptographicSpongeVar::squeeze_integers_in_range`].
+    ///
+    /// Returns [`SynthesisError::Unsatisfiable`] if the squeezed integers do not fit in `CF`.
+    fn squeeze_permutation(&mut self, n: usize) -> Result<Vec<FpVar<CF>>, SynthesisError> {
+        let mut permutation: Vec<FpVar<CF>> = (0..n)
+            .map(|i| FpVar::Constant(CF::from(i as u64)))
+            .collect();
+        if n <= 1 {
+            return Ok(permutation);
+        }
+        if integer_in_range_num_bits(n as u64) + 2 > CF::Params::CAPACITY as usize {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        let cs = self.cs();
+        let num_bits = (1..n)
+            .map(|i| integer_in_range_num_bits(i as u64 + 1))
+            .sum();
+        let bits = self.squeeze_bits(num_bits)?;
+        let mut offset = 0;
+        for i in (1..n).rev() {
+            let bound = i as u64 + 1;
+            let num_bits = integer_in_range_num_bits(bound);
+            let j = integer_in_range_var(&cs, &bits[offset..offset + num_bits], bound)?;
+            offset += num_bits;
+
+            // Swaps the elements at positions `i` and `j`.
+            let old_last = permutation[i].clone();
+            let mut new_last = FpVar::zero();
+            for (k, elem) in permutation[..=i].iter_mut().enumerate() {
+                let is_selected = j.is_eq(&FpVar::Constant(CF::from(k as u64)))?;
+                new_last += FpVar::from(is_selected.clone()) * &*elem;
+                if k < i {
+                    *elem = FpVar::conditionally_select(&is_selected, &old_last, elem)?;
+                }
+            }
+            permutation[i] = new_last;
+        }
+        Ok(permutation)
+    }
+
+    /// Squeeze a challenge `c` and return its first `n` powers `[1, c, c^2, ..., c^(n-1)]`, as
+    /// [`CryptographicSponge::squeeze_powers`], with `n - 2` multiplications.
+    fn squeeze_powers(&mut self, n: usize) -> Result<Vec<FpVar<CF>>, SynthesisError> {
+        self.absorb(&UInt8::constant_vec(&squeeze_powers_input(n)))?;
+        let challenge = self.squeeze_field_elements(1)?.remove(0);
+        let mut powers = Vec::with_capacity(n);
+        if n > 0 {
+            powers.push(FpVar::one());
+        }
+        if n > 1 {
+            powers.push(challenge.clone());
+        }
+        while powers.len() < n {
+            let power = powers.last().unwrap() * &challenge;
+            powers.push(power);
+        }
+        Ok(powers)
+    }
+
+    /// Squeeze `num_indices` distinct integers in `[0, range)`, as
+    /// [`CryptographicSponge::squeeze_distinct_indices`], together with their `k` little-endian
+    /// bits, where `k` is the bit length of `range - 1`, e.g. to select Merkle tree paths.
+    ///
+    /// Every candidate is checked against the range and compared with the previous ones, and
+    /// every output is selected among the candidates, which costs `O(num_candidates^2 +
+    /// num_candidates * num_indices)` constraints. The constraints are not satisfied if the
+    /// candidates contain fewer than `num_indices` distinct integers of the range, when the
+    /// native method fails.
+    ///
+    /// Returns [`SynthesisError::Unsatisfiable`] if `range` is zero, if `num_indices` is greater
+    /// than `range`, or if the candidates cannot contain `num_indices` distinct integers of the
+    /// range whatever their values, e.g. if `num_candidates` is less than `num_indices`.
+    #[allow(clippy::type_complexity)]
+    fn squeeze_distinct_indices(
+        &mut self,
+        range: u64,
+        num_indices: usize,
+        num_candidates: usize,
+    ) -> Result<(Vec<FpVar<CF>>, Vec<Vec<Boolean<CF>>>), SynthesisError> {
+        if range == 0 || num_indices as u64 > range {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let num_bits = (64 - (range - 1).leading_zeros()) as usize;
+
+        let cs = self.cs();
+        let mode = if cs.is_none() {
+            AllocationMode::Constant
+        } else {
+            AllocationMode::Witness
+        };
+        let bits = self.squeeze_bits(num_bits * num_candidates)?;
+
+        // The candidates, whether they are in the range and new, and the number of new
+        // candidates in the range before them.
+        let mut candidates: Vec<FpVar<CF>> = Vec::with_capacity(num_candidates);
+        let mut valid = Vec::with_capacity(num_candidates);
+        let mut counts = Vec::with_capacity(num_candidates);
+        let mut count = FpVar::zero();
+        for j in 0..num_candidates {
+            let bits = &bits[j * num_bits..(j + 1) * num_bits];
+            let mut candidate = FpVar::zero();
+            let mut power = CF::one();
+            for bit in bits {
+                candidate += FpVar::from(bit.clone()) * power;
+                power.double_in_place();
+            }
+
+            // Compares the candidate with `range` from the least significant bit, unless `range`
+            // is `2^num_bits` and all the candidates are in it.
+            let mut in_range = Boolean::TRUE;
+            if range >> num_bits == 0 {
+                in_range = Boolean::FALSE;
+                for (i, bit) in bits.iter().enumerate() {
+                    in_range = if (range >> i) & 1 == 1 {
+                        bit.not().or(&in_range)?
+                    } else {
+                        bit.not().and(&in_range)?
+                    };
+                }
+            }
+            let mut repeated = Boolean::FALSE;
+            for previous in &candidates {
+                repeated = repeated.or(&previous.is_eq(&candidate)?)?;
+            }
+
+            let is_valid = in_range.and(&repeated.not())?;
+            counts.push(count.clone());
+            count += FpVar::from(is_valid.clone());
+            valid.push(is_valid);
+            candidates.push(candidate);
+        }
+
+        let mut indices = Vec::with_capacity(num_indices);
+        let mut index_bits = Vec::with_capacity(num_indices);
+        for t in 0..num_indices {
+            let position = FpVar::Constant(CF::from(t as u64));
+            let mut index = FpVar::zero();
+            let mut num_selected = FpVar::zero();
+            for ((candidate, is_valid), count) in candidates.iter().zip(&valid).zip(&counts) {
+                let selected = is_valid.and(&count.is_eq(&position)?)?;
+                index += FpVar::from(selected.clone()) * candidate;
+                num_selected += FpVar::from(selected);
+            }
+            // Fails if there are not enough distinct integers in the range.
+            if num_selected.is_constant() && num_selected.value()? != CF::one() {
+                return Err(SynthesisError::Unsatisfiable);
+            }
+            num_selected.enforce_equal(&FpVar::one())?;
+
+            let value = index.value();
+            let bits = (0..num_bits)
+                .map(|i| {
+                    Boolean::new_variable(
+                        cs.clone(),
+                        || value.map(|v| v.into_repr().get_bit(i)),
+                        mode,
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Boolean::le_bits_to_fp_var(&bits)?.enforce_equal(&index)?;
+            indices.push(index);
+            index_bits.push(bits);
+        }
+        Ok((indices, index_bits))
+    }
+
+    /// Squeeze `num_elements` nonnative field elements from the sponge in the given
+    /// [`SqueezeMode`], as [`CryptographicSponge::squeeze_field_elements_with_mode`].
+    ///
+    /// In [`SqueezeMode::Uniform`], the squeezed bits are split into chunks of `CAPACITY` bits,
+    /// which are combined with nonnative arithmetic.
+    fn squeeze_nonnative_field_elements_with_mode<F: PrimeField>(
+        &mut self,
+        num_elements: usize,
+        mode: SqueezeMode,
+    ) -> Result<Vec<NonNativeFieldVar<F, CF>>, SynthesisError> {
+        match mode {
+            SqueezeMode::Truncated => Ok(self.squeeze_nonnative_field_elements(num_elements)?.0),
+            SqueezeMode::Uniform => {
+                let cs = self.cs();
+                let num_bits = F::Params::MODULUS_BITS as usize + UNIFORM_SQUEEZE_NUM_EXTRA_BITS;
+                let chunk_size = F::Params::CAPACITY as usize;
+                let bits = self.squeeze_bits(num_bits * num_elements)?;
+
+                let mut output = Vec::with_capacity(num_elements);
+                for bits in bits.chunks(num_bits) {
+                    let chunks = bits
+                        .chunks(chunk_size)
+                        .map(|chunk| chunk.to_vec())
+                        .collect::<Vec<_>>();
+                    let chunks = bits_le_to_nonnative::<F, CF>(
+                        cs.clone(),
+                        chunks.iter(),
+                        optimization_type(&cs),
+                    )?;
+
+                    let mut elem = NonNativeFieldVar::zero();
+                    let mut power = F::one();
+                    for chunk in chunks {
+                        elem += chunk * power;
+                        for _ in 0..chunk_size {
+                            power.double_in_place();
+                        }
+                    }
+                    output.push(elem);
+                }
+                Ok(output)
+            }
+        }
+    }
+}
+
+/// The gadget counterpart of [`SpongeExt`]: in addition to the operations of
+/// [`CryptographicSpongeVar`], the sponge can convert itself to a state, and instantiate itself
+/// from a state.
+///
+/// With the [`AllocVar`] implementations of the states, a circuit can continue a transcript
+/// whose state was computed natively, for instance by another component of a recursive proof.
+pub trait SpongeExtVar<CF: PrimeField, S: CryptographicSponge>:
+    CryptographicSpongeVar<CF, S>
+{
+    /// The full state of the sponge gadget.
+    type State: Clone;
+    /// Returns a sponge gadget that uses `state`.
+    fn from_state(
+        cs: ConstraintSystemRef<CF>,
+        state: Self::State,
+        params: &Self::Parameters,
+    ) -> Self;
+    /// Consumes `self` and returns the state.
+    fn into_state(self) -> Self::State;
+
+    /// Returns a sponge gadget that continues the transcript of `native`, whose state is
+    /// allocated with `mode`, so that a circuit does not need to absorb the preceding transcript
+    /// again. `params` must be the parameters of `native`.
+    ///
+    /// With [`AllocationMode::Constant`], the gadget permutes natively until a variable is
+    /// absorbed.
+    fn new_from_native(
+        cs: ConstraintSystemRef<CF>,
+        native: &S,
+        params: &Self::Parameters,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError>
+    where
+        S: SpongeExt,
+        Self::State: AllocVar<S::State, CF>,
+    {
+        let state =
+            Self::State::new_variable(cs.clone(), || Ok(native.clone().into_state()), mode)?;
+        Ok(Self::from_state(cs, state, params))
+    }
 }
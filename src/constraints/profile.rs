@@ -0,0 +1,386 @@
+use crate::constraints::{AbsorbGadget, CryptographicSpongeVar};
+use crate::{CryptographicSponge, FieldElementSize};
+use ark_ff::PrimeField;
+use ark_nonnative_field::NonNativeFieldVar;
+use ark_r1cs_std::bits::boolean::Boolean;
+use ark_r1cs_std::bits::uint8::UInt8;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use ark_std::fmt;
+use ark_std::marker::PhantomData;
+use ark_std::vec::Vec;
+
+/// An operation of a sponge gadget recorded by [`ProfiledSpongeVar`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SpongeOperation {
+    /// An absorb of `num_elements` native field elements.
+    Absorb {
+        /// The number of native field elements of the input.
+        num_elements: usize,
+    },
+    /// A squeeze of `num_bytes` bytes.
+    SqueezeBytes {
+        /// The number of bytes squeezed.
+        num_bytes: usize,
+    },
+    /// A squeeze of `num_bits` bits.
+    SqueezeBits {
+        /// The number of bits squeezed.
+        num_bits: usize,
+    },
+    /// A squeeze of `num_elements` native field elements.
+    SqueezeFieldElements {
+        /// The number of field elements squeezed.
+        num_elements: usize,
+    },
+    /// A squeeze of `num_elements` nonnative field elements.
+    SqueezeNonnativeFieldElements {
+        /// The number of field elements squeezed.
+        num_elements: usize,
+    },
+    /// A squeeze of `num_challenges` short challenges.
+    SqueezeShortChallenges {
+        /// The number of challenges squeezed.
+        num_challenges: usize,
+    },
+    /// A squeeze of `num_integers` integers in a range.
+    SqueezeIntegersInRange {
+        /// The number of integers squeezed.
+        num_integers: usize,
+    },
+    /// A squeeze of `num_indices` distinct integers in a range.
+    SqueezeDistinctIndices {
+        /// The number of integers squeezed.
+        num_indices: usize,
+    },
+    /// A squeeze of a permutation of `[0, n)`.
+    SqueezePermutation {
+        /// The number of elements permuted.
+        n: usize,
+    },
+    /// A squeeze of the first `n` powers of a challenge.
+    SqueezePowers {
+        /// The number of powers.
+        n: usize,
+    },
+}
+
+/// The constraints and witness variables added by one operation of a sponge gadget.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OperationCost {
+    /// The operation.
+    pub operation: SpongeOperation,
+    /// The number of constraints added by the operation.
+    pub num_constraints: usize,
+    /// The number of witness variables added by the operation.
+    pub num_witness_variables: usize,
+}
+
+/// The report of a [`ProfiledSpongeVar`]: the cost of each of its operations, in order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConstraintProfile {
+    /// The costs of the operations, in the order they were made.
+    pub operations: Vec<OperationCost>,
+}
+
+impl ConstraintProfile {
+    /// The number of constraints added by all the operations.
+    pub fn num_constraints(&self) -> usize {
+        self.operations.iter().map(|op| op.num_constraints).sum()
+    }
+
+    /// The number of witness variables added by all the operations.
+    pub fn num_witness_variables(&self) -> usize {
+        self.operations
+            .iter()
+            .map(|op| op.num_witness_variables)
+            .sum()
+    }
+}
+
+/// One line per operation, followed by the totals.
+impl fmt::Display for ConstraintProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, op) in self.operations.iter().enumerate() {
+            writeln!(
+                f,
+                "{}: {:?}: {} constraints, {} witness variables",
+                i, op.operation, op.num_constraints, op.num_witness_variables
+            )?;
+        }
+        write!(
+            f,
+            "total: {} constraints, {} witness variables",
+            self.num_constraints(),
+            self.num_witness_variables()
+        )
+    }
+}
+
+/// A sponge gadget that records the constraints and witness variables added by each operation of
+/// the gadget `SV`, for budgeting the size of a circuit.
+///
+/// It is a gadget for the same native sponge `S` as `SV`, with the same outputs, so it can be
+/// used in place of `SV` in code generic over the gadget. The costs are read from the constraint
+/// system before and after each operation. The absorbs, the squeezes of bytes, bits and field
+/// elements, and the squeezes of nonnative elements, short challenges, integers in a range and
+/// distinct indices are recorded as one operation each; the other provided methods are recorded
+/// as the operations they are made of.
+///
+/// Absorbed inputs are converted to native field elements once, and the field elements are
+/// absorbed by `SV`, as all the field-based gadgets do.
+pub struct ProfiledSpongeVar<
+    CF: PrimeField,
+    S: CryptographicSponge,
+    SV: CryptographicSpongeVar<CF, S>,
+> {
+    sponge: SV,
+    profile: ConstraintProfile,
+    _sponge: PhantomData<(CF, S)>,
+}
+
+impl<CF: PrimeField, S: CryptographicSponge, SV: CryptographicSpongeVar<CF, S>>
+    ProfiledSpongeVar<CF, S, SV>
+{
+    /// Profiles the operations made on `sponge` from now on.
+    pub fn from_sponge(sponge: SV) -> Self {
+        Self {
+            sponge,
+            profile: ConstraintProfile::default(),
+            _sponge: PhantomData,
+        }
+    }
+
+    /// The costs of the operations made so far.
+    pub fn profile(&self) -> &ConstraintProfile {
+        &self.profile
+    }
+
+    /// Returns the profiled sponge and the costs of the operations made on it.
+    pub fn into_parts(self) -> (SV, ConstraintProfile) {
+        (self.sponge, self.profile)
+    }
+
+    fn record<T>(
+        &mut self,
+        operation: SpongeOperation,
+        f: impl FnOnce(&mut SV) -> Result<T, SynthesisError>,
+    ) -> Result<T, SynthesisError> {
+        let cs = self.sponge.cs();
+        let num_constraints = cs.num_constraints();
+        let num_witness_variables = cs.num_witness_variables();
+        let output = f(&mut self.sponge)?;
+        self.profile.operations.push(OperationCost {
+            operation,
+            num_constraints: cs.num_constraints() - num_constraints,
+            num_witness_variables: cs.num_witness_variables() - num_witness_variables,
+        });
+        Ok(output)
+    }
+}
+
+impl<CF: PrimeField, S: CryptographicSponge, SV: CryptographicSpongeVar<CF, S>> Clone
+    for ProfiledSpongeVar<CF, S, SV>
+{
+    fn clone(&self) -> Self {
+        Self {
+            sponge: self.sponge.clone(),
+            profile: self.profile.clone(),
+            _sponge: PhantomData,
+        }
+    }
+}
+
+impl<CF: PrimeField, S: CryptographicSponge, SV: CryptographicSpongeVar<CF, S>>
+    CryptographicSpongeVar<CF, S> for ProfiledSpongeVar<CF, S, SV>
+{
+    type Parameters = SV::Parameters;
+
+    fn new(cs: ConstraintSystemRef<CF>, params: &Self::Parameters) -> Self {
+        Self::from_sponge(SV::new(cs, params))
+    }
+
+    fn rate(&self) -> usize {
+        self.sponge.rate()
+    }
+
+    fn capacity(&self) -> usize {
+        self.sponge.capacity()
+    }
+
+    fn security_level(&self) -> usize {
+        self.sponge.security_level()
+    }
+
+    fn cs(&self) -> ConstraintSystemRef<CF> {
+        self.sponge.cs()
+    }
+
+    fn absorb(&mut self, input: &impl AbsorbGadget<CF>) -> Result<(), SynthesisError> {
+        let cs = self.sponge.cs();
+        let num_constraints = cs.num_constraints();
+        let num_witness_variables = cs.num_witness_variables();
+        let elems = input.to_sponge_field_elements()?;
+        self.sponge.absorb(&elems)?;
+        self.profile.operations.push(OperationCost {
+            operation: SpongeOperation::Absorb {
+                num_elements: elems.len(),
+            },
+            num_constraints: cs.num_constraints() - num_constraints,
+            num_witness_variables: cs.num_witness_variables() - num_witness_variables,
+        });
+        Ok(())
+    }
+
+    fn squeeze_bytes(&mut self, num_bytes: usize) -> Result<Vec<UInt8<CF>>, SynthesisError> {
+        self.record(SpongeOperation::SqueezeBytes { num_bytes }, |sponge| {
+            sponge.squeeze_bytes(num_bytes)
+        })
+    }
+
+    fn squeeze_bits(&mut self, num_bits: usize) -> Result<Vec<Boolean<CF>>, SynthesisError> {
+        self.record(SpongeOperation::SqueezeBits { num_bits }, |sponge| {
+            sponge.squeeze_bits(num_bits)
+        })
+    }
+
+    fn squeeze_field_elements(
+        &mut self,
+        num_elements: usize,
+    ) -> Result<Vec<FpVar<CF>>, SynthesisError> {
+        self.record(
+            SpongeOperation::SqueezeFieldElements { num_elements },
+            |sponge| sponge.squeeze_field_elements(num_elements),
+        )
+    }
+
+    fn squeeze_nonnative_field_elements_with_sizes<F: PrimeField>(
+        &mut self,
+        sizes: &[FieldElementSize],
+    ) -> Result<(Vec<NonNativeFieldVar<F, CF>>, Vec<Vec<Boolean<CF>>>), SynthesisError> {
+        self.record(
+            SpongeOperation::SqueezeNonnativeFieldElements {
+                num_elements: sizes.len(),
+            },
+            |sponge| sponge.squeeze_nonnative_field_elements_with_sizes(sizes),
+        )
+    }
+
+    fn squeeze_short_challenges(
+        &mut self,
+        num_challenges: usize,
+        num_bits: usize,
+    ) -> Result<(Vec<FpVar<CF>>, Vec<Vec<Boolean<CF>>>), SynthesisError> {
+        self.record(
+            SpongeOperation::SqueezeShortChallenges { num_challenges },
+            |sponge| sponge.squeeze_short_challenges(num_challenges, num_bits),
+        )
+    }
+
+    fn squeeze_integers_in_range(
+        &mut self,
+        bound: u64,
+        num_integers: usize,
+    ) -> Result<Vec<FpVar<CF>>, SynthesisError> {
+        self.record(
+            SpongeOperation::SqueezeIntegersInRange { num_integers },
+            |sponge| sponge.squeeze_integers_in_range(bound, num_integers),
+        )
+    }
+
+    fn squeeze_distinct_indices(
+        &mut self,
+        range: u64,
+        num_indices: usize,
+        num_candidates: usize,
+    ) -> Result<(Vec<FpVar<CF>>, Vec<Vec<Boolean<CF>>>), SynthesisError> {
+        self.record(
+            SpongeOperation::SqueezeDistinctIndices { num_indices },
+            |sponge| sponge.squeeze_distinct_indices(range, num_indices, num_candidates),
+        )
+    }
+
+    fn squeeze_permutation(&mut self, n: usize) -> Result<Vec<FpVar<CF>>, SynthesisError> {
+        self.record(SpongeOperation::SqueezePermutation { n }, |sponge| {
+            sponge.squeeze_permutation(n)
+        })
+    }
+
+    fn squeeze_powers(&mut self, n: usize) -> Result<Vec<FpVar<CF>>, SynthesisError> {
+        self.record(SpongeOperation::SqueezePowers { n }, |sponge| {
+            sponge.squeeze_powers(n)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constraints::{
+        CryptographicSpongeVar, OperationCost, ProfiledSpongeVar, SpongeOperation,
+    };
+    use crate::poseidon::constraints::PoseidonSpongeVar;
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::PoseidonSponge;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::R1CSVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::vec;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn profile_operations() {
+        let params = poseidon_parameters_for_test::<Fr>();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let input = vec![
+            FpVar::new_witness(cs.clone(), || Ok(Fr::from(1u64))).unwrap(),
+            FpVar::new_witness(cs.clone(), || Ok(Fr::from(2u64))).unwrap(),
+        ];
+        let num_constraints = cs.num_constraints();
+
+        let mut sponge = PoseidonSpongeVar::new(cs.clone(), &params);
+        let mut profiled =
+            ProfiledSpongeVar::<_, PoseidonSponge<Fr>, _>::from_sponge(sponge.clone());
+        profiled.absorb(&input).unwrap();
+        sponge.absorb(&input).unwrap();
+        assert_eq!(
+            profiled.squeeze_field_elements(1).unwrap().value().unwrap(),
+            sponge.squeeze_field_elements(1).unwrap().value().unwrap()
+        );
+        profiled.squeeze_bits(8).unwrap();
+
+        let (_, profile) = profiled.into_parts();
+        let operations: Vec<_> = profile
+            .operations
+            .iter()
+            .map(|op| op.operation.clone())
+            .collect();
+        assert_eq!(
+            operations,
+            vec![
+                SpongeOperation::Absorb { num_elements: 2 },
+                SpongeOperation::SqueezeFieldElements { num_elements: 1 },
+                SpongeOperation::SqueezeBits { num_bits: 8 },
+            ]
+        );
+        // absorbing only adds to the state, and the squeeze permutes
+        assert_eq!(
+            profile.operations[0],
+            OperationCost {
+                operation: SpongeOperation::Absorb { num_elements: 2 },
+                num_constraints: 0,
+                num_witness_variables: 0,
+            }
+        );
+        assert!(profile.operations[1].num_constraints > 0);
+        assert!(profile.operations[2].num_constraints > 0);
+        // the unprofiled sponge made the same operations, except for the bits
+        let profiled_constraints = profile.num_constraints();
+        let sponge_constraints = profile.operations[1].num_constraints;
+        assert_eq!(
+            cs.num_constraints() - num_constraints,
+            profiled_constraints + sponge_constraints
+        );
+    }
+}
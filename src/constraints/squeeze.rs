@@ -0,0 +1,66 @@
+use crate::constraints::CryptographicSpongeVar;
+use crate::CryptographicSponge;
+use ark_ff::PrimeField;
+use ark_r1cs_std::bits::boolean::Boolean;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_relations::r1cs::SynthesisError;
+use ark_std::vec::Vec;
+
+/// Derives [`SqueezeFromSpongeGadget`] for a struct of gadgets by squeezing its fields in
+/// declaration order. Fields marked with `#[sponge(skip)]` are not squeezed and take their
+/// default value.
+#[cfg(feature = "derive")]
+pub use ark_sponge_derive::SqueezeFromSpongeGadget;
+
+/// An interface for gadgets that can be squeezed from a `CryptographicSpongeVar` whose
+/// constraint field is `CF`, as their native types are squeezed with
+/// [`SqueezeFromSponge`](crate::SqueezeFromSponge).
+pub trait SqueezeFromSpongeGadget<CF: PrimeField>: Sized {
+    /// What is needed to squeeze a value besides the sponge, as
+    /// [`SqueezeFromSponge::Shape`](crate::SqueezeFromSponge::Shape).
+    type Shape;
+
+    /// Squeezes a value of shape `shape` from `sponge`.
+    fn squeeze_from_sponge<S: CryptographicSponge, SV: CryptographicSpongeVar<CF, S>>(
+        sponge: &mut SV,
+        shape: &Self::Shape,
+    ) -> Result<Self, SynthesisError>;
+}
+
+impl<CF: PrimeField> SqueezeFromSpongeGadget<CF> for FpVar<CF> {
+    type Shape = ();
+
+    fn squeeze_from_sponge<S: CryptographicSponge, SV: CryptographicSpongeVar<CF, S>>(
+        sponge: &mut SV,
+        _shape: &(),
+    ) -> Result<Self, SynthesisError> {
+        Ok(sponge.squeeze_field_elements(1)?.remove(0))
+    }
+}
+
+impl<CF: PrimeField> SqueezeFromSpongeGadget<CF> for Boolean<CF> {
+    type Shape = ();
+
+    fn squeeze_from_sponge<S: CryptographicSponge, SV: CryptographicSpongeVar<CF, S>>(
+        sponge: &mut SV,
+        _shape: &(),
+    ) -> Result<Self, SynthesisError> {
+        Ok(sponge.squeeze_bits(1)?.remove(0))
+    }
+}
+
+/// A vector of `shape` values, squeezed one after the other.
+impl<CF: PrimeField, T: SqueezeFromSpongeGadget<CF, Shape = ()>> SqueezeFromSpongeGadget<CF>
+    for Vec<T>
+{
+    type Shape = usize;
+
+    fn squeeze_from_sponge<S: CryptographicSponge, SV: CryptographicSpongeVar<CF, S>>(
+        sponge: &mut SV,
+        shape: &usize,
+    ) -> Result<Self, SynthesisError> {
+        (0..*shape)
+            .map(|_| T::squeeze_from_sponge(sponge, &()))
+            .collect()
+    }
+}
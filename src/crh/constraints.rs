@@ -0,0 +1,146 @@
+use crate::constraints::CryptographicSpongeVar;
+use crate::crh::{encoded_field_element_size, PoseidonCRH, PoseidonTwoToOneCRH};
+use crate::poseidon::constraints::PoseidonSpongeVar;
+use crate::poseidon::PoseidonParameters;
+use crate::Absorb;
+use ark_crypto_primitives::crh::{CRHGadget, TwoToOneCRHGadget};
+use ark_ff::{FpParameters, PrimeField};
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{Namespace, SynthesisError};
+use ark_std::borrow::Borrow;
+use ark_std::marker::PhantomData;
+use ark_std::vec::Vec;
+
+/// The parameters of the Poseidon CRH gadgets, which are constants of the circuit.
+#[derive(Clone)]
+pub struct CRHParametersVar<F: PrimeField> {
+    /// The parameters of the sponge.
+    pub parameters: PoseidonParameters<F>,
+}
+
+impl<F: PrimeField> AllocVar<PoseidonParameters<F>, F> for CRHParametersVar<F> {
+    fn new_variable<T: Borrow<PoseidonParameters<F>>>(
+        _cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        _mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        f().map(|parameters| Self {
+            parameters: parameters.borrow().clone(),
+        })
+    }
+}
+
+/// The gadget for [`PoseidonCRH`].
+pub struct PoseidonCRHGadget<F: PrimeField + Absorb> {
+    _field: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> CRHGadget<PoseidonCRH<F>, F> for PoseidonCRHGadget<F> {
+    type OutputVar = FpVar<F>;
+    type ParametersVar = CRHParametersVar<F>;
+
+    #[tracing::instrument(target = "r1cs", skip(parameters, input))]
+    fn evaluate(
+        parameters: &Self::ParametersVar,
+        input: &[UInt8<F>],
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        let mut sponge = PoseidonSpongeVar::new(input.cs(), &parameters.parameters);
+        sponge.absorb(&input)?;
+        Ok(sponge.squeeze_field_elements(1)?.remove(0))
+    }
+}
+
+/// The gadget for [`PoseidonTwoToOneCRH`].
+///
+/// Each input is the little-endian byte encoding of a field element, as output by
+/// [`ToBytesGadget`], and is enforced to be canonical.
+pub struct PoseidonTwoToOneCRHGadget<F: PrimeField + Absorb> {
+    _field: PhantomData<F>,
+}
+
+/// Returns the field element encoded by `bytes`, enforcing that the encoding is canonical.
+fn field_element_from_bytes_var<F: PrimeField>(
+    bytes: &[UInt8<F>],
+) -> Result<FpVar<F>, SynthesisError> {
+    if bytes.len() * 8 < F::Params::MODULUS_BITS as usize
+        || bytes.len() > encoded_field_element_size::<F>()
+    {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    let bits = bytes
+        .iter()
+        .map(|byte| byte.to_bits_le())
+        .collect::<Result<Vec<_>, _>>()?
+        .concat();
+    Boolean::le_bits_to_fp_var(&bits)
+}
+
+impl<F: PrimeField + Absorb> TwoToOneCRHGadget<PoseidonTwoToOneCRH<F>, F>
+    for PoseidonTwoToOneCRHGadget<F>
+{
+    type OutputVar = FpVar<F>;
+    type ParametersVar = CRHParametersVar<F>;
+
+    #[tracing::instrument(target = "r1cs", skip(parameters, left_input, right_input))]
+    fn evaluate(
+        parameters: &Self::ParametersVar,
+        left_input: &[UInt8<F>],
+        right_input: &[UInt8<F>],
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        let left = field_element_from_bytes_var(left_input)?;
+        let right = field_element_from_bytes_var(right_input)?;
+        let cs = left.cs().or(right.cs());
+        let mut sponge = PoseidonSpongeVar::new(cs, &parameters.parameters);
+        sponge.absorb(&[left, right])?;
+        Ok(sponge.squeeze_field_elements(1)?.remove(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crh::constraints::{PoseidonCRHGadget, PoseidonTwoToOneCRHGadget};
+    use crate::crh::{PoseidonCRH, PoseidonTwoToOneCRH};
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use ark_crypto_primitives::crh::{CRHGadget, TwoToOneCRH, TwoToOneCRHGadget, CRH};
+    use ark_ff::to_bytes;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn test_crh_gadget() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let params = poseidon_parameters_for_test();
+        let params_var = <PoseidonCRHGadget<Fr> as CRHGadget<_, Fr>>::ParametersVar::new_constant(
+            cs.clone(),
+            &params,
+        )
+        .unwrap();
+
+        let input = [1u8, 2, 3];
+        let input_var = UInt8::new_witness_vec(cs.clone(), &input).unwrap();
+        let hash = PoseidonCRH::<Fr>::evaluate(&params, &input).unwrap();
+        let hash_var = PoseidonCRHGadget::evaluate(&params_var, &input_var).unwrap();
+        assert_eq!(hash_var.value().unwrap(), hash);
+
+        let (left, right) = (Fr::from(1u64), Fr::from(2u64));
+        let hash = PoseidonTwoToOneCRH::<Fr>::evaluate(
+            &params,
+            &to_bytes!(left).unwrap(),
+            &to_bytes!(right).unwrap(),
+        )
+        .unwrap();
+        let left_var = FpVar::new_witness(cs.clone(), || Ok(left)).unwrap();
+        let right_var = FpVar::new_witness(cs.clone(), || Ok(right)).unwrap();
+        let hash_var = PoseidonTwoToOneCRHGadget::evaluate(
+            &params_var,
+            &left_var.to_bytes().unwrap(),
+            &right_var.to_bytes().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(hash_var.value().unwrap(), hash);
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
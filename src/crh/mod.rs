@@ -0,0 +1,131 @@
+use crate::poseidon::{PoseidonParameters, PoseidonSponge};
+use crate::{Absorb, CryptographicSponge, FieldBasedCryptographicSponge, SpongeError};
+use ark_crypto_primitives::crh::{TwoToOneCRH, CRH};
+use ark_crypto_primitives::Error;
+use ark_ff::{BigInteger, FpParameters, PrimeField};
+use ark_std::marker::PhantomData;
+use ark_std::rand::Rng;
+
+/// constraints for the Poseidon CRHs
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+/// The [`CRH`] of `ark-crypto-primitives` over [`PoseidonSponge`].
+///
+/// The input bytes are absorbed as a byte string, which is prefixed with its length, and the
+/// output is the first squeezed field element. Inputs may have any length, so `INPUT_SIZE_BITS`
+/// is zero. The parameters are [`PoseidonParameters`], and `setup` returns the default
+/// parameters.
+pub struct PoseidonCRH<F: PrimeField + Absorb> {
+    _field: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> CRH for PoseidonCRH<F> {
+    const INPUT_SIZE_BITS: usize = 0;
+
+    type Output = F;
+    type Parameters = PoseidonParameters<F>;
+
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(PoseidonParameters::default())
+    }
+
+    fn evaluate(parameters: &Self::Parameters, input: &[u8]) -> Result<Self::Output, Error> {
+        let mut sponge = PoseidonSponge::new(parameters);
+        sponge.absorb(&input);
+        Ok(sponge.squeeze_native_field_elements(1)[0])
+    }
+}
+
+/// The [`TwoToOneCRH`] of `ark-crypto-primitives` over [`PoseidonSponge`], which compresses two
+/// field elements, for instance two nodes of a Merkle tree.
+///
+/// Each input must be the canonical byte encoding of a field element, as written by
+/// [`ToBytes`](ark_ff::ToBytes); the two elements are absorbed and the output is the first
+/// squeezed field element.
+pub struct PoseidonTwoToOneCRH<F: PrimeField + Absorb> {
+    _field: PhantomData<F>,
+}
+
+/// Reads the field element encoded by `bytes`, which must be canonical.
+pub(crate) fn field_element_from_bytes<F: PrimeField>(bytes: &[u8]) -> Result<F, Error> {
+    let expected = encoded_field_element_size::<F>();
+    if bytes.len() != expected {
+        return Err(SpongeError::InvalidInputLength {
+            expected,
+            found: bytes.len(),
+        }
+        .into());
+    }
+    Ok(F::read(bytes)?)
+}
+
+/// The size of the byte encoding of an element of `F`.
+pub(crate) fn encoded_field_element_size<F: PrimeField>() -> usize {
+    F::zero().into_repr().to_bytes_le().len()
+}
+
+impl<F: PrimeField + Absorb> TwoToOneCRH for PoseidonTwoToOneCRH<F> {
+    const LEFT_INPUT_SIZE_BITS: usize = F::Params::MODULUS_BITS as usize;
+    const RIGHT_INPUT_SIZE_BITS: usize = F::Params::MODULUS_BITS as usize;
+
+    type Output = F;
+    type Parameters = PoseidonParameters<F>;
+
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(PoseidonParameters::default())
+    }
+
+    fn evaluate(
+        parameters: &Self::Parameters,
+        left_input: &[u8],
+        right_input: &[u8],
+    ) -> Result<Self::Output, Error> {
+        let left = field_element_from_bytes::<F>(left_input)?;
+        let right = field_element_from_bytes::<F>(right_input)?;
+        let mut sponge = PoseidonSponge::new(parameters);
+        sponge.absorb(&[left, right].as_ref());
+        Ok(sponge.squeeze_native_field_elements(1)[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crh::{PoseidonCRH, PoseidonTwoToOneCRH};
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use ark_crypto_primitives::crh::{TwoToOneCRH, CRH};
+    use ark_ff::{to_bytes, Zero};
+    use ark_std::test_rng;
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn test_crh() {
+        let params = poseidon_parameters_for_test();
+        let hash = PoseidonCRH::<Fr>::evaluate(&params, &[1, 2, 3]).unwrap();
+        assert_eq!(hash, PoseidonCRH::evaluate(&params, &[1, 2, 3]).unwrap());
+        // The input is framed with its length.
+        assert_ne!(hash, PoseidonCRH::evaluate(&params, &[1, 2, 3, 0]).unwrap());
+        assert!(PoseidonCRH::<Fr>::setup(&mut test_rng()).is_ok());
+    }
+
+    #[test]
+    fn test_two_to_one_crh() {
+        let params = poseidon_parameters_for_test();
+        let (left, right) = (Fr::from(1u64), Fr::from(2u64));
+        let left_bytes = to_bytes!(left).unwrap();
+        let right_bytes = to_bytes!(right).unwrap();
+
+        let hash = PoseidonTwoToOneCRH::<Fr>::evaluate(&params, &left_bytes, &right_bytes).unwrap();
+        assert_ne!(
+            hash,
+            PoseidonTwoToOneCRH::evaluate(&params, &right_bytes, &left_bytes).unwrap()
+        );
+        assert!(!hash.is_zero());
+
+        // Inputs must be canonical encodings.
+        assert!(
+            PoseidonTwoToOneCRH::<Fr>::evaluate(&params, &left_bytes[1..], &right_bytes).is_err()
+        );
+        assert!(PoseidonTwoToOneCRH::<Fr>::evaluate(&params, &[0xff; 32], &right_bytes).is_err());
+    }
+}
@@ -0,0 +1,216 @@
+use crate::constraints::{
+    bits_le_to_nonnative, optimization_type, CryptographicSpongeVar, SpongeWithGadget,
+};
+use crate::cycle::{CycleChallenge, CYCLE_CHALLENGE_BITS};
+use ark_ec::SWModelParameters;
+use ark_ff::PrimeField;
+use ark_nonnative_field::NonNativeFieldVar;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::groups::curves::short_weierstrass::ProjectiveVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::SynthesisError;
+use ark_std::marker::PhantomData;
+use ark_std::vec::Vec;
+
+/// The gadget for [`CycleChallenge`].
+#[derive(Clone)]
+pub struct CycleChallengeVar<CF: PrimeField> {
+    bits_le: Vec<Boolean<CF>>,
+}
+
+impl<CF: PrimeField> CycleChallengeVar<CF> {
+    /// The little-endian bits of the challenge, e.g. for
+    /// [`CurveVar::scalar_mul_le`].
+    pub fn bits_le(&self) -> &[Boolean<CF>] {
+        &self.bits_le
+    }
+
+    /// The challenge as a native element, i.e., as a scalar of the other curve.
+    pub fn to_native(&self) -> Result<FpVar<CF>, SynthesisError> {
+        Boolean::le_bits_to_fp_var(&self.bits_le)
+    }
+
+    /// The challenge as an element of `F`, e.g. as a scalar of the curve of the sponge.
+    pub fn to_nonnative<F: PrimeField>(&self) -> Result<NonNativeFieldVar<F, CF>, SynthesisError> {
+        let cs = self.bits_le.cs();
+        let mut elems = bits_le_to_nonnative(cs.clone(), [&self.bits_le], optimization_type(&cs))?;
+        Ok(elems.remove(0))
+    }
+
+    /// The value of the challenge.
+    pub fn value(&self) -> Result<CycleChallenge, SynthesisError> {
+        Ok(CycleChallenge {
+            bits_le: self.bits_le.value()?,
+        })
+    }
+}
+
+/// The gadget for [`CycleSponge`](crate::cycle::CycleSponge), which absorbs and squeezes with
+/// the same encodings.
+pub struct CycleSpongeVar<P, S>
+where
+    P: SWModelParameters,
+    P::BaseField: PrimeField,
+    S: SpongeWithGadget<P::BaseField>,
+{
+    sponge: S::Var,
+    _curve: PhantomData<P>,
+}
+
+impl<P, S> Clone for CycleSpongeVar<P, S>
+where
+    P: SWModelParameters,
+    P::BaseField: PrimeField,
+    S: SpongeWithGadget<P::BaseField>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            sponge: self.sponge.clone(),
+            _curve: PhantomData,
+        }
+    }
+}
+
+impl<P, S> CycleSpongeVar<P, S>
+where
+    P: SWModelParameters,
+    P::BaseField: PrimeField,
+    P::ScalarField: PrimeField,
+    S: SpongeWithGadget<P::BaseField>,
+{
+    /// Wraps a sponge gadget over the base field of `P`.
+    pub fn new(sponge: S::Var) -> Self {
+        Self {
+            sponge,
+            _curve: PhantomData,
+        }
+    }
+
+    /// Returns the wrapped sponge gadget.
+    pub fn into_inner(self) -> S::Var {
+        self.sponge
+    }
+
+    /// Absorbs points of `P`, as
+    /// [`CycleSponge::absorb_points`](crate::cycle::CycleSponge::absorb_points).
+    pub fn absorb_points(
+        &mut self,
+        points: &[ProjectiveVar<P, FpVar<P::BaseField>>],
+    ) -> Result<(), SynthesisError> {
+        self.sponge.absorb(&points)
+    }
+
+    /// Absorbs scalars of the other curve, as
+    /// [`CycleSponge::absorb_other_scalars`](crate::cycle::CycleSponge::absorb_other_scalars).
+    pub fn absorb_other_scalars(
+        &mut self,
+        scalars: &[FpVar<P::BaseField>],
+    ) -> Result<(), SynthesisError> {
+        self.sponge.absorb(&scalars)
+    }
+
+    /// Absorbs scalars of `P`, as
+    /// [`CycleSponge::absorb_scalars`](crate::cycle::CycleSponge::absorb_scalars).
+    pub fn absorb_scalars(
+        &mut self,
+        scalars: &[NonNativeFieldVar<P::ScalarField, P::BaseField>],
+    ) -> Result<(), SynthesisError> {
+        self.sponge.absorb_nonnative(scalars)
+    }
+
+    /// Squeezes `num_challenges` challenges of `num_bits` bits each, as
+    /// [`CycleSponge::squeeze_challenges`](crate::cycle::CycleSponge::squeeze_challenges).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_bits` is not smaller than the moduli of both fields.
+    pub fn squeeze_challenges(
+        &mut self,
+        num_challenges: usize,
+        num_bits: usize,
+    ) -> Result<Vec<CycleChallengeVar<P::BaseField>>, SynthesisError> {
+        assert!(
+            num_bits < P::BaseField::size_in_bits() && num_bits < P::ScalarField::size_in_bits(),
+            "the challenges do not fit in both fields"
+        );
+        let bits = self.sponge.squeeze_bits(num_challenges * num_bits)?;
+        Ok(bits
+            .chunks(num_bits.max(1))
+            .take(num_challenges)
+            .map(|bits_le| CycleChallengeVar {
+                bits_le: bits_le[..num_bits].to_vec(),
+            })
+            .collect())
+    }
+
+    /// Absorbs `points` and squeezes `num_challenges` challenges, as
+    /// [`CycleSponge::absorb_points_and_squeeze_challenges`](crate::cycle::CycleSponge::absorb_points_and_squeeze_challenges).
+    pub fn absorb_points_and_squeeze_challenges(
+        &mut self,
+        points: &[ProjectiveVar<P, FpVar<P::BaseField>>],
+        num_challenges: usize,
+    ) -> Result<Vec<CycleChallengeVar<P::BaseField>>, SynthesisError> {
+        self.absorb_points(points)?;
+        self.squeeze_challenges(num_challenges, CYCLE_CHALLENGE_BITS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constraints::CryptographicSpongeVar;
+    use crate::cycle::constraints::CycleSpongeVar;
+    use crate::cycle::CycleSponge;
+    use crate::poseidon::constraints::PoseidonSpongeVar;
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::PoseidonSponge;
+    use crate::CryptographicSponge;
+    use ark_ec::ProjectiveCurve;
+    use ark_nonnative_field::NonNativeFieldVar;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::groups::curves::short_weierstrass::ProjectiveVar;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::{test_rng, UniformRand};
+    use ark_test_curves::bls12_381::{g1::Parameters, Fq, Fr, G1Projective};
+
+    #[test]
+    fn matches_native() {
+        let mut rng = test_rng();
+        let params = poseidon_parameters_for_test::<Fq>();
+        let point = G1Projective::rand(&mut rng);
+        let scalar = Fr::rand(&mut rng);
+        let other_scalar = Fq::rand(&mut rng);
+
+        let mut native = CycleSponge::<Parameters, _>::new(PoseidonSponge::new(&params));
+        native.absorb_scalars(&[scalar]);
+        native.absorb_other_scalars(&[other_scalar]);
+        let challenges = native.absorb_points_and_squeeze_challenges(&[point.into_affine()], 2);
+
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let point_var =
+            ProjectiveVar::<Parameters, FpVar<Fq>>::new_witness(cs.clone(), || Ok(point)).unwrap();
+        let scalar_var = NonNativeFieldVar::new_witness(cs.clone(), || Ok(scalar)).unwrap();
+        let other_scalar_var = FpVar::new_witness(cs.clone(), || Ok(other_scalar)).unwrap();
+        let mut gadget = CycleSpongeVar::<Parameters, PoseidonSponge<Fq>>::new(
+            PoseidonSpongeVar::new(cs.clone(), &params),
+        );
+        gadget.absorb_scalars(&[scalar_var]).unwrap();
+        gadget.absorb_other_scalars(&[other_scalar_var]).unwrap();
+        let challenge_vars = gadget
+            .absorb_points_and_squeeze_challenges(&[point_var], 2)
+            .unwrap();
+
+        for (challenge, var) in challenges.iter().zip(&challenge_vars) {
+            assert_eq!(&var.value().unwrap(), challenge);
+            assert_eq!(
+                var.to_native().unwrap().value().unwrap(),
+                challenge.to_field::<Fq>()
+            );
+            assert_eq!(
+                var.to_nonnative::<Fr>().unwrap().value().unwrap(),
+                challenge.to_field::<Fr>()
+            );
+        }
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
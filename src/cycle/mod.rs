@@ -0,0 +1,212 @@
+use crate::{Absorb, FieldBasedCryptographicSponge};
+use ark_ec::models::short_weierstrass_jacobian::GroupAffine as SWAffine;
+use ark_ec::SWModelParameters;
+use ark_ff::{BigInteger, FpParameters, PrimeField};
+use ark_std::marker::PhantomData;
+use ark_std::vec::Vec;
+
+/// constraints for the sponges of a cycle of curves
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+/// The default size of the challenges of [`CycleSponge::squeeze_challenges`], which is small
+/// enough for the challenges to be scalars of both curves of any cycle of 255-bit curves.
+pub const CYCLE_CHALLENGE_BITS: usize = 128;
+
+/// A challenge squeezed by a [`CycleSponge`], as little-endian bits.
+///
+/// A challenge has fewer bits than the moduli of both fields of the cycle, so it is encoded by
+/// the same integer in both, and can be used as a scalar of either curve without reduction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CycleChallenge {
+    bits_le: Vec<bool>,
+}
+
+impl CycleChallenge {
+    /// The little-endian bits of the challenge, e.g. for a scalar multiplication.
+    pub fn bits_le(&self) -> &[bool] {
+        &self.bits_le
+    }
+
+    /// The challenge as an element of `F`, which is either field of the cycle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the challenge has as many bits as the modulus of `F`.
+    pub fn to_field<F: PrimeField>(&self) -> F {
+        assert!(
+            self.bits_le.len() < F::Params::MODULUS_BITS as usize,
+            "the challenge does not fit in the field"
+        );
+        F::from_repr(F::BigInt::from_bits_le(&self.bits_le)).unwrap()
+    }
+}
+
+/// A sponge over the base field of the curve `P` of a 2-cycle, such as Pallas and Vesta, with
+/// the encodings of its points and of the scalars of both curves fixed.
+///
+/// The points of `P` and the scalars of the other curve, which are elements of the base field of
+/// `P`, are absorbed natively, and the scalars of `P` in the canonical encoding of
+/// [`FieldBasedCryptographicSponge::absorb_nonnative`]. Challenges are squeezed as bits, which
+/// are scalars of both curves. The gadget [`CycleSpongeVar`](constraints::CycleSpongeVar) uses
+/// the same encodings, so that a recursive verifier derives the same challenges as the prover.
+///
+/// The paired sponge over the other field is a `CycleSponge` for the other curve.
+pub struct CycleSponge<P: SWModelParameters, S> {
+    sponge: S,
+    _curve: PhantomData<P>,
+}
+
+impl<P, S> Clone for CycleSponge<P, S>
+where
+    P: SWModelParameters,
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            sponge: self.sponge.clone(),
+            _curve: PhantomData,
+        }
+    }
+}
+
+impl<P, S> CycleSponge<P, S>
+where
+    P: SWModelParameters,
+    P::BaseField: PrimeField + Absorb,
+    P::ScalarField: PrimeField,
+    S: FieldBasedCryptographicSponge<P::BaseField>,
+{
+    /// Wraps a sponge over the base field of `P`.
+    pub fn new(sponge: S) -> Self {
+        Self {
+            sponge,
+            _curve: PhantomData,
+        }
+    }
+
+    /// Returns the wrapped sponge.
+    pub fn into_inner(self) -> S {
+        self.sponge
+    }
+
+    /// Absorbs points of `P`, each as its affine coordinates and infinity flag.
+    pub fn absorb_points(&mut self, points: &[SWAffine<P>]) {
+        self.sponge.absorb(&points);
+    }
+
+    /// Absorbs scalars of the other curve, which are elements of the base field of `P`.
+    pub fn absorb_other_scalars(&mut self, scalars: &[P::BaseField]) {
+        self.sponge.absorb_native_field_elements(scalars);
+    }
+
+    /// Absorbs scalars of `P`, which are not native to the sponge.
+    pub fn absorb_scalars(&mut self, scalars: &[P::ScalarField]) {
+        self.sponge.absorb_nonnative(scalars);
+    }
+
+    /// Squeezes `num_challenges` challenges of `num_bits` bits each.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_bits` is not smaller than the moduli of both fields.
+    pub fn squeeze_challenges(
+        &mut self,
+        num_challenges: usize,
+        num_bits: usize,
+    ) -> Vec<CycleChallenge> {
+        assert!(
+            num_bits < P::BaseField::size_in_bits() && num_bits < P::ScalarField::size_in_bits(),
+            "the challenges do not fit in both fields"
+        );
+        let bits = self.sponge.squeeze_bits(num_challenges * num_bits);
+        bits.chunks(num_bits.max(1))
+            .take(num_challenges)
+            .map(|bits_le| CycleChallenge {
+                bits_le: bits_le[..num_bits].to_vec(),
+            })
+            .collect()
+    }
+
+    /// Absorbs `points` and squeezes `num_challenges` challenges of [`CYCLE_CHALLENGE_BITS`]
+    /// bits, as in a round of a protocol whose prover sends commitments to `P`.
+    pub fn absorb_points_and_squeeze_challenges(
+        &mut self,
+        points: &[SWAffine<P>],
+        num_challenges: usize,
+    ) -> Vec<CycleChallenge> {
+        self.absorb_points(points);
+        self.squeeze_challenges(num_challenges, CYCLE_CHALLENGE_BITS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cycle::{CycleSponge, CYCLE_CHALLENGE_BITS};
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::PoseidonSponge;
+    use crate::CryptographicSponge;
+    use ark_ec::ProjectiveCurve;
+    use ark_ff::{BigInteger, PrimeField};
+    use ark_std::{test_rng, UniformRand};
+    use ark_test_curves::bls12_381::{g1::Parameters, Fq, Fr, G1Projective};
+
+    #[test]
+    fn challenges() {
+        let mut rng = test_rng();
+        let params = poseidon_parameters_for_test::<Fq>();
+        let point = G1Projective::rand(&mut rng).into_affine();
+
+        let mut sponge = CycleSponge::<Parameters, _>::new(PoseidonSponge::new(&params));
+        let challenges = sponge.absorb_points_and_squeeze_challenges(&[point], 2);
+
+        // The same as absorbing the point and squeezing bits directly.
+        let mut expected = PoseidonSponge::new(&params);
+        expected.absorb(&point);
+        let bits = expected.squeeze_bits(2 * CYCLE_CHALLENGE_BITS);
+        assert_eq!(challenges.len(), 2);
+        for (challenge, bits) in challenges.iter().zip(bits.chunks(CYCLE_CHALLENGE_BITS)) {
+            assert_eq!(challenge.bits_le(), bits);
+            // The challenge is the same integer in both fields.
+            let scalar: Fr = challenge.to_field();
+            let base: Fq = challenge.to_field();
+            assert_eq!(
+                scalar.into_repr().to_bits_le()[..CYCLE_CHALLENGE_BITS],
+                base.into_repr().to_bits_le()[..CYCLE_CHALLENGE_BITS]
+            );
+        }
+
+        // The scalars of both curves are bound.
+        let mut other = CycleSponge::<Parameters, _>::new(PoseidonSponge::new(&params));
+        other.absorb_points(&[point]);
+        other.absorb_scalars(&[Fr::from(2u8)]);
+        let mut other_scalar = CycleSponge::<Parameters, _>::new(PoseidonSponge::new(&params));
+        other_scalar.absorb_points(&[point]);
+        other_scalar.absorb_other_scalars(&[Fq::from(1u8)]);
+        let other = other.squeeze_challenges(2, CYCLE_CHALLENGE_BITS);
+        assert_ne!(other, challenges);
+        assert_ne!(
+            other,
+            other_scalar.squeeze_challenges(2, CYCLE_CHALLENGE_BITS)
+        );
+    }
+
+    #[cfg(feature = "pallas")]
+    #[test]
+    fn pallas() {
+        use crate::poseidon::PoseidonParameters;
+        use ark_ec::AffineCurve;
+        use ark_pallas::{Affine, Fq, Fr, PallasParameters};
+
+        let params = PoseidonParameters::<Fq>::default_for(2, false).unwrap();
+        let mut sponge = CycleSponge::<PallasParameters, _>::new(PoseidonSponge::new(&params));
+        let challenges =
+            sponge.absorb_points_and_squeeze_challenges(&[Affine::prime_subgroup_generator()], 1);
+        let scalar: Fr = challenges[0].to_field();
+        let base: Fq = challenges[0].to_field();
+        assert_eq!(
+            scalar.into_repr().to_bits_le()[..CYCLE_CHALLENGE_BITS],
+            base.into_repr().to_bits_le()[..CYCLE_CHALLENGE_BITS]
+        );
+    }
+}
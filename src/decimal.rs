@@ -0,0 +1,26 @@
+//! Decimal representations of field elements, readable by implementations in other languages.
+
+use crate::SpongeError;
+use ark_ff::{BigInteger, FpParameters, PrimeField};
+use ark_std::string::{String, ToString};
+use num_bigint::BigUint;
+
+/// The decimal representation of `elem`.
+pub(crate) fn field_to_string<F: PrimeField>(elem: &F) -> String {
+    BigUint::from_bytes_le(&elem.into_repr().to_bytes_le()).to_string()
+}
+
+/// Parses the decimal representation of an element of `F`, which must be smaller than the
+/// modulus.
+pub(crate) fn string_to_field<F: PrimeField>(elem: &str) -> Result<F, SpongeError> {
+    let value = BigUint::parse_bytes(elem.as_bytes(), 10).ok_or(SpongeError::InvalidParameters(
+        "a field element is not a decimal integer",
+    ))?;
+    let modulus = BigUint::from_bytes_le(&F::Params::MODULUS.to_bytes_le());
+    if value >= modulus {
+        return Err(SpongeError::InvalidParameters(
+            "a field element is not smaller than the modulus",
+        ));
+    }
+    Ok(F::from_le_bytes_mod_order(&value.to_bytes_le()))
+}
@@ -0,0 +1,130 @@
+// `digest` 0.9 exposes generic-array 0.14, whose latest patch release deprecates its own items.
+#![allow(deprecated)]
+
+use crate::CryptographicSponge;
+use ark_std::marker::PhantomData;
+use ark_std::vec::Vec;
+use digest::consts::U32;
+use digest::generic_array::{ArrayLength, GenericArray};
+use digest::{FixedOutput, Reset, Update};
+
+/// A hash function with the RustCrypto [`Digest`](digest::Digest) API over any sponge, with an
+/// output of `N` bytes, for generic code that expects a hash function.
+///
+/// The digest of a message is the same as absorbing the message into a fresh sponge as a byte
+/// string (`&[u8]`, which is prefixed with its length) and squeezing `N` bytes. Since the length
+/// is absorbed first, the message is buffered until the digest is finalized.
+///
+/// [`Default`] (and hence [`Digest::new`](digest::Digest::new)) uses the default parameters of
+/// the sponge; use [`SpongeDigest::new`] to choose them.
+pub struct SpongeDigest<S: CryptographicSponge, N: ArrayLength<u8> = U32> {
+    initial_sponge: S,
+    message: Vec<u8>,
+    _output_size: PhantomData<N>,
+}
+
+impl<S: CryptographicSponge, N: ArrayLength<u8>> SpongeDigest<S, N> {
+    /// Creates a hash function over the sponge with the parameters `params`.
+    pub fn new(params: &S::Parameters) -> Self {
+        Self::from_sponge(S::new(params))
+    }
+
+    /// Creates a hash function over `sponge`, which may already have absorbed a domain separator.
+    pub fn from_sponge(sponge: S) -> Self {
+        Self {
+            initial_sponge: sponge,
+            message: Vec::new(),
+            _output_size: PhantomData,
+        }
+    }
+}
+
+impl<S: CryptographicSponge, N: ArrayLength<u8>> Clone for SpongeDigest<S, N> {
+    fn clone(&self) -> Self {
+        Self {
+            initial_sponge: self.initial_sponge.clone(),
+            message: self.message.clone(),
+            _output_size: PhantomData,
+        }
+    }
+}
+
+impl<S, N> Default for SpongeDigest<S, N>
+where
+    S: CryptographicSponge,
+    S::Parameters: Default,
+    N: ArrayLength<u8>,
+{
+    fn default() -> Self {
+        Self::new(&S::Parameters::default())
+    }
+}
+
+impl<S: CryptographicSponge, N: ArrayLength<u8>> Update for SpongeDigest<S, N> {
+    fn update(&mut self, data: impl AsRef<[u8]>) {
+        self.message.extend_from_slice(data.as_ref());
+    }
+}
+
+impl<S: CryptographicSponge, N: ArrayLength<u8>> FixedOutput for SpongeDigest<S, N> {
+    type OutputSize = N;
+
+    fn finalize_into(mut self, out: &mut GenericArray<u8, Self::OutputSize>) {
+        self.finalize_into_reset(out);
+    }
+
+    fn finalize_into_reset(&mut self, out: &mut GenericArray<u8, Self::OutputSize>) {
+        let mut sponge = self.initial_sponge.clone();
+        sponge.absorb(&self.message.as_slice());
+        out.copy_from_slice(&sponge.squeeze_bytes(N::USIZE));
+        self.reset();
+    }
+}
+
+impl<S: CryptographicSponge, N: ArrayLength<u8>> Reset for SpongeDigest<S, N> {
+    fn reset(&mut self) {
+        self.message.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::digest_compat::SpongeDigest;
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::{PoseidonParameters, PoseidonSponge};
+    use crate::CryptographicSponge;
+    use ark_test_curves::bls12_381::Fr;
+    use digest::consts::U64;
+    use digest::Digest;
+
+    #[test]
+    fn test_digest() {
+        let params = poseidon_parameters_for_test::<Fr>();
+        let mut hasher = SpongeDigest::<PoseidonSponge<Fr>>::new(&params);
+        hasher.update(b"hello ");
+        hasher.update(b"world");
+        let digest = hasher.finalize_reset();
+
+        let mut sponge = PoseidonSponge::new(&params);
+        sponge.absorb(&b"hello world".as_ref());
+        assert_eq!(digest.to_vec(), sponge.squeeze_bytes(32));
+
+        // The hasher is reset after finalizing.
+        hasher.update(b"hello world");
+        assert_eq!(hasher.finalize(), digest);
+
+        let digest = SpongeDigest::<PoseidonSponge<Fr>, U64>::new(&params)
+            .chain(b"hello world")
+            .finalize();
+        assert_eq!(digest.len(), 64);
+
+        // `Digest::digest` uses the default parameters.
+        let params = PoseidonParameters::<Fr>::default();
+        assert_eq!(
+            SpongeDigest::<PoseidonSponge<Fr>>::digest(b"hello world"),
+            SpongeDigest::<PoseidonSponge<Fr>>::new(&params)
+                .chain(b"hello world")
+                .finalize()
+        );
+    }
+}
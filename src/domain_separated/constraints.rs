@@ -0,0 +1,265 @@
+use crate::constraints::{AbsorbGadget, CryptographicSpongeVar, SpongeWithGadget};
+use crate::domain_separated::{
+    encode_domain_path, DomainSeparatedSponge, DomainSeparator, DOMAIN_MARKER,
+};
+use crate::Absorb;
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use ark_std::marker::PhantomData;
+use ark_std::vec;
+use ark_std::vec::Vec;
+
+/// The gadget for [`DomainSeparatedSponge`], over the gadget of the sponge `S`.
+///
+/// The domain is absorbed as constants, so it costs no constraints for sponges that absorb
+/// constants for free.
+pub struct DomainSeparatedSpongeVar<CF: PrimeField, S: SpongeWithGadget<CF>, D: DomainSeparator> {
+    sponge: S::Var,
+    /// The sponge before any domain is absorbed, from which subdomains are created.
+    root: S::Var,
+    /// The path of nested domains, from the outermost to the innermost.
+    path: Vec<Vec<u8>>,
+    _domain: PhantomData<D>,
+}
+
+impl<CF: PrimeField, S: SpongeWithGadget<CF>, D: DomainSeparator> SpongeWithGadget<CF>
+    for DomainSeparatedSponge<CF, S, D>
+{
+    type Var = DomainSeparatedSpongeVar<CF, S, D>;
+}
+
+impl<CF: PrimeField, S: SpongeWithGadget<CF>, D: DomainSeparator>
+    DomainSeparatedSpongeVar<CF, S, D>
+{
+    /// Creates a sponge with the given parameters, separated into `domain` instead of the domain
+    /// of `D`, as [`DomainSeparatedSponge::new_with_domain`].
+    pub fn new_with_domain(
+        cs: ConstraintSystemRef<CF>,
+        params: &<S::Var as CryptographicSpongeVar<CF, S>>::Parameters,
+        domain: &[u8],
+    ) -> Result<Self, SynthesisError> {
+        Self::from_root(S::Var::new(cs, params), vec![domain.to_vec()])
+    }
+
+    /// Creates a sponge with the given parameters, separated into `domain` with the encoding of
+    /// earlier versions of this crate, as [`DomainSeparatedSponge::new_with_unframed_domain`].
+    pub fn new_with_unframed_domain(
+        cs: ConstraintSystemRef<CF>,
+        params: &<S::Var as CryptographicSpongeVar<CF, S>>::Parameters,
+        domain: &[u8],
+    ) -> Result<Self, SynthesisError> {
+        let root = S::Var::new(cs, params);
+        Ok(Self {
+            sponge: root.fork(domain)?,
+            root,
+            path: vec![domain.to_vec()],
+            _domain: PhantomData,
+        })
+    }
+
+    /// Creates a sponge separated into the subdomain `label` of the domain of this sponge, as
+    /// [`DomainSeparatedSponge::subdomain`].
+    pub fn subdomain(&self, label: &[u8]) -> Result<Self, SynthesisError> {
+        let mut path = self.path.clone();
+        path.push(label.to_vec());
+        Self::from_root(self.root.clone(), path)
+    }
+
+    /// Returns the path of nested domains of this sponge, from the outermost to the innermost.
+    pub fn domain_path(&self) -> &[Vec<u8>] {
+        &self.path
+    }
+
+    fn from_root(root: S::Var, path: Vec<Vec<u8>>) -> Result<Self, SynthesisError> {
+        #[cfg(feature = "trace")]
+        tracing::trace!(target: "ark_sponge", domain_path = ?path, "domain");
+        let mut sponge = root.clone();
+        for input in [DOMAIN_MARKER.to_vec(), encode_domain_path(&path)] {
+            let elems: Vec<CF> = input.to_sponge_field_elements_as_vec();
+            let elem_vars: Vec<_> = elems.into_iter().map(FpVar::Constant).collect();
+            sponge.absorb(&elem_vars)?;
+        }
+
+        Ok(Self {
+            sponge,
+            root,
+            path,
+            _domain: PhantomData,
+        })
+    }
+}
+
+impl<CF: PrimeField, S: SpongeWithGadget<CF>, D: DomainSeparator> Clone
+    for DomainSeparatedSpongeVar<CF, S, D>
+{
+    fn clone(&self) -> Self {
+        Self {
+            sponge: self.sponge.clone(),
+            root: self.root.clone(),
+            path: self.path.clone(),
+            _domain: PhantomData,
+        }
+    }
+}
+
+impl<CF: PrimeField, S: SpongeWithGadget<CF>, D: DomainSeparator>
+    CryptographicSpongeVar<CF, DomainSeparatedSponge<CF, S, D>>
+    for DomainSeparatedSpongeVar<CF, S, D>
+{
+    type Parameters = <S::Var as CryptographicSpongeVar<CF, S>>::Parameters;
+
+    /// Creates the sponge separated into the domain of `D`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if absorbing the domain fails, which happens only if the constraint system fails.
+    fn new(cs: ConstraintSystemRef<CF>, params: &Self::Parameters) -> Self {
+        Self::new_with_domain(cs, params, &D::domain())
+            .expect("absorbing a constant domain does not fail")
+    }
+
+    fn cs(&self) -> ConstraintSystemRef<CF> {
+        self.sponge.cs()
+    }
+
+    fn rate(&self) -> usize {
+        self.sponge.rate()
+    }
+
+    fn capacity(&self) -> usize {
+        self.sponge.capacity()
+    }
+
+    fn security_level(&self) -> usize {
+        self.sponge.security_level()
+    }
+
+    fn absorb(&mut self, input: &impl AbsorbGadget<CF>) -> Result<(), SynthesisError> {
+        self.sponge.absorb(input)
+    }
+
+    fn squeeze_bytes(&mut self, num_bytes: usize) -> Result<Vec<UInt8<CF>>, SynthesisError> {
+        self.sponge.squeeze_bytes(num_bytes)
+    }
+
+    fn squeeze_bits(&mut self, num_bits: usize) -> Result<Vec<Boolean<CF>>, SynthesisError> {
+        self.sponge.squeeze_bits(num_bits)
+    }
+
+    fn squeeze_field_elements(
+        &mut self,
+        num_elements: usize,
+    ) -> Result<Vec<FpVar<CF>>, SynthesisError> {
+        self.sponge.squeeze_field_elements(num_elements)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constraints::CryptographicSpongeVar;
+    use crate::domain_separated::constraints::DomainSeparatedSpongeVar;
+    use crate::domain_separated::{DomainSeparatedSponge, RuntimeDomain};
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::PoseidonSponge;
+    use crate::{CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    type Sponge = DomainSeparatedSponge<Fr, PoseidonSponge<Fr>, RuntimeDomain>;
+    type SpongeVar = DomainSeparatedSpongeVar<Fr, PoseidonSponge<Fr>, RuntimeDomain>;
+
+    #[test]
+    fn test_runtime_domain_var() {
+        let cs = ConstraintSystem::new_ref();
+        let params = poseidon_parameters_for_test();
+        let input = [Fr::from(1u64), Fr::from(2u64), Fr::from(3u64)];
+
+        let mut sponge = Sponge::new_with_domain(&params, b"circuit 42");
+        sponge.absorb(&input);
+        let expected = sponge.squeeze_native_field_elements(2);
+
+        let input_var: Vec<_> = input
+            .iter()
+            .map(|elem| FpVar::new_witness(cs.clone(), || Ok(*elem)).unwrap())
+            .collect();
+        let mut sponge_var =
+            SpongeVar::new_with_domain(cs.clone(), &params, b"circuit 42").unwrap();
+        sponge_var.absorb(&input_var).unwrap();
+        let output = sponge_var.squeeze_field_elements(2).unwrap();
+        assert_eq!(output.value().unwrap(), expected);
+
+        let mut round_sponge = sponge.subdomain(b"round-1");
+        let mut round_sponge_var = sponge_var.subdomain(b"round-1").unwrap();
+        assert_eq!(
+            round_sponge_var
+                .squeeze_field_elements(2)
+                .unwrap()
+                .value()
+                .unwrap(),
+            round_sponge.squeeze_native_field_elements(2)
+        );
+
+        let mut sponge = Sponge::new_with_unframed_domain(&params, b"old");
+        let mut sponge_var =
+            SpongeVar::new_with_unframed_domain(cs.clone(), &params, b"old").unwrap();
+        assert_eq!(
+            sponge_var
+                .squeeze_field_elements(1)
+                .unwrap()
+                .value()
+                .unwrap(),
+            sponge.squeeze_native_field_elements(1)
+        );
+
+        let mut sponge = Sponge::new(&params);
+        let mut sponge_var = SpongeVar::new(cs.clone(), &params);
+        assert_eq!(
+            sponge_var
+                .squeeze_field_elements(1)
+                .unwrap()
+                .value()
+                .unwrap(),
+            sponge.squeeze_native_field_elements(1)
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_str_domain_var() {
+        crate::domain_separator!(StrTestDomain = "circuit 42");
+
+        let cs = ConstraintSystem::new_ref();
+        let params = poseidon_parameters_for_test();
+        let mut sponge =
+            DomainSeparatedSponge::<Fr, PoseidonSponge<Fr>, StrTestDomain>::new(&params);
+        let mut sponge_var = DomainSeparatedSpongeVar::<Fr, PoseidonSponge<Fr>, StrTestDomain>::new(
+            cs.clone(),
+            &params,
+        );
+        let mut runtime_sponge_var =
+            SpongeVar::new_with_domain(cs.clone(), &params, b"circuit 42").unwrap();
+        let expected = sponge.squeeze_native_field_elements(2);
+        assert_eq!(
+            sponge_var
+                .squeeze_field_elements(2)
+                .unwrap()
+                .value()
+                .unwrap(),
+            expected
+        );
+        assert_eq!(
+            runtime_sponge_var
+                .squeeze_field_elements(2)
+                .unwrap()
+                .value()
+                .unwrap(),
+            expected
+        );
+        assert_eq!(cs.num_constraints(), 0);
+    }
+}
@@ -0,0 +1,406 @@
+use crate::{Absorb, CryptographicSponge, FieldBasedCryptographicSponge, FieldElementSize};
+use ark_ff::PrimeField;
+use ark_std::marker::PhantomData;
+use ark_std::vec;
+use ark_std::vec::Vec;
+
+/// constraints for the domain-separated sponge
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+/// A domain, known at compile time, into which a [`DomainSeparatedSponge`] separates its inputs.
+pub trait DomainSeparator {
+    /// Returns the bytes identifying the domain.
+    fn domain() -> Vec<u8>;
+}
+
+/// A domain separator whose domain is a string, which is a [`DomainSeparator`] with the bytes of
+/// the string as its domain.
+///
+/// It is usually declared with [`domain_separator!`](crate::domain_separator).
+pub trait StrDomainSeparator {
+    /// The string identifying the domain, e.g. the name and version of a protocol.
+    const DOMAIN: &'static str;
+}
+
+impl<D: StrDomainSeparator> DomainSeparator for D {
+    fn domain() -> Vec<u8> {
+        D::DOMAIN.as_bytes().to_vec()
+    }
+}
+
+/// Declares unit structs implementing [`StrDomainSeparator`], and thus [`DomainSeparator`], each
+/// with a string as its domain, to use with [`DomainSeparatedSponge`] and its gadget:
+///
+/// ```
+/// # use ark_sponge::domain_separated::DomainSeparatedSponge;
+/// # use ark_sponge::domain_separator;
+/// # use ark_sponge::poseidon::{PoseidonParameters, PoseidonSponge};
+/// # use ark_sponge::CryptographicSponge;
+/// # use ark_test_curves::bls12_381::Fr;
+/// domain_separator! {
+///     /// The domain of the first version of the protocol.
+///     pub ProtocolV1 = "my-protocol/v1";
+///     ProtocolV2 = "my-protocol/v2";
+/// }
+///
+/// let params = PoseidonParameters::<Fr>::default();
+/// let sponge = DomainSeparatedSponge::<Fr, PoseidonSponge<Fr>, ProtocolV1>::new(&params);
+/// ```
+///
+/// Each declaration takes attributes, such as doc comments, and a visibility. The structs derive
+/// `Clone`, `Copy`, and `Debug`.
+#[macro_export]
+macro_rules! domain_separator {
+    ($($(#[$attr:meta])* $vis:vis $name:ident = $domain:expr);+ $(;)?) => {
+        $(
+            $(#[$attr])*
+            #[derive(Clone, Copy, Debug)]
+            $vis struct $name;
+
+            impl $crate::domain_separated::StrDomainSeparator for $name {
+                const DOMAIN: &'static str = $domain;
+            }
+        )+
+    };
+}
+
+/// The domain separator to use with [`DomainSeparatedSponge::new_with_domain`] when the domain is
+/// only known at runtime, such as an identifier of a circuit.
+///
+/// Its own domain is empty, so a sponge created with [`CryptographicSponge::new`] is separated
+/// into the empty domain.
+#[derive(Clone, Copy, Debug)]
+pub struct RuntimeDomain;
+
+impl DomainSeparator for RuntimeDomain {
+    fn domain() -> Vec<u8> {
+        Vec::new()
+    }
+}
+
+/// The marker absorbed, as bytes, before the path of domains of a [`DomainSeparatedSponge`], so
+/// that the domain tag is distinguished from the inputs absorbed without framing.
+///
+/// Field-based sponges pack it into a single field element.
+pub const DOMAIN_MARKER: &[u8] = b"ark-sponge/domain";
+
+/// Encodes a path of nested domains, from the outermost to the innermost, as the number of
+/// domains followed by each domain prefixed with its length.
+///
+/// The encoding is prefix-free: the encoding of a path is never a prefix of the encoding of
+/// another path, so it cannot be forged by absorbing more inputs after it.
+pub(crate) fn encode_domain_path(path: &[Vec<u8>]) -> Vec<u8> {
+    let mut encoding = Absorb::to_sponge_bytes_as_vec(&path.len());
+    for domain in path {
+        domain.len().to_sponge_bytes(&mut encoding);
+        encoding.extend_from_slice(domain);
+    }
+    encoding
+}
+
+/// A sponge over `S` whose inputs are separated into the domain of `D`, so that sponges with the
+/// same parameters but different domains give independent outputs.
+///
+/// [`CryptographicSponge::new`] uses the domain [`DomainSeparator::domain`] of `D`, and
+/// [`DomainSeparatedSponge::new_with_domain`] a domain given as a value. Domains can be nested
+/// with [`DomainSeparatedSponge::subdomain`], e.g. for the rounds of a protocol.
+///
+/// When the sponge is created, [`DOMAIN_MARKER`] is absorbed, followed by the path of nested
+/// domains in a prefix-free encoding (the number of domains and each domain prefixed with its
+/// length), so that the domains `"ab"` and `"c"` do not collide with `"a"` and `"bc"`, and no
+/// sequence of inputs absorbed in a domain collides with a subdomain.
+///
+/// Sponges created before the domain tag was framed absorbed the domain as
+/// [`CryptographicSponge::fork`] does; [`DomainSeparatedSponge::new_with_unframed_domain`]
+/// recreates them to verify old transcripts.
+pub struct DomainSeparatedSponge<CF: PrimeField, S: CryptographicSponge, D: DomainSeparator> {
+    sponge: S,
+    /// The sponge before any domain is absorbed, from which subdomains are created.
+    root: S,
+    /// The path of nested domains, from the outermost to the innermost.
+    path: Vec<Vec<u8>>,
+    _field: PhantomData<CF>,
+    _domain: PhantomData<D>,
+}
+
+impl<CF: PrimeField, S: CryptographicSponge, D: DomainSeparator> DomainSeparatedSponge<CF, S, D> {
+    /// Creates a sponge with the given parameters, separated into `domain` instead of the domain
+    /// of `D`.
+    pub fn new_with_domain(params: &S::Parameters, domain: &[u8]) -> Self {
+        Self::from_root(S::new(params), vec![domain.to_vec()])
+    }
+
+    /// Creates a sponge with the given parameters, separated into `domain` with the encoding of
+    /// earlier versions of this crate, which absorbed `domain` as [`CryptographicSponge::fork`]
+    /// does, without [`DOMAIN_MARKER`] or the number of domains.
+    ///
+    /// This is only meant to verify transcripts created with these versions. The subdomains of
+    /// the sponge use the framed encoding.
+    pub fn new_with_unframed_domain(params: &S::Parameters, domain: &[u8]) -> Self {
+        let root = S::new(params);
+        Self::from_parts(root.fork(domain), root, vec![domain.to_vec()])
+    }
+
+    /// Creates a sponge separated into the subdomain `label` of the domain of this sponge.
+    ///
+    /// The new sponge starts from the parameters of this one and does not depend on the inputs
+    /// absorbed by it; to bind a sub-protocol to them, absorb a challenge squeezed from this
+    /// sponge into the new one.
+    pub fn subdomain(&self, label: &[u8]) -> Self {
+        let mut path = self.path.clone();
+        path.push(label.to_vec());
+        Self::from_root(self.root.clone(), path)
+    }
+
+    /// Returns the path of nested domains of this sponge, from the outermost to the innermost.
+    pub fn domain_path(&self) -> &[Vec<u8>] {
+        &self.path
+    }
+
+    fn from_root(root: S, path: Vec<Vec<u8>>) -> Self {
+        #[cfg(feature = "trace")]
+        tracing::trace!(target: "ark_sponge", domain_path = ?path, "domain");
+        let mut sponge = root.clone();
+        sponge.absorb(&DOMAIN_MARKER);
+        sponge.absorb(&encode_domain_path(&path));
+        Self::from_parts(sponge, root, path)
+    }
+
+    fn from_parts(sponge: S, root: S, path: Vec<Vec<u8>>) -> Self {
+        Self {
+            sponge,
+            root,
+            path,
+            _field: PhantomData,
+            _domain: PhantomData,
+        }
+    }
+}
+
+impl<CF: PrimeField, S: CryptographicSponge, D: DomainSeparator> Clone
+    for DomainSeparatedSponge<CF, S, D>
+{
+    fn clone(&self) -> Self {
+        Self {
+            sponge: self.sponge.clone(),
+            root: self.root.clone(),
+            path: self.path.clone(),
+            _field: PhantomData,
+            _domain: PhantomData,
+        }
+    }
+}
+
+impl<CF: PrimeField, S: CryptographicSponge, D: DomainSeparator> CryptographicSponge
+    for DomainSeparatedSponge<CF, S, D>
+{
+    type Parameters = S::Parameters;
+
+    fn new(params: &Self::Parameters) -> Self {
+        Self::new_with_domain(params, &D::domain())
+    }
+
+    fn rate(&self) -> usize {
+        self.sponge.rate()
+    }
+
+    fn capacity(&self) -> usize {
+        self.sponge.capacity()
+    }
+
+    fn security_level(&self) -> usize {
+        self.sponge.security_level()
+    }
+
+    fn absorb(&mut self, input: &impl Absorb) {
+        self.sponge.absorb(input);
+    }
+
+    fn absorb_iter<A: Absorb>(&mut self, iter: impl IntoIterator<Item = A>) {
+        self.sponge.absorb_iter(iter);
+    }
+
+    fn squeeze_bytes(&mut self, num_bytes: usize) -> Vec<u8> {
+        self.sponge.squeeze_bytes(num_bytes)
+    }
+
+    fn squeeze_bits(&mut self, num_bits: usize) -> Vec<bool> {
+        self.sponge.squeeze_bits(num_bits)
+    }
+
+    fn squeeze_field_elements_with_sizes<F: PrimeField>(
+        &mut self,
+        sizes: &[FieldElementSize],
+    ) -> Vec<F> {
+        self.sponge.squeeze_field_elements_with_sizes(sizes)
+    }
+
+    fn squeeze_field_elements<F: PrimeField>(&mut self, num_elements: usize) -> Vec<F> {
+        self.sponge.squeeze_field_elements(num_elements)
+    }
+}
+
+impl<CF: PrimeField, S: FieldBasedCryptographicSponge<CF>, D: DomainSeparator>
+    FieldBasedCryptographicSponge<CF> for DomainSeparatedSponge<CF, S, D>
+{
+    fn absorb_native_field_elements(&mut self, elems: &[CF])
+    where
+        CF: Absorb,
+    {
+        self.sponge.absorb_native_field_elements(elems);
+    }
+
+    fn squeeze_native_field_elements(&mut self, num_elements: usize) -> Vec<CF> {
+        self.sponge.squeeze_native_field_elements(num_elements)
+    }
+
+    fn squeeze_native_field_elements_with_sizes(&mut self, sizes: &[FieldElementSize]) -> Vec<CF> {
+        self.sponge.squeeze_native_field_elements_with_sizes(sizes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain_separated::{
+        encode_domain_path, DomainSeparatedSponge, DomainSeparator, RuntimeDomain, DOMAIN_MARKER,
+    };
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::PoseidonSponge;
+    use crate::{CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    struct TestDomain;
+
+    impl DomainSeparator for TestDomain {
+        fn domain() -> Vec<u8> {
+            b"test domain".to_vec()
+        }
+    }
+
+    type TestSponge<D> = DomainSeparatedSponge<Fr, PoseidonSponge<Fr>, D>;
+
+    crate::domain_separator! {
+        StrTestDomain = "test domain";
+        pub(crate) OtherStrTestDomain = "other";
+    }
+
+    #[test]
+    fn test_runtime_domain() {
+        let params = poseidon_parameters_for_test();
+        let input = [Fr::from(1u64), Fr::from(2u64), Fr::from(3u64)];
+
+        let mut sponge = TestSponge::<TestDomain>::new(&params);
+        let mut runtime_sponge =
+            TestSponge::<RuntimeDomain>::new_with_domain(&params, b"test domain");
+        sponge.absorb(&input);
+        runtime_sponge.absorb(&input);
+        let output = sponge.squeeze_native_field_elements(2);
+        assert_eq!(runtime_sponge.squeeze_native_field_elements(2), output);
+
+        let mut other_sponge = TestSponge::<RuntimeDomain>::new_with_domain(&params, b"other");
+        other_sponge.absorb(&input);
+        assert_ne!(other_sponge.squeeze_native_field_elements(2), output);
+
+        let mut sponge = TestSponge::<RuntimeDomain>::new(&params);
+        let mut empty_sponge = TestSponge::<TestDomain>::new_with_domain(&params, b"");
+        assert_eq!(sponge.squeeze_bytes(8), empty_sponge.squeeze_bytes(8));
+    }
+
+    #[test]
+    fn test_subdomain() {
+        let params = poseidon_parameters_for_test();
+        let sponge = TestSponge::<RuntimeDomain>::new_with_domain(&params, b"protocol");
+        let round_sponge = sponge.subdomain(b"round-1");
+        assert_eq!(
+            round_sponge.domain_path(),
+            &[b"protocol".to_vec(), b"round-1".to_vec()][..]
+        );
+
+        // The subdomain does not depend on the inputs absorbed in the parent domain.
+        let mut used_sponge = sponge.clone();
+        used_sponge.absorb(&Fr::from(1u64));
+        let mut round_sponge_from_used = used_sponge.subdomain(b"round-1");
+        let mut round_sponge = round_sponge.clone();
+        let output = round_sponge.squeeze_native_field_elements(2);
+        assert_eq!(
+            round_sponge_from_used.squeeze_native_field_elements(2),
+            output
+        );
+
+        // Absorbing the framing of the subdomain in the parent domain gives a different sponge.
+        let mut forged_sponge = sponge.clone();
+        forged_sponge.absorb(&b"round-1".len());
+        forged_sponge.absorb(&b"round-1".to_vec());
+        assert_ne!(forged_sponge.squeeze_native_field_elements(2), output);
+        let mut forked_sponge = sponge.fork(b"round-1");
+        assert_ne!(forked_sponge.squeeze_native_field_elements(2), output);
+
+        // Nested domains are not confused with a single domain of the concatenated labels.
+        let mut concatenated_sponge =
+            TestSponge::<RuntimeDomain>::new_with_domain(&params, b"protocolround-1");
+        assert_ne!(concatenated_sponge.squeeze_native_field_elements(2), output);
+        let mut other_round_sponge = sponge.subdomain(b"round-2");
+        assert_ne!(other_round_sponge.squeeze_native_field_elements(2), output);
+    }
+
+    #[test]
+    fn test_framed_domain() {
+        let params = poseidon_parameters_for_test();
+        let mut sponge =
+            TestSponge::<RuntimeDomain>::new_with_domain(&params, b"ab").subdomain(b"c");
+        let output = sponge.squeeze_native_field_elements(2);
+        let mut other_sponge =
+            TestSponge::<RuntimeDomain>::new_with_domain(&params, b"a").subdomain(b"bc");
+        assert_ne!(other_sponge.squeeze_native_field_elements(2), output);
+
+        // The marker distinguishes the domain from the same encoding absorbed as an input.
+        let path = [b"ab".to_vec(), b"c".to_vec()];
+        let mut unmarked_sponge = PoseidonSponge::new(&params);
+        unmarked_sponge.absorb(&encode_domain_path(&path));
+        assert_ne!(unmarked_sponge.squeeze_native_field_elements(2), output);
+        let mut marked_sponge = PoseidonSponge::new(&params);
+        marked_sponge.absorb(&DOMAIN_MARKER);
+        marked_sponge.absorb(&encode_domain_path(&path));
+        assert_eq!(marked_sponge.squeeze_native_field_elements(2), output);
+    }
+
+    #[test]
+    fn test_unframed_domain() {
+        let params = poseidon_parameters_for_test();
+        let mut sponge = TestSponge::<RuntimeDomain>::new_with_unframed_domain(&params, b"old");
+        let mut forked_sponge = PoseidonSponge::new(&params).fork(b"old");
+        let output = sponge.squeeze_native_field_elements(2);
+        assert_eq!(forked_sponge.squeeze_native_field_elements(2), output);
+        let mut framed_sponge = TestSponge::<RuntimeDomain>::new_with_domain(&params, b"old");
+        assert_ne!(framed_sponge.squeeze_native_field_elements(2), output);
+
+        let mut round_sponge = sponge.subdomain(b"round-1");
+        let mut framed_round_sponge = framed_sponge.subdomain(b"round-1");
+        assert_eq!(
+            round_sponge.squeeze_native_field_elements(2),
+            framed_round_sponge.squeeze_native_field_elements(2)
+        );
+    }
+
+    #[test]
+    fn test_str_domain() {
+        assert_eq!(StrTestDomain::domain(), TestDomain::domain());
+
+        let params = poseidon_parameters_for_test();
+        let mut sponge = TestSponge::<StrTestDomain>::new(&params);
+        let mut expected_sponge = TestSponge::<TestDomain>::new(&params);
+        assert_eq!(
+            sponge.squeeze_native_field_elements(2),
+            expected_sponge.squeeze_native_field_elements(2)
+        );
+
+        let mut other_sponge = TestSponge::<OtherStrTestDomain>::new(&params);
+        let mut runtime_sponge = TestSponge::<RuntimeDomain>::new_with_domain(&params, b"other");
+        assert_eq!(
+            other_sponge.squeeze_native_field_elements(2),
+            runtime_sponge.squeeze_native_field_elements(2)
+        );
+    }
+}
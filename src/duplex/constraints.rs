@@ -0,0 +1,386 @@
+use crate::constraints::AbsorbGadget;
+use crate::constraints::{
+    constant_state, select_state, CryptographicSpongeVar, SpongeExtVar, SpongeWithGadget,
+};
+use crate::duplex::{DuplexSponge, DuplexSpongeState, FieldPermutation};
+use crate::{
+    bits_from_squeezed_element_vars, bytes_from_squeezed_element_vars, field_sponge_security_level,
+    squeeze_bits_num_elements, squeeze_bytes_num_elements, DuplexSpongeMode,
+};
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+use ark_std::borrow::Borrow;
+use ark_std::vec;
+use ark_std::vec::Vec;
+
+/// A [`FieldPermutation`] that can also be applied to a state of variables.
+pub trait FieldPermutationGadget<F: PrimeField>: FieldPermutation<F> {
+    /// Applies the permutation to `state`, which has `rate + capacity` elements.
+    fn permute_var(&self, state: &mut [FpVar<F>]) -> Result<(), SynthesisError>;
+}
+
+/// The gadget for [`DuplexSponge`].
+#[derive(Clone)]
+pub struct DuplexSpongeVar<F: PrimeField, P: FieldPermutationGadget<F>> {
+    /// Constraint system
+    pub cs: ConstraintSystemRef<F>,
+
+    /// The permutation, including its parameters.
+    pub permutation: P,
+
+    // Sponge State
+    /// The sponge's state
+    pub state: Vec<FpVar<F>>,
+    /// The mode
+    pub mode: DuplexSpongeMode,
+}
+
+impl<F: PrimeField, P: FieldPermutationGadget<F>> SpongeWithGadget<F> for DuplexSponge<F, P> {
+    type Var = DuplexSpongeVar<F, P>;
+}
+
+impl<F: PrimeField, P: FieldPermutationGadget<F>> DuplexSpongeVar<F, P> {
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    fn apply_permutation(&mut self) -> Result<(), SynthesisError> {
+        #[cfg(feature = "trace")]
+        tracing::trace!(
+            target: "ark_sponge",
+            sponge = "duplex",
+            "permute"
+        );
+        if let Some(mut values) = constant_state(&self.state) {
+            self.permutation.permute(&mut values);
+            self.state = values.into_iter().map(FpVar::Constant).collect();
+            return Ok(());
+        }
+        self.permutation.permute_var(&mut self.state)
+    }
+
+    /// Applies the permutation now, as [`DuplexSponge::permute`].
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    pub fn permute(&mut self) -> Result<(), SynthesisError> {
+        self.apply_permutation()?;
+        self.mode = match self.mode {
+            DuplexSpongeMode::Absorbing { .. } => DuplexSpongeMode::Absorbing {
+                next_absorb_index: 0,
+            },
+            DuplexSpongeMode::Squeezing { .. } => DuplexSpongeMode::Squeezing {
+                next_squeeze_index: 0,
+            },
+        };
+        Ok(())
+    }
+
+    /// Closes the absorb phase, as [`DuplexSponge::finish_absorb`].
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    pub fn finish_absorb(&mut self) -> Result<(), SynthesisError> {
+        if let DuplexSpongeMode::Absorbing { .. } = self.mode {
+            self.apply_permutation()?;
+            self.mode = DuplexSpongeMode::Squeezing {
+                next_squeeze_index: 0,
+            };
+        }
+        Ok(())
+    }
+
+    /// Permutes the state and zeroes its rate, so that the inputs absorbed before cannot be
+    /// recovered from the state afterwards. The sponge is then absorbing.
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    pub fn ratchet(&mut self) -> Result<(), SynthesisError> {
+        self.apply_permutation()?;
+        let capacity = self.permutation.capacity();
+        for elem in &mut self.state[capacity..] {
+            *elem = FpVar::zero();
+        }
+        self.mode = DuplexSpongeMode::Absorbing {
+            next_absorb_index: 0,
+        };
+        Ok(())
+    }
+
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    fn absorb_internal(
+        &mut self,
+        mut rate_start_index: usize,
+        elements: &[FpVar<F>],
+    ) -> Result<(), SynthesisError> {
+        let rate = self.permutation.rate();
+        let capacity = self.permutation.capacity();
+        let mut remaining_elements = elements;
+
+        loop {
+            // if we can finish in this call
+            if rate_start_index + remaining_elements.len() <= rate {
+                for (i, element) in remaining_elements.iter().enumerate() {
+                    self.state[capacity + i + rate_start_index] += element;
+                }
+                self.mode = DuplexSpongeMode::Absorbing {
+                    next_absorb_index: rate_start_index + remaining_elements.len(),
+                };
+
+                return Ok(());
+            }
+            // otherwise absorb (rate - rate_start_index) elements
+            let num_elements_absorbed = rate - rate_start_index;
+            for (i, element) in remaining_elements
+                .iter()
+                .enumerate()
+                .take(num_elements_absorbed)
+            {
+                self.state[capacity + i + rate_start_index] += element;
+            }
+            self.apply_permutation()?;
+            // the input elements got truncated by num elements absorbed
+            remaining_elements = &remaining_elements[num_elements_absorbed..];
+            rate_start_index = 0;
+        }
+    }
+
+    // Squeeze |output| many elements. This does not end in a squeeze
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    fn squeeze_internal(
+        &mut self,
+        mut rate_start_index: usize,
+        output: &mut [FpVar<F>],
+    ) -> Result<(), SynthesisError> {
+        let rate = self.permutation.rate();
+        let capacity = self.permutation.capacity();
+        let mut remaining_output = output;
+
+        loop {
+            // if we can finish in this call
+            if rate_start_index + remaining_output.len() <= rate {
+                remaining_output.clone_from_slice(
+                    &self.state[capacity + rate_start_index
+                        ..(capacity + remaining_output.len() + rate_start_index)],
+                );
+                self.mode = DuplexSpongeMode::Squeezing {
+                    next_squeeze_index: rate_start_index + remaining_output.len(),
+                };
+                return Ok(());
+            }
+            // otherwise squeeze (rate - rate_start_index) elements
+            let num_elements_squeezed = rate - rate_start_index;
+            remaining_output[..num_elements_squeezed].clone_from_slice(
+                &self.state[capacity + rate_start_index
+                    ..(capacity + num_elements_squeezed + rate_start_index)],
+            );
+
+            // Unless we are done with squeezing in this call, permute.
+            if remaining_output.len() != rate {
+                self.apply_permutation()?;
+            }
+            // Repeat with updated output slices and rate start index
+            remaining_output = &mut remaining_output[num_elements_squeezed..];
+            rate_start_index = 0;
+        }
+    }
+}
+
+impl<F: PrimeField, P: FieldPermutationGadget<F>> CryptographicSpongeVar<F, DuplexSponge<F, P>>
+    for DuplexSpongeVar<F, P>
+{
+    type Parameters = P;
+
+    #[tracing::instrument(target = "r1cs", skip(cs, permutation))]
+    fn new(cs: ConstraintSystemRef<F>, permutation: &P) -> Self {
+        let zero = FpVar::<F>::zero();
+        let state = vec![zero; permutation.rate() + permutation.capacity()];
+        let mode = DuplexSpongeMode::Absorbing {
+            next_absorb_index: 0,
+        };
+
+        Self {
+            cs,
+            permutation: permutation.clone(),
+            state,
+            mode,
+        }
+    }
+
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    fn cs(&self) -> ConstraintSystemRef<F> {
+        self.cs.clone()
+    }
+
+    fn rate(&self) -> usize {
+        self.permutation.rate()
+    }
+
+    fn capacity(&self) -> usize {
+        self.permutation.capacity()
+    }
+
+    fn security_level(&self) -> usize {
+        field_sponge_security_level::<F>(self.permutation.capacity())
+    }
+
+    #[tracing::instrument(target = "r1cs", skip(self, input))]
+    fn absorb(&mut self, input: &impl AbsorbGadget<F>) -> Result<(), SynthesisError> {
+        let input = input.to_sponge_field_elements()?;
+        if input.is_empty() {
+            return Ok(());
+        }
+        #[cfg(feature = "trace")]
+        tracing::trace!(
+            target: "ark_sponge",
+            sponge = "duplex",
+            num_elements = input.len(),
+            mode = ?self.mode,
+            "absorb"
+        );
+
+        match self.mode {
+            DuplexSpongeMode::Absorbing { next_absorb_index } => {
+                let mut absorb_index = next_absorb_index;
+                if absorb_index == self.permutation.rate() {
+                    self.apply_permutation()?;
+                    absorb_index = 0;
+                }
+                self.absorb_internal(absorb_index, input.as_slice())?;
+            }
+            // The squeeze that put the sponge in this mode already permuted after the last
+            // absorb, and the next squeeze permutes again, so the input can go straight into
+            // the rate.
+            DuplexSpongeMode::Squeezing {
+                next_squeeze_index: _,
+            } => {
+                self.absorb_internal(0, input.as_slice())?;
+            }
+        };
+
+        Ok(())
+    }
+
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    fn squeeze_bytes(&mut self, num_bytes: usize) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        let src_elements =
+            self.squeeze_field_elements(squeeze_bytes_num_elements::<F>(num_bytes))?;
+        bytes_from_squeezed_element_vars(&src_elements, num_bytes)
+    }
+
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    fn squeeze_bits(&mut self, num_bits: usize) -> Result<Vec<Boolean<F>>, SynthesisError> {
+        let src_elements = self.squeeze_field_elements(squeeze_bits_num_elements::<F>(num_bits))?;
+        bits_from_squeezed_element_vars(&src_elements, num_bits)
+    }
+
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    fn squeeze_field_elements(
+        &mut self,
+        num_elements: usize,
+    ) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        #[cfg(feature = "trace")]
+        tracing::trace!(
+            target: "ark_sponge",
+            sponge = "duplex",
+            num_elements = num_elements,
+            mode = ?self.mode,
+            "squeeze"
+        );
+        let zero = FpVar::zero();
+        let mut squeezed_elems = vec![zero; num_elements];
+        match self.mode {
+            DuplexSpongeMode::Absorbing { .. } => {
+                self.finish_absorb()?;
+                self.squeeze_internal(0, &mut squeezed_elems)?;
+            }
+            DuplexSpongeMode::Squeezing { next_squeeze_index } => {
+                let mut squeeze_index = next_squeeze_index;
+                if squeeze_index == self.permutation.rate() {
+                    self.apply_permutation()?;
+                    squeeze_index = 0;
+                }
+                self.squeeze_internal(squeeze_index, &mut squeezed_elems)?;
+            }
+        };
+
+        Ok(squeezed_elems)
+    }
+}
+
+/// Merges two sponge gadgets under a [`Boolean`], so that a circuit can continue a single
+/// transcript after a data-dependent branch.
+///
+/// The mode is part of the structure of the circuit, so both branches must have absorbed and
+/// squeezed the same number of elements; otherwise [`SynthesisError::Unsatisfiable`] is
+/// returned. Both sponges must use the same permutation, and that of `true_value` is kept.
+impl<F: PrimeField, P: FieldPermutationGadget<F>> CondSelectGadget<F> for DuplexSpongeVar<F, P> {
+    #[tracing::instrument(target = "r1cs", skip(cond, true_value, false_value))]
+    fn conditionally_select(
+        cond: &Boolean<F>,
+        true_value: &Self,
+        false_value: &Self,
+    ) -> Result<Self, SynthesisError> {
+        if true_value.mode != false_value.mode {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let mut sponge = true_value.clone();
+        sponge.cs = true_value.cs.clone().or(false_value.cs.clone());
+        sponge.state = select_state(cond, &true_value.state, &false_value.state)?;
+        Ok(sponge)
+    }
+}
+
+/// The gadget counterpart of [`DuplexSpongeState`]: the state of a [`DuplexSpongeVar`].
+///
+/// The mode is part of the structure of the circuit, so the native state must be known when
+/// allocating, and only the elements of the state are allocated as variables.
+#[derive(Clone)]
+pub struct DuplexSpongeStateVar<F: PrimeField> {
+    state: Vec<FpVar<F>>,
+    mode: DuplexSpongeMode,
+}
+
+impl<F: PrimeField> AllocVar<DuplexSpongeState<F>, F> for DuplexSpongeStateVar<F> {
+    fn new_variable<T: Borrow<DuplexSpongeState<F>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let native = f()?;
+        let native = native.borrow();
+        Ok(Self {
+            state: Vec::new_variable(ns.cs(), || Ok(native.state.clone()), mode)?,
+            mode: native.mode.clone(),
+        })
+    }
+}
+
+impl<F: PrimeField> R1CSVar<F> for DuplexSpongeStateVar<F> {
+    type Value = DuplexSpongeState<F>;
+
+    fn cs(&self) -> ConstraintSystemRef<F> {
+        self.state.cs()
+    }
+
+    fn value(&self) -> Result<Self::Value, SynthesisError> {
+        Ok(DuplexSpongeState {
+            state: self.state.value()?,
+            mode: self.mode.clone(),
+        })
+    }
+}
+
+impl<F: PrimeField, P: FieldPermutationGadget<F>> SpongeExtVar<F, DuplexSponge<F, P>>
+    for DuplexSpongeVar<F, P>
+{
+    type State = DuplexSpongeStateVar<F>;
+
+    fn from_state(cs: ConstraintSystemRef<F>, state: Self::State, permutation: &P) -> Self {
+        let mut sponge = Self::new(cs, permutation);
+        sponge.mode = state.mode;
+        sponge.state = state.state;
+        sponge
+    }
+
+    fn into_state(self) -> Self::State {
+        Self::State {
+            state: self.state,
+            mode: self.mode,
+        }
+    }
+}
@@ -0,0 +1,570 @@
+#[cfg(feature = "stats")]
+use crate::SpongeStats;
+use crate::{
+    batch_field_cast, bits_from_squeezed_elements, bytes_from_squeezed_elements,
+    check_sponge_state, deserialize_state_version, field_sponge_security_level,
+    squeeze_bits_num_elements, squeeze_bytes_num_elements,
+    squeeze_field_elements_with_sizes_default_impl, Absorb, CryptographicSponge, DuplexSpongeMode,
+    FieldBasedCryptographicSponge, FieldElementSize, SpongeError, SpongeExt, SPONGE_STATE_VERSION,
+};
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use ark_std::any::TypeId;
+use ark_std::vec;
+use ark_std::vec::Vec;
+
+/// constraints for the duplex sponge
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+/// a duplex sponge whose absorbing and squeezing phases are checked by the type system
+pub mod phased;
+
+/// A permutation of a state of `rate + capacity` field elements.
+///
+/// Any such permutation gives a sponge through [`DuplexSponge`], which absorbs into and squeezes
+/// from the last `rate` elements of the state, exactly as [`PoseidonSponge`] does.
+///
+/// [`PoseidonSponge`]: crate::poseidon::PoseidonSponge
+pub trait FieldPermutation<F: PrimeField>: Clone {
+    /// The rate (in terms of number of field elements).
+    fn rate(&self) -> usize;
+
+    /// The capacity (in terms of number of field elements).
+    fn capacity(&self) -> usize;
+
+    /// Applies the permutation to `state`, which has `rate + capacity` elements.
+    fn permute(&self, state: &mut [F]);
+
+    /// Applies the permutation to each of `states`, which are independent. Implementations may
+    /// evaluate the permutations together, which is faster than permuting the states one by one.
+    fn permute_batch(&self, states: &mut [Vec<F>]) {
+        for state in states {
+            self.permute(state);
+        }
+    }
+}
+
+/// A duplex sponge over the permutation `P`.
+///
+/// The permutation (including its parameters) is the `Parameters` of the sponge.
+#[derive(Clone)]
+pub struct DuplexSponge<F: PrimeField, P: FieldPermutation<F>> {
+    /// The permutation, including its parameters.
+    pub permutation: P,
+
+    // Sponge State
+    /// Current sponge's state (current elements in the permutation block)
+    pub state: Vec<F>,
+    /// Current mode (whether its absorbing or squeezing)
+    pub mode: DuplexSpongeMode,
+    /// The counters of the work done by the sponge.
+    #[cfg(feature = "stats")]
+    stats: SpongeStats,
+}
+
+impl<F: PrimeField, P: FieldPermutation<F>> DuplexSponge<F, P> {
+    fn apply_permutation(&mut self) {
+        self.permutation.permute(&mut self.state);
+        #[cfg(feature = "trace")]
+        tracing::trace!(
+            target: "ark_sponge",
+            sponge = "duplex",
+            "permute"
+        );
+        #[cfg(feature = "stats")]
+        {
+            self.stats.num_permutations += 1;
+        }
+    }
+
+    /// Returns the counters of the permutations, absorbed elements, and squeezed elements of this
+    /// sponge since it was created.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> SpongeStats {
+        self.stats
+    }
+
+    /// Applies the permutation now, so that the next absorb or squeeze starts at the beginning of
+    /// the rate, e.g. for a protocol that derives its challenges only at rate boundaries. The rest
+    /// of the current block is skipped: no padding is applied, and unread outputs are dropped.
+    pub fn permute(&mut self) {
+        self.apply_permutation();
+        self.mode = match self.mode {
+            DuplexSpongeMode::Absorbing { .. } => DuplexSpongeMode::Absorbing {
+                next_absorb_index: 0,
+            },
+            DuplexSpongeMode::Squeezing { .. } => DuplexSpongeMode::Squeezing {
+                next_squeeze_index: 0,
+            },
+        };
+    }
+
+    /// Closes the absorb phase: applies the permutation, as the next squeeze would do, so that the
+    /// permutation happens now and the next squeeze reads the state directly. Squeezing gives the
+    /// same outputs with or without this call. Does nothing if the sponge is already squeezing.
+    pub fn finish_absorb(&mut self) {
+        if let DuplexSpongeMode::Absorbing { .. } = self.mode {
+            self.apply_permutation();
+            self.mode = DuplexSpongeMode::Squeezing {
+                next_squeeze_index: 0,
+            };
+        }
+    }
+
+    /// Permutes the state and zeroes its rate, so that the inputs absorbed before cannot be
+    /// recovered from the state afterwards. The sponge is then absorbing.
+    pub fn ratchet(&mut self) {
+        self.apply_permutation();
+        let capacity = self.permutation.capacity();
+        for elem in &mut self.state[capacity..] {
+            *elem = F::zero();
+        }
+        self.mode = DuplexSpongeMode::Absorbing {
+            next_absorb_index: 0,
+        };
+    }
+
+    /// Restores the zero state of a new sponge, so that one sponge can hash many inputs without
+    /// being created again. The permutation and the allocated state are reused, and the
+    /// statistics are kept.
+    pub fn reset(&mut self) {
+        for elem in &mut self.state {
+            *elem = F::zero();
+        }
+        self.mode = DuplexSpongeMode::Absorbing {
+            next_absorb_index: 0,
+        };
+    }
+
+    fn absorb_elements(&mut self, elems: &[F]) {
+        if elems.is_empty() {
+            return;
+        }
+        #[cfg(feature = "trace")]
+        tracing::trace!(
+            target: "ark_sponge",
+            sponge = "duplex",
+            num_elements = elems.len(),
+            mode = ?self.mode,
+            "absorb"
+        );
+        #[cfg(feature = "stats")]
+        {
+            self.stats.num_absorbed += elems.len() as u64;
+        }
+
+        match self.mode {
+            DuplexSpongeMode::Absorbing { next_absorb_index } => {
+                let mut absorb_index = next_absorb_index;
+                if absorb_index == self.permutation.rate() {
+                    self.apply_permutation();
+                    absorb_index = 0;
+                }
+                self.absorb_internal(absorb_index, elems);
+            }
+            // The squeeze that put the sponge in this mode already permuted after the last
+            // absorb, and the next squeeze permutes again, so the input can go straight into
+            // the rate.
+            DuplexSpongeMode::Squeezing {
+                next_squeeze_index: _,
+            } => {
+                self.absorb_internal(0, elems);
+            }
+        };
+    }
+
+    // Absorbs everything in elements, this does not end in an absorbtion.
+    fn absorb_internal(&mut self, mut rate_start_index: usize, elements: &[F]) {
+        let rate = self.permutation.rate();
+        let capacity = self.permutation.capacity();
+        let mut remaining_elements = elements;
+
+        loop {
+            // if we can finish in this call
+            if rate_start_index + remaining_elements.len() <= rate {
+                for (i, element) in remaining_elements.iter().enumerate() {
+                    self.state[capacity + i + rate_start_index] += element;
+                }
+                self.mode = DuplexSpongeMode::Absorbing {
+                    next_absorb_index: rate_start_index + remaining_elements.len(),
+                };
+
+                return;
+            }
+            // otherwise absorb (rate - rate_start_index) elements
+            let num_elements_absorbed = rate - rate_start_index;
+            for (i, element) in remaining_elements
+                .iter()
+                .enumerate()
+                .take(num_elements_absorbed)
+            {
+                self.state[capacity + i + rate_start_index] += element;
+            }
+            self.apply_permutation();
+            // the input elements got truncated by num elements absorbed
+            remaining_elements = &remaining_elements[num_elements_absorbed..];
+            rate_start_index = 0;
+        }
+    }
+
+    // Squeeze |output| many elements. This does not end in a squeeze
+    fn squeeze_internal(&mut self, mut rate_start_index: usize, output: &mut [F]) {
+        let rate = self.permutation.rate();
+        let capacity = self.permutation.capacity();
+        let mut output_remaining = output;
+
+        loop {
+            // if we can finish in this call
+            if rate_start_index + output_remaining.len() <= rate {
+                output_remaining.clone_from_slice(
+                    &self.state[capacity + rate_start_index
+                        ..(capacity + output_remaining.len() + rate_start_index)],
+                );
+                self.mode = DuplexSpongeMode::Squeezing {
+                    next_squeeze_index: rate_start_index + output_remaining.len(),
+                };
+                return;
+            }
+            // otherwise squeeze (rate - rate_start_index) elements
+            let num_elements_squeezed = rate - rate_start_index;
+            output_remaining[..num_elements_squeezed].clone_from_slice(
+                &self.state[capacity + rate_start_index
+                    ..(capacity + num_elements_squeezed + rate_start_index)],
+            );
+
+            // Unless we are done with squeezing in this call, permute.
+            if output_remaining.len() != rate {
+                self.apply_permutation();
+            }
+            // Repeat with updated output slices
+            output_remaining = &mut output_remaining[num_elements_squeezed..];
+            rate_start_index = 0;
+        }
+    }
+}
+
+/// Zeroes the state, which may depend on secret inputs, and resets the sponge to absorbing.
+#[cfg(feature = "zeroize")]
+impl<F: PrimeField, P: FieldPermutation<F>> zeroize::Zeroize for DuplexSponge<F, P> {
+    fn zeroize(&mut self) {
+        self.state.iter_mut().for_each(|elem| elem.zeroize());
+        self.mode = DuplexSpongeMode::Absorbing {
+            next_absorb_index: 0,
+        };
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<F: PrimeField, P: FieldPermutation<F>> Drop for DuplexSponge<F, P> {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<F: PrimeField, P: FieldPermutation<F>> zeroize::ZeroizeOnDrop for DuplexSponge<F, P> {}
+
+impl<F: PrimeField, P: FieldPermutation<F>> CryptographicSponge for DuplexSponge<F, P> {
+    type Parameters = P;
+
+    fn new(permutation: &Self::Parameters) -> Self {
+        let state = vec![F::zero(); permutation.rate() + permutation.capacity()];
+        let mode = DuplexSpongeMode::Absorbing {
+            next_absorb_index: 0,
+        };
+
+        Self {
+            permutation: permutation.clone(),
+            state,
+            mode,
+            #[cfg(feature = "stats")]
+            stats: SpongeStats::default(),
+        }
+    }
+
+    fn rate(&self) -> usize {
+        self.permutation.rate()
+    }
+
+    fn capacity(&self) -> usize {
+        self.permutation.capacity()
+    }
+
+    fn security_level(&self) -> usize {
+        field_sponge_security_level::<F>(self.permutation.capacity())
+    }
+
+    fn absorb(&mut self, input: &impl Absorb) {
+        let elems = input.to_sponge_field_elements_as_vec::<F>();
+        self.absorb_elements(&elems);
+    }
+
+    fn squeeze_bytes(&mut self, num_bytes: usize) -> Vec<u8> {
+        let src_elements =
+            self.squeeze_native_field_elements(squeeze_bytes_num_elements::<F>(num_bytes));
+        bytes_from_squeezed_elements(&src_elements, num_bytes)
+    }
+
+    fn squeeze_bits(&mut self, num_bits: usize) -> Vec<bool> {
+        let src_elements =
+            self.squeeze_native_field_elements(squeeze_bits_num_elements::<F>(num_bits));
+        bits_from_squeezed_elements(&src_elements, num_bits)
+    }
+
+    fn squeeze_field_elements_with_sizes<F2: PrimeField>(
+        &mut self,
+        sizes: &[FieldElementSize],
+    ) -> Vec<F2> {
+        if F::characteristic() == F2::characteristic() {
+            // native case
+            let mut buf = Vec::with_capacity(sizes.len());
+            batch_field_cast(
+                &self.squeeze_native_field_elements_with_sizes(sizes),
+                &mut buf,
+            )
+            .unwrap();
+            buf
+        } else {
+            squeeze_field_elements_with_sizes_default_impl(self, sizes)
+        }
+    }
+
+    fn squeeze_field_elements<F2: PrimeField>(&mut self, num_elements: usize) -> Vec<F2> {
+        if TypeId::of::<F>() == TypeId::of::<F2>() {
+            let result = self.squeeze_native_field_elements(num_elements);
+            let mut cast = Vec::with_capacity(result.len());
+            batch_field_cast(&result, &mut cast).unwrap();
+            cast
+        } else {
+            self.squeeze_field_elements_with_sizes::<F2>(
+                vec![FieldElementSize::Full; num_elements].as_slice(),
+            )
+        }
+    }
+}
+
+impl<F: PrimeField, P: FieldPermutation<F>> FieldBasedCryptographicSponge<F>
+    for DuplexSponge<F, P>
+{
+    fn absorb_native_field_elements(&mut self, elems: &[F]) {
+        self.absorb_elements(elems);
+    }
+
+    fn squeeze_native_field_elements(&mut self, num_elements: usize) -> Vec<F> {
+        #[cfg(feature = "trace")]
+        tracing::trace!(
+            target: "ark_sponge",
+            sponge = "duplex",
+            num_elements = num_elements,
+            mode = ?self.mode,
+            "squeeze"
+        );
+        #[cfg(feature = "stats")]
+        {
+            self.stats.num_squeezed += num_elements as u64;
+        }
+        let mut squeezed_elems = vec![F::zero(); num_elements];
+        match self.mode {
+            DuplexSpongeMode::Absorbing { .. } => {
+                self.finish_absorb();
+                self.squeeze_internal(0, &mut squeezed_elems);
+            }
+            DuplexSpongeMode::Squeezing { next_squeeze_index } => {
+                let mut squeeze_index = next_squeeze_index;
+                if squeeze_index == self.permutation.rate() {
+                    self.apply_permutation();
+                    squeeze_index = 0;
+                }
+                self.squeeze_internal(squeeze_index, &mut squeezed_elems);
+            }
+        };
+
+        squeezed_elems
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// Stores the state of a duplex sponge. Does not store the permutation.
+///
+/// The state can be serialized to checkpoint a transcript and resumed later with
+/// [`SpongeExt::from_state`], using the same permutation. The serialization is the version byte
+/// [`SPONGE_STATE_VERSION`], the [`DuplexSpongeMode`], and the state, in the format of
+/// [`PoseidonSpongeState`](crate::poseidon::PoseidonSpongeState).
+pub struct DuplexSpongeState<F: PrimeField> {
+    state: Vec<F>,
+    mode: DuplexSpongeMode,
+}
+
+impl<F: PrimeField> CanonicalSerialize for DuplexSpongeState<F> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        SPONGE_STATE_VERSION.serialize(&mut writer)?;
+        self.mode.serialize(&mut writer)?;
+        self.state.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        SPONGE_STATE_VERSION.serialized_size()
+            + self.mode.serialized_size()
+            + self.state.serialized_size()
+    }
+}
+
+impl<F: PrimeField> CanonicalDeserialize for DuplexSpongeState<F> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        deserialize_state_version(&mut reader)?;
+        Ok(Self {
+            mode: DuplexSpongeMode::deserialize(&mut reader)?,
+            state: Vec::deserialize(&mut reader)?,
+        })
+    }
+}
+
+impl<F: PrimeField, P: FieldPermutation<F>> SpongeExt for DuplexSponge<F, P> {
+    type State = DuplexSpongeState<F>;
+
+    /// # Panics
+    ///
+    /// Panics if `state` does not fit `permutation`, as checked by [`Self::try_from_state`].
+    fn from_state(state: Self::State, permutation: &Self::Parameters) -> Self {
+        Self::try_from_state(state, permutation).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Returns [`SpongeError::InvalidState`] if `state` does not have `rate + capacity` elements,
+    /// or if the index of its mode is beyond the rate.
+    fn try_from_state(
+        state: Self::State,
+        permutation: &Self::Parameters,
+    ) -> Result<Self, SpongeError> {
+        check_sponge_state(
+            state.state.len(),
+            &state.mode,
+            permutation.rate(),
+            permutation.capacity(),
+        )?;
+        let mut sponge = Self::new(permutation);
+        sponge.mode = state.mode;
+        sponge.state = state.state;
+        Ok(sponge)
+    }
+
+    fn into_state(mut self) -> Self::State {
+        Self::State {
+            state: ark_std::mem::take(&mut self.state),
+            mode: self.mode.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::duplex::{DuplexSponge, DuplexSpongeState};
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::{PoseidonParameters, PoseidonPermutation, PoseidonSponge};
+    use crate::{CryptographicSponge, DuplexSpongeMode, FieldBasedCryptographicSponge, SpongeExt};
+    use ark_ff::UniformRand;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use ark_std::test_rng;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn test_matches_poseidon_sponge() {
+        let mut rng = test_rng();
+        let parameters = poseidon_parameters_for_test::<Fr>();
+        let mut duplex_sponge = DuplexSponge::new(&PoseidonPermutation::new(&parameters));
+        let mut poseidon_sponge = PoseidonSponge::new(&parameters);
+
+        for num_elements in [0, 1, 2, 3, 5] {
+            let input: Vec<_> = (0..num_elements).map(|_| Fr::rand(&mut rng)).collect();
+            duplex_sponge.absorb(&input);
+            poseidon_sponge.absorb(&input);
+            assert_eq!(
+                duplex_sponge.squeeze_native_field_elements(num_elements),
+                poseidon_sponge.squeeze_native_field_elements(num_elements)
+            );
+            assert_eq!(
+                duplex_sponge.squeeze_bytes(40),
+                poseidon_sponge.squeeze_bytes(40)
+            );
+        }
+    }
+
+    #[test]
+    fn test_ratchet() {
+        let parameters = poseidon_parameters_for_test::<Fr>();
+        let mut duplex_sponge = DuplexSponge::new(&PoseidonPermutation::new(&parameters));
+        let mut poseidon_sponge = PoseidonSponge::new(&parameters);
+
+        duplex_sponge.absorb(&Fr::from(1u64));
+        poseidon_sponge.absorb(&Fr::from(1u64));
+        duplex_sponge.ratchet();
+        poseidon_sponge.ratchet();
+        assert_eq!(duplex_sponge.state, poseidon_sponge.state);
+        assert_eq!(
+            duplex_sponge.squeeze_native_field_elements(3),
+            poseidon_sponge.squeeze_native_field_elements(3)
+        );
+    }
+
+    #[test]
+    fn test_reset() {
+        let permutation = PoseidonPermutation::new(&poseidon_parameters_for_test::<Fr>());
+        let mut sponge = DuplexSponge::new(&permutation);
+        sponge.absorb(&Fr::from(1u64));
+        sponge.squeeze_native_field_elements(3);
+        sponge.reset();
+        assert_eq!(
+            sponge.squeeze_native_field_elements(3),
+            DuplexSponge::new(&permutation).squeeze_native_field_elements(3)
+        );
+    }
+
+    #[test]
+    fn test_permute_and_finish_absorb() {
+        let parameters = poseidon_parameters_for_test::<Fr>();
+        let mut duplex_sponge = DuplexSponge::new(&PoseidonPermutation::new(&parameters));
+        let mut poseidon_sponge = PoseidonSponge::new(&parameters);
+        for sponge_input in [Fr::from(1u8), Fr::from(2u8)] {
+            duplex_sponge.absorb(&sponge_input);
+            poseidon_sponge.absorb(&sponge_input);
+            duplex_sponge.permute();
+            poseidon_sponge.permute();
+        }
+        duplex_sponge.finish_absorb();
+        poseidon_sponge.finish_absorb();
+        assert_eq!(duplex_sponge.state, poseidon_sponge.state);
+        assert_eq!(
+            duplex_sponge.squeeze_native_field_elements(3),
+            poseidon_sponge.squeeze_native_field_elements(3)
+        );
+    }
+
+    #[test]
+    fn test_checkpoint_state() {
+        let permutation = PoseidonPermutation::new(&poseidon_parameters_for_test::<Fr>());
+        let mut sponge = DuplexSponge::new(&permutation);
+        sponge.absorb(&Fr::from(1u64));
+        sponge.squeeze_native_field_elements(1);
+        let expected = sponge.clone().squeeze_native_field_elements(3);
+
+        let mut checkpoint = Vec::new();
+        sponge.into_state().serialize(&mut checkpoint).unwrap();
+        let state = DuplexSpongeState::<Fr>::deserialize(checkpoint.as_slice()).unwrap();
+        let mut resumed = DuplexSponge::from_state(state.clone(), &permutation);
+        assert_eq!(resumed.squeeze_native_field_elements(3), expected);
+
+        // the state must fit the permutation
+        let wider = PoseidonPermutation::new(&PoseidonParameters::<Fr>::generate(3, 1, 128));
+        assert!(DuplexSponge::try_from_state(state.clone(), &wider).is_err());
+        let mut bad_index = state;
+        bad_index.mode = DuplexSpongeMode::Absorbing {
+            next_absorb_index: 3,
+        };
+        assert!(DuplexSponge::try_from_state(bad_index, &permutation).is_err());
+
+        // the mode tag must be valid
+        checkpoint[1] = 2;
+        assert!(DuplexSpongeState::<Fr>::deserialize(checkpoint.as_slice()).is_err());
+    }
+}
@@ -0,0 +1,181 @@
+use crate::duplex::FieldPermutation;
+use crate::Absorb;
+use ark_ff::PrimeField;
+use ark_std::marker::PhantomData;
+use ark_std::vec;
+use ark_std::vec::Vec;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// The phase of a [`PhasedSponge`], either [`Absorbing`] or [`Squeezing`].
+pub trait Phase: sealed::Sealed {}
+
+/// The phase of a [`PhasedSponge`] that absorbs inputs.
+#[derive(Clone, Copy, Debug)]
+pub struct Absorbing;
+
+/// The phase of a [`PhasedSponge`] that squeezes outputs.
+#[derive(Clone, Copy, Debug)]
+pub struct Squeezing;
+
+impl sealed::Sealed for Absorbing {}
+impl sealed::Sealed for Squeezing {}
+impl Phase for Absorbing {}
+impl Phase for Squeezing {}
+
+/// A duplex sponge over the permutation `P` whose phase is part of its type.
+///
+/// A `PhasedSponge<F, P, Absorbing>` can only absorb, and a `PhasedSponge<F, P, Squeezing>` can
+/// only squeeze. Switching between phases consumes the sponge. Starting to squeeze always permutes
+/// the state, so every phase is bound to the output, even when it is empty, while starting to
+/// absorb does not, since the next squeeze permutes anyway. For non-empty phases, the outputs are
+/// the same as those of [`DuplexSponge`](crate::duplex::DuplexSponge).
+#[derive(Clone)]
+pub struct PhasedSponge<F: PrimeField, P: FieldPermutation<F>, S: Phase> {
+    permutation: P,
+    state: Vec<F>,
+    /// The next position of the rate to absorb into or squeeze from.
+    position: usize,
+    _phase: PhantomData<S>,
+}
+
+impl<F: PrimeField, P: FieldPermutation<F>, S: Phase> PhasedSponge<F, P, S> {
+    fn permute(&mut self) {
+        self.permutation.permute(&mut self.state);
+    }
+
+    fn into_phase<T: Phase>(mut self) -> PhasedSponge<F, P, T> {
+        PhasedSponge {
+            permutation: self.permutation.clone(),
+            state: ark_std::mem::take(&mut self.state),
+            position: 0,
+            _phase: PhantomData,
+        }
+    }
+}
+
+/// Zeroes the state, which may depend on secret inputs.
+#[cfg(feature = "zeroize")]
+impl<F: PrimeField, P: FieldPermutation<F>, S: Phase> zeroize::Zeroize for PhasedSponge<F, P, S> {
+    fn zeroize(&mut self) {
+        self.state.iter_mut().for_each(|elem| elem.zeroize());
+        self.position.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<F: PrimeField, P: FieldPermutation<F>, S: Phase> Drop for PhasedSponge<F, P, S> {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<F: PrimeField, P: FieldPermutation<F>, S: Phase> zeroize::ZeroizeOnDrop
+    for PhasedSponge<F, P, S>
+{
+}
+
+impl<F: PrimeField, P: FieldPermutation<F>> PhasedSponge<F, P, Absorbing> {
+    /// Creates a sponge in the absorbing phase, with a zero state.
+    pub fn new(permutation: &P) -> Self {
+        Self {
+            permutation: permutation.clone(),
+            state: vec![F::zero(); permutation.rate() + permutation.capacity()],
+            position: 0,
+            _phase: PhantomData,
+        }
+    }
+
+    /// Absorbs `input`, permuting whenever the rate is full.
+    pub fn absorb(&mut self, input: &impl Absorb) {
+        let rate = self.permutation.rate();
+        let capacity = self.permutation.capacity();
+        for elem in input.to_sponge_field_elements_as_vec::<F>() {
+            if self.position == rate {
+                self.permute();
+                self.position = 0;
+            }
+            self.state[capacity + self.position] += elem;
+            self.position += 1;
+        }
+    }
+
+    /// Ends the absorbing phase, and starts squeezing.
+    pub fn start_squeezing(mut self) -> PhasedSponge<F, P, Squeezing> {
+        self.permute();
+        self.into_phase()
+    }
+}
+
+impl<F: PrimeField, P: FieldPermutation<F>> PhasedSponge<F, P, Squeezing> {
+    /// Squeezes `num_elements` field elements, permuting whenever the rate is exhausted.
+    pub fn squeeze_native_field_elements(&mut self, num_elements: usize) -> Vec<F> {
+        let rate = self.permutation.rate();
+        let capacity = self.permutation.capacity();
+        let mut output = Vec::with_capacity(num_elements);
+        for _ in 0..num_elements {
+            if self.position == rate {
+                self.permute();
+                self.position = 0;
+            }
+            output.push(self.state[capacity + self.position]);
+            self.position += 1;
+        }
+        output
+    }
+
+    /// Ends the squeezing phase, and starts absorbing.
+    pub fn start_absorbing(self) -> PhasedSponge<F, P, Absorbing> {
+        self.into_phase()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::duplex::phased::PhasedSponge;
+    use crate::duplex::DuplexSponge;
+    use crate::poseidon2::Poseidon2Parameters;
+    use crate::{CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_ff::UniformRand;
+    use ark_std::test_rng;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn test_matches_duplex_sponge() {
+        let mut rng = test_rng();
+        let parameters = Poseidon2Parameters::<Fr>::generate(2, 1, 128);
+        let mut duplex_sponge = DuplexSponge::new(&parameters);
+        let mut phased_sponge = PhasedSponge::new(&parameters);
+
+        for num_elements in [1, 2, 3, 5] {
+            let input: Vec<_> = (0..num_elements).map(|_| Fr::rand(&mut rng)).collect();
+            duplex_sponge.absorb(&input);
+            phased_sponge.absorb(&input);
+
+            let mut squeezing = phased_sponge.start_squeezing();
+            assert_eq!(
+                squeezing.squeeze_native_field_elements(num_elements),
+                duplex_sponge.squeeze_native_field_elements(num_elements)
+            );
+            phased_sponge = squeezing.start_absorbing();
+        }
+    }
+
+    #[test]
+    fn test_empty_phases() {
+        let parameters = Poseidon2Parameters::<Fr>::generate(2, 1, 128);
+        let mut sponge1 = PhasedSponge::new(&parameters).start_squeezing();
+        let mut sponge2 = PhasedSponge::new(&parameters)
+            .start_squeezing()
+            .start_absorbing()
+            .start_squeezing();
+        assert_ne!(
+            sponge1.squeeze_native_field_elements(1),
+            sponge2.squeeze_native_field_elements(1)
+        );
+    }
+}
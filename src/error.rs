@@ -0,0 +1,114 @@
+use ark_std::fmt;
+
+/// An error of the operations of this crate whose inputs are invalid.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SpongeError {
+    /// More bits were requested than fit in the capacity of the field.
+    TooManyBits {
+        /// The number of bits requested.
+        num_bits: usize,
+        /// The maximum number of bits.
+        capacity: usize,
+    },
+    /// An input, of field elements or bytes, does not have the required length.
+    InvalidInputLength {
+        /// The required length.
+        expected: usize,
+        /// The length of the input.
+        found: usize,
+    },
+    /// An input, e.g. an IV, is longer than the construction supports.
+    InputTooLong {
+        /// The length of the input.
+        length: usize,
+        /// The maximum length.
+        max: usize,
+    },
+    /// Integers were requested from an empty range.
+    EmptyRange,
+    /// More distinct integers were requested than there are in the range.
+    TooManyIndices {
+        /// The number of distinct integers requested.
+        num_indices: usize,
+        /// The size of the range.
+        range: u64,
+    },
+    /// The squeezed candidates contain fewer distinct integers in the range than requested.
+    NotEnoughDistinctIndices {
+        /// The number of distinct integers found.
+        found: usize,
+        /// The number of distinct integers requested.
+        expected: usize,
+    },
+    /// An output longer than the construction supports was requested.
+    OutputTooLong {
+        /// The requested length.
+        length: usize,
+        /// The maximum length.
+        max: usize,
+    },
+    /// The parameters of a construction are invalid.
+    InvalidParameters(&'static str),
+    /// An encoding of a field element is not the encoding of its canonical representative, e.g.
+    /// an integer not smaller than the modulus.
+    NonCanonicalFieldElement,
+    /// A sponge state, e.g. a deserialized checkpoint, does not fit the parameters it is resumed
+    /// with.
+    InvalidState(&'static str),
+    /// None of the candidates of try-and-increment is the abscissa of a point of the curve.
+    NoCurvePoint,
+    /// A replayed transcript diverged from its log.
+    TranscriptMismatch {
+        /// The index of the first entry of the log that could not be reproduced.
+        entry: usize,
+    },
+}
+
+impl fmt::Display for SpongeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpongeError::TooManyBits { num_bits, capacity } => write!(
+                f,
+                "{} bits were requested, but the capacity of the field is {} bits",
+                num_bits, capacity
+            ),
+            SpongeError::InvalidInputLength { expected, found } => {
+                write!(f, "the input has length {} instead of {}", found, expected)
+            }
+            SpongeError::InputTooLong { length, max } => write!(
+                f,
+                "an input of length {} was given, but the maximum is {}",
+                length, max
+            ),
+            SpongeError::EmptyRange => write!(f, "the range is empty"),
+            SpongeError::TooManyIndices { num_indices, range } => write!(
+                f,
+                "{} distinct integers were requested from a range of {}",
+                num_indices, range
+            ),
+            SpongeError::NotEnoughDistinctIndices { found, expected } => write!(
+                f,
+                "the candidates contain {} distinct integers in the range instead of {}",
+                found, expected
+            ),
+            SpongeError::OutputTooLong { length, max } => write!(
+                f,
+                "an output of length {} was requested, but the maximum is {}",
+                length, max
+            ),
+            SpongeError::InvalidParameters(reason) => write!(f, "invalid parameters: {}", reason),
+            SpongeError::NonCanonicalFieldElement => {
+                write!(f, "the encoding of the field element is not canonical")
+            }
+            SpongeError::InvalidState(reason) => write!(f, "invalid sponge state: {}", reason),
+            SpongeError::NoCurvePoint => {
+                write!(f, "no point was found by try-and-increment")
+            }
+            SpongeError::TranscriptMismatch { entry } => {
+                write!(f, "the transcript diverged from its log at entry {}", entry)
+            }
+        }
+    }
+}
+
+impl ark_std::error::Error for SpongeError {}
@@ -0,0 +1,90 @@
+use crate::duplex::constraints::{DuplexSpongeVar, FieldPermutationGadget};
+use crate::gmimc::GmimcParameters;
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::SynthesisError;
+
+/// The gadget for [`GmimcSponge`](crate::gmimc::GmimcSponge).
+pub type GmimcSpongeVar<F> = DuplexSpongeVar<F, GmimcParameters<F>>;
+
+impl<F: PrimeField> FieldPermutationGadget<F> for GmimcParameters<F> {
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    fn permute_var(&self, state: &mut [FpVar<F>]) -> Result<(), SynthesisError> {
+        for constant in &self.round_constants {
+            let s = (&state[0] + *constant).pow_by_constant([self.alpha])?;
+            for elem in state[1..].iter_mut() {
+                *elem += &s;
+            }
+            state.rotate_left(1);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constraints::CryptographicSpongeVar;
+    use crate::duplex::constraints::FieldPermutationGadget;
+    use crate::duplex::FieldPermutation;
+    use crate::gmimc::constraints::GmimcSpongeVar;
+    use crate::gmimc::{GmimcParameters, GmimcSponge};
+    use crate::{CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_ff::UniformRand;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_relations::*;
+    use ark_std::test_rng;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn permutation_test() {
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::new_ref();
+        let parameters = GmimcParameters::<Fr>::generate(4, 1, 128);
+
+        let mut state: Vec<_> = (0..parameters.state_size())
+            .map(|_| Fr::rand(&mut rng))
+            .collect();
+        let mut state_var: Vec<_> = state
+            .iter()
+            .map(|v| FpVar::new_witness(ns!(cs, "state"), || Ok(*v)).unwrap())
+            .collect();
+
+        parameters.permute(&mut state);
+        parameters.permute_var(&mut state_var).unwrap();
+
+        assert_eq!(state_var.value().unwrap(), state);
+        assert!(cs.is_satisfied().unwrap());
+        // A single x^5 per round, which costs 3 constraints
+        assert_eq!(cs.num_constraints(), 3 * parameters.rounds());
+    }
+
+    #[test]
+    fn sponge_test() {
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::new_ref();
+        let parameters = GmimcParameters::<Fr>::generate(2, 1, 128);
+
+        let absorb: Vec<_> = (0..5).map(|_| Fr::rand(&mut rng)).collect();
+        let absorb_var: Vec<_> = absorb
+            .iter()
+            .map(|v| FpVar::new_input(ns!(cs, "absorb"), || Ok(*v)).unwrap())
+            .collect();
+
+        let mut native_sponge = GmimcSponge::<Fr>::new(&parameters);
+        let mut constraint_sponge = GmimcSpongeVar::<Fr>::new(cs.clone(), &parameters);
+
+        native_sponge.absorb(&absorb);
+        constraint_sponge.absorb(&absorb_var).unwrap();
+
+        let squeeze1 = native_sponge.squeeze_native_field_elements(3);
+        let squeeze2 = constraint_sponge.squeeze_field_elements(3).unwrap();
+
+        assert_eq!(squeeze2.value().unwrap(), squeeze1);
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
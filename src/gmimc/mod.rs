@@ -0,0 +1,170 @@
+use crate::duplex::{DuplexSponge, FieldPermutation};
+use crate::poseidon::find_poseidon_alpha;
+use crate::poseidon::grain_lfsr::PoseidonGrainLFSR;
+use crate::poseidon::round_numbers::log2;
+use ark_ff::{FpParameters, PrimeField};
+use ark_std::vec::Vec;
+
+/// constraints for GMiMC
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+/// A duplex sponge based on the [GMiMC-erf][gmimc] permutation.
+///
+/// [gmimc]: https://eprint.iacr.org/2019/397
+pub type GmimcSponge<F> = DuplexSponge<F, GmimcParameters<F>>;
+
+/// Parameters of the [GMiMC-erf][gmimc] permutation, an unbalanced Feistel network with an
+/// expanding round function.
+///
+/// Round `i` computes `s = (x_0 + round_constants[i])^alpha`, adds `s` to every other element
+/// of the state, and rotates the state to the left by one element. Each round thus costs a
+/// single S-box, whatever the size of the state.
+///
+/// [gmimc]: https://eprint.iacr.org/2019/397
+#[derive(Clone, Debug)]
+pub struct GmimcParameters<F: PrimeField> {
+    /// Exponent used in the S-box.
+    pub alpha: u64,
+    /// Round constants, one per round.
+    pub round_constants: Vec<F>,
+    /// The rate (in terms of number of field elements).
+    pub rate: usize,
+    /// The capacity (in terms of number of field elements).
+    pub capacity: usize,
+}
+
+impl<F: PrimeField> GmimcParameters<F> {
+    /// Initialize the parameters of the GMiMC-erf permutation.
+    pub fn new(alpha: u64, round_constants: Vec<F>, rate: usize, capacity: usize) -> Self {
+        assert!(alpha > 1, "alpha must be greater than 1");
+        assert!(
+            rate + capacity > 1,
+            "the state must have at least two elements"
+        );
+
+        Self {
+            alpha,
+            round_constants,
+            rate,
+            capacity,
+        }
+    }
+
+    /// Generate parameters for `F` providing `security_level` bits of security.
+    ///
+    /// The S-box exponent is the smallest `alpha` for which `x^alpha` is a permutation of `F`,
+    /// the number of rounds is computed by [`find_gmimc_rounds`], and the round constants are
+    /// sampled with the Grain LFSR, as for Poseidon.
+    pub fn generate(rate: usize, capacity: usize, security_level: usize) -> Self {
+        let state_size = rate + capacity;
+        let alpha = find_poseidon_alpha::<F>();
+        let rounds = find_gmimc_rounds(state_size, alpha, security_level);
+
+        let mut lfsr = PoseidonGrainLFSR::new(
+            false,
+            F::Params::MODULUS_BITS as u64,
+            state_size as u64,
+            rounds as u64,
+            0,
+        );
+        let round_constants = lfsr.get_field_elements_rejection_sampling::<F>(rounds);
+
+        Self::new(alpha, round_constants, rate, capacity)
+    }
+
+    /// The width of the permutation state, `t = rate + capacity`.
+    pub fn state_size(&self) -> usize {
+        self.rate + self.capacity
+    }
+
+    /// The number of rounds.
+    pub fn rounds(&self) -> usize {
+        self.round_constants.len()
+    }
+}
+
+impl<F: PrimeField> FieldPermutation<F> for GmimcParameters<F> {
+    fn rate(&self) -> usize {
+        self.rate
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn permute(&self, state: &mut [F]) {
+        for constant in &self.round_constants {
+            let s = (state[0] + constant).pow([self.alpha]);
+            for elem in state[1..].iter_mut() {
+                *elem += s;
+            }
+            state.rotate_left(1);
+        }
+    }
+}
+
+/// Computes the number of rounds of GMiMC-erf for a state of `state_size` elements.
+///
+/// The degree of the permutation and its inverse must exceed `2^security_level`, against
+/// interpolation and Gröbner basis attacks, which takes `log_alpha(2^security_level)` rounds in
+/// each direction. On top of these, `2 * state_size + 2` rounds account for the slow diffusion
+/// of the unbalanced Feistel network, against differential and integral attacks.
+pub fn find_gmimc_rounds(state_size: usize, alpha: u64, security_level: usize) -> usize {
+    let algebraic_rounds = (security_level as f64 / log2(alpha as f64)) as usize + 1;
+    2 * algebraic_rounds + 2 * state_size + 2
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::duplex::FieldPermutation;
+    use crate::gmimc::{find_gmimc_rounds, GmimcParameters, GmimcSponge};
+    use crate::{CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_ff::{Field, UniformRand};
+    use ark_std::test_rng;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn test_rounds() {
+        assert_eq!(find_gmimc_rounds(3, 5, 128), 2 * 56 + 8);
+        assert_eq!(find_gmimc_rounds(3, 3, 128), 2 * 81 + 8);
+        assert_eq!(find_gmimc_rounds(9, 5, 128), 2 * 56 + 20);
+    }
+
+    #[test]
+    fn test_permutation() {
+        let mut rng = test_rng();
+        let parameters = GmimcParameters::<Fr>::generate(2, 1, 128);
+        assert_eq!(parameters.rounds(), find_gmimc_rounds(3, 5, 128));
+
+        let input: Vec<_> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        let mut state = input.clone();
+        parameters.permute(&mut state);
+
+        // Invert the rounds one by one.
+        for constant in parameters.round_constants.iter().rev() {
+            state.rotate_right(1);
+            let s = (state[0] + constant).pow([parameters.alpha]);
+            for elem in state[1..].iter_mut() {
+                *elem -= s;
+            }
+        }
+        assert_eq!(state, input);
+    }
+
+    #[test]
+    fn test_sponge() {
+        let mut rng = test_rng();
+        let parameters = GmimcParameters::<Fr>::generate(2, 1, 128);
+        let input: Vec<_> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+
+        let mut sponge = GmimcSponge::new(&parameters);
+        sponge.absorb(&input);
+        let output = sponge.squeeze_native_field_elements(3);
+
+        let mut other = GmimcSponge::new(&parameters);
+        other.absorb(&input[..2].to_vec());
+        assert_ne!(other.squeeze_native_field_elements(3), output);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::duplex::constraints::{DuplexSpongeVar, FieldPermutationGadget};
+use crate::griffin::GriffinParameters;
+use crate::poseidon2::Poseidon2Parameters;
+use crate::rescue::constraints::inverse_s_box_var;
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::SynthesisError;
+
+/// The gadget for [`GriffinSponge`](crate::griffin::GriffinSponge).
+pub type GriffinSpongeVar<F> = DuplexSpongeVar<F, GriffinParameters<F>>;
+
+impl<F: PrimeField> GriffinParameters<F> {
+    fn apply_non_linear_layer_var(&self, state: &mut [FpVar<F>]) -> Result<(), SynthesisError> {
+        let input = state.to_vec();
+        state[0] = inverse_s_box_var(&input[0], self.alpha, &self.alpha_inverse)?;
+        state[1] = input[1].pow_by_constant([self.alpha])?;
+
+        // `(i - 1) * y_0 + y_1`
+        let mut l = state[1].clone();
+        for (i, (alpha_i, beta_i)) in (2..).zip(self.alphas_betas()) {
+            l += &state[0];
+            let l_i = if i > 2 { &l + &input[i - 1] } else { l.clone() };
+            state[i] = &input[i] * (l_i.square()? + &l_i * alpha_i + beta_i);
+        }
+
+        Ok(())
+    }
+}
+
+impl<F: PrimeField> FieldPermutationGadget<F> for GriffinParameters<F> {
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    fn permute_var(&self, state: &mut [FpVar<F>]) -> Result<(), SynthesisError> {
+        Poseidon2Parameters::apply_external_matrix_var(state);
+        for round in 0..self.rounds {
+            self.apply_non_linear_layer_var(state)?;
+            Poseidon2Parameters::apply_external_matrix_var(state);
+            if let Some(ark) = self.ark.get(round) {
+                for (elem, constant) in state.iter_mut().zip(ark) {
+                    *elem += *constant;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constraints::CryptographicSpongeVar;
+    use crate::duplex::constraints::FieldPermutationGadget;
+    use crate::duplex::FieldPermutation;
+    use crate::griffin::constraints::GriffinSpongeVar;
+    use crate::griffin::{GriffinParameters, GriffinSponge};
+    use crate::{CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_ff::UniformRand;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_relations::*;
+    use ark_std::test_rng;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn permutation_test() {
+        let mut rng = test_rng();
+        for rate in [2, 7] {
+            let cs = ConstraintSystem::new_ref();
+            let parameters = GriffinParameters::<Fr>::generate(rate, 1, 128);
+
+            let mut state: Vec<_> = (0..parameters.state_size())
+                .map(|_| Fr::rand(&mut rng))
+                .collect();
+            let mut state_var: Vec<_> = state
+                .iter()
+                .map(|v| FpVar::new_witness(ns!(cs, "state"), || Ok(*v)).unwrap())
+                .collect();
+
+            parameters.permute(&mut state);
+            parameters.permute_var(&mut state_var).unwrap();
+
+            assert_eq!(state_var.value().unwrap(), state);
+            assert!(cs.is_satisfied().unwrap());
+            // x^5 and x^(1/5) cost 3 constraints each, and every other element costs 2
+            assert_eq!(
+                cs.num_constraints(),
+                parameters.rounds * (6 + 2 * (parameters.state_size() - 2))
+            );
+        }
+    }
+
+    #[test]
+    fn sponge_test() {
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::new_ref();
+        let parameters = GriffinParameters::<Fr>::generate(2, 1, 128);
+
+        let absorb: Vec<_> = (0..5).map(|_| Fr::rand(&mut rng)).collect();
+        let absorb_var: Vec<_> = absorb
+            .iter()
+            .map(|v| FpVar::new_input(ns!(cs, "absorb"), || Ok(*v)).unwrap())
+            .collect();
+
+        let mut native_sponge = GriffinSponge::<Fr>::new(&parameters);
+        let mut constraint_sponge = GriffinSpongeVar::<Fr>::new(cs.clone(), &parameters);
+
+        native_sponge.absorb(&absorb);
+        constraint_sponge.absorb(&absorb_var).unwrap();
+
+        let squeeze1 = native_sponge.squeeze_native_field_elements(3);
+        let squeeze2 = constraint_sponge.squeeze_field_elements(3).unwrap();
+
+        assert_eq!(squeeze2.value().unwrap(), squeeze1);
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
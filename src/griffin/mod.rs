@@ -0,0 +1,267 @@
+use crate::duplex::{DuplexSponge, FieldPermutation};
+use crate::poseidon::find_poseidon_alpha;
+use crate::poseidon::grain_lfsr::PoseidonGrainLFSR;
+use crate::poseidon::round_numbers::log2;
+use crate::poseidon2::Poseidon2Parameters;
+use crate::rescue::inverse_exponent;
+use ark_ff::{FpParameters, PrimeField};
+use ark_std::vec::Vec;
+
+/// constraints for Griffin
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+/// A duplex sponge based on the [Griffin][griffin] permutation.
+///
+/// [griffin]: https://eprint.iacr.org/2022/403
+pub type GriffinSponge<F> = DuplexSponge<F, GriffinParameters<F>>;
+
+/// Parameters of the [Griffin][griffin] permutation.
+///
+/// Griffin applies the linear layer to the input, followed by `rounds` rounds. Each round
+/// applies the non-linear layer, then the linear layer, and then adds round constants, except
+/// for the last round.
+///
+/// The non-linear layer ("Horst") maps `x` to `y`, where `y_0 = x_0^(1/alpha)`,
+/// `y_1 = x_1^alpha`, and `y_i = x_i * (l_i^2 + alpha_i * l_i + beta_i)` for `i >= 2`, with
+/// `l_i = (i - 1) * y_0 + y_1 + x_(i - 1)` (without the last term for `i = 2`),
+/// `alpha_i = (i - 1) * alpha_2` and `beta_i = (i - 1)^2 * beta_2`. The linear layer is the
+/// external matrix of [Poseidon2](crate::poseidon2).
+///
+/// [griffin]: https://eprint.iacr.org/2022/403
+#[derive(Clone, Debug)]
+pub struct GriffinParameters<F: PrimeField> {
+    /// Number of rounds.
+    pub rounds: usize,
+    /// Exponent used in the S-boxes.
+    pub alpha: u64,
+    /// Exponent used in the inverse S-box, i.e., the inverse of `alpha` modulo `p - 1`,
+    /// as little-endian limbs.
+    pub alpha_inverse: Vec<u64>,
+    /// The coefficients `(alpha_2, beta_2)` of the non-linear layer, such that
+    /// `alpha_2^2 - 4 * beta_2` is a quadratic non-residue.
+    pub alpha_beta: (F, F),
+    /// Round constants of all rounds but the last, indexed by `ark[round][state_element_index]`.
+    pub ark: Vec<Vec<F>>,
+    /// The rate (in terms of number of field elements).
+    pub rate: usize,
+    /// The capacity (in terms of number of field elements).
+    pub capacity: usize,
+}
+
+impl<F: PrimeField> GriffinParameters<F> {
+    /// Initialize the parameters of the Griffin permutation.
+    pub fn new(
+        rounds: usize,
+        alpha: u64,
+        alpha_beta: (F, F),
+        ark: Vec<Vec<F>>,
+        rate: usize,
+        capacity: usize,
+    ) -> Self {
+        let state_size = rate + capacity;
+        assert!(
+            state_size == 3 || state_size.is_multiple_of(4),
+            "the state size must be 3 or a multiple of 4"
+        );
+        assert!(rounds > 0, "there must be at least one round");
+        assert!(
+            is_quadratic_non_residue(alpha_beta.0.square() - alpha_beta.1.double().double()),
+            "alpha_2^2 - 4 * beta_2 must be a quadratic non-residue"
+        );
+        assert_eq!(ark.len(), rounds - 1);
+        for item in &ark {
+            assert_eq!(item.len(), state_size);
+        }
+
+        Self {
+            rounds,
+            alpha,
+            alpha_inverse: inverse_exponent::<F>(alpha),
+            alpha_beta,
+            ark,
+            rate,
+            capacity,
+        }
+    }
+
+    /// Generate parameters for `F` providing `security_level` bits of security.
+    ///
+    /// The S-box exponent is the smallest `alpha` for which `x^alpha` is a permutation of `F`,
+    /// and the number of rounds is computed by [`find_griffin_rounds`]. The round constants,
+    /// followed by the candidates for `(alpha_2, beta_2)`, are sampled with the Grain LFSR, as
+    /// for Poseidon.
+    pub fn generate(rate: usize, capacity: usize, security_level: usize) -> Self {
+        let state_size = rate + capacity;
+        let alpha = find_poseidon_alpha::<F>();
+        let rounds = find_griffin_rounds(state_size, alpha, security_level);
+
+        let mut lfsr = PoseidonGrainLFSR::new(
+            false,
+            F::Params::MODULUS_BITS as u64,
+            state_size as u64,
+            rounds as u64,
+            0,
+        );
+        let ark = (1..rounds)
+            .map(|_| lfsr.get_field_elements_rejection_sampling::<F>(state_size))
+            .collect();
+        let alpha_beta = loop {
+            let candidate = lfsr.get_field_elements_rejection_sampling::<F>(2);
+            let (alpha_2, beta_2) = (candidate[0], candidate[1]);
+            if is_quadratic_non_residue(alpha_2.square() - beta_2.double().double()) {
+                break (alpha_2, beta_2);
+            }
+        };
+
+        Self::new(rounds, alpha, alpha_beta, ark, rate, capacity)
+    }
+
+    /// The width of the permutation state, `t = rate + capacity`.
+    pub fn state_size(&self) -> usize {
+        self.rate + self.capacity
+    }
+
+    /// Returns `(alpha_i, beta_i)` for every `i >= 2`.
+    fn alphas_betas(&self) -> Vec<(F, F)> {
+        let (alpha_2, beta_2) = self.alpha_beta;
+        (2..self.state_size())
+            .map(|i| {
+                let i_minus_one = F::from((i - 1) as u64);
+                (i_minus_one * alpha_2, i_minus_one.square() * beta_2)
+            })
+            .collect()
+    }
+
+    fn apply_non_linear_layer(&self, state: &mut [F]) {
+        let input = state.to_vec();
+        state[0] = input[0].pow(&self.alpha_inverse);
+        state[1] = input[1].pow([self.alpha]);
+
+        // `(i - 1) * y_0 + y_1`
+        let mut l = state[1];
+        for (i, (alpha_i, beta_i)) in (2..).zip(self.alphas_betas()) {
+            l += state[0];
+            let l_i = if i > 2 { l + input[i - 1] } else { l };
+            state[i] = input[i] * (l_i.square() + alpha_i * l_i + beta_i);
+        }
+    }
+}
+
+impl<F: PrimeField> FieldPermutation<F> for GriffinParameters<F> {
+    fn rate(&self) -> usize {
+        self.rate
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn permute(&self, state: &mut [F]) {
+        Poseidon2Parameters::apply_external_matrix(state);
+        for round in 0..self.rounds {
+            self.apply_non_linear_layer(state);
+            Poseidon2Parameters::apply_external_matrix(state);
+            if let Some(ark) = self.ark.get(round) {
+                for (elem, constant) in state.iter_mut().zip(ark) {
+                    *elem += constant;
+                }
+            }
+        }
+    }
+}
+
+fn is_quadratic_non_residue<F: PrimeField>(elem: F) -> bool {
+    elem.pow(F::Params::MODULUS_MINUS_ONE_DIV_TWO) == -F::one()
+}
+
+/// Computes the number of rounds of Griffin for a state of `state_size` elements, following
+/// Section 7 of the paper.
+///
+/// This is `ceil(1.2 * max(6, 1 + r))`, where `r` is the smallest number of rounds for which
+/// the complexity of Gröbner basis attacks, `binomial(r * (alpha + t) + 1, 1 + t * r)^2`,
+/// exceeds `2^security_level`.
+pub fn find_griffin_rounds(state_size: usize, alpha: u64, security_level: usize) -> usize {
+    // log2 of the binomial coefficient `(n choose k)`
+    let log2_binomial = |n: usize, k: usize| -> f64 {
+        (1..=k)
+            .map(|i| log2((n - k + i) as f64) - log2(i as f64))
+            .sum()
+    };
+
+    let mut r = 1;
+    while 2.0 * log2_binomial(r * (alpha as usize + state_size) + 1, 1 + state_size * r)
+        < security_level as f64
+    {
+        r += 1;
+    }
+
+    // add 20%
+    let rounds = 6 * core::cmp::max(6, 1 + r);
+    rounds.div_ceil(5)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::duplex::FieldPermutation;
+    use crate::griffin::{find_griffin_rounds, GriffinParameters, GriffinSponge};
+    use crate::{CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_ff::{Field, UniformRand};
+    use ark_std::test_rng;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn test_rounds() {
+        assert_eq!(find_griffin_rounds(3, 5, 128), 12);
+        assert_eq!(find_griffin_rounds(4, 5, 128), 11);
+        assert_eq!(find_griffin_rounds(8, 5, 128), 9);
+        assert_eq!(find_griffin_rounds(3, 3, 128), 16);
+        assert_eq!(find_griffin_rounds(24, 5, 128), 8);
+    }
+
+    #[test]
+    fn test_non_linear_layer() {
+        let mut rng = test_rng();
+        for rate in [2, 7] {
+            let parameters = GriffinParameters::<Fr>::generate(rate, 1, 128);
+            let input: Vec<_> = (0..parameters.state_size())
+                .map(|_| Fr::rand(&mut rng))
+                .collect();
+            let mut output = input.clone();
+            parameters.apply_non_linear_layer(&mut output);
+
+            // Invert the non-linear layer.
+            let mut inverse = output.clone();
+            inverse[0] = output[0].pow([parameters.alpha]);
+            inverse[1] = output[1].pow(&parameters.alpha_inverse);
+            for (i, (alpha_i, beta_i)) in (2..).zip(parameters.alphas_betas()) {
+                let mut l_i = Fr::from((i - 1) as u64) * output[0] + output[1];
+                if i > 2 {
+                    l_i += inverse[i - 1];
+                }
+                inverse[i] = output[i] * (l_i.square() + alpha_i * l_i + beta_i).inverse().unwrap();
+            }
+            assert_eq!(inverse, input);
+        }
+    }
+
+    #[test]
+    fn test_sponge() {
+        let mut rng = test_rng();
+        let parameters = GriffinParameters::<Fr>::generate(2, 1, 128);
+        let input: Vec<_> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+
+        let mut state = input.clone();
+        parameters.permute(&mut state);
+        assert_ne!(state, input);
+
+        let mut sponge = GriffinSponge::new(&parameters);
+        sponge.absorb(&input);
+        let output = sponge.squeeze_native_field_elements(3);
+
+        let mut other = GriffinSponge::new(&parameters);
+        other.absorb(&input);
+        assert_eq!(other.squeeze_native_field_elements(3), output);
+    }
+}
@@ -0,0 +1,149 @@
+use crate::constraints::CryptographicSpongeVar;
+use crate::hash_to_curve::{curve_equation_rhs, TRY_AND_INCREMENT_ATTEMPTS};
+use crate::CryptographicSponge;
+use ark_ec::SWModelParameters;
+use ark_ff::{BitIteratorLE, FftField, Field, LegendreSymbol, PrimeField, SquareRootField, Zero};
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::groups::curves::short_weierstrass::ProjectiveVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::SynthesisError;
+use ark_std::vec::Vec;
+
+/// Returns the in-circuit `x^3 + a * x + b`.
+fn curve_equation_rhs_var<P: SWModelParameters>(
+    x: &FpVar<P::BaseField>,
+) -> Result<FpVar<P::BaseField>, SynthesisError>
+where
+    P::BaseField: PrimeField,
+{
+    Ok(x.square()? * x + x * P::COEFF_A + P::COEFF_B)
+}
+
+/// The gadget for the try-and-increment map of
+/// [`squeeze_group_elements`](crate::hash_to_curve::squeeze_group_elements).
+///
+/// For each candidate, the prover shows that `x^3 + a * x + b` is either a square, with a
+/// square root, or a non-zero non-square, with a square root of its product with a fixed
+/// non-square. The first square is selected, so the prover cannot choose the point.
+#[tracing::instrument(target = "r1cs", skip(x0))]
+fn try_and_increment_var<P: SWModelParameters>(
+    x0: &FpVar<P::BaseField>,
+) -> Result<ProjectiveVar<P, FpVar<P::BaseField>>, SynthesisError>
+where
+    P::BaseField: PrimeField + SquareRootField,
+{
+    let cs = x0.cs();
+    let non_residue = P::BaseField::multiplicative_generator();
+
+    let mut found = Boolean::FALSE;
+    let mut x = FpVar::zero();
+    let mut y = FpVar::zero();
+    for i in 0..TRY_AND_INCREMENT_ATTEMPTS {
+        let x_i = x0 + P::BaseField::from(i);
+        let rhs = curve_equation_rhs_var::<P>(&x_i)?;
+        let rhs_value = || x_i.value().map(|x_i| curve_equation_rhs::<P>(&x_i));
+
+        let is_square = Boolean::new_witness(cs.clone(), || {
+            Ok(rhs_value()?.legendre() != LegendreSymbol::QuadraticNonResidue)
+        })?;
+        let root = FpVar::new_witness(cs.clone(), || {
+            let rhs = rhs_value()?;
+            let square = if rhs.legendre() == LegendreSymbol::QuadraticNonResidue {
+                rhs * non_residue
+            } else {
+                rhs
+            };
+            square.sqrt().ok_or(SynthesisError::Unsatisfiable)
+        })?;
+        let rhs_inverse = FpVar::new_witness(cs.clone(), || {
+            let rhs = rhs_value()?;
+            if rhs.legendre() == LegendreSymbol::QuadraticNonResidue {
+                rhs.inverse().ok_or(SynthesisError::Unsatisfiable)
+            } else {
+                Ok(P::BaseField::zero())
+            }
+        })?;
+
+        // root^2 = rhs if is_square, and rhs * non_residue otherwise
+        let factor = is_square.select(&FpVar::one(), &FpVar::Constant(non_residue))?;
+        root.square_equals(&(&rhs * factor))?;
+        // rhs is non-zero if it is not a square
+        rhs.mul_equals(&rhs_inverse, &FpVar::from(is_square.not()))?;
+
+        let is_selected = is_square.and(&found.not())?;
+        x = is_selected.select(&x_i, &x)?;
+        y = is_selected.select(&root, &y)?;
+        found = found.or(&is_square)?;
+    }
+    found.enforce_equal(&Boolean::TRUE)?;
+
+    let is_odd = y.to_bits_le()?[0].clone();
+    let y = is_odd.select(&y.negate()?, &y)?;
+
+    let point = ProjectiveVar::new(x, y, FpVar::one());
+    let cofactor_bits: Vec<_> = BitIteratorLE::without_trailing_zeros(P::COFACTOR)
+        .map(Boolean::constant)
+        .collect();
+    point.scalar_mul_le(cofactor_bits.iter())
+}
+
+/// The gadget for [`squeeze_group_elements`](crate::hash_to_curve::squeeze_group_elements),
+/// squeezing `num_elements` points of the prime-order subgroup of the short Weierstrass curve
+/// `P` from a sponge over its base field.
+#[allow(clippy::type_complexity)]
+#[tracing::instrument(target = "r1cs", skip(sponge))]
+pub fn squeeze_group_elements_var<P, S, V>(
+    sponge: &mut V,
+    num_elements: usize,
+) -> Result<Vec<ProjectiveVar<P, FpVar<P::BaseField>>>, SynthesisError>
+where
+    P: SWModelParameters,
+    P::BaseField: PrimeField + SquareRootField,
+    S: CryptographicSponge,
+    V: CryptographicSpongeVar<P::BaseField, S>,
+{
+    sponge
+        .squeeze_field_elements(num_elements)?
+        .iter()
+        .map(try_and_increment_var::<P>)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constraints::CryptographicSpongeVar;
+    use crate::hash_to_curve::constraints::squeeze_group_elements_var;
+    use crate::hash_to_curve::squeeze_group_elements;
+    use crate::poseidon::constraints::PoseidonSpongeVar;
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::PoseidonSponge;
+    use crate::CryptographicSponge;
+    use ark_ec::ProjectiveCurve;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_test_curves::bls12_381::{g1::Parameters, Fq};
+
+    #[test]
+    fn test_squeeze_group_elements_var() {
+        let cs = ConstraintSystem::new_ref();
+        let params = poseidon_parameters_for_test();
+        let input = Fq::from(1u64);
+
+        let mut sponge = PoseidonSponge::<Fq>::new(&params);
+        sponge.absorb(&input);
+        let points = squeeze_group_elements::<Parameters, _>(&mut sponge, 2);
+
+        let mut sponge_var = PoseidonSpongeVar::<Fq>::new(cs.clone(), &params);
+        sponge_var
+            .absorb(&FpVar::new_witness(cs.clone(), || Ok(input)).unwrap())
+            .unwrap();
+        let points_var =
+            squeeze_group_elements_var::<Parameters, _, _>(&mut sponge_var, 2).unwrap();
+
+        for (point, point_var) in points.iter().zip(&points_var) {
+            assert_eq!(point_var.value().unwrap().into_affine(), *point);
+        }
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
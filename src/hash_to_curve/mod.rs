@@ -0,0 +1,119 @@
+use crate::{FieldBasedCryptographicSponge, SpongeError};
+use ark_ec::models::short_weierstrass_jacobian::GroupAffine as SWAffine;
+use ark_ec::{AffineCurve, SWModelParameters};
+use ark_ff::{BigInteger, Field, PrimeField, SquareRootField, Zero};
+use ark_std::vec::Vec;
+
+/// constraints for squeezing group elements
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+/// The number of candidates tried by [`squeeze_group_elements`] for each point.
+///
+/// About half of the candidates are the abscissa of a point, so all of them fail with
+/// probability about `2^-128`.
+pub const TRY_AND_INCREMENT_ATTEMPTS: u64 = 128;
+
+/// Returns `x^3 + a * x + b`.
+pub(crate) fn curve_equation_rhs<P: SWModelParameters>(x: &P::BaseField) -> P::BaseField {
+    let mut rhs = x.square() * x + P::COEFF_B;
+    if !P::COEFF_A.is_zero() {
+        rhs += P::mul_by_a(x);
+    }
+    rhs
+}
+
+/// Maps `x0` to a point of the prime-order subgroup, with the first `x0 + i` that is the
+/// abscissa of a point, the even ordinate of that point, and cofactor clearing.
+fn try_and_increment<P: SWModelParameters>(x0: P::BaseField) -> Result<SWAffine<P>, SpongeError>
+where
+    P::BaseField: PrimeField + SquareRootField,
+{
+    for i in 0..TRY_AND_INCREMENT_ATTEMPTS {
+        let x = x0 + P::BaseField::from(i);
+        if let Some(y) = curve_equation_rhs::<P>(&x).sqrt() {
+            let y = if y.into_repr().is_odd() { -y } else { y };
+            return Ok(SWAffine::new(x, y, false).mul_by_cofactor());
+        }
+    }
+    Err(SpongeError::NoCurvePoint)
+}
+
+/// Squeezes `num_elements` points of the prime-order subgroup of the short Weierstrass curve
+/// `P` from a sponge over its base field.
+///
+/// Each point is derived from one squeezed field element `x0` by try-and-increment: the
+/// abscissa is the first `x0 + i`, for `i < TRY_AND_INCREMENT_ATTEMPTS`, for which
+/// `x^3 + a * x + b` is a square, the ordinate is its even square root, and the point is then
+/// multiplied by the cofactor. The gadget
+/// [`squeeze_group_elements_var`](constraints::squeeze_group_elements_var) computes the same
+/// points in a circuit.
+///
+/// # Panics
+///
+/// Panics if none of the candidates is the abscissa of a point, which happens with negligible
+/// probability; see [`try_squeeze_group_elements`].
+pub fn squeeze_group_elements<P, S>(sponge: &mut S, num_elements: usize) -> Vec<SWAffine<P>>
+where
+    P: SWModelParameters,
+    P::BaseField: PrimeField + SquareRootField,
+    S: FieldBasedCryptographicSponge<P::BaseField>,
+{
+    try_squeeze_group_elements(sponge, num_elements).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Same as [`squeeze_group_elements`], but returns [`SpongeError::NoCurvePoint`] if none of the
+/// candidates for a point is the abscissa of a point.
+pub fn try_squeeze_group_elements<P, S>(
+    sponge: &mut S,
+    num_elements: usize,
+) -> Result<Vec<SWAffine<P>>, SpongeError>
+where
+    P: SWModelParameters,
+    P::BaseField: PrimeField + SquareRootField,
+    S: FieldBasedCryptographicSponge<P::BaseField>,
+{
+    sponge
+        .squeeze_native_field_elements(num_elements)
+        .into_iter()
+        .map(try_and_increment::<P>)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hash_to_curve::{squeeze_group_elements, try_squeeze_group_elements};
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::PoseidonSponge;
+    use crate::CryptographicSponge;
+    use ark_ff::Zero;
+    use ark_test_curves::bls12_381::{g1::Parameters, Fq};
+
+    #[test]
+    fn test_squeeze_group_elements() {
+        let mut sponge = PoseidonSponge::<Fq>::new(&poseidon_parameters_for_test());
+        sponge.absorb(&Fq::from(1u64));
+        let mut other = sponge.clone();
+
+        let points = squeeze_group_elements::<Parameters, _>(&mut sponge, 3);
+        assert_eq!(points, squeeze_group_elements(&mut other, 3));
+        for point in &points {
+            assert!(point.is_on_curve());
+            assert!(point.is_in_correct_subgroup_assuming_on_curve());
+            assert!(!point.is_zero());
+        }
+        assert_ne!(points[0], points[1]);
+    }
+
+    #[test]
+    fn test_try_squeeze_group_elements() {
+        let mut sponge = PoseidonSponge::<Fq>::new(&poseidon_parameters_for_test());
+        sponge.absorb(&Fq::from(1u64));
+        let mut other = sponge.clone();
+
+        assert_eq!(
+            try_squeeze_group_elements::<Parameters, _>(&mut sponge, 2).unwrap(),
+            squeeze_group_elements::<Parameters, _>(&mut other, 2)
+        );
+    }
+}
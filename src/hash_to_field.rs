@@ -0,0 +1,130 @@
+use crate::{CryptographicSponge, SpongeError};
+use ark_ff::{FpParameters, PrimeField};
+use ark_std::vec::Vec;
+
+/// The security parameter `k` of [`hash_to_field`], in bits.
+pub const HASH_TO_FIELD_SECURITY: usize = 128;
+
+/// The prefix of the domain separation tags that are too long, as in RFC 9380.
+const OVERSIZE_DST_PREFIX: &[u8] = b"H2C-OVERSIZE-DST-";
+
+/// Expands `msg` into `len_in_bytes` uniform bytes under the domain separation tag `dst`, with
+/// the sponge `S` as the extendable-output function of `expand_message_xof` in
+/// [RFC 9380](https://www.rfc-editor.org/rfc/rfc9380#section-5.3.2).
+///
+/// The sponge absorbs `msg || I2OSP(len_in_bytes, 2) || DST_prime` as a single byte string and
+/// squeezes `len_in_bytes` bytes. Tags longer than 255 bytes are first reduced as in the RFC.
+///
+/// Returns [`SpongeError::OutputTooLong`] if `len_in_bytes` is larger than `65535`.
+pub fn expand_message<S: CryptographicSponge>(
+    params: &S::Parameters,
+    msg: &[u8],
+    dst: &[u8],
+    len_in_bytes: usize,
+) -> Result<Vec<u8>, SpongeError> {
+    if len_in_bytes > u16::MAX as usize {
+        return Err(SpongeError::OutputTooLong {
+            length: len_in_bytes,
+            max: u16::MAX as usize,
+        });
+    }
+
+    let oversize_dst;
+    let dst = if dst.len() > u8::MAX as usize {
+        let mut sponge = S::new(params);
+        sponge.absorb(&[OVERSIZE_DST_PREFIX, dst].concat());
+        oversize_dst = sponge.squeeze_bytes((2 * HASH_TO_FIELD_SECURITY).div_ceil(8));
+        &oversize_dst[..]
+    } else {
+        dst
+    };
+
+    let msg_prime = [
+        msg,
+        &(len_in_bytes as u16).to_be_bytes(),
+        dst,
+        &[dst.len() as u8],
+    ]
+    .concat();
+    let mut sponge = S::new(params);
+    sponge.absorb(&msg_prime);
+    Ok(sponge.squeeze_bytes(len_in_bytes))
+}
+
+/// Hashes `msg` to `count` elements of `F` under the domain separation tag `dst`, following
+/// `hash_to_field` of [RFC 9380](https://www.rfc-editor.org/rfc/rfc9380#section-5.2) with
+/// [`expand_message`] over the sponge `S`.
+///
+/// Every element is reduced from `L = ceil((ceil(log2(p)) + k) / 8)` big-endian bytes, where `k`
+/// is [`HASH_TO_FIELD_SECURITY`], so the elements are statistically close to uniform.
+///
+/// Returns [`SpongeError::OutputTooLong`] if the `count` elements take more than `65535` bytes.
+pub fn hash_to_field<S: CryptographicSponge, F: PrimeField>(
+    params: &S::Parameters,
+    msg: &[u8],
+    dst: &[u8],
+    count: usize,
+) -> Result<Vec<F>, SpongeError> {
+    let len_per_elem = (F::Params::MODULUS_BITS as usize + HASH_TO_FIELD_SECURITY).div_ceil(8);
+    let uniform_bytes = expand_message::<S>(params, msg, dst, count * len_per_elem)?;
+    Ok(uniform_bytes
+        .chunks(len_per_elem)
+        .map(F::from_be_bytes_mod_order)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hash_to_field::{expand_message, hash_to_field};
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::PoseidonSponge;
+    use crate::CryptographicSponge;
+    use ark_ff::PrimeField;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::{Fq, Fr};
+
+    type Sponge = PoseidonSponge<Fr>;
+
+    #[test]
+    fn test_expand_message() {
+        let params = poseidon_parameters_for_test();
+        let bytes = expand_message::<Sponge>(&params, b"abc", b"DST", 100).unwrap();
+        assert_eq!(bytes.len(), 100);
+
+        // The output length and the tag are bound to the output.
+        let shorter = expand_message::<Sponge>(&params, b"abc", b"DST", 50).unwrap();
+        assert_ne!(bytes[..50].to_vec(), shorter);
+        assert_ne!(
+            bytes,
+            expand_message::<Sponge>(&params, b"abc", b"DST2", 100).unwrap()
+        );
+
+        let mut sponge = Sponge::new(&params);
+        sponge.absorb(&b"abc\x00\x64DST\x03".to_vec());
+        assert_eq!(bytes, sponge.squeeze_bytes(100));
+
+        // Long tags are hashed first.
+        let long_dst = [7u8; 300];
+        assert_eq!(
+            expand_message::<Sponge>(&params, b"abc", &long_dst, 32)
+                .unwrap()
+                .len(),
+            32
+        );
+
+        assert!(expand_message::<Sponge>(&params, b"abc", b"DST", 65536).is_err());
+    }
+
+    #[test]
+    fn test_hash_to_field() {
+        let params = poseidon_parameters_for_test();
+        let elements: Vec<Fq> = hash_to_field::<Sponge, Fq>(&params, b"abc", b"DST", 2).unwrap();
+        assert_eq!(elements.len(), 2);
+        assert_ne!(elements[0], elements[1]);
+
+        // Each element is reduced from 64 bytes for a 381-bit modulus.
+        let bytes = expand_message::<Sponge>(&params, b"abc", b"DST", 128).unwrap();
+        assert_eq!(elements[0], Fq::from_be_bytes_mod_order(&bytes[..64]));
+        assert_eq!(elements[1], Fq::from_be_bytes_mod_order(&bytes[64..]));
+    }
+}
@@ -0,0 +1,145 @@
+use crate::transcript::Transcript;
+use crate::{Absorb, CryptographicSponge};
+use ark_ff::{FpParameters, PrimeField};
+use ark_std::vec::Vec;
+
+/// The domain of the transcripts of [`SpongePrf`].
+const PRF_DOMAIN: &[u8] = b"ark-sponge/prf";
+
+/// The domain of the transcripts of [`SpongeKdf`].
+const KDF_DOMAIN: &[u8] = b"ark-sponge/kdf";
+
+/// The domain of the transcripts of [`derive_nonce`].
+pub const NONCE_DOMAIN: &[u8] = b"ark-sponge/nonce";
+
+/// A pseudorandom function over any cryptographic sponge, mapping absorbable inputs to a field
+/// element under a secret key.
+///
+/// The key and the input are appended to a [`Transcript`] with distinct labels, and the output
+/// is squeezed from it.
+#[derive(Clone)]
+pub struct SpongePrf<S: CryptographicSponge> {
+    transcript: Transcript<S>,
+}
+
+impl<S: CryptographicSponge> SpongePrf<S> {
+    /// Creates the PRF with the key `key`.
+    pub fn new(params: &S::Parameters, key: &impl Absorb) -> Self {
+        let mut transcript = Transcript::new(params, PRF_DOMAIN);
+        transcript.append(b"key", key);
+        Self { transcript }
+    }
+
+    /// Evaluates the PRF on `input`.
+    pub fn evaluate<F: PrimeField>(&self, input: &impl Absorb) -> F {
+        let mut transcript = self.transcript.clone();
+        transcript.append(b"input", input);
+        transcript.challenge_field_elements(b"output", 1)[0]
+    }
+}
+
+/// A key derivation function over any cryptographic sponge, deriving field elements from a
+/// secret key and a context, in the style of HKDF.
+///
+/// The context plays the role of the `info` of HKDF: keys derived from the same secret for
+/// different contexts are independent, and so are keys of different lengths.
+#[derive(Clone)]
+pub struct SpongeKdf<S: CryptographicSponge> {
+    transcript: Transcript<S>,
+}
+
+impl<S: CryptographicSponge> SpongeKdf<S> {
+    /// Creates the KDF with the given sponge parameters.
+    pub fn new(params: &S::Parameters) -> Self {
+        Self {
+            transcript: Transcript::new(params, KDF_DOMAIN),
+        }
+    }
+
+    /// Derives `length` field elements from `key` for the context `context`.
+    pub fn derive<F: PrimeField>(
+        &self,
+        key: &impl Absorb,
+        context: &[u8],
+        length: usize,
+    ) -> Vec<F> {
+        let mut transcript = self.transcript.clone();
+        transcript.append(b"key", key);
+        transcript.append(b"context", &context);
+        transcript.challenge_field_elements(b"output", length)
+    }
+}
+
+/// Derives a deterministic nonce for signing `message` with `secret_key`, in the spirit of
+/// [RFC 6979](https://www.rfc-editor.org/rfc/rfc6979), for Schnorr-style signatures whose
+/// nonces live in the scalar field `F`.
+///
+/// The secret key and the message are appended to a [`Transcript`] with the domain
+/// [`NONCE_DOMAIN`], and the nonce is reduced from 128 more bits than the size of the modulus of
+/// `F`, so it is statistically close to uniform even when `F` is not the field of the sponge.
+/// The nonce is never zero.
+pub fn derive_nonce<S: CryptographicSponge, F: PrimeField>(
+    params: &S::Parameters,
+    secret_key: &impl Absorb,
+    message: &impl Absorb,
+) -> F {
+    let mut transcript = Transcript::<S>::new(params, NONCE_DOMAIN);
+    transcript.append(b"secret key", secret_key);
+    transcript.append(b"message", message);
+
+    let num_bytes = (F::Params::MODULUS_BITS as usize + 128).div_ceil(8);
+    loop {
+        let nonce = F::from_le_bytes_mod_order(&transcript.challenge_bytes(b"nonce", num_bytes));
+        if !nonce.is_zero() {
+            return nonce;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::kdf::{derive_nonce, SpongeKdf, SpongePrf};
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::PoseidonSponge;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::{Fq, Fr};
+
+    #[test]
+    fn test_prf() {
+        let params = poseidon_parameters_for_test();
+        let prf = SpongePrf::<PoseidonSponge<Fr>>::new(&params, &Fr::from(1u64));
+        let other = SpongePrf::<PoseidonSponge<Fr>>::new(&params, &Fr::from(2u64));
+
+        let output: Fr = prf.evaluate(&3u64);
+        assert_eq!(output, prf.evaluate(&3u64));
+        assert_ne!(output, prf.evaluate(&4u64));
+        assert_ne!(output, other.evaluate(&3u64));
+    }
+
+    #[test]
+    fn test_kdf() {
+        let kdf = SpongeKdf::<PoseidonSponge<Fr>>::new(&poseidon_parameters_for_test());
+        let key = Fr::from(1u64);
+
+        let derived: Vec<Fr> = kdf.derive(&key, b"session", 3);
+        assert_eq!(derived.len(), 3);
+        assert_eq!(derived, kdf.derive(&key, b"session", 3));
+        assert_ne!(derived, kdf.derive::<Fr>(&key, b"other session", 3));
+        assert_ne!(derived, kdf.derive::<Fr>(&Fr::from(2u64), b"session", 3));
+        // The length is bound to the output.
+        assert_ne!(derived[..2].to_vec(), kdf.derive::<Fr>(&key, b"session", 2));
+    }
+
+    #[test]
+    fn test_derive_nonce() {
+        let params = poseidon_parameters_for_test();
+        let derive = |secret_key: u64, message: &[u8]| -> Fq {
+            derive_nonce::<PoseidonSponge<Fr>, Fq>(&params, &Fr::from(secret_key), &message)
+        };
+
+        let nonce = derive(1, b"message");
+        assert_eq!(nonce, derive(1, b"message"));
+        assert_ne!(nonce, derive(2, b"message"));
+        assert_ne!(nonce, derive(1, b"other message"));
+    }
+}
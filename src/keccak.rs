@@ -0,0 +1,201 @@
+use crate::{
+    squeeze_field_elements_with_sizes_from_bytes, Absorb, CryptographicSponge, FieldElementSize,
+};
+use ark_ff::PrimeField;
+use ark_std::vec;
+use ark_std::vec::Vec;
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::Shake256;
+
+/// The number of bytes that summarize the state when absorbing after squeezing.
+const CHAINING_BYTES: usize = 64;
+
+/// A byte-oriented sponge based on SHAKE256, the extendable-output function of SHA-3.
+///
+/// Inputs are absorbed through their byte encoding ([`Absorb::to_sponge_bytes`]), and outputs are
+/// read from the XOF output of everything absorbed so far. Consecutive squeezes continue
+/// reading the same output stream. Absorbing after squeezing restarts SHAKE256 from the next
+/// 64 bytes of the stream, which binds the new inputs to all previous operations.
+///
+/// Field elements are squeezed with a modular reduction of 128 bits more than the size of the
+/// modulus, so they are statistically close to uniform.
+#[derive(Clone)]
+pub struct Sha3Sponge {
+    hasher: Shake256,
+    /// The number of bytes squeezed from the current stream, if squeezing
+    num_squeezed_bytes: Option<usize>,
+}
+
+impl Sha3Sponge {
+    fn read_bytes(&mut self, output: &mut [u8]) {
+        let num_squeezed_bytes = self.num_squeezed_bytes.unwrap_or(0);
+        let mut reader = self.hasher.clone().finalize_xof();
+
+        // skip the bytes that have already been squeezed
+        let mut skipped = [0u8; 136];
+        let mut remaining = num_squeezed_bytes;
+        while remaining > 0 {
+            let len = core::cmp::min(remaining, skipped.len());
+            reader.read(&mut skipped[..len]);
+            remaining -= len;
+        }
+
+        reader.read(output);
+        self.num_squeezed_bytes = Some(num_squeezed_bytes + output.len());
+    }
+}
+
+/// Resets the hash state, which may depend on secret inputs.
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for Sha3Sponge {
+    fn zeroize(&mut self) {
+        sha3::digest::Reset::reset(&mut self.hasher);
+        self.num_squeezed_bytes = None;
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Sha3Sponge {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::ZeroizeOnDrop for Sha3Sponge {}
+
+impl CryptographicSponge for Sha3Sponge {
+    type Parameters = ();
+
+    fn new(_params: &Self::Parameters) -> Self {
+        Self {
+            hasher: Shake256::default(),
+            num_squeezed_bytes: None,
+        }
+    }
+
+    /// SHAKE256 absorbs 136 bytes per permutation of Keccak-f\[1600\].
+    fn rate(&self) -> usize {
+        136
+    }
+
+    fn capacity(&self) -> usize {
+        64
+    }
+
+    fn security_level(&self) -> usize {
+        256
+    }
+
+    fn absorb(&mut self, input: &impl Absorb) {
+        if self.num_squeezed_bytes.is_some() {
+            let mut chaining_value = [0u8; CHAINING_BYTES];
+            self.read_bytes(&mut chaining_value);
+            self.hasher = Shake256::default();
+            self.hasher.update(chaining_value);
+            self.num_squeezed_bytes = None;
+        }
+        self.hasher.update(input.to_sponge_bytes_as_vec());
+    }
+
+    fn squeeze_bytes(&mut self, num_bytes: usize) -> Vec<u8> {
+        let mut output = vec![0u8; num_bytes];
+        self.read_bytes(&mut output);
+        output
+    }
+
+    fn squeeze_bits(&mut self, num_bits: usize) -> Vec<bool> {
+        let bytes = self.squeeze_bytes(num_bits.div_ceil(8));
+        let mut bits: Vec<bool> = bytes
+            .iter()
+            .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1))
+            .collect();
+        bits.truncate(num_bits);
+        bits
+    }
+
+    fn squeeze_field_elements_with_sizes<F: PrimeField>(
+        &mut self,
+        sizes: &[FieldElementSize],
+    ) -> Vec<F> {
+        squeeze_field_elements_with_sizes_from_bytes(self, sizes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::keccak::Sha3Sponge;
+    use crate::{CryptographicSponge, FieldElementSize, SpongeError};
+    use ark_ff::{BigInteger, FpParameters, PrimeField};
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+    use sha3::digest::{ExtendableOutput, Update, XofReader};
+    use sha3::Shake256;
+
+    #[test]
+    fn test_matches_shake256() {
+        let mut sponge = Sha3Sponge::new(&());
+        sponge.absorb(&[1u8, 2, 3].as_ref());
+        sponge.absorb(&[4u8, 5].as_ref());
+        let mut squeezed = sponge.squeeze_bytes(50);
+        squeezed.extend(sponge.squeeze_bytes(200));
+
+        let mut hasher = Shake256::default();
+        hasher.update([1u8, 2, 3, 4, 5]);
+        let mut expected = [0u8; 250];
+        hasher.finalize_xof().read(&mut expected);
+        assert_eq!(squeezed, expected.to_vec());
+    }
+
+    #[test]
+    fn test_absorb_after_squeeze() {
+        let mut sponge = Sha3Sponge::new(&());
+        sponge.absorb(&1u8);
+        let mut other = sponge.clone();
+
+        sponge.squeeze_bytes(1);
+        sponge.absorb(&2u8);
+        other.absorb(&2u8);
+        assert_ne!(sponge.squeeze_bytes(32), other.squeeze_bytes(32));
+    }
+
+    #[test]
+    fn test_squeeze_field_elements() {
+        let mut sponge = Sha3Sponge::new(&());
+        sponge.absorb(&42u64);
+        let elements: Vec<Fr> = sponge.squeeze_field_elements(10);
+        assert_eq!(elements.len(), 10);
+        assert!(elements[1..].iter().all(|e| e != &elements[0]));
+
+        let truncated: Vec<Fr> =
+            sponge.squeeze_field_elements_with_sizes(&[FieldElementSize::Truncated(10); 20]);
+        assert!(truncated.iter().all(|e| e.into_repr().num_bits() <= 10));
+        assert!(truncated.iter().any(|e| e.into_repr().num_bits() > 8));
+    }
+
+    #[test]
+    fn test_truncated_size_beyond_capacity() {
+        let capacity = <Fr as PrimeField>::Params::CAPACITY as usize;
+        let mut sponge = Sha3Sponge::new(&());
+        let elements = sponge
+            .try_squeeze_field_elements_with_sizes::<Fr>(&[FieldElementSize::Truncated(capacity)])
+            .unwrap();
+        assert_eq!(elements.len(), 1);
+        assert_eq!(
+            sponge.try_squeeze_field_elements_with_sizes::<Fr>(&[FieldElementSize::Truncated(
+                capacity + 1
+            )]),
+            Err(SpongeError::TooManyBits {
+                num_bits: capacity + 1,
+                capacity
+            })
+        );
+    }
+
+    #[test]
+    fn test_rate_capacity_and_security_level() {
+        let sponge = Sha3Sponge::new(&());
+        assert_eq!(sponge.rate() + sponge.capacity(), 200);
+        assert_eq!(sponge.security_level(), 256);
+    }
+}
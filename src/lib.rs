@@ -1,7 +1,6 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 //! A crate for the cryptographic sponge trait.
 #![deny(
-    const_err,
     future_incompatible,
     missing_docs,
     non_shorthand_field_patterns,
@@ -16,9 +15,17 @@
 )]
 #![forbid(unsafe_code)]
 
-use ark_ff::{FpParameters, PrimeField};
+extern crate alloc;
+
+use ark_ff::{BigInteger, FpParameters, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use ark_std::cfg_chunks;
+use ark_std::marker::PhantomData;
+use ark_std::rand::SeedableRng;
 use ark_std::vec;
 use ark_std::vec::Vec;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 /// Infrastructure for the constraints counterparts.
 #[cfg(feature = "r1cs")]
@@ -27,6 +34,43 @@ pub mod constraints;
 mod absorb;
 pub use absorb::*;
 
+mod squeeze;
+pub use squeeze::*;
+
+mod error;
+pub use error::*;
+
+mod prefix_cache;
+pub use prefix_cache::*;
+
+mod output;
+
+/// The paths used by the code of the derive macros, so that crates deriving the traits of this
+/// crate only need to depend on it.
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub mod __private {
+    pub use ark_ff::PrimeField;
+    #[cfg(feature = "r1cs")]
+    pub use ark_r1cs_std::{fields::fp::FpVar, uint8::UInt8};
+    #[cfg(feature = "r1cs")]
+    pub use ark_relations::r1cs::SynthesisError;
+    pub use ark_std::vec::Vec;
+}
+
+#[cfg(feature = "serde")]
+mod decimal;
+#[cfg(feature = "r1cs")]
+pub(crate) use output::{bits_from_squeezed_element_vars, bytes_from_squeezed_element_vars};
+pub(crate) use output::{
+    bits_from_squeezed_elements, bytes_from_squeezed_elements, field_element_from_bits,
+    order_squeezed_units, split_squeezed_bits, squeezed_units_for_endianness,
+};
+pub use output::{
+    squeeze_bits_num_elements, squeeze_bits_per_element, squeeze_bytes_num_elements,
+    squeeze_bytes_per_element,
+};
+
 /// The sponge for Poseidon
 ///
 /// This implementation of Poseidon is entirely from Fractal's implementation in [COS20][cos]
@@ -35,6 +79,105 @@ pub use absorb::*;
 /// [cos]: https://eprint.iacr.org/2019/1076
 pub mod poseidon;
 
+/// A generic duplex sponge over a field permutation
+pub mod duplex;
+
+/// A sponge separated into a domain given as a type or as a value
+pub mod domain_separated;
+
+/// The sponge for Poseidon2
+pub mod poseidon2;
+
+/// The sponge for Rescue-Prime
+pub mod rescue;
+
+/// The sponge for MiMC
+pub mod mimc;
+
+/// The sponge for GMiMC
+pub mod gmimc;
+
+/// The sponge for Griffin
+pub mod griffin;
+
+/// The sponge and the Jive compression function for Anemoi
+pub mod anemoi;
+
+/// A byte-oriented sponge based on SHA-3
+pub mod keccak;
+
+/// A byte-oriented sponge based on BLAKE2b
+pub mod blake2b;
+
+/// A byte-oriented sponge based on BLAKE2s, with a gadget for verifying it in circuits
+pub mod blake2s;
+
+/// Sponges generic over their native unit, bytes or field elements, with adapters between them
+pub mod unit;
+
+/// An RNG squeezing from a sponge, and the Fiat-Shamir RNG interface of `ark-marlin`
+pub mod rng;
+
+/// A labeled Fiat-Shamir transcript over a sponge
+pub mod transcript;
+
+/// A log of the messages and challenges of a transcript, which can be serialized and replayed
+#[cfg(feature = "transcript-log")]
+pub mod transcript_log;
+
+/// A writer absorbing into a sponge
+#[cfg(feature = "std")]
+pub mod writer;
+
+/// Interoperability with merlin transcripts
+#[cfg(feature = "merlin")]
+pub mod merlin_compat;
+
+/// The RustCrypto `Digest` API over a sponge
+pub mod digest_compat;
+
+/// The SAFE API, a sponge checked against a declared IO pattern
+pub mod safe;
+
+/// A message authentication code over a sponge
+pub mod mac;
+
+/// Authenticated encryption over a field permutation
+pub mod aead;
+
+/// A pseudorandom function and a key derivation function over a sponge
+pub mod kdf;
+
+/// A hiding commitment scheme over a sponge
+pub mod commitment;
+
+/// Hashing to fields with a sponge as the XOF of RFC 9380
+pub mod hash_to_field;
+
+/// Squeezing elliptic curve points from a sponge
+pub mod hash_to_curve;
+
+/// Sponges over the fields of a cycle of curves, with matching native and in-circuit encodings
+pub mod cycle;
+
+/// A Merkle tree whose nodes are compressed with a sponge
+pub mod merkle;
+
+/// A cheap, deterministic and insecure sponge for the tests of downstream protocols
+pub mod test_sponge;
+
+/// A randomized harness checking that the gadgets of a sponge agree with the native sponge
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+
+/// Known-answer test vectors of the sponges, serializable as JSON
+#[cfg(feature = "test-vectors")]
+pub mod test_vectors;
+
+/// The collision-resistant hash functions of `ark-crypto-primitives` over Poseidon
+#[cfg(feature = "crypto-primitives")]
+pub mod crh;
+
 /// An enum for specifying the output field element size.
 #[derive(Clone, Copy, Eq, PartialEq)]
 pub enum FieldElementSize {
@@ -46,61 +189,144 @@ pub enum FieldElementSize {
 }
 
 impl FieldElementSize {
-    pub(crate) fn num_bits<F: PrimeField>(&self) -> usize {
-        if let FieldElementSize::Truncated(num_bits) = self {
-            if *num_bits > (F::Params::MODULUS_BITS as usize) {
-                panic!("num_bits is greater than the capacity of the field.")
+    /// Returns the number of bits of an element of this size in `F`, or
+    /// [`SpongeError::TooManyBits`] if it is truncated to more bits than the capacity of `F`.
+    pub fn try_num_bits<F: PrimeField>(&self) -> Result<usize, SpongeError> {
+        let capacity = F::Params::CAPACITY as usize;
+        match self {
+            FieldElementSize::Full => Ok(capacity),
+            FieldElementSize::Truncated(num_bits) if *num_bits > capacity => {
+                Err(SpongeError::TooManyBits {
+                    num_bits: *num_bits,
+                    capacity,
+                })
             }
-        };
-        F::Params::CAPACITY as usize
+            FieldElementSize::Truncated(num_bits) => Ok(*num_bits),
+        }
+    }
+
+    pub(crate) fn num_bits<F: PrimeField>(&self) -> usize {
+        self.try_num_bits::<F>().unwrap_or_else(|e| panic!("{}", e))
     }
 
     /// Calculate the sum of field element sizes in `elements`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an element is truncated to more bits than the capacity of `F`.
     pub fn sum<F: PrimeField>(elements: &[Self]) -> usize {
         elements.iter().map(|item| item.num_bits::<F>()).sum()
     }
 }
 
+/// A security level, from which the capacity and the round numbers of the parameters of a sponge
+/// are derived.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SecurityLevel {
+    /// 128 bits of security.
+    Bits128,
+    /// 256 bits of security.
+    Bits256,
+}
+
+impl SecurityLevel {
+    /// Returns the number of bits of security.
+    pub fn bits(&self) -> usize {
+        match self {
+            SecurityLevel::Bits128 => 128,
+            SecurityLevel::Bits256 => 256,
+        }
+    }
+
+    /// Returns the capacity, in elements of `F`, of a sponge over `F` with this security level.
+    ///
+    /// A sponge with a capacity of `c` bits offers `c / 2` bits of security against generic
+    /// attacks. As in the Poseidon paper, an element counts as its size rounded up to whole
+    /// bytes, so that one element of a 255-bit field is enough for 128 bits of security.
+    pub fn capacity<F: PrimeField>(&self) -> usize {
+        let element_bits = F::size_in_bits().div_ceil(8) * 8;
+        (2 * self.bits()).div_ceil(element_bits)
+    }
+}
+
+/// The number of squeezed bits of an integer of [`CryptographicSponge::squeeze_integers_in_range`]
+/// for `bound`.
+pub(crate) fn integer_in_range_num_bits(bound: u64) -> usize {
+    (64 - bound.leading_zeros()) as usize + UNIFORM_SQUEEZE_NUM_EXTRA_BITS
+}
+
+/// The little-endian integer of `bits` modulo `bound`.
+fn integer_in_range_from_bits(bits: &[bool], bound: u64) -> u64 {
+    bits.iter().rev().fold(0u64, |acc, &bit| {
+        ((((acc as u128) << 1) | bit as u128) % bound as u128) as u64
+    })
+}
+
+/// The security level of a sponge over `F` with `capacity` elements of capacity.
+pub(crate) fn field_sponge_security_level<F: PrimeField>(capacity: usize) -> usize {
+    capacity * F::size_in_bits() / 2
+}
+
 /// Default implementation of `CryptographicSponge::squeeze_field_elements_with_sizes`
 pub(crate) fn squeeze_field_elements_with_sizes_default_impl<F: PrimeField>(
     sponge: &mut impl CryptographicSponge,
     sizes: &[FieldElementSize],
 ) -> Vec<F> {
-    if sizes.len() == 0 {
+    if sizes.is_empty() {
         return Vec::new();
     }
 
-    let mut total_bits = 0usize;
-    for size in sizes {
-        total_bits += size.num_bits::<F>();
-    }
+    let num_bits: Vec<usize> = sizes.iter().map(|size| size.num_bits::<F>()).collect();
+    let bits = sponge.squeeze_bits(num_bits.iter().sum());
 
-    let bits = sponge.squeeze_bits(total_bits);
-    let mut bits_window = bits.as_slice();
+    split_squeezed_bits(&bits, &num_bits)
+        .iter()
+        .map(|nonnative_bits_le| field_element_from_bits(nonnative_bits_le, Endianness::Little))
+        .collect()
+}
 
-    let mut output = Vec::with_capacity(sizes.len());
-    for size in sizes {
-        let num_bits = size.num_bits::<F>();
-        let nonnative_bits_le: Vec<bool> = bits_window[..num_bits].to_vec();
-        bits_window = &bits_window[num_bits..];
+/// The domain separator absorbed by `CryptographicSponge::squeeze_many_field_elements_fast`
+/// before squeezing the seed of its ChaCha20 expansion.
+pub const FAST_SQUEEZE_DOMAIN: &[u8] = b"ark-sponge/squeeze_many_field_elements_fast";
 
-        let nonnative_bytes = nonnative_bits_le
-            .chunks(8)
-            .map(|bits| {
-                let mut byte = 0u8;
-                for (i, &bit) in bits.into_iter().enumerate() {
-                    if bit {
-                        byte += 1 << i;
-                    }
-                }
-                byte
-            })
-            .collect::<Vec<_>>();
+/// The domain separator absorbed by `CryptographicSponge::squeeze_powers` before squeezing its
+/// challenge.
+pub const SQUEEZE_POWERS_DOMAIN: &[u8] = b"ark-sponge/squeeze_powers";
 
-        output.push(F::from_le_bytes_mod_order(nonnative_bytes.as_slice()));
-    }
+/// The bytes absorbed by `CryptographicSponge::squeeze_powers` for `n` powers:
+/// [`SQUEEZE_POWERS_DOMAIN`] followed by `n` as a little-endian `u64`.
+pub(crate) fn squeeze_powers_input(n: usize) -> Vec<u8> {
+    let mut input = SQUEEZE_POWERS_DOMAIN.to_vec();
+    input.extend_from_slice(&(n as u64).to_le_bytes());
+    input
+}
 
-    output
+/// Implementation of `CryptographicSponge::squeeze_field_elements_with_sizes` for byte-oriented
+/// sponges. Full field elements are reduced from 128 bits more than the size of the modulus,
+/// which makes the bias of the reduction negligible.
+pub(crate) fn squeeze_field_elements_with_sizes_from_bytes<F: PrimeField>(
+    sponge: &mut impl CryptographicSponge,
+    sizes: &[FieldElementSize],
+) -> Vec<F> {
+    sizes
+        .iter()
+        .map(|size| match size {
+            FieldElementSize::Full => {
+                let num_bits = F::Params::MODULUS_BITS as usize + 128;
+                F::from_le_bytes_mod_order(&sponge.squeeze_bytes(num_bits.div_ceil(8)))
+            }
+            FieldElementSize::Truncated(_) => {
+                let num_bits = size.num_bits::<F>();
+                let mut bytes = sponge.squeeze_bytes(num_bits.div_ceil(8));
+                if let Some(last) = bytes.last_mut() {
+                    if !num_bits.is_multiple_of(8) {
+                        *last &= (1u8 << (num_bits % 8)) - 1;
+                    }
+                }
+                F::from_le_bytes_mod_order(&bytes)
+            }
+        })
+        .collect()
 }
 
 /// The interface for a cryptographic sponge.
@@ -113,10 +339,58 @@ pub trait CryptographicSponge: Clone {
     /// Initialize a new instance of the sponge.
     fn new(params: &Self::Parameters) -> Self;
 
+    /// The number of native units of the sponge, field elements or bytes, absorbed or squeezed
+    /// per permutation, e.g. to chunk the inputs of a protocol.
+    ///
+    /// This is a method rather than an associated constant, as are [`Self::capacity`] and
+    /// [`Self::security_level`], since the rate of sponges such as [`poseidon::PoseidonSponge`]
+    /// is given by runtime parameters.
+    fn rate(&self) -> usize;
+
+    /// The number of native units of the state that are never absorbed into or squeezed from.
+    fn capacity(&self) -> usize;
+
+    /// The bits of security of the sponge against generic attacks, which is half the size of its
+    /// capacity in bits, e.g. to choose the size of challenges.
+    fn security_level(&self) -> usize;
+
     /// Absorb an input into the sponge.
     fn absorb(&mut self, input: &impl Absorb);
 
+    /// Absorb the items of `iter` one by one, without collecting them.
+    ///
+    /// This is the same as absorbing each item in order, which for field elements is the same as
+    /// absorbing them as a slice.
+    fn absorb_iter<A: Absorb>(&mut self, iter: impl IntoIterator<Item = A>) {
+        for item in iter {
+            self.absorb(&item);
+        }
+    }
+
+    /// Absorbs the length of `input`, as a `usize`, and then `input`, so that adjacent inputs of
+    /// variable length cannot be confused with each other. This is the encoding of
+    /// [`AbsorbWithLength::to_sponge_bytes_with_length`].
+    fn absorb_with_length(&mut self, input: &impl AbsorbWithLength) {
+        self.absorb(&input.absorb_length());
+        self.absorb(input);
+    }
+
+    /// Returns the sponge cached in `cache` under `key`, which is computed by `prefix` the first
+    /// time, typically by absorbing a transcript prefix shared by many proofs. See
+    /// [`PrefixCache`].
+    fn with_cached_prefix<K: Ord>(
+        cache: &mut PrefixCache<K, Self>,
+        key: K,
+        prefix: impl FnOnce() -> Self,
+    ) -> Self {
+        cache.get_or_insert_with(key, prefix)
+    }
+
     /// Squeeze `num_bytes` bytes from the sponge.
+    ///
+    /// The field-based sponges of this crate squeeze the minimal number of native elements,
+    /// [`squeeze_bytes_num_elements`], and read the [`squeeze_bytes_per_element`] low bytes of
+    /// each, in little-endian order.
     fn squeeze_bytes(&mut self, num_bytes: usize) -> Vec<u8>;
 
     /// Squeeze `num_bits` bits from the sponge.
@@ -132,6 +406,11 @@ pub trait CryptographicSponge: Clone {
     ///
     /// Note that when `FieldElementSize` is `FULL`, the output is not strictly uniform. Output
     /// space is uniform in \[0, 2^{F::MODULUS_BITS - 1}\]
+    ///
+    /// # Panics
+    ///
+    /// Panics if a size is truncated to more bits than the capacity of `F`; see
+    /// [`Self::try_squeeze_field_elements_with_sizes`].
     fn squeeze_field_elements_with_sizes<F: PrimeField>(
         &mut self,
         sizes: &[FieldElementSize],
@@ -139,6 +418,19 @@ pub trait CryptographicSponge: Clone {
         squeeze_field_elements_with_sizes_default_impl(self, sizes)
     }
 
+    /// Same as [`Self::squeeze_field_elements_with_sizes`], but returns
+    /// [`SpongeError::TooManyBits`], without squeezing anything, if a size is truncated to more
+    /// bits than the capacity of `F`.
+    fn try_squeeze_field_elements_with_sizes<F: PrimeField>(
+        &mut self,
+        sizes: &[FieldElementSize],
+    ) -> Result<Vec<F>, SpongeError> {
+        for size in sizes {
+            size.try_num_bits::<F>()?;
+        }
+        Ok(self.squeeze_field_elements_with_sizes(sizes))
+    }
+
     /// Squeeze `num_elements` nonnative field elements from the sponge.
     ///
     /// Because of rust limitation, for field-based implementation, using this method to squeeze
@@ -149,8 +441,219 @@ pub trait CryptographicSponge: Clone {
         )
     }
 
-    /// Creates a new sponge with applied domain separation.
+    /// Squeeze `sizes.len()` field elements from the sponge, where the `i`-th element of the
+    /// output has size `sizes[i]`, exactly as
+    /// [`CryptographicSpongeVar::squeeze_nonnative_field_elements_with_sizes`] does in a circuit.
+    ///
+    /// The elements are always built from squeezed bits, even if `F` is the native field of a
+    /// field-based sponge, so that native verifiers derive the same challenges as their gadgets.
+    ///
+    /// [`CryptographicSpongeVar::squeeze_nonnative_field_elements_with_sizes`]: constraints::CryptographicSpongeVar::squeeze_nonnative_field_elements_with_sizes
+    fn squeeze_nonnative_field_elements_with_sizes<F: PrimeField>(
+        &mut self,
+        sizes: &[FieldElementSize],
+    ) -> Vec<F> {
+        squeeze_field_elements_with_sizes_default_impl(self, sizes)
+    }
+
+    /// Squeeze `num_elements` nonnative field elements from the sponge, exactly as
+    /// [`CryptographicSpongeVar::squeeze_nonnative_field_elements`] does in a circuit.
+    ///
+    /// [`CryptographicSpongeVar::squeeze_nonnative_field_elements`]: constraints::CryptographicSpongeVar::squeeze_nonnative_field_elements
+    fn squeeze_nonnative_field_elements<F: PrimeField>(&mut self, num_elements: usize) -> Vec<F> {
+        self.squeeze_nonnative_field_elements_with_sizes::<F>(
+            vec![FieldElementSize::Full; num_elements].as_slice(),
+        )
+    }
+
+    /// Squeeze `num_elements` field elements from the sponge in the given [`SqueezeMode`], with
+    /// the same output as the gadgets.
+    ///
+    /// In [`SqueezeMode::Uniform`], each element is the little-endian integer of
+    /// `MODULUS_BITS + UNIFORM_SQUEEZE_NUM_EXTRA_BITS` squeezed bits, reduced modulo the field.
+    fn squeeze_field_elements_with_mode<F: PrimeField>(
+        &mut self,
+        num_elements: usize,
+        mode: SqueezeMode,
+    ) -> Vec<F> {
+        match mode {
+            SqueezeMode::Truncated => self.squeeze_field_elements(num_elements),
+            SqueezeMode::Uniform => {
+                let num_bits = F::Params::MODULUS_BITS as usize + UNIFORM_SQUEEZE_NUM_EXTRA_BITS;
+                let bits = self.squeeze_bits(num_bits * num_elements);
+                bits.chunks(num_bits)
+                    .map(|bits| {
+                        let bytes = bits
+                            .chunks(8)
+                            .map(|bits| {
+                                bits.iter()
+                                    .enumerate()
+                                    .fold(0u8, |byte, (i, &bit)| byte | ((bit as u8) << i))
+                            })
+                            .collect::<Vec<_>>();
+                        F::from_le_bytes_mod_order(&bytes)
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Squeeze `num_integers` integers uniformly distributed in `[0, bound)`, such as query
+    /// indices, with the same output as the gadgets.
+    ///
+    /// Each integer is the little-endian integer of `k + UNIFORM_SQUEEZE_NUM_EXTRA_BITS` squeezed
+    /// bits, where `k` is the bit length of `bound`, reduced modulo `bound`, which makes it
+    /// statistically close to uniform.
+    ///
+    /// Returns [`SpongeError::EmptyRange`] if `bound` is zero.
+    fn squeeze_integers_in_range(
+        &mut self,
+        bound: u64,
+        num_integers: usize,
+    ) -> Result<Vec<u64>, SpongeError> {
+        if bound == 0 {
+            return Err(SpongeError::EmptyRange);
+        }
+        let num_bits = integer_in_range_num_bits(bound);
+        let bits = self.squeeze_bits(num_bits * num_integers);
+        Ok(bits
+            .chunks(num_bits)
+            .map(|bits| integer_in_range_from_bits(bits, bound))
+            .collect())
+    }
+
+    /// Squeeze `num_indices` distinct integers in `[0, range)`, such as the query positions of
+    /// FRI, with the same output as the gadgets.
+    ///
+    /// The sponge squeezes `num_candidates` candidates, each the little-endian integer of `k`
+    /// squeezed bits, where `k` is the bit length of `range - 1`. The output is the first
+    /// `num_indices` distinct candidates less than `range`, in order: candidates out of the range
+    /// and repetitions are skipped. As all the candidates are squeezed whatever the number of
+    /// retries, the gadgets have a fixed shape. The candidates are uniform, so the output is
+    /// exactly uniform among the sequences of `num_indices` distinct integers of the range,
+    /// without the bias of a modular reduction.
+    ///
+    /// Each candidate is new with probability more than `(range - num_indices) / (2 * range)`,
+    /// which bounds the `num_candidates` needed for a negligible probability of failure.
+    ///
+    /// Returns [`SpongeError::EmptyRange`] if `range` is zero and
+    /// [`SpongeError::TooManyIndices`] if `num_indices` is greater than `range`, without
+    /// squeezing, and [`SpongeError::NotEnoughDistinctIndices`] if the candidates contain fewer
+    /// than `num_indices` distinct integers of the range.
+    fn squeeze_distinct_indices(
+        &mut self,
+        range: u64,
+        num_indices: usize,
+        num_candidates: usize,
+    ) -> Result<Vec<u64>, SpongeError> {
+        if range == 0 {
+            return Err(SpongeError::EmptyRange);
+        }
+        if num_indices as u64 > range {
+            return Err(SpongeError::TooManyIndices { num_indices, range });
+        }
+        let num_bits = (64 - (range - 1).leading_zeros()) as usize;
+        let bits = self.squeeze_bits(num_bits * num_candidates);
+
+        let mut indices = Vec::with_capacity(num_indices);
+        for j in 0..num_candidates {
+            if indices.len() == num_indices {
+                break;
+            }
+            let candidate = bits[j * num_bits..(j + 1) * num_bits]
+                .iter()
+                .rev()
+                .fold(0u64, |acc, &bit| (acc << 1) | bit as u64);
+            if candidate < range && !indices.contains(&candidate) {
+                indices.push(candidate);
+            }
+        }
+        if indices.len() < num_indices {
+            return Err(SpongeError::NotEnoughDistinctIndices {
+                found: indices.len(),
+                expected: num_indices,
+            });
+        }
+        Ok(indices)
+    }
+
+    /// Squeeze a permutation of `[0, n)`, e.g. to shuffle a list or to select a committee, with
+    /// the same output as the gadgets. The `i`-th element of the output is the image of `i`.
+    ///
+    /// The permutation is the Fisher-Yates shuffle of the identity: for `i` from `n - 1` down to
+    /// `1`, the element at position `i` is swapped with the one at a position `j` in `[0, i]`,
+    /// where `j` is the integer of [`CryptographicSponge::squeeze_integers_in_range`] with a bound
+    /// of `i + 1`. The bits of all the `j`s are squeezed at once, in this order. As every `j` is
+    /// statistically close to uniform, so is the permutation.
+    ///
+    /// Nothing is squeezed if `n` is at most 1.
+    fn squeeze_permutation(&mut self, n: usize) -> Vec<usize> {
+        let mut permutation: Vec<usize> = (0..n).collect();
+        if n <= 1 {
+            return permutation;
+        }
+        let num_bits = (1..n)
+            .map(|i| integer_in_range_num_bits(i as u64 + 1))
+            .sum();
+        let bits = self.squeeze_bits(num_bits);
+        let mut offset = 0;
+        for i in (1..n).rev() {
+            let bound = i as u64 + 1;
+            let num_bits = integer_in_range_num_bits(bound);
+            let j = integer_in_range_from_bits(&bits[offset..offset + num_bits], bound);
+            permutation.swap(i, j as usize);
+            offset += num_bits;
+        }
+        permutation
+    }
+
+    /// Squeeze `num_elements` field elements by expanding a seed squeezed from the sponge with
+    /// ChaCha20, which is much faster than squeezing each element from the sponge when many
+    /// elements are needed (e.g. the coefficients of a random linear combination).
+    ///
+    /// The sponge first absorbs [`FAST_SQUEEZE_DOMAIN`] and `num_elements` (as a `u64`), and then
+    /// squeezes 32 bytes, which seed a ChaCha20 RNG from which the elements are sampled. Later
+    /// operations on the sponge are bound to the seed, but not to the expanded elements.
+    fn squeeze_many_field_elements_fast<F: PrimeField>(&mut self, num_elements: usize) -> Vec<F> {
+        self.absorb(&FAST_SQUEEZE_DOMAIN);
+        self.absorb(&(num_elements as u64));
+
+        let mut seed = [0u8; 32];
+        seed.copy_from_slice(&self.squeeze_bytes(32));
+        let mut rng = rand_chacha::ChaCha20Rng::from_seed(seed);
+        (0..num_elements).map(|_| F::rand(&mut rng)).collect()
+    }
+
+    /// Squeeze a challenge `c` and return its first `n` powers `[1, c, c^2, ..., c^(n-1)]`, e.g.
+    /// the coefficients of a random linear combination, with the same output as the gadgets.
+    ///
+    /// The sponge first absorbs the bytes of [`SQUEEZE_POWERS_DOMAIN`] followed by `n` as a
+    /// little-endian `u64`, so that the challenge is bound to its use and to the number of
+    /// powers, and then squeezes `c` with [`CryptographicSponge::squeeze_field_elements`].
+    fn squeeze_powers<F: PrimeField>(&mut self, n: usize) -> Vec<F> {
+        self.absorb(&squeeze_powers_input(n));
+        let challenge = self.squeeze_field_elements::<F>(1)[0];
+        ark_std::iter::successors(Some(F::one()), |power| Some(*power * challenge))
+            .take(n)
+            .collect()
+    }
+
+    /// Returns a [`SpongeWriter`](writer::SpongeWriter) absorbing the bytes written to it into
+    /// the sponge.
+    #[cfg(feature = "std")]
+    fn as_writer(&mut self) -> writer::SpongeWriter<'_, Self> {
+        writer::SpongeWriter::new(self)
+    }
+
+    /// Creates a new sponge with applied domain separation: the sponge is cloned, and `domain`,
+    /// prefixed with its length as a `u64`, is absorbed into the clone as bytes.
+    ///
+    /// The sponge itself is not modified, so several independent sponges, e.g. one per column of
+    /// a matrix, can be derived from one transcript with different tags. The tags are
+    /// length-prefixed, so no tag is a prefix of another one.
     fn fork(&self, domain: &[u8]) -> Self {
+        #[cfg(feature = "trace")]
+        tracing::trace!(target: "ark_sponge", domain = ?domain, "fork");
         let mut new_sponge = self.clone();
 
         let mut input = Absorb::to_sponge_bytes_as_vec(&domain.len());
@@ -164,9 +667,88 @@ pub trait CryptographicSponge: Clone {
 /// The interface for field-based cryptographic sponge.
 /// `CF` is the native field used by the cryptographic sponge implementation.
 pub trait FieldBasedCryptographicSponge<CF: PrimeField>: CryptographicSponge {
+    /// Absorbs native field elements, which is the same as absorbing `elems` as a slice.
+    ///
+    /// The sponges of this crate absorb the elements in place, without copying them into a new
+    /// vector as [`CryptographicSponge::absorb`] does, so that absorbing a large slice does not
+    /// double the memory it uses.
+    fn absorb_native_field_elements(&mut self, elems: &[CF])
+    where
+        CF: Absorb,
+    {
+        self.absorb(&elems);
+    }
+
     /// Squeeze `num_elements` field elements from the sponge.
     fn squeeze_native_field_elements(&mut self, num_elements: usize) -> Vec<CF>;
 
+    /// Absorbs `bits`, packed into native field elements: the number of bits as a `u64`, followed
+    /// by the bits in chunks of `CAPACITY` little-endian bits per element.
+    ///
+    /// This is the packing of [`CryptographicSpongeVar::absorb_bits`], so that bits squeezed
+    /// earlier can be absorbed again in the same way in and out of circuits.
+    ///
+    /// [`CryptographicSpongeVar::absorb_bits`]: constraints::CryptographicSpongeVar::absorb_bits
+    fn absorb_bits(&mut self, bits: &[bool])
+    where
+        CF: Absorb,
+    {
+        self.absorb_native_field_elements(&pack_bits(bits));
+    }
+
+    /// Absorbs `words`, packed into native field elements as their little-endian bits would be
+    /// by [`FieldBasedCryptographicSponge::absorb_bits`]: the number of bits, followed by the
+    /// bits in chunks of `CAPACITY` bits per element.
+    ///
+    /// Over a field of about 64 bits, such as those of
+    /// [`PoseidonParameters::for_small_field`](poseidon::PoseidonParameters::for_small_field),
+    /// a word does not fit in one element, and this packing wastes no bits, whereas absorbing
+    /// the words with [`CryptographicSponge::absorb`] uses two elements per word. Circuits
+    /// absorb the same elements with [`CryptographicSpongeVar::absorb_u64s`].
+    ///
+    /// [`CryptographicSpongeVar::absorb_u64s`]: constraints::CryptographicSpongeVar::absorb_u64s
+    fn absorb_u64s(&mut self, words: &[u64])
+    where
+        CF: Absorb,
+    {
+        let mut elems = vec![CF::from(64 * words.len() as u64)];
+        pack_u64s_without_length(words, &mut elems);
+        self.absorb_native_field_elements(&elems);
+    }
+
+    /// Absorbs the public inputs of a statement, in order and prefixed with their number as
+    /// [`CryptographicSponge::absorb_with_length`] frames them, so that a verifier binds the
+    /// statement as its recursive verifier does with
+    /// [`CryptographicSpongeVar::absorb_public_inputs`].
+    ///
+    /// [`CryptographicSpongeVar::absorb_public_inputs`]: constraints::CryptographicSpongeVar::absorb_public_inputs
+    fn absorb_public_inputs(&mut self, inputs: &[CF])
+    where
+        CF: Absorb,
+    {
+        self.absorb_with_length(&inputs);
+    }
+
+    /// Absorbs elements of another field `F`, e.g. scalars of another curve, in the canonical
+    /// encoding of [`CryptographicSpongeVar::absorb_nonnative`]: the `MODULUS_BITS` little-endian
+    /// bits of each element, in chunks of `CAPACITY` bits per native field element.
+    ///
+    /// A native verifier and a circuit absorbing the same elements thus reach the same state,
+    /// whatever the limbs of the elements in the circuit.
+    ///
+    /// [`CryptographicSpongeVar::absorb_nonnative`]: constraints::CryptographicSpongeVar::absorb_nonnative
+    fn absorb_nonnative<F: PrimeField>(&mut self, elems: &[F])
+    where
+        CF: Absorb,
+    {
+        let mut packed = Vec::new();
+        for elem in elems {
+            let bits = elem.into_repr().to_bits_le();
+            pack_bits_without_length(&bits[..F::Params::MODULUS_BITS as usize], &mut packed);
+        }
+        self.absorb_native_field_elements(&packed);
+    }
+
     /// Squeeze `sizes.len()` field elements from the sponge, where the `i`-th element of
     /// the output has size `sizes[i]`.
     fn squeeze_native_field_elements_with_sizes(&mut self, sizes: &[FieldElementSize]) -> Vec<CF> {
@@ -184,6 +766,163 @@ pub trait FieldBasedCryptographicSponge<CF: PrimeField>: CryptographicSponge {
             squeeze_field_elements_with_sizes_default_impl(self, sizes)
         }
     }
+
+    /// Squeeze `num_bits` bits from the sponge in `endianness`, with the same output as
+    /// [`CryptographicSpongeVar::squeeze_bits_with_endianness`].
+    ///
+    /// The bits are those of [`CryptographicSponge::squeeze_bits`], in chunks of
+    /// [`squeeze_bits_per_element`] bits per squeezed element, and [`Endianness::Big`] reverses
+    /// the order of the bits of each chunk. A last partial chunk is made of the most
+    /// significant bits of its element.
+    ///
+    /// [`CryptographicSpongeVar::squeeze_bits_with_endianness`]: constraints::CryptographicSpongeVar::squeeze_bits_with_endianness
+    fn squeeze_bits_with_endianness(
+        &mut self,
+        num_bits: usize,
+        endianness: Endianness,
+    ) -> Vec<bool> {
+        let bits_per_element = squeeze_bits_per_element::<CF>();
+        let bits = self.squeeze_bits(squeezed_units_for_endianness(
+            num_bits,
+            bits_per_element,
+            endianness,
+        ));
+        order_squeezed_units(bits, bits_per_element, num_bits, endianness)
+    }
+
+    /// Squeeze `num_bytes` bytes from the sponge in `endianness`, with the same output as
+    /// [`CryptographicSpongeVar::squeeze_bytes_with_endianness`].
+    ///
+    /// The bytes are those of [`CryptographicSponge::squeeze_bytes`], in chunks of
+    /// [`squeeze_bytes_per_element`] bytes per squeezed element, and [`Endianness::Big`]
+    /// reverses the order of the bytes of each chunk. A last partial chunk is made of the most
+    /// significant bytes of its element.
+    ///
+    /// [`CryptographicSpongeVar::squeeze_bytes_with_endianness`]: constraints::CryptographicSpongeVar::squeeze_bytes_with_endianness
+    fn squeeze_bytes_with_endianness(
+        &mut self,
+        num_bytes: usize,
+        endianness: Endianness,
+    ) -> Vec<u8> {
+        let bytes_per_element = squeeze_bytes_per_element::<CF>();
+        let bytes = self.squeeze_bytes(squeezed_units_for_endianness(
+            num_bytes,
+            bytes_per_element,
+            endianness,
+        ));
+        order_squeezed_units(bytes, bytes_per_element, num_bytes, endianness)
+    }
+
+    /// Squeeze `sizes.len()` nonnative field elements from the sponge, as
+    /// [`CryptographicSponge::squeeze_nonnative_field_elements_with_sizes`], but from the bits of
+    /// [`Self::squeeze_bits_with_endianness`], each element being the integer of its bits in
+    /// `endianness`. The output is the same as
+    /// [`CryptographicSpongeVar::squeeze_nonnative_field_elements_with_sizes_and_endianness`].
+    ///
+    /// [`CryptographicSpongeVar::squeeze_nonnative_field_elements_with_sizes_and_endianness`]: constraints::CryptographicSpongeVar::squeeze_nonnative_field_elements_with_sizes_and_endianness
+    fn squeeze_nonnative_field_elements_with_sizes_and_endianness<F: PrimeField>(
+        &mut self,
+        sizes: &[FieldElementSize],
+        endianness: Endianness,
+    ) -> Vec<F> {
+        let num_bits: Vec<usize> = sizes.iter().map(|size| size.num_bits::<F>()).collect();
+        let bits = self.squeeze_bits_with_endianness(num_bits.iter().sum(), endianness);
+        split_squeezed_bits(&bits, &num_bits)
+            .iter()
+            .map(|bits| field_element_from_bits(bits, endianness))
+            .collect()
+    }
+
+    /// Squeeze `num_challenges` short challenges of `num_bits` bits each (e.g. 128), embedded in
+    /// the native field, with the same output as
+    /// [`CryptographicSpongeVar::squeeze_short_challenges`].
+    ///
+    /// Returns [`SpongeError::TooManyBits`] if `num_bits` is greater than the capacity of the
+    /// field.
+    ///
+    /// [`CryptographicSpongeVar::squeeze_short_challenges`]: constraints::CryptographicSpongeVar::squeeze_short_challenges
+    fn squeeze_short_challenges(
+        &mut self,
+        num_challenges: usize,
+        num_bits: usize,
+    ) -> Result<Vec<CF>, SpongeError> {
+        let capacity = CF::Params::CAPACITY as usize;
+        if num_bits > capacity {
+            return Err(SpongeError::TooManyBits { num_bits, capacity });
+        }
+        Ok(squeeze_field_elements_with_sizes_default_impl(
+            self,
+            &vec![FieldElementSize::Truncated(num_bits); num_challenges],
+        ))
+    }
+
+    /// Returns an infinite iterator squeezing native field elements on demand, for protocols that
+    /// do not know in advance how many challenges they need.
+    ///
+    /// Taking `n` elements from the iterator is the same as squeezing `n` elements at once.
+    fn squeeze_field_elements_iter(&mut self) -> SqueezeIter<'_, CF, Self> {
+        SqueezeIter {
+            sponge: self,
+            _field: PhantomData,
+        }
+    }
+
+    /// Absorbs `input` in tree mode: every chunk of `chunk_size` items is absorbed, with its
+    /// index, into a copy of the current sponge, which is squeezed into a digest of the chunk.
+    /// The sponge then absorbs the length of `input` and the digests of the chunks.
+    ///
+    /// The output differs from absorbing `input` directly, but the chunks are independent, so
+    /// they are hashed in parallel with the `parallel` feature, which speeds up the absorption of
+    /// large inputs such as polynomial evaluations.
+    ///
+    /// Returns [`SpongeError::InvalidParameters`] if `chunk_size` is zero.
+    fn absorb_tree<A: Absorb + Sync>(
+        &mut self,
+        input: &[A],
+        chunk_size: usize,
+    ) -> Result<(), SpongeError>
+    where
+        Self: Clone + Sync,
+        CF: Absorb,
+    {
+        if chunk_size == 0 {
+            return Err(SpongeError::InvalidParameters(
+                "the chunk size must be positive",
+            ));
+        }
+        let sponge: &Self = self;
+        let digests: Vec<CF> = cfg_chunks!(input, chunk_size)
+            .enumerate()
+            .map(|(index, chunk)| {
+                let mut chunk_sponge = sponge.clone();
+                chunk_sponge.absorb(&(index as u64));
+                chunk_sponge.absorb(&chunk);
+                chunk_sponge.squeeze_native_field_elements(1)[0]
+            })
+            .collect();
+        self.absorb(&(input.len() as u64));
+        self.absorb_native_field_elements(&digests);
+        Ok(())
+    }
+}
+
+/// An infinite iterator squeezing native field elements from a sponge, returned by
+/// [`FieldBasedCryptographicSponge::squeeze_field_elements_iter`].
+pub struct SqueezeIter<'a, CF: PrimeField, S: FieldBasedCryptographicSponge<CF>> {
+    sponge: &'a mut S,
+    _field: PhantomData<CF>,
+}
+
+impl<'a, CF: PrimeField, S: FieldBasedCryptographicSponge<CF>> Iterator for SqueezeIter<'a, CF, S> {
+    type Item = CF;
+
+    fn next(&mut self) -> Option<CF> {
+        self.sponge.squeeze_native_field_elements(1).pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
 }
 
 /// An extension for the interface of a cryptographic sponge.
@@ -193,13 +932,89 @@ pub trait SpongeExt: CryptographicSponge {
     /// The full state of the cryptographic sponge.
     type State: Clone;
     /// Returns a sponge that uses `state`.
+    ///
+    /// Implementations may panic if `state` does not fit `params`, e.g. a checkpoint of a sponge
+    /// with other parameters; [`Self::try_from_state`] returns an error instead.
     fn from_state(state: Self::State, params: &Self::Parameters) -> Self;
+    /// Same as [`Self::from_state`], but returns an error, e.g. [`SpongeError::InvalidState`], if
+    /// `state` does not fit `params`. The sponges of this crate check the state; the default
+    /// implementation calls [`Self::from_state`].
+    fn try_from_state(state: Self::State, params: &Self::Parameters) -> Result<Self, SpongeError> {
+        Ok(Self::from_state(state, params))
+    }
     /// Consumes `self` and returns the state.
     fn into_state(self) -> Self::State;
 }
 
+/// Counters of the work done by a sponge instance, returned by the `stats` method of the sponges
+/// when the `stats` feature is enabled.
+#[cfg(feature = "stats")]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SpongeStats {
+    /// The number of permutations applied to the state.
+    pub num_permutations: u64,
+    /// The number of field elements absorbed, excluding padding.
+    pub num_absorbed: u64,
+    /// The number of native field elements squeezed. Bytes, bits, and nonnative field elements
+    /// count as the native field elements they are squeezed from.
+    pub num_squeezed: u64,
+}
+
+/// The padding applied by a sponge to its input when it starts squeezing, so that inputs of
+/// different lengths are never absorbed into the same state.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum PaddingMode {
+    /// No padding: absorbing `x` and `(x, 0)` lead to the same state.
+    #[default]
+    None,
+    /// The multi-rate padding `10*1`: a one is added after the input, and another one to the
+    /// last element of the rate, which may take an extra permutation.
+    MultiRate,
+    /// The number of field elements absorbed since the last squeeze is absorbed after them.
+    LengthSuffix,
+}
+
+/// How a sponge absorbs field elements into the rate of its state.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum AbsorbMode {
+    /// The elements are added to the rate, as in the duplex construction.
+    #[default]
+    Add,
+    /// The elements overwrite the rate, as in the overwrite mode of some duplex-based schemes.
+    Overwrite,
+}
+
+/// How field elements are squeezed by `squeeze_field_elements_with_mode`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum SqueezeMode {
+    /// As `squeeze_field_elements`: native field elements are read from the state of
+    /// field-based sponges, and other field elements are built from `CAPACITY` squeezed bits,
+    /// which is not uniform over the field.
+    #[default]
+    Truncated,
+    /// Every field element is reduced from [`UNIFORM_SQUEEZE_NUM_EXTRA_BITS`] bits more than the
+    /// size of the modulus, which makes it statistically close to uniform.
+    Uniform,
+}
+
+/// The number of squeezed bits beyond the size of the modulus that are reduced into each field
+/// element in [`SqueezeMode::Uniform`].
+pub const UNIFORM_SQUEEZE_NUM_EXTRA_BITS: usize = 128;
+
+/// The order of the bits and bytes of each squeezed element in
+/// `squeeze_bits_with_endianness`, `squeeze_bytes_with_endianness`, and
+/// `squeeze_nonnative_field_elements_with_sizes_and_endianness`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Endianness {
+    /// The least significant bit or byte first, as `squeeze_bits` and `squeeze_bytes`.
+    #[default]
+    Little,
+    /// The most significant bit or byte first, as circom circuits and the EVM expect.
+    Big,
+}
+
 /// The mode structure for duplex sponges
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum DuplexSpongeMode {
     /// The sponge is currently absorbing data.
     Absorbing {
@@ -212,3 +1027,130 @@ pub enum DuplexSpongeMode {
         next_squeeze_index: usize,
     },
 }
+
+/// The version of the serialization format of the sponge states, written as their first byte.
+///
+/// Deserializing a state written with another version fails, so that a checkpoint is never
+/// misread after the format changes.
+pub const SPONGE_STATE_VERSION: u8 = 2;
+
+/// Reads the version byte of a serialized sponge state and checks that it is
+/// [`SPONGE_STATE_VERSION`].
+pub(crate) fn deserialize_state_version<R: Read>(reader: R) -> Result<(), SerializationError> {
+    if u8::deserialize(reader)? != SPONGE_STATE_VERSION {
+        return Err(SerializationError::InvalidData);
+    }
+    Ok(())
+}
+
+/// Checks that a state of `state_len` elements in `mode` can be resumed by a sponge of the given
+/// rate and capacity.
+pub(crate) fn check_sponge_state(
+    state_len: usize,
+    mode: &DuplexSpongeMode,
+    rate: usize,
+    capacity: usize,
+) -> Result<(), SpongeError> {
+    if state_len != rate + capacity {
+        return Err(SpongeError::InvalidState(
+            "the state must have `rate + capacity` elements",
+        ));
+    }
+    let index = match mode {
+        DuplexSpongeMode::Absorbing { next_absorb_index } => next_absorb_index,
+        DuplexSpongeMode::Squeezing { next_squeeze_index } => next_squeeze_index,
+    };
+    if *index > rate {
+        return Err(SpongeError::InvalidState(
+            "the index of the mode is beyond the rate",
+        ));
+    }
+    Ok(())
+}
+
+/// A mode is serialized as a tag byte, `0` for absorbing and `1` for squeezing, followed by the
+/// index as a `u64`.
+impl CanonicalSerialize for DuplexSpongeMode {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        let (tag, index) = match self {
+            DuplexSpongeMode::Absorbing { next_absorb_index } => (0u8, next_absorb_index),
+            DuplexSpongeMode::Squeezing { next_squeeze_index } => (1u8, next_squeeze_index),
+        };
+        tag.serialize(&mut writer)?;
+        index.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        1 + 8
+    }
+}
+
+impl CanonicalDeserialize for DuplexSpongeMode {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let tag = u8::deserialize(&mut reader)?;
+        let index = usize::deserialize(&mut reader)?;
+        match tag {
+            0 => Ok(DuplexSpongeMode::Absorbing {
+                next_absorb_index: index,
+            }),
+            1 => Ok(DuplexSpongeMode::Squeezing {
+                next_squeeze_index: index,
+            }),
+            _ => Err(SerializationError::InvalidData),
+        }
+    }
+}
+
+/// An absorb mode is serialized as a tag byte: `0` for [`AbsorbMode::Add`] and `1` for
+/// [`AbsorbMode::Overwrite`].
+impl CanonicalSerialize for AbsorbMode {
+    fn serialize<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        let tag: u8 = match self {
+            AbsorbMode::Add => 0,
+            AbsorbMode::Overwrite => 1,
+        };
+        tag.serialize(writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        1
+    }
+}
+
+impl CanonicalDeserialize for AbsorbMode {
+    fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        match u8::deserialize(reader)? {
+            0 => Ok(AbsorbMode::Add),
+            1 => Ok(AbsorbMode::Overwrite),
+            _ => Err(SerializationError::InvalidData),
+        }
+    }
+}
+
+/// A padding mode is serialized as a tag byte: `0` for [`PaddingMode::None`], `1` for
+/// [`PaddingMode::MultiRate`], and `2` for [`PaddingMode::LengthSuffix`].
+impl CanonicalSerialize for PaddingMode {
+    fn serialize<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        let tag: u8 = match self {
+            PaddingMode::None => 0,
+            PaddingMode::MultiRate => 1,
+            PaddingMode::LengthSuffix => 2,
+        };
+        tag.serialize(writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        1
+    }
+}
+
+impl CanonicalDeserialize for PaddingMode {
+    fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        match u8::deserialize(reader)? {
+            0 => Ok(PaddingMode::None),
+            1 => Ok(PaddingMode::MultiRate),
+            2 => Ok(PaddingMode::LengthSuffix),
+            _ => Err(SerializationError::InvalidData),
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use crate::transcript::Transcript;
+use crate::{Absorb, CryptographicSponge};
+
+/// The size of the tags of [`SpongeMac`], in bytes.
+pub const MAC_TAG_SIZE: usize = 32;
+
+/// The domain of the transcripts of [`SpongeMac`].
+const MAC_DOMAIN: &[u8] = b"ark-sponge/mac";
+
+/// A message authentication code over any cryptographic sponge, keyed with any absorbable key.
+///
+/// The key and every part of the message are appended to a [`Transcript`] with distinct labels,
+/// and the tag is squeezed from it, so the key and the message can never be confused. Note that
+/// a message passed to [`SpongeMac::update`] in several parts gets a different tag than the
+/// same message passed at once.
+#[derive(Clone)]
+pub struct SpongeMac<S: CryptographicSponge> {
+    transcript: Transcript<S>,
+}
+
+impl<S: CryptographicSponge> SpongeMac<S> {
+    /// Starts a MAC with the key `key`.
+    pub fn new(params: &S::Parameters, key: &impl Absorb) -> Self {
+        let mut transcript = Transcript::new(params, MAC_DOMAIN);
+        transcript.append(b"key", key);
+        Self { transcript }
+    }
+
+    /// Absorbs the next part of the message.
+    pub fn update(&mut self, message: &impl Absorb) {
+        self.transcript.append(b"message", message);
+    }
+
+    /// Returns the tag of the message.
+    pub fn finalize(mut self) -> [u8; MAC_TAG_SIZE] {
+        let mut tag = [0u8; MAC_TAG_SIZE];
+        tag.copy_from_slice(&self.transcript.challenge_bytes(b"tag", MAC_TAG_SIZE));
+        tag
+    }
+
+    /// Checks that `tag` is the tag of the message, in constant time.
+    pub fn verify(self, tag: &[u8]) -> bool {
+        if tag.len() != MAC_TAG_SIZE {
+            return false;
+        }
+        let difference = self
+            .finalize()
+            .iter()
+            .zip(tag)
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b));
+        difference == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mac::SpongeMac;
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::PoseidonSponge;
+    use ark_test_curves::bls12_381::Fr;
+
+    type Mac = SpongeMac<PoseidonSponge<Fr>>;
+
+    #[test]
+    fn test_mac() {
+        let params = poseidon_parameters_for_test();
+        let key = Fr::from(1234u64);
+        let mac = |key: &Fr, message: &[u8]| {
+            let mut mac = Mac::new(&params, key);
+            mac.update(&message);
+            mac
+        };
+
+        let tag = mac(&key, b"message").finalize();
+        assert!(mac(&key, b"message").verify(&tag));
+        assert!(!mac(&key, b"other message").verify(&tag));
+        assert!(!mac(&Fr::from(1235u64), b"message").verify(&tag));
+        assert!(!mac(&key, b"message").verify(&tag[..31]));
+
+        let mut forged = tag;
+        forged[0] ^= 1;
+        assert!(!mac(&key, b"message").verify(&forged));
+    }
+}
@@ -0,0 +1,195 @@
+use crate::constraints::{AbsorbGadget, CryptographicSpongeVar, SpongeWithGadget};
+use crate::merkle::{Path, MERKLE_LEAF_DOMAIN, MERKLE_NODE_DOMAIN};
+use crate::{Absorb, FieldBasedCryptographicSponge};
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+use ark_std::borrow::Borrow;
+use ark_std::marker::PhantomData;
+use ark_std::vec::Vec;
+
+/// The gadget for [`Path`], which verifies authentication paths of a
+/// [`MerkleTree`](crate::merkle::MerkleTree).
+///
+/// The position of the node on the path among its siblings is allocated as a one-hot vector at
+/// each level, so the index of the leaf is not revealed unless it is enforced by the caller
+/// with [`PathVar::leaf_index`].
+pub struct PathVar<F: PrimeField, S: SpongeWithGadget<F>> {
+    /// For each level from the leaves, the one-hot position of the node among its siblings.
+    positions: Vec<Vec<Boolean<F>>>,
+    /// For each level from the leaves, the `arity - 1` siblings of the node, in order.
+    siblings: Vec<Vec<FpVar<F>>>,
+    _sponge: PhantomData<S>,
+}
+
+impl<F, S> AllocVar<Path<F, S>, F> for PathVar<F, S>
+where
+    F: PrimeField + Absorb,
+    S: FieldBasedCryptographicSponge<F> + SpongeWithGadget<F>,
+{
+    fn new_variable<T: Borrow<Path<F, S>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+
+        let value = f()?;
+        let path = value.borrow();
+        let mut positions = Vec::with_capacity(path.siblings.len());
+        let mut siblings = Vec::with_capacity(path.siblings.len());
+        let mut node_index = path.leaf_index;
+        for level_siblings in &path.siblings {
+            let arity = level_siblings.len() + 1;
+            let position = (0..arity)
+                .map(|j| Boolean::new_variable(cs.clone(), || Ok(node_index % arity == j), mode))
+                .collect::<Result<Vec<_>, _>>()?;
+            positions.push(position);
+            siblings.push(Vec::new_variable(
+                cs.clone(),
+                || Ok(level_siblings.clone()),
+                mode,
+            )?);
+            node_index /= arity;
+        }
+
+        Ok(Self {
+            positions,
+            siblings,
+            _sponge: PhantomData,
+        })
+    }
+}
+
+impl<F: PrimeField, S: SpongeWithGadget<F>> PathVar<F, S> {
+    fn cs(&self) -> ConstraintSystemRef<F> {
+        self.positions
+            .iter()
+            .flatten()
+            .map(|is_j| is_j.cs())
+            .chain(self.siblings.iter().flatten().map(|sibling| sibling.cs()))
+            .fold(ConstraintSystemRef::None, |cs, other| cs.or(other))
+    }
+
+    /// Returns the index of the leaf.
+    pub fn leaf_index(&self) -> Result<FpVar<F>, SynthesisError> {
+        let mut index = FpVar::zero();
+        let mut num_leaves = F::one();
+        for position in &self.positions {
+            for (j, is_j) in position.iter().enumerate() {
+                index += FpVar::from(is_j.clone()) * (num_leaves * F::from(j as u64));
+            }
+            num_leaves *= F::from(position.len() as u64);
+        }
+        Ok(index)
+    }
+
+    /// Returns the root of the tree in which `leaf` has this path.
+    #[tracing::instrument(target = "r1cs", skip(self, params, leaf))]
+    pub fn calculate_root(
+        &self,
+        params: &<S::Var as CryptographicSpongeVar<F, S>>::Parameters,
+        leaf: &impl AbsorbGadget<F>,
+    ) -> Result<FpVar<F>, SynthesisError> {
+        let cs = self.cs();
+        let mut sponge = S::Var::new(cs.clone(), params);
+        sponge.absorb(&FpVar::Constant(F::from_le_bytes_mod_order(
+            MERKLE_LEAF_DOMAIN,
+        )))?;
+        sponge.absorb(leaf)?;
+        let mut node = sponge.squeeze_field_elements(1)?.remove(0);
+
+        for (position, level_siblings) in self.positions.iter().zip(&self.siblings) {
+            let mut num_set = FpVar::zero();
+            for is_j in position {
+                num_set += FpVar::from(is_j.clone());
+            }
+            num_set.enforce_equal(&FpVar::one())?;
+
+            // The `j`-th child is the node if it is at position `j`, the `(j - 1)`-th sibling if
+            // the node is before `j`, and the `j`-th sibling otherwise.
+            let mut children = Vec::with_capacity(position.len());
+            let mut before = FpVar::zero();
+            for (j, is_j) in position.iter().enumerate() {
+                let is_j = FpVar::from(is_j.clone());
+                let after = FpVar::one() - &before - &is_j;
+                let mut child = &is_j * &node;
+                if j > 0 {
+                    child += &before * &level_siblings[j - 1];
+                }
+                if j < level_siblings.len() {
+                    child += &after * &level_siblings[j];
+                }
+                children.push(child);
+                before += is_j;
+            }
+
+            let mut sponge = S::Var::new(cs.clone(), params);
+            sponge.absorb(&FpVar::Constant(F::from_le_bytes_mod_order(
+                MERKLE_NODE_DOMAIN,
+            )))?;
+            sponge.absorb(&children)?;
+            node = sponge.squeeze_field_elements(1)?.remove(0);
+        }
+        Ok(node)
+    }
+
+    /// Returns whether `leaf` is at the index of this path in the tree with the root `root`.
+    #[tracing::instrument(target = "r1cs", skip(self, params, root, leaf))]
+    pub fn verify_membership(
+        &self,
+        params: &<S::Var as CryptographicSpongeVar<F, S>>::Parameters,
+        root: &FpVar<F>,
+        leaf: &impl AbsorbGadget<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        self.calculate_root(params, leaf)?.is_eq(root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::merkle::constraints::PathVar;
+    use crate::merkle::MerkleTree;
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::PoseidonSponge;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn test_path_var() {
+        let params = poseidon_parameters_for_test();
+        let leaves: Vec<_> = (0..7u64).map(Fr::from).collect();
+
+        for arity in 2..5 {
+            let tree = MerkleTree::<Fr, PoseidonSponge<Fr>>::new(&params, arity, &leaves).unwrap();
+            for (i, leaf) in leaves.iter().enumerate() {
+                let cs = ConstraintSystem::new_ref();
+                let path = tree.generate_proof(i).unwrap();
+                let path_var = PathVar::new_witness(cs.clone(), || Ok(path)).unwrap();
+                let root_var = FpVar::new_input(cs.clone(), || Ok(tree.root())).unwrap();
+                let leaf_var = FpVar::new_witness(cs.clone(), || Ok(*leaf)).unwrap();
+
+                let is_member = path_var
+                    .verify_membership(&params, &root_var, &leaf_var)
+                    .unwrap();
+                assert!(is_member.value().unwrap());
+                assert_eq!(
+                    path_var.leaf_index().unwrap().value().unwrap(),
+                    Fr::from(i as u64)
+                );
+
+                let other_leaf = FpVar::Constant(Fr::from(7u64));
+                let is_member = path_var
+                    .verify_membership(&params, &root_var, &other_leaf)
+                    .unwrap();
+                assert!(!is_member.value().unwrap());
+                assert!(cs.is_satisfied().unwrap());
+            }
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use crate::{Absorb, FieldBasedCryptographicSponge, SpongeError};
+use ark_ff::PrimeField;
+use ark_std::marker::PhantomData;
+use ark_std::vec::Vec;
+
+/// constraints for the sponge Merkle tree
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+/// The domain separator absorbed before a leaf of a [`MerkleTree`].
+pub const MERKLE_LEAF_DOMAIN: &[u8] = b"ark-sponge/merkle/leaf";
+
+/// The domain separator absorbed before the children of an inner node of a [`MerkleTree`].
+pub const MERKLE_NODE_DOMAIN: &[u8] = b"ark-sponge/merkle/node";
+
+/// Hashes `leaf` into a leaf node.
+fn hash_leaf<F, S>(params: &S::Parameters, leaf: &impl Absorb) -> F
+where
+    F: PrimeField + Absorb,
+    S: FieldBasedCryptographicSponge<F>,
+{
+    let mut sponge = S::new(params);
+    sponge.absorb(&F::from_le_bytes_mod_order(MERKLE_LEAF_DOMAIN));
+    sponge.absorb(leaf);
+    sponge.squeeze_native_field_elements(1)[0]
+}
+
+/// Compresses `children` into their parent node.
+fn compress<F, S>(params: &S::Parameters, children: &[F]) -> F
+where
+    F: PrimeField + Absorb,
+    S: FieldBasedCryptographicSponge<F>,
+{
+    let mut sponge = S::new(params);
+    sponge.absorb(&F::from_le_bytes_mod_order(MERKLE_NODE_DOMAIN));
+    sponge.absorb_native_field_elements(children);
+    sponge.squeeze_native_field_elements(1)[0]
+}
+
+/// A Merkle tree of arbitrary arity whose nodes are compressed with a sponge over `F`.
+///
+/// A leaf node is the first native field element squeezed after absorbing
+/// [`MERKLE_LEAF_DOMAIN`] (as a field element) and the leaf, and an inner node is the first
+/// element squeezed after absorbing [`MERKLE_NODE_DOMAIN`] and its `arity` children. The leaves
+/// are padded with zero nodes up to a power of the arity.
+pub struct MerkleTree<F: PrimeField + Absorb, S: FieldBasedCryptographicSponge<F>> {
+    arity: usize,
+    num_leaves: usize,
+    /// The nodes of each level, from the leaves to the root.
+    levels: Vec<Vec<F>>,
+    _sponge: PhantomData<S>,
+}
+
+impl<F: PrimeField + Absorb, S: FieldBasedCryptographicSponge<F>> MerkleTree<F, S> {
+    /// Builds the tree of `leaves`, whose inner nodes have `arity` children.
+    ///
+    /// Returns [`SpongeError::InvalidParameters`] if `arity` is less than 2 or if there are no
+    /// leaves.
+    pub fn new<L: Absorb>(
+        params: &S::Parameters,
+        arity: usize,
+        leaves: &[L],
+    ) -> Result<Self, SpongeError> {
+        if arity < 2 {
+            return Err(SpongeError::InvalidParameters(
+                "the arity of a Merkle tree must be at least 2",
+            ));
+        }
+        if leaves.is_empty() {
+            return Err(SpongeError::InvalidParameters(
+                "a Merkle tree must have at least one leaf",
+            ));
+        }
+
+        let mut levels = Vec::new();
+        let mut level: Vec<F> = leaves
+            .iter()
+            .map(|leaf| hash_leaf::<F, S>(params, leaf))
+            .collect();
+        while level.len() > 1 {
+            level.resize(level.len().div_ceil(arity) * arity, F::zero());
+            let parents = level
+                .chunks(arity)
+                .map(|children| compress::<F, S>(params, children))
+                .collect();
+            levels.push(level);
+            level = parents;
+        }
+        levels.push(level);
+
+        Ok(Self {
+            arity,
+            num_leaves: leaves.len(),
+            levels,
+            _sponge: PhantomData,
+        })
+    }
+
+    /// Returns the number of leaves, without the padding.
+    pub fn num_leaves(&self) -> usize {
+        self.num_leaves
+    }
+
+    /// Returns the number of children of the inner nodes.
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    /// Returns the number of inner levels, which is the length of the authentication paths.
+    pub fn height(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    /// Returns the root of the tree.
+    pub fn root(&self) -> F {
+        self.levels[self.height()][0]
+    }
+
+    /// Returns the authentication path of the leaf at `index`, or `None` if there is no such
+    /// leaf.
+    pub fn generate_proof(&self, index: usize) -> Option<Path<F, S>> {
+        if index >= self.num_leaves {
+            return None;
+        }
+
+        let mut siblings = Vec::with_capacity(self.height());
+        let mut node_index = index;
+        for level in &self.levels[..self.height()] {
+            let first = node_index - node_index % self.arity;
+            let mut level_siblings = level[first..first + self.arity].to_vec();
+            level_siblings.remove(node_index % self.arity);
+            siblings.push(level_siblings);
+            node_index /= self.arity;
+        }
+
+        Some(Path {
+            leaf_index: index,
+            siblings,
+            _sponge: PhantomData,
+        })
+    }
+}
+
+/// The authentication path of a leaf of a [`MerkleTree`].
+#[derive(Clone)]
+pub struct Path<F: PrimeField + Absorb, S: FieldBasedCryptographicSponge<F>> {
+    /// The index of the leaf.
+    pub leaf_index: usize,
+    /// For each level from the leaves, the `arity - 1` siblings of the node on the path, in
+    /// order.
+    pub siblings: Vec<Vec<F>>,
+    _sponge: PhantomData<S>,
+}
+
+impl<F: PrimeField + Absorb, S: FieldBasedCryptographicSponge<F>> Path<F, S> {
+    /// Returns the root of the tree in which `leaf` has this path.
+    pub fn calculate_root(&self, params: &S::Parameters, leaf: &impl Absorb) -> F {
+        let mut node = hash_leaf::<F, S>(params, leaf);
+        let mut node_index = self.leaf_index;
+        for level_siblings in &self.siblings {
+            let arity = level_siblings.len() + 1;
+            let mut children = level_siblings.clone();
+            children.insert(node_index % arity, node);
+            node = compress::<F, S>(params, &children);
+            node_index /= arity;
+        }
+        node
+    }
+
+    /// Checks that `leaf` is at the index of this path in the tree with the root `root`.
+    pub fn verify(&self, params: &S::Parameters, root: &F, leaf: &impl Absorb) -> bool {
+        let num_leaves = self.siblings.iter().try_fold(1usize, |n, level_siblings| {
+            n.checked_mul(level_siblings.len() + 1)
+        });
+        match num_leaves {
+            Some(n) if self.leaf_index < n => self.calculate_root(params, leaf) == *root,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::merkle::MerkleTree;
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::PoseidonSponge;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    type Tree = MerkleTree<Fr, PoseidonSponge<Fr>>;
+
+    #[test]
+    fn test_merkle_tree() {
+        let params = poseidon_parameters_for_test();
+        let leaves: Vec<_> = (0..10u64).map(Fr::from).collect();
+
+        for arity in 2..5 {
+            let tree = Tree::new(&params, arity, &leaves).unwrap();
+            let root = tree.root();
+            for (i, leaf) in leaves.iter().enumerate() {
+                let path = tree.generate_proof(i).unwrap();
+                assert!(path.verify(&params, &root, leaf));
+                assert!(!path.verify(&params, &root, &Fr::from(10u64)));
+
+                let mut wrong_index = path.clone();
+                wrong_index.leaf_index = (i + 1) % leaves.len();
+                assert!(!wrong_index.verify(&params, &root, leaf));
+            }
+            assert!(tree.generate_proof(leaves.len()).is_none());
+        }
+
+        // The leaves are hashed, so a tree of one leaf has a path of length zero.
+        let tree = Tree::new(&params, 2, &leaves[..1]).unwrap();
+        assert_eq!(tree.height(), 0);
+        assert!(tree
+            .generate_proof(0)
+            .unwrap()
+            .verify(&params, &tree.root(), &leaves[0]));
+        assert!(tree.generate_proof(1).is_none());
+
+        assert!(Tree::new(&params, 1, &leaves).is_err());
+        assert!(Tree::new::<Fr>(&params, 2, &[]).is_err());
+    }
+}
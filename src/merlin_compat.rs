@@ -0,0 +1,168 @@
+use crate::transcript::Transcript;
+use crate::{
+    squeeze_field_elements_with_sizes_from_bytes, Absorb, CryptographicSponge, FieldElementSize,
+};
+use ark_ff::PrimeField;
+use ark_std::vec;
+use ark_std::vec::Vec;
+
+/// The label of the messages appended to the merlin transcript by [`MerlinSponge::absorb`].
+const ABSORB_LABEL: &[u8] = b"ark-sponge absorb";
+
+/// The label of the challenges squeezed from the merlin transcript by
+/// [`MerlinSponge::squeeze_bytes`].
+const SQUEEZE_LABEL: &[u8] = b"ark-sponge squeeze";
+
+/// A sponge over a [`merlin::Transcript`], for protocols that share their transcript with
+/// implementations built on merlin.
+///
+/// Absorbing appends the byte encoding of the input as a message with the label
+/// `"ark-sponge absorb"`, and squeezing reads a challenge with the label `"ark-sponge squeeze"`.
+/// The parameters of the sponge are the label of the merlin transcript.
+#[derive(Clone)]
+pub struct MerlinSponge {
+    transcript: merlin::Transcript,
+}
+
+impl MerlinSponge {
+    /// Wraps an existing merlin transcript, which may already contain messages.
+    pub fn from_transcript(transcript: merlin::Transcript) -> Self {
+        Self { transcript }
+    }
+
+    /// Returns the underlying merlin transcript.
+    pub fn into_transcript(self) -> merlin::Transcript {
+        self.transcript
+    }
+}
+
+impl CryptographicSponge for MerlinSponge {
+    type Parameters = &'static [u8];
+
+    fn new(label: &Self::Parameters) -> Self {
+        Self::from_transcript(merlin::Transcript::new(label))
+    }
+
+    /// Merlin is built on STROBE-128, which absorbs 166 bytes per permutation of Keccak-f\[1600\].
+    fn rate(&self) -> usize {
+        166
+    }
+
+    fn capacity(&self) -> usize {
+        32
+    }
+
+    fn security_level(&self) -> usize {
+        128
+    }
+
+    fn absorb(&mut self, input: &impl Absorb) {
+        self.transcript
+            .append_message(ABSORB_LABEL, &input.to_sponge_bytes_as_vec());
+    }
+
+    fn squeeze_bytes(&mut self, num_bytes: usize) -> Vec<u8> {
+        let mut output = vec![0u8; num_bytes];
+        self.transcript.challenge_bytes(SQUEEZE_LABEL, &mut output);
+        output
+    }
+
+    fn squeeze_bits(&mut self, num_bits: usize) -> Vec<bool> {
+        let bytes = self.squeeze_bytes(num_bits.div_ceil(8));
+        let mut bits: Vec<bool> = bytes
+            .iter()
+            .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1))
+            .collect();
+        bits.truncate(num_bits);
+        bits
+    }
+
+    fn squeeze_field_elements_with_sizes<F: PrimeField>(
+        &mut self,
+        sizes: &[FieldElementSize],
+    ) -> Vec<F> {
+        squeeze_field_elements_with_sizes_from_bytes(self, sizes)
+    }
+}
+
+/// A transcript over any sponge with the API of [`merlin::Transcript`], so that code written
+/// against merlin can run over the sponges of this crate.
+///
+/// This is a thin wrapper around [`Transcript`], and is not compatible with the outputs of
+/// merlin itself; use [`MerlinSponge`] for that.
+#[derive(Clone)]
+pub struct SpongeTranscript<S: CryptographicSponge> {
+    transcript: Transcript<S>,
+}
+
+impl<S: CryptographicSponge> SpongeTranscript<S> {
+    /// Starts a transcript for the protocol identified by `label`, as `merlin::Transcript::new`.
+    pub fn new(params: &S::Parameters, label: &'static [u8]) -> Self {
+        Self {
+            transcript: Transcript::new(params, label),
+        }
+    }
+
+    /// Appends `message` with the label `label`, as `merlin::Transcript::append_message`.
+    pub fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        self.transcript.append(label, &message);
+    }
+
+    /// Appends `x` with the label `label`, as `merlin::Transcript::append_u64`.
+    pub fn append_u64(&mut self, label: &'static [u8], x: u64) {
+        self.transcript.append(label, &x);
+    }
+
+    /// Fills `dest` with a challenge with the label `label`, as
+    /// `merlin::Transcript::challenge_bytes`.
+    pub fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]) {
+        dest.copy_from_slice(&self.transcript.challenge_bytes(label, dest.len()));
+    }
+
+    /// Returns the underlying transcript.
+    pub fn into_transcript(self) -> Transcript<S> {
+        self.transcript
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::merlin_compat::{MerlinSponge, SpongeTranscript};
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::PoseidonSponge;
+    use crate::CryptographicSponge;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn test_merlin_sponge() {
+        let mut sponge = MerlinSponge::new(&b"test".as_ref());
+        sponge.absorb(&[1u8, 2, 3].as_ref());
+        let squeezed = sponge.squeeze_bytes(32);
+
+        let mut transcript = merlin::Transcript::new(b"test");
+        transcript.append_message(b"ark-sponge absorb", &[1, 2, 3]);
+        let mut expected = [0u8; 32];
+        transcript.challenge_bytes(b"ark-sponge squeeze", &mut expected);
+        assert_eq!(squeezed, expected.to_vec());
+
+        let elements: Vec<Fr> = sponge.squeeze_field_elements(2);
+        assert_ne!(elements[0], elements[1]);
+    }
+
+    #[test]
+    fn test_sponge_transcript() {
+        let params = poseidon_parameters_for_test();
+        let mut t1 = SpongeTranscript::<PoseidonSponge<Fr>>::new(&params, b"test");
+        let mut t2 = SpongeTranscript::<PoseidonSponge<Fr>>::new(&params, b"test");
+        t1.append_message(b"message", b"hello");
+        t2.append_message(b"message", b"hello");
+        t1.append_u64(b"count", 1);
+        t2.append_u64(b"count", 2);
+
+        let (mut c1, mut c2) = ([0u8; 16], [0u8; 16]);
+        t1.challenge_bytes(b"challenge", &mut c1);
+        t2.challenge_bytes(b"challenge", &mut c2);
+        assert_ne!(c1, c2);
+    }
+}
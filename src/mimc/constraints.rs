@@ -0,0 +1,95 @@
+use crate::duplex::constraints::{DuplexSpongeVar, FieldPermutationGadget};
+use crate::mimc::MimcParameters;
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::SynthesisError;
+
+/// The gadget for [`MimcSponge`](crate::mimc::MimcSponge).
+pub type MimcSpongeVar<F> = DuplexSpongeVar<F, MimcParameters<F>>;
+
+impl<F: PrimeField> FieldPermutationGadget<F> for MimcParameters<F> {
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    fn permute_var(&self, state: &mut [FpVar<F>]) -> Result<(), SynthesisError> {
+        let (mut x_l, mut x_r) = (state[1].clone(), state[0].clone());
+        let last_round = self.rounds() - 1;
+        for (i, constant) in self.round_constants.iter().enumerate() {
+            let t = &x_l + (self.key + constant);
+            let new_x_l = x_r + t.pow_by_constant([self.alpha])?;
+            if i < last_round {
+                x_r = x_l;
+                x_l = new_x_l;
+            } else {
+                x_r = new_x_l;
+            }
+        }
+        state[1] = x_l;
+        state[0] = x_r;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constraints::CryptographicSpongeVar;
+    use crate::duplex::constraints::FieldPermutationGadget;
+    use crate::duplex::FieldPermutation;
+    use crate::mimc::constraints::MimcSpongeVar;
+    use crate::mimc::{MimcParameters, MimcSponge};
+    use crate::{CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_ff::UniformRand;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_relations::*;
+    use ark_std::test_rng;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn permutation_test() {
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::new_ref();
+        let parameters = MimcParameters::<Fr>::circom(Fr::rand(&mut rng));
+
+        let mut state: Vec<_> = (0..2).map(|_| Fr::rand(&mut rng)).collect();
+        let mut state_var: Vec<_> = state
+            .iter()
+            .map(|v| FpVar::new_witness(ns!(cs, "state"), || Ok(*v)).unwrap())
+            .collect();
+
+        parameters.permute(&mut state);
+        parameters.permute_var(&mut state_var).unwrap();
+
+        assert_eq!(state_var.value().unwrap(), state);
+        assert!(cs.is_satisfied().unwrap());
+        // x^5 costs 3 constraints
+        assert_eq!(cs.num_constraints(), 3 * parameters.rounds());
+    }
+
+    #[test]
+    fn sponge_test() {
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::new_ref();
+        let parameters = MimcParameters::<Fr>::circom(Fr::rand(&mut rng));
+
+        let absorb: Vec<_> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        let absorb_var: Vec<_> = absorb
+            .iter()
+            .map(|v| FpVar::new_input(ns!(cs, "absorb"), || Ok(*v)).unwrap())
+            .collect();
+
+        let mut native_sponge = MimcSponge::<Fr>::new(&parameters);
+        let mut constraint_sponge = MimcSpongeVar::<Fr>::new(cs.clone(), &parameters);
+
+        native_sponge.absorb(&absorb);
+        constraint_sponge.absorb(&absorb_var).unwrap();
+
+        let squeeze1 = native_sponge.squeeze_native_field_elements(2);
+        let squeeze2 = constraint_sponge.squeeze_field_elements(2).unwrap();
+
+        assert_eq!(squeeze2.value().unwrap(), squeeze1);
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
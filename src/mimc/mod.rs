@@ -0,0 +1,213 @@
+use crate::duplex::{DuplexSponge, FieldPermutation};
+use ark_ff::PrimeField;
+use ark_std::vec::Vec;
+use sha3::{Digest, Keccak256};
+
+/// constraints for MiMC
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+/// The seed from which circomlib derives the round constants of `MiMCSponge`.
+pub const CIRCOM_MIMC_SPONGE_SEED: &[u8] = b"mimcsponge";
+
+/// The number of rounds of circomlib's `MiMCSponge`.
+pub const CIRCOM_MIMC_SPONGE_ROUNDS: usize = 220;
+
+/// A duplex sponge based on the [MiMC][mimc]-Feistel permutation, with a rate and capacity of
+/// one element each.
+///
+/// With [`MimcParameters::circom`], absorbing elements one by one and squeezing outputs gives the
+/// same results as `MiMCSponge.multiHash` of circomlib (with the key of the parameters), which is
+/// also the MiMC sponge deployed on Ethereum.
+///
+/// [mimc]: https://eprint.iacr.org/2016/492
+pub type MimcSponge<F> = DuplexSponge<F, MimcParameters<F>>;
+
+/// Parameters of the [MiMC][mimc]-Feistel permutation.
+///
+/// The state consists of a left half `xL`, which is the rate, and a right half `xR`, which is
+/// the capacity. Round `i` computes `t = xL + key + round_constants[i]` and maps `(xL, xR)` to
+/// `(xR + t^alpha, xL)`, except for the last round, which does not swap the halves.
+///
+/// [mimc]: https://eprint.iacr.org/2016/492
+#[derive(Clone, Debug)]
+pub struct MimcParameters<F: PrimeField> {
+    /// Exponent used in the round function.
+    pub alpha: u64,
+    /// Round constants, one per round. There is at least one round.
+    round_constants: Vec<F>,
+    /// The key added in every round.
+    pub key: F,
+}
+
+impl<F: PrimeField> MimcParameters<F> {
+    /// Initialize the parameters of the MiMC-Feistel permutation.
+    pub fn new(alpha: u64, round_constants: Vec<F>, key: F) -> Self {
+        assert!(alpha > 1, "alpha must be greater than 1");
+        assert!(
+            !round_constants.is_empty(),
+            "there must be at least one round"
+        );
+
+        Self {
+            alpha,
+            round_constants,
+            key,
+        }
+    }
+
+    /// Derive `rounds` round constants from `seed` as circomlib does: the constants of the first
+    /// and last rounds are zero, and the constant of round `i` is the `(i + 1)`-th iterated
+    /// Keccak-256 hash of `seed`, interpreted as a big-endian integer and reduced modulo the field size.
+    pub fn from_seed(seed: &[u8], rounds: usize, alpha: u64, key: F) -> Self {
+        let mut round_constants = Vec::with_capacity(rounds);
+        let mut digest = Keccak256::digest(seed);
+        round_constants.push(F::zero());
+        for _ in 1..rounds {
+            digest = Keccak256::digest(&digest);
+            round_constants.push(F::from_be_bytes_mod_order(&digest));
+        }
+        if let Some(last) = round_constants.last_mut() {
+            *last = F::zero();
+        }
+
+        Self::new(alpha, round_constants, key)
+    }
+
+    /// The parameters of circomlib's `MiMCSponge` with the given key: 220 rounds of `x^5`,
+    /// with round constants derived from [`CIRCOM_MIMC_SPONGE_SEED`].
+    ///
+    /// These are only compatible with circomlib for the BN254 scalar field.
+    pub fn circom(key: F) -> Self {
+        Self::from_seed(CIRCOM_MIMC_SPONGE_SEED, CIRCOM_MIMC_SPONGE_ROUNDS, 5, key)
+    }
+
+    /// The number of rounds.
+    pub fn rounds(&self) -> usize {
+        self.round_constants.len()
+    }
+
+    /// Round constants, one per round.
+    pub fn round_constants(&self) -> &[F] {
+        &self.round_constants
+    }
+}
+
+impl<F: PrimeField> FieldPermutation<F> for MimcParameters<F> {
+    fn rate(&self) -> usize {
+        1
+    }
+
+    fn capacity(&self) -> usize {
+        1
+    }
+
+    fn permute(&self, state: &mut [F]) {
+        let (mut x_l, mut x_r) = (state[1], state[0]);
+        let last_round = self.rounds() - 1;
+        for (i, constant) in self.round_constants.iter().enumerate() {
+            let t = x_l + self.key + constant;
+            let new_x_l = x_r + t.pow([self.alpha]);
+            if i < last_round {
+                x_r = x_l;
+                x_l = new_x_l;
+            } else {
+                x_r = new_x_l;
+            }
+        }
+        state[1] = x_l;
+        state[0] = x_r;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::duplex::FieldPermutation;
+    use crate::mimc::{MimcParameters, MimcSponge, CIRCOM_MIMC_SPONGE_ROUNDS};
+    use crate::{CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_ff::{UniformRand, Zero};
+    use ark_std::test_rng;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn test_round_constants() {
+        let parameters = MimcParameters::<Fr>::circom(Fr::zero());
+        assert_eq!(parameters.rounds(), CIRCOM_MIMC_SPONGE_ROUNDS);
+        assert!(parameters.round_constants[0].is_zero());
+        assert!(parameters.round_constants[CIRCOM_MIMC_SPONGE_ROUNDS - 1].is_zero());
+        assert!(parameters.round_constants[1..CIRCOM_MIMC_SPONGE_ROUNDS - 1]
+            .iter()
+            .all(|c| !c.is_zero()));
+
+        let other = MimcParameters::<Fr>::from_seed(b"other", 10, 5, Fr::zero());
+        assert_ne!(other.round_constants[1], parameters.round_constants[1]);
+    }
+
+    /// Checks the sponge against a direct transcription of circomlib's `multiHash`.
+    #[test]
+    fn test_multi_hash() {
+        let mut rng = test_rng();
+        let key = Fr::rand(&mut rng);
+        let parameters = MimcParameters::<Fr>::circom(key);
+        let inputs: Vec<_> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+
+        let hash = |x_l: Fr, x_r: Fr| {
+            let mut state = [x_r, x_l];
+            parameters.permute(&mut state);
+            (state[1], state[0])
+        };
+        let (mut r, mut c) = (Fr::zero(), Fr::zero());
+        for input in &inputs {
+            let (new_r, new_c) = hash(r + input, c);
+            r = new_r;
+            c = new_c;
+        }
+        let mut expected = ark_std::vec![r];
+        for _ in 1..3 {
+            let (new_r, new_c) = hash(r, c);
+            r = new_r;
+            c = new_c;
+            expected.push(r);
+        }
+
+        let mut sponge = MimcSponge::new(&parameters);
+        for input in &inputs {
+            sponge.absorb(input);
+        }
+        assert_eq!(sponge.squeeze_native_field_elements(3), expected);
+
+        let mut sponge = MimcSponge::new(&parameters);
+        sponge.absorb(&inputs);
+        assert_eq!(sponge.squeeze_native_field_elements(3), expected);
+    }
+
+    /// The test vector of circomlibjs: `multiHash([1, 2])`, with the key zero, over BN254.
+    #[cfg(feature = "bn254")]
+    #[test]
+    fn test_circom_vector() {
+        use ark_bn254::Fr;
+        use ark_ff::field_new;
+
+        let parameters = MimcParameters::<Fr>::circom(Fr::zero());
+        assert_eq!(
+            parameters.round_constants[1],
+            field_new!(
+                Fr,
+                "7120861356467848435263064379192047478074060781135320967663101236819528304084"
+            )
+        );
+
+        let mut sponge = MimcSponge::new(&parameters);
+        sponge.absorb(&Fr::from(1u8));
+        sponge.absorb(&Fr::from(2u8));
+        // 0x2bcea035a1251603f1ceaf73cd4ae89427c47075bb8e3a944039ff1e3d6d2a6f
+        assert_eq!(
+            sponge.squeeze_native_field_elements(1),
+            ark_std::vec![field_new!(
+                Fr,
+                "19814528709687996974327303300007262407299502847885145507292406548098437687919"
+            )]
+        );
+    }
+}
@@ -0,0 +1,304 @@
+//! The derivation of bytes, bits, and truncated field elements from the squeezed native elements
+//! of a field-based sponge, shared by the native sponges and their gadgets so that both always
+//! read the same bits in the same order.
+
+use crate::Endianness;
+use ark_ff::{BigInteger, FpParameters, PrimeField};
+use ark_std::vec::Vec;
+#[cfg(feature = "r1cs")]
+use {
+    crate::constraints::to_canonical_bits_le,
+    ark_r1cs_std::{bits::boolean::Boolean, bits::uint8::UInt8, fields::fp::FpVar},
+    ark_relations::r1cs::SynthesisError,
+};
+
+/// The number of bytes that the field-based sponges read from each squeezed element of `F` in
+/// [`CryptographicSponge::squeeze_bytes`](crate::CryptographicSponge::squeeze_bytes): the
+/// `CAPACITY / 8` low bytes of its little-endian representation, i.e., 31 bytes for a 255-bit
+/// field.
+///
+/// The bytes of a uniform element are within statistical distance `2^(8 * bytes) / p` of
+/// uniform, which is not negligible for fields whose modulus is far from a power of two, e.g.
+/// about `2^-6.8` for the scalar field of BLS12-381. Protocols that need full-entropy bytes
+/// should squeeze field elements in [`SqueezeMode::Uniform`](crate::SqueezeMode::Uniform) and
+/// serialize them, or use a byte-oriented sponge.
+pub fn squeeze_bytes_per_element<F: PrimeField>() -> usize {
+    (F::Params::CAPACITY / 8) as usize
+}
+
+/// The number of elements of `F` that the field-based sponges squeeze for `num_bytes` bytes in
+/// [`CryptographicSponge::squeeze_bytes`](crate::CryptographicSponge::squeeze_bytes), which cost
+/// `num_elements / rate` permutations, rounded up, from the start of a squeeze.
+pub fn squeeze_bytes_num_elements<F: PrimeField>(num_bytes: usize) -> usize {
+    num_bytes.div_ceil(squeeze_bytes_per_element::<F>())
+}
+
+/// The number of bits that the field-based sponges read from each squeezed element of `F` in
+/// [`CryptographicSponge::squeeze_bits`](crate::CryptographicSponge::squeeze_bits): the
+/// `CAPACITY` low bits of its little-endian representation.
+pub fn squeeze_bits_per_element<F: PrimeField>() -> usize {
+    F::Params::CAPACITY as usize
+}
+
+/// The number of elements of `F` that the field-based sponges squeeze for `num_bits` bits in
+/// [`CryptographicSponge::squeeze_bits`](crate::CryptographicSponge::squeeze_bits).
+pub fn squeeze_bits_num_elements<F: PrimeField>(num_bits: usize) -> usize {
+    num_bits.div_ceil(squeeze_bits_per_element::<F>())
+}
+
+/// The first `num_bytes` bytes of `elements`, with the rule of [`squeeze_bytes_per_element`].
+pub(crate) fn bytes_from_squeezed_elements<F: PrimeField>(
+    elements: &[F],
+    num_bytes: usize,
+) -> Vec<u8> {
+    let usable_bytes = squeeze_bytes_per_element::<F>();
+    let mut bytes = Vec::with_capacity(usable_bytes * elements.len());
+    for elem in elements {
+        bytes.extend_from_slice(&elem.into_repr().to_bytes_le()[..usable_bytes]);
+    }
+    bytes.truncate(num_bytes);
+    bytes
+}
+
+/// The first `num_bits` bits of `elements`, with the rule of [`squeeze_bits_per_element`].
+pub(crate) fn bits_from_squeezed_elements<F: PrimeField>(
+    elements: &[F],
+    num_bits: usize,
+) -> Vec<bool> {
+    let usable_bits = squeeze_bits_per_element::<F>();
+    let mut bits = Vec::with_capacity(usable_bits * elements.len());
+    for elem in elements {
+        bits.extend_from_slice(&elem.into_repr().to_bits_le()[..usable_bits]);
+    }
+    bits.truncate(num_bits);
+    bits
+}
+
+/// The gadget for [`bytes_from_squeezed_elements`].
+#[cfg(feature = "r1cs")]
+pub(crate) fn bytes_from_squeezed_element_vars<F: PrimeField>(
+    elements: &[FpVar<F>],
+    num_bytes: usize,
+) -> Result<Vec<UInt8<F>>, SynthesisError> {
+    let usable_bytes = squeeze_bytes_per_element::<F>();
+    let mut bytes = Vec::with_capacity(usable_bytes * elements.len());
+    for elem in elements {
+        let bits = to_canonical_bits_le(elem)?;
+        bytes.extend(bits[..8 * usable_bytes].chunks(8).map(UInt8::from_bits_le));
+    }
+    bytes.truncate(num_bytes);
+    Ok(bytes)
+}
+
+/// The gadget for [`bits_from_squeezed_elements`].
+#[cfg(feature = "r1cs")]
+pub(crate) fn bits_from_squeezed_element_vars<F: PrimeField>(
+    elements: &[FpVar<F>],
+    num_bits: usize,
+) -> Result<Vec<Boolean<F>>, SynthesisError> {
+    let usable_bits = squeeze_bits_per_element::<F>();
+    let mut bits = Vec::with_capacity(usable_bits * elements.len());
+    for elem in elements {
+        bits.extend_from_slice(&to_canonical_bits_le(elem)?[..usable_bits]);
+    }
+    bits.truncate(num_bits);
+    Ok(bits)
+}
+
+/// The number of bits or bytes to squeeze for `num_units` of them in `endianness`, with
+/// `units_per_element` of them read from each squeezed element: in big-endian order, the units
+/// of the last element are all squeezed, so that its most significant ones can be output first.
+pub(crate) fn squeezed_units_for_endianness(
+    num_units: usize,
+    units_per_element: usize,
+    endianness: Endianness,
+) -> usize {
+    match endianness {
+        Endianness::Little => num_units,
+        Endianness::Big => num_units.div_ceil(units_per_element) * units_per_element,
+    }
+}
+
+/// Orders squeezed bits or bytes, natively or in a circuit, for `endianness`, and keeps the
+/// first `num_units` of them. Big-endian order reverses the `units_per_element` units read from
+/// each squeezed element, which must all be in `units`.
+pub(crate) fn order_squeezed_units<T>(
+    mut units: Vec<T>,
+    units_per_element: usize,
+    num_units: usize,
+    endianness: Endianness,
+) -> Vec<T> {
+    if endianness == Endianness::Big {
+        units
+            .chunks_mut(units_per_element)
+            .for_each(|element_units| element_units.reverse());
+    }
+    units.truncate(num_units);
+    units
+}
+
+/// The element of `F` whose bits are `bits` in `endianness`, reduced modulo the field.
+pub(crate) fn field_element_from_bits<F: PrimeField>(bits: &[bool], endianness: Endianness) -> F {
+    let mut bits = bits.to_vec();
+    if endianness == Endianness::Big {
+        bits.reverse();
+    }
+    let bytes = bits
+        .chunks(8)
+        .map(|bits| {
+            bits.iter()
+                .enumerate()
+                .fold(0u8, |byte, (i, &bit)| byte | ((bit as u8) << i))
+        })
+        .collect::<Vec<_>>();
+    F::from_le_bytes_mod_order(&bytes)
+}
+
+/// Splits squeezed bits, natively or in a circuit, into the consecutive little-endian bits of
+/// elements of the given sizes, in order. `bits` must have at least the sum of `sizes` bits.
+pub(crate) fn split_squeezed_bits<T: Clone>(bits: &[T], sizes: &[usize]) -> Vec<Vec<T>> {
+    let mut bits_window = bits;
+    sizes
+        .iter()
+        .map(|&num_bits| {
+            let (element_bits, rest) = bits_window.split_at(num_bits);
+            bits_window = rest;
+            element_bits.to_vec()
+        })
+        .collect()
+}
+
+#[cfg(all(test, feature = "r1cs"))]
+mod tests {
+    use crate::constraints::{CryptographicSpongeVar, SpongeWithGadget};
+    use crate::domain_separated::{DomainSeparatedSponge, DomainSeparator};
+    use crate::duplex::DuplexSponge;
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::{PoseidonPermutation, PoseidonSponge};
+    use crate::{Endianness, FieldBasedCryptographicSponge, FieldElementSize};
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::R1CSVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::{Fq, Fr};
+
+    struct TestDomain;
+
+    impl DomainSeparator for TestDomain {
+        fn domain() -> Vec<u8> {
+            b"output-test".to_vec()
+        }
+    }
+
+    type VarParameters<S> =
+        <<S as SpongeWithGadget<Fr>>::Var as CryptographicSpongeVar<Fr, S>>::Parameters;
+
+    /// Checks that the gadget of `S` derives the same bytes, bits, and field elements as `S`.
+    fn check_native_and_gadget_outputs<
+        S: SpongeWithGadget<Fr> + FieldBasedCryptographicSponge<Fr>,
+    >(
+        params: &S::Parameters,
+        var_params: &VarParameters<S>,
+    ) {
+        let input: Vec<Fr> = (1..=5u8).map(Fr::from).collect();
+        let sponges = || {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let mut native = S::new(params);
+            native.absorb(&input);
+            let mut var = S::Var::new(cs.clone(), var_params);
+            let input_var: Vec<_> = input
+                .iter()
+                .map(|x| FpVar::new_witness(cs.clone(), || Ok(*x)).unwrap())
+                .collect();
+            var.absorb(&input_var).unwrap();
+            (native, var)
+        };
+
+        for &num_bytes in &[0, 1, 31, 32, 100] {
+            let (mut native, mut var) = sponges();
+            let bytes = var.squeeze_bytes(num_bytes).unwrap();
+            assert_eq!(bytes.value().unwrap(), native.squeeze_bytes(num_bytes));
+            assert!(var.cs().is_satisfied().unwrap());
+        }
+
+        for &num_bits in &[0, 1, 254, 255, 600] {
+            let (mut native, mut var) = sponges();
+            let bits = var.squeeze_bits(num_bits).unwrap();
+            assert_eq!(bits.value().unwrap(), native.squeeze_bits(num_bits));
+            assert!(var.cs().is_satisfied().unwrap());
+        }
+
+        for &endianness in &[Endianness::Little, Endianness::Big] {
+            let (mut native, mut var) = sponges();
+            let bytes = var.squeeze_bytes_with_endianness(40, endianness).unwrap();
+            assert_eq!(
+                bytes.value().unwrap(),
+                native.squeeze_bytes_with_endianness(40, endianness)
+            );
+            let bits = var.squeeze_bits_with_endianness(300, endianness).unwrap();
+            assert_eq!(
+                bits.value().unwrap(),
+                native.squeeze_bits_with_endianness(300, endianness)
+            );
+            let sizes = [FieldElementSize::Truncated(100), FieldElementSize::Full];
+            let (elements, _) = var
+                .squeeze_nonnative_field_elements_with_sizes_and_endianness::<Fq>(
+                    &sizes, endianness,
+                )
+                .unwrap();
+            assert_eq!(
+                elements.value().unwrap(),
+                native.squeeze_nonnative_field_elements_with_sizes_and_endianness::<Fq>(
+                    &sizes, endianness
+                )
+            );
+            assert!(var.cs().is_satisfied().unwrap());
+        }
+
+        let (mut native, mut var) = sponges();
+        let elements = var.squeeze_field_elements(3).unwrap();
+        assert_eq!(
+            elements.value().unwrap(),
+            native.squeeze_field_elements::<Fr>(3)
+        );
+
+        let sizes = [
+            FieldElementSize::Full,
+            FieldElementSize::Truncated(128),
+            FieldElementSize::Full,
+        ];
+        let (mut native, mut var) = sponges();
+        let (elements, _) = var
+            .squeeze_nonnative_field_elements_with_sizes::<Fq>(&sizes)
+            .unwrap();
+        assert_eq!(
+            elements.value().unwrap(),
+            native.squeeze_field_elements_with_sizes::<Fq>(&sizes)
+        );
+        assert!(var.cs().is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn poseidon_outputs() {
+        let params = poseidon_parameters_for_test::<Fr>();
+        check_native_and_gadget_outputs::<PoseidonSponge<Fr>>(&params, &params);
+    }
+
+    #[test]
+    fn duplex_outputs() {
+        let permutation = PoseidonPermutation::new(&poseidon_parameters_for_test::<Fr>());
+        check_native_and_gadget_outputs::<DuplexSponge<Fr, PoseidonPermutation<Fr>>>(
+            &permutation,
+            &permutation,
+        );
+    }
+
+    #[test]
+    fn domain_separated_outputs() {
+        let params = poseidon_parameters_for_test::<Fr>();
+        check_native_and_gadget_outputs::<DomainSeparatedSponge<Fr, PoseidonSponge<Fr>, TestDomain>>(
+            &params, &params,
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use crate::poseidon::traits::find_poseidon_ark_and_mds_with_state_size;
+use crate::poseidon::PoseidonParameters;
+use crate::SpongeError;
+use ark_ff::{FpParameters, PrimeField};
+use ark_std::vec::Vec;
+
+/// The number of partial rounds of the Poseidon instances of circomlib, for 1 to 16 inputs.
+pub const CIRCOM_PARTIAL_ROUNDS: [usize; 16] = [
+    56, 57, 56, 60, 60, 63, 64, 63, 60, 66, 60, 65, 70, 60, 64, 68,
+];
+
+/// The number of full rounds of the Poseidon instances of circomlib.
+pub const CIRCOM_FULL_ROUNDS: usize = 8;
+
+impl<F: PrimeField> PoseidonParameters<F> {
+    /// Returns the parameters of the Poseidon instance of
+    /// [circomlib](https://github.com/iden3/circomlib) for `num_inputs` inputs, which has a state
+    /// of `num_inputs + 1` elements, the S-box `x^5`, and round constants and MDS matrix derived
+    /// with the Grain LFSR as in the reference implementation.
+    ///
+    /// Over the scalar field of BN254, [`circom_poseidon_hash`] with these parameters gives the
+    /// same hashes as circomlib and its JavaScript and Solidity implementations, and
+    /// [`evm_poseidon_hash`](crate::poseidon::evm_poseidon_hash) hashes their EVM words.
+    ///
+    /// Returns [`SpongeError::InvalidParameters`] if `num_inputs` is not between 1 and 16.
+    pub fn circom(num_inputs: usize) -> Result<Self, SpongeError> {
+        if num_inputs == 0 || num_inputs > CIRCOM_PARTIAL_ROUNDS.len() {
+            return Err(SpongeError::InvalidParameters(
+                "circomlib supports between 1 and 16 inputs",
+            ));
+        }
+        let partial_rounds = CIRCOM_PARTIAL_ROUNDS[num_inputs - 1];
+        let (ark, mds) = find_poseidon_ark_and_mds_with_state_size::<F>(
+            F::Params::MODULUS_BITS as u64,
+            num_inputs + 1,
+            CIRCOM_FULL_ROUNDS as u64,
+            partial_rounds as u64,
+            0,
+        );
+        Ok(Self::new(
+            CIRCOM_FULL_ROUNDS,
+            partial_rounds,
+            5,
+            mds,
+            ark,
+            num_inputs,
+            1,
+        ))
+    }
+}
+
+/// Hashes `input` as the Poseidon template of circomlib, with parameters given by
+/// [`PoseidonParameters::circom`] for `input.len()` inputs: the state is a zero followed by the
+/// inputs, and the hash is the first element of the permuted state.
+///
+/// This is not a sponge: the input has a fixed length, and is neither padded nor absorbed in
+/// the rate of the parameters, so the hash differs from [`poseidon_hash`](crate::poseidon::poseidon_hash).
+///
+/// # Panics
+///
+/// Panics if `parameters` do not have a capacity of 1 and a rate of `input.len()`.
+pub fn circom_poseidon_hash<F: PrimeField>(parameters: &PoseidonParameters<F>, input: &[F]) -> F {
+    assert!(
+        parameters.capacity == 1 && parameters.rate == input.len(),
+        "the parameters must be those of circomlib for the number of inputs"
+    );
+    let mut state = Vec::with_capacity(parameters.state_size());
+    state.push(F::zero());
+    state.extend_from_slice(input);
+    parameters.permute(&mut state);
+    state[0]
+}
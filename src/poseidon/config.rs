@@ -0,0 +1,127 @@
+//! The serde representation of [`PoseidonParameters`], for parameter sets loaded from
+//! configuration files.
+
+use crate::decimal::{field_to_string, string_to_field};
+use crate::poseidon::{PoseidonDomainTag, PoseidonParameters};
+use crate::SpongeError;
+use ark_ff::{BigInteger, FpParameters, PrimeField};
+use ark_std::string::{String, ToString};
+use ark_std::vec::Vec;
+use num_bigint::BigUint;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The fields of [`PoseidonParameters`], with field elements written as decimal strings.
+///
+/// The modulus of the field is written too, so that parameters are never read over another
+/// field. The optimized form of the permutation is not written, and is derived again when the
+/// parameters are read.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ParametersConfig {
+    modulus: String,
+    full_rounds: usize,
+    partial_rounds: usize,
+    alpha: i64,
+    rate: usize,
+    capacity: usize,
+    #[serde(default)]
+    domain_tag: DomainTagConfig,
+    ark: Vec<Vec<String>>,
+    mds: Vec<Vec<String>>,
+}
+
+/// A [`PoseidonDomainTag`], where a label is written as its field element.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum DomainTagConfig {
+    #[default]
+    None,
+    Arity,
+    Element(String),
+}
+
+fn modulus<F: PrimeField>() -> String {
+    BigUint::from_bytes_le(&F::Params::MODULUS.to_bytes_le()).to_string()
+}
+
+fn elements_to_strings<F: PrimeField>(rows: &[Vec<F>]) -> Vec<Vec<String>> {
+    rows.iter()
+        .map(|row| row.iter().map(field_to_string).collect())
+        .collect()
+}
+
+fn strings_to_elements<F: PrimeField>(rows: &[Vec<String>]) -> Result<Vec<Vec<F>>, SpongeError> {
+    rows.iter()
+        .map(|row| row.iter().map(|elem| string_to_field(elem)).collect())
+        .collect()
+}
+
+impl ParametersConfig {
+    fn new<F: PrimeField>(parameters: &PoseidonParameters<F>) -> Self {
+        let domain_tag = match &parameters.domain_tag {
+            PoseidonDomainTag::None => DomainTagConfig::None,
+            PoseidonDomainTag::Arity => DomainTagConfig::Arity,
+            tag => DomainTagConfig::Element(field_to_string(&tag.value(parameters.rate))),
+        };
+        Self {
+            modulus: modulus::<F>(),
+            full_rounds: parameters.full_rounds,
+            partial_rounds: parameters.partial_rounds,
+            alpha: parameters.alpha,
+            rate: parameters.rate,
+            capacity: parameters.capacity,
+            domain_tag,
+            ark: elements_to_strings(&parameters.ark),
+            mds: elements_to_strings(&parameters.mds),
+        }
+    }
+
+    fn into_parameters<F: PrimeField>(self) -> Result<PoseidonParameters<F>, SpongeError> {
+        if self.modulus != modulus::<F>() {
+            return Err(SpongeError::InvalidParameters(
+                "the parameters are for another field",
+            ));
+        }
+        let domain_tag = match self.domain_tag {
+            DomainTagConfig::None => PoseidonDomainTag::None,
+            DomainTagConfig::Arity => PoseidonDomainTag::Arity,
+            DomainTagConfig::Element(elem) => PoseidonDomainTag::Element(string_to_field(&elem)?),
+        };
+        if self.capacity == 0 && domain_tag != PoseidonDomainTag::None {
+            return Err(SpongeError::InvalidParameters(
+                "a domain tag requires a capacity of at least one element",
+            ));
+        }
+        let parameters = PoseidonParameters::new_checked(
+            self.full_rounds,
+            self.partial_rounds,
+            self.alpha,
+            strings_to_elements(&self.mds)?,
+            strings_to_elements(&self.ark)?,
+            self.rate,
+            self.capacity,
+        )?;
+        Ok(parameters.with_domain_tag(domain_tag))
+    }
+}
+
+/// Writes the parameters with their field elements as decimal strings, together with the modulus
+/// of the field. A [`PoseidonDomainTag::Label`] is written as its field element, and is read
+/// back as a [`PoseidonDomainTag::Element`] with the same value.
+impl<F: PrimeField> Serialize for PoseidonParameters<F> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ParametersConfig::new(self).serialize(serializer)
+    }
+}
+
+/// Reads parameters written by their [`Serialize`] implementation, such as a vetted parameter
+/// set of a configuration file, and checks them as [`PoseidonParameters::new_checked`]. Fails if
+/// the parameters are for another field.
+impl<'de, F: PrimeField> Deserialize<'de> for PoseidonParameters<F> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ParametersConfig::deserialize(deserializer)?
+            .into_parameters()
+            .map_err(D::Error::custom)
+    }
+}
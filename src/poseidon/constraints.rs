@@ -1,11 +1,25 @@
 use crate::constraints::AbsorbGadget;
-use crate::constraints::{CryptographicSpongeVar, SpongeWithGadget};
-use crate::poseidon::{PoseidonParameters, PoseidonSponge};
-use crate::DuplexSpongeMode;
-use ark_ff::{FpParameters, PrimeField};
+use crate::constraints::{
+    constant_state, select_state, CryptographicSpongeVar, SpongeExtVar, SpongeWithGadget,
+};
+use crate::duplex::constraints::FieldPermutationGadget;
+use crate::poseidon::optimized::PoseidonOptimizedParameters;
+use crate::poseidon::{
+    NeptuneHashType, PoseidonFamily, PoseidonParameters, PoseidonPermutation, PoseidonSponge,
+    PoseidonSpongeState, COMPRESSION_DOMAIN,
+};
+use crate::{
+    bits_from_squeezed_element_vars, bytes_from_squeezed_element_vars, field_sponge_security_level,
+    squeeze_bits_num_elements, squeeze_bytes_num_elements, AbsorbMode, DuplexSpongeMode,
+    PaddingMode,
+};
+use alloc::sync::Arc;
+use ark_ff::PrimeField;
+use ark_nonnative_field::NonNativeFieldVar;
 use ark_r1cs_std::fields::fp::FpVar;
 use ark_r1cs_std::prelude::*;
-use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+use ark_std::borrow::Borrow;
 use ark_std::vec;
 use ark_std::vec::Vec;
 
@@ -15,26 +29,452 @@ use ark_std::vec::Vec;
 /// This implementation of Poseidon is entirely from Fractal's implementation in [COS20][cos]
 /// with small syntax changes.
 ///
+/// The gadget is instantiated from runtime [`PoseidonParameters`] through
+/// [`PoseidonSpongeVar::new_with_parameters`] or [`CryptographicSpongeVar::new`], and must use
+/// the same parameters as the native sponge.
+///
+/// Like [`PoseidonSponge`], the gadget evaluates partial rounds with sparse matrices, which
+/// keeps the linear combinations of the state small.
+///
+/// The round constants and the MDS matrix are not allocated: they are folded into the linear
+/// combinations of the state as constants, so a permutation only costs the constraints of its
+/// S-boxes. A gadget created with [`PoseidonSpongeVar::with_parameters_var`] instead takes them
+/// from a [`PoseidonParametersVar`], so that a circuit can be used with parameters chosen when
+/// proving.
+///
+/// The gadget created by [`CryptographicSpongeVar::new`] does not pad its input; use
+/// [`PoseidonSpongeVar::with_padding`] with the [`PaddingMode`] of the native sponge, and
+/// [`PoseidonSpongeVar::with_absorb_mode`] with its [`AbsorbMode`].
+///
 /// [cos]: https://eprint.iacr.org/2019/1076
 pub struct PoseidonSpongeVar<F: PrimeField> {
     /// Constraint system
     pub cs: ConstraintSystemRef<F>,
 
-    /// Sponge Parameters
-    pub parameters: PoseidonParameters<F>,
+    /// Sponge Parameters, shared by the clones of the sponge. Modifying them with
+    /// [`Arc::make_mut`] copies them for this sponge only.
+    pub parameters: Arc<PoseidonParameters<F>>,
+    /// The round constants and the MDS matrix as variables, which replace those of `parameters`.
+    parameters_var: Option<Arc<PoseidonParametersVar<F>>>,
 
     // Sponge State
     /// The sponge's state
     pub state: Vec<FpVar<F>>,
     /// The mode
     pub mode: DuplexSpongeMode,
+    /// The padding applied when the sponge starts squeezing.
+    pub padding: PaddingMode,
+    /// Whether absorbed elements are added to the rate or overwrite it.
+    pub absorb_mode: AbsorbMode,
+    /// The number of elements absorbed since the last squeeze.
+    num_absorbed: usize,
+}
+
+/// Poseidon parameters whose round constants and MDS matrix are variables of the circuit, for a
+/// [`PoseidonSpongeVar`] created with [`PoseidonSpongeVar::with_parameters_var`].
+///
+/// The other parameters (the numbers of rounds, the S-box, the rate, the capacity and the domain
+/// tag) make up the shape of the circuit and remain constants. The parameters are therefore
+/// needed to allocate the variables even when generating the keys of a proof system, where
+/// any parameters of the same shape can be given, and the parameters used when proving are then
+/// bound by the variables, e.g. by allocating them as public inputs.
+///
+/// A permutation costs one more constraint per entry of the MDS matrix in every round, which
+/// multiplies two variables, on top of the constraints of its S-boxes.
+#[derive(Clone)]
+pub struct PoseidonParametersVar<F: PrimeField> {
+    /// The parameters, whose round constants and MDS matrix are those given when allocating.
+    pub parameters: PoseidonParameters<F>,
+    /// The round constants, indexed as [`PoseidonParameters::ark`].
+    pub ark: Vec<Vec<FpVar<F>>>,
+    /// The MDS matrix, indexed as [`PoseidonParameters::mds`].
+    pub mds: Vec<Vec<FpVar<F>>>,
+}
+
+impl<F: PrimeField> AllocVar<PoseidonParameters<F>, F> for PoseidonParametersVar<F> {
+    fn new_variable<T: Borrow<PoseidonParameters<F>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let cs = cs.into().cs();
+        let parameters = f()?.borrow().clone();
+        let new_matrix = |matrix: &[Vec<F>]| {
+            matrix
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|elem| FpVar::new_variable(cs.clone(), || Ok(*elem), mode))
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let ark = new_matrix(&parameters.ark)?;
+        let mds = new_matrix(&parameters.mds)?;
+        Ok(Self {
+            parameters,
+            ark,
+            mds,
+        })
+    }
 }
 
 impl<F: PrimeField> SpongeWithGadget<F> for PoseidonSponge<F> {
     type Var = PoseidonSpongeVar<F>;
 }
 
+impl<F: PrimeField> FieldPermutationGadget<F> for PoseidonPermutation<F> {
+    #[tracing::instrument(target = "r1cs", skip(self, state))]
+    fn permute_var(&self, state: &mut [FpVar<F>]) -> Result<(), SynthesisError> {
+        let gadget = PoseidonSpongeVar {
+            cs: ConstraintSystemRef::None,
+            parameters: self.sponge.parameters.clone(),
+            parameters_var: None,
+            state: Vec::new(),
+            mode: DuplexSpongeMode::Absorbing {
+                next_absorb_index: 0,
+            },
+            padding: PaddingMode::None,
+            absorb_mode: AbsorbMode::Add,
+            num_absorbed: 0,
+        };
+        let permuted = gadget.permuted(state.to_vec())?;
+        state.clone_from_slice(&permuted);
+        Ok(())
+    }
+}
+
+/// Hashes `input` with a [`PoseidonSpongeVar`], as
+/// [`poseidon_hash`](crate::poseidon::poseidon_hash).
+#[tracing::instrument(target = "r1cs", skip(cs, parameters, input))]
+pub fn poseidon_hash_var<F: PrimeField, A: AbsorbGadget<F>>(
+    cs: ConstraintSystemRef<F>,
+    parameters: &PoseidonParameters<F>,
+    input: &[A],
+) -> Result<FpVar<F>, SynthesisError> {
+    let mut sponge = PoseidonSpongeVar::new(cs, parameters);
+    sponge.absorb(&input)?;
+    Ok(sponge.squeeze_field_elements(1)?.remove(0))
+}
+
+/// Hashes `input` as the Poseidon template of circomlib, as
+/// [`circom_poseidon_hash`](crate::poseidon::circom_poseidon_hash).
+///
+/// Returns [`SynthesisError::Unsatisfiable`] if `parameters` do not have a capacity of 1 and a
+/// rate of `input.len()`.
+#[tracing::instrument(target = "r1cs", skip(parameters, input))]
+pub fn circom_poseidon_hash_var<F: PrimeField>(
+    parameters: &PoseidonParameters<F>,
+    input: &[FpVar<F>],
+) -> Result<FpVar<F>, SynthesisError> {
+    if parameters.capacity != 1 || parameters.rate != input.len() {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    let mut state = Vec::with_capacity(parameters.state_size());
+    state.push(FpVar::zero());
+    state.extend_from_slice(input);
+    PoseidonPermutation::new(parameters).permute_var(&mut state)?;
+    Ok(state.remove(0))
+}
+
+/// Hashes `input` as neptune, as [`neptune_poseidon_hash`](crate::poseidon::neptune_poseidon_hash).
+///
+/// Returns [`SynthesisError::Unsatisfiable`] if `parameters` do not have a capacity of 1, or if
+/// the length of `input` is not allowed by `parameters` and `hash_type`.
+#[tracing::instrument(target = "r1cs", skip(parameters, input))]
+pub fn neptune_poseidon_hash_var<F: PrimeField>(
+    parameters: &PoseidonParameters<F>,
+    hash_type: NeptuneHashType,
+    input: &[FpVar<F>],
+) -> Result<FpVar<F>, SynthesisError> {
+    let required_length = match hash_type {
+        NeptuneHashType::MerkleTree => Some(parameters.rate),
+        NeptuneHashType::ConstantLength(length) => Some(length),
+        _ => None,
+    };
+    if parameters.capacity != 1
+        || input.len() > parameters.rate
+        || required_length.is_some_and(|length| length != input.len())
+    {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    let mut state = Vec::with_capacity(parameters.state_size());
+    state.push(FpVar::constant(hash_type.domain_tag(parameters.rate)));
+    state.extend_from_slice(input);
+    state.resize(parameters.state_size(), FpVar::zero());
+    PoseidonPermutation::new(parameters).permute_var(&mut state)?;
+    Ok(state.remove(1))
+}
+
+/// Applies the Poseidon S-box with exponent `alpha` to `elem`: `x^alpha` for a positive `alpha`,
+/// and the inverse S-box, which maps `x` to `x^(-1)` and `0` to `0`, for an `alpha` of `-1`.
+///
+/// A variable costs `alpha.count_ones() + log2(alpha) - 1` constraints for a positive `alpha`,
+/// e.g. 3 for `alpha = 5` with the chain `x^2, x^4, x^5`, and 3 for the inverse S-box: one for
+/// the product of `x` and its witnessed inverse, and two which guard the case `x = 0`. A
+/// constant costs none.
+pub fn poseidon_s_box_var<F: PrimeField>(
+    elem: &FpVar<F>,
+    alpha: i64,
+) -> Result<FpVar<F>, SynthesisError> {
+    s_box_var(elem, alpha)
+}
+
+// The S-box of `poseidon_s_box_var` over any field gadget, e.g. nonnative field elements.
+fn s_box_var<F: PrimeField, CF: PrimeField, V: FieldVar<F, CF>>(
+    elem: &V,
+    alpha: i64,
+) -> Result<V, SynthesisError> {
+    match alpha {
+        -1 => inverse_s_box_var(elem),
+        5 => {
+            let square = elem.square()?;
+            Ok(square.square()? * elem)
+        }
+        _ => elem.pow_by_constant([alpha as u64]),
+    }
+}
+
+fn inverse_s_box_var<F: PrimeField, CF: PrimeField, V: FieldVar<F, CF>>(
+    elem: &V,
+) -> Result<V, SynthesisError> {
+    if elem.is_constant() {
+        return Ok(V::constant(elem.value()?.inverse().unwrap_or_else(F::zero)));
+    }
+    let inverse = V::new_witness(elem.cs(), || {
+        Ok(elem.value()?.inverse().unwrap_or_else(F::zero))
+    })?;
+    // `product` is 1 if `elem` is nonzero and 0 otherwise, which forces `inverse` to be the
+    // inverse of `elem` in the first case, and to be zero in the second.
+    let product = elem.clone() * &inverse;
+    product.mul_equals(elem, elem)?;
+    product.mul_equals(&inverse, &inverse)?;
+    Ok(inverse)
+}
+
 impl<F: PrimeField> PoseidonSpongeVar<F> {
+    /// Creates a gadget with the given parameters, as [`CryptographicSpongeVar::new`].
+    pub fn new_with_parameters(
+        cs: ConstraintSystemRef<F>,
+        parameters: &PoseidonParameters<F>,
+    ) -> Self {
+        <Self as CryptographicSpongeVar<F, PoseidonSponge<F>>>::new(cs, parameters)
+    }
+
+    /// Creates a gadget with the given parameters, which pads its input with `padding`.
+    #[tracing::instrument(target = "r1cs", skip(cs))]
+    pub fn with_padding(
+        cs: ConstraintSystemRef<F>,
+        parameters: &PoseidonParameters<F>,
+        padding: PaddingMode,
+    ) -> Self {
+        let state = parameters
+            .initial_state::<Vec<F>>()
+            .into_iter()
+            .map(FpVar::constant)
+            .collect();
+        let mode = DuplexSpongeMode::Absorbing {
+            next_absorb_index: 0,
+        };
+
+        Self {
+            cs,
+            parameters: parameters.to_shared(),
+            parameters_var: None,
+            state,
+            mode,
+            padding,
+            absorb_mode: AbsorbMode::Add,
+            num_absorbed: 0,
+        }
+    }
+
+    /// Creates a gadget which takes its round constants and MDS matrix from `parameters_var`, so
+    /// that the parameters of the permutation can be chosen when proving. It does not pad its
+    /// input, and it matches a [`PoseidonSponge`] created with `parameters_var.parameters`.
+    #[tracing::instrument(target = "r1cs", skip(cs, parameters_var))]
+    pub fn with_parameters_var(
+        cs: ConstraintSystemRef<F>,
+        parameters_var: &PoseidonParametersVar<F>,
+    ) -> Self {
+        let mut sponge = Self::with_padding(cs, &parameters_var.parameters, PaddingMode::None);
+        sponge.parameters_var = Some(Arc::new(parameters_var.clone()));
+        sponge
+    }
+
+    /// Returns the parameters of the sponge.
+    pub fn parameters(&self) -> &PoseidonParameters<F> {
+        &self.parameters
+    }
+
+    /// Creates a gadget whose capacity is initialized with `iv`, as
+    /// [`GenericPoseidonSponge::new_with_iv`](crate::poseidon::GenericPoseidonSponge::new_with_iv).
+    /// The IV may be a witness, e.g. a secret key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `iv` is longer than the capacity.
+    #[tracing::instrument(target = "r1cs", skip(cs, iv))]
+    pub fn new_with_iv(
+        cs: ConstraintSystemRef<F>,
+        parameters: &PoseidonParameters<F>,
+        iv: &[FpVar<F>],
+    ) -> Self {
+        assert!(
+            iv.len() <= parameters.capacity,
+            "the IV is longer than the capacity"
+        );
+        let mut sponge = Self::with_padding(cs, parameters, PaddingMode::None);
+        for (elem, iv_elem) in sponge.state.iter_mut().zip(iv) {
+            *elem += iv_elem;
+        }
+        sponge
+    }
+
+    /// Applies the permutation now, as
+    /// [`GenericPoseidonSponge::permute`](crate::poseidon::GenericPoseidonSponge::permute).
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    pub fn permute(&mut self) -> Result<(), SynthesisError> {
+        self.apply_permutation()?;
+        self.mode = match self.mode {
+            DuplexSpongeMode::Absorbing { .. } => DuplexSpongeMode::Absorbing {
+                next_absorb_index: 0,
+            },
+            DuplexSpongeMode::Squeezing { .. } => DuplexSpongeMode::Squeezing {
+                next_squeeze_index: 0,
+            },
+        };
+        Ok(())
+    }
+
+    /// Closes the absorb phase, as
+    /// [`GenericPoseidonSponge::finish_absorb`](crate::poseidon::GenericPoseidonSponge::finish_absorb).
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    pub fn finish_absorb(&mut self) -> Result<(), SynthesisError> {
+        if let DuplexSpongeMode::Absorbing { next_absorb_index } = self.mode {
+            self.pad(next_absorb_index)?;
+            self.apply_permutation()?;
+            self.mode = DuplexSpongeMode::Squeezing {
+                next_squeeze_index: 0,
+            };
+        }
+        Ok(())
+    }
+
+    /// Returns this gadget with the given absorb mode, as
+    /// [`GenericPoseidonSponge::with_absorb_mode`](crate::poseidon::GenericPoseidonSponge::with_absorb_mode).
+    pub fn with_absorb_mode(mut self, absorb_mode: AbsorbMode) -> Self {
+        self.absorb_mode = absorb_mode;
+        self
+    }
+
+    /// Permutes the state and zeroes its rate, so that the inputs absorbed before cannot be
+    /// recovered from the state afterwards. The sponge is then absorbing.
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    pub fn ratchet(&mut self) -> Result<(), SynthesisError> {
+        self.apply_permutation()?;
+        for elem in &mut self.state[self.parameters.capacity..] {
+            *elem = FpVar::zero();
+        }
+        self.mode = DuplexSpongeMode::Absorbing {
+            next_absorb_index: 0,
+        };
+        self.num_absorbed = 0;
+        Ok(())
+    }
+
+    /// Compresses exactly `rate` field elements into one with a single permutation, as
+    /// [`GenericPoseidonSponge::compress`](crate::poseidon::GenericPoseidonSponge::compress). The state of the gadget is not used or modified.
+    ///
+    /// Returns [`SynthesisError::Unsatisfiable`] if `input` does not have `rate` elements.
+    #[tracing::instrument(target = "r1cs", skip(self, input))]
+    pub fn compress(&self, input: &[FpVar<F>]) -> Result<FpVar<F>, SynthesisError> {
+        if input.len() != self.parameters.rate {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let mut state = vec![FpVar::zero(); self.parameters.state_size()];
+        state[0] = FpVar::Constant(F::from_le_bytes_mod_order(COMPRESSION_DOMAIN));
+        state[self.parameters.capacity..].clone_from_slice(input);
+        Ok(self.permuted(state)?.remove(self.parameters.capacity))
+    }
+
+    /// Returns the root of the binary Merkle tree in which `leaf` has the authentication path
+    /// `path`, as
+    /// [`GenericPoseidonSponge::hash_path`](crate::poseidon::GenericPoseidonSponge::hash_path).
+    /// The state of the gadget is not used or modified.
+    ///
+    /// Each level costs a permutation, and one constraint which orders the node and its sibling.
+    ///
+    /// Returns [`SynthesisError::Unsatisfiable`] if the rate is not 2.
+    #[tracing::instrument(target = "r1cs", skip(self, leaf, path))]
+    pub fn hash_path(
+        &self,
+        leaf: &FpVar<F>,
+        path: &[(Boolean<F>, FpVar<F>)],
+    ) -> Result<FpVar<F>, SynthesisError> {
+        let mut node = leaf.clone();
+        for (is_right, sibling) in path {
+            // `offset` moves the node to the right and the sibling to the left if `is_right`.
+            let offset = FpVar::from(is_right.clone()) * (sibling - &node);
+            node = self.compress(&[&node + &offset, sibling - &offset])?;
+        }
+        Ok(node)
+    }
+
+    /// Returns whether `leaf` has the authentication path `path` in the binary Merkle tree with
+    /// the root `root`, as hashed by [`PoseidonSpongeVar::hash_path`].
+    #[tracing::instrument(target = "r1cs", skip(self, leaf, path, root))]
+    pub fn verify_path(
+        &self,
+        leaf: &FpVar<F>,
+        path: &[(Boolean<F>, FpVar<F>)],
+        root: &FpVar<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        self.hash_path(leaf, path)?.is_eq(root)
+    }
+
+    /// Returns whether every leaf of `paths` has its authentication path in the binary Merkle
+    /// tree with the root `root`, as [`PoseidonSpongeVar::verify_path`]. An empty batch is
+    /// accepted.
+    #[allow(clippy::type_complexity)]
+    #[tracing::instrument(target = "r1cs", skip(self, paths, root))]
+    pub fn verify_paths(
+        &self,
+        paths: &[(FpVar<F>, Vec<(Boolean<F>, FpVar<F>)>)],
+        root: &FpVar<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let are_members = paths
+            .iter()
+            .map(|(leaf, path)| self.verify_path(leaf, path, root))
+            .collect::<Result<Vec<_>, _>>()?;
+        if are_members.is_empty() {
+            return Ok(Boolean::TRUE);
+        }
+        Boolean::kary_and(&are_members)
+    }
+
+    /// Applies the Poseidon permutation of the parameters to `state`, exactly as the sponge does,
+    /// so that other modes can be built on the permutation. The state of the gadget is not used
+    /// or modified.
+    ///
+    /// [`PoseidonPermutation`] gives the same permutation natively, and as a
+    /// [`FieldPermutationGadget`] for states that are slices.
+    ///
+    /// Returns [`SynthesisError::Unsatisfiable`] if `WIDTH` is not the state size of the
+    /// parameters.
+    #[tracing::instrument(target = "r1cs", skip(self, state))]
+    pub fn permute_state<const WIDTH: usize>(
+        &self,
+        state: &mut [FpVar<F>; WIDTH],
+    ) -> Result<(), SynthesisError> {
+        if WIDTH != self.parameters.state_size() {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let permuted = self.permuted(state.to_vec())?;
+        state.clone_from_slice(&permuted);
+        Ok(())
+    }
+
     #[tracing::instrument(target = "r1cs", skip(self))]
     fn apply_s_box(
         &self,
@@ -44,33 +484,69 @@ impl<F: PrimeField> PoseidonSpongeVar<F> {
         // Full rounds apply the S Box (x^alpha) to every element of state
         if is_full_round {
             for state_item in state.iter_mut() {
-                *state_item = state_item.pow_by_constant(&[self.parameters.alpha])?;
+                *state_item = self.s_box(state_item)?;
             }
         }
         // Partial rounds apply the S Box (x^alpha) to just the first element of state
         else {
-            state[0] = state[0].pow_by_constant(&[self.parameters.alpha])?;
+            state[0] = self.s_box(&state[0])?;
         }
 
         Ok(())
     }
 
+    fn s_box(&self, elem: &FpVar<F>) -> Result<FpVar<F>, SynthesisError> {
+        poseidon_s_box_var(elem, self.parameters.alpha)
+    }
+
     #[tracing::instrument(target = "r1cs", skip(self))]
     fn apply_ark(&self, state: &mut [FpVar<F>], round_number: usize) -> Result<(), SynthesisError> {
-        for (i, state_elem) in state.iter_mut().enumerate() {
-            *state_elem += self.parameters.ark[round_number][i];
+        match &self.parameters_var {
+            Some(parameters_var) => {
+                for (state_elem, constant) in
+                    state.iter_mut().zip(&parameters_var.ark[round_number])
+                {
+                    *state_elem += constant;
+                }
+            }
+            None => Self::add_round_constants(state, &self.parameters.ark[round_number]),
         }
         Ok(())
     }
 
     #[tracing::instrument(target = "r1cs", skip(self))]
     fn apply_mds(&self, state: &mut [FpVar<F>]) -> Result<(), SynthesisError> {
+        match &self.parameters_var {
+            Some(parameters_var) => Self::apply_matrix_var(&parameters_var.mds, state),
+            None => Self::apply_matrix(&self.parameters.mds, state),
+        }
+    }
+
+    // Multiplies the state by a matrix of variables, which costs a constraint per product of two
+    // variables.
+    fn apply_matrix_var(
+        matrix: &[Vec<FpVar<F>>],
+        state: &mut [FpVar<F>],
+    ) -> Result<(), SynthesisError> {
+        let new_state: Vec<_> = matrix
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(state.iter())
+                    .fold(FpVar::zero(), |acc, (m, state_elem)| acc + m * state_elem)
+            })
+            .collect();
+        state.clone_from_slice(&new_state);
+        Ok(())
+    }
+
+    fn apply_matrix(matrix: &[Vec<F>], state: &mut [FpVar<F>]) -> Result<(), SynthesisError> {
         let mut new_state = Vec::new();
         let zero = FpVar::<F>::zero();
-        for i in 0..state.len() {
+        for row in matrix.iter().take(state.len()) {
             let mut cur = zero.clone();
             for (j, state_elem) in state.iter().enumerate() {
-                let term = state_elem * self.parameters.mds[i][j];
+                let term = state_elem * row[j];
                 cur += &term;
             }
             new_state.push(cur);
@@ -80,9 +556,92 @@ impl<F: PrimeField> PoseidonSpongeVar<F> {
     }
 
     #[tracing::instrument(target = "r1cs", skip(self))]
-    fn permute(&mut self) -> Result<(), SynthesisError> {
+    fn apply_permutation(&mut self) -> Result<(), SynthesisError> {
+        #[cfg(feature = "trace")]
+        tracing::trace!(
+            target: "ark_sponge",
+            sponge = "poseidon",
+            "permute"
+        );
+        self.state = self.permuted(self.state.clone())?;
+        Ok(())
+    }
+
+    fn permuted(&self, state: Vec<FpVar<F>>) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        if self.parameters_var.is_some() {
+            return self.permute_unoptimized(state);
+        }
+        if let Some(mut values) = constant_state(&state) {
+            self.parameters.permute(&mut values);
+            return Ok(values.into_iter().map(FpVar::Constant).collect());
+        }
+        match self.parameters.optimized_parameters() {
+            Some(optimized_parameters) => self.permute_optimized(optimized_parameters, state),
+            None => self.permute_unoptimized(state),
+        }
+    }
+
+    fn permute_optimized(
+        &self,
+        optimized_parameters: &PoseidonOptimizedParameters<F>,
+        mut state: Vec<FpVar<F>>,
+    ) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        let full_rounds_over_2 = self.parameters.full_rounds / 2;
+        let (first_full_ark, last_full_ark) =
+            optimized_parameters.full_ark.split_at(full_rounds_over_2);
+
+        for (i, ark) in first_full_ark.iter().enumerate() {
+            Self::add_round_constants(&mut state, ark);
+            self.apply_s_box(&mut state, true)?;
+            if i + 1 == full_rounds_over_2 {
+                Self::apply_matrix(&optimized_parameters.pre_sparse_mds, &mut state)?;
+            } else {
+                self.apply_mds(&mut state)?;
+            }
+        }
+
+        for (ark, sparse_mds) in optimized_parameters
+            .partial_ark
+            .iter()
+            .zip(&optimized_parameters.sparse_mds)
+        {
+            state[0] += *ark;
+            self.apply_s_box(&mut state, false)?;
+
+            let first = state[0].clone();
+            let mut new_first = &first * sparse_mds.m_00;
+            for ((state_elem, row_elem), column_elem) in state[1..]
+                .iter_mut()
+                .zip(&sparse_mds.row)
+                .zip(&sparse_mds.column)
+            {
+                new_first += &*state_elem * *row_elem;
+                *state_elem += &first * *column_elem;
+            }
+            state[0] = new_first;
+        }
+
+        for ark in last_full_ark {
+            Self::add_round_constants(&mut state, ark);
+            self.apply_s_box(&mut state, true)?;
+            self.apply_mds(&mut state)?;
+        }
+
+        Ok(state)
+    }
+
+    fn add_round_constants(state: &mut [FpVar<F>], ark: &[F]) {
+        for (state_elem, constant) in state.iter_mut().zip(ark) {
+            *state_elem += *constant;
+        }
+    }
+
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    fn permute_unoptimized(
+        &self,
+        mut state: Vec<FpVar<F>>,
+    ) -> Result<Vec<FpVar<F>>, SynthesisError> {
         let full_rounds_over_2 = self.parameters.full_rounds / 2;
-        let mut state = self.state.clone();
         for i in 0..full_rounds_over_2 {
             self.apply_ark(&mut state, i)?;
             self.apply_s_box(&mut state, true)?;
@@ -102,8 +661,7 @@ impl<F: PrimeField> PoseidonSpongeVar<F> {
             self.apply_mds(&mut state)?;
         }
 
-        self.state = state;
-        Ok(())
+        Ok(state)
     }
 
     #[tracing::instrument(target = "r1cs", skip(self))]
@@ -117,7 +675,7 @@ impl<F: PrimeField> PoseidonSpongeVar<F> {
             // if we can finish in this call
             if rate_start_index + remaining_elements.len() <= self.parameters.rate {
                 for (i, element) in remaining_elements.iter().enumerate() {
-                    self.state[self.parameters.capacity + i + rate_start_index] += element;
+                    self.absorb_element(rate_start_index + i, element);
                 }
                 self.mode = DuplexSpongeMode::Absorbing {
                     next_absorb_index: rate_start_index + remaining_elements.len(),
@@ -132,15 +690,24 @@ impl<F: PrimeField> PoseidonSpongeVar<F> {
                 .enumerate()
                 .take(num_elements_absorbed)
             {
-                self.state[self.parameters.capacity + i + rate_start_index] += element;
+                self.absorb_element(rate_start_index + i, element);
             }
-            self.permute()?;
+            self.apply_permutation()?;
             // the input elements got truncated by num elements absorbed
             remaining_elements = &remaining_elements[num_elements_absorbed..];
             rate_start_index = 0;
         }
     }
 
+    // Absorbs `element` into the element of the rate at `rate_index`.
+    fn absorb_element(&mut self, rate_index: usize, element: &FpVar<F>) {
+        let state_elem = &mut self.state[self.parameters.capacity + rate_index];
+        match self.absorb_mode {
+            AbsorbMode::Add => *state_elem += element,
+            AbsorbMode::Overwrite => *state_elem = element.clone(),
+        }
+    }
+
     // Squeeze |output| many elements. This does not end in a squeeze
     #[tracing::instrument(target = "r1cs", skip(self))]
     fn squeeze_internal(
@@ -170,13 +737,43 @@ impl<F: PrimeField> PoseidonSpongeVar<F> {
 
             // Unless we are done with squeezing in this call, permute.
             if remaining_output.len() != self.parameters.rate {
-                self.permute()?;
+                self.apply_permutation()?;
             }
             // Repeat with updated output slices and rate start index
             remaining_output = &mut remaining_output[num_elements_squeezed..];
             rate_start_index = 0;
         }
     }
+
+    // Pads the input absorbed so far, before the sponge starts squeezing.
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    fn pad(&mut self, mut absorb_index: usize) -> Result<(), SynthesisError> {
+        let rate = self.parameters.rate;
+        let capacity = self.parameters.capacity;
+        match self.padding {
+            PaddingMode::None => {}
+            PaddingMode::MultiRate => {
+                if absorb_index == rate {
+                    self.apply_permutation()?;
+                    absorb_index = 0;
+                }
+                self.state[capacity + absorb_index] += F::one();
+                if absorb_index == rate - 1 {
+                    self.apply_permutation()?;
+                }
+                self.state[capacity + rate - 1] += F::one();
+            }
+            PaddingMode::LengthSuffix => {
+                if absorb_index == rate {
+                    self.apply_permutation()?;
+                    absorb_index = 0;
+                }
+                let length = FpVar::Constant(F::from(self.num_absorbed as u64));
+                self.absorb_internal(absorb_index, &[length])?;
+            }
+        }
+        Ok(())
+    }
 }
 
 impl<F: PrimeField> CryptographicSpongeVar<F, PoseidonSponge<F>> for PoseidonSpongeVar<F> {
@@ -184,18 +781,7 @@ impl<F: PrimeField> CryptographicSpongeVar<F, PoseidonSponge<F>> for PoseidonSpo
 
     #[tracing::instrument(target = "r1cs", skip(cs))]
     fn new(cs: ConstraintSystemRef<F>, parameters: &PoseidonParameters<F>) -> Self {
-        let zero = FpVar::<F>::zero();
-        let state = vec![zero; parameters.rate + parameters.capacity];
-        let mode = DuplexSpongeMode::Absorbing {
-            next_absorb_index: 0,
-        };
-
-        Self {
-            cs,
-            parameters: parameters.clone(),
-            state,
-            mode,
-        }
+        Self::with_padding(cs, parameters, PaddingMode::None)
     }
 
     #[tracing::instrument(target = "r1cs", skip(self))]
@@ -203,63 +789,68 @@ impl<F: PrimeField> CryptographicSpongeVar<F, PoseidonSponge<F>> for PoseidonSpo
         self.cs.clone()
     }
 
+    fn rate(&self) -> usize {
+        self.parameters.rate
+    }
+
+    fn capacity(&self) -> usize {
+        self.parameters.capacity
+    }
+
+    fn security_level(&self) -> usize {
+        field_sponge_security_level::<F>(self.parameters.capacity)
+    }
+
     #[tracing::instrument(target = "r1cs", skip(self, input))]
     fn absorb(&mut self, input: &impl AbsorbGadget<F>) -> Result<(), SynthesisError> {
         let input = input.to_sponge_field_elements()?;
         if input.is_empty() {
             return Ok(());
         }
+        #[cfg(feature = "trace")]
+        tracing::trace!(
+            target: "ark_sponge",
+            sponge = "poseidon",
+            num_elements = input.len(),
+            mode = ?self.mode,
+            "absorb"
+        );
 
         match self.mode {
             DuplexSpongeMode::Absorbing { next_absorb_index } => {
                 let mut absorb_index = next_absorb_index;
                 if absorb_index == self.parameters.rate {
-                    self.permute()?;
+                    self.apply_permutation()?;
                     absorb_index = 0;
                 }
                 self.absorb_internal(absorb_index, input.as_slice())?;
             }
+            // The squeeze that put the sponge in this mode already permuted after the last
+            // absorb, and the next squeeze permutes again, so the input can go straight into
+            // the rate.
             DuplexSpongeMode::Squeezing {
                 next_squeeze_index: _,
             } => {
-                self.permute()?;
+                self.num_absorbed = 0;
                 self.absorb_internal(0, input.as_slice())?;
             }
         };
+        self.num_absorbed += input.len();
 
         Ok(())
     }
 
     #[tracing::instrument(target = "r1cs", skip(self))]
     fn squeeze_bytes(&mut self, num_bytes: usize) -> Result<Vec<UInt8<F>>, SynthesisError> {
-        let usable_bytes = (F::Params::CAPACITY / 8) as usize;
-
-        let num_elements = (num_bytes + usable_bytes - 1) / usable_bytes;
-        let src_elements = self.squeeze_field_elements(num_elements)?;
-
-        let mut bytes: Vec<UInt8<F>> = Vec::with_capacity(usable_bytes * num_elements);
-        for elem in &src_elements {
-            bytes.extend_from_slice(&elem.to_bytes()?[..usable_bytes]);
-        }
-
-        bytes.truncate(num_bytes);
-        Ok(bytes)
+        let src_elements =
+            self.squeeze_field_elements(squeeze_bytes_num_elements::<F>(num_bytes))?;
+        bytes_from_squeezed_element_vars(&src_elements, num_bytes)
     }
 
     #[tracing::instrument(target = "r1cs", skip(self))]
     fn squeeze_bits(&mut self, num_bits: usize) -> Result<Vec<Boolean<F>>, SynthesisError> {
-        let usable_bits = F::Params::CAPACITY as usize;
-
-        let num_elements = (num_bits + usable_bits - 1) / usable_bits;
-        let src_elements = self.squeeze_field_elements(num_elements)?;
-
-        let mut bits: Vec<Boolean<F>> = Vec::with_capacity(usable_bits * num_elements);
-        for elem in &src_elements {
-            bits.extend_from_slice(&elem.to_bits_le()?[..usable_bits]);
-        }
-
-        bits.truncate(num_bits);
-        Ok(bits)
+        let src_elements = self.squeeze_field_elements(squeeze_bits_num_elements::<F>(num_bits))?;
+        bits_from_squeezed_element_vars(&src_elements, num_bits)
     }
 
     #[tracing::instrument(target = "r1cs", skip(self))]
@@ -267,19 +858,25 @@ impl<F: PrimeField> CryptographicSpongeVar<F, PoseidonSponge<F>> for PoseidonSpo
         &mut self,
         num_elements: usize,
     ) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        #[cfg(feature = "trace")]
+        tracing::trace!(
+            target: "ark_sponge",
+            sponge = "poseidon",
+            num_elements = num_elements,
+            mode = ?self.mode,
+            "squeeze"
+        );
         let zero = FpVar::zero();
         let mut squeezed_elems = vec![zero; num_elements];
         match self.mode {
-            DuplexSpongeMode::Absorbing {
-                next_absorb_index: _,
-            } => {
-                self.permute()?;
+            DuplexSpongeMode::Absorbing { .. } => {
+                self.finish_absorb()?;
                 self.squeeze_internal(0, &mut squeezed_elems)?;
             }
             DuplexSpongeMode::Squeezing { next_squeeze_index } => {
                 let mut squeeze_index = next_squeeze_index;
                 if squeeze_index == self.parameters.rate {
-                    self.permute()?;
+                    self.apply_permutation()?;
                     squeeze_index = 0;
                 }
                 self.squeeze_internal(squeeze_index, &mut squeezed_elems)?;
@@ -290,51 +887,400 @@ impl<F: PrimeField> CryptographicSpongeVar<F, PoseidonSponge<F>> for PoseidonSpo
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::constraints::CryptographicSpongeVar;
-    use crate::poseidon::constraints::PoseidonSpongeVar;
-    use crate::poseidon::tests::poseidon_parameters_for_test;
-    use crate::poseidon::PoseidonSponge;
-    use crate::{CryptographicSponge, FieldBasedCryptographicSponge};
-    use ark_ff::UniformRand;
-    use ark_r1cs_std::fields::fp::FpVar;
-    use ark_r1cs_std::prelude::*;
-    use ark_relations::r1cs::ConstraintSystem;
-    use ark_relations::*;
-    use ark_std::test_rng;
-    use ark_test_curves::bls12_381::Fr;
-
-    #[test]
-    fn absorb_test() {
-        let mut rng = test_rng();
-        let cs = ConstraintSystem::new_ref();
-
-        let absorb1: Vec<_> = (0..256).map(|_| Fr::rand(&mut rng)).collect();
-        let absorb1_var: Vec<_> = absorb1
-            .iter()
-            .map(|v| FpVar::new_input(ns!(cs, "absorb1"), || Ok(*v)).unwrap())
-            .collect();
+/// A Poseidon sponge over a field `F` emulated in a circuit over another field `CF`, whose
+/// state is made of [`NonNativeFieldVar`]s, for verifiers that reproduce in-circuit a
+/// transcript over `F` without a cycle of curves.
+///
+/// It absorbs and squeezes elements of `F` as a [`PoseidonSponge`] over `F` created with the
+/// same parameters absorbs them with [`CryptographicSponge::absorb`] and squeezes them with
+/// [`FieldBasedCryptographicSponge::squeeze_native_field_elements`]. It does not pad its input
+/// and adds the absorbed elements to the rate, as a [`PoseidonSponge`] created with
+/// [`CryptographicSponge::new`](crate::CryptographicSponge::new) does.
+///
+/// The round constants and the MDS matrix are constants, but every multiplication of the S-boxes
+/// is a multiplication of nonnative field elements, which costs hundreds of constraints, so a
+/// permutation is far more expensive than that of a [`PoseidonSpongeVar`]. A permutation of
+/// constants is computed natively and costs none.
+///
+/// [`CryptographicSponge::absorb`]: crate::CryptographicSponge::absorb
+/// [`FieldBasedCryptographicSponge::squeeze_native_field_elements`]: crate::FieldBasedCryptographicSponge::squeeze_native_field_elements
+#[derive(Clone)]
+pub struct NonNativePoseidonSpongeVar<F: PrimeField, CF: PrimeField> {
+    /// Constraint system
+    pub cs: ConstraintSystemRef<CF>,
+    /// Sponge Parameters, shared by the clones of the sponge.
+    parameters: Arc<PoseidonParameters<F>>,
+    /// The sponge's state
+    pub state: Vec<NonNativeFieldVar<F, CF>>,
+    /// The mode
+    pub mode: DuplexSpongeMode,
+}
 
-        let absorb2: Vec<_> = (0..8).map(|i| vec![i, i + 1, i + 2]).collect();
-        let absorb2_var: Vec<_> = absorb2
-            .iter()
-            .map(|v| UInt8::new_input_vec(ns!(cs, "absorb2"), v).unwrap())
+impl<F: PrimeField, CF: PrimeField> NonNativePoseidonSpongeVar<F, CF> {
+    /// Creates a sponge with the given parameters.
+    pub fn new(cs: ConstraintSystemRef<CF>, parameters: &PoseidonParameters<F>) -> Self {
+        let state = parameters
+            .initial_state::<Vec<F>>()
+            .into_iter()
+            .map(NonNativeFieldVar::Constant)
             .collect();
+        Self {
+            cs,
+            parameters: parameters.to_shared(),
+            state,
+            mode: DuplexSpongeMode::Absorbing {
+                next_absorb_index: 0,
+            },
+        }
+    }
 
-        let sponge_params = poseidon_parameters_for_test();
-
-        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
-        let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
-
-        native_sponge.absorb(&absorb1);
-        constraint_sponge.absorb(&absorb1_var).unwrap();
+    /// Returns a ref to the underlying constraint system the sponge is operating in.
+    pub fn cs(&self) -> ConstraintSystemRef<CF> {
+        self.cs.clone()
+    }
 
-        let squeeze1 = native_sponge.squeeze_native_field_elements(1);
-        let squeeze2 = constraint_sponge.squeeze_field_elements(1).unwrap();
+    /// Returns the parameters of the sponge.
+    pub fn parameters(&self) -> &PoseidonParameters<F> {
+        &self.parameters
+    }
 
-        assert_eq!(squeeze2.value().unwrap(), squeeze1);
-        assert!(cs.is_satisfied().unwrap());
+    /// Absorbs `elems`, as [`PoseidonSpongeVar`] absorbs native field elements.
+    #[tracing::instrument(target = "r1cs", skip(self, elems))]
+    pub fn absorb(&mut self, elems: &[NonNativeFieldVar<F, CF>]) -> Result<(), SynthesisError> {
+        if elems.is_empty() {
+            return Ok(());
+        }
+        #[cfg(feature = "trace")]
+        tracing::trace!(
+            target: "ark_sponge",
+            sponge = "poseidon",
+            num_elements = elems.len(),
+            mode = ?self.mode,
+            "absorb"
+        );
+        let mut absorb_index = match self.mode {
+            DuplexSpongeMode::Absorbing { next_absorb_index } => next_absorb_index,
+            DuplexSpongeMode::Squeezing { .. } => 0,
+        };
+        if absorb_index == self.parameters.rate {
+            self.permute()?;
+            absorb_index = 0;
+        }
+
+        let mut remaining_elems = elems;
+        loop {
+            let num_absorbed =
+                ark_std::cmp::min(self.parameters.rate - absorb_index, remaining_elems.len());
+            let rate_start = self.parameters.capacity + absorb_index;
+            for (state_elem, elem) in self.state[rate_start..]
+                .iter_mut()
+                .zip(&remaining_elems[..num_absorbed])
+            {
+                *state_elem += elem;
+            }
+            remaining_elems = &remaining_elems[num_absorbed..];
+            if remaining_elems.is_empty() {
+                self.mode = DuplexSpongeMode::Absorbing {
+                    next_absorb_index: absorb_index + num_absorbed,
+                };
+                return Ok(());
+            }
+            self.permute()?;
+            absorb_index = 0;
+        }
+    }
+
+    /// Squeezes `num_elements` elements of `F`, as [`PoseidonSpongeVar`] squeezes native field
+    /// elements.
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    pub fn squeeze_field_elements(
+        &mut self,
+        num_elements: usize,
+    ) -> Result<Vec<NonNativeFieldVar<F, CF>>, SynthesisError> {
+        #[cfg(feature = "trace")]
+        tracing::trace!(
+            target: "ark_sponge",
+            sponge = "poseidon",
+            num_elements = num_elements,
+            mode = ?self.mode,
+            "squeeze"
+        );
+        let mut squeeze_index = match self.mode {
+            DuplexSpongeMode::Absorbing { .. } => {
+                self.permute()?;
+                0
+            }
+            DuplexSpongeMode::Squeezing { next_squeeze_index } => next_squeeze_index,
+        };
+        if squeeze_index == self.parameters.rate {
+            self.permute()?;
+            squeeze_index = 0;
+        }
+
+        let mut output = Vec::with_capacity(num_elements);
+        loop {
+            let num_squeezed = ark_std::cmp::min(
+                self.parameters.rate - squeeze_index,
+                num_elements - output.len(),
+            );
+            let rate_start = self.parameters.capacity + squeeze_index;
+            output.extend_from_slice(&self.state[rate_start..rate_start + num_squeezed]);
+            if output.len() == num_elements {
+                self.mode = DuplexSpongeMode::Squeezing {
+                    next_squeeze_index: squeeze_index + num_squeezed,
+                };
+                return Ok(output);
+            }
+            // As the native sponge, skip the permutation if the elements squeezed so far in this
+            // call fill exactly one rate.
+            if num_elements - output.len() + num_squeezed != self.parameters.rate {
+                self.permute()?;
+            }
+            squeeze_index = 0;
+        }
+    }
+
+    /// Applies the permutation to the state.
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    pub fn permute(&mut self) -> Result<(), SynthesisError> {
+        #[cfg(feature = "trace")]
+        tracing::trace!(
+            target: "ark_sponge",
+            sponge = "poseidon",
+            "permute"
+        );
+        if self.state.iter().all(|elem| elem.is_constant()) {
+            let mut values = self.state.value()?;
+            self.parameters.permute(&mut values);
+            self.state = values
+                .into_iter()
+                .map(NonNativeFieldVar::Constant)
+                .collect();
+            return Ok(());
+        }
+
+        let parameters = self.parameters.clone();
+        let full_rounds_over_2 = parameters.full_rounds / 2;
+        let num_rounds = parameters.full_rounds + parameters.partial_rounds;
+        for (round, ark) in parameters.ark.iter().enumerate().take(num_rounds) {
+            for (state_elem, constant) in self.state.iter_mut().zip(ark) {
+                *state_elem += *constant;
+            }
+            let is_full_round = round < full_rounds_over_2
+                || round >= full_rounds_over_2 + parameters.partial_rounds;
+            let num_s_boxes = if is_full_round { self.state.len() } else { 1 };
+            for state_elem in &mut self.state[..num_s_boxes] {
+                *state_elem = s_box_var(state_elem, parameters.alpha)?;
+            }
+            self.state = parameters
+                .mds
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .zip(&self.state)
+                        .fold(NonNativeFieldVar::zero(), |acc, (m, state_elem)| {
+                            acc + state_elem * *m
+                        })
+                })
+                .collect();
+        }
+        Ok(())
+    }
+}
+
+/// The gadget counterpart of [`PoseidonSpongeState`]: the state of a [`PoseidonSpongeVar`].
+///
+/// The mode, the padding, the absorb mode, and the number of absorbed elements are part of the
+/// structure of the circuit, so the native state must be known when allocating, and only the elements of the
+/// state are allocated as variables.
+#[derive(Clone)]
+pub struct PoseidonSpongeStateVar<F: PrimeField> {
+    state: Vec<FpVar<F>>,
+    mode: DuplexSpongeMode,
+    padding: PaddingMode,
+    absorb_mode: AbsorbMode,
+    num_absorbed: usize,
+}
+
+impl<F: PrimeField> AllocVar<PoseidonSpongeState<F>, F> for PoseidonSpongeStateVar<F> {
+    fn new_variable<T: Borrow<PoseidonSpongeState<F>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let native = f()?;
+        let native = native.borrow();
+        Ok(Self {
+            state: Vec::new_variable(ns.cs(), || Ok(native.state.clone()), mode)?,
+            mode: native.mode.clone(),
+            padding: native.padding,
+            absorb_mode: native.absorb_mode,
+            num_absorbed: native.num_absorbed,
+        })
+    }
+}
+
+impl<F: PrimeField> R1CSVar<F> for PoseidonSpongeStateVar<F> {
+    type Value = PoseidonSpongeState<F>;
+
+    fn cs(&self) -> ConstraintSystemRef<F> {
+        self.state.cs()
+    }
+
+    fn value(&self) -> Result<Self::Value, SynthesisError> {
+        Ok(PoseidonSpongeState {
+            state: self.state.value()?,
+            mode: self.mode.clone(),
+            padding: self.padding,
+            absorb_mode: self.absorb_mode,
+            num_absorbed: self.num_absorbed,
+        })
+    }
+}
+
+impl<F: PrimeField> SpongeExtVar<F, PoseidonSponge<F>> for PoseidonSpongeVar<F> {
+    type State = PoseidonSpongeStateVar<F>;
+
+    fn from_state(
+        cs: ConstraintSystemRef<F>,
+        state: Self::State,
+        parameters: &PoseidonParameters<F>,
+    ) -> Self {
+        let mut sponge =
+            Self::with_padding(cs, parameters, state.padding).with_absorb_mode(state.absorb_mode);
+        sponge.mode = state.mode;
+        sponge.state = state.state;
+        sponge.num_absorbed = state.num_absorbed;
+        sponge
+    }
+
+    fn into_state(self) -> Self::State {
+        Self::State {
+            state: self.state,
+            mode: self.mode,
+            padding: self.padding,
+            absorb_mode: self.absorb_mode,
+            num_absorbed: self.num_absorbed,
+        }
+    }
+}
+
+/// Merges two sponge gadgets under a [`Boolean`], so that a circuit can continue a single
+/// transcript after a data-dependent branch.
+///
+/// The mode, the padding, the absorb mode, and the number of absorbed elements are part of the
+/// structure of the circuit, so both branches must have absorbed and squeezed the same number of
+/// elements with the same modes; otherwise [`SynthesisError::Unsatisfiable`] is returned. Both
+/// sponges must use the same parameters, and those of `true_value` are kept.
+impl<F: PrimeField> CondSelectGadget<F> for PoseidonSpongeVar<F> {
+    #[tracing::instrument(target = "r1cs", skip(cond, true_value, false_value))]
+    fn conditionally_select(
+        cond: &Boolean<F>,
+        true_value: &Self,
+        false_value: &Self,
+    ) -> Result<Self, SynthesisError> {
+        if true_value.mode != false_value.mode
+            || true_value.padding != false_value.padding
+            || true_value.absorb_mode != false_value.absorb_mode
+            || true_value.num_absorbed != false_value.num_absorbed
+        {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let mut sponge = true_value.clone();
+        sponge.cs = true_value.cs.clone().or(false_value.cs.clone());
+        sponge.state = select_state(cond, &true_value.state, &false_value.state)?;
+        Ok(sponge)
+    }
+}
+
+/// The gadget for [`PoseidonFamily`], with the same instances.
+#[derive(Clone)]
+pub struct PoseidonFamilyVar<F: PrimeField> {
+    /// The instances, sorted by arity.
+    instances: Vec<PoseidonSpongeVar<F>>,
+}
+
+impl<F: PrimeField> PoseidonFamilyVar<F> {
+    /// Creates the gadgets of the instances of `family`.
+    pub fn new(cs: ConstraintSystemRef<F>, family: &PoseidonFamily<F>) -> Self {
+        let instances = family
+            .arities()
+            .into_iter()
+            .map(|arity| {
+                let instance = family.instance(arity).unwrap();
+                PoseidonSpongeVar::new(cs.clone(), &instance.parameters)
+            })
+            .collect();
+        Self { instances }
+    }
+
+    /// Compresses `input` into one element with the instance whose arity is `input.len()`, as
+    /// [`PoseidonFamily::compress_n`].
+    ///
+    /// Returns [`SynthesisError::Unsatisfiable`] if the family has no instance of this arity.
+    pub fn compress_n(&self, input: &[FpVar<F>]) -> Result<FpVar<F>, SynthesisError> {
+        self.instances
+            .iter()
+            .find(|instance| instance.parameters.rate == input.len())
+            .ok_or(SynthesisError::Unsatisfiable)?
+            .compress(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constraints::CryptographicSpongeVar;
+    use crate::poseidon::constraints::{
+        poseidon_hash_var, NonNativePoseidonSpongeVar, PoseidonSpongeVar,
+    };
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::{poseidon_hash, PoseidonParameters, PoseidonSponge};
+    use crate::{
+        CryptographicSponge, FieldBasedCryptographicSponge, FieldElementSize, PaddingMode,
+        SpongeError, SqueezeMode,
+    };
+    use ark_ff::{Field, One, UniformRand, Zero};
+    use ark_nonnative_field::NonNativeFieldVar;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_relations::*;
+    use ark_std::test_rng;
+    use ark_std::vec;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::{Fq, Fr};
+
+    #[test]
+    fn absorb_test() {
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::new_ref();
+
+        let absorb1: Vec<_> = (0..256).map(|_| Fr::rand(&mut rng)).collect();
+        let absorb1_var: Vec<_> = absorb1
+            .iter()
+            .map(|v| FpVar::new_input(ns!(cs, "absorb1"), || Ok(*v)).unwrap())
+            .collect();
+
+        let absorb2: Vec<_> = (0..8).map(|i| vec![i, i + 1, i + 2]).collect();
+        let absorb2_var: Vec<_> = absorb2
+            .iter()
+            .map(|v| UInt8::new_input_vec(ns!(cs, "absorb2"), v).unwrap())
+            .collect();
+
+        let sponge_params = poseidon_parameters_for_test();
+
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+
+        native_sponge.absorb(&absorb1);
+        constraint_sponge.absorb(&absorb1_var).unwrap();
+
+        let squeeze1 = native_sponge.squeeze_native_field_elements(1);
+        let squeeze2 = constraint_sponge.squeeze_field_elements(1).unwrap();
+
+        assert_eq!(squeeze2.value().unwrap(), squeeze1);
+        assert!(cs.is_satisfied().unwrap());
 
         native_sponge.absorb(&absorb2);
         constraint_sponge.absorb(&absorb2_var).unwrap();
@@ -345,4 +1291,1843 @@ mod tests {
         assert_eq!(squeeze2.value().unwrap(), squeeze1);
         assert!(cs.is_satisfied().unwrap());
     }
+
+    #[test]
+    fn new_with_parameters() {
+        let cs = ConstraintSystem::new_ref();
+        let sponge_params = poseidon_parameters_for_test();
+
+        let mut native_sponge = PoseidonSponge::<Fr>::new_with_parameters(&sponge_params);
+        let mut constraint_sponge =
+            PoseidonSpongeVar::<Fr>::new_with_parameters(cs.clone(), &sponge_params);
+        native_sponge.absorb(&Fr::from(1u8));
+        constraint_sponge
+            .absorb(&FpVar::new_witness(cs.clone(), || Ok(Fr::from(1u8))).unwrap())
+            .unwrap();
+
+        let mut expected = PoseidonSponge::<Fr>::new(&sponge_params);
+        expected.absorb(&Fr::from(1u8));
+        let expected = expected.squeeze_native_field_elements(2);
+        assert_eq!(native_sponge.squeeze_native_field_elements(2), expected);
+        assert_eq!(
+            constraint_sponge
+                .squeeze_field_elements(2)
+                .unwrap()
+                .value()
+                .unwrap(),
+            expected
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn state_size_test() {
+        let mut rng = test_rng();
+        let absorb: Vec<_> = (0..20).map(|_| Fr::rand(&mut rng)).collect();
+
+        // A narrow and a wide instance, as well as one with a larger capacity.
+        let all_params = [
+            PoseidonParameters::<Fr>::generate(2, 1, 128),
+            PoseidonParameters::<Fr>::generate(8, 1, 128),
+            PoseidonParameters::<Fr>::generate(3, 2, 128),
+        ];
+        let state_sizes: Vec<_> = all_params.iter().map(|p| p.state_size()).collect();
+        assert_eq!(state_sizes, vec![3, 9, 5]);
+
+        for sponge_params in all_params.iter() {
+            let cs = ConstraintSystem::new_ref();
+            let absorb_var: Vec<_> = absorb
+                .iter()
+                .map(|v| FpVar::new_witness(ns!(cs, "absorb"), || Ok(*v)).unwrap())
+                .collect();
+
+            let mut native_sponge = PoseidonSponge::<Fr>::new(sponge_params);
+            let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), sponge_params);
+            assert_eq!(native_sponge.state.len(), sponge_params.state_size());
+            assert_eq!(constraint_sponge.state.len(), sponge_params.state_size());
+
+            native_sponge.absorb(&absorb);
+            constraint_sponge.absorb(&absorb_var).unwrap();
+
+            let squeeze1 = native_sponge.squeeze_native_field_elements(11);
+            let squeeze2 = constraint_sponge.squeeze_field_elements(11).unwrap();
+
+            assert_eq!(squeeze2.value().unwrap(), squeeze1);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn optimized_permutation_test() {
+        let mut rng = test_rng();
+        let sponge_params = PoseidonParameters::<Fr>::generate(2, 1, 128);
+        assert_eq!(
+            (sponge_params.full_rounds, sponge_params.partial_rounds),
+            (8, 56)
+        );
+        assert_eq!(sponge_params.alpha, 5);
+        let state: Vec<_> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+
+        let mut stats = Vec::new();
+        for optimized in [true, false] {
+            let cs = ConstraintSystem::new_ref();
+            let mut sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+            sponge.state = state
+                .iter()
+                .map(|v| FpVar::new_witness(ns!(cs, "state"), || Ok(*v)).unwrap())
+                .collect();
+
+            let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+            native_sponge.state = state.clone();
+            native_sponge.permute();
+            if optimized {
+                sponge.permute().unwrap();
+            } else {
+                sponge.state = sponge.permute_unoptimized(sponge.state.clone()).unwrap();
+            }
+            assert_eq!(sponge.state.value().unwrap(), native_sponge.state);
+
+            assert!(cs.is_satisfied().unwrap());
+            stats.push((
+                cs.num_constraints(),
+                cs.borrow().unwrap().num_linear_combinations,
+            ));
+        }
+
+        // x^5 costs 3 constraints, so each of the 8 * 3 + 56 S-boxes costs 3 constraints, and
+        // the linear layers are free.
+        assert_eq!(stats[0].0, 240);
+        assert_eq!(stats[1].0, 240);
+        // The sparse partial rounds need far fewer linear combinations.
+        assert!(
+            stats[0].1 < stats[1].1,
+            "optimized: {:?}, unoptimized: {:?}",
+            stats[0],
+            stats[1]
+        );
+    }
+
+    #[test]
+    fn s_box_test() {
+        let mut rng = test_rng();
+        let absorb: Vec<_> = (0..5).map(|_| Fr::rand(&mut rng)).collect();
+
+        for alpha in [3, 5, 7, -1] {
+            let cs = ConstraintSystem::new_ref();
+            let absorb_var: Vec<_> = absorb
+                .iter()
+                .map(|v| FpVar::new_witness(ns!(cs, "absorb"), || Ok(*v)).unwrap())
+                .collect();
+
+            let mut sponge_params = poseidon_parameters_for_test();
+            sponge_params.alpha = alpha;
+
+            let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+            let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+
+            native_sponge.absorb(&absorb);
+            constraint_sponge.absorb(&absorb_var).unwrap();
+
+            let squeeze1 = native_sponge.squeeze_native_field_elements(2);
+            let squeeze2 = constraint_sponge.squeeze_field_elements(2).unwrap();
+
+            assert_eq!(squeeze2.value().unwrap(), squeeze1);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn s_box_constraints() {
+        use crate::poseidon::constraints::poseidon_s_box_var;
+
+        let mut rng = test_rng();
+        let elem = Fr::rand(&mut rng);
+        for (alpha, num_constraints) in [(3, 2), (5, 3), (7, 4), (17, 5), (-1, 3)] {
+            let cs = ConstraintSystem::new_ref();
+            let elem_var = FpVar::new_witness(cs.clone(), || Ok(elem)).unwrap();
+            let output = poseidon_s_box_var(&elem_var, alpha).unwrap();
+            let expected = if alpha == -1 {
+                elem.inverse().unwrap()
+            } else {
+                elem.pow([alpha as u64])
+            };
+            assert_eq!(output.value().unwrap(), expected);
+            assert_eq!(cs.num_constraints(), num_constraints, "alpha = {}", alpha);
+            assert!(cs.is_satisfied().unwrap());
+
+            let output = poseidon_s_box_var(&FpVar::Constant(elem), alpha).unwrap();
+            assert!(output.is_constant());
+            assert_eq!(output.value().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn round_constraints() {
+        use crate::duplex::constraints::FieldPermutationGadget;
+        use crate::poseidon::PoseidonPermutation;
+        use crate::SecurityLevel;
+
+        // the S-boxes are the only nonlinear part of a round, so a full round costs one S-box
+        // per element of the state, and a partial round a single S-box
+        let mut inverse_params = poseidon_parameters_for_test::<Fr>();
+        inverse_params.alpha = -1;
+        for (params, s_box_constraints) in [
+            (poseidon_parameters_for_test::<Fr>(), 5),
+            (inverse_params, 3),
+            (
+                PoseidonParameters::for_security_level(2, SecurityLevel::Bits128),
+                3,
+            ),
+            (
+                PoseidonParameters::for_security_level(4, SecurityLevel::Bits128),
+                3,
+            ),
+        ] {
+            let cs = ConstraintSystem::new_ref();
+            let mut state: Vec<_> = (0..params.state_size())
+                .map(|i| FpVar::new_witness(cs.clone(), || Ok(Fr::from(i as u64 + 3))).unwrap())
+                .collect();
+            PoseidonPermutation::new(&params)
+                .permute_var(&mut state)
+                .unwrap();
+            assert_eq!(
+                cs.num_constraints(),
+                (params.state_size() * params.full_rounds + params.partial_rounds)
+                    * s_box_constraints
+            );
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn inverse_s_box_test() {
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::new_ref();
+
+        let mut sponge_params = poseidon_parameters_for_test();
+        sponge_params.alpha = -1;
+        let sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+
+        let elem = Fr::rand(&mut rng);
+        let elem_var = FpVar::new_witness(ns!(cs, "elem"), || Ok(elem)).unwrap();
+        let zero_var = FpVar::new_witness(ns!(cs, "zero"), || Ok(Fr::zero())).unwrap();
+
+        assert_eq!(
+            sponge.s_box(&elem_var).unwrap().value().unwrap(),
+            elem.inverse().unwrap()
+        );
+        assert_eq!(
+            sponge.s_box(&zero_var).unwrap().value().unwrap(),
+            Fr::zero()
+        );
+        assert_eq!(
+            sponge
+                .s_box(&FpVar::Constant(Fr::zero()))
+                .unwrap()
+                .value()
+                .unwrap(),
+            Fr::zero()
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn padding_test() {
+        let mut rng = test_rng();
+        let sponge_params = poseidon_parameters_for_test();
+
+        for padding in [
+            PaddingMode::None,
+            PaddingMode::MultiRate,
+            PaddingMode::LengthSuffix,
+        ] {
+            for len in 0..6 {
+                let cs = ConstraintSystem::new_ref();
+                let absorb: Vec<_> = (0..len).map(|_| Fr::rand(&mut rng)).collect();
+                let absorb_var: Vec<_> = absorb
+                    .iter()
+                    .map(|v| FpVar::new_witness(ns!(cs, "absorb"), || Ok(*v)).unwrap())
+                    .collect();
+
+                let mut native_sponge = PoseidonSponge::<Fr>::with_padding(&sponge_params, padding);
+                let mut constraint_sponge =
+                    PoseidonSpongeVar::<Fr>::with_padding(cs.clone(), &sponge_params, padding);
+
+                native_sponge.absorb(&absorb);
+                constraint_sponge.absorb(&absorb_var).unwrap();
+                let mut squeeze1 = native_sponge.squeeze_native_field_elements(3);
+                let mut squeeze2 = constraint_sponge.squeeze_field_elements(3).unwrap();
+
+                native_sponge.absorb(&absorb);
+                constraint_sponge.absorb(&absorb_var).unwrap();
+                squeeze1.extend(native_sponge.squeeze_native_field_elements(1));
+                squeeze2.extend(constraint_sponge.squeeze_field_elements(1).unwrap());
+
+                assert_eq!(squeeze2.value().unwrap(), squeeze1);
+                assert!(cs.is_satisfied().unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn ratchet_test() {
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::new_ref();
+        let sponge_params = poseidon_parameters_for_test();
+        let absorb: Vec<_> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        let absorb_var: Vec<_> = absorb
+            .iter()
+            .map(|v| FpVar::new_witness(ns!(cs, "absorb"), || Ok(*v)).unwrap())
+            .collect();
+
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+
+        native_sponge.absorb(&absorb);
+        constraint_sponge.absorb(&absorb_var).unwrap();
+        native_sponge.ratchet();
+        constraint_sponge.ratchet().unwrap();
+        native_sponge.absorb(&absorb[..1].to_vec());
+        constraint_sponge.absorb(&absorb_var[..1].to_vec()).unwrap();
+
+        let squeeze1 = native_sponge.squeeze_native_field_elements(2);
+        let squeeze2 = constraint_sponge.squeeze_field_elements(2).unwrap();
+        assert_eq!(squeeze2.value().unwrap(), squeeze1);
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn compress_test() {
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::new_ref();
+        let sponge_params = poseidon_parameters_for_test();
+        let input: Vec<_> = (0..sponge_params.rate)
+            .map(|_| Fr::rand(&mut rng))
+            .collect();
+        let input_var: Vec<_> = input
+            .iter()
+            .map(|v| FpVar::new_witness(ns!(cs, "input"), || Ok(*v)).unwrap())
+            .collect();
+
+        let native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        let constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+        assert_eq!(
+            constraint_sponge
+                .compress(&input_var)
+                .unwrap()
+                .value()
+                .unwrap(),
+            native_sponge.compress(&input).unwrap()
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn one_shot_hash_test() {
+        let cs = ConstraintSystem::new_ref();
+        let sponge_params = poseidon_parameters_for_test();
+        let input = [Fr::from(1u64), Fr::from(2u64), Fr::from(3u64)];
+        let input_var: Vec<_> = input
+            .iter()
+            .map(|v| FpVar::new_witness(ns!(cs, "input"), || Ok(*v)).unwrap())
+            .collect();
+
+        let hash = poseidon_hash_var(cs.clone(), &sponge_params, &input_var).unwrap();
+        assert_eq!(hash.value().unwrap(), poseidon_hash(&sponge_params, &input));
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn absorb_iter_test() {
+        let cs = ConstraintSystem::new_ref();
+        let sponge_params = poseidon_parameters_for_test();
+        let input: Vec<_> = (0..10u64).map(Fr::from).collect();
+        let input_var: Vec<_> = input
+            .iter()
+            .map(|v| FpVar::new_witness(ns!(cs, "input"), || Ok(*v)).unwrap())
+            .collect();
+
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+        native_sponge.absorb_iter(input);
+        constraint_sponge.absorb_iter(input_var).unwrap();
+        assert_eq!(
+            constraint_sponge
+                .squeeze_field_elements(2)
+                .unwrap()
+                .value()
+                .unwrap(),
+            native_sponge.squeeze_native_field_elements(2)
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn squeeze_truncated_test() {
+        let cs = ConstraintSystem::new_ref();
+        let sponge_params = poseidon_parameters_for_test();
+        let sizes = [
+            FieldElementSize::Truncated(128),
+            FieldElementSize::Full,
+            FieldElementSize::Truncated(10),
+        ];
+
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+        native_sponge.absorb(&Fr::from(42u64));
+        constraint_sponge
+            .absorb(&FpVar::Constant(Fr::from(42u64)))
+            .unwrap();
+
+        let native: Vec<Fq> = native_sponge.squeeze_field_elements_with_sizes(&sizes);
+        let (gadget, bits) = constraint_sponge
+            .squeeze_nonnative_field_elements_with_sizes::<Fq>(&sizes)
+            .unwrap();
+        assert_eq!(gadget.value().unwrap(), native);
+        assert_eq!(bits[0].len(), 128);
+        assert!(native[0] < Fq::from(2u64).pow([128]));
+        assert!(native[2] < Fq::from(1024u64));
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn squeeze_nonnative_test() {
+        let cs = ConstraintSystem::new_ref();
+        let sponge_params = poseidon_parameters_for_test();
+
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+        native_sponge.absorb(&Fr::from(42u64));
+        constraint_sponge
+            .absorb(&FpVar::Constant(Fr::from(42u64)))
+            .unwrap();
+
+        let (gadget, _) = constraint_sponge
+            .squeeze_nonnative_field_elements::<Fq>(2)
+            .unwrap();
+        assert_eq!(
+            gadget.value().unwrap(),
+            native_sponge.squeeze_nonnative_field_elements::<Fq>(2)
+        );
+        // The native field is also squeezed through bits.
+        let (gadget, _) = constraint_sponge
+            .squeeze_nonnative_field_elements::<Fr>(2)
+            .unwrap();
+        assert_eq!(
+            gadget.value().unwrap(),
+            native_sponge.squeeze_nonnative_field_elements::<Fr>(2)
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn squeeze_uniform_test() {
+        let cs = ConstraintSystem::new_ref();
+        let sponge_params = poseidon_parameters_for_test();
+
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+        native_sponge.absorb(&Fr::from(42u64));
+        constraint_sponge
+            .absorb(&FpVar::Constant(Fr::from(42u64)))
+            .unwrap();
+
+        let native: Vec<Fr> =
+            native_sponge.squeeze_field_elements_with_mode(2, SqueezeMode::Uniform);
+        let gadget = constraint_sponge
+            .squeeze_field_elements_with_mode(2, SqueezeMode::Uniform)
+            .unwrap();
+        assert_eq!(gadget.value().unwrap(), native);
+
+        let native: Vec<Fq> =
+            native_sponge.squeeze_field_elements_with_mode(2, SqueezeMode::Uniform);
+        let gadget = constraint_sponge
+            .squeeze_nonnative_field_elements_with_mode::<Fq>(2, SqueezeMode::Uniform)
+            .unwrap();
+        assert_eq!(gadget.value().unwrap(), native);
+
+        let native: Vec<Fq> =
+            native_sponge.squeeze_field_elements_with_mode(2, SqueezeMode::Truncated);
+        let gadget = constraint_sponge
+            .squeeze_nonnative_field_elements_with_mode::<Fq>(2, SqueezeMode::Truncated)
+            .unwrap();
+        assert_eq!(gadget.value().unwrap(), native);
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn squeeze_integers_in_range_test() {
+        let sponge_params = poseidon_parameters_for_test();
+
+        for bound in [1u64, 2, 10, 1000, u64::MAX] {
+            let cs = ConstraintSystem::new_ref();
+            let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+            let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+            native_sponge.absorb(&Fr::from(42u64));
+            constraint_sponge
+                .absorb(&FpVar::Constant(Fr::from(42u64)))
+                .unwrap();
+
+            let native = native_sponge.squeeze_integers_in_range(bound, 5).unwrap();
+            assert!(native.iter().all(|i| *i < bound));
+            let gadget = constraint_sponge
+                .squeeze_integers_in_range(bound, 5)
+                .unwrap();
+            let expected: Vec<_> = native.iter().map(|i| Fr::from(*i)).collect();
+            assert_eq!(gadget.value().unwrap(), expected);
+            assert!(cs.is_satisfied().unwrap());
+        }
+
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        assert_eq!(
+            native_sponge.squeeze_integers_in_range(0, 1),
+            Err(SpongeError::EmptyRange)
+        );
+    }
+
+    #[test]
+    fn squeeze_permutation_test() {
+        let sponge_params = poseidon_parameters_for_test();
+
+        for n in [0usize, 1, 2, 5, 17] {
+            let cs = ConstraintSystem::new_ref();
+            let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+            let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+            native_sponge.absorb(&Fr::from(n as u64));
+            constraint_sponge
+                .absorb(&FpVar::new_witness(cs.clone(), || Ok(Fr::from(n as u64))).unwrap())
+                .unwrap();
+
+            let native = native_sponge.squeeze_permutation(n);
+            let mut sorted = native.clone();
+            sorted.sort_unstable();
+            assert_eq!(sorted, (0..n).collect::<Vec<_>>());
+            let gadget = constraint_sponge.squeeze_permutation(n).unwrap();
+            let expected: Vec<_> = native.iter().map(|i| Fr::from(*i as u64)).collect();
+            assert_eq!(gadget.value().unwrap(), expected);
+            assert!(cs.is_satisfied().unwrap());
+
+            // the sponges are still in sync
+            assert_eq!(
+                constraint_sponge
+                    .squeeze_field_elements(1)
+                    .unwrap()
+                    .value()
+                    .unwrap(),
+                native_sponge.squeeze_native_field_elements(1)
+            );
+        }
+    }
+
+    #[test]
+    fn squeeze_powers_test() {
+        let sponge_params = poseidon_parameters_for_test();
+
+        for n in [0usize, 1, 2, 6] {
+            let cs = ConstraintSystem::new_ref();
+            let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+            let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+            native_sponge.absorb(&Fr::from(5u64));
+            constraint_sponge
+                .absorb(&FpVar::new_witness(cs.clone(), || Ok(Fr::from(5u64))).unwrap())
+                .unwrap();
+
+            let mut plain_sponge = native_sponge.clone();
+            let native = native_sponge.squeeze_powers::<Fr>(n);
+            assert_eq!(native.len(), n);
+            if n > 1 {
+                assert_eq!(native[0], Fr::one());
+                assert!(native.windows(2).all(|w| w[1] == w[0] * native[1]));
+                // the challenge is domain-separated
+                assert_ne!(native[1], plain_sponge.squeeze_native_field_elements(1)[0]);
+            }
+
+            let num_constraints = cs.num_constraints();
+            let mut squeeze_sponge = constraint_sponge.clone();
+            squeeze_sponge
+                .absorb(&UInt8::constant_vec(&crate::squeeze_powers_input(n)))
+                .unwrap();
+            squeeze_sponge.squeeze_field_elements(1).unwrap();
+            let squeeze_constraints = cs.num_constraints() - num_constraints;
+
+            let num_constraints = cs.num_constraints();
+            let gadget = constraint_sponge.squeeze_powers(n).unwrap();
+            assert_eq!(gadget.value().unwrap(), native);
+            assert_eq!(
+                cs.num_constraints() - num_constraints,
+                squeeze_constraints + n.saturating_sub(2)
+            );
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn squeeze_distinct_indices_test() {
+        let sponge_params = poseidon_parameters_for_test();
+
+        for (range, num_indices, num_candidates) in [(1u64, 1, 2), (10, 4, 12), (16, 8, 24)] {
+            let cs = ConstraintSystem::new_ref();
+            let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+            let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+            native_sponge.absorb(&Fr::from(42u64));
+            constraint_sponge
+                .absorb(&FpVar::Constant(Fr::from(42u64)))
+                .unwrap();
+            let num_bits = (64 - (range - 1).leading_zeros()) as usize;
+
+            let native = native_sponge
+                .squeeze_distinct_indices(range, num_indices, num_candidates)
+                .unwrap();
+            assert_eq!(native.len(), num_indices);
+            assert!(native.iter().all(|i| *i < range));
+            assert!(native
+                .iter()
+                .enumerate()
+                .all(|(j, i)| !native[..j].contains(i)));
+
+            let (indices, bits) = constraint_sponge
+                .squeeze_distinct_indices(range, num_indices, num_candidates)
+                .unwrap();
+            let expected: Vec<_> = native.iter().map(|i| Fr::from(*i)).collect();
+            assert_eq!(indices.value().unwrap(), expected);
+            for (bits, index) in bits.iter().zip(&native) {
+                let expected: Vec<_> = (0..num_bits).map(|i| (index >> i) & 1 == 1).collect();
+                assert_eq!(bits.value().unwrap(), expected);
+            }
+            assert!(cs.is_satisfied().unwrap());
+
+            // both sponges squeezed all the candidates
+            assert_eq!(
+                constraint_sponge
+                    .squeeze_field_elements(1)
+                    .unwrap()
+                    .value()
+                    .unwrap(),
+                native_sponge.squeeze_native_field_elements(1)
+            );
+        }
+
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        assert_eq!(
+            native_sponge.squeeze_distinct_indices(0, 0, 1),
+            Err(SpongeError::EmptyRange)
+        );
+        assert_eq!(
+            native_sponge.squeeze_distinct_indices(3, 4, 10),
+            Err(SpongeError::TooManyIndices {
+                num_indices: 4,
+                range: 3
+            })
+        );
+        assert_eq!(
+            native_sponge.squeeze_distinct_indices(1 << 20, 3, 2),
+            Err(SpongeError::NotEnoughDistinctIndices {
+                found: 2,
+                expected: 3
+            })
+        );
+
+        let mut constraint_sponge =
+            PoseidonSpongeVar::<Fr>::new(ConstraintSystem::new_ref(), &sponge_params);
+        assert!(constraint_sponge
+            .squeeze_distinct_indices(1 << 20, 3, 2)
+            .is_err());
+
+        // the constraints are not satisfied when the native method fails
+        let input = (0..100u64)
+            .map(Fr::from)
+            .find(|input| {
+                let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+                native_sponge.absorb(input);
+                native_sponge.squeeze_distinct_indices(10, 3, 3).is_err()
+            })
+            .unwrap();
+        let cs = ConstraintSystem::new_ref();
+        let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+        constraint_sponge
+            .absorb(&FpVar::new_witness(cs.clone(), || Ok(input)).unwrap())
+            .unwrap();
+        constraint_sponge
+            .squeeze_distinct_indices(10, 3, 3)
+            .unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn squeeze_short_challenges_test() {
+        let cs = ConstraintSystem::new_ref();
+        let sponge_params = poseidon_parameters_for_test();
+
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+        native_sponge.absorb(&Fr::from(42u64));
+        constraint_sponge
+            .absorb(&FpVar::Constant(Fr::from(42u64)))
+            .unwrap();
+
+        let native = native_sponge.squeeze_short_challenges(3, 128).unwrap();
+        let (gadget, bits) = constraint_sponge.squeeze_short_challenges(3, 128).unwrap();
+        assert_eq!(gadget.value().unwrap(), native);
+        assert!(bits.iter().all(|bits| bits.len() == 128));
+        assert!(native.iter().all(|c| *c < Fr::from(2u64).pow([128])));
+        assert!(native_sponge.squeeze_short_challenges(1, 255).is_err());
+        assert!(constraint_sponge.squeeze_short_challenges(1, 255).is_err());
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn resume_from_native_state() {
+        use crate::constraints::SpongeExtVar;
+        use crate::poseidon::constraints::PoseidonSpongeStateVar;
+        use crate::SpongeExt;
+
+        let cs = ConstraintSystem::new_ref();
+        let sponge_params = poseidon_parameters_for_test();
+
+        let mut native_sponge =
+            PoseidonSponge::<Fr>::with_padding(&sponge_params, PaddingMode::MultiRate);
+        native_sponge.absorb(&vec![Fr::from(1u64), Fr::from(2u64)]);
+        native_sponge.squeeze_native_field_elements(1);
+        native_sponge.absorb(&Fr::from(3u64));
+        let expected = native_sponge.clone().squeeze_native_field_elements(2);
+
+        let native_state = native_sponge.into_state();
+        let state_var =
+            PoseidonSpongeStateVar::new_witness(ns!(cs, "state"), || Ok(native_state.clone()))
+                .unwrap();
+        assert_eq!(state_var.value().unwrap(), native_state);
+
+        let mut constraint_sponge =
+            PoseidonSpongeVar::from_state(cs.clone(), state_var, &sponge_params);
+        let squeezed = constraint_sponge.squeeze_field_elements(2).unwrap();
+        assert_eq!(squeezed.value().unwrap(), expected);
+        assert_eq!(
+            constraint_sponge.into_state().value().unwrap().state.len(),
+            sponge_params.state_size()
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn new_from_native() {
+        use crate::constraints::SpongeExtVar;
+
+        let sponge_params = poseidon_parameters_for_test();
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        native_sponge.absorb(&vec![Fr::from(1u64), Fr::from(2u64)]);
+        native_sponge.squeeze_native_field_elements(1);
+        native_sponge.absorb(&Fr::from(3u64));
+
+        for mode in [AllocationMode::Constant, AllocationMode::Witness] {
+            let cs = ConstraintSystem::new_ref();
+            let mut constraint_sponge = PoseidonSpongeVar::new_from_native(
+                cs.clone(),
+                &native_sponge,
+                &sponge_params,
+                mode,
+            )
+            .unwrap();
+            let mut expected_sponge = native_sponge.clone();
+            assert_eq!(
+                constraint_sponge
+                    .squeeze_field_elements(2)
+                    .unwrap()
+                    .value()
+                    .unwrap(),
+                expected_sponge.squeeze_native_field_elements(2)
+            );
+            match mode {
+                AllocationMode::Constant => assert_eq!(cs.num_constraints(), 0),
+                _ => assert!(cs.num_constraints() > 0),
+            }
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn fork() {
+        let cs = ConstraintSystem::new_ref();
+        let sponge_params = poseidon_parameters_for_test();
+
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        native_sponge.absorb(&Fr::from(7u64));
+        let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+        constraint_sponge
+            .absorb(&FpVar::new_witness(cs.clone(), || Ok(Fr::from(7u64))).unwrap())
+            .unwrap();
+
+        for tag in [&b"column 1"[..], b"column 2", b""] {
+            let mut native_fork = native_sponge.fork(tag);
+            let mut constraint_fork = constraint_sponge.fork(tag).unwrap();
+            assert_eq!(
+                constraint_fork
+                    .squeeze_field_elements(2)
+                    .unwrap()
+                    .value()
+                    .unwrap(),
+                native_fork.squeeze_native_field_elements(2)
+            );
+        }
+        assert!(cs.is_satisfied().unwrap());
+    }
+    #[test]
+    fn absorb_bytes() {
+        let cs = ConstraintSystem::new_ref();
+        let sponge_params = poseidon_parameters_for_test();
+        let bytes: Vec<u8> = (0..100).collect();
+
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        native_sponge.absorb(&bytes.as_slice());
+        let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+        let bytes_var = UInt8::new_witness_vec(cs.clone(), &bytes).unwrap();
+        constraint_sponge.absorb_bytes(&bytes_var).unwrap();
+
+        assert_eq!(
+            constraint_sponge
+                .squeeze_field_elements(2)
+                .unwrap()
+                .value()
+                .unwrap(),
+            native_sponge.squeeze_native_field_elements(2)
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+    #[test]
+    fn absorb_bits() {
+        let cs = ConstraintSystem::new_ref();
+        let sponge_params = poseidon_parameters_for_test();
+
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        native_sponge.absorb(&Fr::from(1u64));
+        let bits = native_sponge.squeeze_bits(300);
+        native_sponge.absorb_bits(&bits);
+
+        let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+        constraint_sponge
+            .absorb(&FpVar::new_witness(cs.clone(), || Ok(Fr::from(1u64))).unwrap())
+            .unwrap();
+        let bits_var = constraint_sponge.squeeze_bits(300).unwrap();
+        assert_eq!(bits_var.value().unwrap(), bits);
+        constraint_sponge.absorb_bits(&bits_var).unwrap();
+
+        assert_eq!(
+            constraint_sponge
+                .squeeze_field_elements(2)
+                .unwrap()
+                .value()
+                .unwrap(),
+            native_sponge.squeeze_native_field_elements(2)
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+    #[test]
+    fn absorb_nonnative() {
+        use ark_nonnative_field::NonNativeFieldVar;
+
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::new_ref();
+        let sponge_params = poseidon_parameters_for_test();
+        let elems: Vec<Fq> = (0..3).map(|_| Fq::rand(&mut rng)).collect();
+
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        native_sponge.absorb_nonnative(&elems);
+
+        let mut elem_vars: Vec<NonNativeFieldVar<Fq, Fr>> = elems
+            .iter()
+            .map(|elem| NonNativeFieldVar::new_witness(cs.clone(), || Ok(*elem)).unwrap())
+            .collect();
+        // Constants and elements which are not in normal form are absorbed in the same way.
+        elem_vars[1] = NonNativeFieldVar::Constant(elems[1]);
+        elem_vars[2] = &elem_vars[2] * &NonNativeFieldVar::Constant(Fq::one());
+        let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+        constraint_sponge.absorb_nonnative(&elem_vars).unwrap();
+
+        assert_eq!(
+            constraint_sponge
+                .squeeze_field_elements(2)
+                .unwrap()
+                .value()
+                .unwrap(),
+            native_sponge.squeeze_native_field_elements(2)
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn absorb_nonnative_smaller_field() {
+        use ark_nonnative_field::NonNativeFieldVar;
+
+        // elements of a smaller field fit in one native element each
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::new_ref();
+        let sponge_params = poseidon_parameters_for_test();
+        let elems: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+
+        let mut native_sponge = PoseidonSponge::<Fq>::new(&sponge_params);
+        native_sponge.absorb_nonnative(&elems);
+
+        let elem_vars: Vec<NonNativeFieldVar<Fr, Fq>> = elems
+            .iter()
+            .map(|elem| NonNativeFieldVar::new_witness(cs.clone(), || Ok(*elem)).unwrap())
+            .collect();
+        let mut constraint_sponge = PoseidonSpongeVar::<Fq>::new(cs.clone(), &sponge_params);
+        constraint_sponge.absorb_nonnative(&elem_vars).unwrap();
+
+        assert_eq!(
+            constraint_sponge.state.value().unwrap(),
+            native_sponge.state
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+    #[test]
+    fn constant_inputs() {
+        let cs = ConstraintSystem::new_ref();
+        let sponge_params = poseidon_parameters_for_test();
+        let inputs: Vec<Fr> = (0..20u64).map(Fr::from).collect();
+
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        native_sponge.absorb(&inputs);
+        let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+        let input_vars: Vec<_> = inputs.iter().map(|input| FpVar::Constant(*input)).collect();
+        constraint_sponge.absorb(&input_vars).unwrap();
+        let squeezed = constraint_sponge.squeeze_field_elements(2).unwrap();
+        assert!(squeezed.iter().all(|elem| elem.is_constant()));
+        assert_eq!(
+            squeezed.value().unwrap(),
+            native_sponge.squeeze_native_field_elements(2)
+        );
+        assert_eq!(cs.num_constraints(), 0);
+        assert_eq!(cs.num_witness_variables(), 0);
+    }
+
+    #[test]
+    fn parameters_are_constants() {
+        let mut rng = test_rng();
+        let sponge_params = PoseidonParameters::<Fr>::generate(2, 1, 128);
+        let absorb: Vec<_> = (0..5).map(|_| Fr::rand(&mut rng)).collect();
+
+        let cs = ConstraintSystem::new_ref();
+        let mut sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+        assert_eq!(cs.num_witness_variables(), 0);
+        assert_eq!(cs.num_instance_variables(), 1);
+
+        let absorb_var: Vec<_> = absorb
+            .iter()
+            .map(|v| FpVar::new_witness(ns!(cs, "absorb"), || Ok(*v)).unwrap())
+            .collect();
+        sponge.absorb(&absorb_var).unwrap();
+        sponge.squeeze_field_elements(3).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+
+        // Absorbing 5 elements and squeezing 3 takes 4 permutations of 240 constraints, except
+        // that the first S-box on the constant capacity element is free. Each constraint
+        // allocates one S-box output, and nothing else is allocated.
+        let num_constraints = cs.num_constraints();
+        assert_eq!(num_constraints, 4 * 240 - 3);
+        assert_eq!(cs.num_witness_variables(), absorb.len() + num_constraints);
+        assert_eq!(cs.num_instance_variables(), 1);
+    }
+
+    #[test]
+    fn alternating_absorb_and_squeeze() {
+        let sponge_params = PoseidonParameters::<Fr>::generate(2, 1, 128);
+        let cs = ConstraintSystem::new_ref();
+        let mut sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+
+        for i in 0..3u64 {
+            let absorb = FpVar::new_witness(cs.clone(), || Ok(Fr::from(i))).unwrap();
+            sponge.absorb(&absorb).unwrap();
+            native_sponge.absorb(&Fr::from(i));
+            assert_eq!(
+                sponge.squeeze_field_elements(1).unwrap().value().unwrap(),
+                native_sponge.squeeze_native_field_elements(1)
+            );
+        }
+        assert!(cs.is_satisfied().unwrap());
+
+        // Only the squeezes permute, with at most 240 constraints each: absorbing right after a
+        // squeeze writes into the rate without a permutation of its own.
+        assert!(cs.num_constraints() <= 3 * 240);
+    }
+
+    #[test]
+    fn conditionally_select() {
+        let mut rng = test_rng();
+        let sponge_params = poseidon_parameters_for_test();
+        let inputs: Vec<_> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        let branches = [Fr::rand(&mut rng), Fr::rand(&mut rng)];
+
+        for choice in [true, false] {
+            let cs = ConstraintSystem::new_ref();
+            let cond = Boolean::new_witness(cs.clone(), || Ok(choice)).unwrap();
+            let mut sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+            let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+            sponge.absorb(&FpVar::constant(inputs[0])).unwrap();
+            native_sponge.absorb(&inputs[0]);
+
+            let mut true_sponge = sponge.clone();
+            let mut false_sponge = sponge.clone();
+            let branch_vars: Vec<_> = branches
+                .iter()
+                .map(|v| FpVar::new_witness(ns!(cs, "branch"), || Ok(*v)).unwrap())
+                .collect();
+            true_sponge.absorb(&branch_vars[0]).unwrap();
+            false_sponge.absorb(&branch_vars[1]).unwrap();
+            native_sponge.absorb(&branches[if choice { 0 } else { 1 }]);
+
+            let mut merged =
+                PoseidonSpongeVar::conditionally_select(&cond, &true_sponge, &false_sponge)
+                    .unwrap();
+            let suffix: Vec<_> = inputs[1..].iter().map(|v| FpVar::constant(*v)).collect();
+            merged.absorb(&suffix).unwrap();
+            native_sponge.absorb(&inputs[1..].to_vec());
+
+            assert_eq!(
+                merged.squeeze_field_elements(3).unwrap().value().unwrap(),
+                native_sponge.squeeze_native_field_elements(3)
+            );
+            assert!(cs.is_satisfied().unwrap());
+
+            // Both branches must absorb the same number of elements.
+            false_sponge.absorb(&branch_vars[1]).unwrap();
+            assert!(
+                PoseidonSpongeVar::conditionally_select(&cond, &true_sponge, &false_sponge)
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn squeeze_nonnative_direct() {
+        let sponge_params = poseidon_parameters_for_test();
+        let sizes = [
+            FieldElementSize::Full,
+            FieldElementSize::Truncated(128),
+            FieldElementSize::Full,
+        ];
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        native_sponge.absorb(&Fr::from(42u64));
+        let native: Vec<Fq> = native_sponge.squeeze_field_elements_with_sizes(&sizes);
+
+        let mut stats = Vec::new();
+        for direct in [true, false] {
+            let cs = ConstraintSystem::new_ref();
+            let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+            constraint_sponge
+                .absorb(&FpVar::new_witness(cs.clone(), || Ok(Fr::from(42u64))).unwrap())
+                .unwrap();
+            let num_constraints = cs.num_constraints();
+
+            let gadget = if direct {
+                constraint_sponge
+                    .squeeze_nonnative_field_elements_direct::<Fq>(&sizes)
+                    .unwrap()
+            } else {
+                constraint_sponge
+                    .squeeze_nonnative_field_elements_with_sizes::<Fq>(&sizes)
+                    .unwrap()
+                    .0
+            };
+            assert_eq!(gadget.value().unwrap(), native);
+            assert!(cs.is_satisfied().unwrap());
+            stats.push(cs.num_constraints() - num_constraints);
+        }
+        assert!(stats[0] < stats[1], "{:?}", stats);
+
+        // Constant states give constant elements.
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(
+            ark_relations::r1cs::ConstraintSystemRef::None,
+            &sponge_params,
+        );
+        let gadget = constraint_sponge
+            .squeeze_nonnative_field_elements_direct::<Fq>(&sizes)
+            .unwrap();
+        assert!(gadget.iter().all(|elem| elem.is_constant()));
+        assert_eq!(
+            gadget.value().unwrap(),
+            native_sponge.squeeze_field_elements_with_sizes::<Fq>(&sizes)
+        );
+    }
+
+    #[test]
+    fn squeeze_nonnative_with_weight_goal() {
+        use ark_nonnative_field::NonNativeFieldVar;
+        use ark_relations::r1cs::OptimizationGoal;
+
+        let sponge_params = poseidon_parameters_for_test();
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        native_sponge.absorb(&Fr::from(42u64));
+        let native: Vec<Fq> = native_sponge.squeeze_nonnative_field_elements(2);
+        let factor = Fq::from(3u64);
+
+        for goal in [OptimizationGoal::Constraints, OptimizationGoal::Weight] {
+            let cs = ConstraintSystem::new_ref();
+            cs.set_optimization_goal(goal);
+            let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+            constraint_sponge
+                .absorb(&FpVar::new_witness(cs.clone(), || Ok(Fr::from(42u64))).unwrap())
+                .unwrap();
+            let mut direct_sponge = constraint_sponge.clone();
+
+            let (gadget, _) = constraint_sponge
+                .squeeze_nonnative_field_elements::<Fq>(2)
+                .unwrap();
+            let direct = direct_sponge
+                .squeeze_nonnative_field_elements_direct::<Fq>(&[FieldElementSize::Full; 2])
+                .unwrap();
+            let factor_var =
+                NonNativeFieldVar::<Fq, Fr>::new_witness(cs.clone(), || Ok(factor)).unwrap();
+            for elems in [gadget, direct] {
+                for (elem, native) in elems.iter().zip(&native) {
+                    let product = elem * &factor_var;
+                    product
+                        .enforce_equal(
+                            &NonNativeFieldVar::new_witness(cs.clone(), || Ok(*native * factor))
+                                .unwrap(),
+                        )
+                        .unwrap();
+                }
+            }
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn squeeze_nonnative_cached_limbs() {
+        let cs = ConstraintSystem::new_ref();
+        let sponge_params = poseidon_parameters_for_test();
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+        native_sponge.absorb(&Fr::from(42u64));
+        constraint_sponge
+            .absorb(&FpVar::new_witness(cs.clone(), || Ok(Fr::from(42u64))).unwrap())
+            .unwrap();
+
+        // The cached limbs of the short elements are extended for the full ones.
+        for sizes in [
+            [FieldElementSize::Truncated(10); 2],
+            [FieldElementSize::Full; 2],
+            [FieldElementSize::Truncated(128); 2],
+        ] {
+            let native: Vec<Fq> = native_sponge.squeeze_field_elements_with_sizes(&sizes);
+            let (gadget, _) = constraint_sponge
+                .squeeze_nonnative_field_elements_with_sizes::<Fq>(&sizes)
+                .unwrap();
+            assert_eq!(gadget.value().unwrap(), native);
+        }
+        assert_eq!(cs.borrow().unwrap().cache_map.borrow().len(), 1);
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn squeeze_bits_cost() {
+        use ark_ff::PrimeField;
+
+        let sponge_params = poseidon_parameters_for_test();
+        let num_bits = Fr::size_in_bits();
+        let num_permutation_constraints = 255;
+
+        for num_elements in [1, 2] {
+            let cs = ConstraintSystem::new_ref();
+            let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+            let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+            native_sponge.absorb(&Fr::from(1u64));
+            constraint_sponge
+                .absorb(&FpVar::new_witness(cs.clone(), || Ok(Fr::from(1u64))).unwrap())
+                .unwrap();
+
+            // Every squeezed element is decomposed once, with a constraint for each of its
+            // bits and the bits of the two range checks, and 7 more constraints.
+            let bits = constraint_sponge.squeeze_bits(254 * num_elements).unwrap();
+            assert_eq!(
+                bits.value().unwrap(),
+                native_sponge.squeeze_bits(254 * num_elements)
+            );
+            assert_eq!(
+                cs.num_constraints(),
+                num_permutation_constraints + num_elements * (2 * num_bits + 7)
+            );
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn canonical_bits_at_the_bounds() {
+        use crate::constraints::to_canonical_bits_le;
+        use ark_ff::{BigInteger, PrimeField};
+
+        let half = Fr::size_in_bits() / 2;
+        let max = -Fr::one();
+        let mut max_high = max.into_repr();
+        max_high.divn(half as u32);
+        let max_high = Fr::from_repr(max_high).unwrap();
+        let two_to_half = Fr::from(2u64).pow([half as u64]);
+        for value in [
+            Fr::zero(),
+            Fr::one(),
+            max,
+            max - Fr::one(),
+            max_high * two_to_half,
+            max_high * two_to_half - Fr::one(),
+            two_to_half,
+            two_to_half - Fr::one(),
+        ] {
+            let cs = ConstraintSystem::new_ref();
+            let elem = FpVar::new_witness(cs.clone(), || Ok(value)).unwrap();
+            let bits = to_canonical_bits_le(&elem).unwrap();
+            assert_eq!(
+                bits.value().unwrap(),
+                value.into_repr().to_bits_le()[..Fr::size_in_bits()].to_vec()
+            );
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn squeeze_nonnative_constants() {
+        let cs = ConstraintSystem::new_ref();
+        let sponge_params = poseidon_parameters_for_test();
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+        native_sponge.absorb(&Fr::from(42u64));
+        constraint_sponge
+            .absorb(&FpVar::Constant(Fr::from(42u64)))
+            .unwrap();
+
+        let native: Vec<Fq> = native_sponge.squeeze_nonnative_field_elements(2);
+        let (gadget, _) = constraint_sponge
+            .squeeze_nonnative_field_elements::<Fq>(2)
+            .unwrap();
+        assert!(gadget.iter().all(|elem| elem.is_constant()));
+        assert_eq!(gadget.value().unwrap(), native);
+
+        let native: Vec<Fq> =
+            native_sponge.squeeze_field_elements_with_mode(2, SqueezeMode::Uniform);
+        let gadget = constraint_sponge
+            .squeeze_nonnative_field_elements_with_mode::<Fq>(2, SqueezeMode::Uniform)
+            .unwrap();
+        assert!(gadget.iter().all(|elem| elem.is_constant()));
+        assert_eq!(gadget.value().unwrap(), native);
+        assert_eq!(cs.num_constraints(), 0);
+        assert_eq!(cs.num_witness_variables(), 0);
+    }
+
+    #[test]
+    fn permute_state() {
+        use crate::duplex::FieldPermutation;
+        use crate::poseidon::PoseidonPermutation;
+
+        let mut rng = test_rng();
+        let sponge_params = poseidon_parameters_for_test();
+        let cs = ConstraintSystem::new_ref();
+        let constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+
+        let mut native_state = [Fr::rand(&mut rng), Fr::rand(&mut rng), Fr::rand(&mut rng)];
+        let mut state = native_state.map(|v| FpVar::new_witness(cs.clone(), || Ok(v)).unwrap());
+        constraint_sponge.permute_state(&mut state).unwrap();
+        PoseidonPermutation::new(&sponge_params).permute(&mut native_state);
+        assert_eq!(state.value().unwrap(), native_state);
+        assert!(cs.is_satisfied().unwrap());
+
+        let mut wrong_width = [FpVar::zero(), FpVar::zero()];
+        assert!(constraint_sponge.permute_state(&mut wrong_width).is_err());
+    }
+
+    #[test]
+    fn circom_poseidon_hash() {
+        use crate::poseidon::circom_poseidon_hash;
+        use crate::poseidon::constraints::circom_poseidon_hash_var;
+
+        let mut rng = test_rng();
+        for num_inputs in [1, 2, 5] {
+            let cs = ConstraintSystem::new_ref();
+            let parameters = PoseidonParameters::<Fr>::circom(num_inputs).unwrap();
+            let input: Vec<_> = (0..num_inputs).map(|_| Fr::rand(&mut rng)).collect();
+            let input_var: Vec<_> = input
+                .iter()
+                .map(|v| FpVar::new_witness(ns!(cs, "input"), || Ok(*v)).unwrap())
+                .collect();
+            assert_eq!(
+                circom_poseidon_hash_var(&parameters, &input_var)
+                    .unwrap()
+                    .value()
+                    .unwrap(),
+                circom_poseidon_hash(&parameters, &input)
+            );
+            assert!(cs.is_satisfied().unwrap());
+            assert!(circom_poseidon_hash_var(&parameters, &input_var[1..]).is_err());
+        }
+    }
+
+    #[test]
+    fn neptune_poseidon_hash() {
+        use crate::poseidon::constraints::neptune_poseidon_hash_var;
+        use crate::poseidon::{neptune_poseidon_hash, NeptuneHashType, NeptuneStrength};
+
+        let mut rng = test_rng();
+        let parameters = PoseidonParameters::<Fr>::neptune(4, NeptuneStrength::Standard).unwrap();
+        for (hash_type, length) in [
+            (NeptuneHashType::MerkleTree, 4),
+            (NeptuneHashType::ConstantLength(3), 3),
+            (NeptuneHashType::Custom(7), 2),
+        ] {
+            let cs = ConstraintSystem::new_ref();
+            let input: Vec<_> = (0..length).map(|_| Fr::rand(&mut rng)).collect();
+            let input_var: Vec<_> = input
+                .iter()
+                .map(|v| FpVar::new_witness(ns!(cs, "input"), || Ok(*v)).unwrap())
+                .collect();
+            assert_eq!(
+                neptune_poseidon_hash_var(&parameters, hash_type, &input_var)
+                    .unwrap()
+                    .value()
+                    .unwrap(),
+                neptune_poseidon_hash(&parameters, hash_type, &input)
+            );
+            assert!(cs.is_satisfied().unwrap());
+        }
+        let input_var = vec![FpVar::constant(Fr::one()); 3];
+        assert!(
+            neptune_poseidon_hash_var(&parameters, NeptuneHashType::MerkleTree, &input_var)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn domain_tags() {
+        use crate::poseidon::PoseidonDomainTag;
+
+        let mut rng = test_rng();
+        let input: Vec<_> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        for tag in [
+            PoseidonDomainTag::None,
+            PoseidonDomainTag::Arity,
+            PoseidonDomainTag::Label(b"merkle-node"),
+            PoseidonDomainTag::Element(Fr::from(0b1011u64)),
+        ] {
+            let params = poseidon_parameters_for_test::<Fr>().with_domain_tag(tag);
+            let mut native_sponge = PoseidonSponge::new(&params);
+            native_sponge.absorb(&input);
+            let native_squeeze = native_sponge.squeeze_field_elements::<Fr>(2);
+
+            let cs = ConstraintSystem::new_ref();
+            let input_var: Vec<_> = input
+                .iter()
+                .map(|v| FpVar::new_witness(ns!(cs, "input"), || Ok(*v)).unwrap())
+                .collect();
+            let mut constraint_sponge = PoseidonSpongeVar::new(cs.clone(), &params);
+            constraint_sponge.absorb(&input_var).unwrap();
+            let squeeze = constraint_sponge.squeeze_field_elements(2).unwrap();
+            assert_eq!(squeeze.value().unwrap(), native_squeeze);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn overwrite_mode() {
+        use crate::AbsorbMode;
+
+        let mut rng = test_rng();
+        let sponge_params = poseidon_parameters_for_test();
+
+        for padding in [PaddingMode::None, PaddingMode::LengthSuffix] {
+            for len in 0..6 {
+                let cs = ConstraintSystem::new_ref();
+                let absorb: Vec<_> = (0..len).map(|_| Fr::rand(&mut rng)).collect();
+                let absorb_var: Vec<_> = absorb
+                    .iter()
+                    .map(|v| FpVar::new_witness(ns!(cs, "absorb"), || Ok(*v)).unwrap())
+                    .collect();
+
+                let mut native_sponge = PoseidonSponge::<Fr>::with_padding(&sponge_params, padding)
+                    .with_absorb_mode(AbsorbMode::Overwrite);
+                let mut constraint_sponge =
+                    PoseidonSpongeVar::<Fr>::with_padding(cs.clone(), &sponge_params, padding)
+                        .with_absorb_mode(AbsorbMode::Overwrite);
+
+                native_sponge.absorb(&absorb);
+                constraint_sponge.absorb(&absorb_var).unwrap();
+                let mut squeeze1 = native_sponge.squeeze_native_field_elements(3);
+                let mut squeeze2 = constraint_sponge.squeeze_field_elements(3).unwrap();
+
+                native_sponge.absorb(&absorb);
+                constraint_sponge.absorb(&absorb_var).unwrap();
+                squeeze1.extend(native_sponge.squeeze_native_field_elements(1));
+                squeeze2.extend(constraint_sponge.squeeze_field_elements(1).unwrap());
+
+                assert_eq!(squeeze2.value().unwrap(), squeeze1);
+                assert!(cs.is_satisfied().unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn new_with_iv() {
+        let mut rng = test_rng();
+        let sponge_params = poseidon_parameters_for_test();
+        let cs = ConstraintSystem::new_ref();
+
+        let iv = Fr::rand(&mut rng);
+        let absorb = Fr::rand(&mut rng);
+        let iv_var = FpVar::new_witness(ns!(cs, "iv"), || Ok(iv)).unwrap();
+        let absorb_var = FpVar::new_witness(ns!(cs, "absorb"), || Ok(absorb)).unwrap();
+
+        let mut native_sponge = PoseidonSponge::<Fr>::new_with_iv(&sponge_params, &[iv]);
+        let mut constraint_sponge =
+            PoseidonSpongeVar::<Fr>::new_with_iv(cs.clone(), &sponge_params, &[iv_var]);
+        native_sponge.absorb(&absorb);
+        constraint_sponge.absorb(&absorb_var).unwrap();
+        let squeeze1 = native_sponge.squeeze_native_field_elements(2);
+        let squeeze2 = constraint_sponge.squeeze_field_elements(2).unwrap();
+
+        assert_eq!(squeeze2.value().unwrap(), squeeze1);
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn rate_capacity_and_security_level() {
+        let sponge_params = poseidon_parameters_for_test::<Fr>();
+        let cs = ConstraintSystem::new_ref();
+        let native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        let constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs, &sponge_params);
+        assert_eq!(constraint_sponge.rate(), native_sponge.rate());
+        assert_eq!(constraint_sponge.capacity(), native_sponge.capacity());
+        assert_eq!(
+            constraint_sponge.security_level(),
+            native_sponge.security_level()
+        );
+    }
+
+    #[test]
+    fn permute_and_finish_absorb() {
+        let mut rng = test_rng();
+        let sponge_params = poseidon_parameters_for_test();
+        let cs = ConstraintSystem::new_ref();
+
+        let absorb: Vec<_> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        let absorb_var: Vec<_> = absorb
+            .iter()
+            .map(|v| FpVar::new_witness(ns!(cs, "absorb"), || Ok(*v)).unwrap())
+            .collect();
+
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+        native_sponge.absorb(&absorb[0]);
+        constraint_sponge.absorb(&absorb_var[0]).unwrap();
+        native_sponge.permute();
+        constraint_sponge.permute().unwrap();
+        native_sponge.absorb(&absorb[1..].to_vec());
+        constraint_sponge.absorb(&absorb_var[1..].to_vec()).unwrap();
+        native_sponge.finish_absorb();
+        constraint_sponge.finish_absorb().unwrap();
+        let squeeze1 = native_sponge.squeeze_native_field_elements(3);
+        let squeeze2 = constraint_sponge.squeeze_field_elements(3).unwrap();
+
+        assert_eq!(squeeze2.value().unwrap(), squeeze1);
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn absorb_with_length() {
+        let sponge_params = poseidon_parameters_for_test();
+        let cs = ConstraintSystem::new_ref();
+
+        let inputs = [vec![Fr::from(1u8), Fr::from(2u8)], vec![Fr::from(3u8)]];
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+        for input in &inputs {
+            let input_var: Vec<_> = input
+                .iter()
+                .map(|v| FpVar::new_witness(ns!(cs, "absorb"), || Ok(*v)).unwrap())
+                .collect();
+            native_sponge.absorb_with_length(input);
+            constraint_sponge.absorb_with_length(&input_var).unwrap();
+        }
+        let squeeze1 = native_sponge.squeeze_native_field_elements(1);
+        let squeeze2 = constraint_sponge.squeeze_field_elements(1).unwrap();
+        assert_eq!(squeeze2.value().unwrap(), squeeze1);
+        assert!(cs.is_satisfied().unwrap());
+
+        // The same elements split differently give a different output.
+        let mut shifted_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        shifted_sponge.absorb_with_length(&inputs[0][..1].to_vec());
+        shifted_sponge.absorb_with_length(&vec![inputs[0][1], inputs[1][0]]);
+        assert_ne!(shifted_sponge.squeeze_native_field_elements(1), squeeze1);
+    }
+
+    #[test]
+    fn poseidon_family() {
+        use crate::poseidon::constraints::PoseidonFamilyVar;
+        use crate::poseidon::PoseidonFamily;
+        use crate::SecurityLevel;
+
+        let family = PoseidonFamily::<Fr>::with_arities(&[2, 4], SecurityLevel::Bits128);
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let family_var = PoseidonFamilyVar::new(cs.clone(), &family);
+        for arity in [2u64, 4] {
+            let input: Vec<Fr> = (0..arity).map(Fr::from).collect();
+            let input_var: Vec<_> = input
+                .iter()
+                .map(|x| FpVar::new_witness(cs.clone(), || Ok(*x)).unwrap())
+                .collect();
+            assert_eq!(
+                family_var.compress_n(&input_var).unwrap().value().unwrap(),
+                family.compress_n(&input).unwrap()
+            );
+        }
+        assert!(family_var.compress_n(&vec![FpVar::zero(); 3]).is_err());
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn absorb_u64s_small_field() {
+        use crate::poseidon::tests::small_field::TestFp64;
+        use crate::SecurityLevel;
+        use ark_r1cs_std::bits::uint64::UInt64;
+        use ark_std::rand::Rng;
+
+        let mut rng = test_rng();
+        let params = PoseidonParameters::<TestFp64>::for_small_field(SecurityLevel::Bits128);
+        let words: Vec<u64> = (0..20).map(|_| rng.gen()).collect();
+        let mut sponge = PoseidonSponge::new(&params);
+        sponge.absorb_u64s(&words);
+
+        let cs = ConstraintSystem::<TestFp64>::new_ref();
+        let words_var: Vec<_> = words
+            .iter()
+            .map(|word| UInt64::new_witness(cs.clone(), || Ok(*word)).unwrap())
+            .collect();
+        let mut sponge_var = PoseidonSpongeVar::new(cs.clone(), &params);
+        sponge_var.absorb_u64s(&words_var).unwrap();
+        assert_eq!(
+            sponge_var
+                .squeeze_field_elements(3)
+                .unwrap()
+                .value()
+                .unwrap(),
+            sponge.squeeze_native_field_elements(3)
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn absorb_str() {
+        let params = poseidon_parameters_for_test::<Fr>();
+        let mut sponge = PoseidonSponge::new(&params);
+        sponge.absorb(&"transcript");
+        sponge.absorb(&"round 1");
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let mut sponge_var = PoseidonSpongeVar::new(cs.clone(), &params);
+        sponge_var.absorb(&"transcript").unwrap();
+        sponge_var.absorb(&"round 1").unwrap();
+        assert_eq!(
+            sponge_var
+                .squeeze_field_elements(2)
+                .unwrap()
+                .value()
+                .unwrap(),
+            sponge.squeeze_native_field_elements(2)
+        );
+        assert_eq!(cs.num_constraints(), 0);
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn absorb_public_inputs_test() {
+        let sponge_params = poseidon_parameters_for_test();
+        let mut rng = test_rng();
+        let inputs: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        native_sponge.absorb_public_inputs(&inputs);
+        let expected = native_sponge.squeeze_native_field_elements(1);
+
+        let mut framed_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        framed_sponge.absorb(&inputs[..2].to_vec());
+        framed_sponge.absorb(&inputs[2]);
+        assert_ne!(framed_sponge.squeeze_native_field_elements(1), expected);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let input_vars: Vec<_> = inputs
+            .iter()
+            .map(|input| FpVar::new_input(cs.clone(), || Ok(*input)).unwrap())
+            .collect();
+        // witnesses are not public inputs
+        let _witness = FpVar::new_witness(cs.clone(), || Ok(Fr::one())).unwrap();
+
+        let mut explicit_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+        explicit_sponge.absorb_public_inputs(&input_vars).unwrap();
+        let mut all_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+        all_sponge.absorb_all_public_inputs().unwrap();
+        assert_eq!(
+            explicit_sponge
+                .squeeze_field_elements(1)
+                .unwrap()
+                .value()
+                .unwrap(),
+            expected
+        );
+        assert_eq!(
+            all_sponge
+                .squeeze_field_elements(1)
+                .unwrap()
+                .value()
+                .unwrap(),
+            expected
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn parameters_var() {
+        use crate::poseidon::constraints::PoseidonParametersVar;
+
+        let mut rng = test_rng();
+        let input: Vec<Fr> = (0..5).map(|_| Fr::rand(&mut rng)).collect();
+        let parameter_sets = [
+            PoseidonParameters::<Fr>::derive(b"first", 2, 1, 128),
+            PoseidonParameters::<Fr>::derive(b"second", 2, 1, 128),
+        ];
+
+        let mut outputs = Vec::new();
+        let mut num_constraints = Vec::new();
+        for (parameters, mode) in parameter_sets
+            .iter()
+            .zip([AllocationMode::Witness, AllocationMode::Input])
+        {
+            let mut sponge = PoseidonSponge::new(parameters);
+            sponge.absorb(&input);
+            let expected = sponge.squeeze_native_field_elements(2);
+
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let parameters_var =
+                PoseidonParametersVar::new_variable(cs.clone(), || Ok(parameters), mode).unwrap();
+            let input_var =
+                Vec::<FpVar<Fr>>::new_witness(cs.clone(), || Ok(input.clone())).unwrap();
+            let mut sponge_var =
+                PoseidonSpongeVar::with_parameters_var(cs.clone(), &parameters_var);
+            sponge_var.absorb(&input_var).unwrap();
+            let output = sponge_var
+                .squeeze_field_elements(2)
+                .unwrap()
+                .value()
+                .unwrap();
+            assert_eq!(output, expected);
+            assert!(cs.is_satisfied().unwrap());
+
+            outputs.push(output);
+            num_constraints.push(cs.num_constraints());
+        }
+        // the same circuit computes the permutations of both parameter sets
+        assert_ne!(outputs[0], outputs[1]);
+        assert_eq!(num_constraints[0], num_constraints[1]);
+    }
+
+    #[test]
+    fn hash_path_test() {
+        let sponge_params = poseidon_parameters_for_test();
+        let native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        let mut rng = test_rng();
+        let depth = 4;
+        let paths: Vec<(Fr, Vec<(bool, Fr)>)> = (0..3)
+            .map(|_| {
+                let leaf = Fr::rand(&mut rng);
+                let path = (0..depth)
+                    .map(|_| (bool::rand(&mut rng), Fr::rand(&mut rng)))
+                    .collect();
+                (leaf, path)
+            })
+            .collect();
+        let (leaf, path) = &paths[0];
+        let root = native_sponge.hash_path(*leaf, path).unwrap();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let sponge = PoseidonSpongeVar::new(cs.clone(), &sponge_params);
+        let path_vars: Vec<_> = paths
+            .iter()
+            .map(|(leaf, path)| {
+                let leaf = FpVar::new_witness(cs.clone(), || Ok(*leaf)).unwrap();
+                let path = path
+                    .iter()
+                    .map(|(is_right, sibling)| {
+                        (
+                            Boolean::new_witness(cs.clone(), || Ok(*is_right)).unwrap(),
+                            FpVar::new_witness(cs.clone(), || Ok(*sibling)).unwrap(),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                (leaf, path)
+            })
+            .collect();
+        let root_var = FpVar::new_input(cs.clone(), || Ok(root)).unwrap();
+
+        let compress_cs = ConstraintSystem::<Fr>::new_ref();
+        let input =
+            Vec::new_witness(compress_cs.clone(), || Ok(vec![Fr::one(), Fr::one()])).unwrap();
+        let _output = PoseidonSpongeVar::new(compress_cs.clone(), &sponge_params)
+            .compress(&input)
+            .unwrap();
+        let compress_constraints = compress_cs.num_constraints();
+
+        let num_constraints = cs.num_constraints();
+        let (leaf_var, path_var) = &path_vars[0];
+        let root = sponge.hash_path(leaf_var, path_var).unwrap();
+        assert_eq!(root.value().unwrap(), root_var.value().unwrap());
+        assert_eq!(
+            cs.num_constraints() - num_constraints,
+            depth * (compress_constraints + 1)
+        );
+
+        assert!(sponge
+            .verify_path(leaf_var, path_var, &root_var)
+            .unwrap()
+            .value()
+            .unwrap());
+        assert!(sponge
+            .verify_paths(&path_vars[..1], &root_var)
+            .unwrap()
+            .value()
+            .unwrap());
+        assert!(!sponge
+            .verify_paths(&path_vars, &root_var)
+            .unwrap()
+            .value()
+            .unwrap());
+        assert!(sponge
+            .verify_paths(&[], &root_var)
+            .unwrap()
+            .value()
+            .unwrap());
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn nonnative_sponge() {
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+
+        // Few rounds keep the nonnative permutations affordable.
+        let test_parameters = poseidon_parameters_for_test::<Fq>();
+        let parameters = PoseidonParameters::new(
+            2,
+            1,
+            17,
+            test_parameters.mds,
+            test_parameters.ark[..3].to_vec(),
+            2,
+            1,
+        );
+        let mut native_sponge = PoseidonSponge::<Fq>::new(&parameters);
+        let mut sponge = NonNativePoseidonSpongeVar::<Fq, Fr>::new(cs.clone(), &parameters);
+
+        let absorb1: Vec<_> = (0..3).map(|_| Fq::rand(&mut rng)).collect();
+        let absorb1_var: Vec<_> = absorb1
+            .iter()
+            .map(|v| NonNativeFieldVar::new_witness(ns!(cs, "absorb1"), || Ok(*v)).unwrap())
+            .collect();
+        native_sponge.absorb(&absorb1);
+        sponge.absorb(&absorb1_var).unwrap();
+        let squeeze1 = native_sponge.squeeze_native_field_elements(3);
+        let squeeze1_var = sponge.squeeze_field_elements(3).unwrap();
+        assert_eq!(squeeze1_var.value().unwrap(), squeeze1);
+
+        let absorb2 = Fq::rand(&mut rng);
+        let absorb2_var =
+            NonNativeFieldVar::new_witness(ns!(cs, "absorb2"), || Ok(absorb2)).unwrap();
+        native_sponge.absorb(&absorb2);
+        sponge.absorb(&[absorb2_var]).unwrap();
+        let squeeze2 = native_sponge.squeeze_native_field_elements(1);
+        let squeeze2_var = sponge.squeeze_field_elements(1).unwrap();
+        assert_eq!(squeeze2_var.value().unwrap(), squeeze2);
+
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[cfg(all(feature = "trace", feature = "std"))]
+    #[test]
+    fn trace_matches_native() {
+        use ark_std::fmt::{Debug, Write};
+        use ark_std::string::String;
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field as TraceField, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+
+        // Records the fields of the events of the sponges, one line per event.
+        struct Recorder(Arc<Mutex<Vec<String>>>);
+        struct Line(String);
+
+        impl Visit for Line {
+            fn record_debug(&mut self, field: &TraceField, value: &dyn Debug) {
+                write!(self.0, "{}={:?} ", field.name(), value).unwrap();
+            }
+        }
+
+        impl Subscriber for Recorder {
+            fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, _span: &Attributes<'_>) -> Id {
+                Id::from_u64(1)
+            }
+            fn record(&self, _span: &Id, _values: &Record<'_>) {}
+            fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+            fn event(&self, event: &Event<'_>) {
+                if event.metadata().target() == "ark_sponge" {
+                    let mut line = Line(String::new());
+                    event.record(&mut line);
+                    self.0.lock().unwrap().push(line.0);
+                }
+            }
+            fn enter(&self, _span: &Id) {}
+            fn exit(&self, _span: &Id) {}
+        }
+
+        fn trace(f: impl FnOnce()) -> Vec<String> {
+            let lines = Arc::new(Mutex::new(Vec::new()));
+            tracing::subscriber::with_default(Recorder(lines.clone()), f);
+            let lines = lines.lock().unwrap().clone();
+            lines
+        }
+
+        let mut rng = test_rng();
+        let parameters = poseidon_parameters_for_test::<Fr>();
+        let absorb1: Vec<_> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        let absorb2 = Fr::rand(&mut rng);
+
+        let native_trace = trace(|| {
+            let mut sponge = PoseidonSponge::<Fr>::new(&parameters);
+            sponge.absorb(&absorb1);
+            let _challenge: Vec<Fr> = sponge.squeeze_field_elements(1);
+            sponge.absorb(&absorb2);
+            let _challenges: Vec<Fr> = sponge.squeeze_field_elements(3);
+        });
+        let gadget_trace = trace(|| {
+            let cs = ConstraintSystem::new_ref();
+            let mut sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &parameters);
+            let absorb1_var: Vec<_> = absorb1
+                .iter()
+                .map(|v| FpVar::new_witness(ns!(cs, "absorb1"), || Ok(*v)).unwrap())
+                .collect();
+            sponge.absorb(&absorb1_var).unwrap();
+            let _challenge = sponge.squeeze_field_elements(1).unwrap();
+            let absorb2_var = FpVar::new_witness(ns!(cs, "absorb2"), || Ok(absorb2)).unwrap();
+            sponge.absorb(&absorb2_var).unwrap();
+            let _challenges = sponge.squeeze_field_elements(3).unwrap();
+        });
+
+        let count = |message: &str| {
+            native_trace
+                .iter()
+                .filter(|line| line.starts_with(&ark_std::format!("message={} ", message)))
+                .count()
+        };
+        assert_eq!(count("absorb"), 2);
+        assert_eq!(count("squeeze"), 2);
+        assert_eq!(count("permute"), 4);
+        assert_eq!(native_trace, gadget_trace);
+    }
 }
@@ -0,0 +1,154 @@
+//! Default Poseidon parameters for the fields of commonly used curves.
+//!
+//! Each set of parameters is enabled by the feature of the same name and targets 128 bits of
+//! security. The round numbers are those of the
+//! [reference implementation](https://extgit.iaik.tugraz.at/krypto/hadeshash/-/blob/master/code/calc_round_numbers.py)
+//! (see [`find_poseidon_round_numbers`](crate::poseidon::find_poseidon_round_numbers)), and
+//! the round constants and MDS matrices are derived from them with the Grain LFSR the first time
+//! [`PoseidonDefaultParametersField::get_default_poseidon_parameters`] is called. With the `std`
+//! feature, the derived parameters are cached, and later calls return a copy of them.
+//!
+//! [`PoseidonDefaultParametersField::get_default_poseidon_parameters`]: crate::poseidon::PoseidonDefaultParametersField::get_default_poseidon_parameters
+
+use crate::poseidon::{PoseidonDefaultParameters, PoseidonDefaultParametersEntry};
+
+/// Parameters optimized for constraints. These are shared by all the fields below, which are
+/// all of 252 bits or more, and for which `x^5` and `x^17` are permutations.
+const PARAMS_OPT_FOR_CONSTRAINTS: [PoseidonDefaultParametersEntry; 7] = [
+    PoseidonDefaultParametersEntry::new(2, 17, 8, 31, 0),
+    PoseidonDefaultParametersEntry::new(3, 5, 8, 56, 0),
+    PoseidonDefaultParametersEntry::new(4, 5, 8, 56, 0),
+    PoseidonDefaultParametersEntry::new(5, 5, 8, 57, 0),
+    PoseidonDefaultParametersEntry::new(6, 5, 8, 57, 0),
+    PoseidonDefaultParametersEntry::new(7, 5, 8, 57, 0),
+    PoseidonDefaultParametersEntry::new(8, 5, 8, 57, 0),
+];
+
+/// Parameters optimized for weights, shared in the same way.
+const PARAMS_OPT_FOR_WEIGHTS: [PoseidonDefaultParametersEntry; 7] = [
+    PoseidonDefaultParametersEntry::new(2, 257, 8, 13, 0),
+    PoseidonDefaultParametersEntry::new(3, 257, 8, 13, 0),
+    PoseidonDefaultParametersEntry::new(4, 257, 8, 13, 0),
+    PoseidonDefaultParametersEntry::new(5, 257, 8, 13, 0),
+    PoseidonDefaultParametersEntry::new(6, 257, 8, 13, 0),
+    PoseidonDefaultParametersEntry::new(7, 257, 8, 13, 0),
+    PoseidonDefaultParametersEntry::new(8, 257, 8, 13, 0),
+];
+
+macro_rules! impl_poseidon_default_parameters {
+    ($params: path) => {
+        impl PoseidonDefaultParameters for $params {
+            const PARAMS_OPT_FOR_CONSTRAINTS: [PoseidonDefaultParametersEntry; 7] =
+                PARAMS_OPT_FOR_CONSTRAINTS;
+            const PARAMS_OPT_FOR_WEIGHTS: [PoseidonDefaultParametersEntry; 7] =
+                PARAMS_OPT_FOR_WEIGHTS;
+        }
+    };
+}
+
+#[cfg(feature = "bls12_381")]
+impl_poseidon_default_parameters!(ark_bls12_381::FrParameters);
+#[cfg(feature = "bls12_381")]
+impl_poseidon_default_parameters!(ark_bls12_381::FqParameters);
+
+#[cfg(feature = "bn254")]
+impl_poseidon_default_parameters!(ark_bn254::FrParameters);
+#[cfg(feature = "bn254")]
+impl_poseidon_default_parameters!(ark_bn254::FqParameters);
+
+// The base field of ed-on-bls12-381 is the scalar field of BLS12-381, which is covered by the
+// `bls12_381` feature.
+#[cfg(feature = "ed_on_bls12_381")]
+impl_poseidon_default_parameters!(ark_ed_on_bls12_381::FrParameters);
+
+// The scalar and base fields of Vesta are the base and scalar fields of Pallas, respectively.
+#[cfg(feature = "pallas")]
+impl_poseidon_default_parameters!(ark_pallas::FrParameters);
+#[cfg(feature = "pallas")]
+impl_poseidon_default_parameters!(ark_pallas::FqParameters);
+
+#[cfg(test)]
+mod test {
+    use super::{PARAMS_OPT_FOR_CONSTRAINTS, PARAMS_OPT_FOR_WEIGHTS};
+    use crate::poseidon::{find_poseidon_round_numbers, PoseidonParameters, PoseidonSponge};
+    use crate::poseidon::{PoseidonDefaultParameters, PoseidonDefaultParametersField};
+    use crate::{Absorb, CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_ff::{BigInteger, FpParameters, PrimeField};
+    use ark_std::vec;
+
+    /// Checks that the shared entries are secure and valid for `F`.
+    fn check_entries<F: PrimeField + Absorb + PoseidonDefaultParametersField>()
+    where
+        F::Params: PoseidonDefaultParameters,
+    {
+        let mut modulus_minus_one = F::Params::MODULUS;
+        modulus_minus_one.sub_noborrow(&F::BigInt::from(1));
+        let is_permutation = |alpha: u64| {
+            // `x^alpha` is a permutation iff `alpha` is coprime with `p - 1`; all the exponents
+            // in use are prime, so it suffices that `alpha` does not divide `p - 1`.
+            let remainder = modulus_minus_one
+                .as_ref()
+                .iter()
+                .rev()
+                .fold(0u128, |acc, limb| {
+                    ((acc << 64) | *limb as u128) % alpha as u128
+                });
+            remainder != 0
+        };
+
+        for (entries, optimized_for_weights) in [
+            (&PARAMS_OPT_FOR_CONSTRAINTS, false),
+            (&PARAMS_OPT_FOR_WEIGHTS, true),
+        ] {
+            for entry in entries.iter() {
+                let alpha = entry.alpha as u64;
+                assert!(is_permutation(alpha));
+                assert_eq!(
+                    find_poseidon_round_numbers::<F>(
+                        entry.rate + 1,
+                        alpha,
+                        128,
+                        optimized_for_weights
+                    ),
+                    (entry.full_rounds, entry.partial_rounds)
+                );
+            }
+        }
+
+        let parameters: PoseidonParameters<F> = PoseidonParameters::default_for(3, false).unwrap();
+        let mut sponge = PoseidonSponge::new(&parameters);
+        sponge.absorb(&vec![F::from(1u8), F::from(2u8)]);
+        let first = sponge.squeeze_native_field_elements(2);
+        let mut sponge = PoseidonSponge::new(&parameters);
+        sponge.absorb(&vec![F::from(1u8), F::from(2u8)]);
+        assert_eq!(first, sponge.squeeze_native_field_elements(2));
+    }
+
+    #[cfg(feature = "bls12_381")]
+    #[test]
+    fn bls12_381() {
+        check_entries::<ark_bls12_381::Fr>();
+        check_entries::<ark_bls12_381::Fq>();
+    }
+
+    #[cfg(feature = "bn254")]
+    #[test]
+    fn bn254() {
+        check_entries::<ark_bn254::Fr>();
+        check_entries::<ark_bn254::Fq>();
+    }
+
+    #[cfg(feature = "ed_on_bls12_381")]
+    #[test]
+    fn ed_on_bls12_381() {
+        check_entries::<ark_ed_on_bls12_381::Fr>();
+        check_entries::<ark_ed_on_bls12_381::Fq>();
+    }
+
+    #[cfg(feature = "pallas")]
+    #[test]
+    fn pallas_and_vesta() {
+        check_entries::<ark_pallas::Fr>();
+        check_entries::<ark_pallas::Fq>();
+    }
+}
@@ -0,0 +1,81 @@
+use crate::poseidon::{circom_poseidon_hash, PoseidonParameters};
+use crate::SpongeError;
+use ark_ff::{BigInteger, FpParameters, PrimeField};
+use ark_std::vec::Vec;
+
+/// The number of bytes of an EVM word, which holds a Solidity `uint256`.
+pub const EVM_WORD_SIZE: usize = 32;
+
+/// Encodes `elem` as an EVM word: its canonical representative as a big-endian `uint256`, as
+/// the ABI encodes it and as Solidity verifiers return it.
+///
+/// # Panics
+///
+/// Panics if the modulus of `F` has more than 256 bits.
+pub fn field_element_to_evm_word<F: PrimeField>(elem: &F) -> [u8; EVM_WORD_SIZE] {
+    assert!(
+        F::Params::MODULUS_BITS as usize <= 8 * EVM_WORD_SIZE,
+        "the field elements must fit in an EVM word"
+    );
+    let bytes = elem.into_repr().to_bytes_be();
+    // The bytes beyond the word are zero, as the modulus fits in it.
+    let significant = &bytes[bytes.len().saturating_sub(EVM_WORD_SIZE)..];
+    let mut word = [0u8; EVM_WORD_SIZE];
+    word[EVM_WORD_SIZE - significant.len()..].copy_from_slice(significant);
+    word
+}
+
+/// Decodes an EVM word, a big-endian `uint256`, into a field element.
+///
+/// Returns [`SpongeError::NonCanonicalFieldElement`] if the word is not smaller than the modulus
+/// of `F`. The Solidity implementations of Poseidon silently reduce such words, so that two words
+/// hash to the same value; reduce them with [`PrimeField::from_be_bytes_mod_order`] to reproduce
+/// this behavior.
+///
+/// # Panics
+///
+/// Panics if the modulus of `F` has more than 256 bits.
+pub fn field_element_from_evm_word<F: PrimeField>(
+    word: &[u8; EVM_WORD_SIZE],
+) -> Result<F, SpongeError> {
+    let elem = F::from_be_bytes_mod_order(word);
+    if field_element_to_evm_word(&elem) != *word {
+        return Err(SpongeError::NonCanonicalFieldElement);
+    }
+    Ok(elem)
+}
+
+/// Hashes EVM words as the Poseidon contracts of
+/// [poseidon-solidity](https://github.com/chancehudson/poseidon-solidity) and circomlibjs,
+/// e.g. `PoseidonT3.hash([a, b])` for two words: the words are decoded as big-endian `uint256`s,
+/// hashed with [`circom_poseidon_hash`], and the hash is encoded as a word.
+///
+/// Over the scalar field of BN254 with the parameters [`PoseidonParameters::circom`] for
+/// `input.len()` inputs, the hash is the `uint256` returned by these contracts.
+///
+/// Returns [`SpongeError::InvalidInputLength`] if the rate of `parameters` is not `input.len()`,
+/// and [`SpongeError::NonCanonicalFieldElement`] if a word is not smaller than the modulus,
+/// as [`field_element_from_evm_word`].
+///
+/// # Panics
+///
+/// Panics if the capacity of `parameters` is not 1, or if the modulus of `F` has more than 256
+/// bits.
+pub fn evm_poseidon_hash<F: PrimeField>(
+    parameters: &PoseidonParameters<F>,
+    input: &[[u8; EVM_WORD_SIZE]],
+) -> Result<[u8; EVM_WORD_SIZE], SpongeError> {
+    if parameters.rate != input.len() {
+        return Err(SpongeError::InvalidInputLength {
+            expected: parameters.rate,
+            found: input.len(),
+        });
+    }
+    let input = input
+        .iter()
+        .map(field_element_from_evm_word)
+        .collect::<Result<Vec<F>, _>>()?;
+    Ok(field_element_to_evm_word(&circom_poseidon_hash(
+        parameters, &input,
+    )))
+}
@@ -0,0 +1,84 @@
+use crate::poseidon::{PoseidonParameters, PoseidonSponge};
+use crate::{CryptographicSponge, SecurityLevel, SpongeError};
+use ark_ff::PrimeField;
+use ark_std::vec::Vec;
+
+/// The arities of the instances of [`PoseidonFamily::new`].
+pub const POSEIDON_FAMILY_ARITIES: [usize; 3] = [2, 4, 8];
+
+/// A family of Poseidon instances of several arities over the same field and for the same
+/// security level, e.g. for the nodes of Merkle trees of different widths.
+///
+/// The instance of arity `n` has parameters [`PoseidonParameters::for_security_level`] with a
+/// rate of `n`, so all of them have the capacity, S-box and round-number rules of the security
+/// level, and round constants and MDS matrices sampled with the Grain LFSR for their own width.
+/// Instances of different arities are therefore independent.
+#[derive(Clone)]
+pub struct PoseidonFamily<F: PrimeField> {
+    /// The instances, sorted by arity.
+    instances: Vec<PoseidonSponge<F>>,
+}
+
+impl<F: PrimeField> PoseidonFamily<F> {
+    /// Generates the instances of arities [`POSEIDON_FAMILY_ARITIES`] for `security_level`.
+    pub fn new(security_level: SecurityLevel) -> Self {
+        Self::with_arities(&POSEIDON_FAMILY_ARITIES, security_level)
+    }
+
+    /// Generates the instances of the given arities for `security_level`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an arity is zero.
+    pub fn with_arities(arities: &[usize], security_level: SecurityLevel) -> Self {
+        let mut arities = arities.to_vec();
+        arities.sort_unstable();
+        arities.dedup();
+        assert!(
+            arities.first() != Some(&0),
+            "the arities of a Poseidon family must be positive"
+        );
+        let instances = arities
+            .into_iter()
+            .map(|arity| {
+                PoseidonSponge::new(&PoseidonParameters::for_security_level(
+                    arity,
+                    security_level,
+                ))
+            })
+            .collect();
+        Self { instances }
+    }
+
+    /// Returns the arities of the instances, in increasing order.
+    pub fn arities(&self) -> Vec<usize> {
+        self.instances
+            .iter()
+            .map(|instance| instance.parameters.rate)
+            .collect()
+    }
+
+    /// Returns the parameters of the instance of arity `arity`, if the family has one.
+    pub fn parameters(&self, arity: usize) -> Option<&PoseidonParameters<F>> {
+        self.instance(arity).map(|instance| &*instance.parameters)
+    }
+
+    pub(crate) fn instance(&self, arity: usize) -> Option<&PoseidonSponge<F>> {
+        self.instances
+            .binary_search_by_key(&arity, |instance| instance.parameters.rate)
+            .ok()
+            .map(|index| &self.instances[index])
+    }
+
+    /// Compresses `input` into one element with the instance whose arity is `input.len()`, as
+    /// [`GenericPoseidonSponge::compress`](crate::poseidon::GenericPoseidonSponge::compress).
+    ///
+    /// Returns [`SpongeError::InvalidParameters`] if the family has no instance of this arity.
+    pub fn compress_n(&self, input: &[F]) -> Result<F, SpongeError> {
+        self.instance(input.len())
+            .ok_or(SpongeError::InvalidParameters(
+                "the family has no instance of the arity of the input",
+            ))?
+            .compress(input)
+    }
+}
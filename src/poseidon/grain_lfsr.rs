@@ -2,6 +2,8 @@
 
 use ark_ff::{BigInteger, FpParameters, PrimeField};
 use ark_std::vec::Vec;
+use blake2::digest::{FixedOutput, Update};
+use blake2::Blake2b;
 
 pub struct PoseidonGrainLFSR {
     pub prime_num_bits: u64,
@@ -25,11 +27,7 @@ impl PoseidonGrainLFSR {
         state[1] = true;
 
         // b2, ..., b5 describes the S-BOX
-        if is_sbox_an_inverse {
-            state[5] = true;
-        } else {
-            state[5] = false;
-        }
+        state[5] = is_sbox_an_inverse;
 
         // b6, ..., b17 are the binary representation of n (prime_num_bits)
         {
@@ -68,8 +66,8 @@ impl PoseidonGrainLFSR {
         }
 
         // b50, ..., b79 are set to 1
-        for i in 50..=79 {
-            state[i] = true;
+        for bit in state.iter_mut().skip(50) {
+            *bit = true;
         }
 
         let head = 0;
@@ -83,6 +81,18 @@ impl PoseidonGrainLFSR {
         res
     }
 
+    /// Mixes a seed into the state: the first 80 bits of `BLAKE2b(seed)` are XORed into the
+    /// state, and the next 160 bits are discarded, as after the initialization.
+    pub fn mix_seed(&mut self, seed: &[u8]) {
+        let mut hasher = Blake2b::default();
+        hasher.update(seed);
+        let digest = hasher.finalize_fixed();
+        for i in 0..80 {
+            self.state[(self.head + i) % 80] ^= (digest[i / 8] >> (i % 8)) & 1 == 1;
+        }
+        self.init();
+    }
+
     pub fn get_bits(&mut self, num_bits: usize) -> Vec<bool> {
         let mut res = Vec::new();
 
@@ -91,7 +101,7 @@ impl PoseidonGrainLFSR {
             let mut new_bit = self.update();
 
             // Loop until the first bit is true
-            while new_bit == false {
+            while !new_bit {
                 // Discard the second bit
                 let _ = self.update();
                 // Obtain another first bit
@@ -0,0 +1,254 @@
+use crate::SpongeError;
+use ark_ff::{BitIteratorBE, PrimeField};
+use ark_std::rand::Rng;
+use ark_std::vec;
+use ark_std::vec::Vec;
+
+/// Checks that `mds` is a secure linear layer for a Poseidon permutation: it must be square and
+/// invertible, and the minimal polynomials of its powers `mds^i` for `1 <= i <= 2t`, where `t`
+/// is its size, must be irreducible of degree `t`.
+///
+/// The latter is the sufficient condition of Grassi, Rechberger and Schofnegger
+/// ([ePrint 2020/500](https://eprint.iacr.org/2020/500)) under which the partial rounds have no
+/// invariant subspace trail, nor any infinitely long subspace trail, as checked by the parameter
+/// scripts of Poseidon2. It is stronger than the three algorithms of the reference
+/// implementation of Poseidon, so some matrices passing those are rejected.
+///
+/// Returns [`SpongeError::InvalidParameters`] if a check fails.
+pub fn check_mds_security<F: PrimeField>(mds: &[Vec<F>]) -> Result<(), SpongeError> {
+    let t = mds.len();
+    if t == 0 || mds.iter().any(|row| row.len() != t) {
+        return Err(SpongeError::InvalidParameters(
+            "the MDS matrix must be square and nonempty",
+        ));
+    }
+    if determinant(mds).is_zero() {
+        return Err(SpongeError::InvalidParameters(
+            "the MDS matrix is not invertible",
+        ));
+    }
+    let mut power = mds.to_vec();
+    for _ in 0..2 * t {
+        if !is_irreducible(&characteristic_polynomial(&power)) {
+            return Err(SpongeError::InvalidParameters(
+                "a power of the MDS matrix has a reducible minimal polynomial",
+            ));
+        }
+        power = matrix_product(mds, &power);
+    }
+    Ok(())
+}
+
+/// Samples Cauchy matrices `1 / (x_i + y_j)` of size `state_size` with `rng`, until one passes
+/// [`check_mds_security`]. Cauchy matrices are always MDS, and a random one passes the checks
+/// after about `state_size` attempts on average.
+pub fn generate_secure_mds<F: PrimeField, R: Rng + ?Sized>(
+    state_size: usize,
+    rng: &mut R,
+) -> Vec<Vec<F>> {
+    assert!(state_size > 0, "the state must not be empty");
+    loop {
+        let xs: Vec<F> = (0..state_size).map(|_| F::rand(rng)).collect();
+        let ys: Vec<F> = (0..state_size).map(|_| F::rand(rng)).collect();
+        let distinct = |elems: &[F]| {
+            elems
+                .iter()
+                .enumerate()
+                .all(|(i, a)| elems[i + 1..].iter().all(|b| a != b))
+        };
+        if !distinct(&xs) || !distinct(&ys) {
+            continue;
+        }
+        let mds: Option<Vec<Vec<F>>> = xs
+            .iter()
+            .map(|x| ys.iter().map(|y| (*x + y).inverse()).collect())
+            .collect();
+        if let Some(mds) = mds {
+            if check_mds_security(&mds).is_ok() {
+                return mds;
+            }
+        }
+    }
+}
+
+fn matrix_product<F: PrimeField>(a: &[Vec<F>], b: &[Vec<F>]) -> Vec<Vec<F>> {
+    let n = a.len();
+    (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| (0..n).map(|k| a[i][k] * b[k][j]).sum())
+                .collect()
+        })
+        .collect()
+}
+
+fn determinant<F: PrimeField>(matrix: &[Vec<F>]) -> F {
+    let n = matrix.len();
+    let mut matrix = matrix.to_vec();
+    let mut det = F::one();
+    for col in 0..n {
+        let pivot = match (col..n).find(|row| !matrix[*row][col].is_zero()) {
+            Some(pivot) => pivot,
+            None => return F::zero(),
+        };
+        if pivot != col {
+            matrix.swap(pivot, col);
+            det = -det;
+        }
+        det *= matrix[col][col];
+        let inverse = matrix[col][col].inverse().unwrap();
+        let (pivot_rows, other_rows) = matrix.split_at_mut(col + 1);
+        let pivot_row = &pivot_rows[col];
+        for row in other_rows {
+            let factor = row[col] * inverse;
+            for (elem, pivot_elem) in row.iter_mut().zip(pivot_row).skip(col) {
+                *elem -= *pivot_elem * factor;
+            }
+        }
+    }
+    det
+}
+
+/// Returns the coefficients of the characteristic polynomial of `matrix`, from the constant
+/// term to the leading one, with the Faddeev-LeVerrier algorithm.
+fn characteristic_polynomial<F: PrimeField>(matrix: &[Vec<F>]) -> Vec<F> {
+    let n = matrix.len();
+    let mut coeffs = vec![F::zero(); n + 1];
+    coeffs[n] = F::one();
+    // aux = matrix * (previous aux + c * I), starting from the zero matrix
+    let mut aux = vec![vec![F::zero(); n]; n];
+    for k in 1..=n {
+        for (i, row) in aux.iter_mut().enumerate() {
+            row[i] += coeffs[n - k + 1];
+        }
+        aux = matrix_product(matrix, &aux);
+        let trace: F = (0..n).map(|i| aux[i][i]).sum();
+        coeffs[n - k] = -trace * F::from(k as u64).inverse().unwrap();
+    }
+    coeffs
+}
+
+/// Returns whether the monic polynomial `f` is irreducible, with Rabin's test: `f` of degree `n`
+/// is irreducible if and only if it divides `x^(p^n) - x`, and is coprime with
+/// `x^(p^(n/q)) - x` for every prime factor `q` of `n`.
+fn is_irreducible<F: PrimeField>(f: &[F]) -> bool {
+    let n = f.len() - 1;
+    if n <= 1 {
+        return true;
+    }
+    let x = vec![F::zero(), F::one()];
+    // frobenius[k] is x^(p^k) mod f, computed as the composition of x^(p^(k-1)) and x^p
+    let x_p = power_mod(&x, F::characteristic(), f);
+    let mut frobenius = vec![reduce(&x, f), x_p.clone()];
+    for k in 2..=n {
+        let next = compose_mod(&frobenius[k - 1], &x_p, f);
+        frobenius.push(next);
+    }
+    if !trim(reduce(&subtract(&frobenius[n], &x), f)).is_empty() {
+        return false;
+    }
+    prime_factors(n).into_iter().all(|q| {
+        let difference = trim(subtract(&frobenius[n / q], &x));
+        gcd(f.to_vec(), difference).len() == 1
+    })
+}
+
+fn prime_factors(mut n: usize) -> Vec<usize> {
+    let mut factors = Vec::new();
+    let mut q = 2;
+    while q * q <= n {
+        if n.is_multiple_of(q) {
+            factors.push(q);
+            while n.is_multiple_of(q) {
+                n /= q;
+            }
+        }
+        q += 1;
+    }
+    if n > 1 {
+        factors.push(n);
+    }
+    factors
+}
+
+fn trim<F: PrimeField>(mut a: Vec<F>) -> Vec<F> {
+    while a.last().is_some_and(|c| c.is_zero()) {
+        a.pop();
+    }
+    a
+}
+
+fn subtract<F: PrimeField>(a: &[F], b: &[F]) -> Vec<F> {
+    let mut result = vec![F::zero(); ark_std::cmp::max(a.len(), b.len())];
+    for (i, c) in a.iter().enumerate() {
+        result[i] += c;
+    }
+    for (i, c) in b.iter().enumerate() {
+        result[i] -= c;
+    }
+    result
+}
+
+/// Returns the remainder of `a` divided by the nonzero polynomial `f`.
+fn reduce<F: PrimeField>(a: &[F], f: &[F]) -> Vec<F> {
+    let f = trim(f.to_vec());
+    let mut a = trim(a.to_vec());
+    let leading_inverse = f.last().unwrap().inverse().unwrap();
+    while a.len() >= f.len() {
+        let factor = *a.last().unwrap() * leading_inverse;
+        let shift = a.len() - f.len();
+        for (i, c) in f.iter().enumerate() {
+            a[shift + i] -= factor * c;
+        }
+        a = trim(a);
+    }
+    a
+}
+
+fn multiply_mod<F: PrimeField>(a: &[F], b: &[F], f: &[F]) -> Vec<F> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let mut product = vec![F::zero(); a.len() + b.len() - 1];
+    for (i, a_i) in a.iter().enumerate() {
+        for (j, b_j) in b.iter().enumerate() {
+            product[i + j] += *a_i * b_j;
+        }
+    }
+    reduce(&product, f)
+}
+
+fn power_mod<F: PrimeField>(base: &[F], exponent: impl AsRef<[u64]>, f: &[F]) -> Vec<F> {
+    let base = reduce(base, f);
+    let mut result = reduce(&[F::one()], f);
+    for bit in BitIteratorBE::without_leading_zeros(exponent) {
+        result = multiply_mod(&result, &result, f);
+        if bit {
+            result = multiply_mod(&result, &base, f);
+        }
+    }
+    result
+}
+
+/// Returns `g(h) mod f`, with Horner's rule.
+fn compose_mod<F: PrimeField>(g: &[F], h: &[F], f: &[F]) -> Vec<F> {
+    g.iter().rev().fold(Vec::new(), |acc, c| {
+        let mut acc = multiply_mod(&acc, h, f);
+        if acc.is_empty() {
+            acc.push(F::zero());
+        }
+        acc[0] += c;
+        trim(acc)
+    })
+}
+
+fn gcd<F: PrimeField>(mut a: Vec<F>, mut b: Vec<F>) -> Vec<F> {
+    a = trim(a);
+    b = trim(b);
+    while !b.is_empty() {
+        let remainder = reduce(&a, &b);
+        a = b;
+        b = remainder;
+    }
+    a
+}
@@ -1,25 +1,122 @@
+use crate::duplex::FieldPermutation;
+#[cfg(feature = "stats")]
+use crate::SpongeStats;
 use crate::{
-    batch_field_cast, squeeze_field_elements_with_sizes_default_impl, Absorb, CryptographicSponge,
-    DuplexSpongeMode, FieldBasedCryptographicSponge, FieldElementSize, SpongeExt,
+    batch_field_cast, bits_from_squeezed_elements, bytes_from_squeezed_elements,
+    check_sponge_state, deserialize_state_version, field_sponge_security_level,
+    squeeze_bits_num_elements, squeeze_bytes_num_elements,
+    squeeze_field_elements_with_sizes_default_impl, Absorb, AbsorbMode, CryptographicSponge,
+    DuplexSpongeMode, FieldBasedCryptographicSponge, FieldElementSize, PaddingMode, SecurityLevel,
+    SpongeError, SpongeExt, SPONGE_STATE_VERSION,
 };
-use ark_ff::{BigInteger, FpParameters, PrimeField};
+use alloc::sync::Arc;
+use ark_ff::{FpParameters, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
 use ark_std::any::TypeId;
+use ark_std::cfg_chunks_mut;
 use ark_std::vec;
 use ark_std::vec::Vec;
+use grain_lfsr::PoseidonGrainLFSR;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use traits::{find_poseidon_ark_and_mds_with_lfsr, find_poseidon_ark_and_mds_with_state_size};
 
 /// constraints for Poseidon
 #[cfg(feature = "r1cs")]
 pub mod constraints;
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
 
 /// default parameters traits for Poseidon
 pub mod traits;
 pub use traits::*;
 
-mod grain_lfsr;
+/// default parameters for the fields of common curves
+#[cfg(any(
+    feature = "bls12_381",
+    feature = "bn254",
+    feature = "ed_on_bls12_381",
+    feature = "pallas"
+))]
+pub mod curves;
+
+pub(crate) mod grain_lfsr;
+
+pub(crate) mod optimized;
+use optimized::{DerivedOptimizedParameters, PoseidonOptimizedParameters};
+
+pub(crate) mod round_numbers;
+use round_numbers::poseidon_round_numbers_are_secure;
+
+mod mds;
+pub use mds::{check_mds_security, generate_secure_mds};
+pub use round_numbers::{find_poseidon_alpha, find_poseidon_round_numbers};
+
+mod circom;
+pub use circom::{circom_poseidon_hash, CIRCOM_FULL_ROUNDS, CIRCOM_PARTIAL_ROUNDS};
+
+mod evm;
+pub use evm::{
+    evm_poseidon_hash, field_element_from_evm_word, field_element_to_evm_word, EVM_WORD_SIZE,
+};
+
+mod neptune;
+pub use neptune::{neptune_poseidon_hash, NeptuneHashType, NeptuneStrength};
+
+mod family;
+pub use family::{PoseidonFamily, POSEIDON_FAMILY_ARITIES};
+
+#[cfg(feature = "serde")]
+mod config;
+
+/// The domain separator set in the capacity by [`GenericPoseidonSponge::compress`].
+pub const COMPRESSION_DOMAIN: &[u8] = b"ark-sponge/poseidon/compress";
+
+/// A domain tag set in the first element of the capacity of a Poseidon sponge when it is
+/// created, as chosen by [`PoseidonParameters::domain_tag`].
+///
+/// Sponges with different tags start from different states, so their outputs are
+/// domain-separated at no cost: unlike absorbing a tag, this does not take any rate or
+/// permutation, and the gadget sets the tag as a constant.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum PoseidonDomainTag<F: PrimeField> {
+    /// No tag: the capacity starts at zero.
+    #[default]
+    None,
+    /// The rate of the parameters, which separates fixed-arity hashes such as Merkle tree nodes
+    /// of different arities.
+    Arity,
+    /// A label, such as `b"merkle-node"`, read as a little-endian integer modulo the field.
+    Label(&'static [u8]),
+    /// An arbitrary field element, such as a bitfield of flags.
+    Element(F),
+}
+
+impl<F: PrimeField> PoseidonDomainTag<F> {
+    /// Returns the tag as a field element, for parameters of the given rate.
+    pub fn value(&self, rate: usize) -> F {
+        match self {
+            PoseidonDomainTag::None => F::zero(),
+            PoseidonDomainTag::Arity => F::from(rate as u64),
+            PoseidonDomainTag::Label(label) => F::from_le_bytes_mod_order(label),
+            PoseidonDomainTag::Element(elem) => *elem,
+        }
+    }
+}
+
+/// The number of states that are permuted together, round by round, by the batched permutation.
+const PERMUTATION_BATCH_SIZE: usize = 8;
+
+/// The width of the state of [`PoseidonParameters::for_small_field`].
+pub const SMALL_FIELD_STATE_SIZE: usize = 12;
 
 /// Parameters and RNG used
+///
+/// The parameters keep the optimized form of their permutation, which is derived when they are
+/// created. It is only used while the numbers of rounds, the round constants and the MDS matrix
+/// are those it was derived from: after these fields are modified, the permutation is evaluated
+/// in its unoptimized form until [`PoseidonParameters::update_optimized_parameters`] is called,
+/// or a sponge is created from the parameters.
 #[derive(Clone, Debug)]
 pub struct PoseidonParameters<F: PrimeField> {
     /// Number of rounds in a full-round operation.
@@ -27,7 +124,11 @@ pub struct PoseidonParameters<F: PrimeField> {
     /// Number of rounds in a partial-round operation.
     pub partial_rounds: usize,
     /// Exponent used in S-boxes.
-    pub alpha: u64,
+    ///
+    /// This is either an exponent `alpha > 1` such that `x^alpha` is a permutation of the field,
+    /// i.e., `gcd(alpha, p - 1) = 1`, or `-1` for the inverse S-box, which maps `x` to `x^(-1)`
+    /// and `0` to `0`.
+    pub alpha: i64,
     /// Additive Round keys. These are added before each MDS matrix application to make it an affine shift.
     /// They are indexed by `ark[round_num][state_element_index]`
     pub ark: Vec<Vec<F>>,
@@ -39,82 +140,361 @@ pub struct PoseidonParameters<F: PrimeField> {
     pub rate: usize,
     /// The capacity (in terms of number of field elements).
     pub capacity: usize,
+    /// The domain tag set in the first element of the capacity when a sponge is created.
+    pub domain_tag: PoseidonDomainTag<F>,
+    /// The optimized form of the permutation, with the constants it was derived from. It is
+    /// shared by the clones of the parameters.
+    optimized_parameters: Option<Arc<DerivedOptimizedParameters<F>>>,
+}
+
+/// The storage of the state of a [`GenericPoseidonSponge`].
+///
+/// It is implemented for `Vec<F>`, whose width is chosen at runtime by the parameters, for
+/// `[F; WIDTH]`, which is allocated on the stack, and for [`PoseidonCowState`], which is shared
+/// by clones until it is modified.
+pub trait PoseidonState<F: PrimeField>: AsRef<[F]> + AsMut<[F]> + Clone {
+    /// Returns a state of `width` zero elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the storage cannot hold exactly `width` elements.
+    fn zeros(width: usize) -> Self;
 }
 
+impl<F: PrimeField> PoseidonState<F> for Vec<F> {
+    fn zeros(width: usize) -> Self {
+        vec![F::zero(); width]
+    }
+}
+
+/// A state stored behind an [`Arc`], which is shared by the clones of a sponge until one of them
+/// modifies it, so that cloning or forking a [`PoseidonCowSponge`] does not copy its state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PoseidonCowState<F: PrimeField>(Arc<Vec<F>>);
+
+impl<F: PrimeField> AsRef<[F]> for PoseidonCowState<F> {
+    fn as_ref(&self) -> &[F] {
+        &self.0
+    }
+}
+
+impl<F: PrimeField> AsMut<[F]> for PoseidonCowState<F> {
+    fn as_mut(&mut self) -> &mut [F] {
+        Arc::make_mut(&mut self.0).as_mut_slice()
+    }
+}
+
+impl<F: PrimeField> PoseidonState<F> for PoseidonCowState<F> {
+    fn zeros(width: usize) -> Self {
+        Self(Arc::new(vec![F::zero(); width]))
+    }
+}
+
+impl<F: PrimeField, const WIDTH: usize> PoseidonState<F> for [F; WIDTH] {
+    fn zeros(width: usize) -> Self {
+        assert_eq!(
+            width, WIDTH,
+            "the state size of the parameters must be the width of the array"
+        );
+        [F::zero(); WIDTH]
+    }
+}
+
+/// A duplex sponge based using the Poseidon permutation, with a heap-allocated state whose width
+/// is given by the parameters.
+pub type PoseidonSponge<F> = GenericPoseidonSponge<F, Vec<F>>;
+
+/// A duplex sponge based using the Poseidon permutation, with a state of `WIDTH` elements
+/// allocated on the stack.
+///
+/// It gives the same outputs as [`PoseidonSponge`], without allocating in the permutation.
+/// Creating it panics if the state size of the parameters, `rate + capacity`, is not `WIDTH`.
+pub type PoseidonArraySponge<F, const WIDTH: usize> = GenericPoseidonSponge<F, [F; WIDTH]>;
+
+/// A duplex sponge based using the Poseidon permutation, whose state is copied on write.
+///
+/// It gives the same outputs as [`PoseidonSponge`]. Its clones share the state until one of them
+/// absorbs, squeezes or permutes, so that forking it for challenges which are not all used is
+/// cheap.
+pub type PoseidonCowSponge<F> = GenericPoseidonSponge<F, PoseidonCowState<F>>;
+
 #[derive(Clone)]
-/// A duplex sponge based using the Poseidon permutation.
+/// A duplex sponge based using the Poseidon permutation, whose state is stored in `S`.
+///
+/// It is usually used through the aliases [`PoseidonSponge`], [`PoseidonArraySponge`] and
+/// [`PoseidonCowSponge`].
 ///
 /// This implementation of Poseidon is entirely from Fractal's implementation in [COS20][cos]
 /// with small syntax changes.
 ///
+/// The sponge is instantiated from runtime [`PoseidonParameters`] through
+/// [`GenericPoseidonSponge::new_with_parameters`] or [`CryptographicSponge::new`], so any vetted
+/// parameter set for the field can be supplied.
+///
+/// The permutation is evaluated in the optimized form of Appendix B of the
+/// [Poseidon paper][poseidon], which replaces the MDS matrix of each partial round with a sparse
+/// matrix. This form is derived once with the parameters, when they are created and when their
+/// round constants or MDS matrix are replaced.
+///
+/// The sponge created by [`CryptographicSponge::new`] does not pad its input; use
+/// [`GenericPoseidonSponge::with_padding`] to choose a [`PaddingMode`]. It adds its input to the
+/// rate; use [`GenericPoseidonSponge::with_absorb_mode`] to overwrite the rate instead.
+///
 /// [cos]: https://eprint.iacr.org/2019/1076
-pub struct PoseidonSponge<F: PrimeField> {
-    /// Sponge Parameters
-    pub parameters: PoseidonParameters<F>,
+/// [poseidon]: https://eprint.iacr.org/2019/458.pdf
+pub struct GenericPoseidonSponge<F: PrimeField, S: PoseidonState<F>> {
+    /// Sponge Parameters, shared by the clones of the sponge. Modifying them with
+    /// [`Arc::make_mut`] copies them for this sponge only.
+    pub parameters: Arc<PoseidonParameters<F>>,
 
     // Sponge State
     /// Current sponge's state (current elements in the permutation block)
-    pub state: Vec<F>,
+    pub state: S,
     /// Current mode (whether its absorbing or squeezing)
     pub mode: DuplexSpongeMode,
+    /// The padding applied when the sponge starts squeezing.
+    pub padding: PaddingMode,
+    /// Whether absorbed elements are added to the rate or overwrite it.
+    pub absorb_mode: AbsorbMode,
+    /// The number of elements absorbed since the last squeeze.
+    num_absorbed: usize,
+    /// The counters of the work done by the sponge.
+    #[cfg(feature = "stats")]
+    stats: SpongeStats,
 }
 
-impl<F: PrimeField> PoseidonSponge<F> {
-    fn apply_s_box(&self, state: &mut [F], is_full_round: bool) {
-        // Full rounds apply the S Box (x^alpha) to every element of state
-        if is_full_round {
-            for elem in state {
-                *elem = elem.pow(&[self.parameters.alpha]);
-            }
+impl<F: PrimeField, S: PoseidonState<F>> GenericPoseidonSponge<F, S> {
+    /// Creates a sponge with the given parameters, as [`CryptographicSponge::new`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `S` cannot hold a state of `rate + capacity` elements.
+    pub fn new_with_parameters(parameters: &PoseidonParameters<F>) -> Self {
+        <Self as CryptographicSponge>::new(parameters)
+    }
+
+    /// Creates a sponge with the given parameters, which pads its input with `padding`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `S` cannot hold a state of `rate + capacity` elements.
+    pub fn with_padding(parameters: &PoseidonParameters<F>, padding: PaddingMode) -> Self {
+        let state = parameters.initial_state();
+        let mode = DuplexSpongeMode::Absorbing {
+            next_absorb_index: 0,
+        };
+
+        Self {
+            parameters: parameters.to_shared(),
+            state,
+            mode,
+            padding,
+            absorb_mode: AbsorbMode::Add,
+            num_absorbed: 0,
+            #[cfg(feature = "stats")]
+            stats: SpongeStats::default(),
         }
-        // Partial rounds apply the S Box (x^alpha) to just the first element of state
-        else {
-            state[0] = state[0].pow(&[self.parameters.alpha]);
+    }
+
+    /// Creates a sponge whose capacity is initialized with `iv`, e.g. a key, a session identifier
+    /// or a protocol version, instead of absorbing it. The IV is added to the first `iv.len()`
+    /// elements of the capacity, on top of the domain tag of the parameters if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `iv` is longer than the capacity; see [`Self::try_new_with_iv`].
+    pub fn new_with_iv(parameters: &PoseidonParameters<F>, iv: &[F]) -> Self {
+        Self::try_new_with_iv(parameters, iv).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`Self::new_with_iv`], but returns [`SpongeError::InputTooLong`] if `iv` is longer
+    /// than the capacity.
+    pub fn try_new_with_iv(
+        parameters: &PoseidonParameters<F>,
+        iv: &[F],
+    ) -> Result<Self, SpongeError> {
+        if iv.len() > parameters.capacity {
+            return Err(SpongeError::InputTooLong {
+                length: iv.len(),
+                max: parameters.capacity,
+            });
+        }
+        let mut sponge = Self::with_padding(parameters, PaddingMode::None);
+        for (elem, iv_elem) in sponge.state.as_mut().iter_mut().zip(iv) {
+            *elem += iv_elem;
         }
+        Ok(sponge)
     }
 
-    fn apply_ark(&self, state: &mut [F], round_number: usize) {
-        for (i, state_elem) in state.iter_mut().enumerate() {
-            state_elem.add_assign(&self.parameters.ark[round_number][i]);
+    /// Applies the permutation now, so that the next absorb or squeeze starts at the beginning of
+    /// the rate, e.g. for a protocol that derives its challenges only at rate boundaries. The rest
+    /// of the current block is skipped: no padding is applied, and unread outputs are dropped.
+    pub fn permute(&mut self) {
+        self.apply_permutation();
+        self.mode = match self.mode {
+            DuplexSpongeMode::Absorbing { .. } => DuplexSpongeMode::Absorbing {
+                next_absorb_index: 0,
+            },
+            DuplexSpongeMode::Squeezing { .. } => DuplexSpongeMode::Squeezing {
+                next_squeeze_index: 0,
+            },
+        };
+    }
+
+    /// Closes the absorb phase: pads the absorbed input and applies the permutation, as the
+    /// next squeeze would do, so that the permutation happens now and the next squeeze reads the
+    /// state directly. Squeezing gives the same outputs with or without this call. Does nothing
+    /// if the sponge is already squeezing.
+    pub fn finish_absorb(&mut self) {
+        if let DuplexSpongeMode::Absorbing { next_absorb_index } = self.mode {
+            self.pad(next_absorb_index);
+            self.apply_permutation();
+            self.mode = DuplexSpongeMode::Squeezing {
+                next_squeeze_index: 0,
+            };
         }
     }
 
-    fn apply_mds(&self, state: &mut [F]) {
-        let mut new_state = Vec::new();
-        for i in 0..state.len() {
-            let mut cur = F::zero();
-            for (j, state_elem) in state.iter().enumerate() {
-                let term = state_elem.mul(&self.parameters.mds[i][j]);
-                cur.add_assign(&term);
-            }
-            new_state.push(cur);
+    /// Returns this sponge with the given absorb mode, which must be chosen before anything is
+    /// absorbed.
+    pub fn with_absorb_mode(mut self, absorb_mode: AbsorbMode) -> Self {
+        self.absorb_mode = absorb_mode;
+        self
+    }
+
+    /// Returns the parameters of the sponge.
+    pub fn parameters(&self) -> &PoseidonParameters<F> {
+        &self.parameters
+    }
+
+    /// Returns the counters of the permutations, absorbed elements, and squeezed elements of this
+    /// sponge since it was created.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> SpongeStats {
+        self.stats
+    }
+
+    /// Permutes the state and zeroes its rate, so that the inputs absorbed before cannot be
+    /// recovered from the state afterwards. The sponge is then absorbing.
+    pub fn ratchet(&mut self) {
+        self.apply_permutation();
+        for elem in &mut self.state.as_mut()[self.parameters.capacity..] {
+            *elem = F::zero();
+        }
+        self.mode = DuplexSpongeMode::Absorbing {
+            next_absorb_index: 0,
+        };
+        self.num_absorbed = 0;
+    }
+
+    /// Restores the state of a new sponge with the same parameters, including their domain tag,
+    /// so that one sponge can hash many inputs without being created again. The parameters and
+    /// the allocated state are reused, and the padding, the absorb mode and the statistics are
+    /// kept. The IV of [`Self::new_with_iv`] is not restored.
+    pub fn reset(&mut self) {
+        self.parameters.reset_state(self.state.as_mut());
+        self.mode = DuplexSpongeMode::Absorbing {
+            next_absorb_index: 0,
+        };
+        self.num_absorbed = 0;
+    }
+
+    /// Compresses exactly `rate` field elements into one with a single permutation, which is
+    /// cheaper than absorbing and squeezing for fixed-length inputs such as Merkle tree nodes.
+    ///
+    /// The first element of the capacity is set to [`COMPRESSION_DOMAIN`] (as a field element)
+    /// and the rest of it to zero, the rate to `input`, and the output is the first element of
+    /// the rate after the permutation. The state of the sponge is not used or modified.
+    ///
+    /// Returns [`SpongeError::InvalidInputLength`] if `input` does not have `rate` elements.
+    pub fn compress(&self, input: &[F]) -> Result<F, SpongeError> {
+        if input.len() != self.parameters.rate {
+            return Err(SpongeError::InvalidInputLength {
+                expected: self.parameters.rate,
+                found: input.len(),
+            });
         }
-        state.clone_from_slice(&new_state[..state.len()])
+        let mut state = S::zeros(self.parameters.state_size());
+        state.as_mut()[0] = F::from_le_bytes_mod_order(COMPRESSION_DOMAIN);
+        state.as_mut()[self.parameters.capacity..].copy_from_slice(input);
+        Ok(self.permute_state(state).as_ref()[self.parameters.capacity])
     }
 
-    fn permute(&mut self) {
-        let full_rounds_over_2 = self.parameters.full_rounds / 2;
-        let mut state = self.state.clone();
-        for i in 0..full_rounds_over_2 {
-            self.apply_ark(&mut state, i);
-            self.apply_s_box(&mut state, true);
-            self.apply_mds(&mut state);
+    /// Returns the root of a binary Merkle tree whose inner nodes are compressed with
+    /// [`GenericPoseidonSponge::compress`], given the node `leaf` and its authentication path.
+    ///
+    /// `path` holds, for each level from the leaves, whether the node on the path is the right
+    /// child and its sibling. The state of the sponge is not used or modified.
+    ///
+    /// Returns [`SpongeError::InvalidInputLength`] if the rate is not 2.
+    pub fn hash_path(&self, leaf: F, path: &[(bool, F)]) -> Result<F, SpongeError> {
+        path.iter()
+            .try_fold(leaf, |node, (is_right, sibling)| match is_right {
+                true => self.compress(&[*sibling, node]),
+                false => self.compress(&[node, *sibling]),
+            })
+    }
+
+    fn absorb_elements(&mut self, elems: &[F]) {
+        if elems.is_empty() {
+            return;
         }
+        #[cfg(feature = "trace")]
+        tracing::trace!(
+            target: "ark_sponge",
+            sponge = "poseidon",
+            num_elements = elems.len(),
+            mode = ?self.mode,
+            "absorb"
+        );
 
-        for i in full_rounds_over_2..(full_rounds_over_2 + self.parameters.partial_rounds) {
-            self.apply_ark(&mut state, i);
-            self.apply_s_box(&mut state, false);
-            self.apply_mds(&mut state);
+        match self.mode {
+            DuplexSpongeMode::Absorbing { next_absorb_index } => {
+                let mut absorb_index = next_absorb_index;
+                if absorb_index == self.parameters.rate {
+                    self.apply_permutation();
+                    absorb_index = 0;
+                }
+                self.absorb_internal(absorb_index, elems);
+            }
+            // The squeeze that put the sponge in this mode already permuted after the last
+            // absorb, and the next squeeze permutes again, so the input can go straight into
+            // the rate.
+            DuplexSpongeMode::Squeezing {
+                next_squeeze_index: _,
+            } => {
+                self.num_absorbed = 0;
+                self.absorb_internal(0, elems);
+            }
+        };
+        self.num_absorbed += elems.len();
+        #[cfg(feature = "stats")]
+        {
+            self.stats.num_absorbed += elems.len() as u64;
         }
+    }
 
-        for i in (full_rounds_over_2 + self.parameters.partial_rounds)
-            ..(self.parameters.partial_rounds + self.parameters.full_rounds)
+    pub(crate) fn apply_permutation(&mut self) {
+        self.parameters
+            .rounds()
+            .permute_interleaved(ark_std::slice::from_mut(&mut self.state));
+        #[cfg(feature = "trace")]
+        tracing::trace!(
+            target: "ark_sponge",
+            sponge = "poseidon",
+            "permute"
+        );
+        #[cfg(feature = "stats")]
         {
-            self.apply_ark(&mut state, i);
-            self.apply_s_box(&mut state, true);
-            self.apply_mds(&mut state);
+            self.stats.num_permutations += 1;
         }
-        self.state = state;
+    }
+
+    fn permute_state(&self, mut state: S) -> S {
+        self.parameters
+            .rounds()
+            .permute_interleaved(ark_std::slice::from_mut(&mut state));
+        state
     }
 
     // Absorbs everything in elements, this does not end in an absorbtion.
@@ -125,7 +505,7 @@ impl<F: PrimeField> PoseidonSponge<F> {
             // if we can finish in this call
             if rate_start_index + remaining_elements.len() <= self.parameters.rate {
                 for (i, element) in remaining_elements.iter().enumerate() {
-                    self.state[self.parameters.capacity + i + rate_start_index] += element;
+                    self.absorb_element(rate_start_index + i, element);
                 }
                 self.mode = DuplexSpongeMode::Absorbing {
                     next_absorb_index: rate_start_index + remaining_elements.len(),
@@ -140,15 +520,24 @@ impl<F: PrimeField> PoseidonSponge<F> {
                 .enumerate()
                 .take(num_elements_absorbed)
             {
-                self.state[self.parameters.capacity + i + rate_start_index] += element;
+                self.absorb_element(rate_start_index + i, element);
             }
-            self.permute();
+            self.apply_permutation();
             // the input elements got truncated by num elements absorbed
             remaining_elements = &remaining_elements[num_elements_absorbed..];
             rate_start_index = 0;
         }
     }
 
+    // Absorbs `element` into the element of the rate at `rate_index`.
+    fn absorb_element(&mut self, rate_index: usize, element: &F) {
+        let state_elem = &mut self.state.as_mut()[self.parameters.capacity + rate_index];
+        match self.absorb_mode {
+            AbsorbMode::Add => *state_elem += element,
+            AbsorbMode::Overwrite => *state_elem = *element,
+        }
+    }
+
     // Squeeze |output| many elements. This does not end in a squeeze
     fn squeeze_internal(&mut self, mut rate_start_index: usize, output: &mut [F]) {
         let mut output_remaining = output;
@@ -156,7 +545,7 @@ impl<F: PrimeField> PoseidonSponge<F> {
             // if we can finish in this call
             if rate_start_index + output_remaining.len() <= self.parameters.rate {
                 output_remaining.clone_from_slice(
-                    &self.state[self.parameters.capacity + rate_start_index
+                    &self.state.as_ref()[self.parameters.capacity + rate_start_index
                         ..(self.parameters.capacity + output_remaining.len() + rate_start_index)],
                 );
                 self.mode = DuplexSpongeMode::Squeezing {
@@ -167,19 +556,188 @@ impl<F: PrimeField> PoseidonSponge<F> {
             // otherwise squeeze (rate - rate_start_index) elements
             let num_elements_squeezed = self.parameters.rate - rate_start_index;
             output_remaining[..num_elements_squeezed].clone_from_slice(
-                &self.state[self.parameters.capacity + rate_start_index
+                &self.state.as_ref()[self.parameters.capacity + rate_start_index
                     ..(self.parameters.capacity + num_elements_squeezed + rate_start_index)],
             );
 
             // Unless we are done with squeezing in this call, permute.
             if output_remaining.len() != self.parameters.rate {
-                self.permute();
+                self.apply_permutation();
             }
             // Repeat with updated output slices
             output_remaining = &mut output_remaining[num_elements_squeezed..];
             rate_start_index = 0;
         }
     }
+
+    // Pads the input absorbed so far, before the sponge starts squeezing.
+    fn pad(&mut self, mut absorb_index: usize) {
+        let rate = self.parameters.rate;
+        let capacity = self.parameters.capacity;
+        match self.padding {
+            PaddingMode::None => {}
+            PaddingMode::MultiRate => {
+                if absorb_index == rate {
+                    self.apply_permutation();
+                    absorb_index = 0;
+                }
+                self.state.as_mut()[capacity + absorb_index] += F::one();
+                if absorb_index == rate - 1 {
+                    self.apply_permutation();
+                }
+                self.state.as_mut()[capacity + rate - 1] += F::one();
+            }
+            PaddingMode::LengthSuffix => {
+                if absorb_index == rate {
+                    self.apply_permutation();
+                    absorb_index = 0;
+                }
+                self.absorb_internal(absorb_index, &[F::from(self.num_absorbed as u64)]);
+            }
+        }
+    }
+}
+
+impl<F: PrimeField> PoseidonSponge<F> {
+    /// Hashes each of `inputs` as [`poseidon_hash`], sharing the parameters and the batched
+    /// permutation of [`poseidon_hash_batch`] across all the hashes.
+    ///
+    /// The elements of the inputs are absorbed as they are, without the conversion of
+    /// [`Absorb`], which saves time when hashing many small inputs such as Merkle tree leaves.
+    /// With the `parallel` feature, the hashes are computed on multiple threads.
+    pub fn hash_batch(parameters: &PoseidonParameters<F>, inputs: &[Vec<F>]) -> Vec<F> {
+        hash_field_elements_batch(&PoseidonPermutation::new(parameters), inputs)
+    }
+
+    /// Applies the permutation to each of `states`. The states are permuted together, round by
+    /// round, and in parallel with the `parallel` feature.
+    pub(crate) fn permute_states(&self, states: &mut [Vec<F>]) {
+        let rounds = self.parameters.rounds();
+        cfg_chunks_mut!(states, PERMUTATION_BATCH_SIZE)
+            .for_each(|states| rounds.permute_interleaved(states));
+    }
+}
+
+/// The rounds of the Poseidon permutation for some parameters, which are applied to states of any
+/// storage.
+struct PoseidonRounds<'a, F: PrimeField> {
+    parameters: &'a PoseidonParameters<F>,
+    /// The optimized form of the permutation, if it exists for `parameters`.
+    optimized_parameters: Option<&'a PoseidonOptimizedParameters<F>>,
+}
+
+impl<'a, F: PrimeField> PoseidonRounds<'a, F> {
+    /// Applies the permutation to each of `states`, interleaving the states in every round so
+    /// that their independent field operations are evaluated together.
+    fn permute_interleaved<S: PoseidonState<F>>(&self, states: &mut [S]) {
+        match self.optimized_parameters {
+            Some(optimized_parameters) => self.permute_optimized(optimized_parameters, states),
+            None => self.permute_unoptimized(states),
+        }
+    }
+
+    fn permute_optimized<S: PoseidonState<F>>(
+        &self,
+        optimized_parameters: &PoseidonOptimizedParameters<F>,
+        states: &mut [S],
+    ) {
+        let full_rounds_over_2 = self.parameters.full_rounds / 2;
+        let (first_full_ark, last_full_ark) =
+            optimized_parameters.full_ark.split_at(full_rounds_over_2);
+
+        for (i, ark) in first_full_ark.iter().enumerate() {
+            for state in states.iter_mut() {
+                Self::add_round_constants(state.as_mut(), ark);
+                self.apply_s_box(state.as_mut(), true);
+                if i + 1 == full_rounds_over_2 {
+                    Self::apply_matrix(&optimized_parameters.pre_sparse_mds, state);
+                } else {
+                    self.apply_mds(state);
+                }
+            }
+        }
+
+        for (ark, sparse_mds) in optimized_parameters
+            .partial_ark
+            .iter()
+            .zip(&optimized_parameters.sparse_mds)
+        {
+            for state in states.iter_mut() {
+                let state = state.as_mut();
+                state[0] += ark;
+                self.apply_s_box(state, false);
+                sparse_mds.apply(state);
+            }
+        }
+
+        for ark in last_full_ark {
+            for state in states.iter_mut() {
+                Self::add_round_constants(state.as_mut(), ark);
+                self.apply_s_box(state.as_mut(), true);
+                self.apply_mds(state);
+            }
+        }
+    }
+
+    fn permute_unoptimized<S: PoseidonState<F>>(&self, states: &mut [S]) {
+        let full_rounds_over_2 = self.parameters.full_rounds / 2;
+        let partial_rounds_end = full_rounds_over_2 + self.parameters.partial_rounds;
+        for i in 0..(self.parameters.partial_rounds + self.parameters.full_rounds) {
+            let is_full_round = i < full_rounds_over_2 || i >= partial_rounds_end;
+            for state in states.iter_mut() {
+                self.apply_ark(state.as_mut(), i);
+                self.apply_s_box(state.as_mut(), is_full_round);
+                self.apply_mds(state);
+            }
+        }
+    }
+
+    fn apply_s_box(&self, state: &mut [F], is_full_round: bool) {
+        // Full rounds apply the S Box (x^alpha) to every element of state
+        if is_full_round {
+            for elem in state {
+                *elem = self.s_box(elem);
+            }
+        }
+        // Partial rounds apply the S Box (x^alpha) to just the first element of state
+        else {
+            state[0] = self.s_box(&state[0]);
+        }
+    }
+
+    fn s_box(&self, elem: &F) -> F {
+        if self.parameters.alpha == -1 {
+            elem.inverse().unwrap_or_else(F::zero)
+        } else {
+            elem.pow([self.parameters.alpha as u64])
+        }
+    }
+
+    fn apply_ark(&self, state: &mut [F], round_number: usize) {
+        Self::add_round_constants(state, &self.parameters.ark[round_number]);
+    }
+
+    fn add_round_constants(state: &mut [F], ark: &[F]) {
+        for (state_elem, constant) in state.iter_mut().zip(ark) {
+            state_elem.add_assign(constant);
+        }
+    }
+
+    fn apply_mds<S: PoseidonState<F>>(&self, state: &mut S) {
+        Self::apply_matrix(&self.parameters.mds, state)
+    }
+
+    // Multiplies the state by `matrix`, using a copy of the state of the same storage.
+    fn apply_matrix<S: PoseidonState<F>>(matrix: &[Vec<F>], state: &mut S) {
+        let old_state = state.clone();
+        for (new_elem, row) in state.as_mut().iter_mut().zip(matrix) {
+            let mut cur = F::zero();
+            for (state_elem, m) in old_state.as_ref().iter().zip(row) {
+                cur.add_assign(&state_elem.mul(m));
+            }
+            *new_elem = cur;
+        }
+    }
 }
 
 impl<F: PrimeField> PoseidonParameters<F> {
@@ -187,12 +745,16 @@ impl<F: PrimeField> PoseidonParameters<F> {
     pub fn new(
         full_rounds: usize,
         partial_rounds: usize,
-        alpha: u64,
+        alpha: i64,
         mds: Vec<Vec<F>>,
         ark: Vec<Vec<F>>,
         rate: usize,
         capacity: usize,
     ) -> Self {
+        assert!(
+            alpha > 1 || alpha == -1,
+            "alpha must be -1 or greater than 1"
+        );
         assert_eq!(ark.len(), full_rounds + partial_rounds);
         for item in &ark {
             assert_eq!(item.len(), rate + capacity);
@@ -201,7 +763,54 @@ impl<F: PrimeField> PoseidonParameters<F> {
         for item in &mds {
             assert_eq!(item.len(), rate + capacity);
         }
-        Self {
+        let mut parameters = Self {
+            full_rounds,
+            partial_rounds,
+            alpha,
+            mds,
+            ark,
+            rate,
+            capacity,
+            domain_tag: PoseidonDomainTag::None,
+            optimized_parameters: None,
+        };
+        parameters.update_optimized_parameters();
+        parameters
+    }
+
+    /// Same as [`PoseidonParameters::new`], but checks user-supplied constants instead of
+    /// panicking: returns [`SpongeError::InvalidParameters`] if the round constants or the MDS
+    /// matrix do not have the size of the state, or if the MDS matrix fails
+    /// [`check_mds_security`].
+    pub fn new_checked(
+        full_rounds: usize,
+        partial_rounds: usize,
+        alpha: i64,
+        mds: Vec<Vec<F>>,
+        ark: Vec<Vec<F>>,
+        rate: usize,
+        capacity: usize,
+    ) -> Result<Self, SpongeError> {
+        let state_size = rate + capacity;
+        if !(alpha > 1 || alpha == -1) {
+            return Err(SpongeError::InvalidParameters(
+                "alpha must be -1 or greater than 1",
+            ));
+        }
+        if ark.len() != full_rounds + partial_rounds
+            || ark.iter().any(|round| round.len() != state_size)
+        {
+            return Err(SpongeError::InvalidParameters(
+                "there must be a round constant per round and state element",
+            ));
+        }
+        if mds.len() != state_size {
+            return Err(SpongeError::InvalidParameters(
+                "the MDS matrix must have the size of the state",
+            ));
+        }
+        check_mds_security(&mds)?;
+        Ok(Self::new(
             full_rounds,
             partial_rounds,
             alpha,
@@ -209,80 +818,368 @@ impl<F: PrimeField> PoseidonParameters<F> {
             ark,
             rate,
             capacity,
+        ))
+    }
+
+    /// Returns these parameters with the given domain tag, which sponges set in the first
+    /// element of their capacity when they are created.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity is zero and `domain_tag` is not [`PoseidonDomainTag::None`].
+    pub fn with_domain_tag(mut self, domain_tag: PoseidonDomainTag<F>) -> Self {
+        assert!(
+            self.capacity > 0 || domain_tag == PoseidonDomainTag::None,
+            "a domain tag requires a capacity of at least one element"
+        );
+        self.domain_tag = domain_tag;
+        self
+    }
+
+    /// Returns the initial state of a sponge: zero, except for the domain tag in the first
+    /// element of the capacity.
+    pub(crate) fn initial_state<S: PoseidonState<F>>(&self) -> S {
+        let mut state = S::zeros(self.state_size());
+        self.reset_state(state.as_mut());
+        state
+    }
+
+    /// Overwrites `state` with the initial state of a sponge, in place.
+    pub(crate) fn reset_state(&self, state: &mut [F]) {
+        for elem in state.iter_mut() {
+            *elem = F::zero();
+        }
+        if self.domain_tag != PoseidonDomainTag::None {
+            state[0] = self.domain_tag.value(self.rate);
         }
     }
-}
 
-impl<F: PrimeField> CryptographicSponge for PoseidonSponge<F> {
-    type Parameters = PoseidonParameters<F>;
+    /// The width of the permutation state, `t = rate + capacity`.
+    pub fn state_size(&self) -> usize {
+        self.rate + self.capacity
+    }
 
-    fn new(parameters: &Self::Parameters) -> Self {
-        let state = vec![F::zero(); parameters.rate + parameters.capacity];
-        let mode = DuplexSpongeMode::Absorbing {
-            next_absorb_index: 0,
-        };
+    /// Applies the Poseidon permutation of the parameters to `state`, exactly as
+    /// [`PoseidonSponge`] does, so that constructions outside of the sponge, such as compression
+    /// functions or PRNGs, can be built on the same permutation.
+    ///
+    /// [`PoseidonPermutation`] gives the same permutation as a [`FieldPermutation`], and
+    /// [`PoseidonSpongeVar::permute_state`] in a circuit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `state` does not have [`Self::state_size`] elements.
+    ///
+    /// [`PoseidonSpongeVar::permute_state`]: constraints::PoseidonSpongeVar::permute_state
+    pub fn permute(&self, state: &mut [F]) {
+        assert_eq!(
+            state.len(),
+            self.state_size(),
+            "the state must have `rate + capacity` elements"
+        );
+        let rounds = self.rounds();
+        let mut states = [state.to_vec()];
+        rounds.permute_interleaved(&mut states);
+        state.copy_from_slice(&states[0]);
+    }
 
-        Self {
-            parameters: parameters.clone(),
-            state,
-            mode,
+    /// Applies the permutation of [`Self::permute`] to each of `states`.
+    ///
+    /// The states are permuted together, round by round, which amortizes the loads of the round
+    /// constants and MDS matrix over several states, and in parallel with the `parallel`
+    /// feature. This matters most for small fields, whose multiplications are cheap compared to
+    /// the bookkeeping of a round.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a state does not have [`Self::state_size`] elements.
+    pub fn permute_batch(&self, states: &mut [Vec<F>]) {
+        assert!(
+            states.iter().all(|state| state.len() == self.state_size()),
+            "the states must have `rate + capacity` elements"
+        );
+        let rounds = self.rounds();
+        cfg_chunks_mut!(states, PERMUTATION_BATCH_SIZE)
+            .for_each(|states| rounds.permute_interleaved(states));
+    }
+
+    /// Returns the rounds of the permutation, in their optimized form if it exists.
+    fn rounds(&self) -> PoseidonRounds<'_, F> {
+        PoseidonRounds {
+            parameters: self,
+            optimized_parameters: self.optimized_parameters(),
         }
     }
 
-    fn absorb(&mut self, input: &impl Absorb) {
-        let elems = input.to_sponge_field_elements_as_vec::<F>();
-        if elems.is_empty() {
-            return;
+    /// Returns the optimized form of the permutation, if it exists for these parameters and was
+    /// derived from their current constants.
+    pub(crate) fn optimized_parameters(&self) -> Option<&PoseidonOptimizedParameters<F>> {
+        self.optimized_parameters
+            .as_ref()
+            .and_then(|derived| derived.get(self))
+    }
+
+    /// Returns a copy of the parameters with an up-to-date optimized form, to be shared by a
+    /// sponge and its clones.
+    pub(crate) fn to_shared(&self) -> Arc<Self> {
+        let mut parameters = self.clone();
+        parameters.update_optimized_parameters();
+        Arc::new(parameters)
+    }
+
+    /// Derives the optimized form of the permutation again if the numbers of rounds, the round
+    /// constants or the MDS matrix were modified since it was derived. Until then, the
+    /// permutation of the parameters is evaluated in its slower, unoptimized form.
+    pub fn update_optimized_parameters(&mut self) {
+        let is_up_to_date = self
+            .optimized_parameters
+            .as_ref()
+            .is_some_and(|derived| derived.is_derived_from(self));
+        if !is_up_to_date {
+            self.optimized_parameters = Some(Arc::new(DerivedOptimizedParameters::derive(self)));
         }
+    }
+}
 
-        match self.mode {
-            DuplexSpongeMode::Absorbing { next_absorb_index } => {
-                let mut absorb_index = next_absorb_index;
-                if absorb_index == self.parameters.rate {
-                    self.permute();
-                    absorb_index = 0;
-                }
-                self.absorb_internal(absorb_index, elems.as_slice());
-            }
-            DuplexSpongeMode::Squeezing {
-                next_squeeze_index: _,
-            } => {
-                self.permute();
-                self.absorb_internal(0, elems.as_slice());
-            }
-        };
+impl<F: PrimeField> PoseidonParameters<F> {
+    /// Generate parameters for `F` providing `security_level` bits of security against the
+    /// known attacks on the permutation, for a state of `rate + capacity` elements.
+    ///
+    /// The S-box exponent is the smallest `alpha` for which `x^alpha` is a permutation of `F`,
+    /// the round numbers are the cheapest ones satisfying the security bounds of the Poseidon
+    /// paper (see [`find_poseidon_round_numbers`]), and the round constants and MDS matrix are
+    /// sampled with the Grain LFSR exactly as in the reference implementation.
+    ///
+    /// Note that the sponge itself only offers `capacity * log2(p) / 2` bits of security,
+    /// so `capacity` must be chosen accordingly.
+    ///
+    /// The size of the field is not a parameter: it is always that of `F`, with which the round
+    /// numbers and the Grain LFSR are computed, so that it cannot disagree with the field the
+    /// parameters are used over. The capacity is a parameter instead, since the security of the
+    /// sponge depends on it; [`PoseidonParameters::for_security_level`] derives it from the
+    /// security level and the size of `F`.
+    pub fn generate(rate: usize, capacity: usize, security_level: usize) -> Self {
+        Self::generate_with_alpha(rate, capacity, find_poseidon_alpha::<F>(), security_level)
     }
 
-    fn squeeze_bytes(&mut self, num_bytes: usize) -> Vec<u8> {
-        let usable_bytes = (F::Params::CAPACITY / 8) as usize;
+    /// Generates parameters for `F` with the given rate and security level, whose capacity is
+    /// [`SecurityLevel::capacity`] and whose round numbers are those of
+    /// [`PoseidonParameters::generate`] for the same level.
+    pub fn for_security_level(rate: usize, security_level: SecurityLevel) -> Self {
+        Self::generate(rate, security_level.capacity::<F>(), security_level.bits())
+    }
 
-        let num_elements = (num_bytes + usable_bytes - 1) / usable_bytes;
-        let src_elements = self.squeeze_native_field_elements(num_elements);
+    /// Generates parameters for a field of about 64 bits, such as `2^63 - 2^41 + 1`, with a
+    /// state of [`SMALL_FIELD_STATE_SIZE`] elements.
+    ///
+    /// A small field needs several elements of capacity, [`SecurityLevel::capacity`] of them,
+    /// e.g. 4 for 128 bits of security, so the state is wider than for a 255-bit field to keep
+    /// a useful rate: 8 elements at 128 bits, and 4 at 256 bits. The round numbers are
+    /// computed for the size of the field as in [`PoseidonParameters::generate`]. Words are
+    /// absorbed densely into such a sponge with
+    /// [`FieldBasedCryptographicSponge::absorb_u64s`].
+    ///
+    /// Note that the fields of `ark-ff` need a spare bit in their top limb, so the 64-bit
+    /// Goldilocks field `2^64 - 2^32 + 1` itself cannot be used.
+    pub fn for_small_field(security_level: SecurityLevel) -> Self {
+        let capacity = security_level.capacity::<F>();
+        assert!(
+            capacity < SMALL_FIELD_STATE_SIZE,
+            "the field is too small for the security level"
+        );
+        Self::generate(
+            SMALL_FIELD_STATE_SIZE - capacity,
+            capacity,
+            security_level.bits(),
+        )
+    }
 
-        let mut bytes: Vec<u8> = Vec::with_capacity(usable_bytes * num_elements);
-        for elem in &src_elements {
-            let elem_bytes = elem.into_repr().to_bytes_le();
-            bytes.extend_from_slice(&elem_bytes[..usable_bytes]);
+    /// Checks that these parameters offer `security_level`: the capacity must be at least
+    /// [`SecurityLevel::capacity`], and the round numbers must satisfy the security bounds of
+    /// the Poseidon paper, without the security margin added by [`find_poseidon_round_numbers`].
+    ///
+    /// Returns [`SpongeError::InvalidParameters`] otherwise, or if the S-box is the inverse,
+    /// whose bounds are not checked.
+    pub fn check_security_level(&self, security_level: SecurityLevel) -> Result<(), SpongeError> {
+        if self.capacity < security_level.capacity::<F>() {
+            return Err(SpongeError::InvalidParameters(
+                "the capacity is too small for the security level",
+            ));
         }
+        if self.alpha < 3 {
+            return Err(SpongeError::InvalidParameters(
+                "the security of the S-box cannot be checked",
+            ));
+        }
+        if !poseidon_round_numbers_are_secure::<F>(
+            self.state_size(),
+            self.alpha as u64,
+            security_level.bits(),
+            self.full_rounds,
+            self.partial_rounds,
+        ) {
+            return Err(SpongeError::InvalidParameters(
+                "the round numbers are too small for the security level",
+            ));
+        }
+        Ok(())
+    }
 
-        bytes.truncate(num_bytes);
-        bytes
+    /// Same as [`PoseidonParameters::generate`], but with the S-box `x^alpha` for a given `alpha`.
+    pub fn generate_with_alpha(
+        rate: usize,
+        capacity: usize,
+        alpha: u64,
+        security_level: usize,
+    ) -> Self {
+        let (full_rounds, partial_rounds) =
+            find_poseidon_round_numbers::<F>(rate + capacity, alpha, security_level, false);
+        let (ark, mds) = find_poseidon_ark_and_mds_with_state_size::<F>(
+            F::Params::MODULUS_BITS as u64,
+            rate + capacity,
+            full_rounds as u64,
+            partial_rounds as u64,
+            0,
+        );
+
+        Self::new(
+            full_rounds,
+            partial_rounds,
+            alpha as i64,
+            mds,
+            ark,
+            rate,
+            capacity,
+        )
     }
 
-    fn squeeze_bits(&mut self, num_bits: usize) -> Vec<bool> {
-        let usable_bits = F::Params::CAPACITY as usize;
+    /// Derives parameters for `F` like [`PoseidonParameters::generate`], but with round
+    /// constants and MDS matrix that depend on an application-chosen `seed`, typically a domain
+    /// and version string such as `b"my-protocol/v1"`.
+    ///
+    /// The seed is hashed with BLAKE2b and mixed into the Grain LFSR after its initialization, so
+    /// that different seeds give independent constants. The same seed always gives the same
+    /// parameters.
+    pub fn derive(seed: &[u8], rate: usize, capacity: usize, security_level: usize) -> Self {
+        let alpha = find_poseidon_alpha::<F>();
+        let (full_rounds, partial_rounds) =
+            find_poseidon_round_numbers::<F>(rate + capacity, alpha, security_level, false);
+        let mut lfsr = PoseidonGrainLFSR::new(
+            false,
+            F::Params::MODULUS_BITS as u64,
+            (rate + capacity) as u64,
+            full_rounds as u64,
+            partial_rounds as u64,
+        );
+        lfsr.mix_seed(seed);
+        let (ark, mds) = find_poseidon_ark_and_mds_with_lfsr::<F>(
+            &mut lfsr,
+            rate + capacity,
+            full_rounds as u64,
+            partial_rounds as u64,
+            0,
+        );
+
+        Self::new(
+            full_rounds,
+            partial_rounds,
+            alpha as i64,
+            mds,
+            ark,
+            rate,
+            capacity,
+        )
+    }
+}
+
+/// The default parameters are generated by [`PoseidonParameters::generate`] for a rate of 2, a
+/// capacity of 1, and 128 bits of security.
+impl<F: PrimeField> Default for PoseidonParameters<F> {
+    fn default() -> Self {
+        Self::generate(2, 1, 128)
+    }
+}
 
-        let num_elements = (num_bits + usable_bits - 1) / usable_bits;
-        let src_elements = self.squeeze_native_field_elements(num_elements);
+impl<F: PoseidonDefaultParametersField> PoseidonParameters<F> {
+    /// Returns the default parameters for `F` with the given rate, if `F` has any.
+    ///
+    /// This is a shorthand for [`PoseidonDefaultParametersField::get_default_poseidon_parameters`].
+    /// Defaults for the fields of common curves are provided in [`curves`] behind
+    /// features of the same name.
+    pub fn default_for(rate: usize, optimized_for_weights: bool) -> Option<Self> {
+        F::get_default_poseidon_parameters(rate, optimized_for_weights)
+    }
+}
+
+/// Zeroes the state, which may depend on secret inputs, and resets the sponge to absorbing.
+/// The parameters are public and left untouched.
+#[cfg(feature = "zeroize")]
+impl<F: PrimeField, S: PoseidonState<F>> zeroize::Zeroize for GenericPoseidonSponge<F, S> {
+    fn zeroize(&mut self) {
+        self.state
+            .as_mut()
+            .iter_mut()
+            .for_each(|elem| elem.zeroize());
+        self.mode = DuplexSpongeMode::Absorbing {
+            next_absorb_index: 0,
+        };
+        self.num_absorbed.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<F: PrimeField, S: PoseidonState<F>> Drop for GenericPoseidonSponge<F, S> {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<F: PrimeField, S: PoseidonState<F>> zeroize::ZeroizeOnDrop for GenericPoseidonSponge<F, S> {}
+
+impl<F: PrimeField, S: PoseidonState<F>> CryptographicSponge for GenericPoseidonSponge<F, S> {
+    type Parameters = PoseidonParameters<F>;
 
-        let mut bits: Vec<bool> = Vec::with_capacity(usable_bits * num_elements);
-        for elem in &src_elements {
-            let elem_bits = elem.into_repr().to_bits_le();
-            bits.extend_from_slice(&elem_bits[..usable_bits]);
+    fn new(parameters: &Self::Parameters) -> Self {
+        Self::with_padding(parameters, PaddingMode::None)
+    }
+
+    fn rate(&self) -> usize {
+        self.parameters.rate
+    }
+
+    fn capacity(&self) -> usize {
+        self.parameters.capacity
+    }
+
+    fn security_level(&self) -> usize {
+        field_sponge_security_level::<F>(self.parameters.capacity)
+    }
+
+    fn absorb(&mut self, input: &impl Absorb) {
+        let elems = input.to_sponge_field_elements_as_vec::<F>();
+        self.absorb_elements(&elems);
+    }
+
+    fn absorb_iter<A: Absorb>(&mut self, iter: impl IntoIterator<Item = A>) {
+        let mut elems = Vec::new();
+        for item in iter {
+            elems.clear();
+            item.to_sponge_field_elements(&mut elems);
+            self.absorb_elements(&elems);
         }
+    }
+
+    fn squeeze_bytes(&mut self, num_bytes: usize) -> Vec<u8> {
+        let src_elements =
+            self.squeeze_native_field_elements(squeeze_bytes_num_elements::<F>(num_bytes));
+        bytes_from_squeezed_elements(&src_elements, num_bytes)
+    }
 
-        bits.truncate(num_bits);
-        bits
+    fn squeeze_bits(&mut self, num_bits: usize) -> Vec<bool> {
+        let src_elements =
+            self.squeeze_native_field_elements(squeeze_bits_num_elements::<F>(num_bits));
+        bits_from_squeezed_elements(&src_elements, num_bits)
     }
 
     fn squeeze_field_elements_with_sizes<F2: PrimeField>(
@@ -317,20 +1214,36 @@ impl<F: PrimeField> CryptographicSponge for PoseidonSponge<F> {
     }
 }
 
-impl<F: PrimeField> FieldBasedCryptographicSponge<F> for PoseidonSponge<F> {
+impl<F: PrimeField, S: PoseidonState<F>> FieldBasedCryptographicSponge<F>
+    for GenericPoseidonSponge<F, S>
+{
+    fn absorb_native_field_elements(&mut self, elems: &[F]) {
+        self.absorb_elements(elems);
+    }
+
     fn squeeze_native_field_elements(&mut self, num_elements: usize) -> Vec<F> {
+        #[cfg(feature = "trace")]
+        tracing::trace!(
+            target: "ark_sponge",
+            sponge = "poseidon",
+            num_elements = num_elements,
+            mode = ?self.mode,
+            "squeeze"
+        );
+        #[cfg(feature = "stats")]
+        {
+            self.stats.num_squeezed += num_elements as u64;
+        }
         let mut squeezed_elems = vec![F::zero(); num_elements];
         match self.mode {
-            DuplexSpongeMode::Absorbing {
-                next_absorb_index: _,
-            } => {
-                self.permute();
+            DuplexSpongeMode::Absorbing { .. } => {
+                self.finish_absorb();
                 self.squeeze_internal(0, &mut squeezed_elems);
             }
             DuplexSpongeMode::Squeezing { next_squeeze_index } => {
                 let mut squeeze_index = next_squeeze_index;
                 if squeeze_index == self.parameters.rate {
-                    self.permute();
+                    self.apply_permutation();
                     squeeze_index = 0;
                 }
                 self.squeeze_internal(squeeze_index, &mut squeezed_elems);
@@ -341,38 +1254,215 @@ impl<F: PrimeField> FieldBasedCryptographicSponge<F> for PoseidonSponge<F> {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 /// Stores the state of a Poseidon Sponge. Does not store any parameter.
+///
+/// The state can be serialized to checkpoint a transcript and resumed later with
+/// [`SpongeExt::from_state`], using the same parameters. The serialization is, in order:
+/// - the version byte [`SPONGE_STATE_VERSION`],
+/// - the [`DuplexSpongeMode`], as a tag byte (`0` for absorbing and `1` for squeezing) followed
+///   by the index as a little-endian `u64`,
+/// - the [`PaddingMode`], as a tag byte,
+/// - the [`AbsorbMode`], as a tag byte,
+/// - the number of elements absorbed since the last squeeze, as a little-endian `u64`,
+/// - the state, as a little-endian `u64` length followed by the canonical serialization of its
+///   elements.
 pub struct PoseidonSpongeState<F: PrimeField> {
     state: Vec<F>,
     mode: DuplexSpongeMode,
+    padding: PaddingMode,
+    absorb_mode: AbsorbMode,
+    num_absorbed: usize,
+}
+
+impl<F: PrimeField> CanonicalSerialize for PoseidonSpongeState<F> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        SPONGE_STATE_VERSION.serialize(&mut writer)?;
+        self.mode.serialize(&mut writer)?;
+        self.padding.serialize(&mut writer)?;
+        self.absorb_mode.serialize(&mut writer)?;
+        self.num_absorbed.serialize(&mut writer)?;
+        self.state.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        SPONGE_STATE_VERSION.serialized_size()
+            + self.mode.serialized_size()
+            + self.padding.serialized_size()
+            + self.absorb_mode.serialized_size()
+            + self.num_absorbed.serialized_size()
+            + self.state.serialized_size()
+    }
+}
+
+impl<F: PrimeField> CanonicalDeserialize for PoseidonSpongeState<F> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        deserialize_state_version(&mut reader)?;
+        Ok(Self {
+            mode: DuplexSpongeMode::deserialize(&mut reader)?,
+            padding: PaddingMode::deserialize(&mut reader)?,
+            absorb_mode: AbsorbMode::deserialize(&mut reader)?,
+            num_absorbed: usize::deserialize(&mut reader)?,
+            state: Vec::deserialize(&mut reader)?,
+        })
+    }
 }
 
 impl<CF: PrimeField> SpongeExt for PoseidonSponge<CF> {
     type State = PoseidonSpongeState<CF>;
 
+    /// # Panics
+    ///
+    /// Panics if `state` does not fit `params`, as checked by [`Self::try_from_state`].
     fn from_state(state: Self::State, params: &Self::Parameters) -> Self {
-        let mut sponge = Self::new(params);
+        Self::try_from_state(state, params).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Returns [`SpongeError::InvalidState`] if `state` does not have `rate + capacity` elements,
+    /// or if the index of its mode is beyond the rate.
+    fn try_from_state(state: Self::State, params: &Self::Parameters) -> Result<Self, SpongeError> {
+        check_sponge_state(state.state.len(), &state.mode, params.rate, params.capacity)?;
+        let mut sponge =
+            Self::with_padding(params, state.padding).with_absorb_mode(state.absorb_mode);
         sponge.mode = state.mode;
         sponge.state = state.state;
-        sponge
+        sponge.num_absorbed = state.num_absorbed;
+        Ok(sponge)
     }
 
-    fn into_state(self) -> Self::State {
+    fn into_state(mut self) -> Self::State {
         Self::State {
-            state: self.state,
-            mode: self.mode,
+            state: ark_std::mem::take(&mut self.state),
+            mode: self.mode.clone(),
+            padding: self.padding,
+            absorb_mode: self.absorb_mode,
+            num_absorbed: self.num_absorbed,
         }
     }
 }
 
+/// Hashes `input` with a [`PoseidonSponge`]: the elements of `input` are absorbed, and the hash is
+/// the first squeezed native field element.
+pub fn poseidon_hash<F: PrimeField, A: Absorb>(
+    parameters: &PoseidonParameters<F>,
+    input: &[A],
+) -> F {
+    let mut sponge = PoseidonSponge::new(parameters);
+    sponge.absorb(&input);
+    sponge.squeeze_native_field_elements(1)[0]
+}
+
+/// Hashes each of `inputs` as [`poseidon_hash`], permuting the states of all the hashes together
+/// with [`FieldPermutation::permute_batch`].
+///
+/// This is faster than hashing the inputs one by one when there are many of them, as when
+/// building a Merkle tree, and gives the same outputs.
+pub fn poseidon_hash_batch<F: PrimeField, A: Absorb, I: AsRef<[A]>>(
+    parameters: &PoseidonParameters<F>,
+    inputs: &[I],
+) -> Vec<F> {
+    let inputs: Vec<Vec<F>> = inputs
+        .iter()
+        .map(|input| input.as_ref().to_sponge_field_elements_as_vec())
+        .collect();
+    hash_field_elements_batch(&PoseidonPermutation::new(parameters), &inputs)
+}
+
+/// Hashes each of `inputs`, which are native field elements, as [`poseidon_hash`].
+fn hash_field_elements_batch<F: PrimeField, I: AsRef<[F]>>(
+    permutation: &PoseidonPermutation<F>,
+    inputs: &[I],
+) -> Vec<F> {
+    let parameters = permutation.parameters();
+    let (rate, capacity) = (parameters.rate, parameters.capacity);
+    let mut states = vec![vec![F::zero(); parameters.state_size()]; inputs.len()];
+
+    // The sponge permutes once per block of `rate` elements, and once for an empty input.
+    let num_blocks = |input: &I| ark_std::cmp::max(1, input.as_ref().len().div_ceil(rate));
+    let max_num_blocks = inputs.iter().map(num_blocks).max().unwrap_or(0);
+    for block in 0..max_num_blocks {
+        let mut active: Vec<usize> = Vec::new();
+        for (i, input) in inputs.iter().enumerate() {
+            if block < num_blocks(input) {
+                let block_elems = input.as_ref().iter().skip(block * rate).take(rate);
+                for (state_elem, elem) in states[i][capacity..].iter_mut().zip(block_elems) {
+                    *state_elem += elem;
+                }
+                active.push(i);
+            }
+        }
+
+        let mut active_states: Vec<Vec<F>> = active
+            .iter()
+            .map(|&i| ark_std::mem::take(&mut states[i]))
+            .collect();
+        permutation.permute_batch(&mut active_states);
+        for (&i, state) in active.iter().zip(active_states) {
+            states[i] = state;
+        }
+    }
+
+    states.into_iter().map(|state| state[capacity]).collect()
+}
+
+/// The Poseidon permutation as a [`FieldPermutation`], for the constructions of this crate that
+/// are generic over a permutation.
+///
+/// The permutation is evaluated exactly as in [`PoseidonSponge`], so a [`DuplexSponge`] over it
+/// gives the same outputs as a [`PoseidonSponge`] with the same parameters.
+///
+/// [`DuplexSponge`]: crate::duplex::DuplexSponge
+#[derive(Clone)]
+pub struct PoseidonPermutation<F: PrimeField> {
+    sponge: PoseidonSponge<F>,
+}
+
+impl<F: PrimeField> PoseidonPermutation<F> {
+    /// Creates the permutation for the given parameters.
+    pub fn new(parameters: &PoseidonParameters<F>) -> Self {
+        Self {
+            sponge: PoseidonSponge::new(parameters),
+        }
+    }
+
+    /// Returns the parameters of the permutation.
+    pub fn parameters(&self) -> &PoseidonParameters<F> {
+        &self.sponge.parameters
+    }
+}
+
+impl<F: PrimeField> FieldPermutation<F> for PoseidonPermutation<F> {
+    fn rate(&self) -> usize {
+        self.sponge.parameters.rate
+    }
+
+    fn capacity(&self) -> usize {
+        self.sponge.parameters.capacity
+    }
+
+    fn permute(&self, state: &mut [F]) {
+        let permuted = self.sponge.permute_state(state.to_vec());
+        state.copy_from_slice(&permuted);
+    }
+
+    /// The states are permuted together, round by round, and in parallel with the `parallel`
+    /// feature.
+    fn permute_batch(&self, states: &mut [Vec<F>]) {
+        self.sponge.permute_states(states)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::poseidon::{
         PoseidonDefaultParameters, PoseidonDefaultParametersEntry, PoseidonDefaultParametersField,
     };
+    use crate::poseidon::{PoseidonParameters, PoseidonRounds};
     use crate::{poseidon::PoseidonSponge, CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_ff::UniformRand;
     use ark_ff::{field_new, BigInteger256, FftParameters, Fp256, Fp256Parameters, FpParameters};
+    use ark_std::test_rng;
+    use ark_std::vec;
     use ark_test_curves::bls12_381::FrParameters;
 
     pub struct TestFrParameters;
@@ -423,6 +1513,55 @@ mod test {
 
     pub type TestFr = Fp256<TestFrParameters>;
 
+    #[test]
+    fn test_generated_parameters_match_default_parameters() {
+        let default = TestFr::get_default_poseidon_parameters(2, false).unwrap();
+        let generated = PoseidonParameters::<TestFr>::generate_with_alpha(2, 1, 17, 128);
+        assert_eq!(generated.full_rounds, default.full_rounds);
+        assert_eq!(generated.partial_rounds, default.partial_rounds);
+        assert_eq!(generated.ark, default.ark);
+        assert_eq!(generated.mds, default.mds);
+
+        let generated = PoseidonParameters::<TestFr>::generate(3, 2, 128);
+        assert_eq!(generated.alpha, 5);
+        assert_eq!(generated.ark[0].len(), 5);
+        assert_eq!(generated.mds.len(), 5);
+
+        let mut sponge = PoseidonSponge::<TestFr>::new(&generated);
+        sponge.absorb(&vec![TestFr::from(1u8), TestFr::from(2u8)]);
+        assert_eq!(sponge.squeeze_native_field_elements(4).len(), 4);
+    }
+
+    #[test]
+    fn test_optimized_permutation() {
+        let mut rng = test_rng();
+        let mut inverse_s_box = PoseidonParameters::<TestFr>::generate(4, 1, 128);
+        inverse_s_box.alpha = -1;
+
+        for parameters in [
+            TestFr::get_default_poseidon_parameters(2, false).unwrap(),
+            TestFr::get_default_poseidon_parameters(8, true).unwrap(),
+            PoseidonParameters::<TestFr>::generate(3, 2, 128),
+            inverse_s_box,
+        ] {
+            let mut sponge = PoseidonSponge::<TestFr>::new(&parameters);
+            assert!(parameters.rounds().optimized_parameters.is_some());
+            sponge.state = (0..parameters.state_size())
+                .map(|_| TestFr::rand(&mut rng))
+                .collect();
+
+            let mut unoptimized_state = sponge.state.clone();
+            PoseidonRounds {
+                parameters: &parameters,
+                optimized_parameters: None,
+            }
+            .permute_interleaved(ark_std::slice::from_mut(&mut unoptimized_state));
+
+            sponge.permute();
+            assert_eq!(sponge.state, unoptimized_state);
+        }
+    }
+
     #[test]
     fn test_poseidon_sponge_consistency() {
         let sponge_param = TestFr::get_default_poseidon_parameters(2, false).unwrap();
@@ -0,0 +1,197 @@
+use crate::poseidon::grain_lfsr::PoseidonGrainLFSR;
+use crate::poseidon::round_numbers::{ceil, log2};
+use crate::poseidon::PoseidonParameters;
+use crate::SpongeError;
+use ark_ff::{FpParameters, PrimeField};
+use ark_std::vec::Vec;
+
+/// The strength of the round numbers of neptune.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NeptuneStrength {
+    /// The round numbers of the reference implementation for 128 bits of security.
+    Standard,
+    /// The standard round numbers with 25% more partial rounds, as a margin against new attacks.
+    Strengthened,
+}
+
+/// The hash types of neptune, which set the domain tag in the first element of the state.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NeptuneHashType {
+    /// A node of a Merkle tree, whose tag is `2^arity - 1`.
+    MerkleTree,
+    /// A preimage of the given length, at most the arity, whose tag is `length * 2^64`.
+    ConstantLength(usize),
+    /// Encryption, whose tag is `2^32`.
+    Encryption,
+    /// A custom hash type with an identifier between 1 and 256, whose tag is `identifier * 2^40`.
+    Custom(u64),
+}
+
+impl NeptuneHashType {
+    /// Returns the domain tag of the hash type for the given arity.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the identifier of a custom hash type is not between 1 and 256.
+    pub fn domain_tag<F: PrimeField>(&self, arity: usize) -> F {
+        let two = F::from(2u64);
+        match self {
+            NeptuneHashType::MerkleTree => two.pow([arity as u64]) - F::one(),
+            NeptuneHashType::ConstantLength(length) => F::from(*length as u64) * two.pow([64u64]),
+            NeptuneHashType::Encryption => two.pow([32u64]),
+            NeptuneHashType::Custom(identifier) => {
+                assert!(
+                    (1..=256).contains(identifier),
+                    "the identifier of a custom hash type must be between 1 and 256"
+                );
+                F::from(*identifier) * two.pow([40u64])
+            }
+        }
+    }
+}
+
+/// Computes the round numbers of neptune for a state of `state_size` elements, with the
+/// security inequalities and margin of the reference implementation for a 256-bit field and
+/// 128 bits of security.
+fn neptune_round_numbers(state_size: usize, strength: NeptuneStrength) -> (usize, usize) {
+    // neptune computes in single precision, which decides the rounding at the boundaries.
+    let (n, m, t) = (256f32, 128f32, state_size as f32);
+    let is_secure = |full_rounds: usize, partial_rounds: usize| -> bool {
+        let r_p = partial_rounds as f32;
+        let r_f_stat = if m <= (n - 3.0) * (t + 1.0) {
+            6.0
+        } else {
+            10.0
+        };
+        let r_f_interp = 0.43 * m + log2(t as f64) as f32 - r_p;
+        let r_f_grob_1 = 0.21 * n - r_p;
+        let r_f_grob_2 = (0.14 * n - 1.0 - r_p) / (t - 1.0);
+        let r_f_max = [r_f_stat, r_f_interp, r_f_grob_1, r_f_grob_2]
+            .iter()
+            .map(|bound| ceil(*bound as f64) as usize)
+            .max()
+            .unwrap();
+        full_rounds >= r_f_max
+    };
+
+    let mut best = (0, 0);
+    let mut min_cost = usize::MAX;
+    for full_rounds in (2..=1000).step_by(2) {
+        for partial_rounds in 4..200 {
+            if !is_secure(full_rounds, partial_rounds) {
+                continue;
+            }
+
+            // add the security margin
+            let full_rounds = full_rounds + 2;
+            let partial_rounds = ceil((1.075 * partial_rounds as f32) as f64) as usize;
+
+            let cost = state_size * full_rounds + partial_rounds;
+            if cost < min_cost || (cost == min_cost && full_rounds < best.0) {
+                best = (full_rounds, partial_rounds);
+                min_cost = cost;
+            }
+        }
+    }
+
+    match strength {
+        NeptuneStrength::Standard => best,
+        NeptuneStrength::Strengthened => (best.0, ceil(best.1 as f64 * 1.25) as usize),
+    }
+}
+
+impl<F: PrimeField> PoseidonParameters<F> {
+    /// Returns the parameters of the Poseidon instance of
+    /// [neptune](https://github.com/lurk-lab/neptune), the implementation of Filecoin, for
+    /// `arity` inputs and the given strength. The state has `arity + 1` elements, the first of
+    /// which holds the domain tag of a [`NeptuneHashType`]; the S-box is `x^5`, the round
+    /// constants are derived with the Grain LFSR as neptune does, and the MDS matrix is the
+    /// Cauchy matrix `1 / (i + j + arity + 1)`.
+    ///
+    /// Over the scalar field of BLS12-381, [`neptune_poseidon_hash`] with these parameters gives
+    /// the same hashes as neptune.
+    ///
+    /// Returns [`SpongeError::InvalidParameters`] if `arity` is zero.
+    pub fn neptune(arity: usize, strength: NeptuneStrength) -> Result<Self, SpongeError> {
+        if arity == 0 {
+            return Err(SpongeError::InvalidParameters(
+                "neptune requires at least one input",
+            ));
+        }
+        let state_size = arity + 1;
+        let (full_rounds, partial_rounds) = neptune_round_numbers(state_size, strength);
+
+        // neptune marks its S-box as an inverse when seeding the LFSR, unlike the reference
+        // implementation, so its round constants differ from those of `find_poseidon_ark_and_mds`.
+        let mut lfsr = PoseidonGrainLFSR::new(
+            true,
+            F::Params::MODULUS_BITS as u64,
+            state_size as u64,
+            full_rounds as u64,
+            partial_rounds as u64,
+        );
+        let ark = (0..full_rounds + partial_rounds)
+            .map(|_| lfsr.get_field_elements_rejection_sampling(state_size))
+            .collect();
+
+        let mds = (0..state_size)
+            .map(|i| {
+                (0..state_size)
+                    .map(|j| F::from((i + j + state_size) as u64).inverse().unwrap())
+                    .collect()
+            })
+            .collect();
+
+        Ok(Self::new(
+            full_rounds,
+            partial_rounds,
+            5,
+            mds,
+            ark,
+            arity,
+            1,
+        ))
+    }
+}
+
+/// Hashes `input` as neptune, with parameters given by [`PoseidonParameters::neptune`]: the state
+/// is the domain tag of `hash_type` followed by the input, padded with zeros to the arity, and
+/// the hash is the second element of the permuted state.
+///
+/// This is not a sponge, and the hash differs from [`poseidon_hash`](crate::poseidon::poseidon_hash).
+///
+/// # Panics
+///
+/// Panics if `parameters` do not have a capacity of 1, if `input` is longer than the rate of
+/// `parameters`, or if its length is not the one required by `hash_type`: the arity for
+/// [`NeptuneHashType::MerkleTree`], and the given length for
+/// [`NeptuneHashType::ConstantLength`].
+pub fn neptune_poseidon_hash<F: PrimeField>(
+    parameters: &PoseidonParameters<F>,
+    hash_type: NeptuneHashType,
+    input: &[F],
+) -> F {
+    assert!(
+        parameters.capacity == 1 && input.len() <= parameters.rate,
+        "the parameters must be those of neptune for an arity of at least the input length"
+    );
+    match hash_type {
+        NeptuneHashType::MerkleTree => assert_eq!(
+            input.len(),
+            parameters.rate,
+            "a Merkle tree node must have as many inputs as the arity"
+        ),
+        NeptuneHashType::ConstantLength(length) => assert_eq!(
+            input.len(),
+            length,
+            "the input must have the constant length of the hash type"
+        ),
+        _ => {}
+    }
+    let mut state = Vec::with_capacity(parameters.state_size());
+    state.push(hash_type.domain_tag(parameters.rate));
+    state.extend_from_slice(input);
+    state.resize(parameters.state_size(), F::zero());
+    parameters.permute(&mut state);
+    state[1]
+}
@@ -0,0 +1,280 @@
+use crate::poseidon::PoseidonParameters;
+use ark_ff::PrimeField;
+use ark_std::vec;
+use ark_std::vec::Vec;
+
+/// A sparse matrix of the form `[[m_00, row], [column, I]]`, where `I` is the identity matrix.
+#[derive(Clone, Debug)]
+pub(crate) struct SparseMdsMatrix<F: PrimeField> {
+    /// The top left entry.
+    pub(crate) m_00: F,
+    /// The first row, without its first entry.
+    pub(crate) row: Vec<F>,
+    /// The first column, without its first entry.
+    pub(crate) column: Vec<F>,
+}
+
+impl<F: PrimeField> SparseMdsMatrix<F> {
+    /// Computes the product of this matrix with `state`, with `2t - 1` multiplications instead
+    /// of `t^2`.
+    pub(crate) fn apply(&self, state: &mut [F]) {
+        let first = state[0];
+        let mut new_first = self.m_00 * first;
+        for ((state_elem, row_elem), column_elem) in
+            state[1..].iter_mut().zip(&self.row).zip(&self.column)
+        {
+            new_first += *row_elem * *state_elem;
+            *state_elem += *column_elem * first;
+        }
+        state[0] = new_first;
+    }
+}
+
+/// An equivalent representation of the Poseidon permutation, following Appendix B of the
+/// [Poseidon paper](https://eprint.iacr.org/2019/458.pdf), in which every partial round adds
+/// a single round constant and multiplies the state with a [`SparseMdsMatrix`].
+///
+/// It is obtained from [`PoseidonParameters`] with two transformations:
+/// * Since the partial S-box leaves all but the first element unchanged, the round constants of
+///   these elements are moved through the MDS matrix into the constants of the next round.
+/// * Going backwards, the MDS matrix `M` of each partial round is factored as `M'' * M'`, where
+///   `M''` is sparse and `M' = diag(1, M_hat)` commutes with the partial round. `M'` is then
+///   merged into the matrix of the previous round, which is factored in turn, until it is
+///   absorbed by the last full round before the partial rounds.
+#[derive(Clone, Debug)]
+pub(crate) struct PoseidonOptimizedParameters<F: PrimeField> {
+    /// Round constants of the full rounds.
+    pub(crate) full_ark: Vec<Vec<F>>,
+    /// The matrix of the last full round before the partial rounds.
+    pub(crate) pre_sparse_mds: Vec<Vec<F>>,
+    /// Round constants of the partial rounds, which are added to the first element only.
+    pub(crate) partial_ark: Vec<F>,
+    /// Matrices of the partial rounds.
+    pub(crate) sparse_mds: Vec<SparseMdsMatrix<F>>,
+}
+
+/// The optimized form of the permutation of some [`PoseidonParameters`], with the numbers of
+/// rounds, the round constants and the MDS matrix it was derived from, so that it is not used
+/// once the public fields of the parameters are modified.
+#[derive(Debug)]
+pub(crate) struct DerivedOptimizedParameters<F: PrimeField> {
+    full_rounds: usize,
+    partial_rounds: usize,
+    ark: Vec<Vec<F>>,
+    mds: Vec<Vec<F>>,
+    /// The optimized form, if it exists for these constants.
+    optimized_parameters: Option<PoseidonOptimizedParameters<F>>,
+}
+
+impl<F: PrimeField> DerivedOptimizedParameters<F> {
+    /// Derives the optimized form of the permutation of `parameters`.
+    pub(crate) fn derive(parameters: &PoseidonParameters<F>) -> Self {
+        Self {
+            full_rounds: parameters.full_rounds,
+            partial_rounds: parameters.partial_rounds,
+            ark: parameters.ark.clone(),
+            mds: parameters.mds.clone(),
+            optimized_parameters: PoseidonOptimizedParameters::new(parameters),
+        }
+    }
+
+    /// Returns whether `parameters` have the constants this was derived from.
+    pub(crate) fn is_derived_from(&self, parameters: &PoseidonParameters<F>) -> bool {
+        self.full_rounds == parameters.full_rounds
+            && self.partial_rounds == parameters.partial_rounds
+            && self.mds == parameters.mds
+            && self.ark == parameters.ark
+    }
+
+    /// Returns the optimized form of the permutation of `parameters`, if it exists and they have
+    /// the constants this was derived from.
+    pub(crate) fn get(
+        &self,
+        parameters: &PoseidonParameters<F>,
+    ) -> Option<&PoseidonOptimizedParameters<F>> {
+        if self.is_derived_from(parameters) {
+            self.optimized_parameters.as_ref()
+        } else {
+            None
+        }
+    }
+}
+
+impl<F: PrimeField> PoseidonOptimizedParameters<F> {
+    /// Derives the optimized representation of the permutation, or returns `None` if it does not
+    /// exist for `parameters`. This is the case if the permutation does not start with a full
+    /// round, or if a submatrix of the MDS matrix that needs to be inverted is singular, which
+    /// never happens for a true MDS matrix.
+    pub(crate) fn new(parameters: &PoseidonParameters<F>) -> Option<Self> {
+        let full_rounds_over_2 = parameters.full_rounds / 2;
+        let partial_rounds = parameters.partial_rounds;
+        let mds = &parameters.mds;
+        if full_rounds_over_2 == 0 || mds.len() < 2 {
+            return None;
+        }
+
+        // Move the round constants of all but the first element of a partial round into the
+        // next round.
+        let mut ark = parameters.ark.clone();
+        for round in full_rounds_over_2..(full_rounds_over_2 + partial_rounds) {
+            let mut rest = ark[round].clone();
+            rest[0] = F::zero();
+            for (next, moved) in ark[round + 1].iter_mut().zip(mat_vec_mul(mds, &rest)) {
+                *next += moved;
+            }
+        }
+
+        // Factor the matrices of the partial rounds, from the last to the first.
+        let mut sparse_mds = Vec::with_capacity(partial_rounds);
+        let mut current = mds.clone();
+        for _ in 0..partial_rounds {
+            let m_hat: Vec<Vec<F>> = current[1..].iter().map(|row| row[1..].to_vec()).collect();
+            let m_hat_inverse = invert(&m_hat)?;
+            let row = vec_mat_mul(&current[0][1..], &m_hat_inverse);
+            let column = current[1..].iter().map(|row| row[0]).collect();
+            sparse_mds.push(SparseMdsMatrix {
+                m_00: current[0][0],
+                row,
+                column,
+            });
+
+            let mut m_prime = identity(mds.len());
+            for (i, m_hat_row) in m_hat.into_iter().enumerate() {
+                m_prime[i + 1][1..].clone_from_slice(&m_hat_row);
+            }
+            current = mat_mul(&m_prime, mds);
+        }
+        sparse_mds.reverse();
+
+        let partial_ark = ark[full_rounds_over_2..(full_rounds_over_2 + partial_rounds)]
+            .iter()
+            .map(|constants| constants[0])
+            .collect();
+        let full_ark = ark[..full_rounds_over_2]
+            .iter()
+            .chain(&ark[(full_rounds_over_2 + partial_rounds)..])
+            .cloned()
+            .collect();
+
+        Some(Self {
+            full_ark,
+            pre_sparse_mds: current,
+            partial_ark,
+            sparse_mds,
+        })
+    }
+}
+
+fn identity<F: PrimeField>(size: usize) -> Vec<Vec<F>> {
+    (0..size)
+        .map(|i| {
+            let mut row = vec![F::zero(); size];
+            row[i] = F::one();
+            row
+        })
+        .collect()
+}
+
+fn mat_vec_mul<F: PrimeField>(matrix: &[Vec<F>], vector: &[F]) -> Vec<F> {
+    matrix
+        .iter()
+        .map(|row| row.iter().zip(vector).map(|(a, b)| *a * b).sum())
+        .collect()
+}
+
+fn vec_mat_mul<F: PrimeField>(vector: &[F], matrix: &[Vec<F>]) -> Vec<F> {
+    (0..matrix[0].len())
+        .map(|j| vector.iter().zip(matrix).map(|(a, row)| *a * row[j]).sum())
+        .collect()
+}
+
+pub(crate) fn mat_mul<F: PrimeField>(a: &[Vec<F>], b: &[Vec<F>]) -> Vec<Vec<F>> {
+    a.iter().map(|row| vec_mat_mul(row, b)).collect()
+}
+
+/// Inverts a square matrix with Gauss-Jordan elimination, returning `None` if it is singular.
+pub(crate) fn invert<F: PrimeField>(matrix: &[Vec<F>]) -> Option<Vec<Vec<F>>> {
+    let size = matrix.len();
+    let mut left = matrix.to_vec();
+    let mut right = identity::<F>(size);
+
+    for column in 0..size {
+        let pivot = (column..size).find(|&row| !left[row][column].is_zero())?;
+        left.swap(column, pivot);
+        right.swap(column, pivot);
+
+        let pivot_inverse = left[column][column].inverse().unwrap();
+        for elem in left[column].iter_mut().chain(right[column].iter_mut()) {
+            *elem *= pivot_inverse;
+        }
+
+        for row in 0..size {
+            if row == column || left[row][column].is_zero() {
+                continue;
+            }
+            let factor = left[row][column];
+            for j in 0..size {
+                let left_elem = left[column][j];
+                let right_elem = right[column][j];
+                left[row][j] -= factor * left_elem;
+                right[row][j] -= factor * right_elem;
+            }
+        }
+    }
+
+    Some(right)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{identity, invert, mat_mul, PoseidonOptimizedParameters};
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::PoseidonParameters;
+    use ark_ff::{One, Zero};
+    use ark_std::vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn test_invert() {
+        let parameters = poseidon_parameters_for_test::<Fr>();
+        let inverse = invert(&parameters.mds).unwrap();
+        assert_eq!(mat_mul(&parameters.mds, &inverse), identity(3));
+
+        let singular = vec![vec![Fr::one(), Fr::one()], vec![Fr::one(), Fr::one()]];
+        assert!(invert(&singular).is_none());
+    }
+
+    #[test]
+    fn test_unsupported_parameters() {
+        let mut parameters = PoseidonParameters::<Fr>::generate(2, 1, 128);
+        parameters.mds[1][1] = Fr::zero();
+        parameters.mds[1][2] = Fr::zero();
+        assert!(PoseidonOptimizedParameters::new(&parameters).is_none());
+
+        let mut parameters = PoseidonParameters::<Fr>::generate(2, 1, 128);
+        parameters.full_rounds = 0;
+        assert!(PoseidonOptimizedParameters::new(&parameters).is_none());
+    }
+
+    #[test]
+    fn test_cache() {
+        let mut parameters = PoseidonParameters::<Fr>::generate(2, 1, 128);
+        assert!(parameters.rounds().optimized_parameters.is_some());
+
+        // the optimized form is not used once the MDS matrix is modified, until it is derived
+        // again
+        let original = parameters.clone();
+        parameters.mds[1][1] += Fr::one();
+        assert!(parameters.rounds().optimized_parameters.is_none());
+        parameters.update_optimized_parameters();
+        assert!(parameters.rounds().optimized_parameters.is_some());
+
+        parameters.mds[1][1] = Fr::zero();
+        parameters.mds[1][2] = Fr::zero();
+        parameters.update_optimized_parameters();
+        assert!(parameters.rounds().optimized_parameters.is_none());
+
+        // the optimized form of the original parameters is still used by their clones
+        assert!(original.rounds().optimized_parameters.is_some());
+    }
+}
@@ -0,0 +1,243 @@
+use ark_ff::{BigInteger, FpParameters, PrimeField};
+
+/// Computes the number of full and partial rounds for a Poseidon instance over `F` with state
+/// size `state_size` and S-box `x^alpha`, providing `security_level` bits of security.
+///
+/// This follows the round number computation of the
+/// [reference implementation](https://extgit.iaik.tugraz.at/krypto/hadeshash/-/blob/master/code/calc_round_numbers.py),
+/// including its security margin (two more full rounds and 7.5% more partial rounds).
+/// The returned pair `(full_rounds, partial_rounds)` minimizes the number of S-boxes, or the
+/// total S-box size in bits if `optimized_for_weights` is true.
+pub fn find_poseidon_round_numbers<F: PrimeField>(
+    state_size: usize,
+    alpha: u64,
+    security_level: usize,
+    optimized_for_weights: bool,
+) -> (usize, usize) {
+    assert!(alpha >= 3, "alpha must be at least 3");
+    assert!(
+        state_size >= 2,
+        "the state must contain at least two elements"
+    );
+
+    let is_secure = |full_rounds: usize, partial_rounds: usize| -> bool {
+        poseidon_round_numbers_are_secure::<F>(
+            state_size,
+            alpha,
+            security_level,
+            full_rounds,
+            partial_rounds,
+        )
+    };
+
+    let cost = |full_rounds: usize, partial_rounds: usize| -> usize {
+        let num_s_boxes = full_rounds * state_size + partial_rounds;
+        if optimized_for_weights {
+            num_s_boxes * F::Params::MODULUS_BITS as usize
+        } else {
+            num_s_boxes
+        }
+    };
+
+    let mut best = (0, 0);
+    let mut min_cost = usize::MAX;
+    for partial_rounds in 1..500 {
+        for full_rounds in (4..100).step_by(2) {
+            if !is_secure(full_rounds, partial_rounds) {
+                continue;
+            }
+
+            // add the security margin
+            let full_rounds = full_rounds + 2;
+            let partial_rounds = ceil(partial_rounds as f64 * 1.075) as usize;
+
+            let cur_cost = cost(full_rounds, partial_rounds);
+            if cur_cost < min_cost || (cur_cost == min_cost && full_rounds < best.0) {
+                best = (full_rounds, partial_rounds);
+                min_cost = cur_cost;
+            }
+        }
+    }
+
+    best
+}
+
+/// Returns whether `full_rounds` full rounds and `partial_rounds` partial rounds resist the
+/// statistical, interpolation and Gröbner basis attacks of the Poseidon paper with
+/// `security_level` bits of security, without any security margin.
+pub(crate) fn poseidon_round_numbers_are_secure<F: PrimeField>(
+    state_size: usize,
+    alpha: u64,
+    security_level: usize,
+    full_rounds: usize,
+    partial_rounds: usize,
+) -> bool {
+    let field_size = F::Params::MODULUS_BITS as f64;
+    let log_p = log2_of_modulus::<F>();
+    let t = state_size as f64;
+    let alpha = alpha as f64;
+    let m = security_level as f64;
+    let r_f = full_rounds as f64;
+    let r_p = partial_rounds as f64;
+    let log_alpha_2 = 1.0 / log2(alpha);
+
+    // statistical attacks
+    let r_f_1 = if m <= floor(log_p - (alpha - 1.0) / 2.0) * (t + 1.0) {
+        6.0
+    } else {
+        10.0
+    };
+    // interpolation attacks
+    let r_f_2 = 1.0 + ceil(log_alpha_2 * min(m, field_size)) + ceil(log2(t) * log_alpha_2) - r_p;
+    // Gröbner basis attacks
+    let r_f_3 = log_alpha_2 * min(m, log_p) - r_p;
+    let r_f_4 = t - 1.0 + log_alpha_2 * min(m / (t + 1.0), log_p / 2.0) - r_p;
+    let r_f_5 = (t - 2.0 + m / (2.0 * log2(alpha)) - r_p) / (t - 1.0);
+
+    let r_f_max = [r_f_1, r_f_2, r_f_3, r_f_4, r_f_5]
+        .iter()
+        .map(|bound| ceil(*bound))
+        .fold(f64::MIN, max);
+    r_f >= r_f_max
+}
+
+/// Returns the smallest `alpha >= 3` such that `x^alpha` is a permutation of `F`,
+/// i.e., such that `gcd(alpha, p - 1) = 1`.
+pub fn find_poseidon_alpha<F: PrimeField>() -> u64 {
+    let mut modulus_minus_one = F::Params::MODULUS;
+    modulus_minus_one.sub_noborrow(&F::BigInt::from(1));
+
+    (3u64..)
+        .find(|alpha| gcd(*alpha, mod_small(&modulus_minus_one, *alpha)) == 1)
+        .unwrap()
+}
+
+fn mod_small<B: BigInteger>(value: &B, modulus: u64) -> u64 {
+    value.as_ref().iter().rev().fold(0u64, |acc, limb| {
+        (((acc as u128) << 64 | *limb as u128) % modulus as u128) as u64
+    })
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn log2_of_modulus<F: PrimeField>() -> f64 {
+    let modulus = F::Params::MODULUS;
+    let value = modulus
+        .as_ref()
+        .iter()
+        .rev()
+        .fold(0.0, |acc, limb| acc * 18446744073709551616.0 + *limb as f64);
+    log2(value)
+}
+
+// The following are minimal replacements for the floating point functions of `std`,
+// which are not available in `no_std` environments. Their inputs are always finite and well
+// within the range of an `i64`.
+
+pub(crate) fn log2(mut x: f64) -> f64 {
+    assert!(x > 0.0);
+    let mut result = 0.0;
+    while x >= 2.0 {
+        x /= 2.0;
+        result += 1.0;
+    }
+    while x < 1.0 {
+        x *= 2.0;
+        result -= 1.0;
+    }
+
+    // x is now in [1, 2); compute the fractional bits by repeated squaring
+    let mut bit = 0.5;
+    for _ in 0..52 {
+        x *= x;
+        if x >= 2.0 {
+            x /= 2.0;
+            result += bit;
+        }
+        bit /= 2.0;
+    }
+    result
+}
+
+fn floor(x: f64) -> f64 {
+    let truncated = x as i64 as f64;
+    if truncated > x {
+        truncated - 1.0
+    } else {
+        truncated
+    }
+}
+
+pub(crate) fn ceil(x: f64) -> f64 {
+    let truncated = x as i64 as f64;
+    if truncated < x {
+        truncated + 1.0
+    } else {
+        truncated
+    }
+}
+
+fn min(a: f64, b: f64) -> f64 {
+    if a < b {
+        a
+    } else {
+        b
+    }
+}
+
+fn max(a: f64, b: f64) -> f64 {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::poseidon::round_numbers::{find_poseidon_alpha, find_poseidon_round_numbers, log2};
+    use ark_test_curves::bls12_381::{Fq, Fr};
+
+    #[test]
+    fn test_log2() {
+        assert_eq!(log2(1.0), 0.0);
+        assert_eq!(log2(1024.0), 10.0);
+        assert!((log2(3.0) - 1.584962500721156).abs() < 1e-12);
+        assert!((log2(0.75) + 0.415037499278844).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_round_numbers_match_default_parameters() {
+        // These are the entries of the default parameters for the BLS12-381 scalar field.
+        assert_eq!(
+            find_poseidon_round_numbers::<Fr>(3, 17, 128, false),
+            (8, 31)
+        );
+        assert_eq!(find_poseidon_round_numbers::<Fr>(4, 5, 128, false), (8, 56));
+        assert_eq!(find_poseidon_round_numbers::<Fr>(5, 5, 128, false), (8, 56));
+        for state_size in 6..=9 {
+            assert_eq!(
+                find_poseidon_round_numbers::<Fr>(state_size, 5, 128, false),
+                (8, 57)
+            );
+        }
+        for state_size in 3..=9 {
+            assert_eq!(
+                find_poseidon_round_numbers::<Fr>(state_size, 257, 128, true),
+                (8, 13)
+            );
+        }
+    }
+
+    #[test]
+    fn test_alpha() {
+        assert_eq!(find_poseidon_alpha::<Fr>(), 5);
+        assert_eq!(find_poseidon_alpha::<Fq>(), 5);
+    }
+}
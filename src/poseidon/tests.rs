@@ -1,9 +1,17 @@
-use crate::poseidon::{PoseidonParameters, PoseidonSponge};
+use crate::poseidon::{
+    poseidon_hash, PoseidonArraySponge, PoseidonDomainTag, PoseidonParameters, PoseidonSponge,
+};
 use crate::{absorb, collect_sponge_bytes, collect_sponge_field_elements};
-use crate::{Absorb, AbsorbWithLength, CryptographicSponge, FieldBasedCryptographicSponge};
-use ark_ff::{One, PrimeField, UniformRand};
+use crate::{
+    Absorb, AbsorbWithLength, CryptographicSponge, DuplexSpongeMode, FieldBasedCryptographicSponge,
+    FieldElementSize, PaddingMode, SpongeError,
+};
+use ark_ec::ProjectiveCurve;
+use ark_ff::{BigInteger, FpParameters, One, PrimeField, UniformRand, Zero};
 use ark_std::test_rng;
-use ark_test_curves::bls12_381::Fr;
+use ark_std::vec;
+use ark_std::vec::Vec;
+use ark_test_curves::bls12_381::{Fq, Fr, G1Projective};
 fn assert_different_encodings<F: PrimeField, A: Absorb>(a: &A, b: &A) {
     let bytes1 = a.to_sponge_bytes_as_vec();
     let bytes2 = b.to_sponge_bytes_as_vec();
@@ -115,6 +123,63 @@ fn test_macros() {
     assert_eq!(actual, expected);
 }
 
+#[test]
+fn test_absorb_projective_points() {
+    let mut rng = test_rng();
+    let p1 = G1Projective::rand(&mut rng);
+    let p2 = p1.double();
+
+    // projective points are encoded as their affine form
+    assert_eq!(
+        p1.to_sponge_bytes_as_vec(),
+        p1.into_affine().to_sponge_bytes_as_vec()
+    );
+    assert_eq!(
+        p1.to_sponge_field_elements_as_vec::<Fq>(),
+        p1.into_affine().to_sponge_field_elements_as_vec::<Fq>()
+    );
+    let batch = vec![p1, p2, G1Projective::zero()];
+    let affine_batch: Vec<_> = batch.iter().map(|p| p.into_affine()).collect();
+    assert_eq!(
+        batch.to_sponge_field_elements_as_vec::<Fq>(),
+        affine_batch.to_sponge_field_elements_as_vec::<Fq>()
+    );
+
+    // the point at infinity is distinguished from other points
+    assert_different_encodings::<Fq, _>(&G1Projective::zero(), &p1);
+    assert_different_encodings::<Fq, _>(&p1, &p2);
+}
+
+#[test]
+fn nested_lists_are_unambiguous() {
+    let lst1 = vec![vec![1u8, 2, 3], vec![4]];
+    let lst2 = vec![vec![1u8, 2], vec![3, 4]];
+    assert_different_encodings::<Fr, _>(&lst1, &lst2);
+
+    let lst1 = vec![vec![Fr::one(), Fr::one()], vec![]];
+    let lst2 = vec![vec![Fr::one()], vec![Fr::one()]];
+    assert_different_encodings::<Fr, _>(&lst1, &lst2);
+}
+
+#[test]
+fn tuples_and_arrays() {
+    let elem = Fr::from(7u8);
+    let tuple = (elem, vec![1u8, 2], Some(3u64));
+    assert_eq!(
+        tuple.to_sponge_bytes_as_vec(),
+        collect_sponge_bytes!(elem, vec![1u8, 2], Some(3u64))
+    );
+    let expected: Vec<Fr> = collect_sponge_field_elements!(elem, vec![1u8, 2], Some(3u64));
+    assert_eq!(tuple.to_sponge_field_elements_as_vec::<Fr>(), expected);
+
+    let array = [Fr::one(), elem];
+    assert_eq!(
+        array.to_sponge_field_elements_as_vec::<Fr>(),
+        array.to_vec().to_sponge_field_elements_as_vec::<Fr>()
+    );
+    assert_different_encodings::<Fr, _>(&(1u8, Some(2u8)), &(1u8, None));
+}
+
 /// Generate default parameters (bls381-fr-only) for alpha = 17, state-size = 8
 pub(crate) fn poseidon_parameters_for_test<F: PrimeField>() -> PoseidonParameters<F> {
     let alpha = 17;
@@ -807,13 +872,1559 @@ pub(crate) fn poseidon_parameters_for_test<F: PrimeField>() -> PoseidonParameter
     let partial_rounds = total_rounds - full_rounds;
     let capacity = 1;
     let rate = 2;
-    PoseidonParameters {
-        full_rounds,
-        partial_rounds,
-        alpha,
-        ark,
+    PoseidonParameters::new(full_rounds, partial_rounds, alpha, mds, ark, rate, capacity)
+}
+
+#[test]
+fn squeeze_many_field_elements_fast() {
+    let sponge_param = poseidon_parameters_for_test();
+    let mut sponge1 = PoseidonSponge::<Fr>::new(&sponge_param);
+    sponge1.absorb(&Fr::one());
+    let mut sponge2 = sponge1.clone();
+
+    let elems1: Vec<Fq> = sponge1.squeeze_many_field_elements_fast(100);
+    let elems2: Vec<Fq> = sponge2.squeeze_many_field_elements_fast(100);
+    assert_eq!(elems1, elems2);
+    assert!(elems1[1..].iter().all(|e| *e != elems1[0]));
+
+    // The number of elements is bound into the seed.
+    let mut sponge3 = PoseidonSponge::<Fr>::new(&sponge_param);
+    sponge3.absorb(&Fr::one());
+    let elems3: Vec<Fq> = sponge3.squeeze_many_field_elements_fast(101);
+    assert_ne!(elems1[0], elems3[0]);
+
+    // Later operations depend on the seed.
+    assert_eq!(
+        sponge1.squeeze_native_field_elements(1),
+        sponge2.squeeze_native_field_elements(1)
+    );
+    assert_ne!(
+        sponge1.squeeze_native_field_elements(1),
+        sponge3.squeeze_native_field_elements(1)
+    );
+}
+
+#[test]
+fn padding_modes() {
+    let sponge_param = poseidon_parameters_for_test();
+    let squeeze = |padding: PaddingMode, input: &[Fr]| -> Vec<Fr> {
+        let mut sponge = PoseidonSponge::<Fr>::with_padding(&sponge_param, padding);
+        sponge.absorb(&input);
+        sponge.squeeze_native_field_elements(1)
+    };
+
+    let x = Fr::from(3u64);
+    assert_eq!(
+        squeeze(PaddingMode::None, &[x]),
+        squeeze(PaddingMode::None, &[x, Fr::zero()])
+    );
+    for padding in [PaddingMode::MultiRate, PaddingMode::LengthSuffix] {
+        // Inputs ending with zeros, at and across the boundary of the rate.
+        let inputs: Vec<Vec<Fr>> = (0..6).map(|n| vec![Fr::zero(); n]).collect();
+        let outputs: Vec<_> = inputs.iter().map(|input| squeeze(padding, input)).collect();
+        for i in 0..outputs.len() {
+            for j in 0..i {
+                assert_ne!(outputs[i], outputs[j]);
+            }
+        }
+    }
+
+    // The padding is applied again after each squeeze.
+    let mut sponge1 = PoseidonSponge::<Fr>::with_padding(&sponge_param, PaddingMode::LengthSuffix);
+    let mut sponge2 = sponge1.clone();
+    sponge1.squeeze_native_field_elements(1);
+    sponge2.squeeze_native_field_elements(1);
+    sponge1.absorb(&x);
+    sponge2.absorb(&vec![x, Fr::zero()]);
+    assert_ne!(
+        sponge1.squeeze_native_field_elements(1),
+        sponge2.squeeze_native_field_elements(1)
+    );
+}
+
+#[test]
+fn ratchet() {
+    let sponge_param = poseidon_parameters_for_test();
+    let mut sponge = PoseidonSponge::<Fr>::new(&sponge_param);
+    sponge.absorb(&Fr::from(42u64));
+    let mut other = sponge.clone();
+
+    sponge.ratchet();
+    assert!(sponge.state[sponge_param.capacity..]
+        .iter()
+        .all(|e| e.is_zero()));
+    // The ratchet permutes the state, so it differs from not ratcheting.
+    assert_ne!(
+        sponge.clone().squeeze_native_field_elements(1),
+        other.squeeze_native_field_elements(1)
+    );
+    // Absorbing after the ratchet overwrites the rate.
+    sponge.absorb(&Fr::from(1u64));
+    assert_eq!(sponge.state[sponge_param.capacity], Fr::from(1u64));
+}
+
+#[test]
+fn reset() {
+    let sponge_param =
+        poseidon_parameters_for_test::<Fr>().with_domain_tag(PoseidonDomainTag::Arity);
+    let mut fresh = PoseidonSponge::<Fr>::new(&sponge_param);
+    let expected = fresh.squeeze_native_field_elements(1);
+
+    let mut sponge = PoseidonSponge::<Fr>::new(&sponge_param);
+    for i in 0..3u64 {
+        sponge.absorb(&Fr::from(i));
+        sponge.squeeze_native_field_elements(3);
+        sponge.reset();
+        assert_eq!(sponge.squeeze_native_field_elements(1), expected);
+        sponge.reset();
+    }
+}
+
+#[test]
+fn compress() {
+    let sponge_param = poseidon_parameters_for_test();
+    let sponge = PoseidonSponge::<Fr>::new(&sponge_param);
+    let input: Vec<_> = (0..sponge_param.rate as u64).map(Fr::from).collect();
+    let output = sponge.compress(&input).unwrap();
+
+    let mut reversed = input.clone();
+    reversed.reverse();
+    assert_ne!(output, sponge.compress(&reversed).unwrap());
+    assert_eq!(
+        sponge.compress(&input[1..]),
+        Err(SpongeError::InvalidInputLength {
+            expected: sponge_param.rate,
+            found: sponge_param.rate - 1
+        })
+    );
+    // The compression is domain-separated from the sponge.
+    let mut other = sponge.clone();
+    other.absorb(&input);
+    assert_ne!(output, other.squeeze_native_field_elements(1)[0]);
+}
+
+#[test]
+fn one_shot_hash() {
+    let sponge_param = poseidon_parameters_for_test();
+    let input = [Fr::from(1u64), Fr::from(2u64), Fr::from(3u64)];
+    let mut sponge = PoseidonSponge::<Fr>::new(&sponge_param);
+    sponge.absorb(&input);
+    assert_eq!(
+        poseidon_hash(&sponge_param, &input),
+        sponge.squeeze_native_field_elements(1)[0]
+    );
+}
+
+#[test]
+fn absorb_iter() {
+    let sponge_param = poseidon_parameters_for_test();
+    let input: Vec<_> = (0..10u64).map(Fr::from).collect();
+    let mut sponge1 = PoseidonSponge::<Fr>::new(&sponge_param);
+    let mut sponge2 = sponge1.clone();
+    sponge1.absorb(&input);
+    sponge2.absorb_iter((0..10u64).map(Fr::from));
+    assert_eq!(
+        sponge1.squeeze_native_field_elements(2),
+        sponge2.squeeze_native_field_elements(2)
+    );
+}
+
+#[test]
+fn squeeze_field_elements_iter() {
+    let sponge_param = poseidon_parameters_for_test();
+    let mut sponge1 = PoseidonSponge::<Fr>::new(&sponge_param);
+    sponge1.absorb(&Fr::from(42u64));
+    let mut sponge2 = sponge1.clone();
+
+    let squeezed: Vec<_> = sponge1.squeeze_field_elements_iter().take(5).collect();
+    assert_eq!(squeezed, sponge2.squeeze_native_field_elements(5));
+    assert_eq!(
+        sponge1.squeeze_native_field_elements(1),
+        sponge2.squeeze_native_field_elements(1)
+    );
+}
+
+#[cfg(feature = "zeroize")]
+#[test]
+fn zeroize() {
+    use zeroize::Zeroize;
+
+    let sponge_param = poseidon_parameters_for_test();
+    let mut sponge = PoseidonSponge::<Fr>::new(&sponge_param);
+    sponge.absorb(&Fr::from(42u64));
+    sponge.squeeze_native_field_elements(1);
+    sponge.zeroize();
+    assert!(sponge.state.iter().all(|elem| elem.is_zero()));
+
+    // a zeroized sponge behaves as a new one
+    let mut fresh = PoseidonSponge::<Fr>::new(&sponge_param);
+    sponge.absorb(&Fr::from(1u64));
+    fresh.absorb(&Fr::from(1u64));
+    assert_eq!(
+        sponge.squeeze_native_field_elements(1),
+        fresh.squeeze_native_field_elements(1)
+    );
+}
+
+#[test]
+fn checkpoint_state() {
+    use crate::poseidon::PoseidonSpongeState;
+    use crate::{SpongeExt, SPONGE_STATE_VERSION};
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+    let sponge_param = poseidon_parameters_for_test();
+    let mut sponge = PoseidonSponge::<Fr>::with_padding(&sponge_param, PaddingMode::LengthSuffix);
+    sponge.absorb(&vec![Fr::from(1u64), Fr::from(2u64)]);
+    sponge.squeeze_native_field_elements(1);
+    sponge.absorb(&Fr::from(3u64));
+    let expected = sponge.clone().squeeze_native_field_elements(3);
+
+    let mut checkpoint = Vec::new();
+    sponge.into_state().serialize(&mut checkpoint).unwrap();
+    assert_eq!(checkpoint[0], SPONGE_STATE_VERSION);
+    let state = PoseidonSpongeState::<Fr>::deserialize(checkpoint.as_slice()).unwrap();
+    assert_eq!(state.serialized_size(), checkpoint.len());
+
+    let mut resumed = PoseidonSponge::from_state(state.clone(), &sponge_param);
+    assert_eq!(resumed.padding, PaddingMode::LengthSuffix);
+    assert_eq!(resumed.squeeze_native_field_elements(3), expected);
+
+    // states which do not fit the parameters are rejected
+    let wider_param = PoseidonParameters::<Fr>::generate(3, 1, 128);
+    assert_eq!(
+        PoseidonSponge::try_from_state(state.clone(), &wider_param).err(),
+        Some(SpongeError::InvalidState(
+            "the state must have `rate + capacity` elements"
+        ))
+    );
+    let mut bad_index = state;
+    bad_index.mode = DuplexSpongeMode::Squeezing {
+        next_squeeze_index: sponge_param.rate + 1,
+    };
+    assert_eq!(
+        PoseidonSponge::try_from_state(bad_index, &sponge_param).err(),
+        Some(SpongeError::InvalidState(
+            "the index of the mode is beyond the rate"
+        ))
+    );
+
+    // states of other versions are rejected
+    checkpoint[0] = SPONGE_STATE_VERSION + 1;
+    assert!(PoseidonSpongeState::<Fr>::deserialize(checkpoint.as_slice()).is_err());
+}
+
+#[cfg(feature = "stats")]
+#[test]
+fn stats() {
+    use crate::SpongeStats;
+
+    let sponge_param = poseidon_parameters_for_test::<Fr>();
+    let rate = sponge_param.rate;
+    let mut sponge = PoseidonSponge::<Fr>::new(&sponge_param);
+    assert_eq!(sponge.stats(), SpongeStats::default());
+
+    let input: Vec<_> = (0..=rate as u64).map(Fr::from).collect();
+    // one permutation when the rate is full, and two to squeeze `rate + 1` elements
+    sponge.absorb(&input);
+    sponge.squeeze_native_field_elements(rate + 1);
+    // the next element is read from the remaining rate
+    sponge.squeeze_bytes(1);
+    assert_eq!(
+        sponge.stats(),
+        SpongeStats {
+            num_permutations: 3,
+            num_absorbed: rate as u64 + 1,
+            num_squeezed: rate as u64 + 2,
+        }
+    );
+}
+
+#[test]
+fn absorb_tree() {
+    let sponge_param = poseidon_parameters_for_test();
+    let mut rng = test_rng();
+    let input: Vec<_> = (0..10).map(|_| Fr::rand(&mut rng)).collect();
+
+    let mut sponge = PoseidonSponge::<Fr>::new(&sponge_param);
+    sponge.absorb(&Fr::from(42u64));
+    let mut expected_sponge = sponge.clone();
+    sponge.absorb_tree(&input, 4).unwrap();
+
+    let digests: Vec<Fr> = input
+        .chunks(4)
+        .enumerate()
+        .map(|(index, chunk)| {
+            let mut chunk_sponge = expected_sponge.clone();
+            chunk_sponge.absorb(&(index as u64));
+            chunk_sponge.absorb(&chunk);
+            chunk_sponge.squeeze_native_field_elements(1)[0]
+        })
+        .collect();
+    expected_sponge.absorb(&(input.len() as u64));
+    expected_sponge.absorb(&digests);
+    assert_eq!(
+        sponge.squeeze_native_field_elements(1),
+        expected_sponge.squeeze_native_field_elements(1)
+    );
+
+    assert_eq!(
+        sponge.absorb_tree(&input, 0),
+        Err(SpongeError::InvalidParameters(
+            "the chunk size must be positive"
+        ))
+    );
+}
+
+#[test]
+fn absorb_batch_of_points() {
+    let mut rng = test_rng();
+    let points: Vec<_> = (0..5)
+        .map(|_| G1Projective::rand(&mut rng).into_affine())
+        .collect();
+
+    let mut expected_elems = Vec::<Fq>::new();
+    let mut expected_bytes = Vec::new();
+    for point in &points {
+        point.to_sponge_field_elements(&mut expected_elems);
+        point.to_sponge_bytes(&mut expected_bytes);
+    }
+    assert_eq!(
+        points.as_slice().to_sponge_field_elements_as_vec::<Fq>(),
+        expected_elems
+    );
+    assert_eq!(points.as_slice().to_sponge_bytes_as_vec(), expected_bytes);
+}
+
+#[test]
+fn poseidon_hash_batch() {
+    use crate::duplex::FieldPermutation;
+    use crate::poseidon::{poseidon_hash_batch, PoseidonPermutation};
+
+    let sponge_param = poseidon_parameters_for_test::<Fr>();
+    let mut rng = test_rng();
+
+    // more states than are permuted together, of lengths around multiples of the rate
+    let inputs: Vec<Vec<Fr>> = (0..20)
+        .map(|len| (0..len).map(|_| Fr::rand(&mut rng)).collect())
+        .collect();
+    let expected: Vec<Fr> = inputs
+        .iter()
+        .map(|input| poseidon_hash(&sponge_param, input))
+        .collect();
+    assert_eq!(poseidon_hash_batch(&sponge_param, &inputs), expected);
+    assert!(poseidon_hash_batch::<Fr, Fr, Vec<Fr>>(&sponge_param, &[]).is_empty());
+
+    let permutation = PoseidonPermutation::new(&sponge_param);
+    let mut states: Vec<Vec<Fr>> = (0..10)
+        .map(|_| {
+            (0..sponge_param.state_size())
+                .map(|_| Fr::rand(&mut rng))
+                .collect()
+        })
+        .collect();
+    let mut expected = states.clone();
+    expected
+        .iter_mut()
+        .for_each(|state| permutation.permute(state));
+    permutation.permute_batch(&mut states);
+    assert_eq!(states, expected);
+}
+
+#[test]
+fn hash_batch() {
+    let sponge_param = poseidon_parameters_for_test::<Fr>();
+    let mut rng = test_rng();
+    let inputs: Vec<Vec<Fr>> = (0..10)
+        .map(|len| (0..len).map(|_| Fr::rand(&mut rng)).collect())
+        .collect();
+    let expected: Vec<Fr> = inputs
+        .iter()
+        .map(|input| poseidon_hash(&sponge_param, input))
+        .collect();
+    assert_eq!(PoseidonSponge::hash_batch(&sponge_param, &inputs), expected);
+}
+
+#[test]
+fn array_sponge() {
+    let sponge_param = poseidon_parameters_for_test::<Fr>();
+    let mut rng = test_rng();
+    let input: Vec<Fr> = (0..7).map(|_| Fr::rand(&mut rng)).collect();
+
+    for padding in [
+        PaddingMode::None,
+        PaddingMode::MultiRate,
+        PaddingMode::LengthSuffix,
+    ] {
+        let mut sponge = PoseidonSponge::with_padding(&sponge_param, padding);
+        let mut array_sponge = PoseidonArraySponge::<Fr, 3>::with_padding(&sponge_param, padding);
+        sponge.absorb(&input);
+        array_sponge.absorb(&input);
+        assert_eq!(
+            array_sponge.squeeze_native_field_elements(5),
+            sponge.squeeze_native_field_elements(5)
+        );
+        sponge.absorb(&input[0]);
+        array_sponge.absorb(&input[0]);
+        assert_eq!(array_sponge.squeeze_bytes(40), sponge.squeeze_bytes(40));
+        assert_eq!(array_sponge.state.to_vec(), sponge.state);
+    }
+
+    let sponge = PoseidonSponge::new(&sponge_param);
+    let array_sponge = PoseidonArraySponge::<Fr, 3>::new(&sponge_param);
+    assert_eq!(
+        array_sponge.compress(&input[..2]).unwrap(),
+        sponge.compress(&input[..2]).unwrap()
+    );
+}
+
+#[test]
+#[should_panic]
+fn array_sponge_with_wrong_width() {
+    PoseidonArraySponge::<Fr, 4>::new(&poseidon_parameters_for_test());
+}
+
+#[test]
+fn absorb_native_field_elements() {
+    let sponge_param = poseidon_parameters_for_test::<Fr>();
+    let mut rng = test_rng();
+    let input: Vec<Fr> = (0..7).map(|_| Fr::rand(&mut rng)).collect();
+
+    let mut sponge = PoseidonSponge::new(&sponge_param);
+    sponge.absorb(&Fr::one());
+    sponge.absorb(&input);
+    let mut native_sponge = PoseidonSponge::new(&sponge_param);
+    native_sponge.absorb(&Fr::one());
+    native_sponge.absorb_native_field_elements(&input);
+    assert_eq!(
+        native_sponge.squeeze_native_field_elements(3),
+        sponge.squeeze_native_field_elements(3)
+    );
+
+    // Absorbing after squeezing, and absorbing nothing, are the same as with `absorb`.
+    let prefix = &input[..3];
+    sponge.absorb(&prefix);
+    native_sponge.absorb_native_field_elements(prefix);
+    native_sponge.absorb_native_field_elements(&[]);
+    assert_eq!(native_sponge.squeeze_bytes(10), sponge.squeeze_bytes(10));
+}
+
+#[test]
+fn fork() {
+    let sponge_param = poseidon_parameters_for_test::<Fr>();
+    let mut sponge = PoseidonSponge::new(&sponge_param);
+    sponge.absorb(&Fr::from(7u64));
+
+    let mut fork_1 = sponge.fork(b"column 1");
+    let mut fork_2 = sponge.fork(b"column 2");
+    let mut absorbed = sponge.clone();
+    absorbed.absorb(&[&(8u64.to_le_bytes())[..], b"column 1"].concat());
+
+    let challenges_1 = fork_1.squeeze_native_field_elements(2);
+    assert_eq!(absorbed.squeeze_native_field_elements(2), challenges_1);
+    assert_ne!(fork_2.squeeze_native_field_elements(2), challenges_1);
+
+    // Forking does not modify the sponge.
+    let mut unforked = PoseidonSponge::new(&sponge_param);
+    unforked.absorb(&Fr::from(7u64));
+    assert_eq!(
+        sponge.squeeze_native_field_elements(2),
+        unforked.squeeze_native_field_elements(2)
+    );
+}
+
+#[test]
+fn absorb_bits() {
+    let sponge_param = poseidon_parameters_for_test::<Fr>();
+
+    // The number of bits is absorbed, so trailing zero bits are not ignored.
+    let mut sponge = PoseidonSponge::new(&sponge_param);
+    sponge.absorb_bits(&[true]);
+    let mut padded_sponge = PoseidonSponge::new(&sponge_param);
+    padded_sponge.absorb_bits(&[true, false]);
+    assert_ne!(
+        sponge.squeeze_native_field_elements(1),
+        padded_sponge.squeeze_native_field_elements(1)
+    );
+
+    let bits: Vec<bool> = (0..300).map(|i| i % 3 == 0).collect();
+    let mut sponge = PoseidonSponge::new(&sponge_param);
+    sponge.absorb_bits(&bits);
+    let capacity = <Fr as PrimeField>::Params::CAPACITY as usize;
+    let mut packed_sponge = PoseidonSponge::new(&sponge_param);
+    packed_sponge.absorb(&vec![
+        Fr::from(300u64),
+        Fr::from_repr(<Fr as PrimeField>::BigInt::from_bits_le(&bits[..capacity])).unwrap(),
+        Fr::from_repr(<Fr as PrimeField>::BigInt::from_bits_le(&bits[capacity..])).unwrap(),
+    ]);
+    assert_eq!(
+        sponge.squeeze_native_field_elements(2),
+        packed_sponge.squeeze_native_field_elements(2)
+    );
+}
+
+#[test]
+fn parameters_permute() {
+    use crate::duplex::FieldPermutation;
+    use crate::poseidon::PoseidonPermutation;
+
+    let mut rng = test_rng();
+    let sponge_params = poseidon_parameters_for_test();
+    let state: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+
+    let mut permuted = state.clone();
+    sponge_params.permute(&mut permuted);
+    let mut expected = state.clone();
+    PoseidonPermutation::new(&sponge_params).permute(&mut expected);
+    assert_eq!(permuted, expected);
+    assert_ne!(permuted, state);
+
+    // Modified parameters are not permuted with the optimized form of the original ones.
+    let mut modified_params = sponge_params.clone();
+    modified_params.ark[0][0] += Fr::one();
+    let mut modified = state.clone();
+    modified_params.permute(&mut modified);
+    let mut expected = state;
+    PoseidonPermutation::new(&modified_params).permute(&mut expected);
+    assert_eq!(modified, expected);
+    assert_ne!(modified, permuted);
+}
+
+#[test]
+fn circom_parameters_with_invalid_inputs() {
+    assert!(PoseidonParameters::<Fr>::circom(0).is_err());
+    assert!(PoseidonParameters::<Fr>::circom(17).is_err());
+    assert!(PoseidonParameters::<Fr>::circom(16).is_ok());
+}
+
+/// The hashes of circomlib, from the tests of circomlibjs.
+#[cfg(feature = "bn254")]
+#[test]
+fn circom_test_vectors() {
+    use crate::poseidon::circom_poseidon_hash;
+    use ark_bn254::Fr;
+    use ark_ff::{BigInteger256, PrimeField};
+
+    let vectors: [(&[u64], [u64; 4]); 3] = [
+        (
+            &[1],
+            [
+                0x8b897dc502820133,
+                0x0e96a4d1168b3384,
+                0xc1fe6c654d6a3c13,
+                0x29176100eaa962bd,
+            ],
+        ),
+        (
+            &[1, 2],
+            [
+                0x9e19607a4417189a,
+                0x2a3617f274324551,
+                0x3df64c6b9662e9cf,
+                0x115cc0f5e7d69041,
+            ],
+        ),
+        (
+            &[1, 2, 3, 4],
+            [
+                0xbaa525df65250465,
+                0x37e60ebb1ce0663d,
+                0x9dcefa40e4510b98,
+                0x299c867db6c1fdd7,
+            ],
+        ),
+    ];
+    for (input, expected) in vectors {
+        let input: Vec<Fr> = input.iter().map(|v| Fr::from(*v)).collect();
+        let parameters = PoseidonParameters::circom(input.len()).unwrap();
+        assert_eq!(
+            circom_poseidon_hash(&parameters, &input),
+            Fr::from_repr(BigInteger256::new(expected)).unwrap()
+        );
+    }
+}
+
+#[test]
+fn evm_words() {
+    use crate::poseidon::{field_element_from_evm_word, field_element_to_evm_word};
+    use crate::SpongeError;
+
+    let mut one = [0u8; 32];
+    one[31] = 1;
+    assert_eq!(field_element_to_evm_word(&Fr::one()), one);
+    assert_eq!(field_element_from_evm_word::<Fr>(&one), Ok(Fr::one()));
+
+    let mut rng = test_rng();
+    for elem in [Fr::zero(), -Fr::one(), Fr::rand(&mut rng)] {
+        let word = field_element_to_evm_word(&elem);
+        assert_eq!(word.to_vec(), elem.into_repr().to_bytes_be());
+        assert_eq!(field_element_from_evm_word(&word), Ok(elem));
+    }
+
+    let mut modulus = [0u8; 32];
+    modulus.copy_from_slice(&<Fr as PrimeField>::Params::MODULUS.to_bytes_be());
+    for word in [modulus, [0xff; 32]] {
+        assert_eq!(
+            field_element_from_evm_word::<Fr>(&word),
+            Err(SpongeError::NonCanonicalFieldElement)
+        );
+    }
+}
+
+/// The hashes of the contracts of poseidon-solidity, which are those of circomlibjs.
+#[cfg(feature = "bn254")]
+#[test]
+fn evm_test_vectors() {
+    use crate::poseidon::evm_poseidon_hash;
+    use crate::SpongeError;
+    use ark_bn254::Fr;
+
+    let word = |hex: &str| {
+        let mut word = [0u8; 32];
+        for (i, byte) in word.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+        }
+        word
+    };
+    let small_word = |v: u8| {
+        let mut word = [0u8; 32];
+        word[31] = v;
+        word
+    };
+    let vectors: [(&[u8], &str); 3] = [
+        (
+            &[1],
+            "29176100eaa962bdc1fe6c654d6a3c130e96a4d1168b33848b897dc502820133",
+        ),
+        (
+            &[1, 2],
+            "115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a",
+        ),
+        (
+            &[1, 2, 3, 4],
+            "299c867db6c1fdd79dcefa40e4510b9837e60ebb1ce0663dbaa525df65250465",
+        ),
+    ];
+    for (input, expected) in vectors {
+        let input: Vec<_> = input.iter().map(|v| small_word(*v)).collect();
+        let parameters = PoseidonParameters::<Fr>::circom(input.len()).unwrap();
+        assert_eq!(evm_poseidon_hash(&parameters, &input), Ok(word(expected)));
+        assert_eq!(
+            evm_poseidon_hash(&parameters, &input[1..]),
+            Err(SpongeError::InvalidInputLength {
+                expected: input.len(),
+                found: input.len() - 1,
+            })
+        );
+    }
+
+    // The modulus of the scalar field of BN254, which the contracts reduce to zero.
+    let modulus = word("30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001");
+    let parameters = PoseidonParameters::<Fr>::circom(2).unwrap();
+    assert_eq!(
+        evm_poseidon_hash(&parameters, &[small_word(1), modulus]),
+        Err(SpongeError::NonCanonicalFieldElement)
+    );
+}
+
+#[test]
+fn neptune_parameters() {
+    use crate::poseidon::NeptuneStrength;
+
+    assert!(PoseidonParameters::<Fr>::neptune(0, NeptuneStrength::Standard).is_err());
+    // The partial rounds of neptune for arities 1 to 16.
+    let partial_rounds = [
+        55, 55, 56, 56, 56, 56, 57, 57, 57, 57, 57, 57, 57, 57, 59, 59,
+    ];
+    for (arity, partial_rounds) in (1..).zip(partial_rounds) {
+        let standard = PoseidonParameters::<Fr>::neptune(arity, NeptuneStrength::Standard).unwrap();
+        assert_eq!(standard.full_rounds, 8);
+        assert_eq!(standard.partial_rounds, partial_rounds);
+        let strengthened =
+            PoseidonParameters::<Fr>::neptune(arity, NeptuneStrength::Strengthened).unwrap();
+        assert_eq!(strengthened.full_rounds, 8);
+        assert_eq!(
+            strengthened.partial_rounds,
+            (partial_rounds * 5_usize).div_ceil(4)
+        );
+    }
+}
+
+/// The hashes of neptune over the scalar field of BLS12-381, from its tests.
+#[test]
+fn neptune_test_vectors() {
+    use crate::poseidon::{neptune_poseidon_hash, NeptuneHashType, NeptuneStrength};
+    use ark_ff::{BigInteger256, PrimeField};
+
+    // The hashes of a Merkle tree node whose inputs are `0, 1, ..., arity - 1`.
+    let merkle_vectors = [
+        (
+            NeptuneStrength::Standard,
+            2,
+            [
+                0x2e203c369a02e7ff,
+                0xa6fba9339d05a69d,
+                0x739e0fd902efe161,
+                0x396508d75e76a56b,
+            ],
+        ),
+        (
+            NeptuneStrength::Standard,
+            4,
+            [
+                0x019814ff6662075d,
+                0xfb6b4605bf1327ec,
+                0x00db3c6579229399,
+                0x58a54b10a9e5848a,
+            ],
+        ),
+        (
+            NeptuneStrength::Standard,
+            8,
+            [
+                0x2a9934f56d38a5e6,
+                0x4b682e9d9cc4aed9,
+                0x1201004211677077,
+                0x2394611da3a5de55,
+            ],
+        ),
+        (
+            NeptuneStrength::Standard,
+            11,
+            [
+                0xcee3bbc32b693163,
+                0x09f3dcd8ccb08fc1,
+                0x6ca537e232ebe87a,
+                0x0c0fc1b2e5227f28,
+            ],
+        ),
+        (
+            NeptuneStrength::Strengthened,
+            2,
+            [
+                0x793dbaf54552cd69,
+                0x5278ecbf17040ea6,
+                0xc48b36ecc4cab748,
+                0x33d28a753baee41b,
+            ],
+        ),
+        (
+            NeptuneStrength::Strengthened,
+            4,
+            [
+                0x4650ee190212aa9a,
+                0xe5113a254d6f5c7e,
+                0x54013bdaf68ba4c2,
+                0x09d8207c51ca3f43,
+            ],
+        ),
+        (
+            NeptuneStrength::Strengthened,
+            8,
+            [
+                0x9f0c3c93c3fc894e,
+                0xe843d4cfba662df1,
+                0xd69aae8fe1cb63e8,
+                0x69e61465981ae17e,
+            ],
+        ),
+    ];
+    for (strength, arity, expected) in merkle_vectors {
+        let parameters = PoseidonParameters::neptune(arity, strength).unwrap();
+        let input: Vec<Fr> = (0..arity as u64).map(Fr::from).collect();
+        assert_eq!(
+            neptune_poseidon_hash(&parameters, NeptuneHashType::MerkleTree, &input),
+            Fr::from_repr(BigInteger256::new(expected)).unwrap()
+        );
+    }
+
+    // The hashes of the constant-length input `0, 1, 2, 3`.
+    let constant_length_vectors = [
+        (
+            NeptuneStrength::Standard,
+            4,
+            [
+                0x8935b00a07909d45,
+                0x4984de08542c9977,
+                0x39443980077d7593,
+                0x3a21a6ae86754a29,
+            ],
+        ),
+        (
+            NeptuneStrength::Standard,
+            8,
+            [
+                0x370a94532f818897,
+                0x203e3c7c4a85c1f9,
+                0xcad8b9f8aeb1578f,
+                0x5c6de4b69de9d792,
+            ],
+        ),
+        (
+            NeptuneStrength::Strengthened,
+            4,
+            [
+                0xa31d9dc66a42f972,
+                0xb5be830aae89db0d,
+                0xdff9a095d1d40420,
+                0x466e7819bb809c44,
+            ],
+        ),
+        (
+            NeptuneStrength::Strengthened,
+            8,
+            [
+                0x6f2c393786312ee2,
+                0xadb6da339b87e590,
+                0xbf626c21fd6cb051,
+                0x0bb12009ab1fb62a,
+            ],
+        ),
+    ];
+    let input: Vec<Fr> = (0..4u64).map(Fr::from).collect();
+    for (strength, arity, expected) in constant_length_vectors {
+        let parameters = PoseidonParameters::neptune(arity, strength).unwrap();
+        assert_eq!(
+            neptune_poseidon_hash(&parameters, NeptuneHashType::ConstantLength(4), &input),
+            Fr::from_repr(BigInteger256::new(expected)).unwrap()
+        );
+    }
+}
+
+#[test]
+fn domain_tags() {
+    use crate::poseidon::PoseidonDomainTag;
+
+    let sponge_params = poseidon_parameters_for_test::<Fr>();
+    let hash = |tag: PoseidonDomainTag<Fr>| {
+        let mut sponge = PoseidonSponge::new(&sponge_params.clone().with_domain_tag(tag));
+        sponge.absorb(&vec![Fr::from(1u64), Fr::from(2u64)]);
+        sponge.squeeze_field_elements::<Fr>(1)[0]
+    };
+
+    let untagged = hash(PoseidonDomainTag::None);
+    let mut sponge = PoseidonSponge::new(&sponge_params);
+    sponge.absorb(&vec![Fr::from(1u64), Fr::from(2u64)]);
+    assert_eq!(untagged, sponge.squeeze_field_elements::<Fr>(1)[0]);
+    assert_eq!(untagged, hash(PoseidonDomainTag::Element(Fr::zero())));
+
+    let arity = hash(PoseidonDomainTag::Arity);
+    assert_eq!(
+        arity,
+        hash(PoseidonDomainTag::Element(Fr::from(
+            sponge_params.rate as u64
+        )))
+    );
+    let label = hash(PoseidonDomainTag::Label(b"merkle-node"));
+    assert_eq!(
+        label,
+        hash(PoseidonDomainTag::Element(Fr::from_le_bytes_mod_order(
+            b"merkle-node"
+        )))
+    );
+    assert_ne!(untagged, arity);
+    assert_ne!(untagged, label);
+    assert_ne!(arity, label);
+}
+
+#[test]
+fn overwrite_mode() {
+    use crate::poseidon::PoseidonSpongeState;
+    use crate::{AbsorbMode, SpongeExt};
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+    let sponge_params = poseidon_parameters_for_test::<Fr>();
+    let rate = sponge_params.rate;
+    let input: Vec<Fr> = (1..=2 * rate as u64).map(Fr::from).collect();
+
+    let mut sponge = PoseidonSponge::new(&sponge_params).with_absorb_mode(AbsorbMode::Overwrite);
+    sponge.absorb(&input);
+    let output = sponge.squeeze_native_field_elements(1)[0];
+
+    // each block of the input replaces the rate before the permutation
+    let mut state = vec![Fr::zero(); sponge_params.state_size()];
+    for block in input.chunks(rate) {
+        state[sponge_params.capacity..].copy_from_slice(block);
+        sponge_params.permute(&mut state);
+    }
+    assert_eq!(output, state[sponge_params.capacity]);
+
+    let mut added = PoseidonSponge::new(&sponge_params);
+    added.absorb(&input);
+    assert_ne!(added.squeeze_native_field_elements(1)[0], output);
+
+    // the absorb mode is kept in checkpoints
+    let mut sponge = PoseidonSponge::new(&sponge_params).with_absorb_mode(AbsorbMode::Overwrite);
+    sponge.absorb(&input[0]);
+    let expected = sponge.clone().squeeze_native_field_elements(2);
+    let mut checkpoint = Vec::new();
+    sponge.into_state().serialize(&mut checkpoint).unwrap();
+    let state = PoseidonSpongeState::<Fr>::deserialize(checkpoint.as_slice()).unwrap();
+    let mut resumed = PoseidonSponge::from_state(state, &sponge_params);
+    assert_eq!(resumed.absorb_mode, AbsorbMode::Overwrite);
+    assert_eq!(resumed.squeeze_native_field_elements(2), expected);
+}
+
+#[test]
+fn security_levels() {
+    use crate::SecurityLevel;
+
+    assert_eq!(SecurityLevel::Bits128.capacity::<Fr>(), 1);
+    assert_eq!(SecurityLevel::Bits256.capacity::<Fr>(), 2);
+    assert_eq!(SecurityLevel::Bits128.capacity::<Fq>(), 1);
+    assert_eq!(SecurityLevel::Bits256.capacity::<Fq>(), 2);
+
+    let params = PoseidonParameters::<Fr>::for_security_level(2, SecurityLevel::Bits128);
+    assert_eq!(params.capacity, 1);
+    assert_eq!(
+        params.full_rounds,
+        PoseidonParameters::<Fr>::default().full_rounds
+    );
+    assert!(params.check_security_level(SecurityLevel::Bits128).is_ok());
+    assert!(params.check_security_level(SecurityLevel::Bits256).is_err());
+
+    let params = PoseidonParameters::<Fr>::for_security_level(2, SecurityLevel::Bits256);
+    assert_eq!(params.capacity, 2);
+    assert!(params.check_security_level(SecurityLevel::Bits256).is_ok());
+
+    // too few partial rounds are rejected
+    let mut params = PoseidonParameters::<Fr>::for_security_level(2, SecurityLevel::Bits128);
+    params.partial_rounds = 20;
+    params.ark.truncate(params.full_rounds + 20);
+    assert!(params.check_security_level(SecurityLevel::Bits128).is_err());
+    assert!(poseidon_parameters_for_test::<Fr>()
+        .check_security_level(SecurityLevel::Bits128)
+        .is_ok());
+}
+
+#[test]
+fn mds_security() {
+    use crate::poseidon::{check_mds_security, generate_secure_mds};
+
+    let mut rng = test_rng();
+    for state_size in 1..=5 {
+        let mds = generate_secure_mds::<Fr, _>(state_size, &mut rng);
+        assert!(check_mds_security(&mds).is_ok());
+    }
+
+    let matrix = |rows: &[&[u64]]| -> Vec<Vec<Fr>> {
+        rows.iter()
+            .map(|row| row.iter().map(|v| Fr::from(*v)).collect())
+            .collect()
+    };
+    // not invertible
+    assert!(check_mds_security(&matrix(&[&[1, 2], &[2, 4]])).is_err());
+    // (1, 1, 1) spans an invariant subspace
+    assert!(check_mds_security(&matrix(&[&[2, 1, 1], &[1, 2, 1], &[1, 1, 2]])).is_err());
+    // (1, 1) and (1, -1) are eigenvectors
+    assert!(check_mds_security(&matrix(&[&[0, 1], &[1, 0]])).is_err());
+    // the minimal polynomial x^2 - 7 is irreducible, as 7 is not a square, but the square of
+    // the matrix is 7 times the identity
+    assert!(check_mds_security(&matrix(&[&[0, 7], &[1, 0]])).is_err());
+    assert!(check_mds_security(&matrix(&[&[1, 2, 3], &[4, 5, 6]])).is_err());
+
+    let sponge_params = poseidon_parameters_for_test::<Fr>();
+    let state_size = sponge_params.state_size();
+    let mds = generate_secure_mds::<Fr, _>(state_size, &mut rng);
+    let checked = PoseidonParameters::new_checked(
+        sponge_params.full_rounds,
+        sponge_params.partial_rounds,
+        sponge_params.alpha,
         mds,
-        rate,
-        capacity,
+        sponge_params.ark.clone(),
+        sponge_params.rate,
+        sponge_params.capacity,
+    );
+    assert!(checked.is_ok());
+    let insecure = PoseidonParameters::new_checked(
+        sponge_params.full_rounds,
+        sponge_params.partial_rounds,
+        sponge_params.alpha,
+        matrix(&[&[2, 1, 1], &[1, 2, 1], &[1, 1, 2]]),
+        sponge_params.ark.clone(),
+        sponge_params.rate,
+        sponge_params.capacity,
+    );
+    assert!(insecure.is_err());
+    let wrong_size = PoseidonParameters::new_checked(
+        sponge_params.full_rounds,
+        sponge_params.partial_rounds + 1,
+        sponge_params.alpha,
+        sponge_params.mds.clone(),
+        sponge_params.ark.clone(),
+        sponge_params.rate,
+        sponge_params.capacity,
+    );
+    assert!(wrong_size.is_err());
+}
+
+#[test]
+fn derived_parameters() {
+    let params = PoseidonParameters::<Fr>::derive(b"ark-sponge/test/v1", 2, 1, 128);
+    let again = PoseidonParameters::<Fr>::derive(b"ark-sponge/test/v1", 2, 1, 128);
+    let other = PoseidonParameters::<Fr>::derive(b"ark-sponge/test/v2", 2, 1, 128);
+    let generated = PoseidonParameters::<Fr>::generate(2, 1, 128);
+    assert_eq!(params.ark, again.ark);
+    assert_eq!(params.mds, again.mds);
+    assert_ne!(params.ark, other.ark);
+    assert_ne!(params.ark, generated.ark);
+    assert_ne!(params.mds, generated.mds);
+    // Only the constants depend on the seed.
+    assert_eq!(params.full_rounds, generated.full_rounds);
+    assert_eq!(params.partial_rounds, generated.partial_rounds);
+    assert_eq!(params.alpha, generated.alpha);
+
+    let mut sponge = PoseidonSponge::new(&params);
+    sponge.absorb(&vec![Fr::from(1u8), Fr::from(2u8)]);
+    let out: Vec<Fr> = sponge.squeeze_field_elements(1);
+    let mut sponge = PoseidonSponge::new(&other);
+    sponge.absorb(&vec![Fr::from(1u8), Fr::from(2u8)]);
+    assert_ne!(out, sponge.squeeze_field_elements::<Fr>(1));
+}
+
+#[test]
+fn new_with_iv() {
+    let sponge_params = poseidon_parameters_for_test();
+    let input = vec![Fr::from(1u8), Fr::from(2u8)];
+    let squeeze = |iv: &[Fr]| {
+        let mut sponge = PoseidonSponge::new_with_iv(&sponge_params, iv);
+        sponge.absorb(&input);
+        sponge.squeeze_field_elements::<Fr>(2)
+    };
+
+    // An empty or zero IV is the same as no IV.
+    let mut sponge = PoseidonSponge::new(&sponge_params);
+    sponge.absorb(&input);
+    let no_iv: Vec<Fr> = sponge.squeeze_field_elements(2);
+    assert_eq!(squeeze(&[]), no_iv);
+    assert_eq!(squeeze(&[Fr::zero()]), no_iv);
+
+    // Different IVs give independent sponges.
+    assert_ne!(squeeze(&[Fr::from(1u8)]), no_iv);
+    assert_ne!(squeeze(&[Fr::from(1u8)]), squeeze(&[Fr::from(2u8)]));
+}
+
+#[test]
+#[should_panic]
+fn new_with_iv_too_long() {
+    let sponge_params = poseidon_parameters_for_test();
+    PoseidonSponge::new_with_iv(&sponge_params, &[Fr::one(), Fr::one()]);
+}
+
+#[test]
+fn try_new_with_iv() {
+    let sponge_params = poseidon_parameters_for_test();
+    assert!(PoseidonSponge::try_new_with_iv(&sponge_params, &[Fr::one()]).is_ok());
+    assert_eq!(
+        PoseidonSponge::try_new_with_iv(&sponge_params, &[Fr::one(), Fr::one()]).err(),
+        Some(SpongeError::InputTooLong { length: 2, max: 1 })
+    );
+}
+
+#[test]
+fn truncated_size_beyond_capacity() {
+    let capacity = <Fr as PrimeField>::Params::CAPACITY as usize;
+    let modulus_bits = <Fr as PrimeField>::Params::MODULUS_BITS as usize;
+    let mut sponge = PoseidonSponge::<Fr>::new(&poseidon_parameters_for_test());
+    let elements = sponge
+        .try_squeeze_field_elements_with_sizes::<Fr>(&[FieldElementSize::Truncated(capacity)])
+        .unwrap();
+    assert_eq!(elements.len(), 1);
+    assert_eq!(
+        sponge.try_squeeze_field_elements_with_sizes::<Fr>(&[FieldElementSize::Truncated(
+            modulus_bits
+        )]),
+        Err(SpongeError::TooManyBits {
+            num_bits: modulus_bits,
+            capacity
+        })
+    );
+}
+
+#[test]
+fn rate_capacity_and_security_level() {
+    let sponge_params = PoseidonParameters::<Fr>::generate(4, 2, 128);
+    let sponge = PoseidonSponge::new(&sponge_params);
+    assert_eq!(sponge.rate(), 4);
+    assert_eq!(sponge.capacity(), 2);
+    assert_eq!(sponge.security_level(), 255);
+}
+
+#[test]
+fn permute_and_finish_absorb() {
+    use crate::PaddingMode;
+
+    let sponge_params = poseidon_parameters_for_test();
+    let input = vec![Fr::from(1u8), Fr::from(2u8), Fr::from(3u8)];
+
+    // Closing the absorb phase early does not change the outputs.
+    for padding in [PaddingMode::None, PaddingMode::LengthSuffix] {
+        let mut sponge = PoseidonSponge::with_padding(&sponge_params, padding);
+        sponge.absorb(&input);
+        let expected: Vec<Fr> = sponge.clone().squeeze_field_elements(3);
+        sponge.finish_absorb();
+        let state = sponge.state.clone();
+        sponge.finish_absorb();
+        assert_eq!(sponge.state, state);
+        assert_eq!(sponge.squeeze_field_elements::<Fr>(3), expected);
+    }
+
+    // Forcing a permutation starts the next absorb at the beginning of the rate.
+    let mut sponge = PoseidonSponge::new(&sponge_params);
+    sponge.absorb(&input[0]);
+    sponge.permute();
+    sponge.absorb(&input[1]);
+    let mut state = vec![Fr::zero(), input[0], Fr::zero()];
+    sponge_params.permute(&mut state);
+    state[1] += input[1];
+    assert_eq!(sponge.state, state);
+
+    // Forcing a permutation while squeezing drops the rest of the block.
+    let mut sponge = PoseidonSponge::new(&sponge_params);
+    sponge.absorb(&input);
+    let outputs: Vec<Fr> = sponge.clone().squeeze_field_elements(3);
+    let _: Vec<Fr> = sponge.squeeze_field_elements(1);
+    sponge.permute();
+    assert_eq!(sponge.squeeze_field_elements::<Fr>(1), outputs[2..]);
+}
+
+#[test]
+fn squeeze_bytes_rule() {
+    use crate::{squeeze_bytes_num_elements, squeeze_bytes_per_element};
+
+    assert_eq!(squeeze_bytes_per_element::<Fr>(), 31);
+    assert_eq!(squeeze_bytes_num_elements::<Fr>(62), 2);
+    assert_eq!(squeeze_bytes_num_elements::<Fr>(63), 3);
+
+    let sponge_params = poseidon_parameters_for_test();
+    for num_bytes in [0, 1, 31, 62, 63] {
+        let mut sponge = PoseidonSponge::new(&sponge_params);
+        sponge.absorb(&Fr::from(1u8));
+        let mut elements_sponge = sponge.clone();
+        let bytes = sponge.squeeze_bytes(num_bytes);
+
+        let elements: Vec<Fr> = elements_sponge
+            .squeeze_native_field_elements(squeeze_bytes_num_elements::<Fr>(num_bytes));
+        let mut expected = Vec::new();
+        for elem in elements {
+            expected.extend_from_slice(&elem.into_repr().to_bytes_le()[..31]);
+        }
+        expected.truncate(num_bytes);
+        assert_eq!(bytes, expected);
+        // Nothing more than the minimal number of elements was squeezed.
+        assert_eq!(sponge.state, elements_sponge.state);
+        assert_eq!(sponge.mode, elements_sponge.mode);
+    }
+}
+
+#[test]
+fn squeeze_with_endianness() {
+    use crate::{Endianness, FieldElementSize};
+
+    let sponge_params = poseidon_parameters_for_test();
+    let mut sponge = PoseidonSponge::new(&sponge_params);
+    sponge.absorb(&Fr::from(1u8));
+
+    let elements: Vec<Fr> = sponge.clone().squeeze_native_field_elements(2);
+    let mut expected_bytes: Vec<u8> = elements[0].into_repr().to_bytes_le()[..31].to_vec();
+    expected_bytes.reverse();
+    expected_bytes.push(elements[1].into_repr().to_bytes_le()[30]);
+    assert_eq!(
+        sponge
+            .clone()
+            .squeeze_bytes_with_endianness(32, Endianness::Big),
+        expected_bytes
+    );
+    assert_eq!(
+        sponge
+            .clone()
+            .squeeze_bytes_with_endianness(32, Endianness::Little),
+        sponge.clone().squeeze_bytes(32)
+    );
+
+    let capacity = <Fr as PrimeField>::Params::CAPACITY as usize;
+    let mut expected_bits: Vec<bool> = elements[0].into_repr().to_bits_le()[..capacity].to_vec();
+    expected_bits.reverse();
+    expected_bits.push(elements[1].into_repr().to_bits_le()[capacity - 1]);
+    assert_eq!(
+        sponge
+            .clone()
+            .squeeze_bits_with_endianness(capacity + 1, Endianness::Big),
+        expected_bits
+    );
+
+    // A full element is read from the bits of a whole squeezed element, in either order.
+    let sizes = [FieldElementSize::Full, FieldElementSize::Truncated(8)];
+    let big: Vec<Fr> = sponge
+        .clone()
+        .squeeze_nonnative_field_elements_with_sizes_and_endianness(&sizes, Endianness::Big);
+    let little: Vec<Fr> = sponge
+        .clone()
+        .squeeze_nonnative_field_elements_with_sizes_and_endianness(&sizes, Endianness::Little);
+    assert_eq!(big[0], little[0]);
+    // The truncated element is the integer of the 8 most significant bits of its element.
+    let top_bits = &elements[1].into_repr().to_bits_le()[capacity - 8..capacity];
+    let expected = top_bits
+        .iter()
+        .enumerate()
+        .fold(0u8, |byte, (i, &bit)| byte | ((bit as u8) << i));
+    assert_eq!(big[1], Fr::from(expected));
+    assert_eq!(
+        sponge
+            .clone()
+            .squeeze_nonnative_field_elements_with_sizes_and_endianness::<Fr>(
+                &sizes,
+                Endianness::Little
+            ),
+        sponge.squeeze_nonnative_field_elements_with_sizes::<Fr>(&sizes)
+    );
+}
+
+#[test]
+fn clones_share_parameters() {
+    use crate::poseidon::PoseidonCowSponge;
+
+    let sponge_params = poseidon_parameters_for_test();
+    let mut sponge = PoseidonCowSponge::<Fr>::new(&sponge_params);
+    sponge.absorb(&Fr::from(1u8));
+    let mut fork = sponge.clone();
+    assert!(core::ptr::eq(sponge.parameters(), fork.parameters()));
+    assert!(core::ptr::eq(sponge.state.as_ref(), fork.state.as_ref()));
+
+    // The clones are independent once one of them is used.
+    fork.absorb(&Fr::from(2u8));
+    let fork_output: Vec<Fr> = fork.squeeze_native_field_elements(1);
+    let mut expected = PoseidonSponge::<Fr>::new(&sponge_params);
+    expected.absorb(&Fr::from(1u8));
+    assert_eq!(
+        sponge.squeeze_native_field_elements(1),
+        expected.squeeze_native_field_elements(1)
+    );
+    assert_ne!(sponge.state, fork.state);
+    assert_ne!(fork_output, expected.squeeze_native_field_elements(1));
+}
+
+#[test]
+fn modified_shared_parameters() {
+    let sponge_params = poseidon_parameters_for_test();
+    let mut sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+    let clone = sponge.clone();
+    alloc::sync::Arc::make_mut(&mut sponge.parameters).ark[5][1] += Fr::one();
+
+    let mut modified_params = sponge_params.clone();
+    modified_params.ark[5][1] += Fr::one();
+    let mut expected = PoseidonSponge::<Fr>::new(&modified_params);
+    assert_eq!(
+        sponge.squeeze_native_field_elements(2),
+        expected.squeeze_native_field_elements(2)
+    );
+    assert_eq!(clone.parameters.ark, sponge_params.ark);
+}
+
+#[test]
+fn poseidon_family() {
+    use crate::poseidon::{PoseidonFamily, POSEIDON_FAMILY_ARITIES};
+    use crate::SecurityLevel;
+
+    let family = PoseidonFamily::<Fr>::new(SecurityLevel::Bits128);
+    assert_eq!(family.arities(), POSEIDON_FAMILY_ARITIES.to_vec());
+    for arity in POSEIDON_FAMILY_ARITIES {
+        let params = family.parameters(arity).unwrap();
+        assert_eq!(params.rate, arity);
+        assert_eq!(params.capacity, SecurityLevel::Bits128.capacity::<Fr>());
+
+        let input: Vec<Fr> = (0..arity as u64).map(Fr::from).collect();
+        let expected = PoseidonSponge::new(&PoseidonParameters::for_security_level(
+            arity,
+            SecurityLevel::Bits128,
+        ))
+        .compress(&input)
+        .unwrap();
+        assert_eq!(family.compress_n(&input).unwrap(), expected);
+    }
+    assert!(family.parameters(3).is_none());
+    assert!(matches!(
+        family.compress_n(&[Fr::from(1u8); 3]),
+        Err(SpongeError::InvalidParameters(_))
+    ));
+
+    let family = PoseidonFamily::<Fr>::with_arities(&[4, 2, 4], SecurityLevel::Bits128);
+    assert_eq!(family.arities(), vec![2, 4]);
+}
+
+#[cfg(feature = "stats")]
+#[test]
+fn alternating_absorb_and_squeeze() {
+    let sponge_param = poseidon_parameters_for_test::<Fr>();
+    let mut sponge = PoseidonSponge::<Fr>::new(&sponge_param);
+    for i in 0..5u64 {
+        sponge.absorb(&Fr::from(i));
+        sponge.squeeze_native_field_elements(1);
+    }
+    // only the squeezes permute, as the rate is never exhausted
+    assert_eq!(sponge.stats().num_permutations, 5);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_parameters() {
+    let sponge_param =
+        poseidon_parameters_for_test::<Fr>().with_domain_tag(PoseidonDomainTag::Arity);
+    let json = serde_json::to_string(&sponge_param).unwrap();
+    let read: PoseidonParameters<Fr> = serde_json::from_str(&json).unwrap();
+    assert_eq!(read.ark, sponge_param.ark);
+    assert_eq!(read.mds, sponge_param.mds);
+    assert_eq!(read.domain_tag, PoseidonDomainTag::Arity);
+    assert_eq!(
+        PoseidonSponge::new(&read).squeeze_native_field_elements(3),
+        PoseidonSponge::new(&sponge_param).squeeze_native_field_elements(3)
+    );
+
+    // labels are read back as elements of the same value
+    let labeled = sponge_param.with_domain_tag(PoseidonDomainTag::Label(b"label"));
+    let read: PoseidonParameters<Fr> =
+        serde_json::from_str(&serde_json::to_string(&labeled).unwrap()).unwrap();
+    assert_eq!(
+        read.domain_tag,
+        PoseidonDomainTag::Element(labeled.domain_tag.value(labeled.rate))
+    );
+
+    // parameters of another field, or of the wrong size, are rejected
+    assert!(serde_json::from_str::<PoseidonParameters<Fq>>(&json).is_err());
+    let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    value["ark"].as_array_mut().unwrap().pop();
+    assert!(serde_json::from_value::<PoseidonParameters<Fr>>(value).is_err());
+}
+
+pub(crate) mod small_field {
+    use ark_ff::{BigInteger64, FftParameters, Fp64, Fp64Parameters, FpParameters};
+
+    pub struct TestFp64Parameters;
+
+    impl Fp64Parameters for TestFp64Parameters {}
+    impl FftParameters for TestFp64Parameters {
+        type BigInt = BigInteger64;
+
+        const TWO_ADICITY: u32 = 41;
+
+        const TWO_ADIC_ROOT_OF_UNITY: BigInteger64 = BigInteger64([0x6c679e21623edbd1]);
     }
+
+    // MODULUS = 2^63 - 2^41 + 1 = 9223369837831520257
+    impl FpParameters for TestFp64Parameters {
+        const MODULUS: BigInteger64 = BigInteger64([0x7ffffe0000000001]);
+        const MODULUS_BITS: u32 = 63;
+        const CAPACITY: u32 = Self::MODULUS_BITS - 1;
+        const REPR_SHAVE_BITS: u32 = 1;
+        const R: BigInteger64 = BigInteger64([0x3fffffffffe]);
+        const R2: BigInteger64 = BigInteger64([0x3fffefffffe00004]);
+        const INV: u64 = 0x7ffffdffffffffff;
+        // GENERATOR = 7, in Montgomery form
+        const GENERATOR: BigInteger64 = BigInteger64([0x1bfffffffff2]);
+        const MODULUS_MINUS_ONE_DIV_TWO: BigInteger64 = BigInteger64([0x3fffff0000000000]);
+        const T: BigInteger64 = BigInteger64([0x3fffff]);
+        const T_MINUS_ONE_DIV_TWO: BigInteger64 = BigInteger64([0x1fffff]);
+    }
+
+    /// A 63-bit field, the largest kind of small field `ark-ff` supports.
+    pub type TestFp64 = Fp64<TestFp64Parameters>;
+}
+
+#[test]
+fn small_field() {
+    use crate::poseidon::SMALL_FIELD_STATE_SIZE;
+    use crate::SecurityLevel;
+    use ark_ff::{FftField, Field};
+    use ark_std::rand::Rng;
+    use small_field::TestFp64;
+
+    // sanity checks of the constants of the field
+    assert_eq!(TestFp64::from(7u64), TestFp64::multiplicative_generator());
+    assert_eq!(
+        TestFp64::two_adic_root_of_unity().pow([1u64 << 41]),
+        TestFp64::one()
+    );
+    assert_eq!(
+        TestFp64::from(3u64).inverse().unwrap() * TestFp64::from(3u64),
+        TestFp64::one()
+    );
+
+    let params = PoseidonParameters::<TestFp64>::for_small_field(SecurityLevel::Bits128);
+    assert_eq!(params.state_size(), SMALL_FIELD_STATE_SIZE);
+    assert_eq!((params.rate, params.capacity, params.alpha), (8, 4, 5));
+    assert_eq!((params.full_rounds, params.partial_rounds), (8, 27));
+    assert!(params.check_security_level(SecurityLevel::Bits128).is_ok());
+    let params256 = PoseidonParameters::<TestFp64>::for_small_field(SecurityLevel::Bits256);
+    assert_eq!((params256.rate, params256.capacity), (4, 8));
+    assert!(params256
+        .check_security_level(SecurityLevel::Bits256)
+        .is_ok());
+
+    // the batched permutation agrees with the single one
+    let mut rng = test_rng();
+    let mut states: Vec<Vec<TestFp64>> = (0..11)
+        .map(|_| (0..12).map(|_| TestFp64::rand(&mut rng)).collect())
+        .collect();
+    let mut expected = states.clone();
+    expected.iter_mut().for_each(|state| params.permute(state));
+    params.permute_batch(&mut states);
+    assert_eq!(states, expected);
+
+    // words are packed as their bits, 62 bits per element
+    for num_words in [0usize, 1, 2, 31, 32, 33] {
+        let words: Vec<u64> = (0..num_words).map(|_| rng.gen()).collect();
+        let bits: Vec<bool> = words
+            .iter()
+            .flat_map(|word| (0..64).map(move |i| (word >> i) & 1 == 1))
+            .collect();
+        let mut sponge1 = PoseidonSponge::new(&params);
+        let mut sponge2 = PoseidonSponge::new(&params);
+        sponge1.absorb_u64s(&words);
+        sponge2.absorb_bits(&bits);
+        assert_eq!(
+            sponge1.squeeze_native_field_elements(2),
+            sponge2.squeeze_native_field_elements(2)
+        );
+
+        let mut elems = Vec::<TestFp64>::new();
+        crate::absorb::pack_u64s_without_length(&words, &mut elems);
+        assert_eq!(elems.len(), (64 * num_words).div_ceil(62));
+    }
+}
+
+#[test]
+fn squeeze_permutation() {
+    let sponge_param = poseidon_parameters_for_test::<Fr>();
+    let mut sponge = PoseidonSponge::new(&sponge_param);
+    assert!(sponge.squeeze_permutation(0).is_empty());
+    assert_eq!(sponge.squeeze_permutation(1), vec![0]);
+
+    // every permutation of three elements is reached, and each element of the output is
+    // roughly uniform
+    let mut seen = Vec::new();
+    let mut counts = [0usize; 10];
+    for i in 0u64..300 {
+        sponge.absorb(&i);
+        let permutation = sponge.squeeze_permutation(3);
+        if !seen.contains(&permutation) {
+            seen.push(permutation);
+        }
+        counts[sponge.squeeze_permutation(10)[0]] += 1;
+    }
+    assert_eq!(seen.len(), 6);
+    assert!(counts.iter().all(|count| (10..=50).contains(count)));
+}
+
+#[test]
+fn absorb_heterogeneous_values() {
+    use crate::{AbsorbList, Absorbable};
+
+    let mut rng = test_rng();
+    let point = G1Projective::rand(&mut rng).into_affine();
+    let scalar = Fr::rand(&mut rng);
+    let label = b"round 1".to_vec();
+
+    let mut list = AbsorbList::<Fr>::new();
+    list.push(&point).push(&scalar).push(&label);
+    assert_eq!(list.len(), 3);
+    let values: [&dyn Absorbable<Fr>; 3] = [&point, &scalar, &label];
+    let tuple = (point, scalar, label.clone());
+
+    assert_eq!(
+        list.to_sponge_bytes_as_vec(),
+        tuple.to_sponge_bytes_as_vec()
+    );
+    assert_eq!(
+        list.to_sponge_field_elements_as_vec::<Fr>(),
+        tuple.to_sponge_field_elements_as_vec::<Fr>()
+    );
+    assert_eq!(
+        values.to_sponge_field_elements_as_vec::<Fr>(),
+        tuple.to_sponge_field_elements_as_vec::<Fr>()
+    );
+
+    let sponge_param = poseidon_parameters_for_test();
+    let mut sponge1 = PoseidonSponge::<Fr>::new(&sponge_param);
+    let mut sponge2 = PoseidonSponge::<Fr>::new(&sponge_param);
+    let mut sponge3 = PoseidonSponge::<Fr>::new(&sponge_param);
+    sponge1.absorb(&list);
+    sponge2.absorb(&values);
+    sponge3.absorb(&tuple);
+    let expected = sponge3.squeeze_native_field_elements(2);
+    assert_eq!(sponge1.squeeze_native_field_elements(2), expected);
+    assert_eq!(sponge2.squeeze_native_field_elements(2), expected);
+
+    // a sponge over another field absorbs the elements of `Fr` as nonnative elements
+    let mut sponge = PoseidonSponge::<Fq>::new(&poseidon_parameters_for_test());
+    sponge.absorb(&AbsorbList::<Fr>::new().with(&scalar));
+    let mut expected = PoseidonSponge::<Fq>::new(&poseidon_parameters_for_test());
+    expected.absorb(&scalar);
+    assert_eq!(
+        sponge.squeeze_native_field_elements(1),
+        expected.squeeze_native_field_elements(1)
+    );
+}
+
+#[test]
+fn absorb_strings() {
+    use ark_std::string::String;
+
+    let label = "round 1";
+    assert_eq!(
+        label.to_sponge_bytes_as_vec(),
+        String::from(label).to_sponge_bytes_as_vec()
+    );
+    assert_eq!(
+        label.to_sponge_field_elements_as_vec::<Fr>(),
+        String::from(label).to_sponge_field_elements_as_vec::<Fr>()
+    );
+    assert_eq!(
+        label.to_sponge_field_elements_as_vec::<Fr>(),
+        label.as_bytes().to_sponge_field_elements_as_vec::<Fr>()
+    );
+
+    let bytes = label.to_sponge_bytes_as_vec();
+    assert_eq!(bytes[..8], (label.len() as u64).to_le_bytes());
+    assert_eq!(&bytes[8..], label.as_bytes());
+
+    assert_ne!(
+        ("ab", "c").to_sponge_bytes_as_vec(),
+        ("a", "bc").to_sponge_bytes_as_vec()
+    );
+    assert_ne!(
+        ("ab", "c").to_sponge_field_elements_as_vec::<Fr>(),
+        ("a", "bc").to_sponge_field_elements_as_vec::<Fr>()
+    );
+}
+
+#[test]
+fn hash_path() {
+    let sponge = PoseidonSponge::<Fr>::new(&poseidon_parameters_for_test());
+    let mut rng = test_rng();
+    let leaves: Vec<Fr> = (0..4).map(|_| Fr::rand(&mut rng)).collect();
+    let left = sponge.compress(&leaves[..2]).unwrap();
+    let right = sponge.compress(&leaves[2..]).unwrap();
+    let root = sponge.compress(&[left, right]).unwrap();
+
+    assert_eq!(sponge.hash_path(leaves[0], &[]).unwrap(), leaves[0]);
+    assert_eq!(
+        sponge
+            .hash_path(leaves[2], &[(false, leaves[3]), (true, left)])
+            .unwrap(),
+        root
+    );
+    assert_eq!(
+        sponge
+            .hash_path(leaves[1], &[(true, leaves[0]), (false, right)])
+            .unwrap(),
+        root
+    );
+    assert_ne!(
+        sponge
+            .hash_path(leaves[1], &[(false, leaves[0]), (false, right)])
+            .unwrap(),
+        root
+    );
+
+    let wide = PoseidonSponge::<Fr>::new(&PoseidonParameters::generate(3, 1, 128));
+    assert!(wide.hash_path(leaves[0], &[(false, leaves[1])]).is_err());
 }
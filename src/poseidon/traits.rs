@@ -78,6 +78,11 @@ pub fn get_default_poseidon_parameters_internal<F: PrimeField, P: PoseidonDefaul
 
     for param in params_set.iter() {
         if param.rate == rate {
+            #[cfg(feature = "std")]
+            if let Some(parameters) = cache::get::<F, P>(rate, optimized_for_weights) {
+                return Some(parameters);
+            }
+
             let (ark, mds) = find_poseidon_ark_and_mds::<F>(
                 P::MODULUS_BITS as u64,
                 rate,
@@ -86,21 +91,77 @@ pub fn get_default_poseidon_parameters_internal<F: PrimeField, P: PoseidonDefaul
                 param.skip_matrices as u64,
             );
 
-            return Some(PoseidonParameters {
-                full_rounds: param.full_rounds,
-                partial_rounds: param.partial_rounds,
-                alpha: param.alpha as u64,
-                ark,
+            let parameters = PoseidonParameters::new(
+                param.full_rounds,
+                param.partial_rounds,
+                param.alpha as i64,
                 mds,
-                rate: param.rate,
-                capacity: 1,
-            });
+                ark,
+                param.rate,
+                1,
+            );
+            #[cfg(feature = "std")]
+            cache::insert::<F, P>(rate, optimized_for_weights, &parameters);
+            return Some(parameters);
         }
     }
 
     None
 }
 
+/// The default parameters derived so far, so that the Grain LFSR only runs once for each field,
+/// rate and optimization goal. The parameters are cloned out of the cache, which shares their
+/// optimized form.
+#[cfg(feature = "std")]
+mod cache {
+    use crate::poseidon::PoseidonParameters;
+    use ark_ff::{FpParameters, PrimeField};
+    use std::any::{Any, TypeId};
+    use std::collections::HashMap;
+    use std::sync::{Mutex, OnceLock};
+
+    type Key = (TypeId, TypeId, usize, bool);
+
+    fn cache() -> &'static Mutex<HashMap<Key, Box<dyn Any + Send + Sync>>> {
+        static CACHE: OnceLock<Mutex<HashMap<Key, Box<dyn Any + Send + Sync>>>> = OnceLock::new();
+        CACHE.get_or_init(Default::default)
+    }
+
+    fn key<F: PrimeField, P: FpParameters>(rate: usize, optimized_for_weights: bool) -> Key {
+        (
+            TypeId::of::<F>(),
+            TypeId::of::<P>(),
+            rate,
+            optimized_for_weights,
+        )
+    }
+
+    pub(super) fn get<F: PrimeField, P: FpParameters>(
+        rate: usize,
+        optimized_for_weights: bool,
+    ) -> Option<PoseidonParameters<F>> {
+        let cache = cache().lock().unwrap_or_else(|e| e.into_inner());
+        cache
+            .get(&key::<F, P>(rate, optimized_for_weights))
+            .and_then(|parameters| parameters.downcast_ref::<PoseidonParameters<F>>())
+            .cloned()
+    }
+
+    // The LFSR runs without the lock, so two threads may derive the same parameters, which are
+    // equal.
+    pub(super) fn insert<F: PrimeField, P: FpParameters>(
+        rate: usize,
+        optimized_for_weights: bool,
+        parameters: &PoseidonParameters<F>,
+    ) {
+        let mut cache = cache().lock().unwrap_or_else(|e| e.into_inner());
+        cache.insert(
+            key::<F, P>(rate, optimized_for_weights),
+            Box::new(parameters.clone()),
+        );
+    }
+}
+
 /// Internal function that computes the ark and mds from the Poseidon Grain LFSR.
 pub fn find_poseidon_ark_and_mds<F: PrimeField>(
     prime_bits: u64,
@@ -108,24 +169,58 @@ pub fn find_poseidon_ark_and_mds<F: PrimeField>(
     full_rounds: u64,
     partial_rounds: u64,
     skip_matrices: u64,
+) -> (Vec<Vec<F>>, Vec<Vec<F>>) {
+    find_poseidon_ark_and_mds_with_state_size(
+        prime_bits,
+        rate + 1,
+        full_rounds,
+        partial_rounds,
+        skip_matrices,
+    )
+}
+
+/// Computes the ark and mds from the Poseidon Grain LFSR for a state of `state_size` elements,
+/// i.e., for any split of the state into rate and capacity.
+pub(crate) fn find_poseidon_ark_and_mds_with_state_size<F: PrimeField>(
+    prime_bits: u64,
+    state_size: usize,
+    full_rounds: u64,
+    partial_rounds: u64,
+    skip_matrices: u64,
 ) -> (Vec<Vec<F>>, Vec<Vec<F>>) {
     let mut lfsr = PoseidonGrainLFSR::new(
         false,
         prime_bits,
-        (rate + 1) as u64,
+        state_size as u64,
         full_rounds,
         partial_rounds,
     );
+    find_poseidon_ark_and_mds_with_lfsr(
+        &mut lfsr,
+        state_size,
+        full_rounds,
+        partial_rounds,
+        skip_matrices,
+    )
+}
 
+/// Samples the ark and mds from `lfsr` for a state of `state_size` elements.
+pub(crate) fn find_poseidon_ark_and_mds_with_lfsr<F: PrimeField>(
+    lfsr: &mut PoseidonGrainLFSR,
+    state_size: usize,
+    full_rounds: u64,
+    partial_rounds: u64,
+    skip_matrices: u64,
+) -> (Vec<Vec<F>>, Vec<Vec<F>>) {
     let mut ark = Vec::<Vec<F>>::new();
     for _ in 0..(full_rounds + partial_rounds) {
-        ark.push(lfsr.get_field_elements_rejection_sampling(rate + 1));
+        ark.push(lfsr.get_field_elements_rejection_sampling(state_size));
     }
 
     let mut mds = Vec::<Vec<F>>::new();
-    mds.resize(rate + 1, vec![F::zero(); rate + 1]);
+    mds.resize(state_size, vec![F::zero(); state_size]);
     for _ in 0..skip_matrices {
-        let _ = lfsr.get_field_elements_mod_p::<F>(2 * (rate + 1));
+        let _ = lfsr.get_field_elements_mod_p::<F>(2 * state_size);
     }
 
     // a qualifying matrix must satisfy the following requirements
@@ -133,12 +228,12 @@ pub fn find_poseidon_ark_and_mds<F: PrimeField>(
     // - there is no i and j such that x[i] + y[j] = p
     // - the resultant MDS passes all the three tests
 
-    let xs = lfsr.get_field_elements_mod_p::<F>(rate + 1);
-    let ys = lfsr.get_field_elements_mod_p::<F>(rate + 1);
+    let xs = lfsr.get_field_elements_mod_p::<F>(state_size);
+    let ys = lfsr.get_field_elements_mod_p::<F>(state_size);
 
-    for i in 0..(rate + 1) {
-        for j in 0..(rate + 1) {
-            mds[i][j] = (xs[i] + &ys[j]).inverse().unwrap();
+    for i in 0..state_size {
+        for j in 0..state_size {
+            mds[i][j] = (xs[i] + ys[j]).inverse().unwrap();
         }
     }
 
@@ -451,4 +546,21 @@ mod test {
             )
         );
     }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn default_parameters_are_cached() {
+        use alloc::sync::Arc;
+
+        let first = TestFr::get_default_poseidon_parameters(3, false).unwrap();
+        let second = TestFr::get_default_poseidon_parameters(3, false).unwrap();
+        assert_eq!(first.ark, second.ark);
+        assert!(Arc::ptr_eq(
+            first.optimized_parameters.as_ref().unwrap(),
+            second.optimized_parameters.as_ref().unwrap()
+        ));
+
+        let weights = TestFr::get_default_poseidon_parameters(3, true).unwrap();
+        assert_ne!(first.partial_rounds, weights.partial_rounds);
+    }
 }
@@ -0,0 +1,161 @@
+use crate::duplex::constraints::{DuplexSpongeVar, FieldPermutationGadget};
+use crate::poseidon2::{Poseidon2Parameters, M4};
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::SynthesisError;
+use ark_std::vec::Vec;
+
+/// The gadget for [`Poseidon2Sponge`](crate::poseidon2::Poseidon2Sponge).
+pub type Poseidon2SpongeVar<F> = DuplexSpongeVar<F, Poseidon2Parameters<F>>;
+
+impl<F: PrimeField> Poseidon2Parameters<F> {
+    pub(crate) fn apply_external_matrix_var(state: &mut [FpVar<F>]) {
+        if state.len() < 4 {
+            let sum: FpVar<F> = state.iter().sum();
+            for elem in state.iter_mut() {
+                *elem += &sum;
+            }
+            return;
+        }
+
+        for chunk in state.chunks_exact_mut(4) {
+            let input: Vec<_> = chunk.to_vec();
+            for (elem, row) in chunk.iter_mut().zip(&M4) {
+                *elem = row
+                    .iter()
+                    .zip(&input)
+                    .map(|(entry, x)| x * F::from(*entry))
+                    .fold(FpVar::zero(), |sum, term| sum + term);
+            }
+        }
+        let mut sums = ark_std::vec![FpVar::zero(); 4];
+        for chunk in state.chunks_exact(4) {
+            for (sum, elem) in sums.iter_mut().zip(chunk) {
+                *sum += elem;
+            }
+        }
+        for chunk in state.chunks_exact_mut(4) {
+            for (elem, sum) in chunk.iter_mut().zip(&sums) {
+                *elem += sum;
+            }
+        }
+    }
+
+    fn apply_internal_matrix_var(&self, state: &mut [FpVar<F>]) {
+        let sum: FpVar<F> = state.iter().sum();
+        for (elem, diagonal) in state.iter_mut().zip(&self.internal_diagonal) {
+            *elem = &sum + &*elem * *diagonal;
+        }
+    }
+}
+
+impl<F: PrimeField> FieldPermutationGadget<F> for Poseidon2Parameters<F> {
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    fn permute_var(&self, state: &mut [FpVar<F>]) -> Result<(), SynthesisError> {
+        let full_rounds_over_2 = self.full_rounds / 2;
+        let (first_external_ark, last_external_ark) =
+            self.external_ark.split_at(full_rounds_over_2);
+
+        Self::apply_external_matrix_var(state);
+        for ark in first_external_ark {
+            for (elem, constant) in state.iter_mut().zip(ark) {
+                *elem = (&*elem + *constant).pow_by_constant([self.alpha])?;
+            }
+            Self::apply_external_matrix_var(state);
+        }
+
+        for constant in &self.internal_ark {
+            state[0] = (&state[0] + *constant).pow_by_constant([self.alpha])?;
+            self.apply_internal_matrix_var(state);
+        }
+
+        for ark in last_external_ark {
+            for (elem, constant) in state.iter_mut().zip(ark) {
+                *elem = (&*elem + *constant).pow_by_constant([self.alpha])?;
+            }
+            Self::apply_external_matrix_var(state);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constraints::CryptographicSpongeVar;
+    use crate::duplex::constraints::FieldPermutationGadget;
+    use crate::duplex::FieldPermutation;
+    use crate::poseidon2::constraints::Poseidon2SpongeVar;
+    use crate::poseidon2::{Poseidon2Parameters, Poseidon2Sponge};
+    use crate::{CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_ff::UniformRand;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_relations::*;
+    use ark_std::test_rng;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn permutation_test() {
+        let mut rng = test_rng();
+        let parameters = Poseidon2Parameters::<Fr>::generate(2, 1, 128);
+
+        // Also check a wider instance, with arbitrary internal matrix and round constants.
+        let mut wide_parameters = parameters.clone();
+        wide_parameters.rate = 7;
+        wide_parameters.internal_diagonal = (0..8).map(|_| Fr::rand(&mut rng)).collect();
+        wide_parameters.external_ark = (0..8)
+            .map(|_| (0..8).map(|_| Fr::rand(&mut rng)).collect())
+            .collect();
+
+        for parameters in [parameters, wide_parameters] {
+            let cs = ConstraintSystem::new_ref();
+            let mut state: Vec<_> = (0..parameters.state_size())
+                .map(|_| Fr::rand(&mut rng))
+                .collect();
+            let mut state_var: Vec<_> = state
+                .iter()
+                .map(|v| FpVar::new_witness(ns!(cs, "state"), || Ok(*v)).unwrap())
+                .collect();
+
+            parameters.permute(&mut state);
+            parameters.permute_var(&mut state_var).unwrap();
+
+            assert_eq!(state_var.value().unwrap(), state);
+            assert!(cs.is_satisfied().unwrap());
+            // x^5 costs 3 constraints
+            assert_eq!(
+                cs.num_constraints(),
+                3 * (parameters.full_rounds * parameters.state_size() + parameters.partial_rounds)
+            );
+        }
+    }
+
+    #[test]
+    fn sponge_test() {
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::new_ref();
+        let parameters = Poseidon2Parameters::<Fr>::generate(2, 1, 128);
+
+        let absorb: Vec<_> = (0..5).map(|_| Fr::rand(&mut rng)).collect();
+        let absorb_var: Vec<_> = absorb
+            .iter()
+            .map(|v| FpVar::new_input(ns!(cs, "absorb"), || Ok(*v)).unwrap())
+            .collect();
+
+        let mut native_sponge = Poseidon2Sponge::<Fr>::new(&parameters);
+        let mut constraint_sponge = Poseidon2SpongeVar::<Fr>::new(cs.clone(), &parameters);
+
+        native_sponge.absorb(&absorb);
+        constraint_sponge.absorb(&absorb_var).unwrap();
+
+        let squeeze1 = native_sponge.squeeze_native_field_elements(3);
+        let squeeze2 = constraint_sponge.squeeze_field_elements(3).unwrap();
+
+        assert_eq!(squeeze2.value().unwrap(), squeeze1);
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
@@ -0,0 +1,378 @@
+use crate::duplex::{DuplexSponge, FieldPermutation};
+use crate::poseidon::find_poseidon_round_numbers;
+use crate::poseidon::grain_lfsr::PoseidonGrainLFSR;
+use ark_ff::{FpParameters, PrimeField};
+use ark_std::vec::Vec;
+
+/// constraints for Poseidon2
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+/// The 4x4 matrix from which the external matrix is built.
+pub(crate) const M4: [[u64; 4]; 4] = [[5, 7, 1, 3], [4, 6, 1, 1], [1, 3, 5, 7], [1, 1, 4, 6]];
+
+/// A duplex sponge based on the [Poseidon2][p2] permutation.
+///
+/// [p2]: https://eprint.iacr.org/2023/323
+pub type Poseidon2Sponge<F> = DuplexSponge<F, Poseidon2Parameters<F>>;
+
+/// Parameters of the [Poseidon2][p2] permutation.
+///
+/// Poseidon2 applies an external linear layer to the input, followed by `full_rounds / 2`
+/// external rounds, `partial_rounds` internal rounds, and another `full_rounds / 2` external
+/// rounds. External rounds add a round constant to every element, apply the S-box `x^alpha` to
+/// every element, and multiply with the external matrix. Internal rounds add a round constant
+/// to and apply the S-box to the first element only, and multiply with the internal matrix.
+///
+/// The external matrix is `circ(2, 1)` or `circ(2, 1, 1)` for states of 2 or 3 elements, and is
+/// built from the 4x4 matrix `M4` of the paper for states whose size is a multiple of 4.
+/// The internal matrix is `J + diag(internal_diagonal)`, where `J` is the all-ones matrix.
+///
+/// [p2]: https://eprint.iacr.org/2023/323
+#[derive(Clone, Debug)]
+pub struct Poseidon2Parameters<F: PrimeField> {
+    /// Number of external rounds.
+    pub full_rounds: usize,
+    /// Number of internal rounds.
+    pub partial_rounds: usize,
+    /// Exponent used in S-boxes.
+    pub alpha: u64,
+    /// Round constants of the external rounds, indexed by
+    /// `external_ark[round_num][state_element_index]`.
+    pub external_ark: Vec<Vec<F>>,
+    /// Round constants of the internal rounds.
+    pub internal_ark: Vec<F>,
+    /// The diagonal of the internal matrix, minus one.
+    pub internal_diagonal: Vec<F>,
+    /// The rate (in terms of number of field elements).
+    pub rate: usize,
+    /// The capacity (in terms of number of field elements).
+    pub capacity: usize,
+}
+
+impl<F: PrimeField> Poseidon2Parameters<F> {
+    /// Initialize the parameters of the Poseidon2 permutation.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        full_rounds: usize,
+        partial_rounds: usize,
+        alpha: u64,
+        external_ark: Vec<Vec<F>>,
+        internal_ark: Vec<F>,
+        internal_diagonal: Vec<F>,
+        rate: usize,
+        capacity: usize,
+    ) -> Self {
+        let state_size = rate + capacity;
+        assert!(
+            state_size == 2 || state_size == 3 || state_size.is_multiple_of(4),
+            "the state size must be 2, 3, or a multiple of 4"
+        );
+        assert!(
+            full_rounds.is_multiple_of(2),
+            "the number of full rounds must be even"
+        );
+        assert!(alpha > 1, "alpha must be greater than 1");
+        assert_eq!(external_ark.len(), full_rounds);
+        for item in &external_ark {
+            assert_eq!(item.len(), state_size);
+        }
+        assert_eq!(internal_ark.len(), partial_rounds);
+        assert_eq!(internal_diagonal.len(), state_size);
+
+        Self {
+            full_rounds,
+            partial_rounds,
+            alpha,
+            external_ark,
+            internal_ark,
+            internal_diagonal,
+            rate,
+            capacity,
+        }
+    }
+
+    /// Generate parameters for `F` providing `security_level` bits of security, for a state of
+    /// 2 or 3 elements.
+    ///
+    /// The S-box exponent is the smallest `alpha` for which `x^alpha` is a permutation of `F`,
+    /// the round numbers are those of Poseidon (see [`find_poseidon_round_numbers`]), and the
+    /// round constants are sampled with the Grain LFSR, one per state element in the external
+    /// rounds and a single one in the internal rounds, as in the
+    /// [reference implementation](https://github.com/HorizenLabs/poseidon2). The internal matrices are `[[2, 1], [1, 3]]` and
+    /// `[[2, 1, 1], [1, 2, 1], [1, 1, 3]]`, as in the paper. Larger states require an internal
+    /// matrix chosen for the specific field, which must be provided through
+    /// [`Poseidon2Parameters::new`].
+    pub fn generate(rate: usize, capacity: usize, security_level: usize) -> Self {
+        let state_size = rate + capacity;
+        let internal_diagonal = match state_size {
+            2 => ark_std::vec![F::one(), F::from(2u8)],
+            3 => ark_std::vec![F::one(), F::one(), F::from(2u8)],
+            _ => panic!("parameters can only be generated for states of 2 or 3 elements"),
+        };
+
+        let alpha = crate::poseidon::find_poseidon_alpha::<F>();
+        let (full_rounds, partial_rounds) =
+            find_poseidon_round_numbers::<F>(state_size, alpha, security_level, false);
+
+        let mut lfsr = PoseidonGrainLFSR::new(
+            false,
+            F::Params::MODULUS_BITS as u64,
+            state_size as u64,
+            full_rounds as u64,
+            partial_rounds as u64,
+        );
+        let mut external_ark = Vec::with_capacity(full_rounds);
+        let mut internal_ark = Vec::with_capacity(partial_rounds);
+        for round in 0..(full_rounds + partial_rounds) {
+            if round < full_rounds / 2 || round >= full_rounds / 2 + partial_rounds {
+                external_ark.push(lfsr.get_field_elements_rejection_sampling::<F>(state_size));
+            } else {
+                internal_ark.push(lfsr.get_field_elements_rejection_sampling::<F>(1)[0]);
+            }
+        }
+
+        Self::new(
+            full_rounds,
+            partial_rounds,
+            alpha,
+            external_ark,
+            internal_ark,
+            internal_diagonal,
+            rate,
+            capacity,
+        )
+    }
+
+    /// The width of the permutation state, `t = rate + capacity`.
+    pub fn state_size(&self) -> usize {
+        self.rate + self.capacity
+    }
+
+    pub(crate) fn apply_external_matrix(state: &mut [F]) {
+        if state.len() < 4 {
+            // circ(2, 1) and circ(2, 1, 1) are the identity plus the all-ones matrix
+            let sum: F = state.iter().sum();
+            for elem in state.iter_mut() {
+                *elem += sum;
+            }
+            return;
+        }
+
+        // Apply M4 to every chunk, then add the sum of the chunks to each chunk, which amounts to
+        // multiplying with circ(2 * M4, M4, ..., M4).
+        for chunk in state.chunks_exact_mut(4) {
+            let input = [chunk[0], chunk[1], chunk[2], chunk[3]];
+            for (elem, row) in chunk.iter_mut().zip(&M4) {
+                *elem = row
+                    .iter()
+                    .zip(&input)
+                    .map(|(entry, x)| F::from(*entry) * x)
+                    .sum();
+            }
+        }
+        let mut sums = [F::zero(); 4];
+        for chunk in state.chunks_exact(4) {
+            for (sum, elem) in sums.iter_mut().zip(chunk) {
+                *sum += elem;
+            }
+        }
+        for chunk in state.chunks_exact_mut(4) {
+            for (elem, sum) in chunk.iter_mut().zip(&sums) {
+                *elem += sum;
+            }
+        }
+    }
+
+    fn apply_internal_matrix(&self, state: &mut [F]) {
+        let sum: F = state.iter().sum();
+        for (elem, diagonal) in state.iter_mut().zip(&self.internal_diagonal) {
+            *elem = sum + *diagonal * *elem;
+        }
+    }
+
+    fn s_box(&self, elem: &F) -> F {
+        elem.pow([self.alpha])
+    }
+}
+
+impl<F: PrimeField> FieldPermutation<F> for Poseidon2Parameters<F> {
+    fn rate(&self) -> usize {
+        self.rate
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn permute(&self, state: &mut [F]) {
+        let full_rounds_over_2 = self.full_rounds / 2;
+        let (first_external_ark, last_external_ark) =
+            self.external_ark.split_at(full_rounds_over_2);
+
+        Self::apply_external_matrix(state);
+        for ark in first_external_ark {
+            for (elem, constant) in state.iter_mut().zip(ark) {
+                *elem = self.s_box(&(*elem + constant));
+            }
+            Self::apply_external_matrix(state);
+        }
+
+        for constant in &self.internal_ark {
+            state[0] = self.s_box(&(state[0] + constant));
+            self.apply_internal_matrix(state);
+        }
+
+        for ark in last_external_ark {
+            for (elem, constant) in state.iter_mut().zip(ark) {
+                *elem = self.s_box(&(*elem + constant));
+            }
+            Self::apply_external_matrix(state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::duplex::FieldPermutation;
+    use crate::poseidon2::{Poseidon2Parameters, Poseidon2Sponge, M4};
+    use crate::{CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_ff::{Field, UniformRand, Zero};
+    use ark_std::test_rng;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    fn dense_external_matrix(state_size: usize) -> Vec<Vec<Fr>> {
+        (0..state_size)
+            .map(|i| {
+                (0..state_size)
+                    .map(|j| {
+                        if state_size < 4 {
+                            Fr::from(if i == j { 2u8 } else { 1u8 })
+                        } else {
+                            let entry = Fr::from(M4[i % 4][j % 4]);
+                            if i / 4 == j / 4 {
+                                entry.double()
+                            } else {
+                                entry
+                            }
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_external_matrix() {
+        let mut rng = test_rng();
+        for state_size in [2, 3, 4, 8, 12] {
+            let state: Vec<_> = (0..state_size).map(|_| Fr::rand(&mut rng)).collect();
+            let expected: Vec<_> = dense_external_matrix(state_size)
+                .iter()
+                .map(|row| row.iter().zip(&state).map(|(a, b)| *a * b).sum::<Fr>())
+                .collect();
+
+            let mut result = state.clone();
+            Poseidon2Parameters::apply_external_matrix(&mut result);
+            assert_eq!(result, expected);
+        }
+    }
+
+    #[test]
+    fn test_internal_matrix() {
+        let parameters = Poseidon2Parameters::<Fr>::generate(2, 1, 128);
+        let mut state = ark_std::vec![Fr::from(1u8), Fr::from(2u8), Fr::from(3u8)];
+        parameters.apply_internal_matrix(&mut state);
+        // [[2, 1, 1], [1, 2, 1], [1, 1, 3]] * [1, 2, 3]
+        assert_eq!(
+            state,
+            ark_std::vec![Fr::from(7u8), Fr::from(8u8), Fr::from(12u8)]
+        );
+    }
+
+    #[test]
+    fn test_generate() {
+        let parameters = Poseidon2Parameters::<Fr>::generate(2, 1, 128);
+        assert_eq!(parameters.alpha, 5);
+        assert_eq!((parameters.full_rounds, parameters.partial_rounds), (8, 56));
+        assert_eq!(parameters.external_ark.len(), 8);
+        assert_eq!(parameters.internal_ark.len(), 56);
+    }
+
+    #[test]
+    fn test_permutation() {
+        let mut rng = test_rng();
+        let parameters = Poseidon2Parameters::<Fr>::generate(2, 1, 128);
+
+        let input: Vec<_> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        let mut output = input.clone();
+        parameters.permute(&mut output);
+        assert_ne!(input, output);
+
+        let mut zero = ark_std::vec![Fr::zero(); 3];
+        parameters.permute(&mut zero);
+        assert_ne!(zero, ark_std::vec![Fr::zero(); 3]);
+
+        let mut other = input.clone();
+        other[0] += Fr::from(1u8);
+        parameters.permute(&mut other);
+        assert!(other.iter().zip(&output).all(|(a, b)| a != b));
+    }
+
+    #[test]
+    fn test_sponge() {
+        let parameters = Poseidon2Parameters::<Fr>::generate(1, 1, 128);
+        let mut sponge = Poseidon2Sponge::new(&parameters);
+        sponge.absorb(&ark_std::vec![Fr::from(1u8), Fr::from(2u8), Fr::from(3u8)]);
+        let output = sponge.squeeze_native_field_elements(3);
+
+        let mut other = Poseidon2Sponge::new(&parameters);
+        other.absorb(&ark_std::vec![Fr::from(1u8), Fr::from(2u8)]);
+        other.absorb(&Fr::from(3u8));
+        assert_eq!(other.squeeze_native_field_elements(3), output);
+
+        let mut different = Poseidon2Sponge::new(&parameters);
+        different.absorb(&ark_std::vec![Fr::from(1u8), Fr::from(2u8), Fr::from(4u8)]);
+        assert_ne!(different.squeeze_native_field_elements(3), output);
+    }
+
+    /// The test vector of the [reference implementation][ref] for BN254 and a state of 3
+    /// elements, whose round constants are sampled with the Grain LFSR as in
+    /// [`Poseidon2Parameters::generate`].
+    ///
+    /// [ref]: https://github.com/HorizenLabs/poseidon2/blob/main/plain_implementations/src/poseidon2/poseidon2.rs
+    #[cfg(feature = "bn254")]
+    #[test]
+    fn test_reference_vector() {
+        use ark_bn254::Fr;
+        use ark_ff::PrimeField;
+
+        let hex = |hex: &str| {
+            let bytes: Vec<u8> = (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+                .collect();
+            Fr::from_be_bytes_mod_order(&bytes)
+        };
+
+        let parameters = Poseidon2Parameters::<Fr>::generate(2, 1, 128);
+        assert_eq!(parameters.alpha, 5);
+        assert_eq!((parameters.full_rounds, parameters.partial_rounds), (8, 56));
+        assert_eq!(
+            parameters.external_ark[0][0],
+            hex("1d066a255517b7fd8bddd3a93f7804ef7f8fcde48bb4c37a59a09a1a97052816")
+        );
+
+        let mut state = ark_std::vec![Fr::from(0u8), Fr::from(1u8), Fr::from(2u8)];
+        parameters.permute(&mut state);
+        assert_eq!(
+            state,
+            ark_std::vec![
+                hex("0bb61d24daca55eebcb1929a82650f328134334da98ea4f847f760054f4a3033"),
+                hex("303b6f7c86d043bfcbcc80214f26a30277a15d3f74ca654992defe7ff8d03570"),
+                hex("1ed25194542b12eef8617361c3ba7c52e660b145994427cc86296242cf766ec8"),
+            ]
+        );
+    }
+}
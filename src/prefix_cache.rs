@@ -0,0 +1,99 @@
+use crate::CryptographicSponge;
+use ark_std::collections::BTreeMap;
+
+/// A cache of sponges that have absorbed a common transcript prefix, such as a verifying key,
+/// so that a prover creating many transcripts absorbs each prefix only once.
+///
+/// A sponge is cached under a key chosen by the caller, which must identify the prefix: two
+/// prefixes under the same key are assumed to give the same sponge. Each call to
+/// [`PrefixCache::get_or_insert_with`] returns a clone of the cached sponge, which continues
+/// independently of the cache.
+///
+/// In circuits, absorbing a constant prefix costs no constraints, since the sponge gadgets
+/// permute constant states natively, so there is no gadget counterpart.
+#[derive(Clone)]
+pub struct PrefixCache<K: Ord, S: CryptographicSponge> {
+    sponges: BTreeMap<K, S>,
+}
+
+impl<K: Ord, S: CryptographicSponge> Default for PrefixCache<K, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, S: CryptographicSponge> PrefixCache<K, S> {
+    /// Creates an empty cache.
+    pub fn new() -> Self {
+        Self {
+            sponges: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the sponge cached under `key`, computing it with `prefix` the first time.
+    pub fn get_or_insert_with(&mut self, key: K, prefix: impl FnOnce() -> S) -> S {
+        self.sponges.entry(key).or_insert_with(prefix).clone()
+    }
+
+    /// Whether a sponge is cached under `key`.
+    pub fn contains(&self, key: &K) -> bool {
+        self.sponges.contains_key(key)
+    }
+
+    /// Removes the sponge cached under `key`, e.g. when the prefix is no longer used.
+    pub fn remove(&mut self, key: &K) -> Option<S> {
+        self.sponges.remove(key)
+    }
+
+    /// Removes all the cached sponges.
+    pub fn clear(&mut self) {
+        self.sponges.clear();
+    }
+
+    /// The number of cached sponges.
+    pub fn len(&self) -> usize {
+        self.sponges.len()
+    }
+
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.sponges.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::PoseidonSponge;
+    use crate::{CryptographicSponge, FieldBasedCryptographicSponge, PrefixCache};
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn cached_prefix() {
+        let params = poseidon_parameters_for_test::<Fr>();
+        let verifying_key = [Fr::from(1u8), Fr::from(2u8), Fr::from(3u8)];
+        let mut cache = PrefixCache::new();
+
+        let mut num_prefixes = 0;
+        for proof in 0..3u8 {
+            let mut sponge = PoseidonSponge::with_cached_prefix(&mut cache, "vk", || {
+                num_prefixes += 1;
+                let mut sponge = PoseidonSponge::new(&params);
+                sponge.absorb(&verifying_key.as_ref());
+                sponge
+            });
+            sponge.absorb(&proof);
+
+            let mut expected = PoseidonSponge::new(&params);
+            expected.absorb(&verifying_key.as_ref());
+            expected.absorb(&proof);
+            assert_eq!(
+                sponge.squeeze_native_field_elements(2),
+                expected.squeeze_native_field_elements(2)
+            );
+        }
+        assert_eq!(num_prefixes, 1);
+        assert_eq!(cache.len(), 1);
+        assert!(cache.contains(&"vk"));
+    }
+}
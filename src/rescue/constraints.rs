@@ -0,0 +1,207 @@
+use crate::duplex::constraints::{DuplexSpongeVar, FieldPermutationGadget};
+use crate::rescue::RescuePrimeParameters;
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::SynthesisError;
+use ark_std::vec::Vec;
+
+/// The gadget for [`RescuePrimeSponge`](crate::rescue::RescuePrimeSponge).
+pub type RescuePrimeSpongeVar<F> = DuplexSpongeVar<F, RescuePrimeParameters<F>>;
+
+impl<F: PrimeField> RescuePrimeParameters<F> {
+    fn apply_mds_var(&self, state: &mut [FpVar<F>]) {
+        let new_state: Vec<FpVar<F>> = self
+            .mds
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(state.iter())
+                    .map(|(entry, x)| x * *entry)
+                    .fold(FpVar::zero(), |sum, term| sum + term)
+            })
+            .collect();
+        state.clone_from_slice(&new_state);
+    }
+
+    fn apply_ark_var(state: &mut [FpVar<F>], ark: &[F]) {
+        for (elem, constant) in state.iter_mut().zip(ark) {
+            *elem += *constant;
+        }
+    }
+}
+
+impl<F: PrimeField> FieldPermutationGadget<F> for RescuePrimeParameters<F> {
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    fn permute_var(&self, state: &mut [FpVar<F>]) -> Result<(), SynthesisError> {
+        for ark in self.ark.chunks_exact(2) {
+            for elem in state.iter_mut() {
+                *elem = elem.pow_by_constant([self.alpha])?;
+            }
+            self.apply_mds_var(state);
+            Self::apply_ark_var(state, &ark[0]);
+
+            for elem in state.iter_mut() {
+                *elem = inverse_s_box_var(elem, self.alpha, &self.alpha_inverse)?;
+            }
+            self.apply_mds_var(state);
+            Self::apply_ark_var(state, &ark[1]);
+        }
+
+        Ok(())
+    }
+}
+
+/// Computes `x^(1/alpha)` by allocating it as a witness and enforcing that its `alpha`-th
+/// power is `x`, which costs as much as the forward S-box.
+pub(crate) fn inverse_s_box_var<F: PrimeField>(
+    elem: &FpVar<F>,
+    alpha: u64,
+    alpha_inverse: &[u64],
+) -> Result<FpVar<F>, SynthesisError> {
+    if let FpVar::Constant(value) = elem {
+        return Ok(FpVar::Constant(value.pow(alpha_inverse)));
+    }
+
+    let root = FpVar::new_witness(elem.cs(), || Ok(elem.value()?.pow(alpha_inverse)))?;
+    root.pow_by_constant([alpha - 1])?.mul_equals(&root, elem)?;
+    Ok(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constraints::CryptographicSpongeVar;
+    use crate::duplex::constraints::FieldPermutationGadget;
+    use crate::duplex::FieldPermutation;
+    use crate::rescue::constraints::RescuePrimeSpongeVar;
+    use crate::rescue::{RescuePrimeParameters, RescuePrimeSponge};
+    use crate::{CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_ff::UniformRand;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_relations::*;
+    use ark_std::test_rng;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn permutation_test() {
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::new_ref();
+        let parameters = RescuePrimeParameters::<Fr>::generate(2, 1, 128);
+
+        let mut state: Vec<_> = (0..parameters.state_size())
+            .map(|_| Fr::rand(&mut rng))
+            .collect();
+        let mut state_var: Vec<_> = state
+            .iter()
+            .map(|v| FpVar::new_witness(ns!(cs, "state"), || Ok(*v)).unwrap())
+            .collect();
+
+        parameters.permute(&mut state);
+        parameters.permute_var(&mut state_var).unwrap();
+
+        assert_eq!(state_var.value().unwrap(), state);
+        assert!(cs.is_satisfied().unwrap());
+        // x^5 costs 3 constraints, in both directions
+        assert_eq!(
+            cs.num_constraints(),
+            2 * 3 * parameters.rounds * parameters.state_size()
+        );
+    }
+
+    #[test]
+    fn sponge_test() {
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::new_ref();
+        let parameters = RescuePrimeParameters::<Fr>::generate(2, 1, 128);
+
+        let absorb: Vec<_> = (0..5).map(|_| Fr::rand(&mut rng)).collect();
+        let absorb_var: Vec<_> = absorb
+            .iter()
+            .map(|v| FpVar::new_input(ns!(cs, "absorb"), || Ok(*v)).unwrap())
+            .collect();
+
+        let mut native_sponge = RescuePrimeSponge::<Fr>::new(&parameters);
+        let mut constraint_sponge = RescuePrimeSpongeVar::<Fr>::new(cs.clone(), &parameters);
+
+        native_sponge.absorb(&absorb);
+        constraint_sponge.absorb(&absorb_var).unwrap();
+
+        let squeeze1 = native_sponge.squeeze_native_field_elements(3);
+        let squeeze2 = constraint_sponge.squeeze_field_elements(3).unwrap();
+
+        assert_eq!(squeeze2.value().unwrap(), squeeze1);
+        assert!(cs.is_satisfied().unwrap());
+    }
+    #[test]
+    fn constant_inputs() {
+        let cs = ConstraintSystem::new_ref();
+        let parameters = RescuePrimeParameters::<Fr>::generate(2, 1, 128);
+        let absorb: Vec<_> = (0..5u64).map(Fr::from).collect();
+
+        let mut native_sponge = RescuePrimeSponge::<Fr>::new(&parameters);
+        let mut constraint_sponge = RescuePrimeSpongeVar::<Fr>::new(cs.clone(), &parameters);
+        native_sponge.absorb(&absorb);
+        let absorb_var: Vec<_> = absorb.iter().map(|v| FpVar::Constant(*v)).collect();
+        constraint_sponge.absorb(&absorb_var).unwrap();
+        assert_eq!(
+            constraint_sponge
+                .squeeze_field_elements(3)
+                .unwrap()
+                .value()
+                .unwrap(),
+            native_sponge.squeeze_native_field_elements(3)
+        );
+        assert_eq!(cs.num_constraints(), 0);
+
+        // The state is allocated once a variable is absorbed.
+        let var = FpVar::new_witness(ns!(cs, "var"), || Ok(Fr::from(6u64))).unwrap();
+        native_sponge.absorb(&Fr::from(6u64));
+        constraint_sponge.absorb(&var).unwrap();
+        assert_eq!(
+            constraint_sponge
+                .squeeze_field_elements(1)
+                .unwrap()
+                .value()
+                .unwrap(),
+            native_sponge.squeeze_native_field_elements(1)
+        );
+        assert!(cs.num_constraints() > 0);
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn conditionally_select() {
+        let parameters = RescuePrimeParameters::<Fr>::generate(2, 1, 128);
+        for choice in [true, false] {
+            let cs = ConstraintSystem::new_ref();
+            let cond = Boolean::new_witness(cs.clone(), || Ok(choice)).unwrap();
+            let mut true_sponge = RescuePrimeSpongeVar::<Fr>::new(cs.clone(), &parameters);
+            let mut false_sponge = true_sponge.clone();
+            let a = FpVar::new_witness(ns!(cs, "a"), || Ok(Fr::from(1u64))).unwrap();
+            let b = FpVar::new_witness(ns!(cs, "b"), || Ok(Fr::from(2u64))).unwrap();
+            true_sponge.absorb(&a).unwrap();
+            false_sponge.absorb(&b).unwrap();
+
+            let mut native_sponge = RescuePrimeSponge::<Fr>::new(&parameters);
+            native_sponge.absorb(&Fr::from(if choice { 1u64 } else { 2u64 }));
+
+            let mut merged =
+                RescuePrimeSpongeVar::conditionally_select(&cond, &true_sponge, &false_sponge)
+                    .unwrap();
+            assert_eq!(
+                merged.squeeze_field_elements(2).unwrap().value().unwrap(),
+                native_sponge.squeeze_native_field_elements(2)
+            );
+            assert!(cs.is_satisfied().unwrap());
+
+            true_sponge.squeeze_field_elements(1).unwrap();
+            assert!(
+                RescuePrimeSpongeVar::conditionally_select(&cond, &true_sponge, &false_sponge)
+                    .is_err()
+            );
+        }
+    }
+}
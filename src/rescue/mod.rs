@@ -0,0 +1,419 @@
+use crate::duplex::{DuplexSponge, FieldPermutation};
+use crate::poseidon::find_poseidon_alpha;
+use crate::poseidon::optimized::{invert, mat_mul};
+use crate::poseidon::round_numbers::log2;
+use ark_ff::{FpParameters, PrimeField};
+use ark_std::format;
+use ark_std::vec::Vec;
+use num_bigint::BigUint;
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::Shake256;
+
+/// constraints for Rescue-Prime
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+/// A duplex sponge based on the [Rescue-Prime][rp] permutation.
+///
+/// [rp]: https://eprint.iacr.org/2020/1143
+pub type RescuePrimeSponge<F> = DuplexSponge<F, RescuePrimeParameters<F>>;
+
+/// Parameters of the [Rescue-Prime][rp] permutation.
+///
+/// Each of the `rounds` rounds applies the S-box `x^alpha` to every element, multiplies with
+/// the MDS matrix and adds round constants, and then does the same with the inverse S-box
+/// `x^(1/alpha)`.
+///
+/// [rp]: https://eprint.iacr.org/2020/1143
+#[derive(Clone, Debug)]
+pub struct RescuePrimeParameters<F: PrimeField> {
+    /// Number of rounds.
+    pub rounds: usize,
+    /// Exponent used in the S-box.
+    pub alpha: u64,
+    /// Exponent used in the inverse S-box, i.e., the inverse of `alpha` modulo `p - 1`,
+    /// as little-endian limbs.
+    pub alpha_inverse: Vec<u64>,
+    /// Round constants, indexed by `ark[step][state_element_index]`, where each round has two
+    /// steps.
+    pub ark: Vec<Vec<F>>,
+    /// Maximally Distance Separating (MDS) Matrix.
+    pub mds: Vec<Vec<F>>,
+    /// The rate (in terms of number of field elements).
+    pub rate: usize,
+    /// The capacity (in terms of number of field elements).
+    pub capacity: usize,
+}
+
+impl<F: PrimeField> RescuePrimeParameters<F> {
+    /// Initialize the parameters of the Rescue-Prime permutation.
+    pub fn new(
+        rounds: usize,
+        alpha: u64,
+        ark: Vec<Vec<F>>,
+        mds: Vec<Vec<F>>,
+        rate: usize,
+        capacity: usize,
+    ) -> Self {
+        let state_size = rate + capacity;
+        assert_eq!(ark.len(), 2 * rounds);
+        for item in &ark {
+            assert_eq!(item.len(), state_size);
+        }
+        assert_eq!(mds.len(), state_size);
+        for item in &mds {
+            assert_eq!(item.len(), state_size);
+        }
+
+        Self {
+            rounds,
+            alpha,
+            alpha_inverse: inverse_exponent::<F>(alpha),
+            ark,
+            mds,
+            rate,
+            capacity,
+        }
+    }
+
+    /// Generate parameters for `F` providing `security_level` bits of security, following the
+    /// [reference implementation](https://github.com/KULeuven-COSIC/Marvellous/blob/master/rescue_prime.sage).
+    ///
+    /// The S-box exponent is the smallest `alpha` for which `x^alpha` is a permutation of `F`.
+    /// The number of rounds is 1.5 times the number of rounds resisting Gröbner basis attacks,
+    /// and at least 8. The MDS matrix is derived from a Vandermonde matrix over the generator
+    /// `F::multiplicative_generator()` of the multiplicative group, and the round constants are
+    /// sampled with SHAKE256.
+    ///
+    /// The reference implementation uses the smallest primitive element of the field instead.
+    /// This is the generator of `ark-ff` for the fields of BLS12-381, BN254, ed-on-bls12-381,
+    /// Pallas and Vesta, but a field may define another generator; use
+    /// [`RescuePrimeParameters::generate_with_generator`] to reproduce the reference parameters
+    /// of such a field.
+    pub fn generate(rate: usize, capacity: usize, security_level: usize) -> Self {
+        Self::generate_with_generator(
+            rate,
+            capacity,
+            security_level,
+            F::multiplicative_generator(),
+        )
+    }
+
+    /// Same as [`RescuePrimeParameters::generate`], but with the MDS matrix derived from the
+    /// given `generator` of the multiplicative group of `F`, e.g. the smallest primitive element
+    /// used by the reference implementation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the Vandermonde matrix of `generator` is singular, which never happens for a
+    /// generator of the multiplicative group.
+    pub fn generate_with_generator(
+        rate: usize,
+        capacity: usize,
+        security_level: usize,
+        generator: F,
+    ) -> Self {
+        let state_size = rate + capacity;
+        let alpha = find_poseidon_alpha::<F>();
+        let rounds = find_rescue_prime_rounds(state_size, rate, alpha, security_level);
+
+        Self::new(
+            rounds,
+            alpha,
+            rescue_prime_round_constants::<F>(state_size, capacity, security_level, rounds),
+            rescue_prime_mds(state_size, generator),
+            rate,
+            capacity,
+        )
+    }
+
+    /// The width of the permutation state, `t = rate + capacity`.
+    pub fn state_size(&self) -> usize {
+        self.rate + self.capacity
+    }
+
+    fn apply_mds(&self, state: &mut [F]) {
+        let new_state: Vec<F> = self
+            .mds
+            .iter()
+            .map(|row| row.iter().zip(state.iter()).map(|(a, b)| *a * b).sum())
+            .collect();
+        state.copy_from_slice(&new_state);
+    }
+
+    fn apply_ark(state: &mut [F], ark: &[F]) {
+        for (elem, constant) in state.iter_mut().zip(ark) {
+            *elem += constant;
+        }
+    }
+}
+
+impl<F: PrimeField> FieldPermutation<F> for RescuePrimeParameters<F> {
+    fn rate(&self) -> usize {
+        self.rate
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn permute(&self, state: &mut [F]) {
+        for ark in self.ark.chunks_exact(2) {
+            for elem in state.iter_mut() {
+                *elem = elem.pow([self.alpha]);
+            }
+            self.apply_mds(state);
+            Self::apply_ark(state, &ark[0]);
+
+            for elem in state.iter_mut() {
+                *elem = elem.pow(&self.alpha_inverse);
+            }
+            self.apply_mds(state);
+            Self::apply_ark(state, &ark[1]);
+        }
+    }
+}
+
+/// Computes the inverse of `alpha` modulo `p - 1`, as little-endian limbs, so that
+/// `x^(1/alpha) = x.pow(inverse_exponent::<F>(alpha))`.
+pub(crate) fn inverse_exponent<F: PrimeField>(alpha: u64) -> Vec<u64> {
+    let modulus_minus_one: BigUint = F::Params::MODULUS.into() - 1u8;
+    BigUint::from(alpha)
+        .modinv(&modulus_minus_one)
+        .expect("x^alpha must be a permutation of the field")
+        .to_u64_digits()
+}
+
+/// Computes the number of rounds of Rescue-Prime, as in `get_number_of_rounds` of the
+/// reference implementation.
+fn find_rescue_prime_rounds(
+    state_size: usize,
+    rate: usize,
+    alpha: u64,
+    security_level: usize,
+) -> usize {
+    let m = state_size as f64;
+    let d_con = |n: usize| ((alpha - 1) as f64 * 0.5 * m * (n - 1) as f64) as usize + 2;
+    let v = |n: usize| state_size * (n - 1) + rate;
+    // log2 of the binomial coefficient `(n choose k)`
+    let log2_binomial = |n: usize, k: usize| -> f64 {
+        (1..=k)
+            .map(|i| log2((n - k + i) as f64) - log2(i as f64))
+            .sum()
+    };
+
+    let mut l1 = 1;
+    while l1 < 25 {
+        if 2.0 * log2_binomial(v(l1) + d_con(l1), v(l1)) > security_level as f64 {
+            break;
+        }
+        l1 += 1;
+    }
+
+    // set a minimum value for sanity and add 50%
+    let rounds = 3 * core::cmp::max(5, l1);
+    rounds.div_ceil(2)
+}
+
+/// Computes the MDS matrix of Rescue-Prime, as in `get_mds_matrix` of the reference
+/// implementation: the transpose of the right half of the echelon form of the `m x 2m`
+/// Vandermonde matrix `(g^(i * j))` for a generator `g` of the multiplicative group.
+fn rescue_prime_mds<F: PrimeField>(state_size: usize, generator: F) -> Vec<Vec<F>> {
+    let vandermonde: Vec<Vec<F>> = (0..state_size)
+        .map(|i| {
+            (0..2 * state_size)
+                .map(|j| generator.pow([(i * j) as u64]))
+                .collect()
+        })
+        .collect();
+
+    let left: Vec<Vec<F>> = vandermonde
+        .iter()
+        .map(|row| row[..state_size].to_vec())
+        .collect();
+    let right: Vec<Vec<F>> = vandermonde
+        .iter()
+        .map(|row| row[state_size..].to_vec())
+        .collect();
+    // The echelon form is `[I | left^(-1) * right]`.
+    let right_echelon = mat_mul(&invert(&left).unwrap(), &right);
+
+    (0..state_size)
+        .map(|i| (0..state_size).map(|j| right_echelon[j][i]).collect())
+        .collect()
+}
+
+/// Computes the round constants of Rescue-Prime, as in `get_round_constants` of the reference
+/// implementation.
+fn rescue_prime_round_constants<F: PrimeField>(
+    state_size: usize,
+    capacity: usize,
+    security_level: usize,
+    rounds: usize,
+) -> Vec<Vec<F>> {
+    let modulus: BigUint = F::Params::MODULUS.into();
+    let bytes_per_int = (F::Params::MODULUS_BITS as usize).div_ceil(8) + 1;
+    let seed = format!(
+        "Rescue-XLIX({},{},{},{})",
+        modulus, state_size, capacity, security_level
+    );
+
+    let mut shake = Shake256::default();
+    shake.update(seed.as_bytes());
+    let mut reader = shake.finalize_xof();
+
+    let mut chunk = ark_std::vec![0u8; bytes_per_int];
+    (0..2 * rounds)
+        .map(|_| {
+            (0..state_size)
+                .map(|_| {
+                    reader.read(&mut chunk);
+                    F::from_le_bytes_mod_order(&chunk)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::duplex::FieldPermutation;
+    use crate::rescue::{find_rescue_prime_rounds, RescuePrimeParameters, RescuePrimeSponge};
+    use crate::{CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_ff::{Field, One, UniformRand, Zero};
+    use ark_std::test_rng;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn test_rounds() {
+        assert_eq!(find_rescue_prime_rounds(3, 2, 5, 128), 14);
+        assert_eq!(find_rescue_prime_rounds(3, 2, 3, 128), 18);
+        assert_eq!(find_rescue_prime_rounds(3, 2, 5, 256), 26);
+        // Wide instances are protected by the minimum number of rounds.
+        assert_eq!(find_rescue_prime_rounds(12, 8, 5, 128), 8);
+    }
+
+    #[test]
+    fn test_inverse_s_box() {
+        let mut rng = test_rng();
+        let parameters = RescuePrimeParameters::<Fr>::generate(2, 1, 128);
+        let elem = Fr::rand(&mut rng);
+        assert_eq!(
+            elem.pow([parameters.alpha]).pow(&parameters.alpha_inverse),
+            elem
+        );
+        assert_ne!(elem.pow(&parameters.alpha_inverse), elem);
+    }
+
+    #[test]
+    fn test_mds() {
+        let parameters = RescuePrimeParameters::<Fr>::generate(2, 1, 128);
+        // All 1x1 and 2x2 minors of an MDS matrix are nonzero.
+        for i in 0..3 {
+            for j in 0..3 {
+                assert!(!parameters.mds[i][j].is_zero());
+                for k in (i + 1)..3 {
+                    for l in (j + 1)..3 {
+                        let minor = parameters.mds[i][j] * parameters.mds[k][l]
+                            - parameters.mds[i][l] * parameters.mds[k][j];
+                        assert!(!minor.is_zero());
+                    }
+                }
+            }
+        }
+        assert_ne!(parameters.mds[0][0], Fr::one());
+    }
+
+    #[test]
+    fn test_sponge() {
+        let mut rng = test_rng();
+        let parameters = RescuePrimeParameters::<Fr>::generate(2, 1, 128);
+        let input: Vec<_> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+
+        let mut state = input.clone();
+        parameters.permute(&mut state);
+        assert_ne!(state, input);
+
+        let mut sponge = RescuePrimeSponge::new(&parameters);
+        sponge.absorb(&input);
+        let output = sponge.squeeze_native_field_elements(3);
+
+        let mut other = RescuePrimeSponge::new(&parameters);
+        other.absorb(&input);
+        assert_eq!(other.squeeze_native_field_elements(3), output);
+    }
+
+    /// The permutation of the [reference implementation][ref] for the scalar field of BLS12-381,
+    /// a state of 3 elements with a capacity of 1, and 128 bits of security. The smallest
+    /// primitive element of this field, which the reference uses for the MDS matrix, is the
+    /// generator 7 of `ark-ff`.
+    ///
+    /// [ref]: https://github.com/KULeuven-COSIC/Marvellous/blob/master/rescue_prime.sage
+    #[test]
+    fn test_reference_vector() {
+        use ark_ff::{field_new, FftField};
+
+        assert_eq!(Fr::multiplicative_generator(), Fr::from(7u8));
+        let parameters = RescuePrimeParameters::<Fr>::generate(2, 1, 128);
+        assert_eq!((parameters.alpha, parameters.rounds), (5, 14));
+        assert_eq!(parameters.mds[0][0], Fr::from(343u16));
+        assert_eq!(
+            parameters.ark[0][0],
+            field_new!(
+                Fr,
+                "35495817390819093545263349384941809089491580678942832859579453034368810736263"
+            )
+        );
+
+        let mut state = ark_std::vec![Fr::from(1u8), Fr::from(2u8), Fr::from(3u8)];
+        parameters.permute(&mut state);
+        assert_eq!(
+            state,
+            ark_std::vec![
+                field_new!(
+                    Fr,
+                    "32863683576504234738154559655852813419743588075176372098699547705349823022585"
+                ),
+                field_new!(
+                    Fr,
+                    "46801790419532466758890443880888538635378899893527602159390338094156075825986"
+                ),
+                field_new!(
+                    Fr,
+                    "40169658137760921195899769318577154766000950865246228562511369857159189528985"
+                ),
+            ]
+        );
+
+        // Another generator gives another MDS matrix.
+        let other = RescuePrimeParameters::<Fr>::generate_with_generator(2, 1, 128, Fr::from(10u8));
+        assert_ne!(other.mds, parameters.mds);
+        assert_eq!(other.ark, parameters.ark);
+    }
+
+    /// The smallest primitive elements of the curve fields, computed with Sage, are their
+    /// generators in `ark-ff`, so that [`RescuePrimeParameters::generate`] gives the reference
+    /// parameters for them.
+    #[cfg(all(
+        feature = "bls12_381",
+        feature = "bn254",
+        feature = "ed_on_bls12_381",
+        feature = "pallas"
+    ))]
+    #[test]
+    fn test_reference_generators() {
+        use ark_ff::FftField;
+
+        assert_eq!(ark_bls12_381::Fr::multiplicative_generator(), 7u8.into());
+        assert_eq!(ark_bls12_381::Fq::multiplicative_generator(), 2u8.into());
+        assert_eq!(ark_bn254::Fr::multiplicative_generator(), 5u8.into());
+        assert_eq!(ark_bn254::Fq::multiplicative_generator(), 3u8.into());
+        assert_eq!(
+            ark_ed_on_bls12_381::Fr::multiplicative_generator(),
+            6u8.into()
+        );
+        assert_eq!(ark_pallas::Fr::multiplicative_generator(), 5u8.into());
+        assert_eq!(ark_pallas::Fq::multiplicative_generator(), 5u8.into());
+    }
+}
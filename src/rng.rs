@@ -0,0 +1,188 @@
+use crate::{Absorb, CryptographicSponge, FieldBasedCryptographicSponge, FieldElementSize};
+use ark_ff::{PrimeField, ToConstraintField};
+use ark_std::rand::{CryptoRng, Error, RngCore};
+use ark_std::vec;
+use ark_std::vec::Vec;
+
+/// An RNG whose output is squeezed from a cryptographic sponge, for passing a sponge wherever an
+/// RNG is expected.
+///
+/// Absorbing into the RNG reseeds it: all later output depends on the absorbed input, as well as
+/// on everything absorbed and squeezed before.
+#[derive(Clone)]
+pub struct SpongeRng<S: CryptographicSponge> {
+    sponge: S,
+}
+
+impl<S: CryptographicSponge> SpongeRng<S> {
+    /// Creates an RNG squeezing from `sponge`.
+    pub fn new(sponge: S) -> Self {
+        Self { sponge }
+    }
+
+    /// Reseeds the RNG by absorbing `input` into the sponge.
+    pub fn absorb(&mut self, input: &impl Absorb) {
+        self.sponge.absorb(input);
+    }
+
+    /// Returns the underlying sponge.
+    pub fn into_sponge(self) -> S {
+        self.sponge
+    }
+}
+
+impl<S: CryptographicSponge> RngCore for SpongeRng<S> {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        dest.copy_from_slice(&self.sponge.squeeze_bytes(dest.len()));
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl<S: CryptographicSponge> CryptoRng for SpongeRng<S> {}
+
+/// The interface of the Fiat-Shamir RNGs of `ark-marlin` and `ark-poly-commit`, for an RNG over
+/// the field `CF` whose protocol also handles elements of the field `F`.
+///
+/// [`SpongeRng`] implements it over any sponge on `CF`, so that code written against this
+/// interface can run on a sponge, such as Poseidon, by switching the import of the trait.
+pub trait FiatShamirRng<F: PrimeField, CF: PrimeField>: RngCore {
+    /// Absorbs bytes.
+    fn absorb_bytes(&mut self, bytes: &[u8]);
+
+    /// Absorbs the native field elements of `elems`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an element of `elems` cannot be converted to native field elements.
+    fn absorb_native_field_elements<T: ToConstraintField<CF>>(&mut self, elems: &[T]);
+
+    /// Absorbs elements of `F`.
+    fn absorb_nonnative_field_elements(&mut self, elems: &[F]);
+
+    /// Squeezes `num_elements` native field elements.
+    fn squeeze_native_field_elements(&mut self, num_elements: usize) -> Vec<CF>;
+
+    /// Squeezes `num_elements` elements of `F`.
+    fn squeeze_nonnative_field_elements(&mut self, num_elements: usize) -> Vec<F>;
+
+    /// Squeezes `num_elements` elements of `F` of 128 bits, e.g. for cheaper challenges.
+    fn squeeze_128_bits_nonnative_field_elements(&mut self, num_elements: usize) -> Vec<F>;
+}
+
+/// Absorbs and squeezes with the methods of the sponge of the same names: nonnative elements are
+/// absorbed with [`FieldBasedCryptographicSponge::absorb_nonnative`], and 128-bit elements are
+/// squeezed as [`FieldElementSize::Truncated`]`(128)`, so that the gadgets of the sponge give the
+/// same transcript in a circuit.
+impl<F: PrimeField, CF: PrimeField + Absorb, S: FieldBasedCryptographicSponge<CF>>
+    FiatShamirRng<F, CF> for SpongeRng<S>
+{
+    fn absorb_bytes(&mut self, bytes: &[u8]) {
+        self.sponge.absorb(&bytes);
+    }
+
+    fn absorb_native_field_elements<T: ToConstraintField<CF>>(&mut self, elems: &[T]) {
+        for elem in elems {
+            let elems = elem
+                .to_field_elements()
+                .expect("the element can be converted to native field elements");
+            self.sponge.absorb_native_field_elements(&elems);
+        }
+    }
+
+    fn absorb_nonnative_field_elements(&mut self, elems: &[F]) {
+        self.sponge.absorb_nonnative(elems);
+    }
+
+    fn squeeze_native_field_elements(&mut self, num_elements: usize) -> Vec<CF> {
+        self.sponge.squeeze_native_field_elements(num_elements)
+    }
+
+    fn squeeze_nonnative_field_elements(&mut self, num_elements: usize) -> Vec<F> {
+        self.sponge.squeeze_nonnative_field_elements(num_elements)
+    }
+
+    fn squeeze_128_bits_nonnative_field_elements(&mut self, num_elements: usize) -> Vec<F> {
+        self.sponge
+            .squeeze_nonnative_field_elements_with_sizes(&vec![
+                FieldElementSize::Truncated(128);
+                num_elements
+            ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::PoseidonSponge;
+    use crate::rng::{FiatShamirRng, SpongeRng};
+    use crate::{CryptographicSponge, FieldBasedCryptographicSponge, FieldElementSize};
+    use ark_ff::{BigInteger, PrimeField, UniformRand};
+    use ark_std::rand::RngCore;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::{Fq, Fr};
+
+    #[test]
+    fn test_sponge_rng() {
+        let sponge = PoseidonSponge::<Fr>::new(&poseidon_parameters_for_test());
+        let mut rng1 = SpongeRng::new(sponge.clone());
+        let mut rng2 = SpongeRng::new(sponge.clone());
+
+        // Usable wherever an RNG is expected, and deterministic.
+        assert_eq!(Fr::rand(&mut rng1), Fr::rand(&mut rng2));
+        assert_eq!(rng1.next_u64(), rng2.next_u64());
+
+        // The output is squeezed from the sponge.
+        let mut bytes = [0u8; 10];
+        let mut rng = SpongeRng::new(sponge.clone());
+        rng.fill_bytes(&mut bytes);
+        assert_eq!(bytes.to_vec(), sponge.clone().squeeze_bytes(10));
+
+        // Absorbing reseeds the RNG.
+        rng1.absorb(&1u8);
+        rng2.absorb(&2u8);
+        assert_ne!(rng1.next_u64(), rng2.next_u64());
+    }
+
+    #[test]
+    fn test_fiat_shamir_rng() {
+        let mut sponge = PoseidonSponge::<Fr>::new(&poseidon_parameters_for_test());
+        let mut rng = SpongeRng::new(sponge.clone());
+
+        FiatShamirRng::<Fq, Fr>::absorb_bytes(&mut rng, b"statement");
+        FiatShamirRng::<Fq, Fr>::absorb_native_field_elements(&mut rng, &[Fr::from(1u64)]);
+        rng.absorb_nonnative_field_elements(&[Fq::from(2u64)]);
+        let native: Vec<Fr> = FiatShamirRng::<Fq, _>::squeeze_native_field_elements(&mut rng, 2);
+        let nonnative: Vec<Fq> = rng.squeeze_nonnative_field_elements(1);
+        let short: Vec<Fq> = rng.squeeze_128_bits_nonnative_field_elements(2);
+
+        sponge.absorb(&b"statement".as_ref());
+        sponge.absorb_native_field_elements(&[Fr::from(1u64)]);
+        sponge.absorb_nonnative(&[Fq::from(2u64)]);
+        assert_eq!(native, sponge.squeeze_native_field_elements(2));
+        assert_eq!(nonnative, sponge.squeeze_nonnative_field_elements::<Fq>(1));
+        assert_eq!(
+            short,
+            sponge.squeeze_nonnative_field_elements_with_sizes::<Fq>(&[
+                FieldElementSize::Truncated(128),
+                FieldElementSize::Truncated(128)
+            ])
+        );
+        assert!(short.iter().all(|elem| elem.into_repr().num_bits() <= 128));
+    }
+}
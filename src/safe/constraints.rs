@@ -0,0 +1,145 @@
+use crate::duplex::constraints::FieldPermutationGadget;
+use crate::safe::{operation_length, safe_tag, IoPatternTracker, SafeOperation};
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::SynthesisError;
+use ark_std::vec;
+use ark_std::vec::Vec;
+
+/// The gadget for [`SafeSponge`](crate::safe::SafeSponge).
+///
+/// The IO pattern is checked while generating the constraints: a call that does not follow it
+/// fails with [`SynthesisError::Unsatisfiable`]. The tag is a constant of the circuit.
+#[derive(Clone)]
+pub struct SafeSpongeVar<F: PrimeField, P: FieldPermutationGadget<F>> {
+    permutation: P,
+    state: Vec<FpVar<F>>,
+    absorb_pos: usize,
+    squeeze_pos: usize,
+    io_pattern: IoPatternTracker,
+}
+
+impl<F: PrimeField, P: FieldPermutationGadget<F>> SafeSpongeVar<F, P> {
+    /// Starts a sponge for the given IO pattern and domain separator.
+    ///
+    /// Fails with [`SynthesisError::Unsatisfiable`] if an aggregated operation of the IO pattern
+    /// has more than [`MAX_OPERATION_LENGTH`](crate::safe::MAX_OPERATION_LENGTH) elements.
+    pub fn start(
+        permutation: &P,
+        io_pattern: &[SafeOperation],
+        domain_separator: &[u8],
+    ) -> Result<Self, SynthesisError> {
+        let tag =
+            safe_tag(io_pattern, domain_separator).map_err(|_| SynthesisError::Unsatisfiable)?;
+        let mut state = vec![FpVar::zero(); permutation.rate() + permutation.capacity()];
+        state[0] = FpVar::Constant(tag);
+
+        Ok(Self {
+            permutation: permutation.clone(),
+            state,
+            absorb_pos: 0,
+            squeeze_pos: 0,
+            io_pattern: IoPatternTracker::new(io_pattern)
+                .map_err(|_| SynthesisError::Unsatisfiable)?,
+        })
+    }
+
+    /// Absorbs `input`, which must match the next absorption of the IO pattern.
+    #[tracing::instrument(target = "r1cs", skip(self, input))]
+    pub fn absorb(&mut self, input: &[FpVar<F>]) -> Result<(), SynthesisError> {
+        operation_length(input.len())
+            .and_then(|length| self.io_pattern.consume(SafeOperation::Absorb(length)))
+            .map_err(|_| SynthesisError::Unsatisfiable)?;
+
+        let rate = self.permutation.rate();
+        let capacity = self.permutation.capacity();
+        for elem in input {
+            if self.absorb_pos == rate {
+                self.permutation.permute_var(&mut self.state)?;
+                self.absorb_pos = 0;
+            }
+            self.state[capacity + self.absorb_pos] += elem;
+            self.absorb_pos += 1;
+        }
+        self.squeeze_pos = rate;
+        Ok(())
+    }
+
+    /// Squeezes `num_elements` field elements, which must match the next squeeze of the IO
+    /// pattern.
+    #[tracing::instrument(target = "r1cs", skip(self))]
+    pub fn squeeze(&mut self, num_elements: usize) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        operation_length(num_elements)
+            .and_then(|length| self.io_pattern.consume(SafeOperation::Squeeze(length)))
+            .map_err(|_| SynthesisError::Unsatisfiable)?;
+
+        let rate = self.permutation.rate();
+        let capacity = self.permutation.capacity();
+        let mut output = Vec::with_capacity(num_elements);
+        for _ in 0..num_elements {
+            if self.squeeze_pos == rate {
+                self.permutation.permute_var(&mut self.state)?;
+                self.squeeze_pos = 0;
+                self.absorb_pos = 0;
+            }
+            output.push(self.state[capacity + self.squeeze_pos].clone());
+            self.squeeze_pos += 1;
+        }
+        Ok(output)
+    }
+
+    /// Finishes the sponge, checking that all operations of the IO pattern have been performed.
+    pub fn finish(self) -> Result<(), SynthesisError> {
+        self.io_pattern
+            .finish()
+            .map_err(|_| SynthesisError::Unsatisfiable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::poseidon2::Poseidon2Parameters;
+    use crate::safe::constraints::SafeSpongeVar;
+    use crate::safe::{SafeOperation, SafeSponge};
+    use ark_ff::UniformRand;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::test_rng;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn test_safe_sponge_var() {
+        use SafeOperation::*;
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::new_ref();
+        let parameters = Poseidon2Parameters::<Fr>::generate(2, 1, 128);
+        let io_pattern = [Absorb(3), Squeeze(2), Absorb(1), Squeeze(3)];
+        let input: Vec<_> = (0..4).map(|_| Fr::rand(&mut rng)).collect();
+        let input_var: Vec<_> = input
+            .iter()
+            .map(|x| FpVar::new_witness(cs.clone(), || Ok(*x)).unwrap())
+            .collect();
+
+        let mut sponge = SafeSponge::start(&parameters, &io_pattern, b"test").unwrap();
+        let mut sponge_var = SafeSpongeVar::start(&parameters, &io_pattern, b"test").unwrap();
+
+        sponge.absorb(&input[..3]).unwrap();
+        sponge_var.absorb(&input_var[..3]).unwrap();
+        let mut output = sponge.squeeze(2).unwrap();
+        let mut output_var = sponge_var.squeeze(2).unwrap();
+        sponge.absorb(&input[3..]).unwrap();
+        sponge_var.absorb(&input_var[3..]).unwrap();
+        output.extend(sponge.squeeze(3).unwrap());
+        output_var.extend(sponge_var.squeeze(3).unwrap());
+
+        assert!(sponge_var.clone().squeeze(1).is_err());
+        sponge.finish().unwrap();
+        sponge_var.finish().unwrap();
+
+        assert_eq!(output, output_var.value().unwrap());
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
@@ -0,0 +1,399 @@
+use crate::duplex::FieldPermutation;
+use ark_ff::PrimeField;
+use ark_std::convert::TryFrom;
+use ark_std::fmt;
+use ark_std::vec;
+use ark_std::vec::Vec;
+use sha3::{Digest, Sha3_256};
+
+/// constraints for the SAFE API
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+/// An operation of an IO pattern.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SafeOperation {
+    /// Absorb the given number of field elements.
+    Absorb(u32),
+    /// Squeeze the given number of field elements.
+    Squeeze(u32),
+}
+
+/// An error of the SAFE API, when the calls to the sponge do not follow its IO pattern.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SafeError {
+    /// The call does not match the next operation of the IO pattern.
+    UnexpectedOperation {
+        /// The next operation of the IO pattern, if any.
+        expected: Option<SafeOperation>,
+        /// The operation that was called.
+        found: SafeOperation,
+    },
+    /// The sponge was finished before the end of the IO pattern.
+    UnfinishedPattern {
+        /// The remaining operations of the IO pattern.
+        remaining: Vec<SafeOperation>,
+    },
+    /// An operation, or consecutive operations of the same kind of an IO pattern, have more than
+    /// [`MAX_OPERATION_LENGTH`] elements.
+    LengthTooLarge {
+        /// The number of elements.
+        length: u64,
+    },
+}
+
+impl fmt::Display for SafeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SafeError::UnexpectedOperation { expected, found } => write!(
+                f,
+                "the IO pattern expects {:?}, but {:?} was called",
+                expected, found
+            ),
+            SafeError::UnfinishedPattern { remaining } => write!(
+                f,
+                "the sponge was finished before the operations {:?} of the IO pattern",
+                remaining
+            ),
+            SafeError::LengthTooLarge { length } => write!(
+                f,
+                "an operation of {} elements is longer than the maximum of {}",
+                length, MAX_OPERATION_LENGTH
+            ),
+        }
+    }
+}
+
+impl ark_std::error::Error for SafeError {}
+
+/// The maximum number of elements of an aggregated operation, whose length is encoded in 31
+/// bits of a word of the IO pattern.
+pub const MAX_OPERATION_LENGTH: u32 = 0x7fff_ffff;
+
+/// Returns the length of an operation on `length` elements, or [`SafeError::LengthTooLarge`] if
+/// it is greater than [`MAX_OPERATION_LENGTH`].
+pub(crate) fn operation_length(length: usize) -> Result<u32, SafeError> {
+    u32::try_from(length)
+        .ok()
+        .filter(|length| *length <= MAX_OPERATION_LENGTH)
+        .ok_or(SafeError::LengthTooLarge {
+            length: length as u64,
+        })
+}
+
+/// Merges the consecutive operations of the same kind of `io_pattern`, and drops the empty
+/// ones. Returns [`SafeError::LengthTooLarge`] if a merged operation has more than
+/// [`MAX_OPERATION_LENGTH`] elements.
+fn aggregate(io_pattern: &[SafeOperation]) -> Result<Vec<SafeOperation>, SafeError> {
+    let mut aggregated: Vec<SafeOperation> = Vec::new();
+    for op in io_pattern {
+        match (aggregated.last_mut(), op) {
+            (_, SafeOperation::Absorb(0)) | (_, SafeOperation::Squeeze(0)) => {}
+            (Some(SafeOperation::Absorb(n)), SafeOperation::Absorb(m))
+            | (Some(SafeOperation::Squeeze(n)), SafeOperation::Squeeze(m)) => {
+                *n = n
+                    .checked_add(*m)
+                    .filter(|sum| *sum <= MAX_OPERATION_LENGTH)
+                    .ok_or(SafeError::LengthTooLarge {
+                        length: u64::from(*n) + u64::from(*m),
+                    })?;
+            }
+            (_, SafeOperation::Absorb(m)) | (_, SafeOperation::Squeeze(m))
+                if *m > MAX_OPERATION_LENGTH =>
+            {
+                return Err(SafeError::LengthTooLarge {
+                    length: u64::from(*m),
+                })
+            }
+            _ => aggregated.push(*op),
+        }
+    }
+    Ok(aggregated)
+}
+
+/// Computes the tag of an IO pattern and a domain separator, as in the SAFE specification.
+///
+/// Every aggregated operation is encoded as a 32-bit big-endian word, with the most significant
+/// bit set for absorptions. The tag is the first 128 bits of the SHA3-256 hash of these words
+/// followed by `domain_separator`, as a big-endian integer.
+///
+/// Returns [`SafeError::LengthTooLarge`] if an aggregated operation has more than
+/// [`MAX_OPERATION_LENGTH`] elements, whose length would overlap the bit of absorptions.
+pub fn safe_tag<F: PrimeField>(
+    io_pattern: &[SafeOperation],
+    domain_separator: &[u8],
+) -> Result<F, SafeError> {
+    let mut hasher = Sha3_256::new();
+    for op in aggregate(io_pattern)? {
+        let word = match op {
+            SafeOperation::Absorb(n) => 0x8000_0000 | n,
+            SafeOperation::Squeeze(n) => n,
+        };
+        hasher.update(word.to_be_bytes());
+    }
+    hasher.update(domain_separator);
+    Ok(F::from_be_bytes_mod_order(&hasher.finalize()[..16]))
+}
+
+/// Tracks the progress of the calls to a SAFE sponge through its IO pattern.
+#[derive(Clone, Debug)]
+pub(crate) struct IoPatternTracker {
+    /// The remaining operations, in reverse order.
+    remaining: Vec<SafeOperation>,
+}
+
+impl IoPatternTracker {
+    pub(crate) fn new(io_pattern: &[SafeOperation]) -> Result<Self, SafeError> {
+        let mut remaining = aggregate(io_pattern)?;
+        remaining.reverse();
+        Ok(Self { remaining })
+    }
+
+    /// Consumes `op` from the IO pattern. A call may cover only part of the next aggregated
+    /// operation.
+    pub(crate) fn consume(&mut self, op: SafeOperation) -> Result<(), SafeError> {
+        let error = SafeError::UnexpectedOperation {
+            expected: self.remaining.last().copied(),
+            found: op,
+        };
+        match (self.remaining.last_mut(), op) {
+            (_, SafeOperation::Absorb(0)) | (_, SafeOperation::Squeeze(0)) => return Ok(()),
+            (Some(SafeOperation::Absorb(n)), SafeOperation::Absorb(m))
+            | (Some(SafeOperation::Squeeze(n)), SafeOperation::Squeeze(m))
+                if m <= *n =>
+            {
+                *n -= m;
+                if *n == 0 {
+                    self.remaining.pop();
+                }
+            }
+            _ => return Err(error),
+        }
+        Ok(())
+    }
+
+    pub(crate) fn finish(&self) -> Result<(), SafeError> {
+        if self.remaining.is_empty() {
+            Ok(())
+        } else {
+            let mut remaining = self.remaining.clone();
+            remaining.reverse();
+            Err(SafeError::UnfinishedPattern { remaining })
+        }
+    }
+}
+
+/// A sponge following the [SAFE][safe] (Sponge API for Field Elements) specification, over the
+/// permutation `P`.
+///
+/// The sponge is started with an IO pattern, the sequence of absorptions and squeezes that
+/// the protocol performs, which is hashed with a domain separator into the first element of the
+/// capacity. Every call is checked against the IO pattern, so that the prover and the verifier
+/// of a protocol cannot silently diverge, and sponges for different IO patterns or domain
+/// separators are independent.
+///
+/// The rate is the last `rate` elements of the state, as in [`DuplexSponge`].
+///
+/// [safe]: https://eprint.iacr.org/2023/522
+/// [`DuplexSponge`]: crate::duplex::DuplexSponge
+#[derive(Clone)]
+pub struct SafeSponge<F: PrimeField, P: FieldPermutation<F>> {
+    permutation: P,
+    state: Vec<F>,
+    absorb_pos: usize,
+    squeeze_pos: usize,
+    io_pattern: IoPatternTracker,
+}
+
+impl<F: PrimeField, P: FieldPermutation<F>> SafeSponge<F, P> {
+    /// Starts a sponge for the given IO pattern and domain separator.
+    ///
+    /// Returns [`SafeError::LengthTooLarge`] if an aggregated operation of the IO pattern has
+    /// more than [`MAX_OPERATION_LENGTH`] elements.
+    pub fn start(
+        permutation: &P,
+        io_pattern: &[SafeOperation],
+        domain_separator: &[u8],
+    ) -> Result<Self, SafeError> {
+        let mut state = vec![F::zero(); permutation.rate() + permutation.capacity()];
+        state[0] = safe_tag(io_pattern, domain_separator)?;
+
+        Ok(Self {
+            permutation: permutation.clone(),
+            state,
+            absorb_pos: 0,
+            squeeze_pos: 0,
+            io_pattern: IoPatternTracker::new(io_pattern)?,
+        })
+    }
+
+    /// Absorbs `input`, which must match the next absorption of the IO pattern.
+    pub fn absorb(&mut self, input: &[F]) -> Result<(), SafeError> {
+        self.io_pattern
+            .consume(SafeOperation::Absorb(operation_length(input.len())?))?;
+
+        let rate = self.permutation.rate();
+        let capacity = self.permutation.capacity();
+        for elem in input {
+            if self.absorb_pos == rate {
+                self.permutation.permute(&mut self.state);
+                self.absorb_pos = 0;
+            }
+            self.state[capacity + self.absorb_pos] += elem;
+            self.absorb_pos += 1;
+        }
+        // the next squeeze must permute
+        self.squeeze_pos = rate;
+        Ok(())
+    }
+
+    /// Squeezes `num_elements` field elements, which must match the next squeeze of the IO
+    /// pattern.
+    pub fn squeeze(&mut self, num_elements: usize) -> Result<Vec<F>, SafeError> {
+        self.io_pattern
+            .consume(SafeOperation::Squeeze(operation_length(num_elements)?))?;
+
+        let rate = self.permutation.rate();
+        let capacity = self.permutation.capacity();
+        let mut output = Vec::with_capacity(num_elements);
+        for _ in 0..num_elements {
+            if self.squeeze_pos == rate {
+                self.permutation.permute(&mut self.state);
+                self.squeeze_pos = 0;
+                self.absorb_pos = 0;
+            }
+            output.push(self.state[capacity + self.squeeze_pos]);
+            self.squeeze_pos += 1;
+        }
+        Ok(output)
+    }
+
+    /// Finishes the sponge, checking that all operations of the IO pattern have been performed.
+    pub fn finish(self) -> Result<(), SafeError> {
+        self.io_pattern.finish()
+    }
+}
+
+/// Zeroes the state, which may depend on secret inputs.
+#[cfg(feature = "zeroize")]
+impl<F: PrimeField, P: FieldPermutation<F>> zeroize::Zeroize for SafeSponge<F, P> {
+    fn zeroize(&mut self) {
+        self.state.iter_mut().for_each(|elem| elem.zeroize());
+        self.absorb_pos.zeroize();
+        self.squeeze_pos.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<F: PrimeField, P: FieldPermutation<F>> Drop for SafeSponge<F, P> {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<F: PrimeField, P: FieldPermutation<F>> zeroize::ZeroizeOnDrop for SafeSponge<F, P> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::poseidon2::Poseidon2Parameters;
+    use crate::safe::{
+        operation_length, safe_tag, SafeError, SafeOperation, SafeSponge, MAX_OPERATION_LENGTH,
+    };
+    use ark_ff::UniformRand;
+    use ark_std::test_rng;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn test_tag() {
+        use SafeOperation::*;
+        let tag = safe_tag::<Fr>(&[Absorb(3), Squeeze(1)], b"test").unwrap();
+        // consecutive operations of the same kind are aggregated
+        assert_eq!(
+            tag,
+            safe_tag(&[Absorb(1), Absorb(2), Squeeze(0), Squeeze(1)], b"test").unwrap()
+        );
+        assert_ne!(tag, safe_tag(&[Absorb(3), Squeeze(2)], b"test").unwrap());
+        assert_ne!(tag, safe_tag(&[Absorb(3), Squeeze(1)], b"other").unwrap());
+    }
+
+    #[test]
+    fn test_io_pattern() {
+        use SafeOperation::*;
+        let mut rng = test_rng();
+        let parameters = Poseidon2Parameters::<Fr>::generate(2, 1, 128);
+        let input: Vec<_> = (0..5).map(|_| Fr::rand(&mut rng)).collect();
+        let io_pattern = [Absorb(5), Squeeze(3)];
+
+        let mut sponge = SafeSponge::start(&parameters, &io_pattern, b"test").unwrap();
+        sponge.absorb(&input[..2]).unwrap();
+        sponge.absorb(&input[2..]).unwrap();
+        let output = sponge.squeeze(3).unwrap();
+        sponge.finish().unwrap();
+
+        let mut sponge = SafeSponge::start(&parameters, &io_pattern, b"test").unwrap();
+        sponge.absorb(&input).unwrap();
+        assert_eq!(sponge.squeeze(1).unwrap(), output[..1].to_vec());
+        assert_eq!(
+            sponge.clone().finish(),
+            Err(SafeError::UnfinishedPattern {
+                remaining: ark_std::vec![Squeeze(2)]
+            })
+        );
+        assert_eq!(
+            sponge.absorb(&input[..1]),
+            Err(SafeError::UnexpectedOperation {
+                expected: Some(Squeeze(2)),
+                found: Absorb(1)
+            })
+        );
+        assert!(sponge.squeeze(3).is_err());
+        assert_eq!(sponge.squeeze(2).unwrap(), output[1..].to_vec());
+        assert!(sponge.squeeze(1).is_err());
+
+        // A different domain separator gives an independent sponge.
+        let mut sponge = SafeSponge::start(&parameters, &io_pattern, b"other").unwrap();
+        sponge.absorb(&input).unwrap();
+        assert_ne!(sponge.squeeze(3).unwrap(), output);
+    }
+
+    #[test]
+    fn test_operation_length() {
+        use SafeOperation::*;
+        assert_eq!(
+            operation_length(MAX_OPERATION_LENGTH as usize),
+            Ok(MAX_OPERATION_LENGTH)
+        );
+        assert_eq!(
+            operation_length(MAX_OPERATION_LENGTH as usize + 1),
+            Err(SafeError::LengthTooLarge { length: 1 << 31 })
+        );
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(
+            operation_length((1 << 32) + 3),
+            Err(SafeError::LengthTooLarge {
+                length: (1 << 32) + 3
+            })
+        );
+
+        // aggregated operations must fit too
+        assert!(safe_tag::<Fr>(&[Absorb(MAX_OPERATION_LENGTH), Squeeze(1)], b"test").is_ok());
+        assert_eq!(
+            safe_tag::<Fr>(&[Absorb(MAX_OPERATION_LENGTH), Absorb(1)], b"test"),
+            Err(SafeError::LengthTooLarge { length: 1 << 31 })
+        );
+        assert_eq!(
+            safe_tag::<Fr>(&[Squeeze(3), Squeeze(u32::MAX)], b"test"),
+            Err(SafeError::LengthTooLarge {
+                length: u64::from(u32::MAX) + 3
+            })
+        );
+        assert_eq!(
+            safe_tag::<Fr>(&[Absorb(1 << 31)], b"test"),
+            Err(SafeError::LengthTooLarge { length: 1 << 31 })
+        );
+        let parameters = Poseidon2Parameters::<Fr>::generate(2, 1, 128);
+        assert!(SafeSponge::start(&parameters, &[Absorb(u32::MAX)], b"test").is_err());
+    }
+}
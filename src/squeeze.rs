@@ -0,0 +1,80 @@
+use crate::FieldBasedCryptographicSponge;
+use ark_ff::models::{
+    Fp256, Fp256Parameters, Fp320, Fp320Parameters, Fp384, Fp384Parameters, Fp64, Fp64Parameters,
+    Fp768, Fp768Parameters, Fp832, Fp832Parameters,
+};
+use ark_ff::PrimeField;
+use ark_std::vec::Vec;
+
+/// Derives [`SqueezeFromSponge`] for a struct by squeezing its fields in declaration order.
+/// Fields marked with `#[sponge(skip)]` are not squeezed and take their default value.
+#[cfg(feature = "derive")]
+pub use ark_sponge_derive::SqueezeFromSponge;
+
+/// An interface for values that can be squeezed from a sponge over `CF`, such as the challenges
+/// of a round of a protocol, so that a verifier squeezes them with a single call instead of
+/// squeezing field elements and destructuring them.
+///
+/// [`SqueezeFromSpongeGadget`] is the counterpart of this trait for gadgets: a type and its
+/// gadget with the same shape squeeze the same values.
+///
+/// [`SqueezeFromSpongeGadget`]: crate::constraints::SqueezeFromSpongeGadget
+pub trait SqueezeFromSponge<CF: PrimeField>: Sized {
+    /// What is needed to squeeze a value besides the sponge, such as the lengths of its vectors:
+    /// `()` for the types of fixed size, `usize` for vectors, and the tuple of the shapes of the
+    /// squeezed fields for derived structs.
+    type Shape;
+
+    /// Squeezes a value of shape `shape` from `sponge`.
+    fn squeeze_from_sponge<S: FieldBasedCryptographicSponge<CF>>(
+        sponge: &mut S,
+        shape: &Self::Shape,
+    ) -> Self;
+}
+
+macro_rules! impl_squeezable_field {
+    ($field:ident, $params:ident) => {
+        impl<P: $params> SqueezeFromSponge<$field<P>> for $field<P> {
+            type Shape = ();
+
+            fn squeeze_from_sponge<S: FieldBasedCryptographicSponge<$field<P>>>(
+                sponge: &mut S,
+                _shape: &(),
+            ) -> Self {
+                sponge.squeeze_native_field_elements(1)[0]
+            }
+        }
+    };
+}
+
+impl_squeezable_field!(Fp64, Fp64Parameters);
+impl_squeezable_field!(Fp256, Fp256Parameters);
+impl_squeezable_field!(Fp320, Fp320Parameters);
+impl_squeezable_field!(Fp384, Fp384Parameters);
+impl_squeezable_field!(Fp768, Fp768Parameters);
+impl_squeezable_field!(Fp832, Fp832Parameters);
+
+impl<CF: PrimeField> SqueezeFromSponge<CF> for bool {
+    type Shape = ();
+
+    fn squeeze_from_sponge<S: FieldBasedCryptographicSponge<CF>>(
+        sponge: &mut S,
+        _shape: &(),
+    ) -> Self {
+        sponge.squeeze_bits(1)[0]
+    }
+}
+
+/// A vector of `shape` values, squeezed one after the other.
+impl<CF: PrimeField, T: SqueezeFromSponge<CF, Shape = ()>> SqueezeFromSponge<CF> for Vec<T> {
+    type Shape = usize;
+
+    fn squeeze_from_sponge<S: FieldBasedCryptographicSponge<CF>>(
+        sponge: &mut S,
+        shape: &usize,
+    ) -> Self {
+        (0..*shape)
+            .map(|_| T::squeeze_from_sponge(sponge, &()))
+            .collect()
+    }
+}
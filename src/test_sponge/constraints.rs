@@ -0,0 +1,129 @@
+use crate::constraints::{AbsorbGadget, CryptographicSpongeVar, SpongeWithGadget};
+use crate::test_sponge::{TestSponge, TestSpongeParameters};
+use crate::{CryptographicSponge, FieldBasedCryptographicSponge};
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use ark_std::vec::Vec;
+
+/// The gadget for [`TestSponge`], whose outputs are the constants squeezed by the native
+/// sponge, so that it costs no constraints.
+#[derive(Clone)]
+pub struct TestSpongeVar<F: PrimeField> {
+    cs: ConstraintSystemRef<F>,
+    sponge: TestSponge<F>,
+}
+
+impl<F: PrimeField> SpongeWithGadget<F> for TestSponge<F> {
+    type Var = TestSpongeVar<F>;
+}
+
+impl<F: PrimeField> CryptographicSpongeVar<F, TestSponge<F>> for TestSpongeVar<F> {
+    type Parameters = TestSpongeParameters<F>;
+
+    fn new(cs: ConstraintSystemRef<F>, params: &Self::Parameters) -> Self {
+        Self {
+            cs,
+            sponge: TestSponge::new(params),
+        }
+    }
+
+    fn cs(&self) -> ConstraintSystemRef<F> {
+        self.cs.clone()
+    }
+
+    fn rate(&self) -> usize {
+        self.sponge.rate()
+    }
+
+    fn capacity(&self) -> usize {
+        self.sponge.capacity()
+    }
+
+    fn security_level(&self) -> usize {
+        self.sponge.security_level()
+    }
+
+    fn absorb(&mut self, _input: &impl AbsorbGadget<F>) -> Result<(), SynthesisError> {
+        Ok(())
+    }
+
+    fn squeeze_bytes(&mut self, num_bytes: usize) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        Ok(self
+            .sponge
+            .squeeze_bytes(num_bytes)
+            .into_iter()
+            .map(UInt8::constant)
+            .collect())
+    }
+
+    fn squeeze_bits(&mut self, num_bits: usize) -> Result<Vec<Boolean<F>>, SynthesisError> {
+        Ok(self
+            .sponge
+            .squeeze_bits(num_bits)
+            .into_iter()
+            .map(Boolean::constant)
+            .collect())
+    }
+
+    fn squeeze_field_elements(
+        &mut self,
+        num_elements: usize,
+    ) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        Ok(self
+            .sponge
+            .squeeze_native_field_elements(num_elements)
+            .into_iter()
+            .map(FpVar::constant)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constraints::CryptographicSpongeVar;
+    use crate::test_sponge::constraints::TestSpongeVar;
+    use crate::test_sponge::{TestSponge, TestSpongeParameters};
+    use crate::{CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::vec;
+    use ark_test_curves::bls12_381::{Fq, Fr};
+
+    #[test]
+    fn consistency_with_native() {
+        let params = TestSpongeParameters::new(42).with_challenges(vec![Fr::from(9u64)]);
+        let mut native_sponge = TestSponge::new(&params);
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let mut constraint_sponge = TestSpongeVar::new(cs.clone(), &params);
+
+        let input = FpVar::new_witness(cs.clone(), || Ok(Fr::from(1u64))).unwrap();
+        constraint_sponge.absorb(&input).unwrap();
+        assert_eq!(
+            constraint_sponge
+                .squeeze_field_elements(3)
+                .unwrap()
+                .value()
+                .unwrap(),
+            native_sponge.squeeze_native_field_elements(3)
+        );
+        assert_eq!(
+            constraint_sponge.squeeze_bytes(5).unwrap().value().unwrap(),
+            native_sponge.squeeze_bytes(5)
+        );
+        assert_eq!(
+            constraint_sponge.squeeze_bits(9).unwrap().value().unwrap(),
+            native_sponge.squeeze_bits(9)
+        );
+        let (nonnative, _) = constraint_sponge
+            .squeeze_nonnative_field_elements::<Fq>(2)
+            .unwrap();
+        assert_eq!(
+            nonnative.value().unwrap(),
+            native_sponge.squeeze_field_elements::<Fq>(2)
+        );
+        assert_eq!(cs.num_constraints(), 0);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::{Absorb, CryptographicSponge, FieldBasedCryptographicSponge};
+use ark_ff::PrimeField;
+use ark_std::vec::Vec;
+
+/// constraints for the test sponge
+#[cfg(feature = "r1cs")]
+pub mod constraints;
+
+/// The parameters of a [`TestSponge`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TestSpongeParameters<F: PrimeField> {
+    /// The seed of the outputs.
+    pub seed: u64,
+    /// The field elements returned, in order, by the first calls to
+    /// [`TestSponge::squeeze_native_field_elements`].
+    pub challenges: Vec<F>,
+}
+
+impl<F: PrimeField> TestSpongeParameters<F> {
+    /// Returns the parameters of a sponge seeded with `seed`, without scripted challenges.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            challenges: Vec::new(),
+        }
+    }
+
+    /// Returns these parameters with `challenges` returned first by the sponge.
+    pub fn with_challenges(mut self, challenges: Vec<F>) -> Self {
+        self.challenges = challenges;
+        self
+    }
+}
+
+/// A mock sponge for the tests of downstream protocols, which is cheap and deterministic but
+/// offers no security at all.
+///
+/// The absorbed inputs are ignored. The output of each squeeze is a function of the seed and of
+/// the number of squeezes before it only, computed with a few integer operations, so
+/// [`TestSpongeVar`](constraints::TestSpongeVar) gives the same outputs as constants, without
+/// any constraint. Native field elements are first taken from the scripted
+/// [`challenges`](TestSpongeParameters::challenges), so that tests can choose the challenges of
+/// a protocol.
+#[derive(Clone, Debug)]
+pub struct TestSponge<F: PrimeField> {
+    seed: u64,
+    challenges: Vec<F>,
+    /// The number of challenges already squeezed.
+    num_challenges_squeezed: usize,
+    /// The number of squeezes so far.
+    counter: u64,
+}
+
+impl<F: PrimeField> TestSponge<F> {
+    /// Returns the number of squeezes so far.
+    pub fn counter(&self) -> u64 {
+        self.counter
+    }
+
+    fn next_bytes(&mut self, num_bytes: usize) -> Vec<u8> {
+        let key = splitmix64(self.seed ^ splitmix64(self.counter));
+        self.counter += 1;
+        (0..num_bytes as u64)
+            .map(|i| splitmix64(key.wrapping_add(i / 8)).to_le_bytes()[(i % 8) as usize])
+            .collect()
+    }
+}
+
+/// The finalizer of the SplitMix64 generator, a cheap mixing function of 64-bit integers.
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+impl<F: PrimeField> CryptographicSponge for TestSponge<F> {
+    type Parameters = TestSpongeParameters<F>;
+
+    fn new(params: &Self::Parameters) -> Self {
+        Self {
+            seed: params.seed,
+            challenges: params.challenges.clone(),
+            num_challenges_squeezed: 0,
+            counter: 0,
+        }
+    }
+
+    /// The test sponge has no state, and no security.
+    fn rate(&self) -> usize {
+        1
+    }
+
+    fn capacity(&self) -> usize {
+        0
+    }
+
+    fn security_level(&self) -> usize {
+        0
+    }
+
+    fn absorb(&mut self, _input: &impl Absorb) {}
+
+    fn squeeze_bytes(&mut self, num_bytes: usize) -> Vec<u8> {
+        self.next_bytes(num_bytes)
+    }
+
+    fn squeeze_bits(&mut self, num_bits: usize) -> Vec<bool> {
+        let bytes = self.next_bytes(num_bits.div_ceil(8));
+        (0..num_bits)
+            .map(|i| (bytes[i / 8] >> (i % 8)) & 1 == 1)
+            .collect()
+    }
+}
+
+impl<F: PrimeField> FieldBasedCryptographicSponge<F> for TestSponge<F> {
+    fn squeeze_native_field_elements(&mut self, num_elements: usize) -> Vec<F> {
+        let num_scripted = ark_std::cmp::min(
+            num_elements,
+            self.challenges.len() - self.num_challenges_squeezed,
+        );
+        let mut elems = self.challenges
+            [self.num_challenges_squeezed..self.num_challenges_squeezed + num_scripted]
+            .to_vec();
+        self.num_challenges_squeezed += num_scripted;
+        if num_scripted < num_elements {
+            let num_bytes = F::size_in_bits().div_ceil(8);
+            let bytes = self.next_bytes(num_bytes * (num_elements - num_scripted));
+            elems.extend(bytes.chunks(num_bytes).map(F::from_le_bytes_mod_order));
+        }
+        elems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_sponge::{TestSponge, TestSpongeParameters};
+    use crate::{CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_std::vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    #[test]
+    fn deterministic_outputs() {
+        let params = TestSpongeParameters::<Fr>::new(7);
+        let mut sponge = TestSponge::new(&params);
+        let mut other_sponge = TestSponge::new(&params);
+        sponge.absorb(&1u64);
+        other_sponge.absorb(&2u64);
+        assert_eq!(sponge.squeeze_bytes(20), other_sponge.squeeze_bytes(20));
+        assert_eq!(sponge.counter(), 1);
+
+        let first = sponge.squeeze_native_field_elements(2);
+        let second = sponge.squeeze_native_field_elements(2);
+        assert_ne!(first, second);
+        assert_ne!(first[0], first[1]);
+
+        let mut other_seed = TestSponge::new(&TestSpongeParameters::<Fr>::new(8));
+        let _ = other_seed.squeeze_bytes(20);
+        assert_ne!(other_seed.squeeze_native_field_elements(2), first);
+    }
+
+    #[test]
+    fn scripted_challenges() {
+        let challenges = vec![Fr::from(3u64), Fr::from(5u64), Fr::from(7u64)];
+        let params = TestSpongeParameters::new(0).with_challenges(challenges.clone());
+        let mut sponge = TestSponge::new(&params);
+        assert_eq!(sponge.squeeze_native_field_elements(2), challenges[..2]);
+        let elems = sponge.squeeze_native_field_elements(2);
+        assert_eq!(elems[0], challenges[2]);
+
+        let mut unscripted = TestSponge::new(&TestSpongeParameters::<Fr>::new(0));
+        assert_eq!(unscripted.squeeze_native_field_elements(1)[0], elems[1]);
+    }
+}
@@ -0,0 +1,182 @@
+use crate::constraints::CryptographicSpongeVar;
+use crate::{Absorb, FieldBasedCryptographicSponge};
+use ark_ff::PrimeField;
+use ark_r1cs_std::alloc::AllocVar;
+use ark_r1cs_std::bits::uint8::UInt8;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::R1CSVar;
+use ark_relations::r1cs::ConstraintSystem;
+use ark_std::format;
+use ark_std::rand::Rng;
+use ark_std::string::String;
+use ark_std::vec::Vec;
+
+/// The maximum number of operations of a schedule of [`assert_sponge_consistency`].
+const MAX_SCHEDULE_LENGTH: usize = 8;
+
+/// Checks that the gadget `SV` computes the same outputs as the native sponge `S` over
+/// `num_schedules` random schedules of operations.
+///
+/// Every schedule is a random sequence of absorbs of field elements and bytes, allocated as
+/// witnesses, and of squeezes of bytes, bits and native field elements, which is run on a
+/// sponge `S` created with `params` and a gadget `SV` created with `var_params`. The harness
+/// panics, with the failing schedule, if an output of the gadget differs from the native one or
+/// if the constraints are not satisfied.
+///
+/// Backends that implement [`CryptographicSpongeVar`] can call it from their tests instead of
+/// writing their own consistency tests.
+pub fn assert_sponge_consistency<F, S, SV, R>(
+    params: &S::Parameters,
+    var_params: &SV::Parameters,
+    num_schedules: usize,
+    rng: &mut R,
+) where
+    F: PrimeField + Absorb,
+    S: FieldBasedCryptographicSponge<F>,
+    SV: CryptographicSpongeVar<F, S>,
+    R: Rng,
+{
+    for _ in 0..num_schedules {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let mut sponge = S::new(params);
+        let mut sponge_var = SV::new(cs.clone(), var_params);
+        let mut schedule = Vec::<String>::new();
+
+        for _ in 0..rng.gen_range(1..=MAX_SCHEDULE_LENGTH) {
+            match rng.gen_range(0..5) {
+                0 => {
+                    let input: Vec<F> = (0..rng.gen_range(0..=4)).map(|_| F::rand(rng)).collect();
+                    schedule.push(format!("absorb {} field elements", input.len()));
+                    let input_var: Vec<_> = input
+                        .iter()
+                        .map(|elem| FpVar::new_witness(cs.clone(), || Ok(*elem)).unwrap())
+                        .collect();
+                    sponge.absorb(&input);
+                    sponge_var.absorb(&input_var).unwrap();
+                }
+                1 => {
+                    let input: Vec<u8> = (0..rng.gen_range(0..=40)).map(|_| rng.gen()).collect();
+                    schedule.push(format!("absorb {} bytes", input.len()));
+                    let input_var = UInt8::new_witness_vec(cs.clone(), &input).unwrap();
+                    sponge.absorb(&input);
+                    sponge_var.absorb(&input_var).unwrap();
+                }
+                2 => {
+                    let num_bytes = rng.gen_range(0..=70);
+                    schedule.push(format!("squeeze {} bytes", num_bytes));
+                    let output = sponge_var
+                        .squeeze_bytes(num_bytes)
+                        .unwrap()
+                        .value()
+                        .unwrap();
+                    assert_eq!(
+                        output,
+                        sponge.squeeze_bytes(num_bytes),
+                        "bytes differ after {:?}",
+                        schedule
+                    );
+                }
+                3 => {
+                    let num_bits = rng.gen_range(0..=300);
+                    schedule.push(format!("squeeze {} bits", num_bits));
+                    let output = sponge_var.squeeze_bits(num_bits).unwrap().value().unwrap();
+                    assert_eq!(
+                        output,
+                        sponge.squeeze_bits(num_bits),
+                        "bits differ after {:?}",
+                        schedule
+                    );
+                }
+                _ => {
+                    let num_elements = rng.gen_range(0..=3);
+                    schedule.push(format!("squeeze {} field elements", num_elements));
+                    let output = sponge_var
+                        .squeeze_field_elements(num_elements)
+                        .unwrap()
+                        .value()
+                        .unwrap();
+                    assert_eq!(
+                        output,
+                        sponge.squeeze_native_field_elements(num_elements),
+                        "field elements differ after {:?}",
+                        schedule
+                    );
+                }
+            }
+        }
+
+        assert!(
+            cs.is_satisfied().unwrap(),
+            "the constraints of {:?} are not satisfied",
+            schedule
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::assert_sponge_consistency;
+    use crate::constraints::SpongeWithGadget;
+    use crate::domain_separated::{DomainSeparatedSponge, RuntimeDomain};
+    use crate::duplex::DuplexSponge;
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::{PoseidonPermutation, PoseidonSponge};
+    use crate::test_sponge::{TestSponge, TestSpongeParameters};
+    use ark_std::test_rng;
+    use ark_test_curves::bls12_381::Fr;
+
+    type Var<S> = <S as SpongeWithGadget<Fr>>::Var;
+
+    #[test]
+    fn poseidon_consistency() {
+        let params = poseidon_parameters_for_test::<Fr>();
+        assert_sponge_consistency::<Fr, PoseidonSponge<Fr>, Var<PoseidonSponge<Fr>>, _>(
+            &params,
+            &params,
+            10,
+            &mut test_rng(),
+        );
+    }
+
+    #[test]
+    fn duplex_consistency() {
+        type S = DuplexSponge<Fr, PoseidonPermutation<Fr>>;
+        let permutation = PoseidonPermutation::new(&poseidon_parameters_for_test::<Fr>());
+        assert_sponge_consistency::<Fr, S, Var<S>, _>(
+            &permutation,
+            &permutation,
+            10,
+            &mut test_rng(),
+        );
+    }
+
+    #[test]
+    fn domain_separated_consistency() {
+        type S = DomainSeparatedSponge<Fr, PoseidonSponge<Fr>, RuntimeDomain>;
+        let params = poseidon_parameters_for_test::<Fr>();
+        assert_sponge_consistency::<Fr, S, Var<S>, _>(&params, &params, 5, &mut test_rng());
+    }
+
+    #[test]
+    fn test_sponge_consistency() {
+        let params = TestSpongeParameters::<Fr>::new(7);
+        assert_sponge_consistency::<Fr, TestSponge<Fr>, Var<TestSponge<Fr>>, _>(
+            &params,
+            &params,
+            10,
+            &mut test_rng(),
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "crypto-primitives")]
+    fn blake2s_consistency() {
+        use crate::blake2s::Blake2sSponge;
+        assert_sponge_consistency::<Fr, Blake2sSponge, Var<Blake2sSponge>, _>(
+            &(),
+            &(),
+            3,
+            &mut test_rng(),
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use crate::anemoi::{AnemoiParameters, AnemoiSponge};
+use crate::blake2b::Blake2Sponge;
+use crate::decimal::{field_to_string, string_to_field};
+use crate::gmimc::{GmimcParameters, GmimcSponge};
+use crate::griffin::{GriffinParameters, GriffinSponge};
+use crate::keccak::Sha3Sponge;
+use crate::poseidon::{PoseidonParameters, PoseidonSponge};
+use crate::poseidon2::{Poseidon2Parameters, Poseidon2Sponge};
+use crate::rescue::{RescuePrimeParameters, RescuePrimeSponge};
+use crate::{Absorb, CryptographicSponge, SpongeError};
+use ark_ff::{BigInteger, FpParameters, PrimeField};
+use ark_std::format;
+use ark_std::string::{String, ToString};
+use ark_std::vec::Vec;
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod tests;
+
+/// A known-answer test vector of a sponge: the field elements squeezed after absorbing an input.
+///
+/// Field elements are written as decimal strings, so that the vectors can be read by
+/// implementations in other languages once serialized as JSON with [`to_json`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpongeTestVector {
+    /// The name of the sponge, such as `"poseidon"`.
+    pub backend: String,
+    /// A description of the parameters of the sponge, such as `"generate(2, 1, 128)"`.
+    pub parameters: String,
+    /// The modulus of the field of the elements.
+    pub modulus: String,
+    /// The absorbed field elements, absorbed at once.
+    pub input: Vec<String>,
+    /// The field elements squeezed at once after absorbing `input`.
+    pub output: Vec<String>,
+}
+
+impl SpongeTestVector {
+    /// Computes the test vector of the sponge `S` with parameters `sponge_params`, described by
+    /// `backend` and `parameters`, by absorbing `input` and squeezing `num_outputs` elements.
+    pub fn new<F: PrimeField + Absorb, S: CryptographicSponge>(
+        backend: &str,
+        parameters: &str,
+        sponge_params: &S::Parameters,
+        input: &[F],
+        num_outputs: usize,
+    ) -> Self {
+        let mut sponge = S::new(sponge_params);
+        sponge.absorb(&input.to_vec());
+        let output = sponge.squeeze_field_elements::<F>(num_outputs);
+        Self {
+            backend: backend.to_string(),
+            parameters: parameters.to_string(),
+            modulus: BigUint::from_bytes_le(&F::Params::MODULUS.to_bytes_le()).to_string(),
+            input: input.iter().map(field_to_string).collect(),
+            output: output.iter().map(field_to_string).collect(),
+        }
+    }
+
+    /// Checks the test vector against the sponge `S` with parameters `sponge_params`.
+    ///
+    /// Returns [`SpongeError::InvalidParameters`] if the vector is not over `F` or cannot be
+    /// parsed, and `Ok(false)` if the sponge squeezes a different output.
+    pub fn verify<F: PrimeField + Absorb, S: CryptographicSponge>(
+        &self,
+        sponge_params: &S::Parameters,
+    ) -> Result<bool, SpongeError> {
+        let modulus = BigUint::from_bytes_le(&F::Params::MODULUS.to_bytes_le());
+        if self.modulus != modulus.to_string() {
+            return Err(SpongeError::InvalidParameters(
+                "the test vector is over another field",
+            ));
+        }
+        let input = self
+            .input
+            .iter()
+            .map(|elem| string_to_field::<F>(elem))
+            .collect::<Result<Vec<_>, _>>()?;
+        let computed = Self::new::<F, S>(
+            &self.backend,
+            &self.parameters,
+            sponge_params,
+            &input,
+            self.output.len(),
+        );
+        Ok(computed.output == self.output)
+    }
+}
+
+/// Serializes test vectors as a pretty-printed JSON array.
+pub fn to_json(vectors: &[SpongeTestVector]) -> String {
+    serde_json::to_string_pretty(vectors).expect("test vectors are always serializable")
+}
+
+/// Deserializes test vectors from a JSON array, as written by [`to_json`].
+pub fn from_json(json: &str) -> Result<Vec<SpongeTestVector>, serde_json::Error> {
+    serde_json::from_str(json)
+}
+
+/// The lengths of the inputs of the test vectors of [`generate_test_vectors`].
+pub const TEST_VECTOR_INPUT_LENGTHS: [usize; 4] = [0, 1, 3, 8];
+
+/// The number of elements squeezed by the test vectors of [`generate_test_vectors`].
+pub const TEST_VECTOR_NUM_OUTPUTS: usize = 3;
+
+/// Generates the test vectors of every sponge of the crate over `F`: for each sponge and set of
+/// parameters, the input `1, 2, ..., n` is absorbed for each length `n` of
+/// [`TEST_VECTOR_INPUT_LENGTHS`], and [`TEST_VECTOR_NUM_OUTPUTS`] elements are squeezed.
+///
+/// The field-based sponges use parameters given by their `generate` function for 128 bits of
+/// security, as described in each vector.
+pub fn generate_test_vectors<F: PrimeField + Absorb>() -> Vec<SpongeTestVector> {
+    let mut vectors = Vec::new();
+    macro_rules! add_vectors {
+        ($backend:expr, $sponge:ty, $params:expr, $description:expr) => {{
+            let params = $params;
+            for length in TEST_VECTOR_INPUT_LENGTHS {
+                let input: Vec<F> = (1..=length as u64).map(F::from).collect();
+                vectors.push(SpongeTestVector::new::<F, $sponge>(
+                    $backend,
+                    $description,
+                    &params,
+                    &input,
+                    TEST_VECTOR_NUM_OUTPUTS,
+                ));
+            }
+        }};
+    }
+
+    for (rate, capacity) in [(2, 1), (4, 1)] {
+        let description = format!("generate({}, {}, 128)", rate, capacity);
+        add_vectors!(
+            "poseidon",
+            PoseidonSponge<F>,
+            PoseidonParameters::<F>::generate(rate, capacity, 128),
+            &description
+        );
+    }
+    add_vectors!(
+        "poseidon2",
+        Poseidon2Sponge<F>,
+        Poseidon2Parameters::<F>::generate(2, 1, 128),
+        "generate(2, 1, 128)"
+    );
+    add_vectors!(
+        "rescue-prime",
+        RescuePrimeSponge<F>,
+        RescuePrimeParameters::<F>::generate(2, 1, 128),
+        "generate(2, 1, 128)"
+    );
+    add_vectors!(
+        "anemoi",
+        AnemoiSponge<F>,
+        AnemoiParameters::<F>::generate(1, 1, 128),
+        "generate(1, 1, 128)"
+    );
+    add_vectors!(
+        "gmimc",
+        GmimcSponge<F>,
+        GmimcParameters::<F>::generate(2, 1, 128),
+        "generate(2, 1, 128)"
+    );
+    add_vectors!(
+        "griffin",
+        GriffinSponge<F>,
+        GriffinParameters::<F>::generate(2, 1, 128),
+        "generate(2, 1, 128)"
+    );
+    add_vectors!("sha3", Sha3Sponge, (), "");
+    add_vectors!("blake2b", Blake2Sponge, (), "");
+    vectors
+}
@@ -0,0 +1,56 @@
+use crate::poseidon::{PoseidonParameters, PoseidonSponge};
+use crate::test_vectors::{from_json, generate_test_vectors, to_json, SpongeTestVector};
+use crate::SpongeError;
+use ark_test_curves::bls12_381::{Fq, Fr};
+
+/// The test vectors over the scalar field of BLS12-381, which guard the outputs of the sponges
+/// against unintended changes.
+const BLS12_381_FR_VECTORS: &str = include_str!("bls12_381_fr.json");
+
+#[test]
+fn fixture_matches_generated_vectors() {
+    let fixture = from_json(BLS12_381_FR_VECTORS).unwrap();
+    assert_eq!(fixture, generate_test_vectors::<Fr>());
+    assert_eq!(to_json(&fixture), BLS12_381_FR_VECTORS.trim_end());
+}
+
+#[test]
+fn verify() {
+    let params = PoseidonParameters::<Fr>::generate(2, 1, 128);
+    let input = [Fr::from(1u64), Fr::from(2u64), Fr::from(3u64)];
+    let vector = SpongeTestVector::new::<Fr, PoseidonSponge<Fr>>(
+        "poseidon",
+        "generate(2, 1, 128)",
+        &params,
+        &input,
+        2,
+    );
+    assert_eq!(vector.input, ["1", "2", "3"]);
+    assert_eq!(vector.output.len(), 2);
+    assert_eq!(vector.verify::<Fr, PoseidonSponge<Fr>>(&params), Ok(true));
+
+    let other_params = PoseidonParameters::<Fr>::generate(4, 1, 128);
+    assert_eq!(
+        vector.verify::<Fr, PoseidonSponge<Fr>>(&other_params),
+        Ok(false)
+    );
+
+    let mut tampered = vector.clone();
+    tampered.output[1] = "1".into();
+    assert_eq!(
+        tampered.verify::<Fr, PoseidonSponge<Fr>>(&params),
+        Ok(false)
+    );
+
+    let fq_params = PoseidonParameters::<Fq>::generate(2, 1, 128);
+    assert!(matches!(
+        vector.verify::<Fq, PoseidonSponge<Fq>>(&fq_params),
+        Err(SpongeError::InvalidParameters(_))
+    ));
+
+    let mut invalid = vector.clone();
+    invalid.input[0] = "0x01".into();
+    assert!(invalid.verify::<Fr, PoseidonSponge<Fr>>(&params).is_err());
+    invalid.input[0] = invalid.modulus.clone();
+    assert!(invalid.verify::<Fr, PoseidonSponge<Fr>>(&params).is_err());
+}
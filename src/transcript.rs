@@ -0,0 +1,246 @@
+use crate::{
+    Absorb, AbsorbWithLength, CryptographicSponge, FieldBasedCryptographicSponge, FieldElementSize,
+};
+use ark_ff::PrimeField;
+use ark_std::vec::Vec;
+
+/// Tag of the messages that append a value to the transcript.
+const APPEND_TAG: u8 = 0;
+
+/// Tag of the messages that precede a challenge.
+const CHALLENGE_TAG: u8 = 1;
+
+/// Tag of the message that starts the transcript.
+const DOMAIN_TAG: u8 = 2;
+
+/// A message of the transcript: a tag, a label, and a value, each framed so that the encodings
+/// of different messages never collide.
+struct LabeledMessage<'a, A: Absorb> {
+    tag: u8,
+    label: &'a [u8],
+    value: &'a A,
+}
+
+impl<'a, A: Absorb> Absorb for LabeledMessage<'a, A> {
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.tag.to_sponge_bytes(dest);
+        self.label.to_sponge_bytes_with_length(dest);
+        let value = self.value.to_sponge_bytes_as_vec();
+        value.len().to_sponge_bytes(dest);
+        dest.extend(value);
+    }
+
+    fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
+        self.tag.to_sponge_field_elements(dest);
+        self.label.to_sponge_field_elements_with_length(dest);
+        let value = self.value.to_sponge_field_elements_as_vec::<F>();
+        value.len().to_sponge_field_elements(dest);
+        dest.extend(value);
+    }
+}
+
+/// A labeled Fiat-Shamir transcript over a cryptographic sponge, in the style of
+/// [merlin](https://merlin.cool).
+///
+/// Every value appended to the transcript and every challenge squeezed from it is bound to a
+/// byte-string label, and the labels, values and the sizes of challenges are all framed with
+/// their lengths. Protocols therefore cannot build ambiguous transcripts by mistake, as long as
+/// they use distinct labels for distinct messages.
+#[derive(Clone)]
+pub struct Transcript<S: CryptographicSponge> {
+    sponge: S,
+}
+
+impl<S: CryptographicSponge> Transcript<S> {
+    /// Starts a transcript for the protocol identified by `domain`.
+    pub fn new(params: &S::Parameters, domain: &[u8]) -> Self {
+        Self::from_sponge(S::new(params), domain)
+    }
+
+    /// Starts a transcript for the protocol identified by `domain` from `sponge`, which may
+    /// already have absorbed inputs.
+    pub fn from_sponge(mut sponge: S, domain: &[u8]) -> Self {
+        sponge.absorb(&LabeledMessage {
+            tag: DOMAIN_TAG,
+            label: domain,
+            value: &[0u8; 0],
+        });
+        Self { sponge }
+    }
+
+    /// Appends `value` to the transcript with the label `label`.
+    pub fn append(&mut self, label: &[u8], value: &impl Absorb) {
+        self.sponge.absorb(&LabeledMessage {
+            tag: APPEND_TAG,
+            label,
+            value,
+        });
+    }
+
+    fn begin_challenge(&mut self, label: &[u8], size: &impl Absorb) {
+        self.sponge.absorb(&LabeledMessage {
+            tag: CHALLENGE_TAG,
+            label,
+            value: size,
+        });
+    }
+
+    /// Squeezes a challenge of `num_bytes` bytes with the label `label`.
+    pub fn challenge_bytes(&mut self, label: &[u8], num_bytes: usize) -> Vec<u8> {
+        self.begin_challenge(label, &num_bytes);
+        self.sponge.squeeze_bytes(num_bytes)
+    }
+
+    /// Squeezes a challenge of `num_bits` bits with the label `label`.
+    pub fn challenge_bits(&mut self, label: &[u8], num_bits: usize) -> Vec<bool> {
+        self.begin_challenge(label, &num_bits);
+        self.sponge.squeeze_bits(num_bits)
+    }
+
+    /// Squeezes a challenge of `num_elements` field elements with the label `label`.
+    pub fn challenge_field_elements<F: PrimeField>(
+        &mut self,
+        label: &[u8],
+        num_elements: usize,
+    ) -> Vec<F> {
+        self.begin_challenge(label, &num_elements);
+        self.sponge.squeeze_field_elements(num_elements)
+    }
+
+    /// Squeezes a challenge of `sizes.len()` field elements with the label `label`, where the
+    /// `i`-th element has size `sizes[i]`. Every size is bound to the challenge, as the pair
+    /// `(0, 0)` for [`FieldElementSize::Full`] and `(1, n)` for [`FieldElementSize::Truncated`]
+    /// with `n` bits.
+    pub fn challenge_field_elements_with_sizes<F: PrimeField>(
+        &mut self,
+        label: &[u8],
+        sizes: &[FieldElementSize],
+    ) -> Vec<F> {
+        let encoded_sizes: Vec<(u8, usize)> = sizes
+            .iter()
+            .map(|size| match size {
+                FieldElementSize::Full => (0, 0),
+                FieldElementSize::Truncated(num_bits) => (1, *num_bits),
+            })
+            .collect();
+        self.begin_challenge(label, &encoded_sizes);
+        self.sponge.squeeze_field_elements_with_sizes(sizes)
+    }
+
+    /// Squeezes a challenge of `num_elements` native field elements with the label `label`.
+    pub fn challenge_native_field_elements<CF: PrimeField>(
+        &mut self,
+        label: &[u8],
+        num_elements: usize,
+    ) -> Vec<CF>
+    where
+        S: FieldBasedCryptographicSponge<CF>,
+    {
+        self.begin_challenge(label, &num_elements);
+        self.sponge.squeeze_native_field_elements(num_elements)
+    }
+
+    /// Returns the underlying sponge.
+    pub fn into_sponge(self) -> S {
+        self.sponge
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::PoseidonSponge;
+    use crate::transcript::Transcript;
+    use crate::FieldElementSize;
+    use ark_ff::{FpParameters, PrimeField};
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    fn transcript() -> Transcript<PoseidonSponge<Fr>> {
+        Transcript::new(&poseidon_parameters_for_test(), b"test protocol")
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let mut t1 = transcript();
+        let mut t2 = transcript();
+        t1.append(b"commitment", &[1u8, 2, 3]);
+        t2.append(b"commitment", &[1u8, 2, 3]);
+        assert_eq!(
+            t1.challenge_native_field_elements(b"alpha", 2),
+            t2.challenge_native_field_elements(b"alpha", 2)
+        );
+        assert_eq!(
+            t1.challenge_bytes(b"beta", 10),
+            t2.challenge_bytes(b"beta", 10)
+        );
+    }
+
+    #[test]
+    fn test_labels_and_framing() {
+        let challenge = |f: &dyn Fn(&mut Transcript<PoseidonSponge<Fr>>)| -> Vec<Fr> {
+            let mut t = transcript();
+            f(&mut t);
+            t.challenge_native_field_elements(b"challenge", 1)
+        };
+
+        let reference = challenge(&|t| t.append(b"a", &[1u8, 2]));
+        // different label
+        assert_ne!(reference, challenge(&|t| t.append(b"b", &[1u8, 2])));
+        // moving bytes between the label and the value
+        assert_ne!(reference, challenge(&|t| t.append(b"a\x01", &[2u8])));
+        // splitting the value
+        assert_ne!(
+            reference,
+            challenge(&|t| {
+                t.append(b"a", &[1u8]);
+                t.append(b"", &[2u8]);
+            })
+        );
+        // different domain
+        let mut other =
+            Transcript::<PoseidonSponge<Fr>>::new(&poseidon_parameters_for_test(), b"other");
+        other.append(b"a", &[1u8, 2]);
+        assert_ne!(
+            reference,
+            other.challenge_native_field_elements(b"challenge", 1)
+        );
+    }
+
+    #[test]
+    fn test_challenge_labels() {
+        let mut t1 = transcript();
+        let mut t2 = transcript();
+        assert_ne!(
+            t1.challenge_bytes(b"alpha", 16),
+            t2.challenge_bytes(b"beta", 16)
+        );
+
+        // The size of a challenge is bound to it.
+        let mut t1 = transcript();
+        let mut t2 = transcript();
+        assert_ne!(
+            t1.challenge_bytes(b"alpha", 16),
+            t2.challenge_bytes(b"alpha", 32)[..16].to_vec()
+        );
+
+        // So are the sizes of field elements, and not only their number.
+        let challenge = |sizes: &[FieldElementSize]| -> Fr {
+            transcript().challenge_field_elements_with_sizes::<Fr>(b"alpha", sizes)[0]
+        };
+        let capacity = <Fr as PrimeField>::Params::CAPACITY as usize;
+        let reference = challenge(&[FieldElementSize::Truncated(128)]);
+        assert_ne!(reference, challenge(&[FieldElementSize::Truncated(64)]));
+        assert_ne!(
+            challenge(&[FieldElementSize::Full]),
+            challenge(&[FieldElementSize::Truncated(capacity)])
+        );
+        assert_ne!(
+            challenge(&[FieldElementSize::Truncated(128), FieldElementSize::Full]),
+            challenge(&[
+                FieldElementSize::Truncated(128),
+                FieldElementSize::Truncated(0)
+            ])
+        );
+    }
+}
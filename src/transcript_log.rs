@@ -0,0 +1,432 @@
+use crate::decimal::{field_to_string, string_to_field};
+use crate::transcript::Transcript;
+use crate::{Absorb, CryptographicSponge, FieldElementSize, SpongeError};
+use ark_ff::{BigInteger, PrimeField};
+use ark_std::any::type_name;
+use ark_std::marker::PhantomData;
+use ark_std::string::{String, ToString};
+use ark_std::vec;
+use ark_std::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// A log of a [`Transcript`]: its domain, the values appended to it, and the challenges squeezed
+/// from it, in order.
+///
+/// Byte strings are written in hexadecimal and field elements as decimal strings, so that logs
+/// serialized with serde can be diffed, e.g. to find where the transcripts of a prover and a
+/// verifier diverge with [`TranscriptLog::first_difference`], or kept as audit artifacts.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranscriptLog {
+    /// The domain of the transcript.
+    pub domain: String,
+    /// The entries of the transcript, in order.
+    pub entries: Vec<TranscriptEntry>,
+}
+
+/// An entry of a [`TranscriptLog`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TranscriptEntry {
+    /// A value appended to the transcript.
+    Append {
+        /// The label of the value.
+        label: String,
+        /// The name of the type of the value, for debugging only.
+        value_type: String,
+        /// The sponge bytes of the value, as absorbed by byte-oriented sponges.
+        bytes: String,
+        /// The sponge field elements of the value, as absorbed by the field-based sponges over
+        /// the field of the log.
+        field_elements: Vec<String>,
+    },
+    /// A challenge squeezed from the transcript.
+    Challenge {
+        /// The label of the challenge.
+        label: String,
+        /// The kind and the size of the challenge.
+        kind: ChallengeKind,
+        /// The challenge: a single hexadecimal string of bytes, a single string of `0` and `1`
+        /// bits, or one decimal string per field element.
+        output: Vec<String>,
+    },
+}
+
+/// The kind and the size of a challenge of a [`TranscriptLog`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChallengeKind {
+    /// A number of bytes.
+    Bytes(usize),
+    /// A number of bits.
+    Bits(usize),
+    /// A number of field elements of full size.
+    FieldElements(usize),
+    /// Field elements of the given sizes, `None` for [`FieldElementSize::Full`] and `Some(bits)`
+    /// for [`FieldElementSize::Truncated`].
+    FieldElementsWithSizes(Vec<Option<usize>>),
+}
+
+impl TranscriptLog {
+    /// Returns the index of the first entry in which `self` and `other` differ, or the length
+    /// of the shorter log if one is a prefix of the other, and `None` if they are equal.
+    ///
+    /// A log whose domain differs differs at entry `0`.
+    pub fn first_difference(&self, other: &Self) -> Option<usize> {
+        if self.domain != other.domain {
+            return Some(0);
+        }
+        match self
+            .entries
+            .iter()
+            .zip(&other.entries)
+            .position(|(a, b)| a != b)
+        {
+            Some(index) => Some(index),
+            None if self.entries.len() != other.entries.len() => {
+                Some(self.entries.len().min(other.entries.len()))
+            }
+            None => None,
+        }
+    }
+}
+
+/// A [`Transcript`] that records its entries in a [`TranscriptLog`], with the field elements
+/// of appended values and challenges over `F`.
+///
+/// For a field-based sponge, `F` must be the field of the sponge for [`Self::replay`] to absorb
+/// the same field elements as the logged transcript.
+#[derive(Clone)]
+pub struct LoggedTranscript<S: CryptographicSponge, F: PrimeField> {
+    transcript: Transcript<S>,
+    log: TranscriptLog,
+    _field: PhantomData<F>,
+}
+
+impl<S: CryptographicSponge, F: PrimeField> LoggedTranscript<S, F> {
+    /// Starts a logged transcript for the protocol identified by `domain`, as [`Transcript::new`].
+    pub fn new(params: &S::Parameters, domain: &[u8]) -> Self {
+        Self::from_sponge(S::new(params), domain)
+    }
+
+    /// Starts a logged transcript from `sponge`, as [`Transcript::from_sponge`]. The inputs
+    /// that `sponge` already absorbed are not logged.
+    pub fn from_sponge(sponge: S, domain: &[u8]) -> Self {
+        Self {
+            transcript: Transcript::from_sponge(sponge, domain),
+            log: TranscriptLog {
+                domain: to_hex(domain),
+                entries: Vec::new(),
+            },
+            _field: PhantomData,
+        }
+    }
+
+    /// Appends `value` to the transcript with the label `label`, as [`Transcript::append`].
+    pub fn append<A: Absorb>(&mut self, label: &[u8], value: &A) {
+        let value = RecordedValue {
+            bytes: value.to_sponge_bytes_as_vec(),
+            field_elements: value.to_sponge_field_elements_as_vec::<F>(),
+        };
+        self.append_recorded(label, type_name::<A>().to_string(), value);
+    }
+
+    fn append_recorded(&mut self, label: &[u8], value_type: String, value: RecordedValue<F>) {
+        self.transcript.append(label, &value);
+        self.log.entries.push(TranscriptEntry::Append {
+            label: to_hex(label),
+            value_type,
+            bytes: to_hex(&value.bytes),
+            field_elements: value.field_elements.iter().map(field_to_string).collect(),
+        });
+    }
+
+    fn record_challenge(&mut self, label: &[u8], kind: ChallengeKind, output: Vec<String>) {
+        self.log.entries.push(TranscriptEntry::Challenge {
+            label: to_hex(label),
+            kind,
+            output,
+        });
+    }
+
+    /// Squeezes a challenge of `num_bytes` bytes, as [`Transcript::challenge_bytes`].
+    pub fn challenge_bytes(&mut self, label: &[u8], num_bytes: usize) -> Vec<u8> {
+        let challenge = self.transcript.challenge_bytes(label, num_bytes);
+        let output = vec![to_hex(&challenge)];
+        self.record_challenge(label, ChallengeKind::Bytes(num_bytes), output);
+        challenge
+    }
+
+    /// Squeezes a challenge of `num_bits` bits, as [`Transcript::challenge_bits`].
+    pub fn challenge_bits(&mut self, label: &[u8], num_bits: usize) -> Vec<bool> {
+        let challenge = self.transcript.challenge_bits(label, num_bits);
+        let output = vec![challenge
+            .iter()
+            .map(|&bit| if bit { '1' } else { '0' })
+            .collect()];
+        self.record_challenge(label, ChallengeKind::Bits(num_bits), output);
+        challenge
+    }
+
+    /// Squeezes a challenge of `num_elements` field elements, as
+    /// [`Transcript::challenge_field_elements`].
+    pub fn challenge_field_elements(&mut self, label: &[u8], num_elements: usize) -> Vec<F> {
+        let challenge = self
+            .transcript
+            .challenge_field_elements(label, num_elements);
+        let output = challenge.iter().map(field_to_string).collect();
+        self.record_challenge(label, ChallengeKind::FieldElements(num_elements), output);
+        challenge
+    }
+
+    /// Squeezes a challenge of field elements of the given sizes, as
+    /// [`Transcript::challenge_field_elements_with_sizes`].
+    pub fn challenge_field_elements_with_sizes(
+        &mut self,
+        label: &[u8],
+        sizes: &[FieldElementSize],
+    ) -> Vec<F> {
+        let challenge = self
+            .transcript
+            .challenge_field_elements_with_sizes(label, sizes);
+        let output = challenge.iter().map(field_to_string).collect();
+        let sizes = sizes
+            .iter()
+            .map(|size| match size {
+                FieldElementSize::Full => None,
+                FieldElementSize::Truncated(num_bits) => Some(*num_bits),
+            })
+            .collect();
+        self.record_challenge(label, ChallengeKind::FieldElementsWithSizes(sizes), output);
+        challenge
+    }
+
+    /// Returns the log of the transcript so far.
+    pub fn log(&self) -> &TranscriptLog {
+        &self.log
+    }
+
+    /// Returns the underlying transcript and its log.
+    pub fn into_parts(self) -> (Transcript<S>, TranscriptLog) {
+        (self.transcript, self.log)
+    }
+
+    /// Replays `log` on a new transcript: the logged values are appended again, and every
+    /// challenge is squeezed again and compared with the logged one.
+    ///
+    /// Returns the replayed transcript, which can be continued, or
+    /// [`SpongeError::TranscriptMismatch`] with the first challenge that differs, e.g. because
+    /// the log was produced with other sponge parameters, and
+    /// [`SpongeError::InvalidParameters`] if the log cannot be parsed.
+    pub fn replay(params: &S::Parameters, log: &TranscriptLog) -> Result<Self, SpongeError> {
+        let mut transcript = Self::new(params, &from_hex(&log.domain)?);
+        for (index, entry) in log.entries.iter().enumerate() {
+            match entry {
+                TranscriptEntry::Append {
+                    label,
+                    value_type,
+                    bytes,
+                    field_elements,
+                } => {
+                    let value = RecordedValue {
+                        bytes: from_hex(bytes)?,
+                        field_elements: field_elements
+                            .iter()
+                            .map(|elem| string_to_field(elem))
+                            .collect::<Result<_, _>>()?,
+                    };
+                    transcript.append_recorded(&from_hex(label)?, value_type.clone(), value);
+                }
+                TranscriptEntry::Challenge { label, kind, .. } => {
+                    let label = from_hex(label)?;
+                    match kind {
+                        ChallengeKind::Bytes(num_bytes) => {
+                            transcript.challenge_bytes(&label, *num_bytes);
+                        }
+                        ChallengeKind::Bits(num_bits) => {
+                            transcript.challenge_bits(&label, *num_bits);
+                        }
+                        ChallengeKind::FieldElements(num_elements) => {
+                            transcript.challenge_field_elements(&label, *num_elements);
+                        }
+                        ChallengeKind::FieldElementsWithSizes(sizes) => {
+                            let sizes: Vec<_> = sizes
+                                .iter()
+                                .map(|size| match size {
+                                    None => FieldElementSize::Full,
+                                    Some(num_bits) => FieldElementSize::Truncated(*num_bits),
+                                })
+                                .collect();
+                            transcript.challenge_field_elements_with_sizes(&label, &sizes);
+                        }
+                    }
+                    if transcript.log.entries.last() != Some(entry) {
+                        return Err(SpongeError::TranscriptMismatch { entry: index });
+                    }
+                }
+            }
+        }
+        Ok(transcript)
+    }
+}
+
+/// A value appended to a logged transcript, absorbed as its recorded sponge bytes and field
+/// elements.
+struct RecordedValue<F: PrimeField> {
+    bytes: Vec<u8>,
+    field_elements: Vec<F>,
+}
+
+impl<F: PrimeField> Absorb for RecordedValue<F> {
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        dest.extend_from_slice(&self.bytes);
+    }
+
+    fn to_sponge_field_elements<G: PrimeField>(&self, dest: &mut Vec<G>) {
+        dest.extend(
+            self.field_elements
+                .iter()
+                .map(|elem| G::from_le_bytes_mod_order(&elem.into_repr().to_bytes_le())),
+        );
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    bytes
+        .iter()
+        .flat_map(|byte| {
+            [
+                DIGITS[(byte >> 4) as usize] as char,
+                DIGITS[(byte & 0xf) as usize] as char,
+            ]
+        })
+        .collect()
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>, SpongeError> {
+    let digit = |c: u8| {
+        (c as char)
+            .to_digit(16)
+            .ok_or(SpongeError::InvalidParameters(
+                "a byte string is not hexadecimal",
+            ))
+    };
+    if !hex.len().is_multiple_of(2) {
+        return Err(SpongeError::InvalidParameters(
+            "a byte string is not hexadecimal",
+        ));
+    }
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| Ok((digit(pair[0])? * 16 + digit(pair[1])?) as u8))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChallengeKind, LoggedTranscript, TranscriptEntry, TranscriptLog};
+    use crate::keccak::Sha3Sponge;
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::{PoseidonParameters, PoseidonSponge};
+    use crate::transcript::Transcript;
+    use crate::{FieldElementSize, SpongeError};
+    use ark_std::vec;
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    type PoseidonTranscript = LoggedTranscript<PoseidonSponge<Fr>, Fr>;
+
+    fn prover(params: &PoseidonParameters<Fr>) -> (PoseidonTranscript, Vec<Fr>) {
+        let mut transcript = PoseidonTranscript::new(params, b"test protocol");
+        transcript.append(b"commitment", &[1u8, 2, 3]);
+        transcript.append(b"point", &Fr::from(7u8));
+        transcript.challenge_bytes(b"bytes", 10);
+        transcript.challenge_bits(b"bits", 5);
+        let challenges = transcript.challenge_field_elements_with_sizes(
+            b"alpha",
+            &[FieldElementSize::Full, FieldElementSize::Truncated(128)],
+        );
+        (transcript, challenges)
+    }
+
+    #[test]
+    fn logged_transcript_is_transparent() {
+        let params = poseidon_parameters_for_test();
+        let (logged, challenges) = prover(&params);
+
+        let mut transcript = Transcript::<PoseidonSponge<Fr>>::new(&params, b"test protocol");
+        transcript.append(b"commitment", &[1u8, 2, 3]);
+        transcript.append(b"point", &Fr::from(7u8));
+        let bytes = transcript.challenge_bytes(b"bytes", 10);
+        transcript.challenge_bits(b"bits", 5);
+        assert_eq!(
+            transcript.challenge_field_elements_with_sizes::<Fr>(
+                b"alpha",
+                &[FieldElementSize::Full, FieldElementSize::Truncated(128)],
+            ),
+            challenges
+        );
+
+        let log = logged.log();
+        assert_eq!(log.entries.len(), 5);
+        assert_eq!(
+            log.entries[2],
+            TranscriptEntry::Challenge {
+                label: "6279746573".into(),
+                kind: ChallengeKind::Bytes(10),
+                output: vec![super::to_hex(&bytes)],
+            }
+        );
+    }
+
+    #[test]
+    fn replay() {
+        let params = poseidon_parameters_for_test();
+        let (logged, _) = prover(&params);
+        let json = serde_json::to_string(logged.log()).unwrap();
+        let log: TranscriptLog = serde_json::from_str(&json).unwrap();
+
+        let mut replayed = PoseidonTranscript::replay(&params, &log).unwrap();
+        assert_eq!(replayed.log(), logged.log());
+        // The replayed transcript continues as the original one.
+        let mut logged = logged;
+        assert_eq!(
+            replayed.challenge_field_elements(b"beta", 2),
+            logged.challenge_field_elements(b"beta", 2)
+        );
+
+        // The byte sponges are replayed from the logged bytes.
+        let mut logged = LoggedTranscript::<Sha3Sponge, Fr>::new(&(), b"test protocol");
+        logged.append(b"commitment", &[1u8, 2, 3]);
+        logged.challenge_bits(b"bits", 20);
+        let replayed = LoggedTranscript::<Sha3Sponge, Fr>::replay(&(), logged.log()).unwrap();
+        assert_eq!(replayed.log(), logged.log());
+    }
+
+    #[test]
+    fn replay_mismatch() {
+        let params = poseidon_parameters_for_test();
+        let (logged, _) = prover(&params);
+
+        // A tampered value changes every later challenge.
+        let mut log = logged.log().clone();
+        if let TranscriptEntry::Append { field_elements, .. } = &mut log.entries[0] {
+            field_elements[0] = "9".into();
+        }
+        assert_eq!(
+            PoseidonTranscript::replay(&params, &log).err(),
+            Some(SpongeError::TranscriptMismatch { entry: 2 })
+        );
+        assert_eq!(logged.log().first_difference(&log), Some(0));
+        assert_eq!(logged.log().first_difference(logged.log()), None);
+
+        let mut truncated = logged.log().clone();
+        truncated.entries.truncate(3);
+        assert_eq!(logged.log().first_difference(&truncated), Some(3));
+
+        let mut invalid = logged.log().clone();
+        invalid.domain = "xyz".into();
+        assert!(matches!(
+            PoseidonTranscript::replay(&params, &invalid),
+            Err(SpongeError::InvalidParameters(_))
+        ));
+    }
+}
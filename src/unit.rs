@@ -0,0 +1,216 @@
+use crate::blake2b::Blake2Sponge;
+use crate::blake2s::Blake2sSponge;
+use crate::domain_separated::{DomainSeparatedSponge, DomainSeparator};
+use crate::duplex::{DuplexSponge, FieldPermutation};
+use crate::keccak::Sha3Sponge;
+use crate::poseidon::{GenericPoseidonSponge, PoseidonState};
+use crate::test_sponge::TestSponge;
+use crate::{Absorb, CryptographicSponge, FieldBasedCryptographicSponge};
+use ark_ff::PrimeField;
+use ark_std::marker::PhantomData;
+use ark_std::vec::Vec;
+
+/// A sponge over its native unit `U`, which it absorbs and squeezes without conversion: bytes for
+/// the byte-oriented sponges such as [`Sha3Sponge`], and field elements for the algebraic ones.
+///
+/// Code generic over `UnitSponge<U>` accepts both kinds of backends for the same unit through the
+/// adapters [`ByteUnits`], which turns any sponge into a byte sponge, and [`FieldUnits`], which
+/// turns any sponge into a sponge over a field.
+pub trait UnitSponge<U>: Clone {
+    /// Absorbs `units`.
+    fn absorb_units(&mut self, units: &[U]);
+
+    /// Squeezes `num_units` units.
+    fn squeeze_units(&mut self, num_units: usize) -> Vec<U>;
+}
+
+impl UnitSponge<u8> for Sha3Sponge {
+    fn absorb_units(&mut self, units: &[u8]) {
+        self.absorb(&units);
+    }
+
+    fn squeeze_units(&mut self, num_units: usize) -> Vec<u8> {
+        self.squeeze_bytes(num_units)
+    }
+}
+
+impl UnitSponge<u8> for Blake2Sponge {
+    fn absorb_units(&mut self, units: &[u8]) {
+        self.absorb(&units);
+    }
+
+    fn squeeze_units(&mut self, num_units: usize) -> Vec<u8> {
+        self.squeeze_bytes(num_units)
+    }
+}
+
+impl UnitSponge<u8> for Blake2sSponge {
+    fn absorb_units(&mut self, units: &[u8]) {
+        self.absorb(&units);
+    }
+
+    fn squeeze_units(&mut self, num_units: usize) -> Vec<u8> {
+        self.squeeze_bytes(num_units)
+    }
+}
+
+macro_rules! impl_field_unit_sponge {
+    ($sponge:ty, $($generics:tt)*) => {
+        impl<F: PrimeField + Absorb, $($generics)*> UnitSponge<F> for $sponge {
+            fn absorb_units(&mut self, units: &[F]) {
+                self.absorb_native_field_elements(units);
+            }
+
+            fn squeeze_units(&mut self, num_units: usize) -> Vec<F> {
+                self.squeeze_native_field_elements(num_units)
+            }
+        }
+    };
+}
+
+impl_field_unit_sponge!(GenericPoseidonSponge<F, S>, S: PoseidonState<F>);
+impl_field_unit_sponge!(DuplexSponge<F, P>, P: FieldPermutation<F>);
+impl_field_unit_sponge!(
+    DomainSeparatedSponge<F, S, D>,
+    S: FieldBasedCryptographicSponge<F>,
+    D: DomainSeparator
+);
+impl_field_unit_sponge!(TestSponge<F>,);
+
+/// The adapter of a sponge to a byte sponge, which absorbs bytes as
+/// [`CryptographicSponge::absorb`] of a byte slice and squeezes them with
+/// [`CryptographicSponge::squeeze_bytes`].
+#[derive(Clone)]
+pub struct ByteUnits<S: CryptographicSponge> {
+    sponge: S,
+}
+
+impl<S: CryptographicSponge> ByteUnits<S> {
+    /// Wraps `sponge`.
+    pub fn new(sponge: S) -> Self {
+        Self { sponge }
+    }
+
+    /// Returns the wrapped sponge.
+    pub fn into_inner(self) -> S {
+        self.sponge
+    }
+}
+
+impl<S: CryptographicSponge> UnitSponge<u8> for ByteUnits<S> {
+    fn absorb_units(&mut self, units: &[u8]) {
+        self.sponge.absorb(&units);
+    }
+
+    fn squeeze_units(&mut self, num_units: usize) -> Vec<u8> {
+        self.sponge.squeeze_bytes(num_units)
+    }
+}
+
+/// The adapter of a sponge to a sponge over `F`, which absorbs elements as
+/// [`CryptographicSponge::absorb`] of a slice of elements and squeezes them with
+/// [`CryptographicSponge::squeeze_field_elements`].
+///
+/// Over a byte sponge, the elements are absorbed as their canonical bytes and squeezed from
+/// enough bytes for the bias of the reduction to be negligible, so that no information is lost in
+/// either direction.
+pub struct FieldUnits<F: PrimeField, S: CryptographicSponge> {
+    sponge: S,
+    _field: PhantomData<F>,
+}
+
+impl<F: PrimeField, S: CryptographicSponge> Clone for FieldUnits<F, S> {
+    fn clone(&self) -> Self {
+        Self::new(self.sponge.clone())
+    }
+}
+
+impl<F: PrimeField, S: CryptographicSponge> FieldUnits<F, S> {
+    /// Wraps `sponge`.
+    pub fn new(sponge: S) -> Self {
+        Self {
+            sponge,
+            _field: PhantomData,
+        }
+    }
+
+    /// Returns the wrapped sponge.
+    pub fn into_inner(self) -> S {
+        self.sponge
+    }
+}
+
+impl<F: PrimeField + Absorb, S: CryptographicSponge> UnitSponge<F> for FieldUnits<F, S> {
+    fn absorb_units(&mut self, units: &[F]) {
+        self.sponge.absorb(&units);
+    }
+
+    fn squeeze_units(&mut self, num_units: usize) -> Vec<F> {
+        self.sponge.squeeze_field_elements(num_units)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::keccak::Sha3Sponge;
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::PoseidonSponge;
+    use crate::unit::{ByteUnits, FieldUnits, UnitSponge};
+    use crate::{CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+
+    /// A protocol step generic over the unit of the sponge.
+    fn absorb_and_squeeze<U: Clone, S: UnitSponge<U>>(sponge: &mut S, input: &[U]) -> Vec<U> {
+        sponge.absorb_units(input);
+        sponge.squeeze_units(2)
+    }
+
+    #[test]
+    fn native_units() {
+        let params = poseidon_parameters_for_test();
+        let input = [Fr::from(1u8), Fr::from(2u8)];
+        let mut sponge = PoseidonSponge::new(&params);
+        let output = absorb_and_squeeze(&mut sponge, &input);
+        let mut expected = PoseidonSponge::new(&params);
+        expected.absorb_native_field_elements(&input);
+        assert_eq!(output, expected.squeeze_native_field_elements(2));
+
+        let mut sponge = Sha3Sponge::new(&());
+        let output = absorb_and_squeeze(&mut sponge, b"unit");
+        let mut expected = Sha3Sponge::new(&());
+        expected.absorb(&b"unit".as_ref());
+        assert_eq!(output, expected.squeeze_bytes(2));
+    }
+
+    #[test]
+    fn adapters() {
+        let params = poseidon_parameters_for_test::<Fr>();
+        let mut bytes = ByteUnits::new(PoseidonSponge::new(&params));
+        let output = absorb_and_squeeze(&mut bytes, b"unit");
+        let mut expected = PoseidonSponge::new(&params);
+        expected.absorb(&b"unit".as_ref());
+        assert_eq!(output, expected.squeeze_bytes(2));
+
+        let input = [Fr::from(1u8), Fr::from(2u8)];
+        let mut fields = FieldUnits::<Fr, _>::new(Sha3Sponge::new(&()));
+        let output = absorb_and_squeeze(&mut fields, &input);
+        let mut expected = Sha3Sponge::new(&());
+        expected.absorb(&input.as_ref());
+        assert_eq!(output, expected.squeeze_field_elements::<Fr>(2));
+
+        // The adapters over a native sponge of the same unit are the native sponge.
+        let mut fields = FieldUnits::<Fr, _>::new(PoseidonSponge::new(&params));
+        let mut native = PoseidonSponge::new(&params);
+        assert_eq!(
+            absorb_and_squeeze(&mut fields, &input),
+            absorb_and_squeeze(&mut native, &input)
+        );
+        let mut bytes = ByteUnits::new(Sha3Sponge::new(&()));
+        let mut native = Sha3Sponge::new(&());
+        assert_eq!(
+            absorb_and_squeeze(&mut bytes, b"unit"),
+            absorb_and_squeeze(&mut native, b"unit")
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use crate::CryptographicSponge;
+use ark_std::vec::Vec;
+use std::io;
+
+/// The size of the chunks absorbed by a [`SpongeWriter`].
+pub const WRITER_CHUNK_SIZE: usize = 4096;
+
+/// A writer absorbing the bytes written to it into a sponge, created by
+/// [`CryptographicSponge::as_writer`], for hashing large files or serialized structures
+/// without materializing them.
+///
+/// The bytes are split into chunks of [`WRITER_CHUNK_SIZE`] bytes, and each chunk is absorbed as
+/// a byte string (`&[u8]`, which is prefixed with its length and packed into as few field
+/// elements as possible by field-based sponges). The last chunk is shorter, and possibly empty,
+/// which makes the encoding unambiguous. It is absorbed when the writer is finished or dropped,
+/// so the result does not depend on how the bytes are split into writes, and
+/// [`flush`](io::Write::flush) does nothing.
+pub struct SpongeWriter<'a, S: CryptographicSponge> {
+    sponge: &'a mut S,
+    buffer: Vec<u8>,
+}
+
+impl<'a, S: CryptographicSponge> SpongeWriter<'a, S> {
+    /// Creates a writer absorbing into `sponge`.
+    pub fn new(sponge: &'a mut S) -> Self {
+        Self {
+            sponge,
+            buffer: Vec::with_capacity(WRITER_CHUNK_SIZE),
+        }
+    }
+
+    /// Absorbs the last chunk. This is equivalent to dropping the writer.
+    pub fn finish(self) {}
+}
+
+impl<'a, S: CryptographicSponge> io::Write for SpongeWriter<'a, S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut remaining = buf;
+        while !remaining.is_empty() {
+            let num_bytes =
+                ark_std::cmp::min(WRITER_CHUNK_SIZE - self.buffer.len(), remaining.len());
+            self.buffer.extend_from_slice(&remaining[..num_bytes]);
+            remaining = &remaining[num_bytes..];
+            if self.buffer.len() == WRITER_CHUNK_SIZE {
+                self.sponge.absorb(&self.buffer.as_slice());
+                self.buffer.clear();
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a, S: CryptographicSponge> Drop for SpongeWriter<'a, S> {
+    fn drop(&mut self) {
+        self.sponge.absorb(&self.buffer.as_slice());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::poseidon::tests::poseidon_parameters_for_test;
+    use crate::poseidon::PoseidonSponge;
+    use crate::writer::WRITER_CHUNK_SIZE;
+    use crate::{CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_std::vec::Vec;
+    use ark_test_curves::bls12_381::Fr;
+    use std::io::Write;
+
+    #[test]
+    fn test_writer() {
+        let params = poseidon_parameters_for_test();
+        let message: Vec<u8> = (0..2 * WRITER_CHUNK_SIZE + 100).map(|i| i as u8).collect();
+
+        let mut sponge1 = PoseidonSponge::<Fr>::new(&params);
+        sponge1.as_writer().write_all(&message).unwrap();
+
+        let mut sponge2 = PoseidonSponge::<Fr>::new(&params);
+        let mut writer = sponge2.as_writer();
+        for chunk in message.chunks(1000) {
+            writer.write_all(chunk).unwrap();
+            writer.flush().unwrap();
+        }
+        writer.finish();
+
+        let mut sponge3 = PoseidonSponge::<Fr>::new(&params);
+        for chunk in message.chunks(WRITER_CHUNK_SIZE) {
+            sponge3.absorb(&chunk);
+        }
+
+        let output = sponge1.squeeze_native_field_elements(1);
+        assert_eq!(output, sponge2.squeeze_native_field_elements(1));
+        assert_eq!(output, sponge3.squeeze_native_field_elements(1));
+    }
+}